@@ -27,80 +27,97 @@ pub struct ContactDto {
     pub phones: Vec<PhoneNumberDto>,
 }
 
-#[rustfmt::skip]
-#[derive(Deserialize, Serialize, Clone)]
-pub enum CountryCode { TW, TH, IN, ID, US, Unknown }
+/// declare enum type of ISO code whose variant names are exactly the
+/// literal codes, the generated `as_str()` and `From<&str>` keep the
+/// string conversion in one place, any unsupported code falls back to
+/// `Unknown`
+macro_rules! iso_code_enum {
+    ($(#[$meta:meta])* $name:ident { $($code:ident),+ $(,)? }) => {
+        $(#[$meta])*
+        pub enum $name { $($code,)+ Unknown }
+
+        impl $name {
+            pub fn as_str(&self) -> &'static str {
+                match self {
+                    $(Self::$code => stringify!($code),)+
+                    Self::Unknown => "Unknown",
+                }
+            }
+            /// all valid codes supported in this project, `Unknown` excluded
+            pub fn supported() -> Vec<Self> {
+                vec![$(Self::$code,)+]
+            }
+        }
+
+        impl From<&str> for $name {
+            fn from(value: &str) -> Self {
+                match value {
+                    $(stringify!($code) => Self::$code,)+
+                    _others => Self::Unknown,
+                }
+            }
+        }
+    };
+}
+
+iso_code_enum!(
+    /// ISO 3166-1 alpha-2 country code
+    #[rustfmt::skip]
+    #[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+    CountryCode {
+        TW, TH, IN, ID, US, JP, KR, SG, MY, PH, VN, HK, CN, AU, CA, GB, CH,
+        NO, KW, BH, AT, BE, BG, HR, CY, CZ, DK, EE, FI, FR, DE, GR, HU, IE,
+        IT, LV, LT, LU, MT, NL, PL, PT, RO, SK, SI, ES, SE,
+    }
+);
 
 impl From<CountryCode> for String {
     fn from(value: CountryCode) -> String {
-        let out = match value {
-            CountryCode::TW => "TW",
-            CountryCode::TH => "TH",
-            CountryCode::IN => "IN",
-            CountryCode::ID => "ID",
-            CountryCode::US => "US",
-            CountryCode::Unknown => "Unknown",
-        };
-        out.to_string()
+        value.as_str().to_string()
     }
 } // implement `Into` trait, not replying on serde
 impl From<String> for CountryCode {
-    // TODO, from literal string
     fn from(value: String) -> Self {
-        match value.as_str() {
-            "TW" => Self::TW,
-            "TH" => Self::TH,
-            "IN" => Self::IN,
-            "ID" => Self::ID,
-            "US" => Self::US,
-            _others => Self::Unknown,
-        }
+        Self::from(value.as_str())
     }
 }
 
-#[rustfmt::skip]
-#[allow(clippy::upper_case_acronyms)]
-#[derive(Deserialize, Serialize, Debug, Clone, Hash, Eq, PartialEq)]
-pub enum CurrencyDto { INR, IDR, THB, TWD, USD, Unknown }
+iso_code_enum!(
+    /// ISO 4217 alphabetic currency code
+    #[rustfmt::skip]
+    #[allow(clippy::upper_case_acronyms)]
+    #[derive(Deserialize, Serialize, Debug, Clone, Hash, Eq, PartialEq)]
+    CurrencyDto {
+        INR, IDR, THB, TWD, USD, EUR, JPY, KRW, SGD, MYR, PHP, VND, HKD, CNY,
+        AUD, CAD, GBP, CHF, NOK, SEK, DKK, PLN, CZK, HUF, RON, BGN, KWD, BHD,
+    }
+);
 // #[serde(rename_all = "UPPERCASE")], FIXME, the macro does not work
 
 impl ToString for CurrencyDto {
     fn to_string(&self) -> String {
-        let o = match self {
-            Self::INR => "INR",
-            Self::IDR => "IDR",
-            Self::THB => "THB",
-            Self::TWD => "TWD",
-            Self::USD => "USD",
-            Self::Unknown => "Unknown",
-        };
-        o.to_string()
+        self.as_str().to_string()
     }
 }
 
 impl From<&String> for CurrencyDto {
-    // TODO, from literal string
     fn from(value: &String) -> Self {
-        match value.as_str() {
-            "INR" => Self::INR,
-            "IDR" => Self::IDR,
-            "THB" => Self::THB,
-            "TWD" => Self::TWD,
-            "USD" => Self::USD,
-            _others => Self::Unknown,
-        }
+        Self::from(value.as_str())
     }
 }
 
 impl CurrencyDto {
     /// Number of digits in fraction part of a decimal value allowed
-    /// in a given amount value. Note the decimal places should depends
-    /// on the currency applied, due to the limit specified in 3rd-party
-    /// payment processors such as Stripe
+    /// in a given amount value, which is the minor-unit exponent defined
+    /// in ISO 4217. Note the decimal places should depends on the currency
+    /// applied, due to the limit specified in 3rd-party payment processors
+    /// such as Stripe
     pub fn amount_fraction_scale(&self) -> u32 {
         match self {
-            Self::INR | Self::IDR | Self::THB | Self::TWD | Self::USD => 2,
+            Self::JPY | Self::KRW | Self::VND => 0,
+            Self::KWD | Self::BHD => 3,
             Self::Unknown => 0,
+            _others => 2,
         }
     }
 }
@@ -129,7 +146,20 @@ pub struct OrderLinePayDto {
     pub reserved_until: String, // date-time formatted in RFC3339 spec
     // TODO, add warranty time
     pub quantity: u32,
+    // the discount has been deducted from the amount, the tax is included
     pub amount: PayAmountDto,
+    #[serde(default)]
+    pub discount: Option<PayAmountDto>,
+    #[serde(default)]
+    pub tax: Option<PayAmountDto>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct OrderShippingPayDto {
+    pub seller_id: u32,
+    // shipping fee charged by the seller, converted to buyer's currency,
+    // represented as string, can be converted from decimal type
+    pub amount: String,
 }
 
 #[derive(Deserialize, Serialize)]
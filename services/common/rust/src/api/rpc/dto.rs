@@ -1,7 +1,9 @@
 use std::collections::HashMap;
 
 use super::super::dto::PayAmountDto;
-use crate::api::dto::{BillingDto, CountryCode, OrderCurrencySnapshotDto, OrderLinePayDto};
+use crate::api::dto::{
+    BillingDto, CountryCode, OrderCurrencySnapshotDto, OrderLinePayDto, OrderShippingPayDto,
+};
 use serde::{Deserialize, Serialize};
 
 #[derive(Deserialize)]
@@ -59,6 +61,9 @@ pub struct OrderReplicaPaymentDto {
     pub lines: Vec<OrderLinePayDto>,
     pub currency: OrderCurrencySnapshotDto,
     pub billing: BillingDto,
+    // the fees are charged in addition to the amount of all the lines
+    #[serde(default)]
+    pub shipping: Vec<OrderShippingPayDto>,
 }
 
 #[derive(Serialize, Deserialize)]
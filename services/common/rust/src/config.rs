@@ -154,6 +154,12 @@ pub enum App3rdPartyCfg {
         name: String,
         data_src: String,
     },
+    // data source stored in local file system, the 3rd party is not
+    // accessed through network
+    local {
+        name: String,
+        data_src: String,
+    },
 }
 
 #[allow(non_camel_case_types)]
@@ -190,6 +196,28 @@ pub enum AppDataStoreCfg {
     DbServer(AppDbServerCfg),
 }
 
+#[derive(Deserialize, Debug)]
+pub struct AppInMemoryCacheCfg {
+    pub max_items: u32,
+    pub ttl_secs: u16, // default lifetime of each cached item
+}
+
+#[derive(Deserialize, Debug)]
+pub struct AppCacheServerCfg {
+    // host, port, and password of the cache server
+    #[serde(deserialize_with = "jsn_deny_empty_string")]
+    pub confidentiality_path: String,
+    pub max_conns: u16,
+    pub ttl_secs: u16,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "_type")]
+pub enum AppCacheCfg {
+    InMemory(AppInMemoryCacheCfg),
+    Redis(AppCacheServerCfg),
+}
+
 #[derive(Deserialize)]
 pub struct ApiServerCfg {
     pid_file: PIDfileCfg,
@@ -203,6 +231,7 @@ pub struct ApiServerCfg {
     pub auth: AppAuthCfg,
     pub confidentiality: AppConfidentialCfg,
     pub third_parties: Option<Vec<Arc<App3rdPartyCfg>>>,
+    pub cache: Option<AppCacheCfg>,
 }
 
 pub struct AppBasepathCfg {
@@ -0,0 +1,54 @@
+use ecommerce_common::api::dto::{CountryCode, CurrencyDto};
+
+#[test]
+fn currency_from_literal_string() {
+    [
+        ("JPY", CurrencyDto::JPY, 0u32),
+        ("EUR", CurrencyDto::EUR, 2),
+        ("TWD", CurrencyDto::TWD, 2),
+        ("KWD", CurrencyDto::KWD, 3),
+        ("jpy", CurrencyDto::Unknown, 0),
+        ("XYZ", CurrencyDto::Unknown, 0),
+    ]
+    .into_iter()
+    .map(|(raw, expect, scale)| {
+        let actual = CurrencyDto::from(raw);
+        assert_eq!(actual, expect);
+        assert_eq!(actual.amount_fraction_scale(), scale);
+        let actual = CurrencyDto::from(&raw.to_string());
+        assert_eq!(actual, expect);
+    })
+    .count();
+    let all = CurrencyDto::supported();
+    assert!(!all.contains(&CurrencyDto::Unknown));
+    all.into_iter()
+        .map(|c| {
+            let raw = c.to_string();
+            assert_eq!(CurrencyDto::from(raw.as_str()), c);
+            let serial = serde_json::to_string(&c).unwrap();
+            assert_eq!(serial, format!("\"{raw}\""));
+        })
+        .count();
+}
+
+#[test]
+fn country_from_literal_string() {
+    [
+        ("JP", CountryCode::JP),
+        ("DE", CountryCode::DE),
+        ("TW", CountryCode::TW),
+        ("XX", CountryCode::Unknown),
+    ]
+    .into_iter()
+    .map(|(raw, expect)| {
+        let actual = CountryCode::from(raw.to_string());
+        assert_eq!(actual, expect);
+        let serial: String = actual.into();
+        if expect != CountryCode::Unknown {
+            assert_eq!(serial.as_str(), raw);
+        }
+    })
+    .count();
+    let result = serde_json::from_str::<CountryCode>("\"FR\"");
+    assert!(matches!(result, Ok(CountryCode::FR)));
+}
@@ -108,6 +108,7 @@ fn parse_ext_cfg_file_ok() {
                     assert_eq!(name.as_str(), "external-service-02");
                     assert_eq!(data_src.as_str(), "/path/to/test-data");
                 }
+                App3rdPartyCfg::local { name: _, data_src: _ } => assert!(false),
             })
             .count();
     }
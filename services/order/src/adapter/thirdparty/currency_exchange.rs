@@ -7,7 +7,7 @@ use std::sync::Arc;
 use std::vec::Vec;
 
 use async_trait::async_trait;
-use hyper::header::{HeaderValue, AUTHORIZATION};
+use hyper::header::{HeaderName, HeaderValue, AUTHORIZATION};
 use hyper::Method;
 use rust_decimal::Decimal;
 use serde::Deserialize;
@@ -33,6 +33,10 @@ pub trait AbstractCurrencyExchange: Send + Sync {
     async fn refresh(&self, chosen: Vec<CurrencyDto>) -> Result<CurrencyModelSet, AppError>;
 }
 
+pub(super) const LABEL_OPENEXCHANGERATES: &str = "openexchangerates";
+pub(super) const LABEL_CURRENCYAPI: &str = "currencyapi";
+pub(super) const LABEL_FIXEDRATE: &str = "fixedrate";
+
 pub(super) struct AppCurrencyExchange {
     _host: String,
     _port: u16,
@@ -41,15 +45,49 @@ pub(super) struct AppCurrencyExchange {
     _secure_connector: TlsConnector,
 }
 
+pub(super) struct CurrencyApiExchange {
+    _host: String,
+    _port: u16,
+    _api_key: String,
+    _logctx: Arc<AppLogContext>,
+    _secure_connector: TlsConnector,
+}
+
+// fixed rates loaded from local file, the rates never change until the
+// application restarts
+pub(super) struct FixedRateCurrencyExchange {
+    _base: CurrencyDto,
+    _rates: Vec<CurrencyModel>,
+}
+
+// try each provider in the configured order, until any of them
+// returns the exchange rates successfully
+pub(super) struct CurrencyExchangeChain {
+    _providers: Vec<Box<dyn AbstractCurrencyExchange>>,
+    _logctx: Arc<AppLogContext>,
+}
+
 #[derive(Deserialize)]
 struct ExRateIntermediate {
     base: CurrencyDto,
     rates: HashMap<CurrencyDto, JsnNum>,
 }
 
+#[derive(Deserialize)]
+struct CurrApiRateIntermediate {
+    code: CurrencyDto,
+    value: JsnNum,
+}
+
+#[derive(Deserialize)]
+struct CurrApiIntermediate {
+    data: HashMap<String, CurrApiRateIntermediate>,
+}
+
 type MockDataSource = HashMap<CurrencyDto, Vec<String>>;
 
 pub(super) struct MockCurrencyExchange {
+    _label: String,
     _data: Mutex<MockDataSource>,
 }
 
@@ -78,6 +116,7 @@ impl TryFrom<ExRateIntermediate> for CurrencyModelSet {
             Ok(CurrencyModelSet {
                 base,
                 exchange_rates,
+                provider: None,
             })
         } else {
             let e = errors.remove(0);
@@ -86,6 +125,59 @@ impl TryFrom<ExRateIntermediate> for CurrencyModelSet {
     } // end of fn try-from
 } // end of impl CurrencyModelSet
 
+impl From<CurrApiIntermediate> for ExRateIntermediate {
+    fn from(value: CurrApiIntermediate) -> Self {
+        // the service applies USD as default base currency
+        let rates = value
+            .data
+            .into_values()
+            .map(|item| (item.code, item.value))
+            .collect::<HashMap<_, _>>();
+        Self {
+            base: CurrencyDto::USD,
+            rates,
+        }
+    }
+}
+
+fn load_credential(
+    credential_path: &str,
+    cfdntl: Arc<Box<dyn AbstractConfidentiality>>,
+) -> Result<String, AppError> {
+    let serial = cfdntl
+        .try_get_payload(credential_path)
+        .map_err(|e| AppError {
+            code: e.code,
+            detail: Some(e.detail),
+        })?;
+    serde_json::from_str::<String>(serial.as_str()).map_err(|_e| AppError {
+        code: AppErrorCode::DataCorruption,
+        detail: Some("credential-parse-failure".to_string()),
+    })
+}
+
+fn build_secure_connector() -> Result<TlsConnector, AppError> {
+    let mut builder = native_tls::TlsConnector::builder();
+    builder.min_protocol_version(Some(native_tls::Protocol::Tlsv12));
+    let sc = builder.build().map_err(|e| AppError {
+        code: AppErrorCode::InvalidInput,
+        detail: Some(e.to_string()),
+    })?;
+    Ok(sc.into())
+}
+
+fn open_data_src(
+    cfg_basepath: &AppBasepathCfg,
+    mut data_src_path: String,
+) -> Result<File, AppError> {
+    data_src_path.insert(0, '/');
+    data_src_path.insert_str(0, &cfg_basepath.service);
+    File::open(data_src_path.as_str()).map_err(|e| AppError {
+        code: AppErrorCode::IOerror(e.kind()),
+        detail: Some(data_src_path),
+    })
+}
+
 #[async_trait]
 impl AbstractCurrencyExchange for AppCurrencyExchange {
     async fn refresh(&self, chosen: Vec<CurrencyDto>) -> Result<CurrencyModelSet, AppError> {
@@ -138,25 +230,8 @@ impl AppCurrencyExchange {
         cfdntl: Arc<Box<dyn AbstractConfidentiality>>,
         _logctx: Arc<AppLogContext>,
     ) -> Result<Self, AppError> {
-        let serial = cfdntl
-            .try_get_payload(credential_path.as_str())
-            .map_err(|e| AppError {
-                code: e.code,
-                detail: Some(e.detail),
-            })?;
-        let _app_id = serde_json::from_str::<String>(serial.as_str()).map_err(|_e| AppError {
-            code: AppErrorCode::DataCorruption,
-            detail: Some("credential-parse-failure".to_string()),
-        })?;
-        let _secure_connector = {
-            let mut builder = native_tls::TlsConnector::builder();
-            builder.min_protocol_version(Some(native_tls::Protocol::Tlsv12));
-            let sc = builder.build().map_err(|e| AppError {
-                code: AppErrorCode::InvalidInput,
-                detail: Some(e.to_string()),
-            })?;
-            sc.into()
-        };
+        let _app_id = load_credential(credential_path.as_str(), cfdntl)?;
+        let _secure_connector = build_secure_connector()?;
         Ok(Self {
             _host: host,
             _port: port,
@@ -176,31 +251,182 @@ impl AppCurrencyExchange {
                 detail: Some(detail),
             }
         })?;
-        let obj = CurrencyModelSet::try_from(intermediate).map_err(|e| {
+        let mut obj = CurrencyModelSet::try_from(intermediate).map_err(|e| {
             app_log_event!(logctx_p, AppLogLevel::ERROR, "{:?}", e);
             e
         })?;
+        obj.provider = Some(LABEL_OPENEXCHANGERATES.to_string());
         Ok(obj)
     }
 } // end of impl AppCurrencyExchange
 
+#[async_trait]
+impl AbstractCurrencyExchange for CurrencyApiExchange {
+    async fn refresh(&self, chosen: Vec<CurrencyDto>) -> Result<CurrencyModelSet, AppError> {
+        let symbols = chosen
+            .into_iter()
+            .map(|c| c.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+        let api_key = HeaderValue::from_str(self._api_key.as_str()).map_err(|e| AppError {
+            code: AppErrorCode::InvalidInput,
+            detail: Some(e.to_string()),
+        })?;
+        let mut s_client = BaseClient::try_build(
+            self._host.clone(),
+            self._port,
+            &self._secure_connector,
+            self._logctx.clone(),
+        )
+        .await?;
+        let headers = vec![(HeaderName::from_static("apikey"), api_key)];
+        let resource_path = format!("/v3/latest?currencies={symbols}");
+        let (rawbody, status) = s_client
+            .execute(resource_path.as_str(), Method::GET, headers, None)
+            .await?;
+        if status.is_success() {
+            self._try_into_modelset(rawbody)
+        } else {
+            Err(AppError {
+                code: AppErrorCode::HttpHandshakeFailure,
+                detail: Some(format!(
+                    "host: {}:{}, status:{}",
+                    self._host.as_str(),
+                    self._port,
+                    status.as_u16()
+                )),
+            })
+        }
+    } // end of fn refresh
+} // end of impl CurrencyApiExchange
+
+impl CurrencyApiExchange {
+    pub(super) fn try_build(
+        host: String,
+        port: u16,
+        credential_path: String,
+        cfdntl: Arc<Box<dyn AbstractConfidentiality>>,
+        _logctx: Arc<AppLogContext>,
+    ) -> Result<Self, AppError> {
+        let _api_key = load_credential(credential_path.as_str(), cfdntl)?;
+        let _secure_connector = build_secure_connector()?;
+        Ok(Self {
+            _host: host,
+            _port: port,
+            _secure_connector,
+            _api_key,
+            _logctx,
+        })
+    }
+
+    fn _try_into_modelset(&self, rawbody: Vec<u8>) -> Result<CurrencyModelSet, AppError> {
+        let logctx_p = &self._logctx;
+        let intermediate =
+            serde_json::from_slice::<CurrApiIntermediate>(&rawbody).map_err(|e| {
+                let detail = e.to_string();
+                app_log_event!(logctx_p, AppLogLevel::ERROR, "{}", &detail);
+                AppError {
+                    code: AppErrorCode::DataCorruption,
+                    detail: Some(detail),
+                }
+            })?;
+        let mut obj =
+            CurrencyModelSet::try_from(ExRateIntermediate::from(intermediate)).map_err(|e| {
+                app_log_event!(logctx_p, AppLogLevel::ERROR, "{:?}", e);
+                e
+            })?;
+        obj.provider = Some(LABEL_CURRENCYAPI.to_string());
+        Ok(obj)
+    }
+} // end of impl CurrencyApiExchange
+
+impl FixedRateCurrencyExchange {
+    pub(super) fn try_build(
+        cfg_basepath: &AppBasepathCfg,
+        data_src_path: String,
+    ) -> Result<Self, AppError> {
+        let src_f = open_data_src(cfg_basepath, data_src_path)?;
+        let intermediate =
+            serde_json::from_reader::<File, ExRateIntermediate>(src_f).map_err(|e| AppError {
+                code: AppErrorCode::DataCorruption,
+                detail: Some(e.to_string()),
+            })?;
+        let CurrencyModelSet {
+            base,
+            exchange_rates,
+            provider: _,
+        } = CurrencyModelSet::try_from(intermediate)?;
+        Ok(Self {
+            _base: base,
+            _rates: exchange_rates,
+        })
+    }
+}
+
+#[async_trait]
+impl AbstractCurrencyExchange for FixedRateCurrencyExchange {
+    async fn refresh(&self, chosen: Vec<CurrencyDto>) -> Result<CurrencyModelSet, AppError> {
+        let exchange_rates = self
+            ._rates
+            .iter()
+            .filter(|m| chosen.contains(&m.name))
+            .cloned()
+            .collect::<Vec<_>>();
+        Ok(CurrencyModelSet {
+            base: self._base.clone(),
+            exchange_rates,
+            provider: Some(LABEL_FIXEDRATE.to_string()),
+        })
+    }
+} // end of impl FixedRateCurrencyExchange
+
+impl CurrencyExchangeChain {
+    pub(super) fn new(
+        _providers: Vec<Box<dyn AbstractCurrencyExchange>>,
+        _logctx: Arc<AppLogContext>,
+    ) -> Self {
+        Self {
+            _providers,
+            _logctx,
+        }
+    }
+}
+
+#[async_trait]
+impl AbstractCurrencyExchange for CurrencyExchangeChain {
+    async fn refresh(&self, chosen: Vec<CurrencyDto>) -> Result<CurrencyModelSet, AppError> {
+        let logctx_p = &self._logctx;
+        let mut last_error = AppError {
+            code: AppErrorCode::MissingConfig,
+            detail: Some("currency-exchange".to_string()),
+        };
+        for provider in self._providers.iter() {
+            match provider.refresh(chosen.clone()).await {
+                Ok(ms) => return Ok(ms),
+                Err(e) => {
+                    app_log_event!(logctx_p, AppLogLevel::WARNING, "{:?}", &e);
+                    last_error = e;
+                }
+            }
+        }
+        Err(last_error)
+    }
+} // end of impl CurrencyExchangeChain
+
 impl MockCurrencyExchange {
     pub(super) fn try_build(
         cfg_basepath: &AppBasepathCfg,
-        mut data_src_path: String,
+        label: String,
+        data_src_path: String,
     ) -> Result<Self, AppError> {
-        data_src_path.insert(0, '/');
-        data_src_path.insert_str(0, &cfg_basepath.service);
-        let src_f = File::open(data_src_path.as_str()).map_err(|e| AppError {
-            code: AppErrorCode::IOerror(e.kind()),
-            detail: Some(data_src_path),
-        })?;
+        let src_f = open_data_src(cfg_basepath, data_src_path)?;
         let data_src =
             serde_json::from_reader::<File, MockDataSource>(src_f).map_err(|e| AppError {
                 code: AppErrorCode::DataCorruption,
                 detail: Some(e.to_string()),
             })?;
         Ok(Self {
+            _label: label,
             _data: Mutex::new(data_src),
         })
     }
@@ -232,6 +458,7 @@ impl AbstractCurrencyExchange for MockCurrencyExchange {
         Ok(CurrencyModelSet {
             base: CurrencyDto::USD,
             exchange_rates,
+            provider: Some(self._label.clone()),
         })
     }
 } // end of impl MockCurrencyExchange
@@ -9,63 +9,126 @@ use std::vec::Vec;
 use ecommerce_common::confidentiality::AbstractConfidentiality;
 use ecommerce_common::config::{App3rdPartyCfg, AppBasepathCfg};
 use ecommerce_common::error::AppErrorCode;
-use ecommerce_common::logging::AppLogContext;
+use ecommerce_common::logging::{app_log_event, AppLogContext, AppLogLevel};
 
 use crate::error::AppError;
 
 pub use currency_exchange::AbstractCurrencyExchange;
-use currency_exchange::{AppCurrencyExchange, MockCurrencyExchange};
+use currency_exchange::{
+    AppCurrencyExchange, CurrencyApiExchange, CurrencyExchangeChain, FixedRateCurrencyExchange,
+    MockCurrencyExchange, LABEL_CURRENCYAPI, LABEL_FIXEDRATE, LABEL_OPENEXCHANGERATES,
+};
 
-pub(crate) fn app_currency_context(
+fn currency_provider_build(
     cfg_basepath: &AppBasepathCfg,
-    cfgs3pt: &Option<Vec<Arc<App3rdPartyCfg>>>,
+    cfg3pt: &App3rdPartyCfg,
     cfdntl: Arc<Box<dyn AbstractConfidentiality>>,
     logctx: Arc<AppLogContext>,
-) -> Result<Box<dyn AbstractCurrencyExchange>, AppError> {
-    const THIRD_PARTY_LABEL: &str = "openexchangerates";
-    let _cfgs = cfgs3pt.as_ref().cloned().ok_or(AppError {
-        code: AppErrorCode::MissingConfig,
-        detail: Some("3rd-parties".to_string()),
-    })?;
-    let result = _cfgs.into_iter().find_map(|c| match &*c {
+) -> Option<Result<Box<dyn AbstractCurrencyExchange>, AppError>> {
+    match cfg3pt {
         App3rdPartyCfg::dev {
             name,
             host,
             port,
             confidentiality_path,
-        } => {
-            if name.to_lowercase().as_str() == THIRD_PARTY_LABEL {
+        } => match name.to_lowercase().as_str() {
+            LABEL_OPENEXCHANGERATES => {
                 let r = AppCurrencyExchange::try_build(
                     host.clone(),
                     *port,
                     confidentiality_path.clone(),
-                    cfdntl.clone(),
-                    logctx.clone(),
+                    cfdntl,
+                    logctx,
                 )
                 .map(|v| {
                     let o: Box<dyn AbstractCurrencyExchange> = Box::new(v);
                     o
                 });
                 Some(r)
-            } else {
-                None
             }
-        }
-        App3rdPartyCfg::test { name, data_src } => {
-            if name.to_lowercase().as_str() == THIRD_PARTY_LABEL {
-                let r = MockCurrencyExchange::try_build(cfg_basepath, data_src.clone()).map(|v| {
+            LABEL_CURRENCYAPI => {
+                let r = CurrencyApiExchange::try_build(
+                    host.clone(),
+                    *port,
+                    confidentiality_path.clone(),
+                    cfdntl,
+                    logctx,
+                )
+                .map(|v| {
                     let o: Box<dyn AbstractCurrencyExchange> = Box::new(v);
                     o
                 });
                 Some(r)
+            }
+            _others => None,
+        },
+        App3rdPartyCfg::test { name, data_src } => {
+            let label = name.to_lowercase();
+            if [LABEL_OPENEXCHANGERATES, LABEL_CURRENCYAPI].contains(&label.as_str()) {
+                let r = MockCurrencyExchange::try_build(cfg_basepath, label, data_src.clone()).map(
+                    |v| {
+                        let o: Box<dyn AbstractCurrencyExchange> = Box::new(v);
+                        o
+                    },
+                );
+                Some(r)
             } else {
                 None
             }
         }
-    }); // end of find-map
-    let ctx = result.ok_or(AppError {
+        App3rdPartyCfg::local { name, data_src } => {
+            if name.to_lowercase().as_str() == LABEL_FIXEDRATE {
+                let r =
+                    FixedRateCurrencyExchange::try_build(cfg_basepath, data_src.clone()).map(|v| {
+                        let o: Box<dyn AbstractCurrencyExchange> = Box::new(v);
+                        o
+                    });
+                Some(r)
+            } else {
+                None
+            }
+        }
+    }
+} // end of fn currency_provider_build
+
+// the exchange-rate providers are tried in the same order as they are
+// listed in the configuration, a provider which cannot be built is skipped
+pub(crate) fn app_currency_context(
+    cfg_basepath: &AppBasepathCfg,
+    cfgs3pt: &Option<Vec<Arc<App3rdPartyCfg>>>,
+    cfdntl: Arc<Box<dyn AbstractConfidentiality>>,
+    logctx: Arc<AppLogContext>,
+) -> Result<Box<dyn AbstractCurrencyExchange>, AppError> {
+    let _cfgs = cfgs3pt.as_ref().cloned().ok_or(AppError {
         code: AppErrorCode::MissingConfig,
-        detail: Some("currency-exchange".to_string()),
-    })??;
-    Ok(ctx)
+        detail: Some("3rd-parties".to_string()),
+    })?;
+    let mut errors = Vec::new();
+    let providers = _cfgs
+        .iter()
+        .filter_map(|c| {
+            currency_provider_build(cfg_basepath, c.as_ref(), cfdntl.clone(), logctx.clone())
+        })
+        .filter_map(|r| {
+            r.map_err(|e| {
+                app_log_event!(logctx, AppLogLevel::ERROR, "{:?}", &e);
+                errors.push(e);
+            })
+            .ok()
+        })
+        .collect::<Vec<_>>();
+    if providers.is_empty() {
+        let e = if errors.is_empty() {
+            AppError {
+                code: AppErrorCode::MissingConfig,
+                detail: Some("currency-exchange".to_string()),
+            }
+        } else {
+            errors.remove(0)
+        };
+        Err(e)
+    } else {
+        let chain = CurrencyExchangeChain::new(providers, logctx);
+        Ok(Box::new(chain))
+    }
 } // end of  fn app_currency_context
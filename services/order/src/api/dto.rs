@@ -9,7 +9,7 @@ pub struct ShippingOptionDto {
     pub method: ShippingMethod,
 }
 
-#[derive(Deserialize, Serialize)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 pub enum ShippingMethod {
     UPS,
     FedEx,
@@ -4,7 +4,7 @@ use chrono::offset::FixedOffset;
 use chrono::DateTime;
 use serde::{Deserialize, Serialize};
 
-use ecommerce_common::api::dto::CurrencyDto;
+use ecommerce_common::api::dto::{CountryCode, CurrencyDto};
 
 use crate::api::dto::{ProdAttrValueDto, ShippingDto};
 
@@ -45,6 +45,103 @@ pub struct ProductPriceDto {
     pub creating: Vec<ProductPriceEditDto>,
 }
 
+#[derive(Deserialize)]
+pub struct TaxRateDto {
+    pub country: CountryCode,
+    // omit the region for the rate applied to whole country
+    pub region: Option<String>,
+    // omit the store ID for the rate applied to all stores
+    pub store_id: Option<u32>,
+    pub rate: String, // decimal fraction of the price, e.g. `0.05`
+}
+
+#[derive(Deserialize)]
+pub struct CurrencyRateHistoryReqDto {
+    pub base: CurrencyDto,
+    pub quote: CurrencyDto,
+    pub start: DateTime<FixedOffset>,
+    pub end: DateTime<FixedOffset>,
+}
+
+#[derive(Serialize)]
+pub struct CurrencyPairRateDto {
+    pub time: String,
+    pub rate: String, // number of units in quote currency per unit in base currency
+}
+
+#[derive(Serialize)]
+pub struct CurrencyRateHistoryRespDto {
+    pub base: CurrencyDto,
+    pub quote: CurrencyDto,
+    pub rates: Vec<CurrencyPairRateDto>,
+}
+
+#[derive(Deserialize)]
+pub struct TaxRateEditDto {
+    pub rates: Vec<TaxRateDto>,
+}
+
+#[derive(Deserialize)]
+pub struct StockAlertThresholdDto {
+    pub store_id: u32,
+    pub low_stock_qty: u32,
+    pub expiry_within_days: u16,
+}
+
+#[derive(Deserialize)]
+pub struct StockAlertThresholdEditDto {
+    pub thresholds: Vec<StockAlertThresholdDto>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub enum StockAlertKindDto {
+    #[serde(rename = "low-stock")]
+    LowStock,
+    #[serde(rename = "expiring-batch")]
+    ExpiringBatch,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct StockAlertDto {
+    pub kind: StockAlertKindDto,
+    pub product_id: u64,
+    pub expiry: Option<DateTime<FixedOffset>>,
+    pub num_avail: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct StockAlertPublishDto {
+    pub store_id: u32,
+    pub alerts: Vec<StockAlertDto>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[allow(clippy::upper_case_acronyms)]
+pub enum StockAllocStrategyDto {
+    FEFO,
+    FIFO,
+}
+
+#[derive(Deserialize)]
+pub struct StockAllocPolicyDto {
+    pub store_id: u32,
+    // omit the product ID to set the default policy of the store
+    pub product_id: Option<u64>,
+    pub strategy: StockAllocStrategyDto,
+    pub min_shelf_days: u16,
+}
+
+#[derive(Deserialize)]
+pub struct StockWarehouseDto {
+    pub store_id: u32,
+    // zero indicates default warehouse of the store, which keeps the stock
+    // saved without warehouse ID
+    pub warehouse_id: u32,
+    pub country: CountryCode,
+    pub region: String,
+    pub city: String,
+}
+
 #[derive(Deserialize)]
 pub struct InventoryEditStockLevelDto {
     // number to add to stock level, negative number means cancellation
@@ -54,6 +151,9 @@ pub struct InventoryEditStockLevelDto {
     pub store_id: u32,
     pub product_id: u64, // TODO, declare type alias
     pub expiry: DateTime<FixedOffset>,
+    // omit the warehouse ID for the default warehouse of the store
+    #[serde(default)]
+    pub warehouse_id: u32,
 }
 
 #[derive(Serialize)]
@@ -69,6 +169,17 @@ pub struct StockLevelPresentDto {
     pub store_id: u32,
     pub product_id: u64, // TODO, declare type alias
     pub expiry: DateTime<FixedOffset>,
+    pub warehouse_id: u32,
+}
+
+// backordered lines which failed to take the new stock, they are tried again
+// on next restock of the same products
+#[derive(Serialize)]
+pub struct StockBackorderErrorDto {
+    pub seller_id: u32,
+    // `None` if the backordered lines of the store cannot be loaded
+    pub order_id: Option<String>,
+    pub detail: String,
 }
 
 #[derive(Deserialize)]
@@ -87,6 +198,8 @@ pub struct OrderLineStockReservingDto {
     pub seller_id: u32,
     pub product_id: u64,
     pub qty: u32,
+    // warehouse which ships the items, `None` if no item is allocated yet
+    pub warehouse_id: Option<u32>,
 }
 #[derive(Serialize)]
 pub struct OrderLineStockReturningDto {
@@ -1,13 +1,24 @@
 use std::vec::Vec;
 
+use ecommerce_common::adapter::rpc;
+use ecommerce_common::error::AppErrorCode;
 use ecommerce_common::logging::{app_log_event, AppLogLevel};
 
-use crate::repository::{app_repo_currency, app_repo_product_price};
+use crate::error::AppError;
+use crate::repository::{
+    app_repo_currency, app_repo_product_price, app_repo_stock_alert, app_repo_tax_rate,
+};
 use crate::rpc::AppRpcClientReqProperty;
-use crate::usecase::{CurrencyRateRefreshUseCase, EditProductPriceUseCase};
+use crate::usecase::{
+    CurrencyRateHistoryUseCase, CurrencyRateRefreshUseCase, EditProductPriceUseCase,
+    EditStockAlertThresholdUseCase, EditTaxRateUseCase,
+};
 use crate::AppSharedState;
 
-use super::dto::ProductPriceDto;
+use super::dto::{
+    CurrencyRateHistoryReqDto, CurrencyRateHistoryRespDto, ProductPriceDto,
+    StockAlertThresholdEditDto, TaxRateEditDto,
+};
 use super::{build_error_response, PyCelery};
 
 pub(super) async fn store_products(
@@ -77,3 +88,97 @@ pub(super) async fn currency_refresh(
     };
     resp.to_string().into_bytes()
 }
+
+// the request is plain JSON object without celery task ID, the response carries
+// status and result fields, same as other endpoints which are not celery tasks
+pub(super) async fn currency_history(
+    req: AppRpcClientReqProperty,
+    shr_state: AppSharedState,
+) -> Vec<u8> {
+    let logctx = shr_state.log_context().clone();
+    let result = serde_json::from_slice::<CurrencyRateHistoryReqDto>(req.msgbody.as_slice())
+        .map_err(|e| AppError {
+            code: AppErrorCode::InvalidJsonFormat,
+            detail: Some(e.to_string()),
+        });
+    let result = match result {
+        Ok(v) => match app_repo_currency(shr_state.datastore()).await {
+            Ok(repo) => CurrencyRateHistoryUseCase::execute(repo, v, logctx).await,
+            Err(e) => Err(e),
+        },
+        Err(e) => Err(e),
+    };
+    let resp = match result {
+        Ok(v) => rpc::base_response::<CurrencyRateHistoryRespDto>(2, "SUCCESS", Some(v)).unwrap(),
+        Err(e) => build_error_response(e),
+    };
+    resp.to_string().into_bytes()
+}
+
+pub(super) async fn tax_rates(req: AppRpcClientReqProperty, shr_state: AppSharedState) -> Vec<u8> {
+    let ds = shr_state.datastore();
+    let logctx = shr_state.log_context().clone();
+    let task_id = match PyCelery::get_task_id(&req) {
+        Ok(t) => t,
+        Err(e) => {
+            return build_error_response(e).to_string().into_bytes();
+        }
+    };
+    let repo = match app_repo_tax_rate(ds).await {
+        Ok(r) => r,
+        Err(e) => {
+            return PyCelery::error_response(task_id, e)
+                .to_string()
+                .into_bytes();
+        }
+    };
+    let result = PyCelery::deserialize_req::<Vec<String>, TaxRateEditDto>(&req.msgbody);
+    let s = match result {
+        Ok((_arg, data)) => {
+            let result = EditTaxRateUseCase::execute(repo, data.rates, logctx).await;
+            if let Err(e) = result {
+                PyCelery::error_response(task_id, e)
+            } else {
+                PyCelery::build_response(task_id.as_str(), "SUCCESS")
+            }
+        }
+        Err(e) => PyCelery::error_response(task_id, e),
+    };
+    s.to_string().into_bytes()
+}
+
+pub(super) async fn stock_alert_thresholds(
+    req: AppRpcClientReqProperty,
+    shr_state: AppSharedState,
+) -> Vec<u8> {
+    let ds = shr_state.datastore();
+    let logctx = shr_state.log_context().clone();
+    let task_id = match PyCelery::get_task_id(&req) {
+        Ok(t) => t,
+        Err(e) => {
+            return build_error_response(e).to_string().into_bytes();
+        }
+    };
+    let repo = match app_repo_stock_alert(ds).await {
+        Ok(r) => r,
+        Err(e) => {
+            return PyCelery::error_response(task_id, e)
+                .to_string()
+                .into_bytes();
+        }
+    };
+    let result = PyCelery::deserialize_req::<Vec<String>, StockAlertThresholdEditDto>(&req.msgbody);
+    let s = match result {
+        Ok((_arg, data)) => {
+            let result =
+                EditStockAlertThresholdUseCase::execute(repo, data.thresholds, logctx).await;
+            if let Err(e) = result {
+                PyCelery::error_response(task_id, e)
+            } else {
+                PyCelery::build_response(task_id.as_str(), "SUCCESS")
+            }
+        }
+        Err(e) => PyCelery::error_response(task_id, e),
+    };
+    s.to_string().into_bytes()
+}
@@ -41,6 +41,15 @@ pub async fn route_to_handler(
         RpcConst::EDIT_PRODUCT_PRICE => Ok(misc::store_products(req, shr_state).await),
         RpcConst::STOCK_LEVEL_EDIT => Ok(stock_level::inventory_edit(req, shr_state).await),
         RpcConst::CURRENCY_RATE_REFRESH => Ok(misc::currency_refresh(req, shr_state).await),
+        RpcConst::CURRENCY_RATE_HISTORY => Ok(misc::currency_history(req, shr_state).await),
+        RpcConst::EDIT_TAX_RATE => Ok(misc::tax_rates(req, shr_state).await),
+        RpcConst::STOCK_ALERT_THRESHOLD_EDIT => {
+            Ok(misc::stock_alert_thresholds(req, shr_state).await)
+        }
+        RpcConst::STOCK_ALLOC_POLICY_EDIT => {
+            Ok(stock_level::alloc_policy_edit(req, shr_state).await)
+        }
+        RpcConst::STOCK_WAREHOUSE_EDIT => Ok(stock_level::warehouse_edit(req, shr_state).await),
         RpcConst::STOCK_RETURN_CANCELLED => {
             Ok(stock_level::inventory_return_cancelled(req, shr_state).await)
         }
@@ -1,5 +1,7 @@
 use std::vec::Vec;
 
+use serde_json::Value as JsnVal;
+
 use ecommerce_common::adapter::rpc;
 use ecommerce_common::error::AppErrorCode;
 use ecommerce_common::logging::{app_log_event, AppLogLevel};
@@ -7,16 +9,17 @@ use ecommerce_common::logging::{app_log_event, AppLogLevel};
 use crate::error::AppError;
 use crate::repository::app_repo_order;
 use crate::rpc::AppRpcClientReqProperty;
-use crate::usecase::StockLevelUseCase;
+use crate::usecase::{StockLevelEditUcOutput, StockLevelUseCase};
 use crate::AppSharedState;
 
 use super::build_error_response;
 use super::dto::{
-    InventoryEditStockLevelDto, StockLevelPresentDto, StockLevelReturnDto, StockReturnErrorDto,
+    InventoryEditStockLevelDto, StockAllocPolicyDto, StockLevelPresentDto, StockLevelReturnDto,
+    StockReturnErrorDto, StockWarehouseDto,
 };
 
 macro_rules! common_setup {
-    ($req_type:ty, $shr_state:ident, $serial:expr, $uc_fn:expr, $resp_type:ty) => {{
+    ($req_type:ty, $shr_state:ident, $serial:expr, $uc_fn:expr, reply = $reply_fn:expr) => {{
         let logctx_p = $shr_state.log_context().clone();
         app_log_event!(logctx_p, AppLogLevel::DEBUG, "{:?}", $serial);
         let reqbody = match serde_json::from_slice::<$req_type>($serial) {
@@ -40,8 +43,7 @@ macro_rules! common_setup {
         };
         match $uc_fn(reqbody, repo, logctx_p.clone()).await {
             Ok(uc_resp) => {
-                let result = rpc::base_response::<$resp_type>(2, "SUCCESS", Some(uc_resp));
-                let r = result.unwrap();
+                let r = $reply_fn(uc_resp);
                 let resp_raw = serde_json::to_vec(&r).unwrap();
                 app_log_event!(logctx_p, AppLogLevel::DEBUG, "{:?}", resp_raw);
                 resp_raw
@@ -52,8 +54,34 @@ macro_rules! common_setup {
             }
         }
     }};
+    ($req_type:ty, $shr_state:ident, $serial:expr, $uc_fn:expr, $resp_type:ty) => {
+        common_setup!(
+            $req_type,
+            $shr_state,
+            $serial,
+            $uc_fn,
+            reply = |uc_resp| {
+                rpc::base_response::<$resp_type>(2, "SUCCESS", Some(uc_resp)).unwrap()
+            }
+        )
+    };
 } // end of common_setup
 
+// allocation errors of backorders are reported next to the saved stock levels
+fn inventory_edit_reply(uc_resp: StockLevelEditUcOutput) -> JsnVal {
+    let StockLevelEditUcOutput {
+        levels,
+        backorder_errors,
+    } = uc_resp;
+    let mut out =
+        rpc::base_response::<Vec<StockLevelPresentDto>>(3, "SUCCESS", Some(levels)).unwrap();
+    if let Some(m) = out.as_object_mut() {
+        let errors = serde_json::to_value(backorder_errors).unwrap();
+        m.insert("backorder_errors".to_string(), errors);
+    }
+    out
+}
+
 pub(super) async fn inventory_edit(
     req: AppRpcClientReqProperty,
     shr_state: AppSharedState,
@@ -63,7 +91,33 @@ pub(super) async fn inventory_edit(
         shr_state,
         req.msgbody.as_slice().trim_ascii(),
         StockLevelUseCase::try_edit,
-        Vec<StockLevelPresentDto>
+        reply = inventory_edit_reply
+    )
+}
+
+pub(super) async fn alloc_policy_edit(
+    req: AppRpcClientReqProperty,
+    shr_state: AppSharedState,
+) -> Vec<u8> {
+    common_setup!(
+        Vec<StockAllocPolicyDto>,
+        shr_state,
+        req.msgbody.as_slice(),
+        StockLevelUseCase::try_edit_alloc_policy,
+        ()
+    )
+}
+
+pub(super) async fn warehouse_edit(
+    req: AppRpcClientReqProperty,
+    shr_state: AppSharedState,
+) -> Vec<u8> {
+    common_setup!(
+        Vec<StockWarehouseDto>,
+        shr_state,
+        req.msgbody.as_slice(),
+        StockLevelUseCase::try_edit_warehouse,
+        ()
     )
 }
 
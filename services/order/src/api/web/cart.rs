@@ -1,23 +1,34 @@
 use axum::debug_handler;
-use axum::extract::{Json as ExtractJson, Path as ExtractPath, State as ExtractState};
+use axum::extract::{
+    Json as ExtractJson, Path as ExtractPath, Query as ExtractQuery, State as ExtractState,
+};
 use axum::http::{header, HeaderMap, HeaderValue, StatusCode};
 use axum::response::IntoResponse;
 
 use ecommerce_common::logging::{app_log_event, AppLogLevel};
 
 use crate::constant::HTTP_CONTENT_TYPE_JSON;
-use crate::repository::app_repo_cart;
+use crate::repository::{
+    app_repo_cart, app_repo_currency, app_repo_order, app_repo_product_policy,
+    app_repo_product_price, app_repo_promotion, app_repo_shipping_method, app_repo_tax_rate,
+};
 use crate::usecase::{
-    DiscardCartUsKsResult, DiscardCartUseCase, ModifyCartLineUseCase, ModifyCartUsKsResult,
+    CheckoutCartUsKsResult, CheckoutCartUseCase, CreateOrderUseCase, DiscardCartUsKsResult,
+    DiscardCartUseCase, EditCartCollabUsKsResult, EditCartCollaboratorUseCase,
+    ModifyCartLineUseCase, ModifyCartUsKsResult, QuoteCartUsKsResult, QuoteCartUseCase,
     RetrieveCartUsKsResult, RetrieveCartUseCase,
 };
 use crate::{AppAuthedClaim, AppSharedState};
 
-use super::dto::CartDto;
+use super::dto::{
+    CartCheckoutReqData, CartCollaboratorReqDto, CartDto, CartOwnerReqQuery, CartQuoteReqQuery,
+};
+use super::order::create_order_error_response;
 
 #[debug_handler(state=AppSharedState)]
 pub(super) async fn modify_lines(
     ExtractPath(seq_num): ExtractPath<u8>,
+    ExtractQuery(req_query): ExtractQuery<CartOwnerReqQuery>,
     authed_usr: AppAuthedClaim,
     ExtractState(appstate): ExtractState<AppSharedState>,
     ExtractJson(req_body): ExtractJson<CartDto>,
@@ -35,14 +46,16 @@ pub(super) async fn modify_lines(
             return (StatusCode::INTERNAL_SERVER_ERROR, hdr_map, default_body);
         }
     };
+    let owner = req_query.owner.unwrap_or(authed_usr.profile);
     let uc = ModifyCartLineUseCase {
         repo,
         authed_usr,
         log_ctx: logctx.clone(),
     };
-    let (status, resp_body) = match uc.execute(seq_num, req_body).await {
+    let (status, resp_body) = match uc.execute(owner, seq_num, req_body).await {
         ModifyCartUsKsResult::Success => (StatusCode::OK, default_body),
         ModifyCartUsKsResult::NotFound => (StatusCode::NOT_FOUND, default_body),
+        ModifyCartUsKsResult::PermissionDenied => (StatusCode::FORBIDDEN, default_body),
         ModifyCartUsKsResult::QuotaExceed(e) => {
             (StatusCode::BAD_REQUEST, serde_json::to_string(&e).unwrap())
         }
@@ -57,6 +70,7 @@ pub(super) async fn modify_lines(
 #[debug_handler(state=AppSharedState)]
 pub(super) async fn discard(
     ExtractPath(seq_num): ExtractPath<u8>,
+    ExtractQuery(req_query): ExtractQuery<CartOwnerReqQuery>,
     authed_usr: AppAuthedClaim,
     ExtractState(appstate): ExtractState<AppSharedState>,
 ) -> impl IntoResponse {
@@ -68,10 +82,12 @@ pub(super) async fn discard(
             return (StatusCode::INTERNAL_SERVER_ERROR, HeaderMap::new());
         }
     };
+    let owner = req_query.owner.unwrap_or(authed_usr.profile);
     let uc = DiscardCartUseCase { repo, authed_usr };
-    let status = match uc.execute(seq_num).await {
+    let status = match uc.execute(owner, seq_num).await {
         DiscardCartUsKsResult::Success => StatusCode::NO_CONTENT,
         DiscardCartUsKsResult::NotFound => StatusCode::GONE,
+        DiscardCartUsKsResult::PermissionDenied => StatusCode::FORBIDDEN,
         DiscardCartUsKsResult::ServerError(e) => {
             app_log_event!(logctx, AppLogLevel::ERROR, "{:?}", e);
             StatusCode::INTERNAL_SERVER_ERROR
@@ -83,6 +99,7 @@ pub(super) async fn discard(
 #[debug_handler(state=AppSharedState)]
 pub(super) async fn retrieve(
     ExtractPath(seq_num): ExtractPath<u8>,
+    ExtractQuery(req_query): ExtractQuery<CartOwnerReqQuery>,
     authed_usr: AppAuthedClaim,
     ExtractState(appstate): ExtractState<AppSharedState>,
 ) -> impl IntoResponse {
@@ -102,10 +119,12 @@ pub(super) async fn retrieve(
             return (StatusCode::INTERNAL_SERVER_ERROR, hdr_map, default_body);
         }
     };
+    let owner = req_query.owner.unwrap_or(authed_usr.profile);
     let uc = RetrieveCartUseCase { repo, authed_usr };
-    let (status, resp_body) = match uc.execute(seq_num).await {
+    let (status, resp_body) = match uc.execute(owner, seq_num).await {
         RetrieveCartUsKsResult::Success(v) => (StatusCode::OK, serde_json::to_string(&v).unwrap()),
         RetrieveCartUsKsResult::NotFound => (StatusCode::NOT_FOUND, default_body),
+        RetrieveCartUsKsResult::PermissionDenied => (StatusCode::FORBIDDEN, default_body),
         RetrieveCartUsKsResult::ServerError(e) => {
             app_log_event!(logctx, AppLogLevel::ERROR, "{:?}", e);
             (StatusCode::INTERNAL_SERVER_ERROR, default_body)
@@ -113,3 +132,211 @@ pub(super) async fn retrieve(
     };
     (status, hdr_map, resp_body)
 }
+
+async fn edit_collaborators_common(
+    seq_num: u8,
+    authed_usr: AppAuthedClaim,
+    appstate: AppSharedState,
+    req_body: CartCollaboratorReqDto,
+    is_invite: bool,
+) -> impl IntoResponse {
+    let hdr_map = {
+        let resp_ctype_val = HeaderValue::from_str(HTTP_CONTENT_TYPE_JSON).unwrap();
+        let mut hmap = HeaderMap::new();
+        hmap.insert(header::CONTENT_TYPE, resp_ctype_val);
+        hmap
+    };
+    let default_body = "{}".to_string();
+    let logctx = appstate.log_context().clone();
+
+    let repo = match app_repo_cart(appstate.datastore()).await {
+        Ok(v) => v,
+        Err(e) => {
+            app_log_event!(logctx, AppLogLevel::ERROR, "{:?}", e);
+            return (StatusCode::INTERNAL_SERVER_ERROR, hdr_map, default_body);
+        }
+    };
+    let uc = EditCartCollaboratorUseCase { repo, authed_usr };
+    let result = if is_invite {
+        uc.invite(seq_num, req_body).await
+    } else {
+        uc.revoke(seq_num, req_body).await
+    };
+    let (status, resp_body) = match result {
+        EditCartCollabUsKsResult::Success(v) => {
+            (StatusCode::OK, serde_json::to_string(&v).unwrap())
+        }
+        EditCartCollabUsKsResult::NotFound => (StatusCode::NOT_FOUND, default_body),
+        EditCartCollabUsKsResult::QuotaExceed(e) => {
+            (StatusCode::BAD_REQUEST, serde_json::to_string(&e).unwrap())
+        }
+        EditCartCollabUsKsResult::ServerError(e) => {
+            app_log_event!(logctx, AppLogLevel::ERROR, "{:?}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, default_body)
+        }
+    };
+    (status, hdr_map, resp_body)
+} // end of fn edit_collaborators_common
+
+#[debug_handler(state=AppSharedState)]
+pub(super) async fn invite_collaborators(
+    ExtractPath(seq_num): ExtractPath<u8>,
+    authed_usr: AppAuthedClaim,
+    ExtractState(appstate): ExtractState<AppSharedState>,
+    ExtractJson(req_body): ExtractJson<CartCollaboratorReqDto>,
+) -> impl IntoResponse {
+    edit_collaborators_common(seq_num, authed_usr, appstate, req_body, true).await
+}
+
+#[debug_handler(state=AppSharedState)]
+pub(super) async fn revoke_collaborators(
+    ExtractPath(seq_num): ExtractPath<u8>,
+    authed_usr: AppAuthedClaim,
+    ExtractState(appstate): ExtractState<AppSharedState>,
+    ExtractJson(req_body): ExtractJson<CartCollaboratorReqDto>,
+) -> impl IntoResponse {
+    edit_collaborators_common(seq_num, authed_usr, appstate, req_body, false).await
+}
+
+#[debug_handler(state=AppSharedState)]
+pub(super) async fn checkout(
+    ExtractPath(seq_num): ExtractPath<u8>,
+    ExtractQuery(req_query): ExtractQuery<CartOwnerReqQuery>,
+    authed_usr: AppAuthedClaim,
+    ExtractState(appstate): ExtractState<AppSharedState>,
+    ExtractJson(req_body): ExtractJson<CartCheckoutReqData>,
+) -> impl IntoResponse {
+    let hdr_map = {
+        let resp_ctype_val = HeaderValue::from_str(HTTP_CONTENT_TYPE_JSON).unwrap();
+        let mut hmap = HeaderMap::new();
+        hmap.insert(header::CONTENT_TYPE, resp_ctype_val);
+        hmap
+    };
+    let default_body = "{}".to_string();
+    let logctx = appstate.log_context().clone();
+    let ds = appstate.datastore();
+    let results = (
+        app_repo_cart(ds.clone()).await,
+        app_repo_currency(ds.clone()).await,
+        app_repo_order(ds.clone()).await,
+        app_repo_product_price(ds.clone()).await,
+        app_repo_product_policy(ds.clone()).await,
+        app_repo_promotion(ds.clone()).await,
+        app_repo_tax_rate(ds.clone()).await,
+        app_repo_shipping_method(ds).await,
+    );
+    let (repo, repo_currex, repo_order, repo_price, repo_policy, repo_promo, repo_tax, repo_ship) =
+        match results {
+            (Ok(r0), Ok(r1), Ok(r2), Ok(r3), Ok(r4), Ok(r5), Ok(r6), Ok(r7)) => {
+                (r0, r1, r2, r3, r4, r5, r6, r7)
+            }
+            (r0, r1, r2, r3, r4, r5, r6, r7) => {
+                let errmsgs = [
+                    r0.err(),
+                    r1.err(),
+                    r2.err(),
+                    r3.err(),
+                    r4.err(),
+                    r5.err(),
+                    r6.err(),
+                    r7.err(),
+                ]
+                .into_iter()
+                .flatten()
+                .map(|e| e.to_string())
+                .collect::<Vec<_>>();
+                app_log_event!(logctx, AppLogLevel::ERROR, "{:?}", errmsgs);
+                return (StatusCode::INTERNAL_SERVER_ERROR, hdr_map, default_body);
+            }
+        };
+    let owner = req_query.owner.unwrap_or(authed_usr.profile);
+    let uc_order = CreateOrderUseCase {
+        glb_state: appstate,
+        repo_currex,
+        repo_order,
+        repo_price,
+        repo_policy,
+        repo_promo,
+        repo_tax,
+        repo_ship,
+        auth_claim: authed_usr,
+    };
+    let uc = CheckoutCartUseCase {
+        repo,
+        uc_order,
+        log_ctx: logctx.clone(),
+    };
+    let (status, resp_body) = match uc.execute(owner, seq_num, req_body).await {
+        CheckoutCartUsKsResult::Success(v) => {
+            (StatusCode::CREATED, serde_json::to_string(&v).unwrap())
+        }
+        CheckoutCartUsKsResult::NotFound => (StatusCode::NOT_FOUND, default_body),
+        CheckoutCartUsKsResult::PermissionDenied => (StatusCode::FORBIDDEN, default_body),
+        CheckoutCartUsKsResult::EmptyCart => (
+            StatusCode::BAD_REQUEST,
+            r#"{"reason":"empty-cart"}"#.to_string(),
+        ),
+        CheckoutCartUsKsResult::InProgress => (StatusCode::CONFLICT, default_body),
+        CheckoutCartUsKsResult::CreateOrder(e) => create_order_error_response(e, logctx),
+        CheckoutCartUsKsResult::ServerError(e) => {
+            app_log_event!(logctx, AppLogLevel::ERROR, "{:?}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, default_body)
+        }
+    };
+    (status, hdr_map, resp_body)
+} // end of fn checkout
+
+#[debug_handler(state=AppSharedState)]
+pub(super) async fn quote(
+    ExtractPath(seq_num): ExtractPath<u8>,
+    ExtractQuery(req_query): ExtractQuery<CartQuoteReqQuery>,
+    authed_usr: AppAuthedClaim,
+    ExtractState(appstate): ExtractState<AppSharedState>,
+) -> impl IntoResponse {
+    let hdr_map = {
+        let resp_ctype_val = HeaderValue::from_str(HTTP_CONTENT_TYPE_JSON).unwrap();
+        let mut hmap = HeaderMap::new();
+        hmap.insert(header::CONTENT_TYPE, resp_ctype_val);
+        hmap
+    };
+    let default_body = "{}".to_string();
+    let logctx = appstate.log_context().clone();
+    let ds = appstate.datastore();
+    let results = (
+        app_repo_cart(ds.clone()).await,
+        app_repo_currency(ds.clone()).await,
+        app_repo_order(ds.clone()).await,
+        app_repo_product_price(ds).await,
+    );
+    let (repo, repo_currex, repo_order, repo_price) = match results {
+        (Ok(r0), Ok(r1), Ok(r2), Ok(r3)) => (r0, r1, r2, r3),
+        (r0, r1, r2, r3) => {
+            let errmsgs = [r0.err(), r1.err(), r2.err(), r3.err()]
+                .into_iter()
+                .flatten()
+                .map(|e| e.to_string())
+                .collect::<Vec<_>>();
+            app_log_event!(logctx, AppLogLevel::ERROR, "{:?}", errmsgs);
+            return (StatusCode::INTERNAL_SERVER_ERROR, hdr_map, default_body);
+        }
+    };
+    let CartQuoteReqQuery { owner, currency } = req_query;
+    let owner = owner.unwrap_or(authed_usr.profile);
+    let uc = QuoteCartUseCase {
+        repo,
+        repo_price,
+        repo_stock: repo_order.stock(),
+        repo_currex,
+        authed_usr,
+    };
+    let (status, resp_body) = match uc.execute(owner, seq_num, currency).await {
+        QuoteCartUsKsResult::Success(v) => (StatusCode::OK, serde_json::to_string(&v).unwrap()),
+        QuoteCartUsKsResult::NotFound => (StatusCode::NOT_FOUND, default_body),
+        QuoteCartUsKsResult::PermissionDenied => (StatusCode::FORBIDDEN, default_body),
+        QuoteCartUsKsResult::ServerError(es) => {
+            app_log_event!(logctx, AppLogLevel::ERROR, "{:?}", es);
+            (StatusCode::INTERNAL_SERVER_ERROR, default_body)
+        }
+    };
+    (status, hdr_map, resp_body)
+} // end of fn quote
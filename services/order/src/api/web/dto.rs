@@ -1,13 +1,15 @@
+use chrono::{DateTime, FixedOffset};
 use serde::{Deserialize, Serialize};
 
 use ecommerce_common::api::dto::{
-    BillingDto, CurrencyDto, GenericRangeErrorDto, OrderCurrencySnapshotDto, OrderLinePayDto,
+    BillingDto, CountryCode, CurrencyDto, GenericRangeErrorDto, OrderCurrencySnapshotDto,
+    OrderLinePayDto, OrderShippingPayDto, PayAmountDto,
 };
 use ecommerce_common::api::web::dto::{
     BillingErrorDto, ContactErrorDto, PhyAddrErrorDto, QuotaResourceErrorDto,
 };
 
-use crate::api::dto::{ProdAttrValueDto, ShippingDto};
+use crate::api::dto::{ProdAttrValueDto, ShippingDto, ShippingMethod};
 
 #[derive(Deserialize, Serialize, Debug)]
 pub struct OlineProductAttrDto {
@@ -23,6 +25,12 @@ pub struct OrderLineRsvReqDto {
     pub applied_attr: Option<Vec<OlineProductAttrDto>>,
 }
 #[derive(Deserialize, Serialize)]
+pub struct OrderLineCancelReqDto {
+    pub seller_id: u32,
+    pub product_id: u64,
+    pub attr_set_seq: u16,
+}
+#[derive(Deserialize, Serialize)]
 pub struct OrderLineReturnReqDto {
     pub seller_id: u32,
     pub product_id: u64,
@@ -39,6 +47,23 @@ pub struct CartDto {
     pub lines: Vec<CartLineDto>,
 }
 
+#[derive(Deserialize)]
+pub struct CartOwnerReqQuery {
+    // required only when accessing cart shared by other user
+    pub owner: Option<u32>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct CartCollaboratorReqDto {
+    pub usr_ids: Vec<u32>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct CartCollaboratorDto {
+    pub sharable: bool,
+    pub usr_ids: Vec<u32>,
+}
+
 #[derive(Deserialize, Serialize)]
 pub enum OrderLineCreateErrorReason {
     NotExist,
@@ -46,6 +71,7 @@ pub enum OrderLineCreateErrorReason {
     NotEnoughToClaim,
     DuplicateLines,
     RsvLimitViolation,
+    PromotionExhausted,
 }
 
 #[derive(Serialize)]
@@ -54,6 +80,15 @@ pub enum OrderLineReturnErrorReason {
     WarrantyExpired,
     QtyLimitExceed,
     DuplicateReturn,
+    NotDelivered,
+}
+
+#[derive(Serialize)]
+pub enum OrderLineCancelErrorReason {
+    NotExist,
+    AlreadyPaid,
+    AlreadyCancelled,
+    ReservationExpired,
 }
 
 #[derive(Deserialize, Serialize)]
@@ -82,6 +117,14 @@ pub struct OrderLineReturnErrorDto {
     pub reason: OrderLineReturnErrorReason,
 }
 
+#[derive(Serialize)]
+pub struct OrderLineCancelErrorDto {
+    pub seller_id: u32,
+    pub product_id: u64,
+    pub attr_set_seq: u16,
+    pub reason: OrderLineCancelErrorReason,
+}
+
 #[derive(Deserialize, Serialize)]
 pub struct ShippingOptionErrorDto {
     pub seller_id: Option<ShipOptionSellerErrorReason>,
@@ -118,6 +161,10 @@ pub struct OrderCreateReqData {
     // exchange rate
     pub billing: BillingReqDto,
     pub shipping: ShippingReqDto,
+    // promotion codes chosen by the buyer, at most one promotion is applied
+    // to each order line
+    #[serde(default)]
+    pub coupons: Vec<String>,
 }
 
 #[derive(Deserialize, Serialize)]
@@ -127,6 +174,20 @@ pub struct OrderCreateRespOkDto {
     pub time: u64, // TODO, to RFC3339 formatted string
     pub currency: OrderCurrencySnapshotDto,
     pub reserved_lines: Vec<OrderLinePayDto>,
+    // lines which cannot be fully allocated from current stock, payment of
+    // these lines is held until the remaining items are allocated
+    pub backorders: Vec<OrderLineBackorderDto>,
+    // shipping fee of each seller, charged in addition to the reserved lines
+    pub shipping: Vec<OrderShippingPayDto>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct OrderLineBackorderDto {
+    pub seller_id: u32,
+    pub product_id: u64,
+    pub attr_set_seq: u16,
+    // number of items waiting for new stock
+    pub quantity: u32,
 }
 
 #[derive(Deserialize, Serialize, Default)]
@@ -135,6 +196,64 @@ pub struct OrderCreateRespErrorDto {
     pub billing: Option<BillingErrorDto>,
     pub shipping: Option<ShippingErrorDto>,
     pub quota_olines: Option<QuotaResourceErrorDto>,
+    pub coupons: Option<Vec<CouponErrorDto>>,
+}
+
+#[derive(Deserialize, Serialize, Debug, PartialEq)]
+pub enum CouponErrorReason {
+    NotExist,
+    NotInPeriod,
+    UsageExhausted,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct CouponErrorDto {
+    pub code: String,
+    pub reason: CouponErrorReason,
+}
+
+// order lines are loaded from the cart, not from client request
+#[derive(Deserialize, Serialize)]
+pub struct CartCheckoutReqData {
+    pub currency: CurrencyDto,
+    pub billing: BillingReqDto,
+    pub shipping: ShippingReqDto,
+    #[serde(default)]
+    pub coupons: Vec<String>,
+}
+
+#[derive(Deserialize)]
+pub struct CartQuoteReqQuery {
+    pub owner: Option<u32>,
+    pub currency: CurrencyDto, // currency in buyer's local region
+}
+
+// all amounts are converted to buyer's currency
+#[derive(Deserialize, Serialize)]
+pub struct CartLinePriceQuoteDto {
+    pub base: String,
+    pub attr_surcharge: String,
+    pub amount: PayAmountDto,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct CartLineQuoteDto {
+    pub seller_id: u32,
+    pub product_id: u64,
+    pub quantity: u32,
+    pub applied_attr: Option<Vec<OlineProductAttrDto>>,
+    pub qty_avail: u32,
+    // empty if the seller does not price the product with the chosen attributes
+    pub price: Option<CartLinePriceQuoteDto>,
+}
+
+// the quote is a preview, neither the rate nor the stock is reserved
+#[derive(Deserialize, Serialize)]
+pub struct CartQuoteDto {
+    pub title: String,
+    pub currency: OrderCurrencySnapshotDto,
+    pub lines: Vec<CartLineQuoteDto>,
+    pub total: String,
 }
 
 #[derive(Deserialize, Serialize)]
@@ -143,13 +262,178 @@ pub struct OrderEditReqData {
     pub shipping: ShippingReqDto,
 }
 
+#[derive(Deserialize)]
+pub struct OrderListReqQuery {
+    pub start: Option<DateTime<FixedOffset>>,
+    pub end: Option<DateTime<FixedOffset>>,
+    pub page: Option<u16>, // starts from 1
+    pub size: Option<u16>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct OrderSummaryDto {
+    pub order_id: String,
+    pub time: String, // date-time formatted in RFC3339 spec
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct OrderListRespDto {
+    pub total: usize,
+    pub page: u16,
+    pub size: u16,
+    pub orders: Vec<OrderSummaryDto>,
+}
+
+#[derive(Deserialize)]
+pub struct OrderInfoReadReqFlag {
+    pub lines: Option<bool>,
+    pub billing: Option<bool>,
+    pub shipping: Option<bool>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct OrderLineQtyReadDto {
+    pub reserved: u32,
+    pub paid: u32,
+    pub paid_last_update: Option<String>,
+    pub backordered: u32,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct OrderLineCancelReadDto {
+    pub usr_id: u32,
+    pub time: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct OrderLineReadDto {
+    pub seller_id: u32,
+    pub product_id: u64,
+    pub attr_set_seq: u16,
+    pub reserved_until: String,
+    pub warranty_until: String,
+    pub qty: OrderLineQtyReadDto,
+    pub amount: PayAmountDto, // in buyer's currency
+    pub cancelled: Option<OrderLineCancelReadDto>,
+    pub warehouse_id: Option<u32>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct OrderInfoReadDto {
+    pub order_id: String,
+    pub usr_id: u32,
+    pub time: String,
+    pub currency: OrderCurrencySnapshotDto,
+    pub lines: Option<Vec<OrderLineReadDto>>,
+    pub billing: Option<BillingDto>,
+    pub shipping: Option<ShippingDto>,
+}
+
 #[derive(Deserialize)]
 pub struct ProductPolicyDto {
-    pub product_id: u64, // TODO, new field `seller_id` u32
+    pub seller_id: u32,
+    pub product_id: u64,
     pub auto_cancel_secs: u32,
     pub warranty_hours: u32,
     pub max_num_rsv: Option<u16>,
     pub min_num_rsv: Option<u16>,
+    pub backorder: Option<ProductBackorderPolicyDto>,
+    pub max_num_backorder: Option<u16>,
+    // weight of single item in grams, for estimating shipping fee
+    pub weight_gram: Option<u32>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq)]
+pub enum ProductBackorderPolicyDto {
+    Disabled,
+    Backorder,
+    PreOrder,
+}
+
+#[derive(Deserialize)]
+pub struct ProductPolicyDeleteReqDto {
+    pub seller_id: u32,
+    pub product_id: u64,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub enum PromotionRuleDto {
+    // percentage off the unit price, range from 1 to 100
+    PercentOff(u8),
+    // fixed amount off the unit price, in the seller's currency
+    FixedOff(u32),
+    // for every `buy` items purchased, another `free` items are given at no cost
+    BuyXGetY { buy: u16, free: u16 },
+}
+
+#[derive(Deserialize)]
+pub struct PromotionDto {
+    pub code: String,
+    pub seller_id: u32,
+    // empty list means the promotion applies to all products in the store
+    pub product_ids: Vec<u64>,
+    pub rule: PromotionRuleDto,
+    pub start_after: DateTime<FixedOffset>,
+    pub end_before: DateTime<FixedOffset>,
+    // number of orders which can redeem the promotion, `None` means unlimited
+    pub max_usage: Option<u32>,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub enum PromotionErrorReason {
+    EmptyInput,
+    InvalidCode,
+    InvalidRule,
+    InvalidPeriod,
+    DuplicateCode,
+    CodeOccupied,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct PromotionClientErrorDto {
+    pub code: String,
+    pub reason: PromotionErrorReason,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct ShippingRateDto {
+    pub country: CountryCode,
+    // the rate is applied to whole country if region is not specified
+    pub region: Option<String>,
+    // the rate is applied to the parcels whose total weight in grams does
+    // not exceed this value
+    pub max_weight_gram: u32,
+    // in the seller's currency, same as the product price
+    pub fee: u32,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct ShippingMethodDto {
+    pub seller_id: u32,
+    pub method: ShippingMethod,
+    // the rate table replaces the existing one of the same method, empty
+    // table means the seller no longer supports the method
+    pub rates: Vec<ShippingRateDto>,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub enum ShippingMethodErrorReason {
+    EmptyInput,
+    InvalidSeller,
+    InvalidMethod,
+    DuplicateMethod,
+    InvalidCountry,
+    InvalidRegion,
+    InvalidWeight,
+    DuplicateRate,
+    TooManyRates,
+}
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct ShippingMethodClientErrorDto {
+    pub seller_id: u32,
+    pub method: ShippingMethod,
+    pub reason: ShippingMethodErrorReason,
 }
 
 #[derive(Serialize, PartialEq, Debug)]
@@ -165,9 +449,126 @@ pub struct ProductPolicyNumRsvLimitDto {
 
 #[derive(Serialize, PartialEq, Debug)]
 pub struct ProductPolicyClientErrorDto {
+    pub seller_id: u32,
     pub product_id: u64,
     pub err_type: String, // convert from AppError
     pub auto_cancel_secs: Option<ProductPolicyClientLimitDto>,
     pub warranty_hours: Option<ProductPolicyClientLimitDto>,
     pub num_rsv: Option<ProductPolicyNumRsvLimitDto>,
 }
+
+#[derive(Deserialize)]
+pub struct StockLevelReadReqQuery {
+    // comma-separated product IDs, omit it to list all products in the store
+    pub product_ids: Option<String>,
+    pub expiry_after: Option<DateTime<FixedOffset>>,
+    pub expiry_before: Option<DateTime<FixedOffset>>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct StockBatchReadDto {
+    pub warehouse_id: u32,
+    pub expiry: DateTime<FixedOffset>,
+    pub total: u32,
+    pub booked: u32,
+    pub cancelled: u32,
+    pub remaining: u32,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct ProductStockReadDto {
+    pub product_id: u64,
+    pub total: u32,
+    pub booked: u32,
+    pub cancelled: u32,
+    pub remaining: u32,
+    // sorted by warehouse and expiry time, the earliest comes first
+    pub batches: Vec<StockBatchReadDto>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct StockLevelReadRespDto {
+    pub store_id: u32,
+    pub products: Vec<ProductStockReadDto>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, Debug)]
+pub enum ShipmentStatusDto {
+    Packed,
+    Shipped,
+    Delivered,
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+pub struct ShipmentLineReqDto {
+    pub product_id: u64,
+    pub attr_set_seq: u16,
+    pub quantity: u32,
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+pub struct ShipmentCreateReqDto {
+    pub seller_id: u32,
+    pub carrier: ShippingMethod,
+    // the tracking number may be unknown until the parcel is handed over to
+    // the carrier
+    pub tracking_number: Option<String>,
+    pub lines: Vec<ShipmentLineReqDto>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct ShipmentUpdateReqDto {
+    pub seller_id: u32,
+    pub status: ShipmentStatusDto,
+    pub tracking_number: Option<String>,
+}
+
+#[derive(Serialize, Debug)]
+pub enum ShipmentLineErrorReason {
+    NotExist,
+    InvalidQuantity,
+    QtyLimitExceed,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ShipmentLineErrorDto {
+    pub product_id: u64,
+    pub attr_set_seq: u16,
+    pub reason: ShipmentLineErrorReason,
+}
+
+#[derive(Serialize, Debug)]
+pub enum ShipmentErrorReason {
+    EmptyLines,
+    InvalidLines,
+    InvalidTrackingNumber,
+    MissingTrackingNumber,
+    InvalidTransition,
+    ExceedingMaxLimit,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ShipmentErrorDto {
+    pub reason: ShipmentErrorReason,
+    pub lines: Option<Vec<ShipmentLineErrorDto>>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct ShipmentLineReadDto {
+    pub product_id: u64,
+    pub attr_set_seq: u16,
+    pub quantity: u32,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct ShipmentReadDto {
+    pub seq: u16,
+    pub seller_id: u32,
+    pub carrier: ShippingMethod,
+    pub tracking_number: Option<String>,
+    pub status: ShipmentStatusDto,
+    pub packed_time: DateTime<FixedOffset>,
+    pub shipped_time: Option<DateTime<FixedOffset>>,
+    pub delivered_time: Option<DateTime<FixedOffset>>,
+    pub lines: Vec<ShipmentLineReadDto>,
+}
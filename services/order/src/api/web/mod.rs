@@ -1,6 +1,6 @@
 use std::collections::HashMap;
 
-use axum::routing::{delete, get, patch, post, MethodRouter};
+use axum::routing::{delete, get, patch, post, put, MethodRouter};
 
 use crate::constant::api::web as WebConst;
 use crate::{AppSharedState, WebApiHdlrLabel};
@@ -9,6 +9,10 @@ mod cart;
 pub mod dto;
 mod order;
 mod product_policy;
+mod promotion;
+mod shipment;
+mod shipping_method;
+mod stock_level;
 
 // type parameter `B` for http body of the method router has to match the same
 // type parameter in `axum::Router`
@@ -21,17 +25,53 @@ pub fn route_table() -> ApiRouteTableType {
         WebConst::ADD_PRODUCT_POLICY,
         post(product_policy::post_handler),
     );
+    out.insert(
+        WebConst::DELETE_PRODUCT_POLICY,
+        delete(product_policy::delete_handler),
+    );
+    out.insert(WebConst::EDIT_PROMOTION, post(promotion::post_handler));
     out.insert(WebConst::CREATE_NEW_ORDER, post(order::create_handler));
     out.insert(
         WebConst::ACCESS_EXISTING_ORDER,
         patch(order::edit_billing_shipping_handler),
     );
+    out.insert(
+        WebConst::READ_EXISTING_ORDER,
+        get(order::read_existing_handler),
+    );
+    out.insert(WebConst::LIST_OWNED_ORDERS, get(order::list_owned_handler));
     out.insert(
         WebConst::RETURN_OLINES_REQ,
         patch(order::return_lines_request_handler),
     );
+    out.insert(
+        WebConst::CANCEL_OLINES_REQ,
+        patch(order::cancel_lines_request_handler),
+    );
     out.insert(WebConst::MODIFY_CART_LINES, patch(cart::modify_lines));
     out.insert(WebConst::DISCARD_CART, delete(cart::discard));
     out.insert(WebConst::RETRIEVE_CART_LINES, get(cart::retrieve));
+    out.insert(
+        WebConst::INVITE_CART_COLLABORATORS,
+        post(cart::invite_collaborators),
+    );
+    out.insert(
+        WebConst::REVOKE_CART_COLLABORATORS,
+        delete(cart::revoke_collaborators),
+    );
+    out.insert(WebConst::CHECKOUT_CART, post(cart::checkout));
+    out.insert(WebConst::QUOTE_CART, get(cart::quote));
+    out.insert(WebConst::READ_STOCK_LEVEL, get(stock_level::read_handler));
+    out.insert(WebConst::CREATE_SHIPMENT, post(shipment::create_handler));
+    out.insert(WebConst::UPDATE_SHIPMENT, patch(shipment::update_handler));
+    out.insert(WebConst::READ_SHIPMENTS, get(shipment::read_handler));
+    out.insert(
+        WebConst::EDIT_SHIPPING_METHOD,
+        put(shipping_method::put_handler),
+    );
+    out.insert(
+        WebConst::READ_SHIPPING_METHOD,
+        get(shipping_method::read_handler),
+    );
     out
 }
@@ -1,3 +1,5 @@
+use std::sync::Arc;
+
 use axum::debug_handler;
 use axum::extract::{
     Json as ExtractJson, Path as ExtractPath, Query as ExtractQuery, State as ExtractState,
@@ -10,16 +12,22 @@ use axum::response::IntoResponse;
 use serde::Deserialize;
 use serde_json;
 
-use ecommerce_common::logging::{app_log_event, AppLogLevel};
+use ecommerce_common::logging::{app_log_event, AppLogContext, AppLogLevel};
 
-use crate::api::web::dto::{OrderCreateReqData, OrderEditReqData, OrderLineReturnReqDto};
+use crate::api::web::dto::{
+    OrderCreateReqData, OrderEditReqData, OrderInfoReadReqFlag, OrderLineCancelReqDto,
+    OrderLineReturnReqDto, OrderListReqQuery,
+};
 use crate::constant as AppConst;
 use crate::repository::{
     app_repo_currency, app_repo_order, app_repo_order_return, app_repo_product_policy,
-    app_repo_product_price,
+    app_repo_product_price, app_repo_promotion, app_repo_shipment, app_repo_shipping_method,
+    app_repo_tax_rate,
 };
 use crate::usecase::{
-    CreateOrderUsKsErr, CreateOrderUseCase, ReturnLinesReqUcOutput, ReturnLinesReqUseCase,
+    CancelLinesReqUcOutput, CancelLinesReqUseCase, CreateOrderUsKsErr, CreateOrderUseCase,
+    ListOwnedOrdersUcOutput, ListOwnedOrdersUseCase, ReadOrderInfoUcOutput, ReadOrderInfoUseCase,
+    ReturnLinesReqUcOutput, ReturnLinesReqUseCase,
 };
 use crate::{AppAuthedClaim, AppSharedState};
 
@@ -38,84 +46,117 @@ pub(super) async fn create_handler(
         app_repo_currency(ds.clone()).await,
         app_repo_order(ds.clone()).await,
         app_repo_product_price(ds.clone()).await,
-        app_repo_product_policy(ds).await,
+        app_repo_product_policy(ds.clone()).await,
+        app_repo_promotion(ds.clone()).await,
+        app_repo_tax_rate(ds.clone()).await,
+        app_repo_shipping_method(ds).await,
     );
-    let (resp_status_code, serial_resp_body) =
-        if let (Ok(repo_currex), Ok(repo_o), Ok(repo_price), Ok(repo_policy)) = results {
-            let uc = CreateOrderUseCase {
-                glb_state: _appstate,
-                repo_price,
-                repo_policy,
-                repo_currex,
-                repo_order: repo_o,
-                auth_claim: authed,
-            };
-            match uc.execute(req_body).await {
-                Ok(value) => match serde_json::to_string(&value) {
-                    Ok(s) => (HttpStatusCode::CREATED, s),
-                    Err(_) => (
-                        HttpStatusCode::INTERNAL_SERVER_ERROR,
-                        r#"{"reason":"serialization-faulire"}"#.to_string(),
-                    ),
-                },
-                Err(errwrap) => match errwrap {
-                    CreateOrderUsKsErr::ReqContent(value) => match serde_json::to_string(&value) {
-                        Ok(s) => (HttpStatusCode::BAD_REQUEST, s),
-                        Err(_) => (
-                            HttpStatusCode::INTERNAL_SERVER_ERROR,
-                            "{\"reason\":\"serialization-faulire\"}".to_string(),
-                        ),
-                    },
-                    CreateOrderUsKsErr::Quota(value) => match serde_json::to_string(&value) {
-                        Ok(s) => (HttpStatusCode::FORBIDDEN, s),
-                        Err(_) => (
-                            HttpStatusCode::INTERNAL_SERVER_ERROR,
-                            "{\"reason\":\"serialization-faulire\"}".to_string(),
-                        ),
-                    },
-                    CreateOrderUsKsErr::Server(errors) => {
-                        let msg = errors
-                            .into_iter()
-                            .map(|e| format!("{:?}", e))
-                            .collect::<Vec<_>>()
-                            .join(", ");
-                        app_log_event!(log_ctx, AppLogLevel::ERROR, "{msg}");
-                        (
-                            HttpStatusCode::INTERNAL_SERVER_ERROR,
-                            r#"{"reason":"internal-error"}"#.to_string(),
-                        )
-                    }
-                },
-            }
-        } else {
-            let mut errmsgs = Vec::new();
-            if let Err(e) = results.0 {
-                errmsgs.push(e.to_string());
-            } // TODO, improve error message format
-            if let Err(e) = results.1 {
-                errmsgs.push(e.to_string());
-            }
-            if let Err(e) = results.2 {
-                errmsgs.push(e.to_string());
-            }
-            app_log_event!(
-                log_ctx,
-                AppLogLevel::ERROR,
-                "repository init failure, user:{}, reason: {:?} ",
-                usr_id,
-                errmsgs
-            );
-            (
-                HttpStatusCode::INTERNAL_SERVER_ERROR,
-                r#"{"reason":"internal-error"}"#.to_string(),
-            )
+    let (resp_status_code, serial_resp_body) = if let (
+        Ok(repo_currex),
+        Ok(repo_o),
+        Ok(repo_price),
+        Ok(repo_policy),
+        Ok(repo_promo),
+        Ok(repo_tax),
+        Ok(repo_ship),
+    ) = results
+    {
+        let uc = CreateOrderUseCase {
+            glb_state: _appstate,
+            repo_price,
+            repo_policy,
+            repo_promo,
+            repo_tax,
+            repo_ship,
+            repo_currex,
+            repo_order: repo_o,
+            auth_claim: authed,
         };
+        match uc.execute(req_body).await {
+            Ok(value) => match serde_json::to_string(&value) {
+                Ok(s) => (HttpStatusCode::CREATED, s),
+                Err(_) => (
+                    HttpStatusCode::INTERNAL_SERVER_ERROR,
+                    r#"{"reason":"serialization-faulire"}"#.to_string(),
+                ),
+            },
+            Err(errwrap) => create_order_error_response(errwrap, log_ctx),
+        }
+    } else {
+        let mut errmsgs = Vec::new();
+        if let Err(e) = results.0 {
+            errmsgs.push(e.to_string());
+        } // TODO, improve error message format
+        if let Err(e) = results.1 {
+            errmsgs.push(e.to_string());
+        }
+        if let Err(e) = results.2 {
+            errmsgs.push(e.to_string());
+        }
+        if let Err(e) = results.3 {
+            errmsgs.push(e.to_string());
+        }
+        if let Err(e) = results.4 {
+            errmsgs.push(e.to_string());
+        }
+        if let Err(e) = results.5 {
+            errmsgs.push(e.to_string());
+        }
+        if let Err(e) = results.6 {
+            errmsgs.push(e.to_string());
+        }
+        app_log_event!(
+            log_ctx,
+            AppLogLevel::ERROR,
+            "repository init failure, user:{}, reason: {:?} ",
+            usr_id,
+            errmsgs
+        );
+        (
+            HttpStatusCode::INTERNAL_SERVER_ERROR,
+            r#"{"reason":"internal-error"}"#.to_string(),
+        )
+    };
     let resp_ctype_val = HttpHeaderValue::from_str(AppConst::HTTP_CONTENT_TYPE_JSON).unwrap();
     let mut hdr_map = HttpHeaderMap::new();
     hdr_map.insert(HttpHeader::CONTENT_TYPE, resp_ctype_val);
     (resp_status_code, hdr_map, serial_resp_body)
 } // end of create_handler
 
+pub(super) fn create_order_error_response(
+    errwrap: CreateOrderUsKsErr,
+    log_ctx: Arc<AppLogContext>,
+) -> (HttpStatusCode, String) {
+    match errwrap {
+        CreateOrderUsKsErr::ReqContent(value) => match serde_json::to_string(&value) {
+            Ok(s) => (HttpStatusCode::BAD_REQUEST, s),
+            Err(_) => (
+                HttpStatusCode::INTERNAL_SERVER_ERROR,
+                "{\"reason\":\"serialization-faulire\"}".to_string(),
+            ),
+        },
+        CreateOrderUsKsErr::Quota(value) => match serde_json::to_string(&value) {
+            Ok(s) => (HttpStatusCode::FORBIDDEN, s),
+            Err(_) => (
+                HttpStatusCode::INTERNAL_SERVER_ERROR,
+                "{\"reason\":\"serialization-faulire\"}".to_string(),
+            ),
+        },
+        CreateOrderUsKsErr::Server(errors) => {
+            let msg = errors
+                .into_iter()
+                .map(|e| format!("{:?}", e))
+                .collect::<Vec<_>>()
+                .join(", ");
+            app_log_event!(log_ctx, AppLogLevel::ERROR, "{msg}");
+            (
+                HttpStatusCode::INTERNAL_SERVER_ERROR,
+                r#"{"reason":"internal-error"}"#.to_string(),
+            )
+        }
+    }
+} // end of fn create_order_error_response
+
 #[debug_handler(state=AppSharedState)]
 pub(super) async fn return_lines_request_handler(
     ExtractPath(oid): ExtractPath<String>,
@@ -128,15 +169,15 @@ pub(super) async fn return_lines_request_handler(
     let ds = _app_state.datastore();
     let results = (
         app_repo_order(ds.clone()).await,
-        app_repo_order_return(ds).await,
+        app_repo_order_return(ds.clone()).await,
+        app_repo_shipment(ds).await,
     );
-    let (status_code, resp_body) = if results.0.is_ok() && results.1.is_ok() {
-        let o_repo = results.0.unwrap();
-        let or_repo = results.1.unwrap();
+    let (status_code, resp_body) = if let (Ok(o_repo), Ok(or_repo), Ok(sh_repo)) = results {
         let uc = ReturnLinesReqUseCase {
             authed_claim,
             o_repo,
             or_repo,
+            sh_repo,
             logctx: logctx.clone(),
         };
         match uc.execute(oid.clone(), req_body).await {
@@ -183,6 +224,16 @@ pub(super) async fn return_lines_request_handler(
                 e
             );
         }
+        if let Err(e) = results.2.as_ref() {
+            app_log_event!(
+                logctx,
+                AppLogLevel::ERROR,
+                "failed to init shipment repo, oid:{}, user:{}, reason:{:?}",
+                oid,
+                usr_prof_id,
+                e
+            );
+        }
         (HttpStatusCode::INTERNAL_SERVER_ERROR, r#"{}"#.to_string())
     };
     let resp_ctype_val = HttpHeaderValue::from_str(AppConst::HTTP_CONTENT_TYPE_JSON).unwrap();
@@ -221,3 +272,176 @@ pub(super) async fn edit_billing_shipping_handler(
     );
     (HttpStatusCode::OK, hdr_map, serial_resp_body)
 }
+
+#[debug_handler(state=AppSharedState)]
+pub(super) async fn list_owned_handler(
+    ExtractQuery(req): ExtractQuery<OrderListReqQuery>,
+    authed_claim: AppAuthedClaim,
+    ExtractState(_appstate): ExtractState<AppSharedState>,
+) -> impl IntoResponse {
+    let resp_ctype_val = HttpHeaderValue::from_str(AppConst::HTTP_CONTENT_TYPE_JSON).unwrap();
+    let mut hdr_map = HttpHeaderMap::new();
+    hdr_map.insert(HttpHeader::CONTENT_TYPE, resp_ctype_val);
+    let logctx = _appstate.log_context().clone();
+    let usr_prof_id = authed_claim.profile;
+    let repo = match app_repo_order(_appstate.datastore()).await {
+        Ok(v) => v,
+        Err(e) => {
+            app_log_event!(
+                logctx,
+                AppLogLevel::ERROR,
+                "failed to init order repo, user:{}, reason:{:?}",
+                usr_prof_id,
+                e
+            );
+            return (
+                HttpStatusCode::INTERNAL_SERVER_ERROR,
+                hdr_map,
+                "{}".to_string(),
+            );
+        }
+    };
+    let uc = ListOwnedOrdersUseCase { authed_claim, repo };
+    let (status_code, resp_body) = match uc.execute(req).await {
+        Ok(output) => match output {
+            ListOwnedOrdersUcOutput::Success(v) => {
+                (HttpStatusCode::OK, serde_json::to_string(&v).unwrap())
+            }
+            ListOwnedOrdersUcOutput::InvalidTimeRange => (
+                HttpStatusCode::BAD_REQUEST,
+                r#"{"reason":"invalid-time-range"}"#.to_string(),
+            ),
+            ListOwnedOrdersUcOutput::InvalidPagination => (
+                HttpStatusCode::BAD_REQUEST,
+                r#"{"reason":"invalid-pagination"}"#.to_string(),
+            ),
+        },
+        Err(e) => {
+            app_log_event!(
+                logctx,
+                AppLogLevel::ERROR,
+                "internal error from use-case, user:{}, reason:{:?}",
+                usr_prof_id,
+                e
+            );
+            (HttpStatusCode::INTERNAL_SERVER_ERROR, "{}".to_string())
+        }
+    };
+    (status_code, hdr_map, resp_body)
+} // end of fn list_owned_handler
+
+#[debug_handler(state=AppSharedState)]
+pub(super) async fn read_existing_handler(
+    ExtractPath(oid): ExtractPath<String>,
+    ExtractQuery(flag): ExtractQuery<OrderInfoReadReqFlag>,
+    authed_claim: AppAuthedClaim,
+    ExtractState(_appstate): ExtractState<AppSharedState>,
+) -> impl IntoResponse {
+    let resp_ctype_val = HttpHeaderValue::from_str(AppConst::HTTP_CONTENT_TYPE_JSON).unwrap();
+    let mut hdr_map = HttpHeaderMap::new();
+    hdr_map.insert(HttpHeader::CONTENT_TYPE, resp_ctype_val);
+    let logctx = _appstate.log_context().clone();
+    let usr_prof_id = authed_claim.profile;
+    let repo = match app_repo_order(_appstate.datastore()).await {
+        Ok(v) => v,
+        Err(e) => {
+            app_log_event!(
+                logctx,
+                AppLogLevel::ERROR,
+                "failed to init order repo, oid:{}, user:{}, reason:{:?}",
+                oid,
+                usr_prof_id,
+                e
+            );
+            return (
+                HttpStatusCode::INTERNAL_SERVER_ERROR,
+                hdr_map,
+                "{}".to_string(),
+            );
+        }
+    };
+    let uc = ReadOrderInfoUseCase { authed_claim, repo };
+    let (status_code, resp_body) = match uc.execute(oid.clone(), flag).await {
+        Ok(output) => match output {
+            ReadOrderInfoUcOutput::Success(v) => {
+                (HttpStatusCode::OK, serde_json::to_string(&v).unwrap())
+            }
+            // hide existence of the order from users who do not own it
+            ReadOrderInfoUcOutput::NotFound | ReadOrderInfoUcOutput::InvalidOwner => {
+                (HttpStatusCode::NOT_FOUND, "{}".to_string())
+            }
+        },
+        Err(e) => {
+            app_log_event!(
+                logctx,
+                AppLogLevel::ERROR,
+                "internal error from use-case, oid:{}, user:{}, reason:{:?}",
+                oid.as_str(),
+                usr_prof_id,
+                e
+            );
+            (HttpStatusCode::INTERNAL_SERVER_ERROR, "{}".to_string())
+        }
+    };
+    (status_code, hdr_map, resp_body)
+} // end of fn read_existing_handler
+
+#[debug_handler(state=AppSharedState)]
+pub(super) async fn cancel_lines_request_handler(
+    ExtractPath(oid): ExtractPath<String>,
+    authed_claim: AppAuthedClaim,
+    ExtractState(_appstate): ExtractState<AppSharedState>,
+    ExtractJson(req_body): ExtractJson<Vec<OrderLineCancelReqDto>>,
+) -> impl IntoResponse {
+    let resp_ctype_val = HttpHeaderValue::from_str(AppConst::HTTP_CONTENT_TYPE_JSON).unwrap();
+    let mut hdr_map = HttpHeaderMap::new();
+    hdr_map.insert(HttpHeader::CONTENT_TYPE, resp_ctype_val);
+    let logctx = _appstate.log_context().clone();
+    let usr_prof_id = authed_claim.profile;
+    let repo = match app_repo_order(_appstate.datastore()).await {
+        Ok(v) => v,
+        Err(e) => {
+            app_log_event!(
+                logctx,
+                AppLogLevel::ERROR,
+                "failed to init order repo, oid:{}, user:{}, reason:{:?}",
+                oid,
+                usr_prof_id,
+                e
+            );
+            return (
+                HttpStatusCode::INTERNAL_SERVER_ERROR,
+                hdr_map,
+                "{}".to_string(),
+            );
+        }
+    };
+    let uc = CancelLinesReqUseCase {
+        authed_claim,
+        repo,
+        logctx: logctx.clone(),
+    };
+    let (status_code, resp_body) = match uc.execute(oid.clone(), req_body).await {
+        Ok(output) => match output {
+            CancelLinesReqUcOutput::Success => (HttpStatusCode::OK, "{}".to_string()),
+            CancelLinesReqUcOutput::NotFound => (HttpStatusCode::NOT_FOUND, "{}".to_string()),
+            CancelLinesReqUcOutput::InvalidOwner => (HttpStatusCode::FORBIDDEN, "{}".to_string()),
+            CancelLinesReqUcOutput::InvalidRequest(errors) => {
+                let serialized = serde_json::to_string(&errors).unwrap();
+                (HttpStatusCode::BAD_REQUEST, serialized)
+            }
+        },
+        Err(e) => {
+            app_log_event!(
+                logctx,
+                AppLogLevel::ERROR,
+                "internal error from use-case, oid:{}, user:{}, reason:{:?}",
+                oid.as_str(),
+                usr_prof_id,
+                e
+            );
+            (HttpStatusCode::INTERNAL_SERVER_ERROR, "{}".to_string())
+        }
+    };
+    (status_code, hdr_map, resp_body)
+} // end of fn cancel_lines_request_handler
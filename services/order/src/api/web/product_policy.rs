@@ -10,12 +10,15 @@ use axum::http::{
 use axum::response::IntoResponse;
 
 use ecommerce_common::error::AppErrorCode;
+use ecommerce_common::logging::{app_log_event, AppLogLevel};
 
 use crate::api::rpc::PyCelery;
-use crate::api::web::dto::ProductPolicyDto;
+use crate::api::web::dto::{ProductPolicyDeleteReqDto, ProductPolicyDto};
 use crate::error::AppError;
+use crate::repository::app_repo_product_policy;
 use crate::usecase::{
-    EditProductPolicyResult, EditProductPolicyUseCase, ProductInfoReq, ProductInfoResp,
+    DeleteProductPolicyUseCase, EditProductPolicyResult, EditProductPolicyUseCase, ProductInfoReq,
+    ProductInfoResp,
 };
 use crate::{constant as AppConst, AppAuthedClaim, AppSharedState};
 
@@ -78,6 +81,31 @@ pub(super) async fn post_handler(
     presenter(result)
 } // end of endpoint
 
+#[debug_handler(state = AppSharedState)]
+pub(super) async fn delete_handler(
+    authed_usr: AppAuthedClaim,
+    ExtractState(appstate): ExtractState<AppSharedState>,
+    ExtractJson(req_body): ExtractJson<Vec<ProductPolicyDeleteReqDto>>,
+) -> impl IntoResponse {
+    let log = appstate.log_context().clone();
+    let result = match app_repo_product_policy(appstate.datastore()).await {
+        Ok(repo) => {
+            let uc = DeleteProductPolicyUseCase {
+                log,
+                repo,
+                rpc_ctx: appstate.rpc(),
+                authed_usr,
+            };
+            uc.execute(req_body).await
+        }
+        Err(e) => {
+            app_log_event!(log, AppLogLevel::ERROR, "repo-init-error:{:?}", e);
+            EditProductPolicyResult::Other(e.code)
+        }
+    };
+    presenter(result)
+} // end of endpoint
+
 fn _rpc_deserialize_dummy(_raw: &Vec<u8>) -> DefaultResult<ProductInfoResp, AppError> {
     Err(AppError {
         code: AppErrorCode::RpcRemoteInvalidReply,
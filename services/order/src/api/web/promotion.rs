@@ -0,0 +1,54 @@
+use axum::debug_handler;
+use axum::extract::{Json as ExtractJson, State as ExtractState};
+use axum::http::{
+    header as HttpHeader, HeaderMap as HttpHeaderMap, HeaderValue as HttpHeaderValue,
+    StatusCode as HttpStatusCode,
+};
+use axum::response::IntoResponse;
+
+use ecommerce_common::logging::{app_log_event, AppLogLevel};
+
+use crate::api::web::dto::PromotionDto;
+use crate::repository::app_repo_promotion;
+use crate::usecase::{EditPromotionUsKsResult, EditPromotionUseCase};
+use crate::{constant as AppConst, AppAuthedClaim, AppSharedState};
+
+#[debug_handler(state = AppSharedState)]
+pub(super) async fn post_handler(
+    authed_usr: AppAuthedClaim,
+    ExtractState(appstate): ExtractState<AppSharedState>,
+    ExtractJson(req_body): ExtractJson<Vec<PromotionDto>>,
+) -> impl IntoResponse {
+    let log = appstate.log_context().clone();
+    let result = match app_repo_promotion(appstate.datastore()).await {
+        Ok(repo) => {
+            let uc = EditPromotionUseCase {
+                log,
+                repo,
+                rpc_ctx: appstate.rpc(),
+                authed_usr,
+            };
+            uc.execute(req_body).await
+        }
+        Err(e) => {
+            app_log_event!(log, AppLogLevel::ERROR, "repo-init-error:{:?}", e);
+            EditPromotionUsKsResult::Other(e.code)
+        }
+    };
+    let resp_ctype_val = HttpHeaderValue::from_str(AppConst::HTTP_CONTENT_TYPE_JSON).unwrap();
+    let mut hdr_map = HttpHeaderMap::new();
+    hdr_map.insert(HttpHeader::CONTENT_TYPE, resp_ctype_val);
+    let default_body = "{}".to_string();
+    let (status, serial_resp_body) = match result {
+        EditPromotionUsKsResult::OK => (HttpStatusCode::OK, default_body),
+        EditPromotionUsKsResult::PermissionDeny => (HttpStatusCode::FORBIDDEN, default_body),
+        EditPromotionUsKsResult::ClientError(ce) => (
+            HttpStatusCode::BAD_REQUEST,
+            serde_json::to_string(&ce).unwrap(),
+        ),
+        EditPromotionUsKsResult::Other(_ec) => {
+            (HttpStatusCode::INTERNAL_SERVER_ERROR, default_body)
+        }
+    };
+    (status, hdr_map, serial_resp_body)
+} // end of endpoint
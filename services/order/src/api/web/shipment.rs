@@ -0,0 +1,195 @@
+use std::boxed::Box;
+use std::result::Result as DefaultResult;
+
+use axum::debug_handler;
+use axum::extract::{Json as ExtractJson, Path as ExtractPath, State as ExtractState};
+use axum::http::{
+    header as HttpHeader, HeaderMap as HttpHeaderMap, HeaderValue as HttpHeaderValue,
+    StatusCode as HttpStatusCode,
+};
+use axum::response::IntoResponse;
+
+use ecommerce_common::logging::{app_log_event, AppLogLevel};
+
+use crate::api::web::dto::{ShipmentCreateReqDto, ShipmentUpdateReqDto};
+use crate::error::AppError;
+use crate::repository::{app_repo_order, app_repo_shipment, AbsOrderRepo, AbsShipmentRepo};
+use crate::usecase::{
+    CreateShipmentUseCase, ReadShipmentsUcOutput, ReadShipmentsUseCase, ShipmentUcOutput,
+    UpdateShipmentUseCase,
+};
+use crate::{constant as AppConst, AppAuthedClaim, AppSharedState};
+
+type ShipmentRepoPair = (Box<dyn AbsOrderRepo>, Box<dyn AbsShipmentRepo>);
+
+fn json_header() -> HttpHeaderMap {
+    let resp_ctype_val = HttpHeaderValue::from_str(AppConst::HTTP_CONTENT_TYPE_JSON).unwrap();
+    let mut hdr_map = HttpHeaderMap::new();
+    hdr_map.insert(HttpHeader::CONTENT_TYPE, resp_ctype_val);
+    hdr_map
+}
+
+async fn init_repos(appstate: &AppSharedState) -> DefaultResult<ShipmentRepoPair, AppError> {
+    let ds = appstate.datastore();
+    let o_repo = app_repo_order(ds.clone()).await?;
+    let sh_repo = app_repo_shipment(ds).await?;
+    Ok((o_repo, sh_repo))
+}
+
+fn uc_output_to_resp(output: ShipmentUcOutput) -> (HttpStatusCode, String) {
+    match output {
+        ShipmentUcOutput::Success(v) => (HttpStatusCode::OK, serde_json::to_string(&v).unwrap()),
+        ShipmentUcOutput::PermissionDeny => (HttpStatusCode::FORBIDDEN, "{}".to_string()),
+        ShipmentUcOutput::NotFound => (HttpStatusCode::NOT_FOUND, "{}".to_string()),
+        ShipmentUcOutput::InvalidRequest(e) => (
+            HttpStatusCode::BAD_REQUEST,
+            serde_json::to_string(&e).unwrap(),
+        ),
+    }
+}
+
+#[debug_handler(state = AppSharedState)]
+pub(super) async fn create_handler(
+    ExtractPath(oid): ExtractPath<String>,
+    authed_claim: AppAuthedClaim,
+    ExtractState(appstate): ExtractState<AppSharedState>,
+    ExtractJson(req_body): ExtractJson<ShipmentCreateReqDto>,
+) -> impl IntoResponse {
+    let logctx = appstate.log_context().clone();
+    let usr_prof_id = authed_claim.profile;
+    let (status_code, resp_body) = match init_repos(&appstate).await {
+        Ok((o_repo, sh_repo)) => {
+            let uc = CreateShipmentUseCase {
+                authed_claim,
+                o_repo,
+                sh_repo,
+                rpc_ctx: appstate.rpc(),
+                logctx: logctx.clone(),
+            };
+            match uc.execute(oid.clone(), req_body).await {
+                Ok(output) => uc_output_to_resp(output),
+                Err(e) => {
+                    app_log_event!(
+                        logctx,
+                        AppLogLevel::ERROR,
+                        "internal error from use-case, oid:{}, user:{}, reason:{:?}",
+                        oid,
+                        usr_prof_id,
+                        e
+                    );
+                    (HttpStatusCode::INTERNAL_SERVER_ERROR, "{}".to_string())
+                }
+            }
+        }
+        Err(e) => {
+            app_log_event!(
+                logctx,
+                AppLogLevel::ERROR,
+                "failed to init repo, oid:{}, user:{}, reason:{:?}",
+                oid,
+                usr_prof_id,
+                e
+            );
+            (HttpStatusCode::INTERNAL_SERVER_ERROR, "{}".to_string())
+        }
+    };
+    (status_code, json_header(), resp_body)
+} // end of fn create_handler
+
+#[debug_handler(state = AppSharedState)]
+pub(super) async fn update_handler(
+    ExtractPath((oid, seq)): ExtractPath<(String, u16)>,
+    authed_claim: AppAuthedClaim,
+    ExtractState(appstate): ExtractState<AppSharedState>,
+    ExtractJson(req_body): ExtractJson<ShipmentUpdateReqDto>,
+) -> impl IntoResponse {
+    let logctx = appstate.log_context().clone();
+    let usr_prof_id = authed_claim.profile;
+    let (status_code, resp_body) = match app_repo_shipment(appstate.datastore()).await {
+        Ok(sh_repo) => {
+            let uc = UpdateShipmentUseCase {
+                authed_claim,
+                sh_repo,
+                rpc_ctx: appstate.rpc(),
+                logctx: logctx.clone(),
+            };
+            match uc.execute(oid.clone(), seq, req_body).await {
+                Ok(output) => uc_output_to_resp(output),
+                Err(e) => {
+                    app_log_event!(
+                        logctx,
+                        AppLogLevel::ERROR,
+                        "internal error from use-case, oid:{}, seq:{}, user:{}, reason:{:?}",
+                        oid,
+                        seq,
+                        usr_prof_id,
+                        e
+                    );
+                    (HttpStatusCode::INTERNAL_SERVER_ERROR, "{}".to_string())
+                }
+            }
+        }
+        Err(e) => {
+            app_log_event!(
+                logctx,
+                AppLogLevel::ERROR,
+                "failed to init shipment repo, oid:{}, user:{}, reason:{:?}",
+                oid,
+                usr_prof_id,
+                e
+            );
+            (HttpStatusCode::INTERNAL_SERVER_ERROR, "{}".to_string())
+        }
+    };
+    (status_code, json_header(), resp_body)
+} // end of fn update_handler
+
+#[debug_handler(state = AppSharedState)]
+pub(super) async fn read_handler(
+    ExtractPath(oid): ExtractPath<String>,
+    authed_claim: AppAuthedClaim,
+    ExtractState(appstate): ExtractState<AppSharedState>,
+) -> impl IntoResponse {
+    let logctx = appstate.log_context().clone();
+    let usr_prof_id = authed_claim.profile;
+    let (status_code, resp_body) = match init_repos(&appstate).await {
+        Ok((o_repo, sh_repo)) => {
+            let uc = ReadShipmentsUseCase {
+                authed_claim,
+                o_repo,
+                sh_repo,
+            };
+            match uc.execute(oid.clone()).await {
+                Ok(ReadShipmentsUcOutput::Success(v)) => {
+                    (HttpStatusCode::OK, serde_json::to_string(&v).unwrap())
+                }
+                Ok(ReadShipmentsUcOutput::InvalidOwner) => {
+                    (HttpStatusCode::FORBIDDEN, "{}".to_string())
+                }
+                Err(e) => {
+                    app_log_event!(
+                        logctx,
+                        AppLogLevel::ERROR,
+                        "internal error from use-case, oid:{}, user:{}, reason:{:?}",
+                        oid,
+                        usr_prof_id,
+                        e
+                    );
+                    (HttpStatusCode::INTERNAL_SERVER_ERROR, "{}".to_string())
+                }
+            }
+        }
+        Err(e) => {
+            app_log_event!(
+                logctx,
+                AppLogLevel::ERROR,
+                "failed to init repo, oid:{}, user:{}, reason:{:?}",
+                oid,
+                usr_prof_id,
+                e
+            );
+            (HttpStatusCode::INTERNAL_SERVER_ERROR, "{}".to_string())
+        }
+    };
+    (status_code, json_header(), resp_body)
+} // end of fn read_handler
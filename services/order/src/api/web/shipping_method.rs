@@ -0,0 +1,93 @@
+use axum::debug_handler;
+use axum::extract::{Json as ExtractJson, Path as ExtractPath, State as ExtractState};
+use axum::http::{
+    header as HttpHeader, HeaderMap as HttpHeaderMap, HeaderValue as HttpHeaderValue,
+    StatusCode as HttpStatusCode,
+};
+use axum::response::IntoResponse;
+
+use ecommerce_common::logging::{app_log_event, AppLogLevel};
+
+use crate::api::web::dto::ShippingMethodDto;
+use crate::repository::app_repo_shipping_method;
+use crate::usecase::{
+    EditShippingMethodUsKsResult, EditShippingMethodUseCase, ReadShippingMethodUseCase,
+};
+use crate::{constant as AppConst, AppAuthedClaim, AppSharedState};
+
+fn json_header() -> HttpHeaderMap {
+    let resp_ctype_val = HttpHeaderValue::from_str(AppConst::HTTP_CONTENT_TYPE_JSON).unwrap();
+    let mut hdr_map = HttpHeaderMap::new();
+    hdr_map.insert(HttpHeader::CONTENT_TYPE, resp_ctype_val);
+    hdr_map
+}
+
+#[debug_handler(state = AppSharedState)]
+pub(super) async fn put_handler(
+    authed_usr: AppAuthedClaim,
+    ExtractState(appstate): ExtractState<AppSharedState>,
+    ExtractJson(req_body): ExtractJson<Vec<ShippingMethodDto>>,
+) -> impl IntoResponse {
+    let log = appstate.log_context().clone();
+    let result = match app_repo_shipping_method(appstate.datastore()).await {
+        Ok(repo) => {
+            let uc = EditShippingMethodUseCase {
+                log,
+                repo,
+                rpc_ctx: appstate.rpc(),
+                authed_usr,
+            };
+            uc.execute(req_body).await
+        }
+        Err(e) => {
+            app_log_event!(log, AppLogLevel::ERROR, "repo-init-error:{:?}", e);
+            EditShippingMethodUsKsResult::Other(e.code)
+        }
+    };
+    let default_body = "{}".to_string();
+    let (status, serial_resp_body) = match result {
+        EditShippingMethodUsKsResult::OK => (HttpStatusCode::OK, default_body),
+        EditShippingMethodUsKsResult::PermissionDeny => (HttpStatusCode::FORBIDDEN, default_body),
+        EditShippingMethodUsKsResult::ClientError(ce) => (
+            HttpStatusCode::BAD_REQUEST,
+            serde_json::to_string(&ce).unwrap(),
+        ),
+        EditShippingMethodUsKsResult::Other(_ec) => {
+            (HttpStatusCode::INTERNAL_SERVER_ERROR, default_body)
+        }
+    };
+    (status, json_header(), serial_resp_body)
+} // end of fn put_handler
+
+// buyers can look up the delivery services supported by a seller before
+// creating an order
+#[debug_handler(state = AppSharedState)]
+pub(super) async fn read_handler(
+    ExtractPath(store_id): ExtractPath<u32>,
+    authed_usr: AppAuthedClaim,
+    ExtractState(appstate): ExtractState<AppSharedState>,
+) -> impl IntoResponse {
+    let log = appstate.log_context().clone();
+    let result = match app_repo_shipping_method(appstate.datastore()).await {
+        Ok(repo) => {
+            let uc = ReadShippingMethodUseCase { repo };
+            uc.execute(store_id).await
+        }
+        Err(e) => Err(e),
+    };
+    let (status, serial_resp_body) = match result {
+        Ok(v) => (HttpStatusCode::OK, serde_json::to_string(&v).unwrap()),
+        Err(e) => {
+            app_log_event!(
+                log,
+                AppLogLevel::ERROR,
+                "store:{}, user:{}, reason:{:?}",
+                store_id,
+                authed_usr.profile,
+                e
+            );
+            (HttpStatusCode::INTERNAL_SERVER_ERROR, "{}".to_string())
+        }
+    };
+    (status, json_header(), serial_resp_body)
+} // end of fn read_handler
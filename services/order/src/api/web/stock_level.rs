@@ -0,0 +1,84 @@
+use axum::debug_handler;
+use axum::extract::{Path as ExtractPath, Query as ExtractQuery, State as ExtractState};
+use axum::http::{
+    header as HttpHeader, HeaderMap as HttpHeaderMap, HeaderValue as HttpHeaderValue,
+    StatusCode as HttpStatusCode,
+};
+use axum::response::IntoResponse;
+
+use ecommerce_common::logging::{app_log_event, AppLogLevel};
+
+use crate::api::web::dto::StockLevelReadReqQuery;
+use crate::repository::app_repo_order;
+use crate::usecase::{ReadStockLevelUcOutput, ReadStockLevelUseCase};
+use crate::{constant as AppConst, AppAuthedClaim, AppSharedState};
+
+#[debug_handler(state = AppSharedState)]
+pub(super) async fn read_handler(
+    ExtractPath(store_id): ExtractPath<u32>,
+    ExtractQuery(req): ExtractQuery<StockLevelReadReqQuery>,
+    authed_claim: AppAuthedClaim,
+    ExtractState(appstate): ExtractState<AppSharedState>,
+) -> impl IntoResponse {
+    let resp_ctype_val = HttpHeaderValue::from_str(AppConst::HTTP_CONTENT_TYPE_JSON).unwrap();
+    let mut hdr_map = HttpHeaderMap::new();
+    hdr_map.insert(HttpHeader::CONTENT_TYPE, resp_ctype_val);
+    let logctx = appstate.log_context().clone();
+    let usr_prof_id = authed_claim.profile;
+    let repo = match app_repo_order(appstate.datastore()).await {
+        Ok(v) => v,
+        Err(e) => {
+            app_log_event!(
+                logctx,
+                AppLogLevel::ERROR,
+                "failed to init order repo, store:{}, user:{}, reason:{:?}",
+                store_id,
+                usr_prof_id,
+                e
+            );
+            return (
+                HttpStatusCode::INTERNAL_SERVER_ERROR,
+                hdr_map,
+                "{}".to_string(),
+            );
+        }
+    };
+    let uc = ReadStockLevelUseCase {
+        authed_claim,
+        repo,
+        rpc_ctx: appstate.rpc(),
+        logctx: logctx.clone(),
+    };
+    let (status_code, resp_body) = match uc.execute(store_id, req).await {
+        Ok(output) => match output {
+            ReadStockLevelUcOutput::Success(v) => {
+                (HttpStatusCode::OK, serde_json::to_string(&v).unwrap())
+            }
+            ReadStockLevelUcOutput::PermissionDeny => (HttpStatusCode::FORBIDDEN, "{}".to_string()),
+            ReadStockLevelUcOutput::InvalidProductId => (
+                HttpStatusCode::BAD_REQUEST,
+                r#"{"reason":"invalid-product-id"}"#.to_string(),
+            ),
+            ReadStockLevelUcOutput::TooManyProducts => (
+                HttpStatusCode::BAD_REQUEST,
+                r#"{"reason":"too-many-product-ids"}"#.to_string(),
+            ),
+            ReadStockLevelUcOutput::InvalidExpiryRange => (
+                HttpStatusCode::BAD_REQUEST,
+                r#"{"reason":"invalid-expiry-range"}"#.to_string(),
+            ),
+        },
+        Err(e) => {
+            app_log_event!(
+                logctx,
+                AppLogLevel::ERROR,
+                "internal error from use-case, store:{}, user:{}, reason:{:?}",
+                store_id,
+                usr_prof_id,
+                e
+            );
+            (HttpStatusCode::INTERNAL_SERVER_ERROR, "{}".to_string())
+        }
+    };
+    (status_code, hdr_map, resp_body)
+} // end of fn read_handler
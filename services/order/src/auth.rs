@@ -86,6 +86,10 @@ pub struct AppJwtAuthentication {
 pub enum AppAuthPermissionCode {
     can_create_return_req,
     can_create_product_policy,
+    can_create_promotion,
+    can_view_stock_level,
+    can_manage_shipment,
+    can_manage_shipping_method,
 }
 
 #[derive(Clone, PartialEq)]
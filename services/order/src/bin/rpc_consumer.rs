@@ -4,7 +4,9 @@ use std::env;
 use std::future::Future;
 use std::pin::Pin;
 use std::result::Result as DefaultResult;
+use std::time::Duration;
 
+use chrono::Local;
 use ecommerce_common::confidentiality::{self, AbstractConfidentiality};
 use ecommerce_common::constant::env_vars::EXPECTED_LABELS;
 use ecommerce_common::logging::{app_log_event, AppLogContext, AppLogLevel};
@@ -14,6 +16,8 @@ use tokio::signal::unix::{signal, SignalKind};
 use order::api::rpc::route_to_handler;
 use order::constant::hard_limit;
 use order::error::AppError;
+use order::repository::{app_repo_order, app_repo_stock_alert};
+use order::usecase::StockAlertCheckUseCase;
 use order::{AppCfgHardLimit, AppCfgInitArgs, AppConfig, AppRpcClientReqProperty, AppSharedState};
 
 fn route_handler_wrapper(
@@ -38,6 +42,42 @@ async fn start_rpc_worker(shr_state: AppSharedState) {
     app_log_event!(logctx_p, AppLogLevel::DEBUG, "end-of-rpc-worker");
 }
 
+async fn check_stock_alert(shr_state: &AppSharedState) -> DefaultResult<usize, AppError> {
+    let ds = shr_state.datastore();
+    let uc = StockAlertCheckUseCase {
+        logctx: shr_state.log_context().clone(),
+        alert_repo: app_repo_stock_alert(ds.clone()).await?,
+        order_repo: app_repo_order(ds).await?,
+        rpc_ctx: shr_state.rpc(),
+    };
+    uc.execute(Local::now().fixed_offset()).await
+}
+
+async fn start_stock_alert_check(shr_state: AppSharedState) {
+    let logctx_p = shr_state.log_context().clone();
+    let period = Duration::from_secs(hard_limit::STOCK_ALERT_CHECK_INTERVAL_SECS);
+    let mut shutdown_signal = signal(SignalKind::terminate()).unwrap();
+    loop {
+        match check_stock_alert(&shr_state).await {
+            Ok(num_published) => {
+                app_log_event!(
+                    logctx_p,
+                    AppLogLevel::DEBUG,
+                    "stock-alert-published:{num_published}"
+                );
+            }
+            Err(e) => {
+                app_log_event!(logctx_p, AppLogLevel::ERROR, "stock-alert-check:{:?}", e);
+            }
+        }
+        tokio::select! {
+            _ = tokio::time::sleep(period) => { },
+            _ = shutdown_signal.recv()  => { break; },
+        }
+    } // end of loop
+    app_log_event!(logctx_p, AppLogLevel::INFO, "stock-alert-check-terminating");
+} // end of fn start_stock_alert_check
+
 fn start_async_runtime(cfg: AppConfig, cfdntl: Box<dyn AbstractConfidentiality>) {
     let log_ctx = AppLogContext::new(&cfg.basepath, &cfg.api_server.logging);
     let shr_state = AppSharedState::new(cfg, log_ctx, cfdntl);
@@ -55,6 +95,8 @@ fn start_async_runtime(cfg: AppConfig, cfdntl: Box<dyn AbstractConfidentiality>)
         Ok(rt) => {
             // new worker threads spawned
             rt.block_on(async move {
+                let task_alert = start_stock_alert_check(shr_state.clone());
+                tokio::task::spawn(task_alert);
                 start_rpc_worker(shr_state).await;
             }); // runtime started
         }
@@ -12,6 +12,16 @@ pub mod hard_limit {
     pub const MAX_SECONDS_DB_IDLE: u16 = 600u16;
     pub const MIN_SECS_INTVL_REQ: u16 = 3;
     pub const MAX_NUM_CARTS_PER_USER: u8 = 5; // TODO, configurable in user-mgt app
+    pub const MAX_NUM_CART_COLLABORATORS: usize = 10;
+    pub const CART_CHECKOUT_CLAIM_SECS: i64 = 90;
+    pub const MAX_ORDERS_PER_PAGE: u16 = 50;
+    pub const MAX_DAYS_ORDER_HISTORY_QUERY: i64 = 366;
+    pub const STOCK_ALERT_CHECK_INTERVAL_SECS: u64 = 900;
+    pub const MAX_LEN_TRACKING_NUMBER: usize = 40;
+    pub const MAX_SHIPMENTS_PER_ORDER: u16 = 256;
+    pub const MAX_NUM_SHIPPING_RATES: usize = 64;
+    pub const MAX_PRODUCTS_STOCK_LEVEL_READ: usize = 100;
+    pub const MAX_DAYS_BACKORDER_WAIT: i64 = 30;
 }
 
 pub(crate) mod api {
@@ -26,12 +36,27 @@ pub(crate) mod api {
 
     impl web {
         pub(crate) const ADD_PRODUCT_POLICY: WebApiHdlrLabel = "modify_product_policy";
+        pub(crate) const DELETE_PRODUCT_POLICY: WebApiHdlrLabel = "delete_product_policy";
+        pub(crate) const EDIT_PROMOTION: WebApiHdlrLabel = "modify_promotion";
         pub(crate) const CREATE_NEW_ORDER: WebApiHdlrLabel = "create_new_order";
         pub(crate) const ACCESS_EXISTING_ORDER: WebApiHdlrLabel = "access_existing_order";
+        pub(crate) const READ_EXISTING_ORDER: WebApiHdlrLabel = "read_existing_order";
+        pub(crate) const LIST_OWNED_ORDERS: WebApiHdlrLabel = "list_owned_orders";
         pub(crate) const RETURN_OLINES_REQ: WebApiHdlrLabel = "return_lines_request";
+        pub(crate) const CANCEL_OLINES_REQ: WebApiHdlrLabel = "cancel_lines_request";
         pub(crate) const RETRIEVE_CART_LINES: WebApiHdlrLabel = "retrieve_cart_lines";
         pub(crate) const MODIFY_CART_LINES: WebApiHdlrLabel = "modify_cart_lines";
         pub(crate) const DISCARD_CART: WebApiHdlrLabel = "discard_cart";
+        pub(crate) const INVITE_CART_COLLABORATORS: WebApiHdlrLabel = "invite_cart_collaborators";
+        pub(crate) const REVOKE_CART_COLLABORATORS: WebApiHdlrLabel = "revoke_cart_collaborators";
+        pub(crate) const CHECKOUT_CART: WebApiHdlrLabel = "checkout_cart";
+        pub(crate) const QUOTE_CART: WebApiHdlrLabel = "quote_cart";
+        pub(crate) const READ_STOCK_LEVEL: WebApiHdlrLabel = "read_stock_level";
+        pub(crate) const CREATE_SHIPMENT: WebApiHdlrLabel = "create_shipment";
+        pub(crate) const UPDATE_SHIPMENT: WebApiHdlrLabel = "update_shipment";
+        pub(crate) const READ_SHIPMENTS: WebApiHdlrLabel = "read_shipments";
+        pub(crate) const EDIT_SHIPPING_METHOD: WebApiHdlrLabel = "modify_shipping_method";
+        pub(crate) const READ_SHIPPING_METHOD: WebApiHdlrLabel = "read_shipping_method";
     }
 
     #[allow(non_camel_case_types)]
@@ -40,8 +65,13 @@ pub(crate) mod api {
     impl rpc {
         pub(crate) const EDIT_PRODUCT_PRICE: WebApiHdlrLabel = "update_store_products";
         pub(crate) const CURRENCY_RATE_REFRESH: WebApiHdlrLabel = "currency_exrate_refresh";
+        pub(crate) const CURRENCY_RATE_HISTORY: WebApiHdlrLabel = "currency_exrate_history";
+        pub(crate) const EDIT_TAX_RATE: WebApiHdlrLabel = "update_tax_rates";
         pub(crate) const STOCK_LEVEL_EDIT: WebApiHdlrLabel = "stock_level_edit";
         pub(crate) const STOCK_RETURN_CANCELLED: WebApiHdlrLabel = "stock_return_cancelled";
+        pub(crate) const STOCK_ALERT_THRESHOLD_EDIT: WebApiHdlrLabel = "stock_alert_threshold_edit";
+        pub(crate) const STOCK_ALLOC_POLICY_EDIT: WebApiHdlrLabel = "stock_alloc_policy_edit";
+        pub(crate) const STOCK_WAREHOUSE_EDIT: WebApiHdlrLabel = "stock_warehouse_edit";
         pub(crate) const ORDER_RSV_READ_INVENTORY: WebApiHdlrLabel =
             "order_reserved_replica_inventory";
         pub(crate) const ORDER_RSV_READ_PAYMENT: WebApiHdlrLabel = "order_reserved_replica_payment";
@@ -92,8 +122,13 @@ pub(crate) mod api {
             let valid_labels = [
                 Self::EDIT_PRODUCT_PRICE,
                 Self::CURRENCY_RATE_REFRESH,
+                Self::CURRENCY_RATE_HISTORY,
+                Self::EDIT_TAX_RATE,
                 Self::STOCK_LEVEL_EDIT,
                 Self::STOCK_RETURN_CANCELLED,
+                Self::STOCK_ALERT_THRESHOLD_EDIT,
+                Self::STOCK_ALLOC_POLICY_EDIT,
+                Self::STOCK_WAREHOUSE_EDIT,
                 Self::ORDER_RSV_READ_INVENTORY,
                 Self::ORDER_RSV_READ_PAYMENT,
                 Self::ORDER_RET_READ_REFUND,
@@ -1,14 +1,28 @@
+use std::result::Result as DefaultResult;
+
+use rust_decimal::Decimal;
+
+use ecommerce_common::api::dto::PayAmountDto;
+use ecommerce_common::error::AppErrorCode;
 use ecommerce_common::model::BaseProductIdentity;
 
-use crate::api::web::dto::{CartDto, CartLineDto}; // OlineProductAttrDto
+use ecommerce_common::api::web::dto::QuotaResourceErrorDto;
+
+use crate::api::web::dto::{
+    CartCollaboratorDto, CartDto, CartLineDto, CartLinePriceQuoteDto, CartLineQuoteDto,
+    CartQuoteDto, OlineProductAttrDto,
+};
+use crate::constant::hard_limit;
+use crate::error::AppError;
+
+use super::{OrderCurrencyModel, OrderLinePriceModel, ProductPriceModelSet, StockLevelModelSet};
 
 pub struct CartLineModel {
     pub id_: BaseProductIdentity,
-    // TODO, add following field which represents product variant (e.g.
-    // specific size, color, etc.) attributes, no need to resolve the
-    // chosen attributes in cart line, they might be changed later on
-    // before checkout.
-    //// chosen_attr: Option<Vec<OlineProductAttrDto>>,
+    // product variant (e.g. specific size, color, etc.) attributes chosen
+    // by the shopper, no need to resolve the chosen attributes in cart line,
+    // they might be changed later on before checkout.
+    pub chosen_attr: Option<Vec<OlineProductAttrDto>>,
     pub qty_req: u32,
     // current price and stock level are excluded
 }
@@ -19,10 +33,35 @@ pub struct CartModel {
     pub title: String,
     pub saved_lines: Vec<CartLineModel>,
     pub new_lines: Vec<CartLineModel>,
-    // TODO, add fields which indicate
-    // - extra cost amount for tax,
-    // - sharable flag, can be shared among users
-    // - list of user IDs that can edit the cart
+    // other users can view and edit the cart only when it is sharable and
+    // they are in the collaborator list, the owner is never in the list.
+    pub sharable: bool,
+    pub collaborators: Vec<u32>,
+    // quota of order lines granted to the owner, recorded when the owner manages
+    // collaborators, so edits made by collaborators are limited by the owner's
+    // quota instead of their own
+    pub owner_quota_lines: u32,
+    // TODO, add fields which indicate extra cost amount for tax
+}
+
+impl CartLineModel {
+    pub(crate) fn serialize_attr(&self) -> DefaultResult<Option<String>, AppError> {
+        self.chosen_attr
+            .as_ref()
+            .map(|a| {
+                serde_json::to_string(a).map_err(|e| AppError {
+                    code: AppErrorCode::DataCorruption,
+                    detail: Some(format!("cart-line-attr-serialize : {:?}", e)),
+                })
+            })
+            .transpose()
+    }
+    pub(crate) fn deserialize_attr(raw: &str) -> DefaultResult<Vec<OlineProductAttrDto>, AppError> {
+        serde_json::from_str::<Vec<OlineProductAttrDto>>(raw).map_err(|e| AppError {
+            code: AppErrorCode::DataCorruption,
+            detail: Some(format!("cart-line-attr-deserialize : {:?}", e)),
+        })
+    }
 }
 
 impl From<CartLineDto> for CartLineModel {
@@ -32,6 +71,7 @@ impl From<CartLineDto> for CartLineModel {
                 store_id: value.seller_id,
                 product_id: value.product_id,
             },
+            chosen_attr: value.applied_attr,
             qty_req: value.quantity,
         }
     }
@@ -43,7 +83,7 @@ impl From<CartLineModel> for CartLineDto {
             product_id: value.id_.product_id,
             seller_id: value.id_.store_id,
             quantity: value.qty_req,
-            applied_attr: None, // TODO, finish implementation
+            applied_attr: value.chosen_attr,
         }
     }
 }
@@ -61,7 +101,52 @@ impl From<CartModel> for CartDto {
     }
 }
 
+impl From<&CartModel> for CartCollaboratorDto {
+    fn from(value: &CartModel) -> CartCollaboratorDto {
+        CartCollaboratorDto {
+            sharable: value.sharable,
+            usr_ids: value.collaborators.clone(),
+        }
+    }
+}
+
 impl CartModel {
+    pub fn accessible(&self, usr_id: u32) -> bool {
+        (self.owner == usr_id) || (self.sharable && self.collaborators.contains(&usr_id))
+    }
+
+    pub fn max_num_lines(&self, usr_id: u32, usr_quota_lines: u32) -> u32 {
+        if self.owner == usr_id {
+            usr_quota_lines
+        } else {
+            self.owner_quota_lines
+        }
+    }
+
+    pub fn invite(&mut self, usr_ids: Vec<u32>) -> DefaultResult<(), QuotaResourceErrorDto> {
+        let mut newcomers = usr_ids
+            .into_iter()
+            .filter(|id_| *id_ != self.owner && !self.collaborators.contains(id_))
+            .collect::<Vec<_>>();
+        newcomers.sort();
+        newcomers.dedup();
+        let total = self.collaborators.len() + newcomers.len();
+        if total > hard_limit::MAX_NUM_CART_COLLABORATORS {
+            return Err(QuotaResourceErrorDto {
+                given: total,
+                max_: hard_limit::MAX_NUM_CART_COLLABORATORS as u32,
+            });
+        }
+        self.collaborators.extend(newcomers);
+        self.sharable = !self.collaborators.is_empty();
+        Ok(())
+    }
+
+    pub fn revoke(&mut self, usr_ids: Vec<u32>) {
+        self.collaborators.retain(|id_| !usr_ids.contains(id_));
+        self.sharable = !self.collaborators.is_empty();
+    }
+
     pub fn update(&mut self, data: CartDto) {
         let (new_title, d_lines) = (data.title, data.lines);
         self.title = new_title;
@@ -71,6 +156,7 @@ impl CartModel {
                 let result = self.get_line_mut(&d);
                 if let Some(v) = result {
                     v.qty_req = d.quantity;
+                    v.chosen_attr = d.applied_attr;
                     None
                 } else {
                     Some(d)
@@ -80,6 +166,62 @@ impl CartModel {
             .collect::<Vec<_>>();
     }
 
+    /// estimate cost of each saved line with current price, attribute surcharge
+    /// and buyer's exchange rate, also report available stock of each product.
+    /// Lines without valid price are excluded from the grand total.
+    pub fn quote(
+        self,
+        ms_price: &[ProductPriceModelSet],
+        ms_stock: &StockLevelModelSet,
+        currency: OrderCurrencyModel,
+    ) -> DefaultResult<CartQuoteDto, AppError> {
+        let mut grand_total = Decimal::ZERO;
+        let mut lines = Vec::new();
+        for line in self.saved_lines {
+            let qty_avail = ms_stock
+                .stores
+                .iter()
+                .filter(|s| s.store_id == line.id_.store_id)
+                .flat_map(|s| s.products.iter())
+                .filter(|p| p.id_ == line.id_.product_id)
+                .map(|p| p.quantity.num_avail())
+                .sum::<u32>();
+            let d = CartLineDto::from(line);
+            let result = ms_price.iter().find_map(|ms| ms.find_product(&d));
+            let price = if let Some(pricem) = result {
+                let curr_ex = currency.to_buyer_rate(d.seller_id)?;
+                let cvt = |amount: i64| curr_ex.convert_amount(Decimal::new(amount, 0u32));
+                let (lineprice, attrprice) = OrderLinePriceModel::finalize_price(&d, pricem)?;
+                let line_total = cvt(lineprice.total() as i64);
+                grand_total += line_total;
+                Some(CartLinePriceQuoteDto {
+                    base: cvt(pricem.base_price() as i64).to_string(),
+                    attr_surcharge: cvt(attrprice.total_amount()? as i64).to_string(),
+                    amount: PayAmountDto {
+                        unit: cvt(lineprice.unit() as i64).to_string(),
+                        total: line_total.to_string(),
+                    },
+                })
+            } else {
+                None
+            };
+            lines.push(CartLineQuoteDto {
+                seller_id: d.seller_id,
+                product_id: d.product_id,
+                quantity: d.quantity,
+                applied_attr: d.applied_attr,
+                qty_avail,
+                price,
+            });
+        } // end of loop
+        Ok(CartQuoteDto {
+            title: self.title,
+            currency: currency.into(),
+            lines,
+            total: grand_total.to_string(),
+        })
+    } // end of fn quote
+
     fn get_line_mut(&mut self, item: &CartLineDto) -> Option<&mut CartLineModel> {
         let result = self.saved_lines.iter_mut().find(|obj| {
             obj.id_.store_id == item.seller_id && obj.id_.product_id == item.product_id
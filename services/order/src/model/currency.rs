@@ -1,6 +1,7 @@
 use std::collections::HashMap;
 use std::result::Result;
 
+use chrono::{DateTime, FixedOffset};
 use rust_decimal::Decimal;
 
 use ecommerce_common::api::dto::{
@@ -13,6 +14,11 @@ use crate::error::AppError;
 // the 2 internal constants should be consistent with database schema
 const PRECISION_WHOLE_NUMBER: u32 = 8;
 const PRECISION_FRACTIONAL: u32 = 4;
+// a rate derived from 2 stored rates cannot be more precise than the stored
+// ones, keep the same number of significant digits instead of fixed scale,
+// so rate between currencies of very different magnitude (e.g. VND to KWD)
+// won't be truncated to zero
+const SIGNIFICANT_DIGITS_DERIVED_RATE: u32 = PRECISION_WHOLE_NUMBER + PRECISION_FRACTIONAL;
 
 #[derive(Clone)]
 pub struct CurrencyModel {
@@ -22,6 +28,25 @@ pub struct CurrencyModel {
 pub struct CurrencyModelSet {
     pub base: CurrencyDto,
     pub exchange_rates: Vec<CurrencyModel>,
+    // label of the 3rd-party exchange-rate provider which produced this set,
+    // `None` if the rates are loaded from local storage
+    pub provider: Option<String>,
+}
+
+// rate of single currency which was in force since the given time
+#[derive(Clone)]
+pub struct CurrencyRateHistoryModel {
+    pub name: CurrencyDto,
+    pub rate: Decimal,
+    pub provider: Option<String>,
+    pub time: DateTime<FixedOffset>,
+}
+
+// number of units in quote currency per unit in base currency, since the
+// given time
+pub struct CurrencyPairRateModel {
+    pub time: DateTime<FixedOffset>,
+    pub rate: Decimal,
 }
 
 pub struct OrderCurrencyModel {
@@ -41,6 +66,12 @@ impl From<&CurrencyModel> for CurrencySnapshotDto {
 }
 
 impl CurrencyModel {
+    /// convert the amount with this rate, then truncate the result to number of
+    /// decimal places allowed in the currency, e.g. 0 in JPY, 3 in KWD
+    pub fn convert_amount(&self, orig: Decimal) -> Decimal {
+        let scale = self.name.amount_fraction_scale();
+        (orig * self.rate).trunc_with_scale(scale)
+    }
     pub(crate) fn trunc_rate_fraction(&mut self, scale: u32) {
         let new_rate = self.rate.trunc_with_scale(scale);
         self.rate = new_rate;
@@ -103,6 +134,33 @@ impl CurrencyModelSet {
         CurrencyModel::check_rate_range_multi(ms)
     }
 
+    /// convert all the rates relative to the given new base currency, the new
+    /// base has to be present in the current exchange rates
+    pub fn rebase(&mut self, new_base: CurrencyDto) -> Result<(), AppError> {
+        if self.base == new_base {
+            return Ok(());
+        }
+        let divisor = self.find(&new_base)?.rate;
+        if divisor.is_zero() {
+            return Err(AppError {
+                code: AppErrorCode::DataCorruption,
+                detail: Some(format!("reason:div0, base:{}", new_base.to_string())),
+            });
+        }
+        self.exchange_rates
+            .iter_mut()
+            .map(|m| {
+                m.rate = if m.name == new_base {
+                    Decimal::ONE
+                } else {
+                    m.rate / divisor
+                };
+            })
+            .count();
+        self.base = new_base;
+        Ok(())
+    }
+
     pub(super) fn find(&self, given: &CurrencyDto) -> Result<&CurrencyModel, AppError> {
         self.exchange_rates
             .iter()
@@ -114,6 +172,50 @@ impl CurrencyModelSet {
     }
 } // end of impl CurrencyModelSet
 
+impl CurrencyPairRateModel {
+    /// the argument `initial` contains the rates which were in force at the start
+    /// time, the history records are the rates updated after the start time.
+    pub fn collect(
+        pair: (&CurrencyDto, &CurrencyDto),
+        start: DateTime<FixedOffset>,
+        initial: &CurrencyModelSet,
+        mut records: Vec<CurrencyRateHistoryModel>,
+    ) -> Vec<Self> {
+        let (label_base, label_quote) = pair;
+        let mut rate_base = initial.find(label_base).ok().map(|m| m.rate);
+        let mut rate_quote = initial.find(label_quote).ok().map(|m| m.rate);
+        let mut out: Vec<Self> = Vec::new();
+        let mut push_rate =
+            |time: DateTime<FixedOffset>, b: Option<Decimal>, q: Option<Decimal>| {
+                if let (Some(b), Some(q)) = (b, q) {
+                    if let Some(rate) = q.checked_div(b) {
+                        match out.last_mut() {
+                            Some(last) if last.time == time => {
+                                last.rate = rate;
+                            }
+                            _others => out.push(Self { time, rate }),
+                        }
+                    }
+                }
+            };
+        push_rate(start, rate_base, rate_quote);
+        records.sort_by_key(|r| r.time);
+        records
+            .into_iter()
+            .map(|r| {
+                if &r.name == label_base {
+                    rate_base = Some(r.rate);
+                }
+                if &r.name == label_quote {
+                    rate_quote = Some(r.rate);
+                }
+                push_rate(r.time, rate_base, rate_quote);
+            })
+            .count();
+        out
+    } // end of fn collect
+} // end of impl CurrencyPairRateModel
+
 impl TryFrom<(CurrencyModelSet, CurrencyDto, Vec<(u32, CurrencyDto)>)> for OrderCurrencyModel {
     type Error = Vec<AppError>;
     fn try_from(
@@ -190,6 +292,9 @@ impl OrderCurrencyModel {
             code: AppErrorCode::DataCorruption,
             detail: Some(format!("reason:div0, seller:{seller_id}")),
         })?;
+        let newrate = newrate
+            .round_sf(SIGNIFICANT_DIGITS_DERIVED_RATE)
+            .unwrap_or(newrate);
         Ok(CurrencyModel {
             name: self.buyer.name.clone(),
             rate: newrate,
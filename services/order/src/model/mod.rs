@@ -3,18 +3,33 @@ mod currency;
 mod order;
 mod product_policy;
 mod product_price;
+mod promotion;
+mod shipment;
+mod shipping_rate;
+mod stock_alert;
 mod stock_level;
+mod tax;
 
 pub use cart::{CartLineModel, CartModel};
-pub use currency::{CurrencyModel, CurrencyModelSet, OrderCurrencyModel};
+pub use currency::{
+    CurrencyModel, CurrencyModelSet, CurrencyPairRateModel, CurrencyRateHistoryModel,
+    OrderCurrencyModel,
+};
 pub use order::{
-    OlineDupError, OrderLineAppliedPolicyModel, OrderLineIdentity, OrderLineModel,
-    OrderLineModelSet, OrderLinePriceModel, OrderLineQuantityModel, OrderReturnModel,
-    OrderReturnQuantityModel, ShippingModel, ShippingOptionModel,
+    OlineDupError, OrderLineAppliedPolicyModel, OrderLineCancelModel, OrderLineDiscountModel,
+    OrderLineIdentity, OrderLineModel, OrderLineModelSet, OrderLinePriceModel,
+    OrderLineQuantityModel, OrderLineTaxModel, OrderReturnModel, OrderReturnQuantityModel,
+    ShippingModel, ShippingOptionModel,
 };
-pub use product_policy::{ProductPolicyModel, ProductPolicyModelSet};
+pub use product_policy::{ProductBackorderPolicy, ProductPolicyModel, ProductPolicyModelSet};
 pub use product_price::{ProdAttriPriceModel, ProductPriceModel, ProductPriceModelSet};
+pub use promotion::{PromotionModel, PromotionModelSet, PromotionRuleModel};
+pub use shipment::{ShipmentLineModel, ShipmentModel, ShipmentStatus};
+pub use shipping_rate::{ShippingMethodModel, ShippingMethodModelSet, ShippingRateModel};
+pub use stock_alert::{StockAlertKind, StockAlertModel, StockAlertThresholdModel};
 pub use stock_level::{
-    ProductStockIdentity, ProductStockIdentity2, ProductStockModel, StockLevelModelSet,
-    StockQtyRsvModel, StockQuantityModel, StoreStockModel,
+    ProductStockIdentity, ProductStockIdentity2, ProductStockModel, StockAllocPolicyModel,
+    StockAllocPolicyModelSet, StockAllocStrategy, StockLevelModelSet, StockQtyRsvModel,
+    StockQuantityModel, StockWarehouseModel, StockWarehouseModelSet, StoreStockModel,
 };
+pub use tax::{TaxRateModel, TaxRateModelSet};
@@ -6,7 +6,7 @@ use chrono::{DateTime, Duration, DurationRound, FixedOffset, Local as LocalTime}
 use rust_decimal::Decimal;
 use uuid::Uuid;
 
-use ecommerce_common::api::dto::{OrderLinePayDto, PayAmountDto};
+use ecommerce_common::api::dto::{OrderLinePayDto, OrderShippingPayDto, PayAmountDto};
 use ecommerce_common::api::rpc::dto::{
     OrderLinePaidUpdateDto, OrderLinePayUpdateErrorDto, OrderLinePayUpdateErrorReason,
     OrderLineReplicaRefundDto, OrderReplicaPaymentDto,
@@ -18,11 +18,15 @@ use ecommerce_common::model::BaseProductIdentity;
 use crate::api::dto::{ShippingDto, ShippingMethod, ShippingOptionDto};
 use crate::api::rpc::dto::{
     InventoryEditStockLevelDto, OrderLineStockReservingDto, OrderLineStockReturningDto,
+    StockLevelReturnDto,
 };
 use crate::api::web::dto::{
-    OrderCreateRespOkDto, OrderLineCreateErrorDto, OrderLineCreateErrorReason,
+    OrderCreateRespOkDto, OrderInfoReadDto, OrderLineBackorderDto, OrderLineCancelErrorDto,
+    OrderLineCancelErrorReason, OrderLineCancelReadDto, OrderLineCancelReqDto,
+    OrderLineCreateErrorDto, OrderLineCreateErrorReason, OrderLineQtyReadDto, OrderLineReadDto,
     OrderLineReturnErrorDto, OrderLineReturnErrorReason, OrderLineReturnReqDto, OrderLineRsvReqDto,
-    ShipOptionSellerErrorReason, ShippingErrorDto, ShippingOptionErrorDto,
+    ShipOptionMethodErrorReason, ShipOptionSellerErrorReason, ShippingErrorDto,
+    ShippingOptionErrorDto,
 };
 
 use crate::constant::hard_limit;
@@ -30,11 +34,18 @@ use crate::error::AppError;
 use crate::generate_custom_uid;
 
 use super::product_price::ProdAttriPriceModel;
-use super::{CurrencyModel, OrderCurrencyModel, ProductPolicyModel, ProductPriceModel};
+use super::{
+    CurrencyModel, OrderCurrencyModel, ProductPolicyModel, ProductPriceModel, PromotionModel,
+    ShipmentModel, ShippingMethodModelSet, StockWarehouseModelSet, TaxRateModelSet,
+};
 
+#[derive(Clone)]
 pub struct ShippingOptionModel {
     pub seller_id: u32,
     pub method: ShippingMethod,
+    // estimated by the rate table of the chosen method, in the seller's
+    // currency, zero if buyer picks up the items without delivery
+    pub fee: u32,
 }
 pub struct ShippingModel {
     pub contact: ContactModel,
@@ -54,6 +65,8 @@ pub struct OrderLineIdentity {
 pub struct OrderLineAppliedPolicyModel {
     pub reserved_until: DateTime<FixedOffset>,
     pub warranty_until: DateTime<FixedOffset>,
+    // version of product policy applied at the time the order line was created
+    pub version: u32,
 }
 
 pub struct OrderLinePriceModel {
@@ -68,7 +81,30 @@ pub struct OrderLineQuantityModel {
     pub reserved: u32,
     pub paid: u32,
     pub paid_last_update: Option<DateTime<FixedOffset>>,
-} // TODO, record number of items delivered
+    // number of reserved items which are not allocated from stock yet,
+    // payment of the order line is held until this decreases to zero
+    pub backordered: u32,
+} // number of items delivered is recorded in shipments, see `ShipmentModel`
+
+// buyer who cancelled the unpaid order line, and when
+pub struct OrderLineCancelModel {
+    pub usr_id: u32,
+    pub time: DateTime<FixedOffset>,
+}
+
+pub struct OrderLineDiscountModel {
+    // code of the promotion redeemed in the order line
+    pub code: String,
+    // amount deducted from each unit, in smallest unit of the seller's currency
+    pub unit: u32,
+}
+
+pub struct OrderLineTaxModel {
+    // rate applied at the time the order line was created
+    pub rate: Decimal,
+    // tax amount of each unit, in smallest unit of the seller's currency
+    pub unit: u32,
+}
 
 pub struct OrderLineModel {
     id_: OrderLineIdentity,
@@ -78,6 +114,19 @@ pub struct OrderLineModel {
     // combination of attributes selected in the order line
     pub policy: OrderLineAppliedPolicyModel,
     pub qty: OrderLineQuantityModel,
+    pub cancelled: Option<OrderLineCancelModel>,
+    // the price above is already discounted, the tax is NOT included
+    pub discount: Option<OrderLineDiscountModel>,
+    pub tax: Option<OrderLineTaxModel>,
+    // max number of items allowed to wait for new stock, copied from product
+    // policy only when the order line is created, not saved in repository
+    pub backorder_limit: u32,
+    // warehouse which the items are reserved from, `None` for the lines
+    // created before the store has any warehouse
+    pub warehouse_id: Option<u32>,
+    // warehouses of the seller sorted by distance to shipping address, only
+    // required on stock reservation, not saved in repository
+    pub warehouse_rank: Vec<u32>,
 }
 
 // TODO, new struct for hash-map value, including :
@@ -122,8 +171,9 @@ impl TryFrom<ShippingOptionDto> for ShippingOptionModel {
             Ok(Self {
                 seller_id: value.seller_id,
                 method: value.method,
+                fee: 0,
             })
-        } // TODO, will check whether the seller supports specific delivery service
+        } // the seller's support for the delivery service is checked in `apply_fee()`
     }
 }
 impl ShippingOptionModel {
@@ -160,6 +210,80 @@ impl ShippingOptionModel {
             Ok(objs)
         }
     }
+
+    // each option has to refer to a seller in the order and a delivery service
+    // registered by the seller, the fee is estimated by total weight of the
+    // seller's items and the shipping address. Stores which have not registered
+    // any shipping method yet are not charged for delivery.
+    pub fn apply_fee(
+        options: &mut [Self],
+        ms: &ShippingMethodModelSet,
+        addr: Option<&PhyAddrModel>,
+        weights: &HashMap<u32, u32>,
+    ) -> DefaultResult<(), Vec<Option<ShippingOptionErrorDto>>> {
+        let results = options
+            .iter_mut()
+            .map(|opt| {
+                if !ms.contains_store(opt.seller_id) {
+                    opt.fee = 0;
+                    return Ok(());
+                }
+                let weight = weights.get(&opt.seller_id).ok_or(ShippingOptionErrorDto {
+                    seller_id: Some(ShipOptionSellerErrorReason::NotExist),
+                    method: None,
+                })?;
+                let registered =
+                    ms.find(opt.seller_id, &opt.method)
+                        .ok_or(ShippingOptionErrorDto {
+                            seller_id: None,
+                            method: Some(ShipOptionMethodErrorReason::NotSupport),
+                        })?;
+                opt.fee = match addr {
+                    Some(a) => {
+                        registered
+                            .estimate_fee(a, *weight)
+                            .ok_or(ShippingOptionErrorDto {
+                                seller_id: None,
+                                method: Some(ShipOptionMethodErrorReason::NotSupport),
+                            })?
+                    }
+                    None => 0,
+                };
+                Ok(())
+            })
+            .collect::<Vec<DefaultResult<(), ShippingOptionErrorDto>>>();
+        if results.iter().any(DefaultResult::is_err) {
+            let errors = results.into_iter().map(DefaultResult::err).collect();
+            Err(errors)
+        } else {
+            Ok(())
+        }
+    } // end of fn apply_fee
+
+    fn into_paym_dto(self, curr_ex: CurrencyModel) -> OrderShippingPayDto {
+        let fee_seller = Decimal::new(self.fee as i64, 0u32);
+        OrderShippingPayDto {
+            seller_id: self.seller_id,
+            amount: curr_ex.convert_amount(fee_seller).to_string(),
+        }
+    }
+
+    pub(crate) fn to_paym_dtos(
+        options: Vec<Self>,
+        currency_m: &OrderCurrencyModel,
+    ) -> DefaultResult<Vec<OrderShippingPayDto>, AppError> {
+        // free delivery is not forwarded to payment, such option may refer to
+        // a seller without any line in the order
+        options
+            .into_iter()
+            .filter(|opt| opt.fee > 0)
+            .map(|opt| {
+                currency_m
+                    .to_buyer_rate(opt.seller_id)
+                    .map(|rate| opt.into_paym_dto(rate))
+            })
+            .collect()
+    }
 } // end of impl ShippingOptionModel
 
 impl From<ShippingModel> for ShippingDto {
@@ -216,6 +340,12 @@ impl From<&OrderLineReturnReqDto> for OrderLineIdentity {
         Self::from(args)
     }
 }
+impl From<&OrderLineCancelReqDto> for OrderLineIdentity {
+    fn from(d: &OrderLineCancelReqDto) -> Self {
+        let args = (d.seller_id, d.product_id, d.attr_set_seq);
+        Self::from(args)
+    }
+}
 impl From<(u32, u64, u16)> for OrderLineIdentity {
     fn from(d: (u32, u64, u16)) -> Self {
         Self {
@@ -242,7 +372,7 @@ impl OrderLineIdentity {
     pub fn attrs_seq_num(&self) -> u16 {
         self._attr_set_req
     }
-    fn compare_raw(&self, d: (u32, u64, u16)) -> bool {
+    pub(super) fn compare_raw(&self, d: (u32, u64, u16)) -> bool {
         (self.store_id() == d.0) && (self.product_id() == d.1) && (self.attrs_seq_num() == d.2)
     }
 }
@@ -251,6 +381,9 @@ impl OrderLineQuantityModel {
     pub fn has_unpaid(&self) -> bool {
         self.reserved > self.paid
     }
+    pub fn is_backordered(&self) -> bool {
+        self.backordered > 0
+    }
 }
 
 impl From<(u32, u32)> for OrderLinePriceModel {
@@ -260,7 +393,7 @@ impl From<(u32, u32)> for OrderLinePriceModel {
 }
 
 impl OrderLinePriceModel {
-    fn finalize_price(
+    pub(super) fn finalize_price(
         data: &OrderLineRsvReqDto,
         pricem: &ProductPriceModel,
     ) -> DefaultResult<(Self, ProdAttriPriceModel), AppError> {
@@ -283,15 +416,39 @@ impl OrderLinePriceModel {
         Ok((obj, attrprice))
     }
 
+    // among the applicable promotions, only the one which gives the largest
+    // discount is applied to the order line
+    pub(super) fn apply_promotion(
+        &mut self,
+        data: &OrderLineRsvReqDto,
+        promos: &[PromotionModel],
+    ) -> Option<OrderLineDiscountModel> {
+        let (unit, qty) = (self._unit, data.quantity);
+        let (chosen, amount) = promos
+            .iter()
+            .filter(|m| m.is_applicable(data.seller_id, data.product_id))
+            .map(|m| (m, m.unit_discount(unit, qty)))
+            .filter(|(_, amount)| *amount > 0)
+            .max_by_key(|(_, amount)| *amount)?;
+        self._unit = unit - amount;
+        self._total = self._unit * qty;
+        Some(OrderLineDiscountModel {
+            code: chosen.code.clone(),
+            unit: amount,
+        })
+    }
+
+    fn per_unit_paym_dto(amount: u32, qty: u32, curr_ex: &CurrencyModel) -> PayAmountDto {
+        let obj = Self::from((amount, amount * qty));
+        obj.into_paym_dto(curr_ex.clone())
+    }
+
     fn into_paym_dto(self, curr_ex: CurrencyModel) -> PayAmountDto {
-        let fraction_limit = curr_ex.name.amount_fraction_scale();
         let p_unit_seller = Decimal::new(self.unit() as i64, 0u32);
         let p_total_seller = Decimal::new(self.total() as i64, 0u32);
-        let p_unit_buyer = p_unit_seller * curr_ex.rate;
-        let p_total_buyer = p_total_seller * curr_ex.rate;
         PayAmountDto {
-            unit: p_unit_buyer.trunc_with_scale(fraction_limit).to_string(),
-            total: p_total_buyer.trunc_with_scale(fraction_limit).to_string(),
+            unit: curr_ex.convert_amount(p_unit_seller).to_string(),
+            total: curr_ex.convert_amount(p_total_seller).to_string(),
         }
     }
 
@@ -317,6 +474,12 @@ impl From<OLineModelCvtArgs> for OrderLineModel {
             policy: value.2,
             qty: value.3,
             attrs_charge: value.4,
+            cancelled: None,
+            discount: None,
+            tax: None,
+            backorder_limit: 0,
+            warehouse_id: None,
+            warehouse_rank: Vec::new(),
         }
     }
 }
@@ -327,7 +490,9 @@ impl OrderLineModel {
         policym: &ProductPolicyModel,
         pricem: &ProductPriceModel,
     ) -> DefaultResult<(), AppError> {
-        let result = if data.product_id != policym.product_id {
+        let result = if data.seller_id != policym.store_id {
+            Err("product-policy, store-id")
+        } else if data.product_id != policym.product_id {
             Err("product-policy, id")
         } else if data.product_id != pricem.product_id() {
             Err("product-price, id")
@@ -380,6 +545,7 @@ impl OrderLineModel {
         data: OrderLineRsvReqDto,
         policym: &ProductPolicyModel,
         pricem: &ProductPriceModel,
+        promos: &[PromotionModel],
     ) -> DefaultResult<Self, AppError> {
         Self::validate_id_match(&data, policym, pricem)?;
         Self::validate_rsv_limit(&data, policym)?;
@@ -387,19 +553,25 @@ impl OrderLineModel {
         let timenow = LocalTime::now().fixed_offset();
         let reserved_until = timenow + Duration::seconds(policym.auto_cancel_secs as i64);
         let warranty_until = timenow + Duration::hours(policym.warranty_hours as i64);
-        let (lineprice, attrs_charge) = OrderLinePriceModel::finalize_price(&data, pricem)?;
+        let (mut lineprice, attrs_charge) = OrderLinePriceModel::finalize_price(&data, pricem)?;
+        let discount = lineprice.apply_promotion(&data, promos);
         let id_ = OrderLineIdentity::from((data.seller_id, data.product_id, 0));
         let qty = OrderLineQuantityModel {
             reserved: data.quantity,
             paid: 0,
             paid_last_update: None,
+            backordered: 0,
         };
         let policy = OrderLineAppliedPolicyModel {
             reserved_until,
             warranty_until,
+            version: policym.version,
         };
         let args = (id_, lineprice, policy, qty, attrs_charge);
-        Ok(Self::from(args))
+        let mut obj = Self::from(args);
+        obj.discount = discount;
+        obj.backorder_limit = policym.backorder_limit(data.quantity);
+        Ok(obj)
     } // end of fn try_from
 
     pub fn generate_order_id(machine_code: u8) -> String {
@@ -427,7 +599,9 @@ impl OrderLineModel {
                 });
                 let possible_error = if let Some(m) = result {
                     let new_paid_qty = m.qty.paid + d.qty;
-                    if m.qty.reserved >= new_paid_qty {
+                    if m.cancelled.is_some() || m.qty.is_backordered() {
+                        Some(OrderLinePayUpdateErrorReason::InvalidQuantity)
+                    } else if m.qty.reserved >= new_paid_qty {
                         if let Some(old_dt) = m.qty.paid_last_update.as_ref() {
                             if old_dt < &charge_time {
                                 m.qty.paid = new_paid_qty;
@@ -457,9 +631,83 @@ impl OrderLineModel {
             .collect()
     } // end of update_payments
 
+    pub fn cancel_unpaid(
+        models: &mut [OrderLineModel],
+        pids: Vec<OrderLineIdentity>,
+        cancel: OrderLineCancelModel,
+    ) -> Vec<OrderLineCancelErrorDto> {
+        pids.into_iter()
+            .filter_map(|pid| {
+                let result = models.iter_mut().find(|m| m.id() == &pid);
+                let possible_error = if let Some(m) = result {
+                    if m.cancelled.is_some() {
+                        Some(OrderLineCancelErrorReason::AlreadyCancelled)
+                    } else if m.qty.paid > 0 {
+                        Some(OrderLineCancelErrorReason::AlreadyPaid)
+                    } else if cancel.time >= m.policy.reserved_until {
+                        Some(OrderLineCancelErrorReason::ReservationExpired)
+                    } else {
+                        m.cancelled = Some(OrderLineCancelModel {
+                            usr_id: cancel.usr_id,
+                            time: cancel.time,
+                        });
+                        None
+                    }
+                } else {
+                    Some(OrderLineCancelErrorReason::NotExist)
+                };
+                possible_error.map(|reason| OrderLineCancelErrorDto {
+                    seller_id: pid.store_id(),
+                    product_id: pid.product_id(),
+                    attr_set_seq: pid.attrs_seq_num(),
+                    reason,
+                })
+            })
+            .collect()
+    } // end of cancel_unpaid
+
+    // items of the cancelled lines which go back to stock, lines waiting for
+    // all items backordered have nothing to return
+    pub fn cancelled_stock_return(oid: &str, models: &[OrderLineModel]) -> StockLevelReturnDto {
+        let items = models
+            .iter()
+            .filter(|m| m.cancelled.is_some())
+            .map(InventoryEditStockLevelDto::from)
+            .filter(|d| d.qty_add > 0)
+            .collect::<Vec<_>>();
+        StockLevelReturnDto {
+            items,
+            order_id: oid.to_string(),
+        }
+    }
+
+    // promotions redeemed by the voided lines but no longer by any line of the
+    // same order, usage count of these promotions can be given back
+    pub(crate) fn released_promotions(
+        voided: &[&OrderLineModel],
+        all_lines: &[OrderLineModel],
+        time_now: DateTime<FixedOffset>,
+    ) -> Vec<String> {
+        let mut codes = voided
+            .iter()
+            .filter_map(|m| m.discount.as_ref().map(|d| d.code.clone()))
+            .collect::<Vec<_>>();
+        codes.sort();
+        codes.dedup();
+        codes.retain(|code| !all_lines.iter().any(|m| m.redeems(code, time_now)));
+        codes
+    }
+    fn redeems(&self, code: &str, time_now: DateTime<FixedOffset>) -> bool {
+        let same_code = self.discount.as_ref().map(|d| d.code.as_str()) == Some(code);
+        let alive = self.cancelled.is_none() && time_now < self.policy.reserved_until;
+        same_code && (self.qty.paid > 0 || alive)
+    }
+
     pub(crate) fn num_reserved(&self, time_now: DateTime<FixedOffset>) -> u32 {
-        if time_now < self.policy.reserved_until {
-            self.qty.reserved
+        if self.cancelled.is_some() {
+            self.qty.paid
+        } else if time_now < self.policy.reserved_until {
+            self.qty.reserved - self.qty.backordered
         } else {
             self.qty.paid
         }
@@ -474,14 +722,59 @@ impl OrderLineModel {
         &self.attrs_charge
     }
 
+    // unit price which buyer has to pay, including tax
+    pub(crate) fn taxed_unit_price(&self) -> u32 {
+        let tax = self.tax.as_ref().map_or(0, |t| t.unit);
+        self.price.unit() + tax
+    }
+    fn taxed_price(&self) -> OrderLinePriceModel {
+        let tax = self.tax.as_ref().map_or(0, |t| t.unit);
+        let unit = self.price.unit() + tax;
+        let total = self.price.total() + tax * self.qty.reserved;
+        OrderLinePriceModel::from((unit, total))
+    }
+
+    pub fn apply_tax(lines: &mut [Self], ms: &TaxRateModelSet, addr: &PhyAddrModel) {
+        lines
+            .iter_mut()
+            .map(|line| {
+                let store_id = line.id_.store_id();
+                line.tax = ms.find(store_id, addr).map(|m| OrderLineTaxModel {
+                    rate: m.rate,
+                    unit: m.unit_tax(line.price.unit()),
+                });
+            })
+            .count();
+    }
+
+    pub fn rank_warehouses(lines: &mut [Self], ms: &StockWarehouseModelSet, addr: &PhyAddrModel) {
+        lines
+            .iter_mut()
+            .map(|line| {
+                line.warehouse_rank = ms.rank(line.id_.store_id(), addr);
+            })
+            .count();
+    }
+
     fn into_paym_dto(self, curr_m: CurrencyModel) -> OrderLinePayDto {
+        let price = self.taxed_price();
         let Self {
             id_,
-            price,
+            price: _,
             policy,
             qty,
             attrs_charge: _,
+            cancelled: _,
+            discount,
+            tax,
+            backorder_limit: _,
+            warehouse_id: _,
+            warehouse_rank: _,
         } = self;
+        let discount =
+            discount.map(|d| OrderLinePriceModel::per_unit_paym_dto(d.unit, qty.reserved, &curr_m));
+        let tax =
+            tax.map(|t| OrderLinePriceModel::per_unit_paym_dto(t.unit, qty.reserved, &curr_m));
         OrderLinePayDto {
             seller_id: id_.store_id(),
             product_id: id_.product_id(),
@@ -489,8 +782,46 @@ impl OrderLineModel {
             quantity: qty.reserved,
             reserved_until: policy.reserved_until.to_rfc3339(),
             amount: price.into_paym_dto(curr_m),
+            discount,
+            tax,
         } // TODO, add attribute pricing, and attr-set-seq-num to this dto object
     }
+
+    fn into_read_dto(self, curr_m: CurrencyModel) -> OrderLineReadDto {
+        let price = self.taxed_price();
+        let Self {
+            id_,
+            price: _,
+            policy,
+            qty,
+            attrs_charge: _,
+            cancelled,
+            discount: _,
+            tax: _,
+            backorder_limit: _,
+            warehouse_id,
+            warehouse_rank: _,
+        } = self;
+        OrderLineReadDto {
+            seller_id: id_.store_id(),
+            product_id: id_.product_id(),
+            attr_set_seq: id_.attrs_seq_num(),
+            reserved_until: policy.reserved_until.to_rfc3339(),
+            warranty_until: policy.warranty_until.to_rfc3339(),
+            qty: OrderLineQtyReadDto {
+                reserved: qty.reserved,
+                paid: qty.paid,
+                paid_last_update: qty.paid_last_update.map(|t| t.to_rfc3339()),
+                backordered: qty.backordered,
+            },
+            amount: price.into_paym_dto(curr_m),
+            cancelled: cancelled.map(|c| OrderLineCancelReadDto {
+                usr_id: c.usr_id,
+                time: c.time.to_rfc3339(),
+            }),
+            warehouse_id,
+        }
+    }
 } // end of impl OrderLineModel
 
 impl From<OrderLineModel> for OrderLineStockReservingDto {
@@ -498,20 +829,23 @@ impl From<OrderLineModel> for OrderLineStockReservingDto {
         OrderLineStockReservingDto {
             seller_id: value.id_.store_id(),
             product_id: value.id_.product_id(),
-            qty: value.qty.reserved,
+            qty: value.qty.reserved - value.qty.backordered,
+            warehouse_id: value.warehouse_id,
         }
     }
 }
 
 impl<'a> From<&'a OrderLineModel> for InventoryEditStockLevelDto {
     fn from(value: &'a OrderLineModel) -> InventoryEditStockLevelDto {
-        assert!(value.qty.reserved >= value.qty.paid);
-        let num_returning = (value.qty.reserved - value.qty.paid) as i32;
+        assert!(value.qty.reserved >= (value.qty.paid + value.qty.backordered));
+        // backordered items were never taken from stock, nothing to return
+        let num_returning = (value.qty.reserved - value.qty.paid - value.qty.backordered) as i32;
         InventoryEditStockLevelDto {
             store_id: value.id_.store_id(),
             product_id: value.id_.product_id(),
             qty_add: num_returning,
             expiry: value.policy.reserved_until,
+            warehouse_id: value.warehouse_id.unwrap_or(0),
         } // NOTE, the field `expiry` should NOT be referenced by the entire application
           // , becuase the editing data, converted from order line, does NOT really reflect
           // the expiry time of the original stock item
@@ -587,18 +921,34 @@ impl TryFrom<OrderTopLvlCvtArgs> for OrderLineModelSet {
     }
 }
 
-impl TryFrom<OrderLineModelSet> for OrderCreateRespOkDto {
+impl TryFrom<(OrderLineModelSet, Vec<ShippingOptionModel>)> for OrderCreateRespOkDto {
     type Error = Vec<AppError>;
 
-    fn try_from(value: OrderLineModelSet) -> Result<Self, Self::Error> {
-        let OrderLineModelSet {
-            order_id,
-            owner_id,
-            create_time,
-            currency,
-            lines,
-        } = value;
+    fn try_from(value: (OrderLineModelSet, Vec<ShippingOptionModel>)) -> Result<Self, Self::Error> {
+        let (
+            OrderLineModelSet {
+                order_id,
+                owner_id,
+                create_time,
+                currency,
+                lines,
+            },
+            sh_opts,
+        ) = value;
         let mut errors = Vec::new();
+        let shipping = ShippingOptionModel::to_paym_dtos(sh_opts, &currency)
+            .map_err(|e| errors.push(e))
+            .unwrap_or_default();
+        let backorders = lines
+            .iter()
+            .filter(|line| line.qty.is_backordered())
+            .map(|line| OrderLineBackorderDto {
+                seller_id: line.id_.store_id(),
+                product_id: line.id_.product_id(),
+                attr_set_seq: line.id_.attrs_seq_num(),
+                quantity: line.qty.backordered,
+            })
+            .collect::<Vec<_>>();
         let reserved_lines = lines
             .into_iter()
             .filter_map(|line| {
@@ -615,6 +965,8 @@ impl TryFrom<OrderLineModelSet> for OrderCreateRespOkDto {
                 usr_id: owner_id,
                 currency: currency.into(),
                 reserved_lines,
+                backorders,
+                shipping,
                 time: create_time.timestamp() as u64,
             })
         } else {
@@ -630,10 +982,14 @@ impl OrderLineModelSet {
         olines: Vec<OrderLineModel>,
         currency_m: OrderCurrencyModel,
         billing: BillingModel,
+        sh_opts: Vec<ShippingOptionModel>,
     ) -> DefaultResult<OrderReplicaPaymentDto, AppError> {
+        let shipping = ShippingOptionModel::to_paym_dtos(sh_opts, &currency_m)?;
         let mut errors = Vec::new();
         let lines = olines
             .into_iter()
+            // payment of backordered lines is held until all items are allocated
+            .filter(|line| line.cancelled.is_none() && !line.qty.is_backordered())
             .filter_map(|line| {
                 currency_m
                     .to_buyer_rate(line.id_.store_id())
@@ -649,11 +1005,55 @@ impl OrderLineModelSet {
                 lines,
                 billing: billing.into(),
                 currency: currency_m.into(),
+                shipping,
             })
         } else {
             Err(errors.remove(0))
         }
     }
+    pub(crate) fn into_read_dto(
+        self,
+        load_lines: bool,
+        billing: Option<BillingModel>,
+        shipping: Option<ShippingModel>,
+    ) -> DefaultResult<OrderInfoReadDto, AppError> {
+        let Self {
+            order_id,
+            owner_id,
+            create_time,
+            currency,
+            lines,
+        } = self;
+        let lines = if load_lines {
+            let mut errors = Vec::new();
+            let lines = lines
+                .into_iter()
+                .filter_map(|line| {
+                    currency
+                        .to_buyer_rate(line.id_.store_id())
+                        .map_err(|e| errors.push(e))
+                        .ok()
+                        .map(|rate| line.into_read_dto(rate))
+                })
+                .collect::<Vec<_>>();
+            if !errors.is_empty() {
+                return Err(errors.remove(0));
+            }
+            Some(lines)
+        } else {
+            None
+        };
+        Ok(OrderInfoReadDto {
+            order_id,
+            usr_id: owner_id,
+            time: create_time.to_rfc3339(),
+            currency: currency.into(),
+            lines,
+            billing: billing.map(BillingModel::into),
+            shipping: shipping.map(ShippingModel::into),
+        })
+    } // end of fn into_read_dto
+
     pub fn id(&self) -> &String {
         &self.order_id
     }
@@ -669,14 +1069,55 @@ impl OrderLineModelSet {
     pub fn lines(&self) -> &[OrderLineModel] {
         &self.lines
     }
+    pub(crate) fn lines_mut(&mut self) -> &mut [OrderLineModel] {
+        &mut self.lines
+    }
     #[cfg(feature = "mariadb")]
     pub(crate) fn append_lines(&mut self, new: Vec<OrderLineModel>) {
         self.lines.extend(new);
     }
+    // each promotion is counted once per order, no matter how many lines redeemed it
+    pub(crate) fn redeemed_promotions(&self) -> Vec<String> {
+        let mut codes = self
+            .lines
+            .iter()
+            .filter_map(|m| m.discount.as_ref().map(|d| d.code.clone()))
+            .collect::<Vec<_>>();
+        codes.sort();
+        codes.dedup();
+        codes
+    }
+    // report all the lines redeeming any of the given promotions which have
+    // reached their usage limit
+    pub(crate) fn promotion_exhausted_errors(
+        &self,
+        codes: &[String],
+    ) -> Vec<OrderLineCreateErrorDto> {
+        self.lines
+            .iter()
+            .filter(|m| {
+                m.discount
+                    .as_ref()
+                    .map(|d| codes.contains(&d.code))
+                    .unwrap_or(false)
+            })
+            .map(|m| OrderLineCreateErrorDto {
+                seller_id: m.id().store_id(),
+                product_id: m.id().product_id(),
+                reason: OrderLineCreateErrorReason::PromotionExhausted,
+                nonexist: None,
+                shortage: None,
+                attr_vals: None,
+                rsv_limit: None,
+            })
+            .collect()
+    }
+    // backordered lines are included, their reservation expires when the buyer
+    // has waited too long for new stock
     pub(crate) fn unpaid_lines(&self) -> Vec<&OrderLineModel> {
         self.lines
             .iter()
-            .filter(|m| m.qty.has_unpaid())
+            .filter(|m| m.cancelled.is_none() && m.qty.has_unpaid())
             .collect::<Vec<_>>()
     }
 
@@ -738,10 +1179,14 @@ impl OrderReturnModel {
         }
     }
 
+    // only the items delivered to the buyer can be returned, order lines which
+    // have no shipment record are exempt, sellers may not track the parcels
+    // in this service
     pub fn filter_requests(
         data: Vec<OrderLineReturnReqDto>,
         o_lines: Vec<OrderLineModel>,
         mut o_returns: Vec<Self>,
+        o_shipments: Vec<ShipmentModel>,
     ) -> DefaultResult<Vec<Self>, Vec<OrderLineReturnErrorDto>> {
         let time_now = LocalTime::now().fixed_offset();
         let time_now =
@@ -764,6 +1209,11 @@ impl OrderReturnModel {
                         let tot_num_return = num_returned + d.quantity;
                         if tot_num_return > oline.num_reserved(time_now) {
                             Some(OrderLineReturnErrorReason::QtyLimitExceed)
+                        } else if ShipmentModel::num_shipped(&o_shipments, &oline.id_) > 0
+                            && tot_num_return
+                                > ShipmentModel::num_delivered(&o_shipments, &oline.id_)
+                        {
+                            Some(OrderLineReturnErrorReason::NotDelivered)
                         } else if let Some(r) = result {
                             if r.qty.contains_key(&time_now) {
                                 Some(OrderLineReturnErrorReason::DuplicateReturn)
@@ -807,8 +1257,8 @@ impl OrderReturnModel {
                     .iter()
                     .find(|item| item.id_.compare_raw(req_id_combo))
                     .unwrap();
-                let total = oline.price.unit() * d.quantity;
-                let refund = OrderLinePriceModel::from((oline.price.unit(), total));
+                let unit = oline.taxed_unit_price();
+                let refund = OrderLinePriceModel::from((unit, unit * d.quantity));
                 let val = (d.quantity, refund);
                 if let Some(r) = result {
                     r.qty.clear(); // no need to output saved requests
@@ -1,37 +1,91 @@
-use std::cmp::PartialEq;
+use std::cmp::{min, PartialEq};
+use std::collections::HashMap;
 use std::result::Result as DefaultResult;
 use std::vec::Vec;
 
 use ecommerce_common::error::AppErrorCode;
+use ecommerce_common::model::BaseProductIdentity;
 
 use crate::api::web::dto::{
-    ProductPolicyClientErrorDto, ProductPolicyClientLimitDto, ProductPolicyDto,
-    ProductPolicyNumRsvLimitDto,
+    OrderLineRsvReqDto, ProductBackorderPolicyDto, ProductPolicyClientErrorDto,
+    ProductPolicyClientLimitDto, ProductPolicyDto, ProductPolicyNumRsvLimitDto,
 };
 use crate::error::AppError;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProductBackorderPolicy {
+    // order lines are rejected when stock runs short
+    Disabled,
+    // accept the shortage of stock, up to `max_num_backorder` items in each
+    // order line
+    Backorder,
+    // accept items which are not in stock yet, the number of items is limited
+    // only by `max_num_rsv`
+    PreOrder,
+}
+
 #[derive(Debug)]
 pub struct ProductPolicyModel {
+    // each store front could configure its own policy even with the same product
+    pub store_id: u32,
     pub product_id: u64,
+    // policies are append-only, each update is saved as new version, so existing
+    // order lines can still refer to the version applied at the time of creation
+    pub version: u32,
     pub auto_cancel_secs: u32,
     pub warranty_hours: u32,
     // indicate max/min number of items for each type of product
     // per order transaction.
     pub max_num_rsv: u16,
     pub min_num_rsv: u16,
-    // TODO, add following field:
-    // - `store_id`: u32, each store front could configure its own policy
-    //    even with the same product.
+    pub backorder: ProductBackorderPolicy,
+    pub max_num_backorder: u16,
+    // weight of single item in grams, zero means negligible
+    pub weight_gram: u32,
     pub is_create: bool,
 }
 
 impl PartialEq for ProductPolicyModel {
     fn eq(&self, other: &Self) -> bool {
-        (self.product_id == other.product_id)
+        (self.store_id == other.store_id)
+            && (self.product_id == other.product_id)
+            && (self.version == other.version)
             && (self.auto_cancel_secs == other.auto_cancel_secs)
             && (self.warranty_hours == other.warranty_hours)
             && (self.max_num_rsv == other.max_num_rsv)
             && (self.min_num_rsv == other.min_num_rsv)
+            && (self.backorder == other.backorder)
+            && (self.max_num_backorder == other.max_num_backorder)
+            && (self.weight_gram == other.weight_gram)
+    }
+}
+
+impl ProductPolicyModel {
+    pub fn id(&self) -> BaseProductIdentity {
+        BaseProductIdentity {
+            store_id: self.store_id,
+            product_id: self.product_id,
+        }
+    }
+
+    // max number of items allowed to wait for new stock, in an order line
+    // which requests given number of items
+    pub fn backorder_limit(&self, num_req: u32) -> u32 {
+        match self.backorder {
+            ProductBackorderPolicy::Disabled => 0,
+            ProductBackorderPolicy::Backorder => min(num_req, self.max_num_backorder as u32),
+            ProductBackorderPolicy::PreOrder => num_req,
+        }
+    }
+}
+
+impl From<ProductBackorderPolicyDto> for ProductBackorderPolicy {
+    fn from(value: ProductBackorderPolicyDto) -> Self {
+        match value {
+            ProductBackorderPolicyDto::Disabled => Self::Disabled,
+            ProductBackorderPolicyDto::Backorder => Self::Backorder,
+            ProductBackorderPolicyDto::PreOrder => Self::PreOrder,
+        }
     }
 }
 
@@ -48,6 +102,7 @@ impl ProductPolicyModelSet {
     ) -> DefaultResult<(), Vec<ProductPolicyClientErrorDto>> {
         if newdata.is_empty() {
             let ce = ProductPolicyClientErrorDto {
+                seller_id: 0u32,
                 product_id: 0u64,
                 auto_cancel_secs: None,
                 warranty_hours: None,
@@ -89,6 +144,7 @@ impl ProductPolicyModelSet {
 
                 if num_rsv.is_some() || auto_cancel_secs.is_some() || warranty_hours.is_some() {
                     let ce = ProductPolicyClientErrorDto {
+                        seller_id: item.seller_id,
                         product_id: item.product_id,
                         auto_cancel_secs,
                         warranty_hours,
@@ -115,22 +171,41 @@ impl ProductPolicyModelSet {
             .filter_map(|mut item| {
                 let max_num_rsv = item.max_num_rsv.take().unwrap_or(zero_num_rsv);
                 let min_num_rsv = item.min_num_rsv.take().unwrap_or(zero_num_rsv);
+                let backorder = item
+                    .backorder
+                    .take()
+                    .map(ProductBackorderPolicy::from)
+                    .unwrap_or(ProductBackorderPolicy::Disabled);
+                let max_num_backorder = item.max_num_backorder.take().unwrap_or(0);
+                let weight_gram = item.weight_gram.take().unwrap_or(0);
                 let result = self
                     .policies
                     .iter_mut()
-                    .find(|o| o.product_id == item.product_id);
+                    .find(|o| o.store_id == item.seller_id && o.product_id == item.product_id);
                 if let Some(obj) = result {
+                    // never overwrite saved version, the repository appends it as
+                    // the next version instead
+                    obj.version += 1;
+                    obj.is_create = true;
                     obj.auto_cancel_secs = item.auto_cancel_secs;
                     obj.warranty_hours = item.warranty_hours;
                     obj.max_num_rsv = max_num_rsv;
                     obj.min_num_rsv = min_num_rsv;
+                    obj.backorder = backorder;
+                    obj.max_num_backorder = max_num_backorder;
+                    obj.weight_gram = weight_gram;
                     None
                 } else {
                     Some(ProductPolicyModel {
                         is_create: true,
+                        store_id: item.seller_id,
                         product_id: item.product_id,
+                        version: 0,
                         max_num_rsv,
                         min_num_rsv,
+                        backorder,
+                        max_num_backorder,
+                        weight_gram,
                         auto_cancel_secs: item.auto_cancel_secs,
                         warranty_hours: item.warranty_hours,
                     })
@@ -140,5 +215,23 @@ impl ProductPolicyModelSet {
         self.policies.append(&mut _new_objs);
         Ok(self)
     } // end of fn update
-      // TODO, consider append-only approach, for the order lines which apply previous setup
+
+    // total weight in grams of the requested items, grouped by seller
+    pub fn total_weight(&self, lines: &[OrderLineRsvReqDto]) -> HashMap<u32, u32> {
+        let mut out = HashMap::new();
+        lines
+            .iter()
+            .map(|d| {
+                let unit = self
+                    .policies
+                    .iter()
+                    .find(|p| p.store_id == d.seller_id && p.product_id == d.product_id)
+                    .map(|p| p.weight_gram)
+                    .unwrap_or(0);
+                let entry = out.entry(d.seller_id).or_insert(0u32);
+                *entry = entry.saturating_add(unit.saturating_mul(d.quantity));
+            })
+            .count();
+        out
+    }
 } // end of impl ProductPolicyModelSet
@@ -0,0 +1,263 @@
+use std::cmp::min;
+use std::collections::HashSet;
+use std::result::Result as DefaultResult;
+use std::vec::Vec;
+
+use chrono::{DateTime, FixedOffset};
+
+use ecommerce_common::error::AppErrorCode;
+
+use crate::api::web::dto::{
+    CouponErrorDto, CouponErrorReason, PromotionClientErrorDto, PromotionDto, PromotionErrorReason,
+    PromotionRuleDto,
+};
+use crate::error::AppError;
+
+const MAX_CODE_LENGTH: usize = 32;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PromotionRuleModel {
+    PercentOff(u8),
+    // amount in smallest unit of the seller's currency
+    FixedOff(u32),
+    BuyXGetY { buy: u16, free: u16 },
+}
+
+#[derive(Debug)]
+pub struct PromotionModel {
+    // the code is unique across all stores, buyers redeem a promotion by its code
+    pub code: String,
+    pub store_id: u32,
+    // store-wide campaign if the list is empty
+    pub product_ids: Vec<u64>,
+    pub rule: PromotionRuleModel,
+    pub start_after: DateTime<FixedOffset>,
+    pub end_before: DateTime<FixedOffset>,
+    // number of orders which can redeem the promotion, `None` means unlimited
+    pub max_usage: Option<u32>,
+    pub num_used: u32,
+}
+
+pub struct PromotionModelSet {
+    pub promotions: Vec<PromotionModel>,
+}
+
+impl From<PromotionRuleDto> for PromotionRuleModel {
+    fn from(value: PromotionRuleDto) -> Self {
+        match value {
+            PromotionRuleDto::PercentOff(rate) => Self::PercentOff(rate),
+            PromotionRuleDto::FixedOff(amount) => Self::FixedOff(amount),
+            PromotionRuleDto::BuyXGetY { buy, free } => Self::BuyXGetY { buy, free },
+        }
+    }
+}
+
+// the rule is flattened to a label and two numeric arguments in the datastore
+impl From<&PromotionRuleModel> for (&'static str, u32, u32) {
+    fn from(value: &PromotionRuleModel) -> Self {
+        match value {
+            PromotionRuleModel::PercentOff(rate) => ("PercentOff", *rate as u32, 0),
+            PromotionRuleModel::FixedOff(amount) => ("FixedOff", *amount, 0),
+            PromotionRuleModel::BuyXGetY { buy, free } => ("BuyXGetY", *buy as u32, *free as u32),
+        }
+    }
+}
+
+impl TryFrom<(&str, u32, u32)> for PromotionRuleModel {
+    type Error = AppError;
+    fn try_from(value: (&str, u32, u32)) -> DefaultResult<Self, Self::Error> {
+        let (label, arg0, arg1) = value;
+        let result = match label {
+            "PercentOff" => u8::try_from(arg0).ok().map(Self::PercentOff),
+            "FixedOff" => Some(Self::FixedOff(arg0)),
+            "BuyXGetY" => u16::try_from(arg0)
+                .ok()
+                .zip(u16::try_from(arg1).ok())
+                .map(|(buy, free)| Self::BuyXGetY { buy, free }),
+            _others => None,
+        };
+        result.ok_or(AppError {
+            code: AppErrorCode::DataCorruption,
+            detail: Some(format!("promotion-rule: {label}, {arg0}, {arg1}")),
+        })
+    }
+}
+
+impl PromotionRuleModel {
+    fn is_valid(&self) -> bool {
+        match self {
+            Self::PercentOff(rate) => (1..=100).contains(rate),
+            Self::FixedOff(amount) => *amount > 0,
+            Self::BuyXGetY { buy, free } => *buy > 0 && *free > 0,
+        }
+    }
+}
+
+impl From<PromotionDto> for PromotionModel {
+    fn from(value: PromotionDto) -> Self {
+        Self {
+            code: value.code,
+            store_id: value.seller_id,
+            product_ids: value.product_ids,
+            rule: value.rule.into(),
+            start_after: value.start_after,
+            end_before: value.end_before,
+            max_usage: value.max_usage,
+            num_used: 0,
+        }
+    }
+}
+
+impl PromotionModel {
+    pub fn is_applicable(&self, store_id: u32, product_id: u64) -> bool {
+        (self.store_id == store_id)
+            && (self.product_ids.is_empty() || self.product_ids.contains(&product_id))
+    }
+
+    /// amount deducted from the unit price, in smallest unit of the seller's currency,
+    /// the result never exceeds the given unit price
+    pub fn unit_discount(&self, unit_price: u32, quantity: u32) -> u32 {
+        let amount = match &self.rule {
+            PromotionRuleModel::PercentOff(rate) => {
+                let v = (unit_price as u64) * (*rate as u64) / 100;
+                v as u32
+            }
+            PromotionRuleModel::FixedOff(amount) => *amount,
+            PromotionRuleModel::BuyXGetY { buy, free } => {
+                // value of the free items is spread over all items in the line, so
+                // the line total is still equal to unit price times quantity, the
+                // remainder of the division is dropped in favour of the seller
+                let grp_size = (*buy as u32) + (*free as u32);
+                let num_free = (quantity / grp_size) * (*free as u32);
+                let tot_free = (unit_price as u64) * (num_free as u64);
+                tot_free.checked_div(quantity as u64).unwrap_or(0) as u32
+            }
+        };
+        min(amount, unit_price)
+    }
+
+    fn check_redeemable(
+        &self,
+        time: DateTime<FixedOffset>,
+    ) -> DefaultResult<(), CouponErrorReason> {
+        if time < self.start_after || time >= self.end_before {
+            Err(CouponErrorReason::NotInPeriod)
+        } else if self.max_usage.is_some_and(|limit| self.num_used >= limit) {
+            Err(CouponErrorReason::UsageExhausted)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn validate_code(code: &str) -> bool {
+        !code.is_empty()
+            && code.len() <= MAX_CODE_LENGTH
+            && code
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    }
+} // end of impl PromotionModel
+
+impl PromotionModelSet {
+    pub fn validate(newdata: &[PromotionDto]) -> DefaultResult<(), Vec<PromotionClientErrorDto>> {
+        if newdata.is_empty() {
+            let ce = PromotionClientErrorDto {
+                code: String::new(),
+                reason: PromotionErrorReason::EmptyInput,
+            };
+            return Err(vec![ce]);
+        }
+        let mut codes_seen = HashSet::new();
+        let errors = newdata
+            .iter()
+            .filter_map(|d| {
+                let rule = PromotionRuleModel::from(d.rule.clone());
+                let reason = if !PromotionModel::validate_code(d.code.as_str()) {
+                    Some(PromotionErrorReason::InvalidCode)
+                } else if !codes_seen.insert(d.code.as_str()) {
+                    Some(PromotionErrorReason::DuplicateCode)
+                } else if !rule.is_valid() {
+                    Some(PromotionErrorReason::InvalidRule)
+                } else if d.start_after >= d.end_before {
+                    Some(PromotionErrorReason::InvalidPeriod)
+                } else {
+                    None
+                };
+                reason.map(|reason| PromotionClientErrorDto {
+                    code: d.code.clone(),
+                    reason,
+                })
+            })
+            .collect::<Vec<_>>();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    } // end of fn validate
+
+    // existing promotions are overwritten by the new data except the usage count,
+    // a code already taken by another store cannot be reused
+    pub fn update(
+        self,
+        newdata: Vec<PromotionDto>,
+    ) -> DefaultResult<Self, Vec<PromotionClientErrorDto>> {
+        let mut saved = self.promotions;
+        let errors = newdata
+            .iter()
+            .filter(|d| {
+                saved
+                    .iter()
+                    .any(|m| m.code == d.code && m.store_id != d.seller_id)
+            })
+            .map(|d| PromotionClientErrorDto {
+                code: d.code.clone(),
+                reason: PromotionErrorReason::CodeOccupied,
+            })
+            .collect::<Vec<_>>();
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+        let promotions = newdata
+            .into_iter()
+            .map(|d| {
+                let num_used = saved
+                    .iter()
+                    .position(|m| m.code == d.code)
+                    .map(|idx| saved.remove(idx).num_used)
+                    .unwrap_or(0);
+                let mut m = PromotionModel::from(d);
+                m.num_used = num_used;
+                m
+            })
+            .collect();
+        Ok(Self { promotions })
+    } // end of fn update
+
+    pub fn check_redeemable(
+        &self,
+        codes: &[String],
+        time: DateTime<FixedOffset>,
+    ) -> DefaultResult<(), Vec<CouponErrorDto>> {
+        let errors = codes
+            .iter()
+            .filter_map(|code| {
+                let result = self.promotions.iter().find(|m| &m.code == code);
+                let reason = if let Some(m) = result {
+                    m.check_redeemable(time).err()
+                } else {
+                    Some(CouponErrorReason::NotExist)
+                };
+                reason.map(|reason| CouponErrorDto {
+                    code: code.clone(),
+                    reason,
+                })
+            })
+            .collect::<Vec<_>>();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+} // end of impl PromotionModelSet
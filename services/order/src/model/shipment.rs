@@ -0,0 +1,233 @@
+use std::result::Result as DefaultResult;
+
+use chrono::{DateTime, FixedOffset};
+
+use crate::api::dto::ShippingMethod;
+use crate::api::web::dto::{
+    ShipmentCreateReqDto, ShipmentErrorDto, ShipmentErrorReason, ShipmentLineErrorDto,
+    ShipmentLineErrorReason, ShipmentLineReadDto, ShipmentReadDto, ShipmentStatusDto,
+    ShipmentUpdateReqDto,
+};
+use crate::constant::hard_limit;
+
+use super::{OrderLineIdentity, OrderLineModel};
+
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub enum ShipmentStatus {
+    Packed,
+    Shipped,
+    Delivered,
+}
+
+#[derive(Clone)]
+pub struct ShipmentLineModel {
+    pub id_: OrderLineIdentity,
+    pub qty: u32,
+}
+
+// a parcel sent by a seller, covering part or all of the paid items in the
+// order lines of the same seller
+#[derive(Clone)]
+pub struct ShipmentModel {
+    // sequence number of the shipment in an order
+    pub seq: u16,
+    pub seller_id: u32,
+    pub carrier: ShippingMethod,
+    pub tracking_number: Option<String>,
+    pub status: ShipmentStatus,
+    pub packed_time: DateTime<FixedOffset>,
+    pub shipped_time: Option<DateTime<FixedOffset>>,
+    pub delivered_time: Option<DateTime<FixedOffset>>,
+    pub lines: Vec<ShipmentLineModel>,
+}
+
+impl From<ShipmentStatusDto> for ShipmentStatus {
+    fn from(value: ShipmentStatusDto) -> Self {
+        match value {
+            ShipmentStatusDto::Packed => Self::Packed,
+            ShipmentStatusDto::Shipped => Self::Shipped,
+            ShipmentStatusDto::Delivered => Self::Delivered,
+        }
+    }
+}
+impl From<ShipmentStatus> for ShipmentStatusDto {
+    fn from(value: ShipmentStatus) -> Self {
+        match value {
+            ShipmentStatus::Packed => Self::Packed,
+            ShipmentStatus::Shipped => Self::Shipped,
+            ShipmentStatus::Delivered => Self::Delivered,
+        }
+    }
+}
+
+impl From<ShipmentModel> for ShipmentReadDto {
+    fn from(value: ShipmentModel) -> Self {
+        let lines = value
+            .lines
+            .into_iter()
+            .map(|l| ShipmentLineReadDto {
+                product_id: l.id_.product_id(),
+                attr_set_seq: l.id_.attrs_seq_num(),
+                quantity: l.qty,
+            })
+            .collect();
+        Self {
+            seq: value.seq,
+            seller_id: value.seller_id,
+            carrier: value.carrier,
+            tracking_number: value.tracking_number,
+            status: value.status.into(),
+            packed_time: value.packed_time,
+            shipped_time: value.shipped_time,
+            delivered_time: value.delivered_time,
+            lines,
+        }
+    }
+}
+
+impl ShipmentModel {
+    // all the shipments of an order are loaded, quantity of each new line
+    // cannot exceed the number of paid items which have not been shipped yet
+    pub fn try_create(
+        req: ShipmentCreateReqDto,
+        o_lines: &[OrderLineModel],
+        saved: &[Self],
+        time_now: DateTime<FixedOffset>,
+    ) -> DefaultResult<Self, ShipmentErrorDto> {
+        let ShipmentCreateReqDto {
+            seller_id,
+            carrier,
+            tracking_number,
+            lines,
+        } = req;
+        if saved.len() >= hard_limit::MAX_SHIPMENTS_PER_ORDER as usize {
+            return Err(Self::to_error(ShipmentErrorReason::ExceedingMaxLimit));
+        }
+        if lines.is_empty() {
+            return Err(Self::to_error(ShipmentErrorReason::EmptyLines));
+        }
+        if let Some(v) = tracking_number.as_ref() {
+            Self::validate_tracking_number(v.as_str())?;
+        }
+        let line_errors = lines
+            .iter()
+            .filter_map(|d| {
+                let id_combo = (seller_id, d.product_id, d.attr_set_seq);
+                let result = o_lines.iter().find(|ol| ol.id().compare_raw(id_combo));
+                let reason = if d.quantity == 0 {
+                    Some(ShipmentLineErrorReason::InvalidQuantity)
+                } else if let Some(oline) = result {
+                    // the same order line may appear more than once in a request
+                    let num_req = lines
+                        .iter()
+                        .filter(|d2| {
+                            (d2.product_id, d2.attr_set_seq) == (d.product_id, d.attr_set_seq)
+                        })
+                        .map(|d2| d2.quantity)
+                        .sum::<u32>();
+                    let num_shipped = Self::num_shipped(saved, oline.id());
+                    if num_shipped + num_req > oline.qty.paid {
+                        Some(ShipmentLineErrorReason::QtyLimitExceed)
+                    } else {
+                        None
+                    }
+                } else {
+                    Some(ShipmentLineErrorReason::NotExist)
+                };
+                reason.map(|reason| ShipmentLineErrorDto {
+                    product_id: d.product_id,
+                    attr_set_seq: d.attr_set_seq,
+                    reason,
+                })
+            })
+            .collect::<Vec<_>>();
+        if !line_errors.is_empty() {
+            return Err(ShipmentErrorDto {
+                reason: ShipmentErrorReason::InvalidLines,
+                lines: Some(line_errors),
+            });
+        }
+        let lines = lines
+            .into_iter()
+            .map(|d| ShipmentLineModel {
+                id_: OrderLineIdentity::from((seller_id, d.product_id, d.attr_set_seq)),
+                qty: d.quantity,
+            })
+            .collect();
+        let seq = saved.iter().map(|m| m.seq + 1).max().unwrap_or(0);
+        Ok(Self {
+            seq,
+            seller_id,
+            carrier,
+            tracking_number,
+            status: ShipmentStatus::Packed,
+            packed_time: time_now,
+            shipped_time: None,
+            delivered_time: None,
+            lines,
+        })
+    } // end of fn try_create
+
+    // status can only move forward, the shipment has to come with tracking
+    // number once it is handed over to the carrier
+    pub fn update(
+        &mut self,
+        req: ShipmentUpdateReqDto,
+        time_now: DateTime<FixedOffset>,
+    ) -> DefaultResult<(), ShipmentErrorDto> {
+        let new_status = ShipmentStatus::from(req.status);
+        if new_status <= self.status {
+            return Err(Self::to_error(ShipmentErrorReason::InvalidTransition));
+        }
+        if let Some(v) = req.tracking_number {
+            Self::validate_tracking_number(v.as_str())?;
+            self.tracking_number = Some(v);
+        }
+        if self.tracking_number.is_none() {
+            return Err(Self::to_error(ShipmentErrorReason::MissingTrackingNumber));
+        }
+        if self.shipped_time.is_none() {
+            self.shipped_time = Some(time_now);
+        }
+        if new_status == ShipmentStatus::Delivered {
+            self.delivered_time = Some(time_now);
+        }
+        self.status = new_status;
+        Ok(())
+    }
+
+    fn validate_tracking_number(value: &str) -> DefaultResult<(), ShipmentErrorDto> {
+        let valid = !value.is_empty()
+            && value.len() <= hard_limit::MAX_LEN_TRACKING_NUMBER
+            && value.chars().all(|c| c.is_ascii_alphanumeric() || c == '-');
+        if valid {
+            Ok(())
+        } else {
+            Err(Self::to_error(ShipmentErrorReason::InvalidTrackingNumber))
+        }
+    }
+
+    fn to_error(reason: ShipmentErrorReason) -> ShipmentErrorDto {
+        ShipmentErrorDto {
+            reason,
+            lines: None,
+        }
+    }
+
+    fn num_items(ms: &[Self], id_: &OrderLineIdentity, delivered_only: bool) -> u32 {
+        ms.iter()
+            .filter(|m| !delivered_only || m.status == ShipmentStatus::Delivered)
+            .flat_map(|m| m.lines.iter())
+            .filter(|l| &l.id_ == id_)
+            .map(|l| l.qty)
+            .sum()
+    }
+
+    pub fn num_shipped(ms: &[Self], id_: &OrderLineIdentity) -> u32 {
+        Self::num_items(ms, id_, false)
+    }
+
+    pub fn num_delivered(ms: &[Self], id_: &OrderLineIdentity) -> u32 {
+        Self::num_items(ms, id_, true)
+    }
+} // end of impl ShipmentModel
@@ -0,0 +1,179 @@
+use std::result::Result as DefaultResult;
+use std::vec::Vec;
+
+use ecommerce_common::api::dto::CountryCode;
+use ecommerce_common::model::order::PhyAddrModel;
+
+use crate::api::dto::ShippingMethod;
+use crate::api::web::dto::{
+    ShippingMethodClientErrorDto, ShippingMethodDto, ShippingMethodErrorReason, ShippingRateDto,
+};
+use crate::constant::hard_limit;
+
+// the internal constant should be consistent with database schema
+const MAX_REGION_LENGTH: usize = 32;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShippingRateModel {
+    pub country: CountryCode,
+    // the rate is applied to whole country if region is not specified
+    pub region: Option<String>,
+    // upper bound of total weight of a parcel, in grams
+    pub max_weight_gram: u32,
+    // in the seller's currency, same as the product price
+    pub fee: u32,
+}
+
+// delivery service supported by a seller, with the rate table
+#[derive(Debug, Clone)]
+pub struct ShippingMethodModel {
+    pub store_id: u32,
+    pub method: ShippingMethod,
+    pub rates: Vec<ShippingRateModel>,
+}
+
+pub struct ShippingMethodModelSet {
+    pub methods: Vec<ShippingMethodModel>,
+}
+
+impl From<ShippingRateDto> for ShippingRateModel {
+    fn from(value: ShippingRateDto) -> Self {
+        let ShippingRateDto {
+            country,
+            region,
+            max_weight_gram,
+            fee,
+        } = value;
+        let region = region.filter(|r| !r.is_empty());
+        Self {
+            country,
+            region,
+            max_weight_gram,
+            fee,
+        }
+    }
+}
+impl From<ShippingRateModel> for ShippingRateDto {
+    fn from(value: ShippingRateModel) -> Self {
+        Self {
+            country: value.country,
+            region: value.region,
+            max_weight_gram: value.max_weight_gram,
+            fee: value.fee,
+        }
+    }
+}
+
+impl ShippingRateModel {
+    fn is_applicable(&self, addr: &PhyAddrModel) -> bool {
+        let region_match = self.region.as_ref().is_none_or(|r| r == &addr.region);
+        region_match && (self.country == addr.country)
+    }
+}
+
+impl From<ShippingMethodModel> for ShippingMethodDto {
+    fn from(value: ShippingMethodModel) -> Self {
+        Self {
+            seller_id: value.store_id,
+            method: value.method,
+            rates: value.rates.into_iter().map(ShippingRateDto::from).collect(),
+        }
+    }
+}
+
+impl ShippingMethodModel {
+    // the rates specific to the region take precedence over the rates applied
+    // to whole country, then the lightest weight range covering the parcel
+    // is chosen. `None` means the method cannot deliver the parcel to the address
+    pub fn estimate_fee(&self, addr: &PhyAddrModel, weight_gram: u32) -> Option<u32> {
+        self.rates
+            .iter()
+            .filter(|r| r.is_applicable(addr) && r.max_weight_gram >= weight_gram)
+            .min_by_key(|r| (r.region.is_none(), r.max_weight_gram))
+            .map(|r| r.fee)
+    }
+
+    fn validate(data: &ShippingMethodDto) -> Option<ShippingMethodErrorReason> {
+        let mut rates_seen: Vec<(&CountryCode, &str, u32)> = Vec::new();
+        if data.seller_id == 0 {
+            Some(ShippingMethodErrorReason::InvalidSeller)
+        } else if data.method == ShippingMethod::Unknown {
+            Some(ShippingMethodErrorReason::InvalidMethod)
+        } else if data.rates.len() > hard_limit::MAX_NUM_SHIPPING_RATES {
+            Some(ShippingMethodErrorReason::TooManyRates)
+        } else {
+            data.rates.iter().find_map(|r| {
+                let region = r.region.as_deref().unwrap_or("");
+                let key = (&r.country, region, r.max_weight_gram);
+                if r.country == CountryCode::Unknown {
+                    Some(ShippingMethodErrorReason::InvalidCountry)
+                } else if region.len() > MAX_REGION_LENGTH {
+                    Some(ShippingMethodErrorReason::InvalidRegion)
+                } else if r.max_weight_gram == 0 {
+                    Some(ShippingMethodErrorReason::InvalidWeight)
+                } else if rates_seen.contains(&key) {
+                    Some(ShippingMethodErrorReason::DuplicateRate)
+                } else {
+                    rates_seen.push(key);
+                    None
+                }
+            })
+        }
+    } // end of fn validate
+} // end of impl ShippingMethodModel
+
+impl TryFrom<Vec<ShippingMethodDto>> for ShippingMethodModelSet {
+    type Error = Vec<ShippingMethodClientErrorDto>;
+    fn try_from(value: Vec<ShippingMethodDto>) -> DefaultResult<Self, Self::Error> {
+        if value.is_empty() {
+            let ce = ShippingMethodClientErrorDto {
+                seller_id: 0,
+                method: ShippingMethod::Unknown,
+                reason: ShippingMethodErrorReason::EmptyInput,
+            };
+            return Err(vec![ce]);
+        }
+        let mut methods_seen: Vec<(u32, &ShippingMethod)> = Vec::new();
+        let errors = value
+            .iter()
+            .filter_map(|d| {
+                let key = (d.seller_id, &d.method);
+                let reason = if methods_seen.contains(&key) {
+                    Some(ShippingMethodErrorReason::DuplicateMethod)
+                } else {
+                    methods_seen.push(key);
+                    ShippingMethodModel::validate(d)
+                };
+                reason.map(|reason| ShippingMethodClientErrorDto {
+                    seller_id: d.seller_id,
+                    method: d.method.clone(),
+                    reason,
+                })
+            })
+            .collect::<Vec<_>>();
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+        let methods = value
+            .into_iter()
+            .map(|d| ShippingMethodModel {
+                store_id: d.seller_id,
+                method: d.method,
+                rates: d.rates.into_iter().map(ShippingRateModel::from).collect(),
+            })
+            .collect();
+        Ok(Self { methods })
+    } // end of fn try_from
+} // end of impl ShippingMethodModelSet
+
+impl ShippingMethodModelSet {
+    pub fn find(&self, store_id: u32, method: &ShippingMethod) -> Option<&ShippingMethodModel> {
+        self.methods
+            .iter()
+            .find(|m| m.store_id == store_id && &m.method == method)
+    }
+
+    pub fn contains_store(&self, store_id: u32) -> bool {
+        self.methods.iter().any(|m| m.store_id == store_id)
+    }
+}
@@ -0,0 +1,143 @@
+use std::collections::HashMap;
+use std::result::Result as DefaultResult;
+use std::vec::Vec;
+
+use chrono::{DateTime, Duration, Utc};
+
+use ecommerce_common::error::AppErrorCode;
+
+use super::StoreStockModel;
+use crate::api::rpc::dto::{StockAlertDto, StockAlertKindDto, StockAlertThresholdDto};
+use crate::error::AppError;
+
+const MAX_EXPIRY_WITHIN_DAYS: u16 = 365; // same as `StockAlertThresholdDto` in AsyncAPI doc
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StockAlertThresholdModel {
+    pub store_id: u32,
+    // the alert is raised when total available quantity of a product, across
+    // all unexpired batches, drops below this number, zero means disabled
+    pub low_stock_qty: u32,
+    // the alert is raised when a batch with available items will expire within
+    // given number of days, zero means disabled
+    pub expiry_within_days: u16,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StockAlertKind {
+    LowStock,
+    ExpiringBatch,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StockAlertModel {
+    pub kind: StockAlertKind,
+    pub product_id: u64,
+    // only present in the alert for expiring batch
+    pub expiry: Option<DateTime<Utc>>,
+    pub num_avail: u32,
+}
+
+impl TryFrom<StockAlertThresholdDto> for StockAlertThresholdModel {
+    type Error = AppError;
+    fn try_from(value: StockAlertThresholdDto) -> DefaultResult<Self, Self::Error> {
+        let StockAlertThresholdDto {
+            store_id,
+            low_stock_qty,
+            expiry_within_days,
+        } = value;
+        if expiry_within_days > MAX_EXPIRY_WITHIN_DAYS {
+            return Err(AppError {
+                code: AppErrorCode::InvalidInput,
+                detail: Some(format!(
+                    "store:{store_id}, expiry-within-days:{expiry_within_days}"
+                )),
+            });
+        }
+        Ok(Self {
+            store_id,
+            low_stock_qty,
+            expiry_within_days,
+        })
+    }
+}
+
+impl StockAlertThresholdModel {
+    pub fn detect(&self, store: &StoreStockModel, time_now: DateTime<Utc>) -> Vec<StockAlertModel> {
+        let mut out = Vec::new();
+        let mut avail_per_product: HashMap<u64, u32> = HashMap::new();
+        let expiry_limit = time_now + Duration::days(self.expiry_within_days as i64);
+        store
+            .products
+            .iter()
+            .map(|p| {
+                // the product whose batches have all expired is still counted,
+                // with zero quantity available
+                let sum = avail_per_product.entry(p.id_).or_default();
+                if p.expiry <= time_now {
+                    return;
+                }
+                let num_avail = p.quantity.num_avail();
+                *sum += num_avail;
+                if self.expiry_within_days > 0 && num_avail > 0 && p.expiry <= expiry_limit {
+                    out.push(StockAlertModel {
+                        kind: StockAlertKind::ExpiringBatch,
+                        product_id: p.id_,
+                        expiry: Some(p.expiry),
+                        num_avail,
+                    });
+                }
+            })
+            .count();
+        if self.low_stock_qty > 0 {
+            let iter = avail_per_product
+                .into_iter()
+                .filter(|(_, num_avail)| *num_avail < self.low_stock_qty)
+                .map(|(product_id, num_avail)| StockAlertModel {
+                    kind: StockAlertKind::LowStock,
+                    product_id,
+                    expiry: None,
+                    num_avail,
+                });
+            out.extend(iter);
+        }
+        out.sort_by_key(|a| (a.product_id, a.kind as u8, a.expiry));
+        out
+    } // end of fn detect
+}
+
+impl StockAlertModel {
+    pub fn is_same(&self, other: &Self) -> bool {
+        self.kind == other.kind
+            && self.product_id == other.product_id
+            && self.expiry.map(|t| t.timestamp()) == other.expiry.map(|t| t.timestamp())
+    }
+
+    // split the detected alerts into the ones which have been sent previously,
+    // and the ones which are new and should be published
+    pub fn filter_sent(detected: Vec<Self>, sent: &[Self]) -> (Vec<Self>, Vec<Self>) {
+        detected
+            .into_iter()
+            .partition(|d| sent.iter().any(|s| s.is_same(d)))
+    }
+}
+
+impl From<StockAlertKind> for StockAlertKindDto {
+    fn from(value: StockAlertKind) -> Self {
+        match value {
+            StockAlertKind::LowStock => Self::LowStock,
+            StockAlertKind::ExpiringBatch => Self::ExpiringBatch,
+        }
+    }
+}
+
+impl From<StockAlertModel> for StockAlertDto {
+    fn from(value: StockAlertModel) -> Self {
+        Self {
+            kind: value.kind.into(),
+            product_id: value.product_id,
+            expiry: value.expiry.map(|t| t.fixed_offset()),
+            num_avail: value.num_avail,
+        }
+    }
+}
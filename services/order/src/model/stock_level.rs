@@ -2,18 +2,23 @@ use std::cmp::min;
 use std::result::Result as DefaultResult;
 use std::vec::Vec;
 
-use chrono::{DateTime, SubsecRound, Utc};
+use chrono::{DateTime, Duration, FixedOffset, SubsecRound, Utc};
 
+use ecommerce_common::api::dto::CountryCode;
 use ecommerce_common::error::AppErrorCode;
+use ecommerce_common::model::order::PhyAddrModel;
 use ecommerce_common::model::BaseProductIdentity;
 
 use crate::api::rpc::dto::{
-    InventoryEditStockLevelDto, StockLevelPresentDto, StockLevelReturnDto, StockQuantityPresentDto,
-    StockReturnErrorDto, StockReturnErrorReason,
+    InventoryEditStockLevelDto, StockAllocPolicyDto, StockAllocStrategyDto, StockLevelPresentDto,
+    StockLevelReturnDto, StockQuantityPresentDto, StockReturnErrorDto, StockReturnErrorReason,
+    StockWarehouseDto,
 };
 use crate::api::web::dto::{
     OrderLineCreateErrNonExistDto, OrderLineCreateErrorDto, OrderLineCreateErrorReason,
+    ProductStockReadDto, StockBatchReadDto, StockLevelReadRespDto,
 };
+use crate::constant::hard_limit;
 use crate::error::AppError;
 
 use super::{OrderLineModel, OrderLineModelSet};
@@ -21,6 +26,7 @@ use super::{OrderLineModel, OrderLineModelSet};
 pub struct ProductStockIdentity {
     pub store_id: u32,
     pub product_id: u64, // TODO, declare type alias
+    pub warehouse_id: u32,
     pub expiry: DateTime<Utc>,
 }
 pub type ProductStockIdentity2 = BaseProductIdentity; // TODO, rename
@@ -41,9 +47,15 @@ pub struct StockQuantityModel {
 #[derive(Debug)]
 pub struct ProductStockModel {
     pub id_: u64, // TODO, declare type alias
+    // each batch is kept in single warehouse of the store, zero means default
+    // warehouse
+    pub warehouse_id: u32,
     pub expiry: DateTime<Utc>,
     pub quantity: StockQuantityModel,
     pub is_create: bool,
+    // time the batch was saved for the first time, `None` means the batch is
+    // new and the repository will assign the time on save
+    pub arrival: Option<DateTime<Utc>>,
 }
 pub struct StoreStockModel {
     pub store_id: u32,
@@ -53,6 +65,44 @@ pub struct StockLevelModelSet {
     pub stores: Vec<StoreStockModel>,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StockAllocStrategy {
+    // first-expiry-first-out, batches which expire sooner are taken first
+    Fefo,
+    // first-in-first-out, batches which arrived earlier are taken first
+    Fifo,
+}
+#[derive(Debug, Clone, PartialEq)]
+pub struct StockAllocPolicyModel {
+    pub store_id: u32,
+    // `None` means default policy of the store, which applies to all the
+    // products without their own policy
+    pub product_id: Option<u64>,
+    pub strategy: StockAllocStrategy,
+    // batches which expire within the number of days are never allocated
+    // to new orders, zero means any unexpired batch can be taken
+    pub min_shelf_days: u16,
+}
+pub struct StockAllocPolicyModelSet {
+    pub policies: Vec<StockAllocPolicyModel>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StockWarehouseModel {
+    pub store_id: u32,
+    pub warehouse_id: u32,
+    pub country: CountryCode,
+    pub region: String,
+    pub city: String,
+}
+pub struct StockWarehouseModelSet {
+    pub warehouses: Vec<StockWarehouseModel>,
+}
+
+const MAX_MIN_SHELF_DAYS: u16 = 365; // same as `StockAllocPolicyDto` in AsyncAPI doc
+                                     // consistent with the columns in shipping address table
+const MAX_LOCATION_LENGTH: usize = 32;
+
 impl From<StockQuantityModel> for StockQuantityPresentDto {
     fn from(value: StockQuantityModel) -> StockQuantityPresentDto {
         StockQuantityPresentDto {
@@ -68,6 +118,7 @@ impl Clone for ProductStockIdentity {
         Self {
             store_id: self.store_id,
             product_id: self.product_id,
+            warehouse_id: self.warehouse_id,
             expiry: self.expiry,
         }
     }
@@ -94,9 +145,11 @@ impl Clone for ProductStockModel {
     fn clone(&self) -> Self {
         Self {
             id_: self.id_,
+            warehouse_id: self.warehouse_id,
             expiry: self.expiry,
             quantity: self.quantity.clone(),
             is_create: self.is_create,
+            arrival: self.arrival,
         }
     }
 }
@@ -132,6 +185,7 @@ impl PartialEq for StockQuantityModel {
 impl PartialEq for ProductStockModel {
     fn eq(&self, other: &Self) -> bool {
         self.id_ == other.id_
+            && self.warehouse_id == other.warehouse_id
             && self.quantity == other.quantity
             && self.expiry_without_millis() == other.expiry_without_millis()
     }
@@ -207,39 +261,108 @@ impl ProductStockModel {
 }
 
 impl StoreStockModel {
+    // batches of the product which can be allocated, in the order the policy
+    // specifies
+    fn alloc_candidates(
+        &mut self,
+        product_id: u64,
+        warehouse_id: u32,
+        policy: &StockAllocPolicyModel,
+        time_now: DateTime<Utc>,
+    ) -> Vec<&mut ProductStockModel> {
+        let min_expiry = time_now + Duration::days(policy.min_shelf_days as i64);
+        let mut candidates = self
+            .products
+            .iter_mut()
+            .filter(|p| product_id == p.id_ && warehouse_id == p.warehouse_id)
+            .filter(|p| policy.min_shelf_days == 0 || p.expiry > min_expiry)
+            .collect::<Vec<_>>();
+        match policy.strategy {
+            StockAllocStrategy::Fefo => candidates.sort_by_key(|p| p.expiry),
+            // batches which haven't been saved yet are taken last
+            StockAllocStrategy::Fifo => candidates
+                .sort_by_key(|p| (p.arrival.unwrap_or(DateTime::<Utc>::MAX_UTC), p.expiry)),
+        }
+        candidates
+    }
+
+    // warehouses which keep the product, in the order of the given rank, the
+    // warehouses missing in the rank come last
+    fn warehouses_by_rank(&self, product_id: u64, rank: &[u32]) -> Vec<u32> {
+        let mut out = self
+            .products
+            .iter()
+            .filter(|p| p.id_ == product_id)
+            .map(|p| p.warehouse_id)
+            .collect::<Vec<_>>();
+        out.sort_by_key(|w| (rank.iter().position(|r| r == w).unwrap_or(rank.len()), *w));
+        out.dedup();
+        out
+    }
+
+    // all items of an order line are reserved from single warehouse, the
+    // nearest one which has enough stock is chosen. On failure, the error
+    // comes with the shortage in the warehouse which has most items available
     pub fn try_reserve(
         &mut self,
         oid: &str,
         req: &OrderLineModel,
-    ) -> Option<(OrderLineCreateErrorReason, u32)> {
-        let mut num_required = req.qty.reserved;
-        let _satisfied = self
-            .products
-            .iter()
-            .filter(|p| req.id().product_id() == p.id_)
+        policy: &StockAllocPolicyModel,
+        time_now: DateTime<Utc>,
+    ) -> DefaultResult<u32, (OrderLineCreateErrorReason, u32, u32)> {
+        let (product_id, num_req) = (req.id().product_id(), req.qty.reserved);
+        let default_warehouse = req.warehouse_rank.first().copied().unwrap_or(0);
+        let mut most_avail = (default_warehouse, 0u32);
+        for warehouse_id in self.warehouses_by_rank(product_id, &req.warehouse_rank) {
+            let num_avail = self
+                .alloc_candidates(product_id, warehouse_id, policy, time_now)
+                .into_iter()
+                .map(|p| p.quantity.num_avail())
+                .sum::<u32>();
+            if num_avail >= num_req {
+                let num_taken = self.reserve_available(
+                    oid,
+                    product_id,
+                    warehouse_id,
+                    num_req,
+                    policy,
+                    time_now,
+                );
+                assert_eq!(num_taken, num_req);
+                return Ok(warehouse_id);
+            } else if num_avail > most_avail.1 {
+                most_avail = (warehouse_id, num_avail);
+            }
+        }
+        let (warehouse_id, num_avail) = most_avail;
+        let reason = if num_avail == 0 {
+            OrderLineCreateErrorReason::OutOfStock
+        } else {
+            OrderLineCreateErrorReason::NotEnoughToClaim
+        };
+        Err((reason, num_req - num_avail, warehouse_id))
+    }
+
+    // take as many items as possible up to the given number, return number of
+    // items actually reserved
+    pub fn reserve_available(
+        &mut self,
+        oid: &str,
+        product_id: u64,
+        warehouse_id: u32,
+        num_req: u32,
+        policy: &StockAllocPolicyModel,
+        time_now: DateTime<Utc>,
+    ) -> u32 {
+        let mut num_required = num_req;
+        let _ = self
+            .alloc_candidates(product_id, warehouse_id, policy, time_now)
+            .into_iter()
             .any(|p| {
-                let num_taking = min(p.quantity.num_avail(), num_required);
-                num_required -= num_taking;
+                num_required -= p.quantity.reserve(oid, num_required);
                 num_required == 0
-            }); // dry-run
-        if num_required == 0 {
-            assert!(_satisfied);
-            num_required = req.qty.reserved;
-            let _ = self
-                .products
-                .iter_mut()
-                .filter(|p| req.id().product_id() == p.id_)
-                .any(|p| {
-                    let num_taking = p.quantity.reserve(oid, num_required);
-                    num_required -= num_taking;
-                    num_required == 0
-                });
-            None
-        } else if num_required < req.qty.reserved {
-            Some((OrderLineCreateErrorReason::NotEnoughToClaim, num_required))
-        } else {
-            Some((OrderLineCreateErrorReason::OutOfStock, num_required))
-        }
+            });
+        num_req - num_required
     }
 
     pub fn return_across_expiry(
@@ -285,7 +408,9 @@ impl StoreStockModel {
     ) -> Option<StockReturnErrorReason> {
         assert!(req.qty_add > 0);
         let result = self.products.iter_mut().find(|p| {
-            p.id_ == req.product_id && p.expiry.trunc_subsecs(0) == req.expiry.trunc_subsecs(0)
+            p.id_ == req.product_id
+                && p.warehouse_id == req.warehouse_id
+                && p.expiry.trunc_subsecs(0) == req.expiry.trunc_subsecs(0)
         });
         if let Some(p) = result {
             if let Some(rsv) = &p.quantity.rsv_detail {
@@ -318,12 +443,182 @@ impl From<StockLevelModelSet> for Vec<StockLevelPresentDto> {
                     store_id,
                     product_id: p.id_,
                     expiry: p.expiry.fixed_offset(),
+                    warehouse_id: p.warehouse_id,
                 })
             })
             .collect()
     }
 }
 
+impl From<StoreStockModel> for StockLevelReadRespDto {
+    fn from(value: StoreStockModel) -> Self {
+        let StoreStockModel {
+            store_id,
+            mut products,
+        } = value;
+        products.sort_by(|a, b| {
+            a.id_
+                .cmp(&b.id_)
+                .then(a.warehouse_id.cmp(&b.warehouse_id))
+                .then(a.expiry.cmp(&b.expiry))
+        });
+        let mut out: Vec<ProductStockReadDto> = Vec::new();
+        for p in products {
+            let q = &p.quantity;
+            let batch = StockBatchReadDto {
+                warehouse_id: p.warehouse_id,
+                expiry: p.expiry.fixed_offset(),
+                total: q.total,
+                booked: q.booked,
+                cancelled: q.cancelled,
+                remaining: q.num_avail(),
+            };
+            let summary = match out.last_mut() {
+                Some(v) if v.product_id == p.id_ => v,
+                _others => {
+                    out.push(ProductStockReadDto {
+                        product_id: p.id_,
+                        total: 0,
+                        booked: 0,
+                        cancelled: 0,
+                        remaining: 0,
+                        batches: Vec::new(),
+                    });
+                    out.last_mut().unwrap()
+                }
+            };
+            summary.total += batch.total;
+            summary.booked += batch.booked;
+            summary.cancelled += batch.cancelled;
+            summary.remaining += batch.remaining;
+            summary.batches.push(batch);
+        }
+        Self {
+            store_id,
+            products: out,
+        }
+    }
+}
+
+impl From<StockAllocStrategyDto> for StockAllocStrategy {
+    fn from(value: StockAllocStrategyDto) -> Self {
+        match value {
+            StockAllocStrategyDto::FEFO => Self::Fefo,
+            StockAllocStrategyDto::FIFO => Self::Fifo,
+        }
+    }
+}
+
+impl TryFrom<StockAllocPolicyDto> for StockAllocPolicyModel {
+    type Error = AppError;
+    fn try_from(value: StockAllocPolicyDto) -> DefaultResult<Self, Self::Error> {
+        let StockAllocPolicyDto {
+            store_id,
+            product_id,
+            strategy,
+            min_shelf_days,
+        } = value;
+        if min_shelf_days > MAX_MIN_SHELF_DAYS {
+            return Err(AppError {
+                code: AppErrorCode::InvalidInput,
+                detail: Some(format!(
+                    "store:{store_id}, product:{product_id:?}, min-shelf-days:{min_shelf_days}"
+                )),
+            });
+        }
+        Ok(Self {
+            store_id,
+            product_id,
+            strategy: strategy.into(),
+            min_shelf_days,
+        })
+    }
+}
+
+impl StockAllocPolicyModelSet {
+    // product-specific policy takes precedence over default policy of the store,
+    // FEFO without shelf-life limit applies if neither of them is configured
+    pub fn resolve(&self, store_id: u32, product_id: u64) -> StockAllocPolicyModel {
+        let mut iter = self.policies.iter().filter(|p| p.store_id == store_id);
+        let found = iter
+            .clone()
+            .find(|p| p.product_id == Some(product_id))
+            .or_else(|| iter.find(|p| p.product_id.is_none()));
+        found.cloned().unwrap_or(StockAllocPolicyModel {
+            store_id,
+            product_id: None,
+            strategy: StockAllocStrategy::Fefo,
+            min_shelf_days: 0,
+        })
+    }
+}
+
+impl TryFrom<StockWarehouseDto> for StockWarehouseModel {
+    type Error = AppError;
+    fn try_from(value: StockWarehouseDto) -> DefaultResult<Self, Self::Error> {
+        let StockWarehouseDto {
+            store_id,
+            warehouse_id,
+            country,
+            region,
+            city,
+        } = value;
+        let detail = if country == CountryCode::Unknown {
+            Some("country".to_string())
+        } else if region.len() > MAX_LOCATION_LENGTH {
+            Some(format!("region-length:{}", region.len()))
+        } else if city.len() > MAX_LOCATION_LENGTH {
+            Some(format!("city-length:{}", city.len()))
+        } else {
+            None
+        };
+        if let Some(d) = detail {
+            return Err(AppError {
+                code: AppErrorCode::InvalidInput,
+                detail: Some(format!("store:{store_id}, warehouse:{warehouse_id}, {d}")),
+            });
+        }
+        Ok(Self {
+            store_id,
+            warehouse_id,
+            country,
+            region,
+            city,
+        })
+    }
+}
+
+impl StockWarehouseModel {
+    // the address is not geocoded, larger value simply means more levels of
+    // the location matched
+    fn proximity(&self, addr: &PhyAddrModel) -> u8 {
+        if self.country != addr.country {
+            0
+        } else if self.region != addr.region {
+            1
+        } else if self.city != addr.city {
+            2
+        } else {
+            3
+        }
+    }
+}
+
+impl StockWarehouseModelSet {
+    // warehouses of the store sorted by distance to the address, the nearest
+    // comes first
+    pub fn rank(&self, store_id: u32, addr: &PhyAddrModel) -> Vec<u32> {
+        let mut found = self
+            .warehouses
+            .iter()
+            .filter(|m| m.store_id == store_id)
+            .map(|m| (m.proximity(addr), m.warehouse_id))
+            .collect::<Vec<_>>();
+        found.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+        found.into_iter().map(|(_, w)| w).collect()
+    }
+}
+
 type InnerStoreStockReturnFn =
     fn(&mut StoreStockModel, InventoryEditStockLevelDto) -> Option<StockReturnErrorReason>;
 
@@ -347,7 +642,9 @@ impl StockLevelModelSet {
             }; // TODO,refactor
             let result = store_found.products.iter_mut().find(|m| {
                 let duration = m.expiry.fixed_offset() - d.expiry;
-                m.id_ == d.product_id && duration.num_seconds() == 0
+                m.id_ == d.product_id
+                    && m.warehouse_id == d.warehouse_id
+                    && duration.num_seconds() == 0
             });
             if let Some(_product_found) = result {
                 if d.qty_add >= 0 {
@@ -366,9 +663,11 @@ impl StockLevelModelSet {
                 if d.qty_add >= 0 {
                     let new_prod = ProductStockModel {
                         id_: d.product_id,
+                        warehouse_id: d.warehouse_id,
                         expiry: d.expiry.into(),
                         is_create: true,
                         quantity: StockQuantityModel::new(d.qty_add as u32, 0, 0, None),
+                        arrival: None,
                     };
                     store_found.products.push(new_prod);
                     false
@@ -381,9 +680,10 @@ impl StockLevelModelSet {
         if let Some(d) = err_caught {
             let msg = errmsg.unwrap_or("");
             let final_detail = format!(
-                "store:{}, product:{}, exp:{}, qty_add:{}, reason:{}",
+                "store:{}, product:{}, warehouse:{}, exp:{}, qty_add:{}, reason:{}",
                 d.store_id,
                 d.product_id,
+                d.warehouse_id,
                 d.expiry.to_rfc3339(),
                 d.qty_add,
                 msg
@@ -399,12 +699,22 @@ impl StockLevelModelSet {
 
     // If error happenes in the middle with some internal fields modified,
     // this model instance will be no longer clean and should be discarded immediately.
-    pub fn try_reserve(&mut self, ol_set: &OrderLineModelSet) -> Vec<OrderLineCreateErrorDto> {
+    pub fn try_reserve(
+        &mut self,
+        ol_set: &mut OrderLineModelSet,
+        policies: &StockAllocPolicyModelSet,
+    ) -> Vec<OrderLineCreateErrorDto> {
         self.sort_by_expiry(true);
-        let oid = ol_set.id().as_str();
+        let oid = ol_set.id().clone();
+        let oid = oid.as_str();
+        // remaining shelf life is estimated from the time the order was created
+        let time_now = ol_set.create_time().to_utc();
+        // backordered lines are kept until the wait period expires, the items
+        // already allocated are returned by the job discarding unpaid lines
+        let backorder_wait = Duration::days(hard_limit::MAX_DAYS_BACKORDER_WAIT);
         ol_set
-            .lines()
-            .iter()
+            .lines_mut()
+            .iter_mut()
             .filter_map(|req| {
                 let mut error = OrderLineCreateErrorDto {
                     seller_id: req.id().store_id(),
@@ -420,12 +730,42 @@ impl StockLevelModelSet {
                     .iter_mut()
                     .find(|m| req.id().store_id() == m.store_id);
                 let opt_err = if let Some(store) = result {
-                    if let Some((errtype, num)) = store.try_reserve(oid, req) {
-                        error.shortage = Some(num);
-                        Some(errtype)
-                    } else {
-                        None
+                    let policy = policies.resolve(store.store_id, req.id().product_id());
+                    match store.try_reserve(oid, req, &policy, time_now) {
+                        Ok(warehouse_id) => {
+                            req.warehouse_id = Some(warehouse_id);
+                            None
+                        }
+                        // the shortage is accepted as backorder, the rest of
+                        // the order line is taken from current stock of the
+                        // same warehouse
+                        Err((_errtype, num, warehouse_id)) if num <= req.backorder_limit => {
+                            let (pid, num_avail) = (req.id().product_id(), req.qty.reserved - num);
+                            let num_taken = store.reserve_available(
+                                oid,
+                                pid,
+                                warehouse_id,
+                                num_avail,
+                                &policy,
+                                time_now,
+                            );
+                            req.qty.backordered = req.qty.reserved - num_taken;
+                            req.warehouse_id = Some(warehouse_id);
+                            req.policy.reserved_until += backorder_wait;
+                            None
+                        }
+                        Err((errtype, num, _warehouse_id)) => {
+                            error.shortage = Some(num);
+                            Some(errtype)
+                        }
                     }
+                } else if req.qty.reserved <= req.backorder_limit {
+                    // pre-order from the seller who doesn't have any stock yet,
+                    // the items will be shipped from the nearest warehouse
+                    req.qty.backordered = req.qty.reserved;
+                    req.warehouse_id = Some(req.warehouse_rank.first().copied().unwrap_or(0));
+                    req.policy.reserved_until += backorder_wait;
+                    None
                 } else {
                     error.nonexist = Some(OrderLineCreateErrNonExistDto {
                         product_policy: false,
@@ -444,6 +784,54 @@ impl StockLevelModelSet {
             .collect()
     } // end of try_reserve
 
+    // allocate new stock to the backordered lines of an order, return total
+    // number of items allocated
+    pub fn fulfil_backorder(
+        &mut self,
+        ol_set: &mut OrderLineModelSet,
+        policies: &StockAllocPolicyModelSet,
+        time_now: DateTime<FixedOffset>,
+    ) -> u32 {
+        let oid = ol_set.id().clone();
+        let ctime = ol_set.create_time();
+        ol_set
+            .lines_mut()
+            .iter_mut()
+            .filter(|line| line.cancelled.is_none() && line.qty.is_backordered())
+            .filter(|line| time_now < line.policy.reserved_until)
+            .map(|line| {
+                let (store_id, product_id) = (line.id().store_id(), line.id().product_id());
+                let Some(store) = self.stores.iter_mut().find(|m| m.store_id == store_id) else {
+                    return 0;
+                };
+                let policy = policies.resolve(store_id, product_id);
+                // new stock is taken only from the warehouse recorded in the
+                // line, so all items of the line are shipped together
+                let warehouse_id = line.warehouse_id.unwrap_or(0);
+                let num_taken = store.reserve_available(
+                    oid.as_str(),
+                    product_id,
+                    warehouse_id,
+                    line.qty.backordered,
+                    &policy,
+                    time_now.to_utc(),
+                );
+                line.qty.backordered -= num_taken;
+                if num_taken > 0 {
+                    line.warehouse_id = Some(warehouse_id);
+                }
+                if num_taken > 0 && !line.qty.is_backordered() {
+                    // buyer has the same period of time to pay for the line,
+                    // starting from the moment all items are allocated
+                    let backorder_wait = Duration::days(hard_limit::MAX_DAYS_BACKORDER_WAIT);
+                    let period = line.policy.reserved_until - ctime - backorder_wait;
+                    line.policy.reserved_until = time_now + period;
+                }
+                num_taken
+            })
+            .sum()
+    } // end of fn fulfil_backorder
+
     fn return_common(
         &mut self,
         data: StockLevelReturnDto,
@@ -0,0 +1,121 @@
+use std::result::Result as DefaultResult;
+use std::str::FromStr;
+use std::vec::Vec;
+
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::{Decimal, RoundingStrategy};
+
+use ecommerce_common::api::dto::CountryCode;
+use ecommerce_common::error::AppErrorCode;
+use ecommerce_common::model::order::PhyAddrModel;
+
+use crate::api::rpc::dto::TaxRateDto;
+use crate::error::AppError;
+
+// the 2 internal constants should be consistent with database schema
+const PRECISION_FRACTIONAL: u32 = 4;
+const MAX_REGION_LENGTH: usize = 32;
+
+#[derive(Debug, Clone)]
+pub struct TaxRateModel {
+    pub country: CountryCode,
+    // the rate is applied to whole country if region is not specified
+    pub region: Option<String>,
+    // the rate is applied to all stores if store ID is not specified
+    pub store_id: Option<u32>,
+    // fraction of the price, e.g. 0.05 means 5%
+    pub rate: Decimal,
+}
+
+pub struct TaxRateModelSet {
+    pub rates: Vec<TaxRateModel>,
+}
+
+impl TryFrom<TaxRateDto> for TaxRateModel {
+    type Error = AppError;
+    fn try_from(value: TaxRateDto) -> DefaultResult<Self, Self::Error> {
+        let TaxRateDto {
+            country,
+            region,
+            store_id,
+            rate,
+        } = value;
+        let detail = if country == CountryCode::Unknown {
+            Some("country".to_string())
+        } else if region.as_ref().is_some_and(|r| r.len() > MAX_REGION_LENGTH) {
+            Some(format!("region-length:{}", region.as_ref().unwrap().len()))
+        } else {
+            None
+        };
+        if let Some(d) = detail {
+            return Err(AppError {
+                code: AppErrorCode::InvalidInput,
+                detail: Some(d),
+            });
+        }
+        let rate = Decimal::from_str(rate.as_str()).map_err(|e| AppError {
+            code: AppErrorCode::InvalidInput,
+            detail: Some(format!("rate:{rate}, {e}")),
+        })?;
+        if rate.is_sign_negative() || rate >= Decimal::ONE || rate.scale() > PRECISION_FRACTIONAL {
+            return Err(AppError {
+                code: AppErrorCode::InvalidInput,
+                detail: Some(format!("rate-range:{rate}")),
+            });
+        }
+        let region = region.filter(|r| !r.is_empty());
+        let store_id = store_id.filter(|v| *v != 0);
+        Ok(Self {
+            country,
+            region,
+            store_id,
+            rate,
+        })
+    }
+} // end of impl TaxRateModel
+
+impl TaxRateModel {
+    // tax amount of single unit, in smallest unit of the seller's currency
+    pub fn unit_tax(&self, unit_price: u32) -> u32 {
+        let amount = Decimal::from(unit_price) * self.rate;
+        amount
+            .round_dp_with_strategy(0, RoundingStrategy::MidpointAwayFromZero)
+            .to_u32()
+            .unwrap_or(0)
+    }
+
+    fn is_applicable(&self, store_id: u32, addr: &PhyAddrModel) -> bool {
+        let store_match = self.store_id.is_none_or(|v| v == store_id);
+        let region_match = self.region.as_ref().is_none_or(|r| r == &addr.region);
+        store_match && region_match && (self.country == addr.country)
+    }
+}
+
+impl TryFrom<Vec<TaxRateDto>> for TaxRateModelSet {
+    type Error = AppError;
+    fn try_from(value: Vec<TaxRateDto>) -> DefaultResult<Self, Self::Error> {
+        if value.is_empty() {
+            return Err(AppError {
+                code: AppErrorCode::EmptyInputData,
+                detail: Some("tax-rate".to_string()),
+            });
+        }
+        let rates = value
+            .into_iter()
+            .map(TaxRateModel::try_from)
+            .collect::<DefaultResult<Vec<_>, AppError>>()?;
+        Ok(Self { rates })
+    }
+}
+
+impl TaxRateModelSet {
+    // the rate specific to the store takes precedence over the rate applied to
+    // all stores, then the rate specific to the region takes precedence over
+    // the rate applied to whole country
+    pub fn find(&self, store_id: u32, addr: &PhyAddrModel) -> Option<&TaxRateModel> {
+        self.rates
+            .iter()
+            .filter(|m| m.is_applicable(store_id, addr))
+            .max_by_key(|m| (m.store_id.is_some(), m.region.is_some()))
+    }
+}
@@ -4,6 +4,7 @@ use std::result::Result as DefaultResult;
 use std::sync::Arc;
 
 use async_trait::async_trait;
+use chrono::{DateTime, FixedOffset};
 
 use ecommerce_common::model::BaseProductIdentity;
 
@@ -31,7 +32,18 @@ mod CartTable {
     impl From<UpdateArg<'_>> for AppInMemFetchedSingleRow {
         fn from(value: UpdateArg<'_>) -> AppInMemFetchedSingleRow {
             let obj = value.0;
-            vec![obj.title.clone()]
+            let collaborators = obj
+                .collaborators
+                .iter()
+                .map(|id_| id_.to_string())
+                .collect::<Vec<_>>()
+                .join(",");
+            vec![
+                obj.title.clone(),
+                obj.sharable.to_string(),
+                collaborators,
+                obj.owner_quota_lines.to_string(),
+            ]
         }
     }
     impl From<UpdateArg<'_>> for AppInMemFetchedSingleTable {
@@ -42,9 +54,22 @@ mod CartTable {
     }
 } // end of inner-mod CartTable
 
+#[allow(non_snake_case)]
+mod CartCheckoutClaimTable {
+    #[allow(clippy::redundant_static_lifetimes)]
+    pub(super) const LABEL: &'static str = "cart_checkout_claim";
+
+    pub(super) fn pkey(usr_id: u32, seq: u8) -> String {
+        format!("{usr_id}-{seq}")
+    }
+} // end of inner-mod CartCheckoutClaimTable
+
 #[allow(non_snake_case)]
 mod CartLineTable {
-    use super::{AppInMemFetchedSingleTable, BaseProductIdentity, CartModel, HashMap};
+    use super::{
+        AppError, AppInMemFetchedSingleTable, BaseProductIdentity, CartModel, DefaultResult,
+        HashMap,
+    };
 
     #[allow(clippy::redundant_static_lifetimes)]
     pub(super) const LABEL: &'static str = "cart_line";
@@ -55,8 +80,9 @@ mod CartLineTable {
         format!("{}-{}-{}-{}", usr_id, seq, id_.store_id, id_.product_id)
     }
 
-    impl From<UpdateArg> for AppInMemFetchedSingleTable {
-        fn from(value: UpdateArg) -> AppInMemFetchedSingleTable {
+    impl TryFrom<UpdateArg> for AppInMemFetchedSingleTable {
+        type Error = AppError;
+        fn try_from(value: UpdateArg) -> DefaultResult<AppInMemFetchedSingleTable, AppError> {
             let (usr_id, seq, mut saved_lines, new_lines) = (
                 value.0.owner,
                 value.0.seq_num,
@@ -64,13 +90,16 @@ mod CartLineTable {
                 value.0.new_lines,
             );
             saved_lines.extend(new_lines);
-            let iter0 = saved_lines.into_iter().map(|line| {
+            let mut out = HashMap::new();
+            for line in saved_lines {
+                // empty string indicates no attribute chosen in the line
+                let attr = line.serialize_attr()?.unwrap_or_default();
                 let (id_, qty) = (line.id_, line.qty_req);
                 let key = pkey(usr_id, seq, id_);
-                let row = vec![qty.to_string()];
-                (key, row)
-            });
-            HashMap::from_iter(iter0)
+                let row = vec![qty.to_string(), attr];
+                out.insert(key, row);
+            }
+            Ok(out)
         }
     }
 } // end of inner-mod CartLineTable
@@ -117,11 +146,18 @@ impl TryFrom<(String, Vec<String>)> for CartLineModel {
             tokens.next().unwrap().parse().unwrap(),
         );
         let qty_req = row.remove(0).parse().unwrap();
+        let attr_serial = row.remove(0);
+        let chosen_attr = if attr_serial.is_empty() {
+            None
+        } else {
+            Some(CartLineModel::deserialize_attr(attr_serial.as_str())?)
+        };
         let out = CartLineModel {
             id_: BaseProductIdentity {
                 store_id,
                 product_id,
             },
+            chosen_attr,
             qty_req,
         };
         Ok(out)
@@ -136,12 +172,24 @@ impl From<(String, Vec<String>, Vec<CartLineModel>)> for CartModel {
             tokens.next().unwrap().parse().unwrap(),
             tokens.next().unwrap().parse().unwrap(),
         );
+        let title = row.remove(0);
+        let sharable = row.remove(0).parse().unwrap();
+        let collaborators = row
+            .remove(0)
+            .split(',')
+            .filter(|s| !s.is_empty())
+            .map(|s| s.parse().unwrap())
+            .collect();
+        let owner_quota_lines = row.remove(0).parse().unwrap();
         CartModel {
             owner,
             seq_num,
-            title: row.remove(0),
+            title,
             saved_lines,
             new_lines: Vec::new(),
+            sharable,
+            collaborators,
+            owner_quota_lines,
         }
     }
 }
@@ -154,7 +202,7 @@ pub struct CartInMemRepo {
 impl AbsCartRepo for CartInMemRepo {
     async fn update(&self, obj: CartModel) -> DefaultResult<usize, AppError> {
         let rows0 = CartTable::UpdateArg(&obj).into();
-        let rows1 = CartLineTable::UpdateArg(obj).into();
+        let rows1 = CartLineTable::UpdateArg(obj).try_into()?;
         let data = HashMap::from([
             (CartTable::LABEL.to_string(), rows0),
             (CartLineTable::LABEL.to_string(), rows1),
@@ -188,12 +236,68 @@ impl AbsCartRepo for CartInMemRepo {
         let info = self.filter_keys(owner, seq, Some(pids)).await?;
         self.fetch_common(owner, seq, info).await
     }
+
+    async fn update_collaborators(&self, obj: &CartModel) -> DefaultResult<(), AppError> {
+        let rows = CartTable::UpdateArg(obj).into();
+        let data = HashMap::from([(CartTable::LABEL.to_string(), rows)]);
+        let _num_saved = self.datastore.save(data).await?;
+        Ok(())
+    }
+
+    async fn discard_lines(
+        &self,
+        owner: u32,
+        seq: u8,
+        pids: Vec<BaseProductIdentity>,
+    ) -> DefaultResult<usize, AppError> {
+        let mut info = self.filter_keys(owner, seq, Some(pids)).await?;
+        // top-level metadata of the cart is kept
+        let _ = info.remove(CartTable::LABEL);
+        let num_deleted = self.datastore.delete(info).await?;
+        Ok(num_deleted)
+    }
+
+    async fn try_claim_checkout(
+        &self,
+        owner: u32,
+        seq: u8,
+        time_now: DateTime<FixedOffset>,
+        expiry: DateTime<FixedOffset>,
+    ) -> DefaultResult<bool, AppError> {
+        let label = CartCheckoutClaimTable::LABEL.to_string();
+        let key = CartCheckoutClaimTable::pkey(owner, seq);
+        let info = HashMap::from([(label.clone(), vec![key.clone()])]);
+        let (mut saved, lock) = self.datastore.fetch_acquire(info).await?;
+        let claimed = saved
+            .remove(&label)
+            .and_then(|mut t| t.remove(&key))
+            .and_then(|row| DateTime::parse_from_rfc3339(row[0].as_str()).ok())
+            .map(|t| time_now < t)
+            .unwrap_or(false);
+        if claimed {
+            Ok(false)
+        } else {
+            let row = vec![expiry.to_rfc3339()];
+            let data = HashMap::from([(label, HashMap::from([(key, row)]))]);
+            let _num_saved = self.datastore.save_release(data, lock)?;
+            Ok(true)
+        }
+    }
+
+    async fn release_checkout(&self, owner: u32, seq: u8) -> DefaultResult<(), AppError> {
+        let label = CartCheckoutClaimTable::LABEL.to_string();
+        let key = CartCheckoutClaimTable::pkey(owner, seq);
+        let info = HashMap::from([(label, vec![key])]);
+        let _num_deleted = self.datastore.delete(info).await?;
+        Ok(())
+    }
 } // end of impl AbsCartRepo for CartInMemRepo
 
 impl CartInMemRepo {
     pub async fn new(m: Arc<Box<dyn AbstInMemoryDStore>>) -> DefaultResult<Self, AppError> {
         m.create_table(CartTable::LABEL).await?;
         m.create_table(CartLineTable::LABEL).await?;
+        m.create_table(CartCheckoutClaimTable::LABEL).await?;
         Ok(Self { datastore: m })
     }
 
@@ -253,6 +357,9 @@ impl CartInMemRepo {
                     title: "Untitled".to_string(),
                     new_lines: Vec::new(),
                     saved_lines: Vec::new(),
+                    sharable: false,
+                    collaborators: Vec::new(),
+                    owner_quota_lines: 0,
                 }
             };
             Ok(obj)
@@ -1,20 +1,57 @@
 use std::boxed::Box;
+use std::cmp::Reverse;
 use std::collections::HashMap;
 use std::result::Result;
 use std::sync::Arc;
 
 use async_trait::async_trait;
+use chrono::{DateTime, FixedOffset, Local as LocalTime, SecondsFormat};
 use rust_decimal::Decimal;
 
 use ecommerce_common::api::dto::CurrencyDto;
 use ecommerce_common::error::AppErrorCode;
 
-use crate::datastore::{AbstInMemoryDStore, AppInMemFetchKeys, AppInMemFetchedSingleTable};
+use crate::datastore::{
+    AbsDStoreFilterKeyOp, AbstInMemoryDStore, AppInMemFetchKeys, AppInMemFetchedSingleRow,
+    AppInMemFetchedSingleTable,
+};
 use crate::error::AppError;
-use crate::model::{CurrencyModel, CurrencyModelSet};
+use crate::model::{CurrencyModel, CurrencyModelSet, CurrencyRateHistoryModel};
 use crate::repository::AbsCurrencyRepo;
 
 const TABLE_LABEL: &str = "currency_exchange";
+// each row in the history table stores all the rates saved at the same time,
+// the key is the saving time
+const HIST_TABLE_LABEL: &str = "currency_exchange_history";
+
+enum HistInMemColIdx {
+    Provider,
+    Rates,
+    TotNumColumns,
+}
+
+impl From<HistInMemColIdx> for usize {
+    fn from(value: HistInMemColIdx) -> usize {
+        match value {
+            HistInMemColIdx::Provider => 0,
+            HistInMemColIdx::Rates => 1,
+            HistInMemColIdx::TotNumColumns => 2,
+        }
+    }
+}
+
+struct HistTimeRangeFilter {
+    start: Option<DateTime<FixedOffset>>,
+    end: DateTime<FixedOffset>,
+}
+
+impl AbsDStoreFilterKeyOp for HistTimeRangeFilter {
+    fn filter(&self, k: &String, _v: &Vec<String>) -> bool {
+        DateTime::parse_from_rfc3339(k.as_str())
+            .map(|t| self.start.is_none_or(|s| s < t) && t <= self.end)
+            .unwrap_or(false)
+    }
+}
 
 struct FetchArgs(AppInMemFetchKeys);
 struct UpdateArgs(AppInMemFetchedSingleTable);
@@ -58,6 +95,68 @@ impl From<Vec<CurrencyDto>> for FetchArgs {
     }
 }
 
+fn hist_inmem_to_row(ms: &CurrencyModelSet) -> AppInMemFetchedSingleRow {
+    let rates = ms
+        .exchange_rates
+        .iter()
+        .map(|m| format!("{} {}", m.name.to_string(), m.rate))
+        .collect::<Vec<_>>()
+        .join(",");
+    let mut row = (0..HistInMemColIdx::TotNumColumns.into())
+        .map(|_n| String::new())
+        .collect::<Vec<String>>();
+    let _ = [
+        (
+            HistInMemColIdx::Provider,
+            ms.provider.clone().unwrap_or_default(),
+        ),
+        (HistInMemColIdx::Rates, rates),
+    ]
+    .into_iter()
+    .map(|(idx, val)| {
+        let idx: usize = idx.into();
+        row[idx] = val;
+    })
+    .count();
+    row
+}
+
+fn hist_inmem_from_row(
+    key: String,
+    row: AppInMemFetchedSingleRow,
+) -> Result<Vec<CurrencyRateHistoryModel>, AppError> {
+    let corrupted = |detail: String| AppError {
+        code: AppErrorCode::DataCorruption,
+        detail: Some(detail),
+    };
+    let time = DateTime::parse_from_rfc3339(key.as_str())
+        .map_err(|e| corrupted(format!("currency-history-time: {key}, {e}")))?;
+    let provider = row
+        .get::<usize>(HistInMemColIdx::Provider.into())
+        .filter(|p| !p.is_empty())
+        .cloned();
+    let rates_raw = row
+        .get::<usize>(HistInMemColIdx::Rates.into())
+        .ok_or(corrupted(format!("currency-history-rates: {key}")))?;
+    rates_raw
+        .split(',')
+        .filter(|tkn| !tkn.is_empty())
+        .map(|tkn| {
+            let (label, rate) = tkn
+                .split_once(' ')
+                .ok_or(corrupted(format!("currency-history-rates: {tkn}")))?;
+            let rate = Decimal::from_str_radix(rate, 10)
+                .map_err(|e| corrupted(format!("currency-history-rates: {tkn}, {e}")))?;
+            Ok(CurrencyRateHistoryModel {
+                name: CurrencyDto::from(&label.to_string()),
+                rate,
+                provider: provider.clone(),
+                time,
+            })
+        })
+        .collect()
+}
+
 impl TryFrom<AppInMemFetchedSingleTable> for CurrencyModelSet {
     type Error = AppError;
     fn try_from(value: AppInMemFetchedSingleTable) -> Result<Self, Self::Error> {
@@ -88,10 +187,11 @@ impl TryFrom<AppInMemFetchedSingleTable> for CurrencyModelSet {
             .map(|(name, rate)| CurrencyModel { name, rate })
             .collect();
         if errs_detail.is_empty() {
-            // Note the base currency is always USD in this project
+            // Note the saved rates are always rebased to USD in this project
             Ok(CurrencyModelSet {
                 base: CurrencyDto::USD,
                 exchange_rates,
+                provider: None,
             })
         } else {
             Err(AppError {
@@ -115,16 +215,87 @@ impl AbsCurrencyRepo for CurrencyInMemRepo {
     }
 
     async fn save(&self, ms: CurrencyModelSet) -> Result<(), AppError> {
+        let hist_key = LocalTime::now()
+            .fixed_offset()
+            .to_rfc3339_opts(SecondsFormat::Micros, true);
+        let hist_row = hist_inmem_to_row(&ms);
         let rows = UpdateArgs::try_from(ms)?.0;
-        let data = HashMap::from([(TABLE_LABEL.to_string(), rows)]);
+        let data = HashMap::from([
+            (TABLE_LABEL.to_string(), rows),
+            (
+                HIST_TABLE_LABEL.to_string(),
+                HashMap::from([(hist_key, hist_row)]),
+            ),
+        ]);
         let _num_saved = self.dstore.save(data).await?;
         Ok(())
     }
+
+    async fn fetch_as_of(
+        &self,
+        chosen: Vec<CurrencyDto>,
+        time: DateTime<FixedOffset>,
+    ) -> Result<CurrencyModelSet, AppError> {
+        let mut records = self.fetch_hist_common(None, time).await?;
+        // the latest record of each currency is chosen
+        records.sort_by_key(|r| Reverse(r.time));
+        let mut exchange_rates: Vec<CurrencyModel> = Vec::new();
+        records
+            .into_iter()
+            .filter(|r| chosen.contains(&r.name))
+            .map(|r| {
+                if exchange_rates.iter().all(|m| m.name != r.name) {
+                    exchange_rates.push(CurrencyModel {
+                        name: r.name,
+                        rate: r.rate,
+                    });
+                }
+            })
+            .count();
+        Ok(CurrencyModelSet {
+            base: CurrencyDto::USD,
+            exchange_rates,
+            provider: None,
+        })
+    }
+
+    async fn fetch_history(
+        &self,
+        chosen: Vec<CurrencyDto>,
+        start: DateTime<FixedOffset>,
+        end: DateTime<FixedOffset>,
+    ) -> Result<Vec<CurrencyRateHistoryModel>, AppError> {
+        let mut records = self.fetch_hist_common(Some(start), end).await?;
+        records.retain(|r| chosen.contains(&r.name));
+        records.sort_by_key(|r| r.time);
+        Ok(records)
+    }
 } // end of impl CurrencyInMemRepo
 
 impl CurrencyInMemRepo {
     pub async fn new(dstore: Arc<Box<dyn AbstInMemoryDStore>>) -> Result<Self, AppError> {
         dstore.create_table(TABLE_LABEL).await?;
+        dstore.create_table(HIST_TABLE_LABEL).await?;
         Ok(Self { dstore })
     }
+
+    async fn fetch_hist_common(
+        &self,
+        start: Option<DateTime<FixedOffset>>,
+        end: DateTime<FixedOffset>,
+    ) -> Result<Vec<CurrencyRateHistoryModel>, AppError> {
+        let op = HistTimeRangeFilter { start, end };
+        let keys = self
+            .dstore
+            .filter_keys(HIST_TABLE_LABEL.to_string(), &op)
+            .await?;
+        let info = HashMap::from([(HIST_TABLE_LABEL.to_string(), keys)]);
+        let mut resultset = self.dstore.fetch(info).await?;
+        let rows = resultset.remove(HIST_TABLE_LABEL).unwrap_or_default();
+        let nested = rows
+            .into_iter()
+            .map(|(key, row)| hist_inmem_from_row(key, row))
+            .collect::<Result<Vec<_>, AppError>>()?;
+        Ok(nested.into_iter().flatten().collect())
+    }
 } // end of impl CurrencyInMemRepo
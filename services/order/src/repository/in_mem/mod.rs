@@ -4,6 +4,11 @@ pub(super) mod oline_return;
 pub(super) mod order;
 pub(super) mod product_policy;
 pub(super) mod product_price;
+pub(super) mod promotion;
+pub(super) mod shipment;
+pub(super) mod shipping_method;
+pub(super) mod stock_alert;
 pub(super) mod stock_level;
+pub(super) mod tax;
 
 use self::stock_level::StockLvlInMemRepo;
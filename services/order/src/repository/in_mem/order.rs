@@ -15,16 +15,21 @@ use ecommerce_common::error::AppErrorCode;
 use ecommerce_common::model::order::{BillingModel, ContactModel, PhyAddrModel};
 
 use crate::api::dto::ShippingMethod;
-use crate::datastore::{AbstInMemoryDStore, AppInMemFetchedSingleRow, AppInMemFetchedSingleTable};
+use crate::api::web::dto::OrderLineCancelErrorDto;
+use crate::datastore::{
+    AbstInMemoryDStore, AppInMemFetchedData, AppInMemFetchedSingleRow, AppInMemFetchedSingleTable,
+};
 use crate::error::AppError;
 use crate::model::{
-    CurrencyModel, OrderCurrencyModel, OrderLineAppliedPolicyModel, OrderLineIdentity,
-    OrderLineModel, OrderLineModelSet, OrderLinePriceModel, OrderLineQuantityModel,
-    ProdAttriPriceModel, ShippingModel, ShippingOptionModel,
+    CurrencyModel, OrderCurrencyModel, OrderLineAppliedPolicyModel, OrderLineCancelModel,
+    OrderLineDiscountModel, OrderLineIdentity, OrderLineModel, OrderLineModelSet,
+    OrderLinePriceModel, OrderLineQuantityModel, OrderLineTaxModel, ProdAttriPriceModel,
+    ShippingModel, ShippingOptionModel,
 };
 
 use super::super::{
-    AbsOrderRepo, AbsOrderStockRepo, AppOrderFetchRangeCallback, AppOrderRepoUpdateLinesUserFunc,
+    AbsOrderRepo, AbsOrderStockRepo, AppOrderFetchRangeCallback, AppOrderRepoCancelLinesUserFunc,
+    AppOrderRepoUpdateLinesUserFunc,
 };
 use super::StockLvlInMemRepo;
 
@@ -100,13 +105,14 @@ mod _ship_opt {
     use super::{HashMap, ShippingOptionModel};
 
     #[rustfmt::skip]
-    pub(super) enum InMemColIdx {SellerID, Method, TotNumColumns}
+    pub(super) enum InMemColIdx {SellerID, Method, Fee, TotNumColumns}
     impl From<InMemColIdx> for usize {
         fn from(value: InMemColIdx) -> usize {
             match value {
                 InMemColIdx::SellerID => 0,
                 InMemColIdx::Method => 1,
-                InMemColIdx::TotNumColumns => 2,
+                InMemColIdx::Fee => 2,
+                InMemColIdx::TotNumColumns => 3,
             }
         }
     }
@@ -131,7 +137,9 @@ mod _orderline {
     #[rustfmt::skip]
     pub(super) enum InMemColIdx {
         SellerID, ProductId, AttrSetSeq, QtyReserved, PriceUnit, PriceTotal, PolicyReserved, PolicyWarranty,
-        QtyPaid, QtyPaidLastUpdate, AttrLastUpdate, AttrPriceMap, TotNumColumns,
+        QtyPaid, QtyPaidLastUpdate, AttrLastUpdate, AttrPriceMap, CancelUsrID, CancelTime,
+        PolicyVersion, PromoCode, PriceDiscount, TaxRate, PriceTax, QtyBackordered,
+        WarehouseId, TotNumColumns,
     } // TODO, new column for attr-set-seq-num
     impl From<InMemColIdx> for usize {
         fn from(value: InMemColIdx) -> usize {
@@ -148,7 +156,16 @@ mod _orderline {
                 InMemColIdx::AttrLastUpdate => 9,
                 InMemColIdx::AttrPriceMap => 10,
                 InMemColIdx::AttrSetSeq => 11,
-                InMemColIdx::TotNumColumns => 12,
+                InMemColIdx::CancelUsrID => 12,
+                InMemColIdx::CancelTime => 13,
+                InMemColIdx::PolicyVersion => 14,
+                InMemColIdx::PromoCode => 15,
+                InMemColIdx::PriceDiscount => 16,
+                InMemColIdx::TaxRate => 17,
+                InMemColIdx::PriceTax => 18,
+                InMemColIdx::QtyBackordered => 19,
+                InMemColIdx::WarehouseId => 20,
+                InMemColIdx::TotNumColumns => 21,
             }
         }
     }
@@ -284,6 +301,46 @@ mod _pkey_partial_label {
             (self.t0 < time_mid) && (time_mid < self.t1)
         }
     }
+    pub(super) struct InMemDStoreFiltOwnerTimeOp {
+        pub usr_id: u32,
+        pub t0: DateTime<FixedOffset>,
+        pub t1: DateTime<FixedOffset>,
+    }
+    impl AbsDStoreFilterKeyOp for InMemDStoreFiltOwnerTimeOp {
+        fn filter(&self, _k: &String, row: &Vec<String>) -> bool {
+            use super::_order_toplvl_meta::InMemColIdx;
+            let owner_match = row
+                .get::<usize>(InMemColIdx::OwnerUsrID.into())
+                .and_then(|v| v.parse::<u32>().ok())
+                .is_some_and(|v| v == self.usr_id);
+            let time_match = row
+                .get::<usize>(InMemColIdx::CreateTime.into())
+                .and_then(|v| DateTime::parse_from_rfc3339(v.as_str()).ok())
+                .is_some_and(|t| (self.t0 <= t) && (t <= self.t1));
+            owner_match && time_match
+        }
+    }
+    pub(super) struct InMemDStoreFiltBackorderOp {
+        pub store_id: u32,
+        pub product_ids: Vec<u64>,
+    }
+    impl AbsDStoreFilterKeyOp for InMemDStoreFiltBackorderOp {
+        fn filter(&self, _k: &String, row: &Vec<String>) -> bool {
+            use super::_orderline::InMemColIdx;
+            let get_col = |idx: InMemColIdx| row.get::<usize>(idx.into()).unwrap();
+            let store_match = get_col(InMemColIdx::SellerID)
+                .parse::<u32>()
+                .is_ok_and(|v| v == self.store_id);
+            let prod_match = get_col(InMemColIdx::ProductId)
+                .parse::<u64>()
+                .is_ok_and(|v| self.product_ids.contains(&v));
+            let backordered = get_col(InMemColIdx::QtyBackordered)
+                .parse::<u32>()
+                .is_ok_and(|v| v > 0);
+            let cancelled = !get_col(InMemColIdx::CancelUsrID).is_empty();
+            store_match && prod_match && backordered && !cancelled
+        }
+    }
 } // end of mod _pkey_partial_label
 
 pub struct OrderInMemRepo {
@@ -303,11 +360,39 @@ impl From<&OrderLineModel> for AppInMemFetchedSingleRow {
             assert_eq!(value.qty.paid, 0);
             String::new()
         };
+        let (cancel_usr_id, cancel_time) = if let Some(c) = value.cancelled.as_ref() {
+            (c.usr_id.to_string(), c.time.to_rfc3339())
+        } else {
+            (String::new(), String::new())
+        };
+        let (promo_code, price_discount) = if let Some(d) = value.discount.as_ref() {
+            (d.code.clone(), d.unit.to_string())
+        } else {
+            (String::new(), String::new())
+        };
+        let (tax_rate, price_tax) = if let Some(t) = value.tax.as_ref() {
+            (t.rate.to_string(), t.unit.to_string())
+        } else {
+            (String::new(), String::new())
+        };
         let mut row = (0.._orderline::InMemColIdx::TotNumColumns.into())
             .map(|_num| String::new())
             .collect::<Self>();
         let attributes = value.attrs_charge();
         [
+            (_orderline::InMemColIdx::CancelUsrID, cancel_usr_id),
+            (_orderline::InMemColIdx::CancelTime, cancel_time),
+            (_orderline::InMemColIdx::PromoCode, promo_code),
+            (_orderline::InMemColIdx::PriceDiscount, price_discount),
+            (_orderline::InMemColIdx::TaxRate, tax_rate),
+            (_orderline::InMemColIdx::PriceTax, price_tax),
+            (
+                _orderline::InMemColIdx::WarehouseId,
+                value
+                    .warehouse_id
+                    .map(|v| v.to_string())
+                    .unwrap_or_default(),
+            ),
             (
                 _orderline::InMemColIdx::AttrLastUpdate,
                 attributes.lastupdate().to_rfc3339(),
@@ -321,6 +406,10 @@ impl From<&OrderLineModel> for AppInMemFetchedSingleRow {
                 value.qty.reserved.to_string(),
             ),
             (_orderline::InMemColIdx::QtyPaid, value.qty.paid.to_string()),
+            (
+                _orderline::InMemColIdx::QtyBackordered,
+                value.qty.backordered.to_string(),
+            ),
             (
                 _orderline::InMemColIdx::QtyPaidLastUpdate,
                 _paid_last_update,
@@ -341,6 +430,10 @@ impl From<&OrderLineModel> for AppInMemFetchedSingleRow {
                 _orderline::InMemColIdx::PolicyWarranty,
                 value.policy.warranty_until.to_rfc3339(),
             ),
+            (
+                _orderline::InMemColIdx::PolicyVersion,
+                value.policy.version.to_string(),
+            ),
             (_orderline::InMemColIdx::AttrSetSeq, attrset_seq),
             (_orderline::InMemColIdx::ProductId, prod_id),
             (_orderline::InMemColIdx::SellerID, seller_id_s),
@@ -387,6 +480,9 @@ impl From<AppInMemFetchedSingleRow> for OrderLineModel {
                 .get::<usize>(_orderline::InMemColIdx::QtyPaid.into())
                 .unwrap().parse().unwrap(),
             paid_last_update: qty_paid_last_update,
+            backordered: row
+                .get::<usize>(_orderline::InMemColIdx::QtyBackordered.into())
+                .unwrap().parse().unwrap(),
         };
         if qty.paid_last_update.is_none() {
             assert_eq!(qty.paid, 0);
@@ -403,8 +499,10 @@ impl From<AppInMemFetchedSingleRow> for OrderLineModel {
                 .unwrap();
             DateTime::parse_from_rfc3339(s.as_str()).unwrap()
         };
-
-        let policy = OrderLineAppliedPolicyModel {reserved_until, warranty_until};
+        let version = row
+            .get::<usize>(_orderline::InMemColIdx::PolicyVersion.into())
+            .unwrap().parse().unwrap();
+        let policy = OrderLineAppliedPolicyModel {reserved_until, warranty_until, version};
 
         let attr_lastupdate = {
             let s = row
@@ -420,8 +518,45 @@ impl From<AppInMemFetchedSingleRow> for OrderLineModel {
         };
         let args = (attr_lastupdate, attr_pricemap);
         let attrs_charge = ProdAttriPriceModel::from(args);
+        let cancelled = {
+            let usr_id = row
+                .get::<usize>(_orderline::InMemColIdx::CancelUsrID.into())
+                .unwrap().parse::<u32>().ok();
+            let time = row
+                .get::<usize>(_orderline::InMemColIdx::CancelTime.into())
+                .map(|s| DateTime::parse_from_rfc3339(s.as_str()).ok())
+                .unwrap();
+            usr_id.zip(time).map(|(usr_id, time)| OrderLineCancelModel { usr_id, time })
+        };
+        let discount = {
+            let code = row
+                .get::<usize>(_orderline::InMemColIdx::PromoCode.into())
+                .unwrap();
+            let unit = row
+                .get::<usize>(_orderline::InMemColIdx::PriceDiscount.into())
+                .unwrap().parse::<u32>().ok();
+            unit.map(|unit| OrderLineDiscountModel { code: code.clone(), unit })
+        };
+        let tax = {
+            let rate = row
+                .get::<usize>(_orderline::InMemColIdx::TaxRate.into())
+                .map(|s| Decimal::from_str(s.as_str()).ok())
+                .unwrap();
+            let unit = row
+                .get::<usize>(_orderline::InMemColIdx::PriceTax.into())
+                .unwrap().parse::<u32>().ok();
+            rate.zip(unit).map(|(rate, unit)| OrderLineTaxModel { rate, unit })
+        };
+        let warehouse_id = row
+            .get::<usize>(_orderline::InMemColIdx::WarehouseId.into())
+            .unwrap().parse::<u32>().ok();
         let id_ = OrderLineIdentity::from((seller_id, product_id, attrset_seq));
-        OrderLineModel::from((id_, price, policy, qty, attrs_charge))
+        let mut obj = OrderLineModel::from((id_, price, policy, qty, attrs_charge));
+        obj.cancelled = cancelled;
+        obj.discount = discount;
+        obj.tax = tax;
+        obj.warehouse_id = warehouse_id;
+        obj
     } // end of fn from
 } // end of impl into OrderLineModel
 
@@ -567,6 +702,7 @@ impl From<ShippingOptionModel> for AppInMemFetchedSingleRow {
                 value.seller_id.to_string(),
             ),
             (_ship_opt::InMemColIdx::Method, value.method.into()),
+            (_ship_opt::InMemColIdx::Fee, value.fee.to_string()),
         ]
         .into_iter()
         .map(|(idx, val)| {
@@ -580,16 +716,19 @@ impl From<ShippingOptionModel> for AppInMemFetchedSingleRow {
 impl From<AppInMemFetchedSingleRow> for ShippingOptionModel {
     #[rustfmt::skip]
     fn from(value: AppInMemFetchedSingleRow) -> ShippingOptionModel {
-        let (seller_id, method) = (
+        let (seller_id, method, fee) = (
             value
                 .get::<usize>(_ship_opt::InMemColIdx::SellerID.into())
                 .unwrap().parse().unwrap(),
             value
                 .get::<usize>(_ship_opt::InMemColIdx::Method.into())
                 .unwrap().to_owned(),
+            value
+                .get::<usize>(_ship_opt::InMemColIdx::Fee.into())
+                .unwrap().parse().unwrap(),
         );
         ShippingOptionModel {
-            seller_id, method: ShippingMethod::from(method),
+            seller_id, method: ShippingMethod::from(method), fee,
         }
     }
 }
@@ -839,6 +978,26 @@ impl AbsOrderRepo for OrderInMemRepo {
         })
     } // end of fn update_lines_payment
 
+    async fn cancel_lines(
+        &self,
+        oid: &str,
+        pids: Vec<OrderLineIdentity>,
+        cancel: OrderLineCancelModel,
+        usr_cb: AppOrderRepoCancelLinesUserFunc,
+    ) -> DefaultResult<Vec<OrderLineCancelErrorDto>, AppError> {
+        let (mut models, g_lock) = {
+            let info = HashMap::from([Self::lines_fetch_keys(oid, &pids)]);
+            let (mut rawdata, lock) = self.datastore.fetch_acquire(info).await?;
+            (Self::lines_from_fetched(&mut rawdata), lock)
+        };
+        let errors = usr_cb(&mut models, pids, cancel);
+        if errors.is_empty() {
+            let info = HashMap::from([Self::gen_lines_tablerows(oid, &models)]);
+            let _num = self.datastore.save_release(info, g_lock)?;
+        } // cancellation is all-or-nothing, discard the changes once any error happened
+        Ok(errors)
+    } // end of fn cancel_lines
+
     async fn fetch_lines_by_rsvtime(
         &self,
         time_start: DateTime<FixedOffset>,
@@ -898,6 +1057,41 @@ impl AbsOrderRepo for OrderInMemRepo {
         self.fetch_lines_common(keys).await
     }
 
+    async fn fetch_backordered(
+        &self,
+        store_id: u32,
+        product_ids: Vec<u64>,
+    ) -> DefaultResult<Vec<OrderLineModelSet>, AppError> {
+        let table_name = _orderline::TABLE_LABEL;
+        let op = _pkey_partial_label::InMemDStoreFiltBackorderOp {
+            store_id,
+            product_ids,
+        };
+        let keys_flattened = self
+            .datastore
+            .filter_keys(table_name.to_string(), &op)
+            .await?;
+        let key_grps = _orderline::pk_group_by_oid(keys_flattened);
+        let mut out = Vec::new();
+        for (oid, keys) in key_grps.into_iter() {
+            let InnerTopLvlWrapper(owner_id, create_time, ..) =
+                self.fetch_toplvl_meta(oid.as_str()).await?;
+            let currency = self.currency_exrates(oid.as_str()).await?;
+            let ms = self.fetch_lines_common(keys).await?;
+            let args = (oid, owner_id, create_time, currency, ms);
+            let mset = OrderLineModelSet::try_from_repo(args).map_err(|mut es| {
+                let e = es.remove(0);
+                AppError {
+                    code: AppErrorCode::DataCorruption,
+                    detail: Some(e.to_string()),
+                }
+            })?;
+            out.push(mset);
+        }
+        out.sort_by_key(|m| m.create_time());
+        Ok(out)
+    } // end of fn fetch_backordered
+
     async fn fetch_ids_by_created_time(
         &self,
         start: DateTime<FixedOffset>,
@@ -916,6 +1110,40 @@ impl AbsOrderRepo for OrderInMemRepo {
         Ok(keys)
     }
 
+    async fn fetch_ids_by_owner(
+        &self,
+        usr_id: u32,
+        start: DateTime<FixedOffset>,
+        end: DateTime<FixedOffset>,
+        offset: usize,
+        limit: usize,
+    ) -> DefaultResult<(usize, Vec<(String, DateTime<FixedOffset>)>), AppError> {
+        let tbl_label = _order_toplvl_meta::TABLE_LABEL;
+        let op = _pkey_partial_label::InMemDStoreFiltOwnerTimeOp {
+            usr_id,
+            t0: start,
+            t1: end,
+        };
+        let keys = self
+            .datastore
+            .filter_keys(tbl_label.to_string(), &op)
+            .await?;
+        let info = HashMap::from([(tbl_label.to_string(), keys)]);
+        let mut resultset = self.datastore.fetch(info).await?;
+        let rows = resultset.remove(tbl_label).unwrap_or_default();
+        let mut owned = rows
+            .into_iter()
+            .map(|(oid, row)| {
+                let InnerTopLvlWrapper(_usr_id, ctime, ..) = InnerTopLvlWrapper::from(row);
+                (oid, ctime)
+            })
+            .collect::<Vec<_>>();
+        owned.sort_by_key(|(_oid, ctime)| std::cmp::Reverse(*ctime));
+        let total = owned.len();
+        let paged = owned.into_iter().skip(offset).take(limit).collect();
+        Ok((total, paged))
+    }
+
     async fn owner_id(&self, order_id: &str) -> DefaultResult<u32, AppError> {
         let inner = self.fetch_toplvl_meta(order_id).await?;
         Ok(inner.0)
@@ -1002,6 +1230,28 @@ impl OrderInMemRepo {
             ),
         ] // TODO, add seller-currency table
     }
+    pub(super) fn lines_fetch_keys(oid: &str, pids: &[OrderLineIdentity]) -> (String, Vec<String>) {
+        let keys = pids
+            .iter()
+            .map(|p| _orderline::inmem_pkey(oid, p.store_id(), p.product_id(), p.attrs_seq_num()))
+            .collect();
+        (_orderline::TABLE_LABEL.to_string(), keys)
+    }
+    pub(super) fn lines_from_fetched(rawdata: &mut AppInMemFetchedData) -> Vec<OrderLineModel> {
+        rawdata
+            .remove(_orderline::TABLE_LABEL)
+            .unwrap_or_default()
+            .into_values()
+            .map(AppInMemFetchedSingleRow::into)
+            .collect()
+    }
+    pub(super) fn gen_lines_tablerows(
+        oid: &str,
+        lines: &[OrderLineModel],
+    ) -> (String, AppInMemFetchedSingleTable) {
+        let rows = _orderline::to_inmem_tbl(oid, lines);
+        (_orderline::TABLE_LABEL.to_string(), rows)
+    }
     async fn fetch_lines_common(
         &self,
         keys: Vec<String>,
@@ -5,19 +5,28 @@ use std::convert::Into;
 use std::sync::Arc;
 
 use ecommerce_common::error::AppErrorCode;
+use ecommerce_common::model::BaseProductIdentity;
 
 use super::super::AbstProductPolicyRepo;
-use crate::datastore::AbstInMemoryDStore;
+use crate::datastore::{AbstInMemoryDStore, AppInMemFetchedSingleRow};
 use crate::error::AppError;
-use crate::model::{ProductPolicyModel, ProductPolicyModelSet};
+use crate::model::{ProductBackorderPolicy, ProductPolicyModel, ProductPolicyModelSet};
 
+// the table `product_policy` keeps only the latest version of each policy,
+// all the versions are appended to the table `product_policy_history`
 const TABLE_LABEL: &str = "product_policy";
+const HIST_TABLE_LABEL: &str = "product_policy_history";
 
 enum InMemColIdx {
     AutoCancel,
     Warranty,
     MaxNumRsv,
     MinNumRsv,
+    Version,
+    IsDeleted,
+    Backorder,
+    MaxNumBackorder,
+    WeightGram,
     TotNumColumns,
 }
 
@@ -28,11 +37,132 @@ impl From<InMemColIdx> for usize {
             InMemColIdx::Warranty => 1,
             InMemColIdx::MaxNumRsv => 2,
             InMemColIdx::MinNumRsv => 3,
-            InMemColIdx::TotNumColumns => 4,
+            InMemColIdx::Version => 4,
+            InMemColIdx::IsDeleted => 5,
+            InMemColIdx::Backorder => 6,
+            InMemColIdx::MaxNumBackorder => 7,
+            InMemColIdx::WeightGram => 8,
+            InMemColIdx::TotNumColumns => 9,
         }
     }
 }
 
+fn inmem_pkey(store_id: u32, product_id: u64) -> String {
+    format!("{store_id}-{product_id}")
+}
+
+fn inmem_hist_pkey(store_id: u32, product_id: u64, version: u32) -> String {
+    format!("{store_id}-{product_id}-{version}")
+}
+
+fn inmem_is_deleted(row: &AppInMemFetchedSingleRow) -> bool {
+    let idx: usize = InMemColIdx::IsDeleted.into();
+    row.get(idx).unwrap().parse().unwrap()
+}
+
+fn backorder_to_str(value: ProductBackorderPolicy) -> &'static str {
+    match value {
+        ProductBackorderPolicy::Disabled => "disabled",
+        ProductBackorderPolicy::Backorder => "backorder",
+        ProductBackorderPolicy::PreOrder => "pre-order",
+    }
+}
+
+fn inmem_to_model(key: &str, row: AppInMemFetchedSingleRow) -> ProductPolicyModel {
+    let id_elms = key.split('-').collect::<Vec<&str>>();
+    let store_id = id_elms[0].parse().unwrap();
+    let product_id = id_elms[1].parse().unwrap();
+    let version = row
+        .get::<usize>(InMemColIdx::Version.into())
+        .unwrap()
+        .parse()
+        .unwrap();
+    let auto_cancel_secs = row
+        .get::<usize>(InMemColIdx::AutoCancel.into())
+        .unwrap()
+        .parse()
+        .unwrap();
+    let warranty_hours = row
+        .get::<usize>(InMemColIdx::Warranty.into())
+        .unwrap()
+        .parse()
+        .unwrap();
+    let max_num_rsv = row
+        .get::<usize>(InMemColIdx::MaxNumRsv.into())
+        .unwrap()
+        .parse()
+        .unwrap();
+    let min_num_rsv = row
+        .get::<usize>(InMemColIdx::MinNumRsv.into())
+        .unwrap()
+        .parse()
+        .unwrap();
+    let backorder = match row
+        .get::<usize>(InMemColIdx::Backorder.into())
+        .unwrap()
+        .as_str()
+    {
+        "backorder" => ProductBackorderPolicy::Backorder,
+        "pre-order" => ProductBackorderPolicy::PreOrder,
+        _others => ProductBackorderPolicy::Disabled,
+    };
+    let max_num_backorder = row
+        .get::<usize>(InMemColIdx::MaxNumBackorder.into())
+        .unwrap()
+        .parse()
+        .unwrap();
+    let weight_gram = row
+        .get::<usize>(InMemColIdx::WeightGram.into())
+        .unwrap()
+        .parse()
+        .unwrap();
+    ProductPolicyModel {
+        store_id,
+        product_id,
+        version,
+        auto_cancel_secs,
+        warranty_hours,
+        max_num_rsv,
+        min_num_rsv,
+        backorder,
+        max_num_backorder,
+        weight_gram,
+        is_create: false,
+    }
+}
+
+fn inmem_to_row(m: &ProductPolicyModel, is_deleted: bool) -> AppInMemFetchedSingleRow {
+    // manually allocate space in advance, instead of `Vec::with_capacity`
+    let mut row = (0..InMemColIdx::TotNumColumns.into())
+        .map(|_n| String::new())
+        .collect::<Vec<String>>();
+    let _ = [
+        // so the order of columns can be arbitrary
+        (InMemColIdx::MinNumRsv, m.min_num_rsv.to_string()),
+        (InMemColIdx::MaxNumRsv, m.max_num_rsv.to_string()),
+        (InMemColIdx::Warranty, m.warranty_hours.to_string()),
+        (InMemColIdx::AutoCancel, m.auto_cancel_secs.to_string()),
+        (InMemColIdx::Version, m.version.to_string()),
+        (InMemColIdx::IsDeleted, is_deleted.to_string()),
+        (
+            InMemColIdx::Backorder,
+            backorder_to_str(m.backorder).to_string(),
+        ),
+        (
+            InMemColIdx::MaxNumBackorder,
+            m.max_num_backorder.to_string(),
+        ),
+        (InMemColIdx::WeightGram, m.weight_gram.to_string()),
+    ]
+    .into_iter()
+    .map(|(idx, val)| {
+        let idx: usize = idx.into();
+        row[idx] = val;
+    })
+    .collect::<Vec<()>>();
+    row
+}
+
 pub struct ProductPolicyInMemRepo {
     datastore: Arc<Box<dyn AbstInMemoryDStore>>,
 }
@@ -40,15 +170,22 @@ pub struct ProductPolicyInMemRepo {
 impl ProductPolicyInMemRepo {
     pub async fn new(m: Arc<Box<dyn AbstInMemoryDStore>>) -> Result<Self, AppError> {
         m.create_table(TABLE_LABEL).await?;
+        m.create_table(HIST_TABLE_LABEL).await?;
         Ok(Self { datastore: m })
     }
 }
 
 #[async_trait]
 impl AbstProductPolicyRepo for ProductPolicyInMemRepo {
-    async fn fetch(&self, ids: Vec<u64>) -> Result<ProductPolicyModelSet, AppError> {
+    async fn fetch(
+        &self,
+        ids: Vec<BaseProductIdentity>,
+    ) -> Result<ProductPolicyModelSet, AppError> {
         let info = {
-            let v = ids.iter().map(|prod_id| prod_id.to_string()).collect();
+            let v = ids
+                .iter()
+                .map(|id_| inmem_pkey(id_.store_id, id_.product_id))
+                .collect();
             let items = [(TABLE_LABEL.to_string(), v)];
             HashMap::from(items)
         };
@@ -56,38 +193,8 @@ impl AbstProductPolicyRepo for ProductPolicyInMemRepo {
         let filtered = if let Some(d) = result_raw.get(TABLE_LABEL) {
             // raw strings to model instances
             d.iter()
-                .map(|(key, row)| {
-                    let id_elms = key.split('-').collect::<Vec<&str>>();
-                    let product_id = id_elms[0].parse().unwrap();
-                    let auto_cancel_secs = row
-                        .get::<usize>(InMemColIdx::AutoCancel.into())
-                        .unwrap()
-                        .parse()
-                        .unwrap();
-                    let warranty_hours = row
-                        .get::<usize>(InMemColIdx::Warranty.into())
-                        .unwrap()
-                        .parse()
-                        .unwrap();
-                    let max_num_rsv = row
-                        .get::<usize>(InMemColIdx::MaxNumRsv.into())
-                        .unwrap()
-                        .parse()
-                        .unwrap();
-                    let min_num_rsv = row
-                        .get::<usize>(InMemColIdx::MinNumRsv.into())
-                        .unwrap()
-                        .parse()
-                        .unwrap();
-                    ProductPolicyModel {
-                        product_id,
-                        auto_cancel_secs,
-                        warranty_hours,
-                        max_num_rsv,
-                        min_num_rsv,
-                        is_create: false,
-                    }
-                })
+                .filter(|(_key, row)| !inmem_is_deleted(row))
+                .map(|(key, row)| inmem_to_model(key.as_str(), row.clone()))
                 .collect()
         } else {
             Vec::new()
@@ -95,6 +202,27 @@ impl AbstProductPolicyRepo for ProductPolicyInMemRepo {
         Ok(ProductPolicyModelSet { policies: filtered })
     } // end of fn fetch
 
+    async fn fetch_by_version(
+        &self,
+        ids: Vec<(BaseProductIdentity, u32)>,
+    ) -> Result<ProductPolicyModelSet, AppError> {
+        let info = {
+            let v = ids
+                .iter()
+                .map(|(id_, version)| inmem_hist_pkey(id_.store_id, id_.product_id, *version))
+                .collect();
+            HashMap::from([(HIST_TABLE_LABEL.to_string(), v)])
+        };
+        let mut result_raw = self.datastore.fetch(info).await?;
+        let policies = result_raw
+            .remove(HIST_TABLE_LABEL)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(key, row)| inmem_to_model(key.as_str(), row))
+            .collect();
+        Ok(ProductPolicyModelSet { policies })
+    } // end of fn fetch_by_version
+
     async fn save(&self, ppset: ProductPolicyModelSet) -> Result<(), AppError> {
         if ppset.policies.is_empty() {
             return Err(AppError {
@@ -102,36 +230,80 @@ impl AbstProductPolicyRepo for ProductPolicyInMemRepo {
                 detail: Some("save ProductPolicyModel".to_string()),
             });
         }
+        let mut policies = ppset
+            .policies
+            .into_iter()
+            .filter(|m| m.is_create)
+            .collect::<Vec<_>>();
+        let info = {
+            let v = policies
+                .iter()
+                .map(|m| inmem_pkey(m.store_id, m.product_id))
+                .collect();
+            HashMap::from([(TABLE_LABEL.to_string(), v)])
+        };
+        let (mut saved, lock) = self.datastore.fetch_acquire(info).await?;
+        let saved = saved.remove(TABLE_LABEL).unwrap_or_default();
+        // the version number always grows, even when the previous versions
+        // have been deleted
+        policies
+            .iter_mut()
+            .map(|m| {
+                let pkey = inmem_pkey(m.store_id, m.product_id);
+                m.version = saved
+                    .get(&pkey)
+                    .map(|row| inmem_to_model(pkey.as_str(), row.clone()).version + 1)
+                    .unwrap_or(0);
+            })
+            .count();
         let data = {
-            let mut h = HashMap::new();
-            let table_data = {
-                let kv_pairs = ppset.policies.iter().map(|m| {
-                    let pkey = format!("{}", m.product_id);
-                    // manually allocate space in advance, instead of `Vec::with_capacity`
-                    let mut row = (0..InMemColIdx::TotNumColumns.into())
-                        .map(|_n| String::new())
-                        .collect::<Vec<String>>();
-                    let _ = [
-                        // so the order of columns can be arbitrary
-                        (InMemColIdx::MinNumRsv, m.min_num_rsv.to_string()),
-                        (InMemColIdx::MaxNumRsv, m.max_num_rsv.to_string()),
-                        (InMemColIdx::Warranty, m.warranty_hours.to_string()),
-                        (InMemColIdx::AutoCancel, m.auto_cancel_secs.to_string()),
-                    ]
-                    .into_iter()
-                    .map(|(idx, val)| {
-                        let idx: usize = idx.into();
-                        row[idx] = val;
-                    })
-                    .collect::<Vec<()>>();
-                    (pkey, row)
-                });
-                HashMap::from_iter(kv_pairs)
-            };
-            h.insert(TABLE_LABEL.to_string(), table_data);
-            h
+            let (latest, history) = policies
+                .iter()
+                .map(|m| {
+                    let pkey = inmem_pkey(m.store_id, m.product_id);
+                    let hist_pkey = inmem_hist_pkey(m.store_id, m.product_id, m.version);
+                    (
+                        (pkey, inmem_to_row(m, false)),
+                        (hist_pkey, inmem_to_row(m, false)),
+                    )
+                })
+                .unzip::<_, _, HashMap<_, _>, HashMap<_, _>>();
+            HashMap::from([
+                (TABLE_LABEL.to_string(), latest),
+                (HIST_TABLE_LABEL.to_string(), history),
+            ])
         };
-        let _num_saved = self.datastore.save(data).await?;
+        let _num_saved = self.datastore.save_release(data, lock)?;
         Ok(())
     } // end of fn save
+
+    async fn delete(&self, ids: Vec<BaseProductIdentity>) -> Result<(), AppError> {
+        if ids.is_empty() {
+            return Err(AppError {
+                code: AppErrorCode::EmptyInputData,
+                detail: Some("delete ProductPolicyModel".to_string()),
+            });
+        }
+        let info = {
+            let v = ids
+                .iter()
+                .map(|id_| inmem_pkey(id_.store_id, id_.product_id))
+                .collect();
+            HashMap::from([(TABLE_LABEL.to_string(), v)])
+        };
+        let (mut saved, lock) = self.datastore.fetch_acquire(info).await?;
+        let saved = saved.remove(TABLE_LABEL).unwrap_or_default();
+        // keep the latest version in the table, so the version number can be
+        // continued when the policy is created again
+        let rows = saved
+            .into_iter()
+            .map(|(key, row)| {
+                let m = inmem_to_model(key.as_str(), row);
+                (key, inmem_to_row(&m, true))
+            })
+            .collect();
+        let data = HashMap::from([(TABLE_LABEL.to_string(), rows)]);
+        let _num_saved = self.datastore.save_release(data, lock)?;
+        Ok(())
+    } // end of fn delete
 } // end of impl AbstProductPolicyRepo
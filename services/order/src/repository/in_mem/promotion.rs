@@ -0,0 +1,208 @@
+use async_trait::async_trait;
+use std::boxed::Box;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use chrono::DateTime;
+
+use ecommerce_common::error::AppErrorCode;
+
+use super::super::AbsPromotionRepo;
+use crate::datastore::{AbstInMemoryDStore, AppInMemFetchedSingleRow, AppInMemFetchedSingleTable};
+use crate::error::AppError;
+use crate::model::{PromotionModel, PromotionModelSet, PromotionRuleModel};
+
+pub(super) const TABLE_LABEL: &str = "promotion";
+
+enum InMemColIdx {
+    StoreId,
+    ProductIds,
+    RuleLabel,
+    RuleArg0,
+    RuleArg1,
+    StartAfter,
+    EndBefore,
+    MaxUsage,
+    NumUsed,
+    TotNumColumns,
+}
+
+impl From<InMemColIdx> for usize {
+    fn from(value: InMemColIdx) -> usize {
+        match value {
+            InMemColIdx::StoreId => 0,
+            InMemColIdx::ProductIds => 1,
+            InMemColIdx::RuleLabel => 2,
+            InMemColIdx::RuleArg0 => 3,
+            InMemColIdx::RuleArg1 => 4,
+            InMemColIdx::StartAfter => 5,
+            InMemColIdx::EndBefore => 6,
+            InMemColIdx::MaxUsage => 7,
+            InMemColIdx::NumUsed => 8,
+            InMemColIdx::TotNumColumns => 9,
+        }
+    }
+}
+
+fn inmem_to_model(code: &str, row: AppInMemFetchedSingleRow) -> Result<PromotionModel, AppError> {
+    let get_col = |idx: InMemColIdx| -> &str { row.get::<usize>(idx.into()).unwrap().as_str() };
+    let store_id = get_col(InMemColIdx::StoreId).parse().unwrap();
+    let product_ids = get_col(InMemColIdx::ProductIds)
+        .split(',')
+        .filter(|s| !s.is_empty())
+        .map(|s| s.parse().unwrap())
+        .collect();
+    let rule = {
+        let label = get_col(InMemColIdx::RuleLabel);
+        let arg0 = get_col(InMemColIdx::RuleArg0).parse().unwrap();
+        let arg1 = get_col(InMemColIdx::RuleArg1).parse().unwrap();
+        PromotionRuleModel::try_from((label, arg0, arg1))?
+    };
+    let start_after = DateTime::parse_from_rfc3339(get_col(InMemColIdx::StartAfter)).unwrap();
+    let end_before = DateTime::parse_from_rfc3339(get_col(InMemColIdx::EndBefore)).unwrap();
+    let max_usage = get_col(InMemColIdx::MaxUsage).parse().ok();
+    let num_used = get_col(InMemColIdx::NumUsed).parse().unwrap();
+    Ok(PromotionModel {
+        code: code.to_string(),
+        store_id,
+        product_ids,
+        rule,
+        start_after,
+        end_before,
+        max_usage,
+        num_used,
+    })
+} // end of fn inmem_to_model
+
+fn inmem_to_row(m: &PromotionModel) -> AppInMemFetchedSingleRow {
+    let mut row = (0..InMemColIdx::TotNumColumns.into())
+        .map(|_n| String::new())
+        .collect::<Vec<String>>();
+    let (label, arg0, arg1) = <(&str, u32, u32)>::from(&m.rule);
+    let product_ids = m
+        .product_ids
+        .iter()
+        .map(u64::to_string)
+        .collect::<Vec<_>>()
+        .join(",");
+    let max_usage = m.max_usage.map(|v| v.to_string()).unwrap_or_default();
+    let _ = [
+        (InMemColIdx::StoreId, m.store_id.to_string()),
+        (InMemColIdx::ProductIds, product_ids),
+        (InMemColIdx::RuleLabel, label.to_string()),
+        (InMemColIdx::RuleArg0, arg0.to_string()),
+        (InMemColIdx::RuleArg1, arg1.to_string()),
+        (InMemColIdx::StartAfter, m.start_after.to_rfc3339()),
+        (InMemColIdx::EndBefore, m.end_before.to_rfc3339()),
+        (InMemColIdx::MaxUsage, max_usage),
+        (InMemColIdx::NumUsed, m.num_used.to_string()),
+    ]
+    .into_iter()
+    .map(|(idx, val)| {
+        let idx: usize = idx.into();
+        row[idx] = val;
+    })
+    .count();
+    row
+}
+
+// increase usage count of each given promotion by one, the codes whose usage
+// count already reached the limit, or which no longer exist, are reported
+pub(super) fn inmem_claim_usage(
+    codes: &[String],
+    mut rows: AppInMemFetchedSingleTable,
+) -> Result<AppInMemFetchedSingleTable, Vec<String>> {
+    let (idx_max, idx_used): (usize, usize) =
+        (InMemColIdx::MaxUsage.into(), InMemColIdx::NumUsed.into());
+    let exhausted = codes
+        .iter()
+        .filter(|code| {
+            rows.get_mut(code.as_str())
+                .map(|row| {
+                    let num_used = row[idx_used].parse::<u32>().unwrap();
+                    let allowed = row[idx_max]
+                        .parse::<u32>()
+                        .map(|max_usage| num_used < max_usage)
+                        .unwrap_or(true);
+                    if allowed {
+                        row[idx_used] = (num_used + 1).to_string();
+                    }
+                    !allowed
+                })
+                .unwrap_or(true)
+        })
+        .cloned()
+        .collect::<Vec<_>>();
+    if exhausted.is_empty() {
+        Ok(rows)
+    } else {
+        Err(exhausted)
+    }
+}
+
+// decrease usage count of each promotion in the given rows by one
+pub(super) fn inmem_release_usage(rows: AppInMemFetchedSingleTable) -> AppInMemFetchedSingleTable {
+    let idx: usize = InMemColIdx::NumUsed.into();
+    rows.into_iter()
+        .map(|(code, mut row)| {
+            let num_used = row[idx].parse::<u32>().unwrap();
+            row[idx] = num_used.saturating_sub(1).to_string();
+            (code, row)
+        })
+        .collect()
+}
+
+pub struct PromotionInMemRepo {
+    datastore: Arc<Box<dyn AbstInMemoryDStore>>,
+}
+
+impl PromotionInMemRepo {
+    pub async fn new(m: Arc<Box<dyn AbstInMemoryDStore>>) -> Result<Self, AppError> {
+        m.create_table(TABLE_LABEL).await?;
+        Ok(Self { datastore: m })
+    }
+}
+
+#[async_trait]
+impl AbsPromotionRepo for PromotionInMemRepo {
+    async fn fetch(&self, codes: Vec<String>) -> Result<PromotionModelSet, AppError> {
+        let info = HashMap::from([(TABLE_LABEL.to_string(), codes)]);
+        let mut result_raw = self.datastore.fetch(info).await?;
+        let promotions = result_raw
+            .remove(TABLE_LABEL)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(code, row)| inmem_to_model(code.as_str(), row))
+            .collect::<Result<Vec<_>, AppError>>()?;
+        Ok(PromotionModelSet { promotions })
+    }
+
+    async fn save(&self, ms: PromotionModelSet) -> Result<(), AppError> {
+        if ms.promotions.is_empty() {
+            return Err(AppError {
+                code: AppErrorCode::EmptyInputData,
+                detail: Some("save PromotionModel".to_string()),
+            });
+        }
+        let codes = ms.promotions.iter().map(|m| m.code.clone()).collect();
+        let info = HashMap::from([(TABLE_LABEL.to_string(), codes)]);
+        let (mut saved, lock) = self.datastore.fetch_acquire(info).await?;
+        let saved = saved.remove(TABLE_LABEL).unwrap_or_default();
+        // usage count might be changed by other orders after the promotions
+        // were loaded, always keep the latest one in the datastore
+        let rows = ms
+            .promotions
+            .into_iter()
+            .map(|mut m| {
+                if let Some(row) = saved.get(&m.code) {
+                    let idx: usize = InMemColIdx::NumUsed.into();
+                    m.num_used = row.get(idx).unwrap().parse().unwrap();
+                }
+                (m.code.clone(), inmem_to_row(&m))
+            })
+            .collect();
+        let data = HashMap::from([(TABLE_LABEL.to_string(), rows)]);
+        let _num_saved = self.datastore.save_release(data, lock)?;
+        Ok(())
+    }
+} // end of impl AbsPromotionRepo
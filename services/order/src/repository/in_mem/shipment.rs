@@ -0,0 +1,267 @@
+use async_trait::async_trait;
+use std::boxed::Box;
+use std::collections::HashMap;
+use std::io::ErrorKind;
+use std::sync::Arc;
+
+use chrono::{DateTime, FixedOffset};
+
+use ecommerce_common::error::AppErrorCode;
+
+use super::super::AbsShipmentRepo;
+use crate::api::dto::ShippingMethod;
+use crate::datastore::{AbsDStoreFilterKeyOp, AbstInMemoryDStore, AppInMemFetchedSingleRow};
+use crate::error::AppError;
+use crate::model::{OrderLineIdentity, ShipmentLineModel, ShipmentModel, ShipmentStatus};
+
+const TABLE_LABEL: &str = "order_shipment";
+const LINE_DELIMITER: &str = "/";
+
+enum ShipmentColIdx {
+    SellerId,
+    Carrier,
+    TrackingNumber,
+    Status,
+    PackedTime,
+    ShippedTime,
+    DeliveredTime,
+    Lines,
+    TotNumColumns,
+}
+
+impl From<ShipmentColIdx> for usize {
+    fn from(value: ShipmentColIdx) -> usize {
+        match value {
+            ShipmentColIdx::SellerId => 0,
+            ShipmentColIdx::Carrier => 1,
+            ShipmentColIdx::TrackingNumber => 2,
+            ShipmentColIdx::Status => 3,
+            ShipmentColIdx::PackedTime => 4,
+            ShipmentColIdx::ShippedTime => 5,
+            ShipmentColIdx::DeliveredTime => 6,
+            ShipmentColIdx::Lines => 7,
+            ShipmentColIdx::TotNumColumns => 8,
+        }
+    }
+}
+
+fn status_to_str(value: ShipmentStatus) -> &'static str {
+    match value {
+        ShipmentStatus::Packed => "packed",
+        ShipmentStatus::Shipped => "shipped",
+        ShipmentStatus::Delivered => "delivered",
+    }
+}
+
+fn status_from_str(value: &str) -> Result<ShipmentStatus, AppError> {
+    match value {
+        "packed" => Ok(ShipmentStatus::Packed),
+        "shipped" => Ok(ShipmentStatus::Shipped),
+        "delivered" => Ok(ShipmentStatus::Delivered),
+        _others => Err(AppError {
+            code: AppErrorCode::DataCorruption,
+            detail: Some(format!("shipment-status:{value}")),
+        }),
+    }
+}
+
+fn inmem_pkey(oid: &str, seq: u16) -> String {
+    format!("{oid}/{seq}")
+}
+
+fn shipment_to_row(oid: &str, m: ShipmentModel) -> (String, AppInMemFetchedSingleRow) {
+    let pkey = inmem_pkey(oid, m.seq);
+    let lines = m
+        .lines
+        .iter()
+        .map(|l| format!("{} {} {}", l.id_.product_id(), l.id_.attrs_seq_num(), l.qty))
+        .collect::<Vec<_>>()
+        .join(LINE_DELIMITER);
+    let mut row = (0..ShipmentColIdx::TotNumColumns.into())
+        .map(|_n| String::new())
+        .collect::<AppInMemFetchedSingleRow>();
+    let _ = [
+        (ShipmentColIdx::SellerId, m.seller_id.to_string()),
+        (ShipmentColIdx::Carrier, String::from(m.carrier)),
+        (
+            ShipmentColIdx::TrackingNumber,
+            m.tracking_number.unwrap_or_default(),
+        ),
+        (ShipmentColIdx::Status, status_to_str(m.status).to_string()),
+        (ShipmentColIdx::PackedTime, m.packed_time.to_rfc3339()),
+        (
+            ShipmentColIdx::ShippedTime,
+            m.shipped_time.map(|t| t.to_rfc3339()).unwrap_or_default(),
+        ),
+        (
+            ShipmentColIdx::DeliveredTime,
+            m.delivered_time.map(|t| t.to_rfc3339()).unwrap_or_default(),
+        ),
+        (ShipmentColIdx::Lines, lines),
+    ]
+    .into_iter()
+    .map(|(idx, val)| {
+        let idx: usize = idx.into();
+        row[idx] = val;
+    })
+    .count();
+    (pkey, row)
+}
+
+fn shipment_from_row(pkey: &str, row: AppInMemFetchedSingleRow) -> Result<ShipmentModel, AppError> {
+    let get_col = |idx: ShipmentColIdx| -> &str { row.get::<usize>(idx.into()).unwrap().as_str() };
+    let corrupted = |detail: String| AppError {
+        code: AppErrorCode::DataCorruption,
+        detail: Some(detail),
+    };
+    let parse_time = |idx: ShipmentColIdx| -> Result<Option<DateTime<FixedOffset>>, AppError> {
+        Some(get_col(idx))
+            .filter(|v| !v.is_empty())
+            .map(DateTime::parse_from_rfc3339)
+            .transpose()
+            .map_err(|e| corrupted(format!("shipment-time: {e}")))
+    };
+    let seq = pkey
+        .rsplit('/')
+        .next()
+        .and_then(|v| v.parse::<u16>().ok())
+        .ok_or(corrupted(format!("shipment-key:{pkey}")))?;
+    let seller_id = get_col(ShipmentColIdx::SellerId)
+        .parse::<u32>()
+        .map_err(|e| corrupted(format!("shipment-seller: {e}")))?;
+    let tracking_number = Some(get_col(ShipmentColIdx::TrackingNumber))
+        .filter(|v| !v.is_empty())
+        .map(|v| v.to_string());
+    let lines = get_col(ShipmentColIdx::Lines)
+        .split(LINE_DELIMITER)
+        .map(|tkn| {
+            let mut tokens = tkn.split(' ').map(|v| v.parse::<u64>().ok());
+            match (tokens.next(), tokens.next(), tokens.next()) {
+                (Some(Some(product_id)), Some(Some(attr_seq)), Some(Some(qty))) => {
+                    Ok(ShipmentLineModel {
+                        id_: OrderLineIdentity::from((seller_id, product_id, attr_seq as u16)),
+                        qty: qty as u32,
+                    })
+                }
+                _others => Err(corrupted(format!("shipment-line:{tkn}"))),
+            }
+        })
+        .collect::<Result<Vec<_>, AppError>>()?;
+    let packed_time = parse_time(ShipmentColIdx::PackedTime)?
+        .ok_or(corrupted("shipment-packed-time".to_string()))?;
+    Ok(ShipmentModel {
+        seq,
+        seller_id,
+        carrier: ShippingMethod::from(get_col(ShipmentColIdx::Carrier).to_string()),
+        tracking_number,
+        status: status_from_str(get_col(ShipmentColIdx::Status))?,
+        packed_time,
+        shipped_time: parse_time(ShipmentColIdx::ShippedTime)?,
+        delivered_time: parse_time(ShipmentColIdx::DeliveredTime)?,
+        lines,
+    })
+} // end of fn shipment_from_row
+
+struct InMemFiltOrderOp<'a>(&'a str);
+
+impl AbsDStoreFilterKeyOp for InMemFiltOrderOp<'_> {
+    fn filter(&self, k: &String, _v: &Vec<String>) -> bool {
+        k.split('/').next().is_some_and(|oid| oid == self.0)
+    }
+}
+
+pub struct ShipmentInMemRepo {
+    datastore: Arc<Box<dyn AbstInMemoryDStore>>,
+}
+
+impl ShipmentInMemRepo {
+    pub async fn new(m: Arc<Box<dyn AbstInMemoryDStore>>) -> Result<Self, AppError> {
+        m.create_table(TABLE_LABEL).await?;
+        Ok(Self { datastore: m })
+    }
+
+    async fn exists(&self, pkey: String) -> Result<bool, AppError> {
+        let info = HashMap::from([(TABLE_LABEL.to_string(), vec![pkey])]);
+        let mut result_raw = self.datastore.fetch(info).await?;
+        let found = result_raw
+            .remove(TABLE_LABEL)
+            .is_some_and(|rows| !rows.is_empty());
+        Ok(found)
+    }
+
+    async fn save_common(
+        &self,
+        oid: &str,
+        obj: ShipmentModel,
+        expect_exist: bool,
+    ) -> Result<(), AppError> {
+        let pkey = inmem_pkey(oid, obj.seq);
+        if self.exists(pkey.clone()).await? != expect_exist {
+            let kind = if expect_exist {
+                ErrorKind::NotFound
+            } else {
+                ErrorKind::AlreadyExists
+            };
+            return Err(AppError {
+                code: AppErrorCode::IOerror(kind),
+                detail: Some(format!("shipment:{pkey}")),
+            });
+        }
+        let (pkey, row) = shipment_to_row(oid, obj);
+        let rows = HashMap::from([(pkey, row)]);
+        let data = HashMap::from([(TABLE_LABEL.to_string(), rows)]);
+        let _num_saved = self.datastore.save(data).await?;
+        Ok(())
+    }
+} // end of impl ShipmentInMemRepo
+
+#[async_trait]
+impl AbsShipmentRepo for ShipmentInMemRepo {
+    async fn fetch_by_oid(&self, oid: &str) -> Result<Vec<ShipmentModel>, AppError> {
+        let op = InMemFiltOrderOp(oid);
+        let keys = self
+            .datastore
+            .filter_keys(TABLE_LABEL.to_string(), &op)
+            .await?;
+        let info = HashMap::from([(TABLE_LABEL.to_string(), keys)]);
+        let mut result_raw = self.datastore.fetch(info).await?;
+        let mut out = result_raw
+            .remove(TABLE_LABEL)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(k, row)| shipment_from_row(k.as_str(), row))
+            .collect::<Result<Vec<_>, AppError>>()?;
+        out.sort_by_key(|m| m.seq);
+        Ok(out)
+    }
+
+    async fn create(&self, oid: &str, obj: ShipmentModel) -> Result<bool, AppError> {
+        if obj.lines.is_empty() {
+            return Err(AppError {
+                code: AppErrorCode::EmptyInputData,
+                detail: Some(format!("shipment-lines, oid:{oid}")),
+            });
+        }
+        // shipments are created one after another in an order, if any of them
+        // was created after the caller loaded the shipments, the first one must
+        // have the same sequence number
+        let pkey = inmem_pkey(oid, obj.seq);
+        let info = HashMap::from([(TABLE_LABEL.to_string(), vec![pkey])]);
+        let (mut saved, lock) = self.datastore.fetch_acquire(info).await?;
+        let conflict = saved
+            .remove(TABLE_LABEL)
+            .is_some_and(|rows| !rows.is_empty());
+        if conflict {
+            return Ok(false);
+        }
+        let (pkey, row) = shipment_to_row(oid, obj);
+        let rows = HashMap::from([(pkey, row)]);
+        let data = HashMap::from([(TABLE_LABEL.to_string(), rows)]);
+        let _num_saved = self.datastore.save_release(data, lock)?;
+        Ok(true)
+    }
+
+    async fn update(&self, oid: &str, obj: ShipmentModel) -> Result<(), AppError> {
+        self.save_common(oid, obj, true).await
+    }
+} // end of impl AbsShipmentRepo
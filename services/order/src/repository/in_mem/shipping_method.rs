@@ -0,0 +1,209 @@
+use async_trait::async_trait;
+use std::boxed::Box;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use ecommerce_common::api::dto::CountryCode;
+use ecommerce_common::error::AppErrorCode;
+
+use super::super::AbsShippingMethodRepo;
+use crate::api::dto::ShippingMethod;
+use crate::datastore::{AbsDStoreFilterKeyOp, AbstInMemoryDStore, AppInMemFetchedSingleRow};
+use crate::error::AppError;
+use crate::model::{ShippingMethodModel, ShippingMethodModelSet, ShippingRateModel};
+
+// each row is an entry of the rate table, a seller supports a delivery
+// service as long as there is at least one entry of the method
+const TABLE_LABEL: &str = "shipping_rate";
+
+enum InMemColIdx {
+    Country,
+    Region,
+    MaxWeight,
+    Fee,
+    TotNumColumns,
+}
+
+impl From<InMemColIdx> for usize {
+    fn from(value: InMemColIdx) -> usize {
+        match value {
+            InMemColIdx::Country => 0,
+            InMemColIdx::Region => 1,
+            InMemColIdx::MaxWeight => 2,
+            InMemColIdx::Fee => 3,
+            InMemColIdx::TotNumColumns => 4,
+        }
+    }
+}
+
+fn inmem_pkey(store_id: u32, method: ShippingMethod, seq: usize) -> String {
+    let method: String = method.into();
+    format!("{store_id}/{method}/{seq}")
+}
+
+fn inmem_to_row(
+    store_id: u32,
+    method: ShippingMethod,
+    seq: usize,
+    m: ShippingRateModel,
+) -> (String, AppInMemFetchedSingleRow) {
+    let pkey = inmem_pkey(store_id, method, seq);
+    let mut row = (0..InMemColIdx::TotNumColumns.into())
+        .map(|_n| String::new())
+        .collect::<AppInMemFetchedSingleRow>();
+    let _ = [
+        (InMemColIdx::Country, m.country.into()),
+        (InMemColIdx::Region, m.region.unwrap_or_default()),
+        (InMemColIdx::MaxWeight, m.max_weight_gram.to_string()),
+        (InMemColIdx::Fee, m.fee.to_string()),
+    ]
+    .into_iter()
+    .map(|(idx, val)| {
+        let idx: usize = idx.into();
+        row[idx] = val;
+    })
+    .count();
+    (pkey, row)
+}
+
+fn inmem_to_model(
+    pkey: &str,
+    row: AppInMemFetchedSingleRow,
+) -> Result<(u32, ShippingMethod, ShippingRateModel), AppError> {
+    let get_col = |idx: InMemColIdx| -> &str { row.get::<usize>(idx.into()).unwrap().as_str() };
+    let corrupted = |detail: String| AppError {
+        code: AppErrorCode::DataCorruption,
+        detail: Some(detail),
+    };
+    let mut tokens = pkey.split('/');
+    let store_id = tokens
+        .next()
+        .and_then(|v| v.parse::<u32>().ok())
+        .ok_or(corrupted(format!("shipping-rate-key:{pkey}")))?;
+    let method = tokens
+        .next()
+        .map(|v| ShippingMethod::from(v.to_string()))
+        .ok_or(corrupted(format!("shipping-rate-key:{pkey}")))?;
+    let max_weight_gram = get_col(InMemColIdx::MaxWeight)
+        .parse::<u32>()
+        .map_err(|e| corrupted(format!("shipping-rate-weight: {e}")))?;
+    let fee = get_col(InMemColIdx::Fee)
+        .parse::<u32>()
+        .map_err(|e| corrupted(format!("shipping-rate-fee: {e}")))?;
+    let rate = ShippingRateModel {
+        country: CountryCode::from(get_col(InMemColIdx::Country).to_string()),
+        region: Some(get_col(InMemColIdx::Region))
+            .filter(|r| !r.is_empty())
+            .map(String::from),
+        max_weight_gram,
+        fee,
+    };
+    Ok((store_id, method, rate))
+} // end of fn inmem_to_model
+
+struct InMemFiltStoreOp(Vec<u32>);
+
+impl AbsDStoreFilterKeyOp for InMemFiltStoreOp {
+    fn filter(&self, k: &String, _v: &Vec<String>) -> bool {
+        k.split('/')
+            .next()
+            .and_then(|v| v.parse::<u32>().ok())
+            .is_some_and(|id| self.0.contains(&id))
+    }
+}
+
+struct InMemFiltMethodOp(String);
+
+impl AbsDStoreFilterKeyOp for InMemFiltMethodOp {
+    fn filter(&self, k: &String, _v: &Vec<String>) -> bool {
+        k.starts_with(self.0.as_str())
+    }
+}
+
+pub struct ShippingMethodInMemRepo {
+    datastore: Arc<Box<dyn AbstInMemoryDStore>>,
+}
+
+impl ShippingMethodInMemRepo {
+    pub async fn new(m: Arc<Box<dyn AbstInMemoryDStore>>) -> Result<Self, AppError> {
+        m.create_table(TABLE_LABEL).await?;
+        Ok(Self { datastore: m })
+    }
+}
+
+#[async_trait]
+impl AbsShippingMethodRepo for ShippingMethodInMemRepo {
+    async fn fetch(&self, store_ids: Vec<u32>) -> Result<ShippingMethodModelSet, AppError> {
+        let op = InMemFiltStoreOp(store_ids);
+        let keys = self
+            .datastore
+            .filter_keys(TABLE_LABEL.to_string(), &op)
+            .await?;
+        let info = HashMap::from([(TABLE_LABEL.to_string(), keys)]);
+        let mut result_raw = self.datastore.fetch(info).await?;
+        let mut rows = result_raw
+            .remove(TABLE_LABEL)
+            .unwrap_or_default()
+            .into_iter()
+            .collect::<Vec<_>>();
+        // keep the order of entries in the rate table
+        rows.sort_by_key(|(k, _v)| {
+            let seq = k.rsplit('/').next().and_then(|v| v.parse::<usize>().ok());
+            seq.unwrap_or(0)
+        });
+        let mut methods: Vec<ShippingMethodModel> = Vec::new();
+        for (pkey, row) in rows {
+            let (store_id, method, rate) = inmem_to_model(pkey.as_str(), row)?;
+            let found = methods
+                .iter_mut()
+                .find(|m| m.store_id == store_id && m.method == method);
+            if let Some(m) = found {
+                m.rates.push(rate);
+            } else {
+                let rates = vec![rate];
+                methods.push(ShippingMethodModel {
+                    store_id,
+                    method,
+                    rates,
+                });
+            }
+        }
+        Ok(ShippingMethodModelSet { methods })
+    } // end of fn fetch
+
+    async fn save(&self, ms: ShippingMethodModelSet) -> Result<(), AppError> {
+        if ms.methods.is_empty() {
+            return Err(AppError {
+                code: AppErrorCode::EmptyInputData,
+                detail: Some("save ShippingMethodModel".to_string()),
+            });
+        }
+        for m in ms.methods {
+            let ShippingMethodModel {
+                store_id,
+                method,
+                rates,
+            } = m;
+            let method_label: String = method.clone().into();
+            let op = InMemFiltMethodOp(format!("{store_id}/{method_label}/"));
+            let keys = self
+                .datastore
+                .filter_keys(TABLE_LABEL.to_string(), &op)
+                .await?;
+            if !keys.is_empty() {
+                let info = HashMap::from([(TABLE_LABEL.to_string(), keys)]);
+                let _num_deleted = self.datastore.delete(info).await?;
+            }
+            if !rates.is_empty() {
+                let rows = rates
+                    .into_iter()
+                    .enumerate()
+                    .map(|(seq, r)| inmem_to_row(store_id, method.clone(), seq, r))
+                    .collect();
+                let data = HashMap::from([(TABLE_LABEL.to_string(), rows)]);
+                let _num_saved = self.datastore.save(data).await?;
+            }
+        }
+        Ok(())
+    } // end of fn save
+} // end of impl AbsShippingMethodRepo
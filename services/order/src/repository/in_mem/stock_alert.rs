@@ -0,0 +1,294 @@
+use async_trait::async_trait;
+use std::boxed::Box;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use chrono::DateTime;
+
+use ecommerce_common::error::AppErrorCode;
+
+use super::super::AbsStockAlertRepo;
+use crate::datastore::{AbsDStoreFilterKeyOp, AbstInMemoryDStore, AppInMemFetchedSingleRow};
+use crate::error::AppError;
+use crate::model::{StockAlertKind, StockAlertModel, StockAlertThresholdModel};
+
+const TBL_LABEL_THRESHOLD: &str = "stock_alert_threshold";
+const TBL_LABEL_SENT: &str = "stock_alert_sent";
+
+enum ThresholdColIdx {
+    StoreId,
+    LowStockQty,
+    ExpiryWithinDays,
+    TotNumColumns,
+}
+
+enum SentColIdx {
+    Kind,
+    ProductId,
+    Expiry,
+    NumAvail,
+    TotNumColumns,
+}
+
+impl From<ThresholdColIdx> for usize {
+    fn from(value: ThresholdColIdx) -> usize {
+        match value {
+            ThresholdColIdx::StoreId => 0,
+            ThresholdColIdx::LowStockQty => 1,
+            ThresholdColIdx::ExpiryWithinDays => 2,
+            ThresholdColIdx::TotNumColumns => 3,
+        }
+    }
+}
+
+impl From<SentColIdx> for usize {
+    fn from(value: SentColIdx) -> usize {
+        match value {
+            SentColIdx::Kind => 0,
+            SentColIdx::ProductId => 1,
+            SentColIdx::Expiry => 2,
+            SentColIdx::NumAvail => 3,
+            SentColIdx::TotNumColumns => 4,
+        }
+    }
+}
+
+fn kind_to_str(value: StockAlertKind) -> &'static str {
+    match value {
+        StockAlertKind::LowStock => "low-stock",
+        StockAlertKind::ExpiringBatch => "expiring-batch",
+    }
+}
+
+fn kind_from_str(value: &str) -> Result<StockAlertKind, AppError> {
+    match value {
+        "low-stock" => Ok(StockAlertKind::LowStock),
+        "expiring-batch" => Ok(StockAlertKind::ExpiringBatch),
+        _others => Err(AppError {
+            code: AppErrorCode::DataCorruption,
+            detail: Some(format!("stock-alert-kind:{value}")),
+        }),
+    }
+}
+
+fn new_empty_row(num_cols: usize) -> AppInMemFetchedSingleRow {
+    (0..num_cols).map(|_n| String::new()).collect()
+}
+
+fn threshold_to_row(m: StockAlertThresholdModel) -> (String, AppInMemFetchedSingleRow) {
+    let pkey = m.store_id.to_string();
+    let mut row = new_empty_row(ThresholdColIdx::TotNumColumns.into());
+    let _ = [
+        (ThresholdColIdx::StoreId, m.store_id.to_string()),
+        (ThresholdColIdx::LowStockQty, m.low_stock_qty.to_string()),
+        (
+            ThresholdColIdx::ExpiryWithinDays,
+            m.expiry_within_days.to_string(),
+        ),
+    ]
+    .into_iter()
+    .map(|(idx, val)| {
+        let idx: usize = idx.into();
+        row[idx] = val;
+    })
+    .count();
+    (pkey, row)
+}
+
+fn threshold_from_row(row: AppInMemFetchedSingleRow) -> Result<StockAlertThresholdModel, AppError> {
+    let get_col = |idx: ThresholdColIdx| -> &str { row.get::<usize>(idx.into()).unwrap().as_str() };
+    let parse_err = |e: std::num::ParseIntError| AppError {
+        code: AppErrorCode::DataCorruption,
+        detail: Some(format!("stock-alert-threshold: {e}")),
+    };
+    Ok(StockAlertThresholdModel {
+        store_id: get_col(ThresholdColIdx::StoreId)
+            .parse()
+            .map_err(parse_err)?,
+        low_stock_qty: get_col(ThresholdColIdx::LowStockQty)
+            .parse()
+            .map_err(parse_err)?,
+        expiry_within_days: get_col(ThresholdColIdx::ExpiryWithinDays)
+            .parse()
+            .map_err(parse_err)?,
+    })
+}
+
+// empty expiry in the key indicates the alert is not for specific batch
+fn sent_pkey(store_id: u32, m: &StockAlertModel) -> String {
+    let expiry = m
+        .expiry
+        .map(|t| t.timestamp().to_string())
+        .unwrap_or_default();
+    format!(
+        "{store_id}/{}/{}/{expiry}",
+        kind_to_str(m.kind),
+        m.product_id
+    )
+}
+
+fn sent_to_row(store_id: u32, m: StockAlertModel) -> (String, AppInMemFetchedSingleRow) {
+    let pkey = sent_pkey(store_id, &m);
+    let mut row = new_empty_row(SentColIdx::TotNumColumns.into());
+    let _ = [
+        (SentColIdx::Kind, kind_to_str(m.kind).to_string()),
+        (SentColIdx::ProductId, m.product_id.to_string()),
+        (
+            SentColIdx::Expiry,
+            m.expiry.map(|t| t.to_rfc3339()).unwrap_or_default(),
+        ),
+        (SentColIdx::NumAvail, m.num_avail.to_string()),
+    ]
+    .into_iter()
+    .map(|(idx, val)| {
+        let idx: usize = idx.into();
+        row[idx] = val;
+    })
+    .count();
+    (pkey, row)
+}
+
+fn sent_from_row(row: AppInMemFetchedSingleRow) -> Result<StockAlertModel, AppError> {
+    let get_col = |idx: SentColIdx| -> &str { row.get::<usize>(idx.into()).unwrap().as_str() };
+    let corrupted = |detail: String| AppError {
+        code: AppErrorCode::DataCorruption,
+        detail: Some(detail),
+    };
+    let kind = kind_from_str(get_col(SentColIdx::Kind))?;
+    let product_id = get_col(SentColIdx::ProductId)
+        .parse::<u64>()
+        .map_err(|e| corrupted(format!("stock-alert-product: {e}")))?;
+    let expiry = Some(get_col(SentColIdx::Expiry))
+        .filter(|v| !v.is_empty())
+        .map(DateTime::parse_from_rfc3339)
+        .transpose()
+        .map_err(|e| corrupted(format!("stock-alert-expiry: {e}")))?
+        .map(|t| t.to_utc());
+    let num_avail = get_col(SentColIdx::NumAvail)
+        .parse::<u32>()
+        .map_err(|e| corrupted(format!("stock-alert-num-avail: {e}")))?;
+    Ok(StockAlertModel {
+        kind,
+        product_id,
+        expiry,
+        num_avail,
+    })
+}
+
+struct InMemFiltAllOp;
+struct InMemFiltStoreOp(u32);
+
+impl AbsDStoreFilterKeyOp for InMemFiltAllOp {
+    fn filter(&self, _k: &String, _v: &Vec<String>) -> bool {
+        true
+    }
+}
+
+impl AbsDStoreFilterKeyOp for InMemFiltStoreOp {
+    fn filter(&self, k: &String, _v: &Vec<String>) -> bool {
+        k.split('/')
+            .next()
+            .and_then(|v| v.parse::<u32>().ok())
+            .is_some_and(|store_id| store_id == self.0)
+    }
+}
+
+pub struct StockAlertInMemRepo {
+    datastore: Arc<Box<dyn AbstInMemoryDStore>>,
+}
+
+impl StockAlertInMemRepo {
+    pub async fn new(m: Arc<Box<dyn AbstInMemoryDStore>>) -> Result<Self, AppError> {
+        m.create_table(TBL_LABEL_THRESHOLD).await?;
+        m.create_table(TBL_LABEL_SENT).await?;
+        Ok(Self { datastore: m })
+    }
+
+    async fn fetch_rows(
+        &self,
+        label: &str,
+        op: &dyn AbsDStoreFilterKeyOp,
+    ) -> Result<Vec<AppInMemFetchedSingleRow>, AppError> {
+        let keys = self.datastore.filter_keys(label.to_string(), op).await?;
+        let info = HashMap::from([(label.to_string(), keys)]);
+        let mut result_raw = self.datastore.fetch(info).await?;
+        let rows = result_raw
+            .remove(label)
+            .unwrap_or_default()
+            .into_values()
+            .collect();
+        Ok(rows)
+    }
+}
+
+#[async_trait]
+impl AbsStockAlertRepo for StockAlertInMemRepo {
+    async fn save_thresholds(&self, ms: Vec<StockAlertThresholdModel>) -> Result<(), AppError> {
+        if ms.is_empty() {
+            return Err(AppError {
+                code: AppErrorCode::EmptyInputData,
+                detail: Some("save StockAlertThresholdModel".to_string()),
+            });
+        }
+        let rows = ms.into_iter().map(threshold_to_row).collect();
+        let data = HashMap::from([(TBL_LABEL_THRESHOLD.to_string(), rows)]);
+        let _num_saved = self.datastore.save(data).await?;
+        Ok(())
+    }
+
+    async fn fetch_all_thresholds(&self) -> Result<Vec<StockAlertThresholdModel>, AppError> {
+        self.fetch_rows(TBL_LABEL_THRESHOLD, &InMemFiltAllOp)
+            .await?
+            .into_iter()
+            .map(threshold_from_row)
+            .filter(|r| {
+                r.as_ref()
+                    .map_or(true, |m| m.low_stock_qty > 0 || m.expiry_within_days > 0)
+            })
+            .collect()
+    }
+
+    async fn fetch_sent(&self, store_id: u32) -> Result<Vec<StockAlertModel>, AppError> {
+        self.fetch_rows(TBL_LABEL_SENT, &InMemFiltStoreOp(store_id))
+            .await?
+            .into_iter()
+            .map(sent_from_row)
+            .collect()
+    }
+
+    async fn claim_sent(
+        &self,
+        store_id: u32,
+        ms: Vec<StockAlertModel>,
+    ) -> Result<Vec<StockAlertModel>, AppError> {
+        if ms.is_empty() {
+            return Ok(ms);
+        }
+        let keys = ms.iter().map(|m| sent_pkey(store_id, m)).collect();
+        let info = HashMap::from([(TBL_LABEL_SENT.to_string(), keys)]);
+        let (mut saved, lock) = self.datastore.fetch_acquire(info).await?;
+        let saved = saved.remove(TBL_LABEL_SENT).unwrap_or_default();
+        let claimed = ms
+            .into_iter()
+            .filter(|m| !saved.contains_key(&sent_pkey(store_id, m)))
+            .collect::<Vec<_>>();
+        let rows = claimed
+            .iter()
+            .cloned()
+            .map(|m| sent_to_row(store_id, m))
+            .collect();
+        let data = HashMap::from([(TBL_LABEL_SENT.to_string(), rows)]);
+        let _num_saved = self.datastore.save_release(data, lock)?;
+        Ok(claimed)
+    }
+
+    async fn discard_sent(&self, store_id: u32, ms: Vec<StockAlertModel>) -> Result<(), AppError> {
+        if ms.is_empty() {
+            return Ok(());
+        }
+        let keys = ms.iter().map(|m| sent_pkey(store_id, m)).collect();
+        let info = HashMap::from([(TBL_LABEL_SENT.to_string(), keys)]);
+        let _num_deleted = self.datastore.delete(info).await?;
+        Ok(())
+    }
+} // end of impl AbsStockAlertRepo
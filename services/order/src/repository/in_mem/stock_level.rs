@@ -1,5 +1,5 @@
 use std::boxed::Box;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::result::Result as DefaultResult;
 use std::sync::Arc;
 
@@ -9,20 +9,24 @@ use chrono::{DateTime, FixedOffset};
 use ecommerce_common::error::AppErrorCode;
 
 use crate::api::rpc::dto::{StockLevelReturnDto, StockReturnErrorDto};
+use crate::api::web::dto::OrderLineCancelErrorDto;
 use crate::datastore::{
     AbstInMemoryDStore, AppInMemDstoreLock, AppInMemFetchedData, AppInMemFetchedSingleTable,
 };
 use crate::error::AppError;
 use crate::model::{
-    OrderLineModelSet, ProductStockIdentity, ProductStockIdentity2, ProductStockModel,
-    StockLevelModelSet, StockQtyRsvModel, StockQuantityModel, StoreStockModel,
+    OrderLineCancelModel, OrderLineIdentity, OrderLineModel, OrderLineModelSet,
+    ProductStockIdentity, ProductStockIdentity2, ProductStockModel, StockAllocPolicyModel,
+    StockAllocPolicyModelSet, StockAllocStrategy, StockLevelModelSet, StockQtyRsvModel,
+    StockQuantityModel, StockWarehouseModel, StockWarehouseModelSet, StoreStockModel,
 };
 
 use super::super::{
-    AbsOrderStockRepo, AppStockRepoReserveReturn, AppStockRepoReserveUserFunc,
-    AppStockRepoReturnUserFunc,
+    stock_return_corrupted, AbsOrderStockRepo, AppOrderRepoCancelLinesUserFunc,
+    AppStockRepoReserveReturn, AppStockRepoReserveUserFunc, AppStockRepoReturnUserFunc,
 };
 use super::order::OrderInMemRepo;
+use super::promotion::{inmem_claim_usage, inmem_release_usage, TABLE_LABEL as PROMO_TABLE_LABEL};
 
 mod _stockm {
     use super::{DateTime, FixedOffset, ProductStockIdentity2};
@@ -36,6 +40,7 @@ mod _stockm {
         QtyTotal,
         QtyRsvDetail,
         QtyCancelled,
+        Arrival,
         TotNumColumns,
     }
     impl From<InMemColIdx> for usize {
@@ -45,10 +50,57 @@ mod _stockm {
                 InMemColIdx::QtyTotal => 1,
                 InMemColIdx::QtyRsvDetail => 2,
                 InMemColIdx::QtyCancelled => 3,
-                InMemColIdx::TotNumColumns => 4,
+                InMemColIdx::Arrival => 4,
+                InMemColIdx::TotNumColumns => 5,
             }
         }
     }
+
+    pub(super) const ALLOC_TABLE_LABEL: &str = "order_stock_alloc_policy";
+    pub(super) enum AllocColIdx {
+        ProductId,
+        Strategy,
+        MinShelfDays,
+        TotNumColumns,
+    }
+    impl From<AllocColIdx> for usize {
+        fn from(value: AllocColIdx) -> usize {
+            match value {
+                AllocColIdx::ProductId => 0,
+                AllocColIdx::Strategy => 1,
+                AllocColIdx::MinShelfDays => 2,
+                AllocColIdx::TotNumColumns => 3,
+            }
+        }
+    }
+    pub(super) const WAREHOUSE_TABLE_LABEL: &str = "order_stock_warehouse";
+    pub(super) enum WarehouseColIdx {
+        Country,
+        Region,
+        City,
+        TotNumColumns,
+    }
+    impl From<WarehouseColIdx> for usize {
+        fn from(value: WarehouseColIdx) -> usize {
+            match value {
+                WarehouseColIdx::Country => 0,
+                WarehouseColIdx::Region => 1,
+                WarehouseColIdx::City => 2,
+                WarehouseColIdx::TotNumColumns => 3,
+            }
+        }
+    }
+    pub(super) struct InMemDStoreFiltAllocOp(pub HashSet<u32>);
+    impl AbsDStoreFilterKeyOp for InMemDStoreFiltAllocOp {
+        fn filter(&self, k: &String, _v: &Vec<String>) -> bool {
+            k.split('/')
+                .next()
+                .and_then(|v| v.parse::<u32>().ok())
+                .is_some_and(|store_id| self.0.contains(&store_id))
+        }
+    } // to fetch keys of allocation policies or warehouses in specific stores
+      // key of each stock batch is combo of store-id, product-id, expiry time
+      // and warehouse-id
     pub(super) struct InMemDStoreFiltKeyOp {
         // it is combo of seller-id, product-type as u8, product-id
         options: HashSet<(u32, u64)>,
@@ -87,10 +139,158 @@ mod _stockm {
             }
         }
     }
+
+    pub(super) struct InMemDStoreFiltStoreOp {
+        pub store_id: u32,
+        // empty list means all products in the store
+        pub product_ids: HashSet<u64>,
+        pub expiry_after: Option<DateTime<FixedOffset>>,
+        pub expiry_before: Option<DateTime<FixedOffset>>,
+    }
+    impl AbsDStoreFilterKeyOp for InMemDStoreFiltStoreOp {
+        fn filter(&self, k: &String, _v: &Vec<String>) -> bool {
+            let id_elms = k.split('/').collect::<Vec<&str>>();
+            let (store_id, prod_id, exp_from_combo) = (
+                id_elms[0].parse::<u32>().unwrap(),
+                id_elms[1].parse::<u64>().unwrap(),
+                DateTime::parse_from_str(id_elms[2], EXPIRY_KEY_FORMAT).unwrap(),
+            );
+            let prod_match = self.product_ids.is_empty() || self.product_ids.contains(&prod_id);
+            let after_ok = self
+                .expiry_after
+                .as_ref()
+                .is_none_or(|t| &exp_from_combo >= t);
+            let before_ok = self
+                .expiry_before
+                .as_ref()
+                .is_none_or(|t| &exp_from_combo <= t);
+            store_id == self.store_id && prod_match && after_ok && before_ok
+        }
+    } // to fetch keys of stock records in specific store regardless of expiry
 } // end of inner module _stockm
 
+fn alloc_strategy_to_str(value: StockAllocStrategy) -> &'static str {
+    match value {
+        StockAllocStrategy::Fefo => "FEFO",
+        StockAllocStrategy::Fifo => "FIFO",
+    }
+}
+
+// empty product ID in the key indicates default policy of the store
+fn alloc_policy_to_row(m: StockAllocPolicyModel) -> (String, Vec<String>) {
+    let prod_id = m.product_id.map(|v| v.to_string()).unwrap_or_default();
+    let pkey = format!("{}/{}", m.store_id, prod_id);
+    let mut row = (0.._stockm::AllocColIdx::TotNumColumns.into())
+        .map(|_n| String::new())
+        .collect::<Vec<String>>();
+    let _ = [
+        (_stockm::AllocColIdx::ProductId, prod_id),
+        (
+            _stockm::AllocColIdx::Strategy,
+            alloc_strategy_to_str(m.strategy).to_string(),
+        ),
+        (
+            _stockm::AllocColIdx::MinShelfDays,
+            m.min_shelf_days.to_string(),
+        ),
+    ]
+    .into_iter()
+    .map(|(idx, val)| {
+        let idx: usize = idx.into();
+        row[idx] = val;
+    })
+    .count();
+    (pkey, row)
+}
+
+fn alloc_policy_from_row(
+    (key, row): (String, Vec<String>),
+) -> DefaultResult<StockAllocPolicyModel, AppError> {
+    let corrupted = |detail: String| AppError {
+        code: AppErrorCode::DataCorruption,
+        detail: Some(detail),
+    };
+    let get_col =
+        |idx: _stockm::AllocColIdx| -> &str { row.get::<usize>(idx.into()).unwrap().as_str() };
+    let store_id = key
+        .split('/')
+        .next()
+        .and_then(|v| v.parse::<u32>().ok())
+        .ok_or(corrupted(format!("stock-alloc-key:{key}")))?;
+    let product_id = Some(get_col(_stockm::AllocColIdx::ProductId))
+        .filter(|v| !v.is_empty())
+        .map(|v| v.parse::<u64>())
+        .transpose()
+        .map_err(|e| corrupted(format!("stock-alloc-product:{e}")))?;
+    let strategy = match get_col(_stockm::AllocColIdx::Strategy) {
+        "FEFO" => StockAllocStrategy::Fefo,
+        "FIFO" => StockAllocStrategy::Fifo,
+        others => {
+            return Err(corrupted(format!("stock-alloc-strategy:{others}")));
+        }
+    };
+    let min_shelf_days = get_col(_stockm::AllocColIdx::MinShelfDays)
+        .parse::<u16>()
+        .map_err(|e| corrupted(format!("stock-alloc-shelf-days:{e}")))?;
+    Ok(StockAllocPolicyModel {
+        store_id,
+        product_id,
+        strategy,
+        min_shelf_days,
+    })
+}
+
+fn warehouse_to_row(m: StockWarehouseModel) -> (String, Vec<String>) {
+    let pkey = format!("{}/{}", m.store_id, m.warehouse_id);
+    let mut row = (0.._stockm::WarehouseColIdx::TotNumColumns.into())
+        .map(|_n| String::new())
+        .collect::<Vec<String>>();
+    let _ = [
+        (_stockm::WarehouseColIdx::Country, m.country.into()),
+        (_stockm::WarehouseColIdx::Region, m.region),
+        (_stockm::WarehouseColIdx::City, m.city),
+    ]
+    .into_iter()
+    .map(|(idx, val)| {
+        let idx: usize = idx.into();
+        row[idx] = val;
+    })
+    .count();
+    (pkey, row)
+}
+
+fn warehouse_from_row(
+    (key, row): (String, Vec<String>),
+) -> DefaultResult<StockWarehouseModel, AppError> {
+    let mut id_elms = key.split('/').map(|v| v.parse::<u32>());
+    let (store_id, warehouse_id) = match (id_elms.next(), id_elms.next()) {
+        (Some(Ok(v0)), Some(Ok(v1))) => (v0, v1),
+        _others => {
+            return Err(AppError {
+                code: AppErrorCode::DataCorruption,
+                detail: Some(format!("stock-warehouse-key:{key}")),
+            });
+        }
+    };
+    let get_col =
+        |idx: _stockm::WarehouseColIdx| -> String { row.get::<usize>(idx.into()).unwrap().clone() };
+    Ok(StockWarehouseModel {
+        store_id,
+        warehouse_id,
+        country: get_col(_stockm::WarehouseColIdx::Country).into(),
+        region: get_col(_stockm::WarehouseColIdx::Region),
+        city: get_col(_stockm::WarehouseColIdx::City),
+    })
+}
+
 // list of tuple with order-id and number of reserved for each order
 type FetchedRsv = Vec<(String, u32)>;
+type StockFetchedWithLock<'a> = (
+    StockLevelModelSet,
+    FetchedRsvSet,
+    AppInMemFetchedSingleTable,
+    AppInMemDstoreLock<'a>,
+);
 struct FetchedRsvSet(HashMap<String, FetchedRsv>);
 struct FetchArg(AppInMemFetchedSingleTable, Option<String>);
 struct SaveArg(StockLevelModelSet, FetchedRsvSet);
@@ -113,6 +313,7 @@ impl FetchArg {
     }
     fn to_product_stock(
         prod_id: u64,
+        warehouse_id: u32,
         row: Vec<String>,
         maybe_order_id: &Option<String>,
     ) -> ProductStockModel {
@@ -142,11 +343,17 @@ impl FetchArg {
             .get::<usize>(_stockm::InMemColIdx::Expiry.into())
             .unwrap();
         let expiry = DateTime::parse_from_rfc3339(expiry).unwrap();
+        let arrival = row
+            .get::<usize>(_stockm::InMemColIdx::Arrival.into())
+            .and_then(|v| DateTime::parse_from_rfc3339(v).ok())
+            .map(|t| t.to_utc());
         ProductStockModel {
             is_create: false,
             id_: prod_id,
+            warehouse_id,
             expiry: expiry.into(),
             quantity: StockQuantityModel::new(total, cancelled, booked, rsv_detail),
+            arrival,
         }
     }
 } // end of impl FetchArg
@@ -158,10 +365,11 @@ impl Into<StockLevelModelSet> for FetchArg {
         rows.into_iter()
             .map(|(key, row)| {
                 let id_elms = key.split('/').collect::<Vec<&str>>();
-                let (store_id, prod_id, exp_from_combo) = (
+                let (store_id, prod_id, exp_from_combo, warehouse_id) = (
                     id_elms[0].parse().unwrap(),
                     id_elms[1].parse::<u64>().unwrap(),
                     id_elms[2],
+                    id_elms[3].parse::<u32>().unwrap(),
                 );
                 let result = out.stores.iter_mut().find(|m| m.store_id == store_id);
                 let store_rd = if let Some(m) = result {
@@ -176,7 +384,9 @@ impl Into<StockLevelModelSet> for FetchArg {
                 };
                 let result = store_rd.products.iter().find(|m| {
                     let exp_fmt_verify = m.expiry.format(_stockm::EXPIRY_KEY_FORMAT).to_string();
-                    m.id_ == prod_id && exp_fmt_verify == exp_from_combo
+                    m.id_ == prod_id
+                        && m.warehouse_id == warehouse_id
+                        && exp_fmt_verify == exp_from_combo
                 });
                 if let Some(product_rd) = result {
                     // TODO, return error instead
@@ -185,7 +395,7 @@ impl Into<StockLevelModelSet> for FetchArg {
                         store_rd.store_id, product_rd.id_
                     );
                 } else {
-                    let m = Self::to_product_stock(prod_id, row, &maybe_order_id);
+                    let m = Self::to_product_stock(prod_id, warehouse_id, row, &maybe_order_id);
                     store_rd.products.push(m);
                 }
             })
@@ -213,7 +423,7 @@ impl From<SaveArg> for AppInMemFetchedSingleTable {
                 let exp_fmt = m2
                     .expiry_without_millis()
                     .format(_stockm::EXPIRY_KEY_FORMAT);
-                let pkey = format!("{}/{}/{}", m1.store_id, m2.id_, exp_fmt);
+                let pkey = format!("{}/{}/{}/{}", m1.store_id, m2.id_, exp_fmt, m2.warehouse_id);
                 let rsv_prod = if let Some(r) = rsv_set.get(pkey.as_str()) {
                     r.clone()
                 } else {
@@ -250,6 +460,10 @@ impl From<SaveArg> for AppInMemFetchedSingleTable {
                         m2.quantity.total.to_string(),
                     ),
                     (_stockm::InMemColIdx::Expiry, m2.expiry.to_rfc3339()),
+                    (
+                        _stockm::InMemColIdx::Arrival,
+                        m2.arrival.map(|t| t.to_rfc3339()).unwrap_or_default(),
+                    ),
                 ]
                 .into_iter()
                 .map(|(idx, val)| {
@@ -285,7 +499,10 @@ impl AbsOrderStockRepo for StockLvlInMemRepo {
             .into_iter()
             .map(|d| {
                 let exp_fmt = d.expiry.format(_stockm::EXPIRY_KEY_FORMAT);
-                format!("{}/{}/{}", d.store_id, d.product_id, exp_fmt)
+                format!(
+                    "{}/{}/{}/{}",
+                    d.store_id, d.product_id, exp_fmt, d.warehouse_id
+                )
             })
             .collect();
         let info = HashMap::from([(_stockm::TABLE_LABEL.to_string(), ids)]);
@@ -293,7 +510,49 @@ impl AbsOrderStockRepo for StockLvlInMemRepo {
         Self::try_into_modelset(None, resultset)
     } // end of fn fetch
 
-    async fn save(&self, slset: StockLevelModelSet) -> DefaultResult<(), AppError> {
+    async fn fetch_by_product(
+        &self,
+        pids: Vec<ProductStockIdentity2>,
+    ) -> DefaultResult<StockLevelModelSet, AppError> {
+        let tbl_label = _stockm::TABLE_LABEL.to_string();
+        let op = _stockm::InMemDStoreFiltKeyOp::new(pids, Some(self.curr_time));
+        let stock_ids = self.datastore.filter_keys(tbl_label.clone(), &op).await?;
+        let info = HashMap::from([(tbl_label, stock_ids)]);
+        let resultset = self.datastore.fetch(info).await?;
+        Self::try_into_modelset(None, resultset)
+    }
+
+    async fn fetch_by_store(
+        &self,
+        store_id: u32,
+        product_ids: Vec<u64>,
+        expiry_after: Option<DateTime<FixedOffset>>,
+        expiry_before: Option<DateTime<FixedOffset>>,
+    ) -> DefaultResult<StockLevelModelSet, AppError> {
+        let tbl_label = _stockm::TABLE_LABEL.to_string();
+        let op = _stockm::InMemDStoreFiltStoreOp {
+            store_id,
+            product_ids: HashSet::from_iter(product_ids),
+            expiry_after,
+            expiry_before,
+        };
+        let stock_ids = self.datastore.filter_keys(tbl_label.clone(), &op).await?;
+        let info = HashMap::from([(tbl_label, stock_ids)]);
+        let resultset = self.datastore.fetch(info).await?;
+        Self::try_into_modelset(None, resultset)
+    }
+
+    async fn save(&self, mut slset: StockLevelModelSet) -> DefaultResult<(), AppError> {
+        let time_now = self.curr_time.to_utc();
+        slset
+            .stores
+            .iter_mut()
+            .flat_map(|s| s.products.iter_mut())
+            .filter(|p| p.arrival.is_none())
+            .map(|p| {
+                p.arrival = Some(time_now);
+            })
+            .count();
         let rsv_set = {
             let ids = slset
                 .stores
@@ -301,7 +560,7 @@ impl AbsOrderStockRepo for StockLvlInMemRepo {
                 .flat_map(|s| {
                     s.products.iter().map(|p| {
                         let exp_fmt = p.expiry.format(_stockm::EXPIRY_KEY_FORMAT);
-                        format!("{}/{}/{}", s.store_id, p.id_, exp_fmt)
+                        format!("{}/{}/{}/{}", s.store_id, p.id_, exp_fmt, p.warehouse_id)
                     })
                 })
                 .collect();
@@ -317,36 +576,88 @@ impl AbsOrderStockRepo for StockLvlInMemRepo {
         Ok(())
     } // end of fn save
 
+    async fn fetch_alloc_policy(
+        &self,
+        store_ids: Vec<u32>,
+    ) -> DefaultResult<StockAllocPolicyModelSet, AppError> {
+        let tbl_label = _stockm::ALLOC_TABLE_LABEL.to_string();
+        let op = _stockm::InMemDStoreFiltAllocOp(HashSet::from_iter(store_ids));
+        let keys = self.datastore.filter_keys(tbl_label.clone(), &op).await?;
+        let info = HashMap::from([(tbl_label.clone(), keys)]);
+        let mut resultset = self.datastore.fetch(info).await?;
+        let policies = resultset
+            .remove(&tbl_label)
+            .unwrap_or_default()
+            .into_iter()
+            .map(alloc_policy_from_row)
+            .collect::<DefaultResult<Vec<_>, AppError>>()?;
+        Ok(StockAllocPolicyModelSet { policies })
+    }
+
+    async fn save_alloc_policy(
+        &self,
+        ms: Vec<StockAllocPolicyModel>,
+    ) -> DefaultResult<(), AppError> {
+        if ms.is_empty() {
+            return Err(AppError {
+                code: AppErrorCode::EmptyInputData,
+                detail: Some("save StockAllocPolicyModel".to_string()),
+            });
+        }
+        let rows = ms.into_iter().map(alloc_policy_to_row).collect();
+        let data = HashMap::from([(_stockm::ALLOC_TABLE_LABEL.to_string(), rows)]);
+        let _num_saved = self.datastore.save(data).await?;
+        Ok(())
+    }
+
+    async fn fetch_warehouses(
+        &self,
+        store_ids: Vec<u32>,
+    ) -> DefaultResult<StockWarehouseModelSet, AppError> {
+        let tbl_label = _stockm::WAREHOUSE_TABLE_LABEL.to_string();
+        let op = _stockm::InMemDStoreFiltAllocOp(HashSet::from_iter(store_ids));
+        let keys = self.datastore.filter_keys(tbl_label.clone(), &op).await?;
+        let info = HashMap::from([(tbl_label.clone(), keys)]);
+        let mut resultset = self.datastore.fetch(info).await?;
+        let warehouses = resultset
+            .remove(&tbl_label)
+            .unwrap_or_default()
+            .into_iter()
+            .map(warehouse_from_row)
+            .collect::<DefaultResult<Vec<_>, AppError>>()?;
+        Ok(StockWarehouseModelSet { warehouses })
+    }
+
+    async fn save_warehouses(&self, ms: Vec<StockWarehouseModel>) -> DefaultResult<(), AppError> {
+        if ms.is_empty() {
+            return Err(AppError {
+                code: AppErrorCode::EmptyInputData,
+                detail: Some("save StockWarehouseModel".to_string()),
+            });
+        }
+        let rows = ms.into_iter().map(warehouse_to_row).collect();
+        let data = HashMap::from([(_stockm::WAREHOUSE_TABLE_LABEL.to_string(), rows)]);
+        let _num_saved = self.datastore.save(data).await?;
+        Ok(())
+    }
+
     async fn try_reserve(
         &self,
         usr_cb: AppStockRepoReserveUserFunc,
-        order_req: &OrderLineModelSet,
+        order_req: &mut OrderLineModelSet,
     ) -> AppStockRepoReserveReturn {
-        let pids = order_req
-            .lines()
-            .iter()
-            .map(|d| ProductStockIdentity2 {
-                store_id: d.id().store_id(),
-                product_id: d.id().product_id(),
-            })
-            .collect();
-        let (mut stock_mset, rsv_set, d_lock) = self
-            .fetch_with_lock(order_req.id().to_string(), pids, Some(self.curr_time))
-            .await
-            .map_err(Err)?;
-        usr_cb(&mut stock_mset, order_req)?;
-        let data = {
-            let mut seq = OrderInMemRepo::gen_lowlvl_tablerows(order_req);
-            let rows = AppInMemFetchedSingleTable::from(SaveArg(stock_mset, rsv_set));
-            seq.insert(0, (_stockm::TABLE_LABEL.to_string(), rows));
-            HashMap::from_iter(seq)
-        };
-        if let Err(e) = self.datastore.save_release(data, d_lock) {
-            Err(Err(e))
-        } else {
-            Ok(())
-        }
-    } // end of fn try_reserve
+        self.reserve_common(usr_cb, order_req, true).await
+    }
+
+    async fn try_fulfil_backorder(
+        &self,
+        usr_cb: AppStockRepoReserveUserFunc,
+        order_req: &mut OrderLineModelSet,
+    ) -> AppStockRepoReserveReturn {
+        // the order has already been saved, the callback only allocates stock
+        // to its backordered lines, the promotions were claimed at that time
+        self.reserve_common(usr_cb, order_req, false).await
+    }
 
     async fn try_return(
         &self,
@@ -362,8 +673,8 @@ impl AbsOrderStockRepo for StockLvlInMemRepo {
             })
             .collect();
         // omit expiry check in the key filter
-        let (mut mset, rsv_set, d_lock) = self
-            .fetch_with_lock(data.order_id.clone(), pids, None)
+        let (mut mset, rsv_set, _promo_rows, d_lock) = self
+            .fetch_with_lock(data.order_id.clone(), pids, None, &[])
             .await?;
         let caller_errors = cb(&mut mset, data);
         if caller_errors.is_empty() {
@@ -374,6 +685,69 @@ impl AbsOrderStockRepo for StockLvlInMemRepo {
         }
         Ok(caller_errors)
     }
+
+    async fn try_cancel_return(
+        &self,
+        oid: &str,
+        pids: Vec<OrderLineIdentity>,
+        cancel: OrderLineCancelModel,
+        cancel_cb: AppOrderRepoCancelLinesUserFunc,
+        return_cb: AppStockRepoReturnUserFunc,
+    ) -> DefaultResult<Vec<OrderLineCancelErrorDto>, AppError> {
+        let tbl_label = _stockm::TABLE_LABEL.to_string();
+        let stock_ids = {
+            let stk_pids = pids
+                .iter()
+                .map(|p| ProductStockIdentity2 {
+                    store_id: p.store_id(),
+                    product_id: p.product_id(),
+                })
+                .collect();
+            // omit expiry check in the key filter
+            let op = _stockm::InMemDStoreFiltKeyOp::new(stk_pids, None);
+            self.datastore.filter_keys(tbl_label.clone(), &op).await?
+        };
+        let info = HashMap::from([
+            (tbl_label, stock_ids),
+            OrderInMemRepo::lines_fetch_keys(oid, &pids),
+        ]);
+        // order lines and stock levels are locked together
+        let (mut tableset, d_lock) = self.datastore.fetch_acquire(info).await?;
+        let mut lines: Vec<OrderLineModel> = OrderInMemRepo::lines_from_fetched(&mut tableset);
+        let caller_errors = cancel_cb(&mut lines, pids, cancel);
+        if !caller_errors.is_empty() {
+            return Ok(caller_errors);
+        }
+        let mut data = HashMap::from([OrderInMemRepo::gen_lines_tablerows(oid, &lines)]);
+        let returning = OrderLineModel::cancelled_stock_return(oid, &lines);
+        if !returning.items.is_empty() {
+            let rsv_set = {
+                let rows = tableset.values().next().unwrap();
+                FetchedRsvSet::from(rows)
+            };
+            let mut mset = Self::try_into_modelset(Some(oid.to_string()), tableset)?;
+            let return_errors = return_cb(&mut mset, returning);
+            if !return_errors.is_empty() {
+                return Err(stock_return_corrupted(return_errors));
+            }
+            let rows = AppInMemFetchedSingleTable::from(SaveArg(mset, rsv_set));
+            let _ = data.insert(_stockm::TABLE_LABEL.to_string(), rows);
+        }
+        let _num_saved = self.datastore.save_release(data, d_lock)?;
+        Ok(caller_errors)
+    } // end of fn try_cancel_return
+
+    async fn release_promotion_usage(&self, codes: Vec<String>) -> DefaultResult<(), AppError> {
+        if codes.is_empty() {
+            return Ok(());
+        }
+        let info = HashMap::from([(PROMO_TABLE_LABEL.to_string(), codes)]);
+        let (mut saved, lock) = self.datastore.fetch_acquire(info).await?;
+        let rows = inmem_release_usage(saved.remove(PROMO_TABLE_LABEL).unwrap_or_default());
+        let data = HashMap::from([(PROMO_TABLE_LABEL.to_string(), rows)]);
+        let _num_saved = self.datastore.save_release(data, lock)?;
+        Ok(())
+    }
 } // end of impl StockLvlInMemRepo
 
 impl StockLvlInMemRepo {
@@ -382,6 +756,9 @@ impl StockLvlInMemRepo {
         curr_time: DateTime<FixedOffset>,
     ) -> DefaultResult<Self, AppError> {
         m.create_table(_stockm::TABLE_LABEL).await?;
+        m.create_table(_stockm::ALLOC_TABLE_LABEL).await?;
+        m.create_table(_stockm::WAREHOUSE_TABLE_LABEL).await?;
+        m.create_table(PROMO_TABLE_LABEL).await?;
         let out = Self {
             datastore: m.clone(),
             curr_time,
@@ -389,23 +766,82 @@ impl StockLvlInMemRepo {
         Ok(out)
     }
 
+    async fn reserve_common(
+        &self,
+        usr_cb: AppStockRepoReserveUserFunc,
+        order_req: &mut OrderLineModelSet,
+        claim_promotion: bool,
+    ) -> AppStockRepoReserveReturn {
+        let pids = order_req
+            .lines()
+            .iter()
+            .map(|d| ProductStockIdentity2 {
+                store_id: d.id().store_id(),
+                product_id: d.id().product_id(),
+            })
+            .collect();
+        let store_ids = order_req
+            .lines()
+            .iter()
+            .map(|d| d.id().store_id())
+            .collect();
+        let policies = self.fetch_alloc_policy(store_ids).await.map_err(Err)?;
+        let promo_codes = if claim_promotion {
+            order_req.redeemed_promotions()
+        } else {
+            Vec::new()
+        };
+        let (mut stock_mset, rsv_set, promo_rows, d_lock) = self
+            .fetch_with_lock(
+                order_req.id().to_string(),
+                pids,
+                Some(self.curr_time),
+                &promo_codes,
+            )
+            .await
+            .map_err(Err)?;
+        usr_cb(&mut stock_mset, &policies, order_req)?;
+        let promo_rows = inmem_claim_usage(&promo_codes, promo_rows)
+            .map_err(|codes| Ok(order_req.promotion_exhausted_errors(&codes)))?;
+        let data = {
+            let mut seq = OrderInMemRepo::gen_lowlvl_tablerows(order_req);
+            let rows = AppInMemFetchedSingleTable::from(SaveArg(stock_mset, rsv_set));
+            seq.insert(0, (_stockm::TABLE_LABEL.to_string(), rows));
+            if !promo_codes.is_empty() {
+                seq.push((PROMO_TABLE_LABEL.to_string(), promo_rows));
+            }
+            HashMap::from_iter(seq)
+        };
+        if let Err(e) = self.datastore.save_release(data, d_lock) {
+            Err(Err(e))
+        } else {
+            Ok(())
+        }
+    } // end of fn reserve_common
+
+    // promotions are locked together with the stock levels
     async fn fetch_with_lock(
         &self,
         order_id: String,
         pids: Vec<ProductStockIdentity2>,
         curr_time: Option<DateTime<FixedOffset>>,
-    ) -> DefaultResult<(StockLevelModelSet, FetchedRsvSet, AppInMemDstoreLock), AppError> {
+        promo_codes: &[String],
+    ) -> DefaultResult<StockFetchedWithLock<'_>, AppError> {
         let tbl_label = _stockm::TABLE_LABEL.to_string();
         let op = _stockm::InMemDStoreFiltKeyOp::new(pids, curr_time);
         let stock_ids = self.datastore.filter_keys(tbl_label.clone(), &op).await?;
-        let info = HashMap::from([(tbl_label, stock_ids)]);
-        let (tableset, lock) = self.datastore.fetch_acquire(info).await?;
+        let mut info = HashMap::from([(tbl_label, stock_ids)]);
+        if !promo_codes.is_empty() {
+            info.insert(PROMO_TABLE_LABEL.to_string(), promo_codes.to_vec());
+        }
+        let (mut tableset, lock) = self.datastore.fetch_acquire(info).await?;
+        let promo_rows = tableset.remove(PROMO_TABLE_LABEL);
         let rsv_set = {
             let rows = tableset.values().next().unwrap();
             FetchedRsvSet::from(rows)
         };
         let ms = Self::try_into_modelset(Some(order_id), tableset)?;
-        Ok((ms, rsv_set, lock))
+        Ok((ms, rsv_set, promo_rows.unwrap_or_default(), lock))
     }
     fn try_into_modelset(
         order_id: Option<String>,
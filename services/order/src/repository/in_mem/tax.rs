@@ -0,0 +1,144 @@
+use async_trait::async_trait;
+use std::boxed::Box;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use rust_decimal::Decimal;
+
+use ecommerce_common::api::dto::CountryCode;
+use ecommerce_common::error::AppErrorCode;
+
+use super::super::AbsTaxRateRepo;
+use crate::datastore::{AbstInMemoryDStore, AppInMemFetchedSingleRow};
+use crate::error::AppError;
+use crate::model::{TaxRateModel, TaxRateModelSet};
+
+const TABLE_LABEL: &str = "tax_rate";
+
+enum InMemColIdx {
+    StoreId,
+    Country,
+    Region,
+    Rate,
+    TotNumColumns,
+}
+
+impl From<InMemColIdx> for usize {
+    fn from(value: InMemColIdx) -> usize {
+        match value {
+            InMemColIdx::StoreId => 0,
+            InMemColIdx::Country => 1,
+            InMemColIdx::Region => 2,
+            InMemColIdx::Rate => 3,
+            InMemColIdx::TotNumColumns => 4,
+        }
+    }
+}
+
+// zero store ID and empty region indicate the rate applied to all stores
+// and whole country
+fn inmem_pkey(store_id: u32, country: CountryCode, region: &str) -> String {
+    let country: String = country.into();
+    format!("{store_id}/{country}/{region}")
+}
+
+fn inmem_to_model(row: AppInMemFetchedSingleRow) -> Result<TaxRateModel, AppError> {
+    let get_col = |idx: InMemColIdx| -> &str { row.get::<usize>(idx.into()).unwrap().as_str() };
+    let store_id = get_col(InMemColIdx::StoreId)
+        .parse::<u32>()
+        .ok()
+        .filter(|v| *v != 0);
+    let country = CountryCode::from(get_col(InMemColIdx::Country).to_string());
+    let region = Some(get_col(InMemColIdx::Region))
+        .filter(|r| !r.is_empty())
+        .map(String::from);
+    let rate = Decimal::from_str(get_col(InMemColIdx::Rate)).map_err(|e| AppError {
+        code: AppErrorCode::DataCorruption,
+        detail: Some(format!("tax-rate: {e}")),
+    })?;
+    Ok(TaxRateModel {
+        country,
+        region,
+        store_id,
+        rate,
+    })
+}
+
+fn inmem_to_row(m: TaxRateModel) -> (String, AppInMemFetchedSingleRow) {
+    let store_id = m.store_id.unwrap_or(0);
+    let region = m.region.unwrap_or_default();
+    let pkey = inmem_pkey(store_id, m.country.clone(), region.as_str());
+    let mut row = (0..InMemColIdx::TotNumColumns.into())
+        .map(|_n| String::new())
+        .collect::<Vec<String>>();
+    let _ = [
+        (InMemColIdx::StoreId, store_id.to_string()),
+        (InMemColIdx::Country, m.country.into()),
+        (InMemColIdx::Region, region),
+        (InMemColIdx::Rate, m.rate.to_string()),
+    ]
+    .into_iter()
+    .map(|(idx, val)| {
+        let idx: usize = idx.into();
+        row[idx] = val;
+    })
+    .count();
+    (pkey, row)
+}
+
+pub struct TaxRateInMemRepo {
+    datastore: Arc<Box<dyn AbstInMemoryDStore>>,
+}
+
+impl TaxRateInMemRepo {
+    pub async fn new(m: Arc<Box<dyn AbstInMemoryDStore>>) -> Result<Self, AppError> {
+        m.create_table(TABLE_LABEL).await?;
+        Ok(Self { datastore: m })
+    }
+}
+
+#[async_trait]
+impl AbsTaxRateRepo for TaxRateInMemRepo {
+    async fn fetch(
+        &self,
+        country: CountryCode,
+        region: String,
+        mut store_ids: Vec<u32>,
+    ) -> Result<TaxRateModelSet, AppError> {
+        store_ids.push(0);
+        store_ids.sort();
+        store_ids.dedup();
+        let keys = store_ids
+            .into_iter()
+            .flat_map(|s| {
+                [
+                    inmem_pkey(s, country.clone(), region.as_str()),
+                    inmem_pkey(s, country.clone(), ""),
+                ]
+            })
+            .collect();
+        let info = HashMap::from([(TABLE_LABEL.to_string(), keys)]);
+        let mut result_raw = self.datastore.fetch(info).await?;
+        let rates = result_raw
+            .remove(TABLE_LABEL)
+            .unwrap_or_default()
+            .into_values()
+            .map(inmem_to_model)
+            .collect::<Result<Vec<_>, AppError>>()?;
+        Ok(TaxRateModelSet { rates })
+    }
+
+    async fn save(&self, ms: TaxRateModelSet) -> Result<(), AppError> {
+        if ms.rates.is_empty() {
+            return Err(AppError {
+                code: AppErrorCode::EmptyInputData,
+                detail: Some("save TaxRateModel".to_string()),
+            });
+        }
+        let rows = ms.rates.into_iter().map(inmem_to_row).collect();
+        let data = HashMap::from([(TABLE_LABEL.to_string(), rows)]);
+        let _num_saved = self.datastore.save(data).await?;
+        Ok(())
+    }
+} // end of impl AbsTaxRateRepo
@@ -2,6 +2,7 @@ use std::result::Result as DefaultResult;
 use std::sync::Arc;
 
 use async_trait::async_trait;
+use chrono::{DateTime, FixedOffset, NaiveDateTime};
 use sqlx::database::Database as AbstractDatabase;
 use sqlx::mysql::{MySqlArguments, MySqlRow};
 use sqlx::{Acquire, Arguments, Executor, IntoArguments, MySql, Row, Statement};
@@ -20,11 +21,20 @@ struct InsertUpdateTopLvlArg<'a>(&'a CartModel);
 struct InsertLineArg(u32, u8, Vec<CartLineModel>);
 struct UpdateLineArg(u32, u8, Vec<CartLineModel>);
 struct DiscardLineArg(u32, u8);
+struct DiscardLineByPidArg(u32, u8, Vec<BaseProductIdentity>);
 struct DiscardTopLvlArg(u32, u8);
+struct UpdateSharableArg<'a>(&'a CartModel);
+struct InsertCollaboratorArg(u32, u8, Vec<u32>);
+struct DiscardCollaboratorArg(u32, u8);
+struct InitCheckoutClaimArg(u32, u8);
+struct FetchCheckoutClaimArg(u32, u8);
+struct UpdateCheckoutClaimArg(u32, u8, NaiveDateTime);
+struct DiscardCheckoutClaimArg(u32, u8);
 
 struct FetchTotNumLinesArg(u32, u8);
 struct FetchTopLvlArg(u32, u8);
 struct FetchLinesArg(u32, u8, Option<Vec<BaseProductIdentity>>);
+struct FetchCollaboratorArg(u32, u8);
 
 impl<'a> From<InsertUpdateTopLvlArg<'a>> for (String, MySqlArguments) {
     fn from(value: InsertUpdateTopLvlArg<'a>) -> (String, MySqlArguments) {
@@ -39,48 +49,138 @@ impl<'a> From<InsertUpdateTopLvlArg<'a>> for (String, MySqlArguments) {
     }
 }
 
-impl InsertLineArg {
-    fn sql_pattern(num_batch: usize) -> String {
-        let col_seq = (0..num_batch)
-            .map(|_| "(?,?,?,?,?)")
-            .collect::<Vec<_>>()
-            .join(",");
-        format!(
-            "INSERT INTO `cart_line_detail`(`usr_id`,`seq`,`store_id`,\
-            `product_id`,`quantity`) VALUES {col_seq}"
-        )
+impl<'a> From<UpdateSharableArg<'a>> for (String, MySqlArguments) {
+    fn from(value: UpdateSharableArg<'a>) -> (String, MySqlArguments) {
+        let sql_patt = "INSERT INTO `cart_toplvl_meta`(`usr_id`,`seq`,`title`,`sharable`,\
+                        `owner_quota_lines`) VALUES (?,?,?,?,?) ON DUPLICATE KEY UPDATE \
+                        `sharable`=?,`owner_quota_lines`=?";
+        let mut args = MySqlArguments::default();
+        args.add(value.0.owner).unwrap();
+        args.add(value.0.seq_num).unwrap();
+        args.add(value.0.title.clone()).unwrap();
+        args.add(value.0.sharable).unwrap();
+        args.add(value.0.owner_quota_lines).unwrap();
+        args.add(value.0.sharable).unwrap();
+        args.add(value.0.owner_quota_lines).unwrap();
+        (sql_patt.to_string(), args)
     }
 }
-impl<'q> IntoArguments<'q, MySql> for InsertLineArg {
-    fn into_arguments(self) -> <MySql as AbstractDatabase>::Arguments<'q> {
+
+impl From<InsertCollaboratorArg> for (String, MySqlArguments) {
+    fn from(value: InsertCollaboratorArg) -> (String, MySqlArguments) {
+        let (usr_id, seq_num, collaborators) = (value.0, value.1, value.2);
+        let col_seq = (0..collaborators.len())
+            .map(|_| "(?,?,?)")
+            .collect::<Vec<_>>()
+            .join(",");
+        let sql_patt = format!(
+            "INSERT INTO `cart_collaborator`(`usr_id`,`seq`,`collaborator_id`) VALUES {col_seq}"
+        );
         let mut args = MySqlArguments::default();
-        let (usr_id, seq_num, lines) = (self.0, self.1, self.2);
-        lines
+        collaborators
             .into_iter()
-            .map(|line| {
-                let (id_, quantity) = (line.id_, line.qty_req);
-                let BaseProductIdentity {
-                    store_id,
-                    product_id,
-                } = id_;
+            .map(|c_id| {
                 args.add(usr_id).unwrap();
                 args.add(seq_num).unwrap();
-                args.add(store_id).unwrap();
-                args.add(product_id).unwrap();
-                args.add(quantity).unwrap();
+                args.add(c_id).unwrap();
             })
             .count();
-        args
+        (sql_patt, args)
     }
 }
-impl From<InsertLineArg> for (String, MySqlArguments) {
-    fn from(value: InsertLineArg) -> (String, MySqlArguments) {
-        (
-            InsertLineArg::sql_pattern(value.2.len()),
-            value.into_arguments(),
+
+impl From<DiscardCollaboratorArg> for (String, MySqlArguments) {
+    fn from(value: DiscardCollaboratorArg) -> (String, MySqlArguments) {
+        let (usr_id, seq_num) = (value.0, value.1);
+        let sql_patt = "DELETE FROM `cart_collaborator` WHERE `usr_id`=? AND `seq`=?";
+        let mut args = MySqlArguments::default();
+        args.add(usr_id).unwrap();
+        args.add(seq_num).unwrap();
+        (sql_patt.to_string(), args)
+    }
+}
+
+impl From<InitCheckoutClaimArg> for (String, MySqlArguments) {
+    fn from(value: InitCheckoutClaimArg) -> (String, MySqlArguments) {
+        // the claim record is created in expired state, so the following locking
+        // read always locks an existing row instead of a gap
+        let sql_patt = "INSERT IGNORE INTO `cart_checkout_claim`(`usr_id`,`seq`,`claim_until`) \
+                        VALUES (?,?,'1970-01-01 00:00:01')";
+        let mut args = MySqlArguments::default();
+        args.add(value.0).unwrap();
+        args.add(value.1).unwrap();
+        (sql_patt.to_string(), args)
+    }
+}
+
+impl From<FetchCheckoutClaimArg> for (String, MySqlArguments) {
+    fn from(value: FetchCheckoutClaimArg) -> (String, MySqlArguments) {
+        let sql_patt = "SELECT `claim_until` FROM `cart_checkout_claim` \
+                        WHERE `usr_id`=? AND `seq`=? FOR UPDATE";
+        let mut args = MySqlArguments::default();
+        args.add(value.0).unwrap();
+        args.add(value.1).unwrap();
+        (sql_patt.to_string(), args)
+    }
+}
+
+impl From<UpdateCheckoutClaimArg> for (String, MySqlArguments) {
+    fn from(value: UpdateCheckoutClaimArg) -> (String, MySqlArguments) {
+        let sql_patt = "UPDATE `cart_checkout_claim` SET `claim_until`=? \
+                        WHERE `usr_id`=? AND `seq`=?";
+        let mut args = MySqlArguments::default();
+        args.add(value.2).unwrap();
+        args.add(value.0).unwrap();
+        args.add(value.1).unwrap();
+        (sql_patt.to_string(), args)
+    }
+}
+
+impl From<DiscardCheckoutClaimArg> for (String, MySqlArguments) {
+    fn from(value: DiscardCheckoutClaimArg) -> (String, MySqlArguments) {
+        let sql_patt = "DELETE FROM `cart_checkout_claim` WHERE `usr_id`=? AND `seq`=?";
+        let mut args = MySqlArguments::default();
+        args.add(value.0).unwrap();
+        args.add(value.1).unwrap();
+        (sql_patt.to_string(), args)
+    }
+}
+
+impl InsertLineArg {
+    fn sql_pattern(num_batch: usize) -> String {
+        let col_seq = (0..num_batch)
+            .map(|_| "(?,?,?,?,?,?)")
+            .collect::<Vec<_>>()
+            .join(",");
+        format!(
+            "INSERT INTO `cart_line_detail`(`usr_id`,`seq`,`store_id`,\
+            `product_id`,`quantity`,`chosen_attr`) VALUES {col_seq}"
         )
     }
 }
+impl TryFrom<InsertLineArg> for (String, MySqlArguments) {
+    type Error = AppError;
+    fn try_from(value: InsertLineArg) -> DefaultResult<(String, MySqlArguments), AppError> {
+        let mut args = MySqlArguments::default();
+        let (usr_id, seq_num, lines) = (value.0, value.1, value.2);
+        let num_batch = lines.len();
+        for line in lines {
+            let attr = line.serialize_attr()?;
+            let (id_, quantity) = (line.id_, line.qty_req);
+            let BaseProductIdentity {
+                store_id,
+                product_id,
+            } = id_;
+            args.add(usr_id).unwrap();
+            args.add(seq_num).unwrap();
+            args.add(store_id).unwrap();
+            args.add(product_id).unwrap();
+            args.add(quantity).unwrap();
+            args.add(attr).unwrap();
+        }
+        Ok((InsertLineArg::sql_pattern(num_batch), args))
+    }
+}
 
 impl UpdateLineArg {
     fn sql_pattern(num_batch: usize) -> String {
@@ -94,15 +194,18 @@ impl UpdateLineArg {
             .join("OR");
         // `usr_id`,`seq`,`store_id`,`product_id`
         format!(
-            "UPDATE `cart_line_detail` SET `quantity` = CASE {case_op} ELSE `quantity` END \
+            "UPDATE `cart_line_detail` SET `quantity` = CASE {case_op} ELSE `quantity` END, \
+                `chosen_attr` = CASE {case_op} ELSE `chosen_attr` END \
                 WHERE `usr_id`=? AND `seq`=?  AND ({where_op})"
         )
     }
 }
-impl<'a> IntoArguments<'a, MySql> for UpdateLineArg {
-    fn into_arguments(self) -> <MySql as AbstractDatabase>::Arguments<'a> {
+impl TryFrom<UpdateLineArg> for (String, MySqlArguments) {
+    type Error = AppError;
+    fn try_from(value: UpdateLineArg) -> DefaultResult<(String, MySqlArguments), AppError> {
         let mut args = MySqlArguments::default();
-        let (usr_id, seq, lines) = (self.0, self.1, self.2);
+        let (usr_id, seq, lines) = (value.0, value.1, value.2);
+        let num_batch = lines.len();
         lines
             .iter()
             .map(|line| {
@@ -112,6 +215,12 @@ impl<'a> IntoArguments<'a, MySql> for UpdateLineArg {
                 args.add(qty).unwrap();
             })
             .count();
+        for line in lines.iter() {
+            let attr = line.serialize_attr()?;
+            args.add(line.id_.store_id).unwrap();
+            args.add(line.id_.product_id).unwrap();
+            args.add(attr).unwrap();
+        }
         args.add(usr_id).unwrap();
         args.add(seq).unwrap();
         lines
@@ -122,15 +231,7 @@ impl<'a> IntoArguments<'a, MySql> for UpdateLineArg {
                 args.add(p_id).unwrap();
             })
             .count();
-        args
-    }
-}
-impl From<UpdateLineArg> for (String, MySqlArguments) {
-    fn from(value: UpdateLineArg) -> (String, MySqlArguments) {
-        (
-            UpdateLineArg::sql_pattern(value.2.len()),
-            value.into_arguments(),
-        )
+        Ok((UpdateLineArg::sql_pattern(num_batch), args))
     }
 }
 
@@ -156,6 +257,28 @@ impl From<DiscardLineArg> for (String, MySqlArguments) {
     }
 }
 
+impl From<DiscardLineByPidArg> for (String, MySqlArguments) {
+    fn from(value: DiscardLineByPidArg) -> (String, MySqlArguments) {
+        let (usr_id, seq_num, pids) = (value.0, value.1, value.2);
+        let where_op = (0..pids.len())
+            .map(|_| "(`store_id`=? AND `product_id`=?)")
+            .collect::<Vec<_>>()
+            .join("OR");
+        let sql_patt =
+            format!("DELETE FROM `cart_line_detail` WHERE `usr_id`=? AND `seq`=? AND ({where_op})");
+        let mut args = MySqlArguments::default();
+        args.add(usr_id).unwrap();
+        args.add(seq_num).unwrap();
+        pids.into_iter()
+            .map(|id_| {
+                args.add(id_.store_id).unwrap();
+                args.add(id_.product_id).unwrap();
+            })
+            .count();
+        (sql_patt, args)
+    }
+}
+
 impl From<FetchTotNumLinesArg> for (String, MySqlArguments) {
     fn from(value: FetchTotNumLinesArg) -> (String, MySqlArguments) {
         let (usr_id, seq_num) = (value.0, value.1);
@@ -170,7 +293,19 @@ impl From<FetchTotNumLinesArg> for (String, MySqlArguments) {
 impl From<FetchTopLvlArg> for (String, MySqlArguments) {
     fn from(value: FetchTopLvlArg) -> (String, MySqlArguments) {
         let (usr_id, seq_num) = (value.0, value.1);
-        let sql_patt = "SELECT `usr_id`,`seq`,`title` FROM `cart_toplvl_meta` \
+        let sql_patt = "SELECT `usr_id`,`seq`,`title`,`sharable`,`owner_quota_lines` \
+                        FROM `cart_toplvl_meta` WHERE `usr_id`=? AND `seq`=?";
+        let mut args = MySqlArguments::default();
+        args.add(usr_id).unwrap();
+        args.add(seq_num).unwrap();
+        (sql_patt.to_string(), args)
+    }
+}
+
+impl From<FetchCollaboratorArg> for (String, MySqlArguments) {
+    fn from(value: FetchCollaboratorArg) -> (String, MySqlArguments) {
+        let (usr_id, seq_num) = (value.0, value.1);
+        let sql_patt = "SELECT `collaborator_id` FROM `cart_collaborator` \
                         WHERE `usr_id`=? AND `seq`=?";
         let mut args = MySqlArguments::default();
         args.add(usr_id).unwrap();
@@ -181,7 +316,7 @@ impl From<FetchTopLvlArg> for (String, MySqlArguments) {
 
 impl FetchLinesArg {
     fn sql_pattern(num_batch: usize) -> String {
-        let mut sql_patt = "SELECT `store_id`,`product_id`,`quantity`\
+        let mut sql_patt = "SELECT `store_id`,`product_id`,`quantity`,`chosen_attr` \
                         FROM `cart_line_detail` WHERE `usr_id`=? AND `seq`=?"
             .to_string();
         if num_batch > 0 {
@@ -233,12 +368,17 @@ impl TryFrom<MySqlRow> for CartModel {
         let owner = row.try_get::<u32, usize>(0)?;
         let seq_num = row.try_get::<u8, usize>(1)?;
         let title = row.try_get::<String, usize>(2)?;
+        let sharable = row.try_get::<bool, usize>(3)?;
+        let owner_quota_lines = row.try_get::<u32, usize>(4)?;
         Ok(Self {
             owner,
             seq_num,
             title,
             saved_lines: Vec::new(),
             new_lines: Vec::new(),
+            sharable,
+            collaborators: Vec::new(),
+            owner_quota_lines,
         })
     }
 }
@@ -248,11 +388,16 @@ impl TryFrom<MySqlRow> for CartLineModel {
         let store_id = row.try_get::<u32, usize>(0)?;
         let product_id = row.try_get::<u64, usize>(1)?;
         let qty_req = row.try_get::<u32, usize>(2)?;
+        let chosen_attr = row
+            .try_get::<Option<String>, usize>(3)?
+            .map(|raw| CartLineModel::deserialize_attr(raw.as_str()))
+            .transpose()?;
         Ok(Self {
             id_: BaseProductIdentity {
                 store_id,
                 product_id,
             },
+            chosen_attr,
             qty_req,
         })
     }
@@ -275,11 +420,11 @@ impl AbsCartRepo for CartMariaDbRepo {
             (obj.owner, obj.seq_num, obj.saved_lines, obj.new_lines);
         let (num_updating, num_inserting) = (saved_lines.len(), new_lines.len());
         if !saved_lines.is_empty() {
-            let (sql_patt, args) = UpdateLineArg(usr_id, seq_num, saved_lines).into();
+            let (sql_patt, args) = UpdateLineArg(usr_id, seq_num, saved_lines).try_into()?;
             let _rs = run_query_once(&mut tx, sql_patt, args, Some(num_updating)).await?;
         }
         if !new_lines.is_empty() {
-            let (sql_patt, args) = InsertLineArg(usr_id, seq_num, new_lines).into();
+            let (sql_patt, args) = InsertLineArg(usr_id, seq_num, new_lines).try_into()?;
             let _rs = run_query_once(&mut tx, sql_patt, args, Some(num_inserting)).await?;
         }
         tx.commit().await?;
@@ -291,6 +436,8 @@ impl AbsCartRepo for CartMariaDbRepo {
         let mut tx = conn.begin().await?;
         let (sql_patt, args) = DiscardLineArg(owner, seq).into();
         let _rs = run_query_once(&mut tx, sql_patt, args, None).await?;
+        let (sql_patt, args) = DiscardCollaboratorArg(owner, seq).into();
+        let _rs = run_query_once(&mut tx, sql_patt, args, None).await?;
         let (sql_patt, args) = DiscardTopLvlArg(owner, seq).into();
         let _rs = run_query_once(&mut tx, sql_patt, args, Some(1)).await?;
         tx.commit().await?;
@@ -330,6 +477,81 @@ impl AbsCartRepo for CartMariaDbRepo {
             .await?;
         Ok(out)
     } // end of fn fetch_lines_by_pid
+
+    async fn update_collaborators(&self, obj: &CartModel) -> DefaultResult<(), AppError> {
+        let mut conn = self._db.acquire().await?;
+        let mut tx = conn.begin().await?;
+        let (sql_patt, args) = UpdateSharableArg(obj).into();
+        let _rs = run_query_once(&mut tx, sql_patt, args, None).await?;
+        // simply replace the entire list, number of collaborators per cart is small
+        let (sql_patt, args) = DiscardCollaboratorArg(obj.owner, obj.seq_num).into();
+        let _rs = run_query_once(&mut tx, sql_patt, args, None).await?;
+        if !obj.collaborators.is_empty() {
+            let num_inserting = obj.collaborators.len();
+            let arg = InsertCollaboratorArg(obj.owner, obj.seq_num, obj.collaborators.clone());
+            let (sql_patt, args) = arg.into();
+            let _rs = run_query_once(&mut tx, sql_patt, args, Some(num_inserting)).await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    } // end of fn update_collaborators
+
+    async fn discard_lines(
+        &self,
+        owner: u32,
+        seq: u8,
+        pids: Vec<BaseProductIdentity>,
+    ) -> DefaultResult<usize, AppError> {
+        if pids.is_empty() {
+            return Ok(0);
+        }
+        let (sql_patt, args) = DiscardLineByPidArg(owner, seq, pids).into();
+        let mut conn = self._db.acquire().await?;
+        let mut tx = conn.begin().await?;
+        let rs = run_query_once(&mut tx, sql_patt, args, None).await?;
+        tx.commit().await?;
+        Ok(rs.rows_affected() as usize)
+    }
+
+    async fn try_claim_checkout(
+        &self,
+        owner: u32,
+        seq: u8,
+        time_now: DateTime<FixedOffset>,
+        expiry: DateTime<FixedOffset>,
+    ) -> DefaultResult<bool, AppError> {
+        let mut conn = self._db.acquire().await?;
+        let mut tx = conn.begin().await?;
+        let (sql_patt, args) = InitCheckoutClaimArg(owner, seq).into();
+        let _rs = run_query_once(&mut tx, sql_patt, args, None).await?;
+        let (sql_patt, args) = FetchCheckoutClaimArg(owner, seq).into();
+        let row = {
+            let stmt = tx.prepare(sql_patt.as_str()).await?;
+            let query = stmt.query_with(args);
+            let exec = &mut *tx;
+            exec.fetch_one(query).await?
+        };
+        let claim_until = row.try_get::<NaiveDateTime, usize>(0)?;
+        if time_now.naive_utc() < claim_until {
+            tx.rollback().await?;
+            Ok(false)
+        } else {
+            let arg = UpdateCheckoutClaimArg(owner, seq, expiry.naive_utc());
+            let (sql_patt, args) = arg.into();
+            let _rs = run_query_once(&mut tx, sql_patt, args, Some(1)).await?;
+            tx.commit().await?;
+            Ok(true)
+        }
+    } // end of fn try_claim_checkout
+
+    async fn release_checkout(&self, owner: u32, seq: u8) -> DefaultResult<(), AppError> {
+        let (sql_patt, args) = DiscardCheckoutClaimArg(owner, seq).into();
+        let mut conn = self._db.acquire().await?;
+        let mut tx = conn.begin().await?;
+        let _rs = run_query_once(&mut tx, sql_patt, args, None).await?;
+        tx.commit().await?;
+        Ok(())
+    }
 } // end of impl CartMariaDbRepo
 
 impl CartMariaDbRepo {
@@ -360,6 +582,15 @@ impl CartMariaDbRepo {
         };
         if let Some(row) = result {
             let mut cart = CartModel::try_from(row)?;
+            let (sql_patt, args) = FetchCollaboratorArg(owner, seq).into();
+            let stmt = conn.prepare(sql_patt.as_str()).await?;
+            let query = stmt.query_with(args);
+            let exec = &mut *conn;
+            let rows = exec.fetch_all(query).await?;
+            cart.collaborators = rows
+                .into_iter()
+                .map(|row| row.try_get::<u32, usize>(0))
+                .collect::<DefaultResult<Vec<_>, _>>()?;
             let stmt = conn.prepare(rawsql_line.0.as_str()).await?;
             let query = stmt.query_with(rawsql_line.1);
             let exec = &mut *conn;
@@ -386,6 +617,9 @@ impl CartMariaDbRepo {
                 title: "Untitled".to_string(),
                 saved_lines: Vec::new(),
                 new_lines: Vec::new(),
+                sharable: false,
+                collaborators: Vec::new(),
+                owner_quota_lines: 0,
             })
         }
     }
@@ -3,6 +3,7 @@ use std::sync::Arc;
 use std::vec::Vec;
 
 use async_trait::async_trait;
+use chrono::{DateTime, FixedOffset, Local as LocalTime, NaiveDateTime};
 use rust_decimal::Decimal;
 use sqlx::database::Database as AbstractDatabase;
 use sqlx::mysql::{MySqlArguments, MySqlRow};
@@ -14,11 +15,14 @@ use ecommerce_common::error::AppErrorCode;
 use super::run_query_once;
 use crate::datastore::AppMariaDbStore;
 use crate::error::AppError;
-use crate::model::{CurrencyModel, CurrencyModelSet};
+use crate::model::{CurrencyModel, CurrencyModelSet, CurrencyRateHistoryModel};
 use crate::repository::AbsCurrencyRepo;
 
 struct UpdateArgs(CurrencyModelSet);
 struct FetchArgs(Vec<CurrencyDto>);
+struct InsertHistoryArgs<'a>(&'a CurrencyModelSet, NaiveDateTime);
+struct FetchAsOfArgs(Vec<CurrencyDto>, NaiveDateTime);
+struct FetchHistoryArgs(Vec<CurrencyDto>, NaiveDateTime, NaiveDateTime);
 
 impl UpdateArgs {
     fn sql_pattern(num: usize) -> String {
@@ -38,6 +42,7 @@ impl<'q> IntoArguments<'q, MySql> for UpdateArgs {
         let CurrencyModelSet {
             base: _,
             exchange_rates,
+            provider: _,
         } = self.0;
         let curr_labels = exchange_rates
             .iter()
@@ -88,6 +93,90 @@ impl From<FetchArgs> for (String, MySqlArguments) {
     }
 }
 
+impl<'a> From<InsertHistoryArgs<'a>> for (String, MySqlArguments) {
+    fn from(value: InsertHistoryArgs<'a>) -> Self {
+        let (ms, time) = (value.0, value.1);
+        let items = (0..ms.exchange_rates.len())
+            .map(|_| "(?,?,?,?)")
+            .collect::<Vec<_>>()
+            .join(",");
+        let sql_patt = format!(
+            "INSERT INTO `currency_exchange_history`(`name`,`time`,`rate`,`provider`) \
+             VALUES {items} ON DUPLICATE KEY UPDATE `rate`=VALUE(`rate`),\
+             `provider`=VALUE(`provider`)"
+        );
+        let mut args = MySqlArguments::default();
+        ms.exchange_rates
+            .iter()
+            .map(|m| {
+                args.add(m.name.to_string()).unwrap();
+                args.add(time).unwrap();
+                args.add(m.rate).unwrap();
+                args.add(ms.provider.clone()).unwrap();
+            })
+            .count();
+        (sql_patt, args)
+    }
+}
+
+impl From<FetchAsOfArgs> for (String, MySqlArguments) {
+    fn from(value: FetchAsOfArgs) -> Self {
+        let (chosen, time) = (value.0, value.1);
+        let chosen_labels = (0..chosen.len()).map(|_| "?").collect::<Vec<_>>().join(",");
+        let sql_patt = format!(
+            "SELECT `h`.`name`,`h`.`rate` FROM `currency_exchange_history` AS `h` \
+             INNER JOIN (SELECT `name`, MAX(`time`) AS `latest` FROM `currency_exchange_history` \
+             WHERE `name` IN ({chosen_labels}) AND `time` <= ? GROUP BY `name`) AS `l` \
+             ON `h`.`name`=`l`.`name` AND `h`.`time`=`l`.`latest`"
+        );
+        let mut args = MySqlArguments::default();
+        chosen
+            .into_iter()
+            .map(|c| {
+                args.add(c.to_string()).unwrap();
+            })
+            .count();
+        args.add(time).unwrap();
+        (sql_patt, args)
+    }
+}
+
+impl From<FetchHistoryArgs> for (String, MySqlArguments) {
+    fn from(value: FetchHistoryArgs) -> Self {
+        let (chosen, start, end) = (value.0, value.1, value.2);
+        let chosen_labels = (0..chosen.len()).map(|_| "?").collect::<Vec<_>>().join(",");
+        let sql_patt = format!(
+            "SELECT `name`,`rate`,`provider`,`time` FROM `currency_exchange_history` \
+             WHERE `name` IN ({chosen_labels}) AND `time` > ? AND `time` <= ? ORDER BY `time` ASC"
+        );
+        let mut args = MySqlArguments::default();
+        chosen
+            .into_iter()
+            .map(|c| {
+                args.add(c.to_string()).unwrap();
+            })
+            .count();
+        args.add(start).unwrap();
+        args.add(end).unwrap();
+        (sql_patt, args)
+    }
+}
+
+impl TryFrom<MySqlRow> for CurrencyRateHistoryModel {
+    type Error = AppError;
+    fn try_from(value: MySqlRow) -> Result<Self, Self::Error> {
+        let provider = value.try_get::<Option<String>, usize>(2)?;
+        let time = value.try_get::<NaiveDateTime, usize>(3)?.and_utc().into();
+        let CurrencyModel { name, rate } = CurrencyModel::try_from(value)?;
+        Ok(Self {
+            name,
+            rate,
+            provider,
+            time,
+        })
+    }
+}
+
 impl TryFrom<MySqlRow> for CurrencyModel {
     type Error = AppError;
     fn try_from(value: MySqlRow) -> Result<Self, Self::Error> {
@@ -141,6 +230,7 @@ impl AbsCurrencyRepo for CurrencyMariaDbRepo {
             Ok(CurrencyModelSet {
                 base: CurrencyDto::USD,
                 exchange_rates,
+                provider: None,
             })
         } else {
             Err(errors.remove(0))
@@ -162,13 +252,48 @@ impl AbsCurrencyRepo for CurrencyMariaDbRepo {
             });
         }
         ms.check_rate_range()?;
+        let now = LocalTime::now().naive_utc();
+        let (sql_patt_hist, args_hist) = InsertHistoryArgs(&ms, now).into();
         let (sql_patt, args) = UpdateArgs(ms).into();
         let mut conn = self._db.acquire().await?;
         let mut tx = conn.begin().await?;
         let _rs = run_query_once(&mut tx, sql_patt, args, Some(expect_num_updated)).await?;
+        let _rs = run_query_once(&mut tx, sql_patt_hist, args_hist, None).await?;
         tx.commit().await?;
         Ok(())
     } // end of fn save
+
+    async fn fetch_as_of(
+        &self,
+        chosen: Vec<CurrencyDto>,
+        time: DateTime<FixedOffset>,
+    ) -> Result<CurrencyModelSet, AppError> {
+        let (sql_patt, args) = FetchAsOfArgs(chosen, time.naive_utc()).into();
+        let rows = self.fetch_common(sql_patt, args).await?;
+        let exchange_rates = rows
+            .into_iter()
+            .map(CurrencyModel::try_from)
+            .collect::<Result<Vec<_>, AppError>>()?;
+        Ok(CurrencyModelSet {
+            base: CurrencyDto::USD,
+            exchange_rates,
+            provider: None,
+        })
+    }
+
+    async fn fetch_history(
+        &self,
+        chosen: Vec<CurrencyDto>,
+        start: DateTime<FixedOffset>,
+        end: DateTime<FixedOffset>,
+    ) -> Result<Vec<CurrencyRateHistoryModel>, AppError> {
+        let args = FetchHistoryArgs(chosen, start.naive_utc(), end.naive_utc());
+        let (sql_patt, args) = args.into();
+        let rows = self.fetch_common(sql_patt, args).await?;
+        rows.into_iter()
+            .map(CurrencyRateHistoryModel::try_from)
+            .collect()
+    }
 } // end of impl CurrencyMariaDbRepo
 
 impl CurrencyMariaDbRepo {
@@ -179,4 +304,17 @@ impl CurrencyMariaDbRepo {
         })?;
         Ok(Self { _db })
     }
+
+    async fn fetch_common(
+        &self,
+        sql_patt: String,
+        args: MySqlArguments,
+    ) -> Result<Vec<MySqlRow>, AppError> {
+        let mut conn = self._db.acquire().await?;
+        let stmt = conn.prepare(sql_patt.as_str()).await?;
+        let query = stmt.query_with(args);
+        let exec = &mut *conn;
+        let rows = exec.fetch_all(query).await?;
+        Ok(rows)
+    }
 } // end of impl CurrencyMariaDbRepo
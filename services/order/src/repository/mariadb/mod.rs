@@ -4,7 +4,12 @@ pub(super) mod oline_return;
 pub(super) mod order;
 pub(super) mod product_policy;
 pub(super) mod product_price;
+pub(super) mod promotion;
+pub(super) mod shipment;
+pub(super) mod shipping_method;
 pub(super) mod stock;
+pub(super) mod stock_alert;
+pub(super) mod tax;
 
 use sqlx::error::Error;
 use sqlx::mysql::{MySqlArguments, MySqlQueryResult, MySqlRow};
@@ -20,16 +20,19 @@ use ecommerce_common::error::AppErrorCode;
 use ecommerce_common::model::order::{BillingModel, ContactModel, PhyAddrModel};
 
 use crate::api::dto::ShippingMethod;
+use crate::api::web::dto::OrderLineCancelErrorDto;
 use crate::constant::hard_limit;
 use crate::datastore::AppMariaDbStore;
 use crate::error::AppError;
 use crate::model::{
-    CurrencyModel, OrderCurrencyModel, OrderLineAppliedPolicyModel, OrderLineIdentity,
-    OrderLineModel, OrderLineModelSet, OrderLinePriceModel, OrderLineQuantityModel,
-    ProdAttriPriceModel, ShippingModel, ShippingOptionModel,
+    CurrencyModel, OrderCurrencyModel, OrderLineAppliedPolicyModel, OrderLineCancelModel,
+    OrderLineDiscountModel, OrderLineIdentity, OrderLineModel, OrderLineModelSet,
+    OrderLinePriceModel, OrderLineQuantityModel, OrderLineTaxModel, ProdAttriPriceModel,
+    ShippingModel, ShippingOptionModel,
 };
 use crate::repository::{
-    AbsOrderRepo, AbsOrderStockRepo, AppOrderFetchRangeCallback, AppOrderRepoUpdateLinesUserFunc,
+    AbsOrderRepo, AbsOrderStockRepo, AppOrderFetchRangeCallback, AppOrderRepoCancelLinesUserFunc,
+    AppOrderRepoUpdateLinesUserFunc,
 };
 
 use super::stock::StockMariaDbRepo;
@@ -50,6 +53,8 @@ struct InsertPhyAddr<'a, 'b>(&'a str, &'b OidBytes, PhyAddrModel);
 struct InsertShipOption<'a>(&'a OidBytes, Vec<ShippingOptionModel>);
 
 struct UpdateOLinePayArg<'a>(&'a OidBytes, Vec<OrderLineModel>);
+struct UpdateOLineCancelArg<'a, 'b>(&'a OidBytes, &'b [OrderLineModel]);
+struct UpdateOLineBackorderArg<'a, 'b>(&'a OidBytes, Vec<&'b OrderLineModel>);
 
 struct FetchAllLinesArg(OidBytes);
 struct FetchLineByIdArg<'a>(&'a OidBytes, Vec<OrderLineIdentity>);
@@ -120,9 +125,10 @@ impl<'a, 'b> InsertOLineArg<'a, 'b> {
     fn sql_pattern(num_batch: usize) -> String {
         let col_seq = "`o_id`,`seq`,`store_id`,`product_id`,`price_unit`,`price_total`,\
                        `qty_rsved`,`rsved_until`,`warranty_until`,`attr_lastupdate`,\
-                       `attr_price`,`attr_seq`";
+                       `attr_price`,`attr_seq`,`policy_version`,`promo_code`,`price_discount`,\
+                       `tax_rate`,`price_tax`,`qty_backordered`,`warehouse_id`";
         let items = (0..num_batch)
-            .map(|_| "(?,?,?,?,?,?,?,?,?,?,?,?)")
+            .map(|_| "(?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?,?)")
             .collect::<Vec<_>>();
         format!(
             "INSERT INTO `order_line_detail`({}) VALUES {}",
@@ -155,6 +161,23 @@ impl<'a, 'b, 'q> IntoArguments<'q, MySql> for InsertOLineArg<'a, 'b> {
                 args.add(attr_lupdate).unwrap();
                 args.add(attr_pricemap).unwrap();
                 args.add(o.id().attrs_seq_num()).unwrap();
+                args.add(o.policy.version).unwrap();
+                let (promo_code, discount) = o
+                    .discount
+                    .as_ref()
+                    .map(|d| (Some(d.code.as_str()), d.unit))
+                    .unwrap_or((None, 0));
+                args.add(promo_code).unwrap();
+                args.add(discount).unwrap();
+                let (tax_rate, tax) = o
+                    .tax
+                    .as_ref()
+                    .map(|t| (Some(t.rate), t.unit))
+                    .unwrap_or((None, 0));
+                args.add(tax_rate).unwrap();
+                args.add(tax).unwrap();
+                args.add(o.qty.backordered).unwrap();
+                args.add(o.warehouse_id).unwrap();
             })
             .count();
         args
@@ -275,9 +298,9 @@ impl<'a, 'b> From<InsertPhyAddr<'a, 'b>> for (String, MySqlArguments) {
 }
 impl<'a> InsertShipOption<'a> {
     fn sql_pattern(num_batch: usize) -> String {
-        let items = (0..num_batch).map(|_num| "(?,?,?)").collect::<Vec<_>>();
+        let items = (0..num_batch).map(|_num| "(?,?,?,?)").collect::<Vec<_>>();
         format!(
-            "INSERT INTO `ship_option`(`o_id`,`seller_id`,`method`) VALUES {}",
+            "INSERT INTO `ship_option`(`o_id`,`seller_id`,`method`,`fee`) VALUES {}",
             items.join(",")
         )
     }
@@ -294,6 +317,7 @@ impl<'a, 'q> IntoArguments<'q, MySql> for InsertShipOption<'a> {
                 args.add(&oid).unwrap();
                 args.add(so.seller_id).unwrap();
                 args.add(method).unwrap();
+                args.add(so.fee).unwrap();
             })
             .count();
         args
@@ -377,9 +401,132 @@ impl<'a> From<UpdateOLinePayArg<'a>> for (String, MySqlArguments) {
     }
 }
 
+impl<'a, 'b> UpdateOLineBackorderArg<'a, 'b> {
+    fn sql_pattern(num_batch: usize) -> String {
+        let condition = "(`store_id`=? AND `product_id`=? AND `attr_seq`=?)";
+        let case_ops = (0..num_batch)
+            .flat_map(|_| ["WHEN", condition, "THEN", "?"])
+            .collect::<Vec<_>>()
+            .join(" ");
+        let where_ops = (0..num_batch)
+            .map(|_| condition)
+            .collect::<Vec<_>>()
+            .join("OR");
+        let portions = [
+            format!("`qty_backordered` = CASE {case_ops} ELSE `qty_backordered` END"),
+            format!("`rsved_until` = CASE {case_ops} ELSE `rsved_until` END"),
+            format!("`warehouse_id` = CASE {case_ops} ELSE `warehouse_id` END"),
+        ];
+        format!(
+            "UPDATE `order_line_detail` SET {}, {}, {} WHERE `o_id`=? AND ({})",
+            portions[0], portions[1], portions[2], where_ops
+        )
+    }
+}
+impl<'a, 'b, 'q> IntoArguments<'q, MySql> for UpdateOLineBackorderArg<'a, 'b> {
+    fn into_arguments(self) -> <MySql as AbstractDatabase>::Arguments<'q> {
+        let (oid, lines) = (self.0, self.1);
+        let mut args = MySqlArguments::default();
+        lines
+            .iter()
+            .map(|line| {
+                args.add(line.id().store_id()).unwrap();
+                args.add(line.id().product_id()).unwrap();
+                args.add(line.id().attrs_seq_num()).unwrap();
+                args.add(line.qty.backordered).unwrap();
+            })
+            .count();
+        lines
+            .iter()
+            .map(|line| {
+                args.add(line.id().store_id()).unwrap();
+                args.add(line.id().product_id()).unwrap();
+                args.add(line.id().attrs_seq_num()).unwrap();
+                args.add(line.policy.reserved_until.naive_utc()).unwrap();
+            })
+            .count();
+        lines
+            .iter()
+            .map(|line| {
+                args.add(line.id().store_id()).unwrap();
+                args.add(line.id().product_id()).unwrap();
+                args.add(line.id().attrs_seq_num()).unwrap();
+                args.add(line.warehouse_id).unwrap();
+            })
+            .count();
+        args.add(oid.as_column()).unwrap();
+        lines
+            .into_iter()
+            .map(|line| {
+                args.add(line.id().store_id()).unwrap();
+                args.add(line.id().product_id()).unwrap();
+                args.add(line.id().attrs_seq_num()).unwrap();
+            })
+            .count();
+        args
+    }
+}
+impl<'a, 'b> From<UpdateOLineBackorderArg<'a, 'b>> for (String, MySqlArguments) {
+    fn from(value: UpdateOLineBackorderArg<'a, 'b>) -> (String, MySqlArguments) {
+        let num_batch = value.1.len();
+        assert!(num_batch > 0);
+        (
+            UpdateOLineBackorderArg::sql_pattern(num_batch),
+            value.into_arguments(),
+        )
+    }
+}
+
+impl<'a, 'b> UpdateOLineCancelArg<'a, 'b> {
+    fn sql_pattern(num_batch: usize) -> String {
+        let condition = "(`store_id`=? AND `product_id`=? AND `attr_seq`=?)";
+        let where_ops = (0..num_batch)
+            .map(|_| condition)
+            .collect::<Vec<_>>()
+            .join("OR");
+        format!(
+            "UPDATE `order_line_detail` SET `cancel_usr_id`=?, `cancel_time`=? \
+             WHERE `o_id`=? AND ({})",
+            where_ops
+        )
+    }
+}
+impl<'a, 'b, 'q> IntoArguments<'q, MySql> for UpdateOLineCancelArg<'a, 'b> {
+    fn into_arguments(self) -> <MySql as AbstractDatabase>::Arguments<'q> {
+        let (oid, lines) = (self.0, self.1);
+        // all the lines are cancelled by the same user at the same time
+        let cancel = lines[0].cancelled.as_ref().unwrap();
+        let mut args = MySqlArguments::default();
+        args.add(cancel.usr_id).unwrap();
+        args.add(cancel.time.naive_utc()).unwrap();
+        args.add(oid.as_column()).unwrap();
+        lines
+            .iter()
+            .map(|line| {
+                args.add(line.id().store_id()).unwrap();
+                args.add(line.id().product_id()).unwrap();
+                args.add(line.id().attrs_seq_num()).unwrap();
+            })
+            .count();
+        args
+    }
+}
+impl<'a, 'b> From<UpdateOLineCancelArg<'a, 'b>> for (String, MySqlArguments) {
+    fn from(value: UpdateOLineCancelArg<'a, 'b>) -> (String, MySqlArguments) {
+        let num_batch = value.1.len();
+        assert!(num_batch > 0);
+        (
+            UpdateOLineCancelArg::sql_pattern(num_batch),
+            value.into_arguments(),
+        )
+    }
+}
+
 const OLINE_SELECT_PREFIX: &str = "SELECT `store_id`,`product_id`,`attr_seq`,`price_unit`,\
    `price_total`,`qty_rsved`,`qty_paid`,`qty_paid_last_update`,`rsved_until`,\
-    `warranty_until`,`attr_lastupdate`,`attr_price` FROM `order_line_detail`";
+    `warranty_until`,`attr_lastupdate`,`attr_price`,`cancel_usr_id`,`cancel_time`,`policy_version`,\
+    `promo_code`,`price_discount`,`tax_rate`,`price_tax`,`qty_backordered`,`warehouse_id` FROM \
+    `order_line_detail`";
 
 impl From<FetchAllLinesArg> for (String, MySqlArguments) {
     fn from(value: FetchAllLinesArg) -> (String, MySqlArguments) {
@@ -511,12 +658,29 @@ impl TryFrom<OLineRow> for OrderLineModel {
             })?;
             ProdAttriPriceModel::deserialize_map(serial)?
         };
+        let cancel_usr_id = row.try_get::<Option<u32>, usize>(12)?;
+        let cancel_time = row.try_get::<Option<NaiveDateTime>, usize>(13)?
+            .map(|t| t.and_utc().into());
+        let version = row.try_get::<u32, usize>(14)?;
+        let promo_code = row.try_get::<Option<String>, usize>(15)?;
+        let price_discount = row.try_get::<u32, usize>(16)?;
+        let tax_rate = row.try_get::<Option<Decimal>, usize>(17)?;
+        let price_tax = row.try_get::<u32, usize>(18)?;
+        let backordered = row.try_get::<u32, usize>(19)?;
+        let warehouse_id = row.try_get::<Option<u32>, usize>(20)?;
         let id_ = OrderLineIdentity::from((store_id, product_id, attr_seq));
         let price = OrderLinePriceModel::from((unit, total));
-        let qty = OrderLineQuantityModel {reserved, paid, paid_last_update};
-        let policy = OrderLineAppliedPolicyModel {warranty_until, reserved_until};
+        let qty = OrderLineQuantityModel {reserved, paid, paid_last_update, backordered};
+        let policy = OrderLineAppliedPolicyModel {warranty_until, reserved_until, version};
         let attr_chg = ProdAttriPriceModel::from((attr_lupdate, attrprice));
-        Ok(OrderLineModel::from((id_, price, policy, qty, attr_chg)))
+        let mut obj = OrderLineModel::from((id_, price, policy, qty, attr_chg));
+        obj.cancelled = cancel_usr_id.zip(cancel_time)
+            .map(|(usr_id, time)| OrderLineCancelModel {usr_id, time});
+        obj.discount = promo_code
+            .map(|code| OrderLineDiscountModel {code, unit: price_discount});
+        obj.tax = tax_rate.map(|rate| OrderLineTaxModel {rate, unit: price_tax});
+        obj.warehouse_id = warehouse_id;
+        Ok(obj)
     }
 } // end of impl OrderLineModel
 
@@ -586,7 +750,12 @@ impl TryInto<ShippingOptionModel> for ShipOptionRow {
             detail: Some(e.to_string()),
         })?;
         let method = ShippingMethod::from(mthd_raw.to_string());
-        Ok(ShippingOptionModel { seller_id, method })
+        let fee = row.try_get::<u32, usize>(2)?;
+        Ok(ShippingOptionModel {
+            seller_id,
+            method,
+            fee,
+        })
     }
 }
 
@@ -699,6 +868,22 @@ impl AbsOrderRepo for OrderMariaDbRepo {
             lines: errors,
         })
     }
+    async fn cancel_lines(
+        &self,
+        oid: &str,
+        pids: Vec<OrderLineIdentity>,
+        cancel: OrderLineCancelModel,
+        usr_cb: AppOrderRepoCancelLinesUserFunc,
+    ) -> DefaultResult<Vec<OrderLineCancelErrorDto>, AppError> {
+        let oid_b = OidBytes::try_from(oid)?;
+        let mut conn = self._db.acquire().await?;
+        let mut tx = conn.begin().await?;
+        let (_lines, errors) = Self::cancel_lines_tx(&mut tx, &oid_b, pids, cancel, usr_cb).await?;
+        if errors.is_empty() {
+            tx.commit().await?;
+        }
+        Ok(errors)
+    }
     async fn fetch_lines_by_rsvtime(
         &self,
         time_start: DateTime<FixedOffset>,
@@ -759,6 +944,76 @@ impl AbsOrderRepo for OrderMariaDbRepo {
         let mut tx = conn.begin().await?;
         Self::_fetch_lines_by_pid(&mut tx, &oid_b, pids).await
     }
+    async fn fetch_backordered(
+        &self,
+        store_id: u32,
+        product_ids: Vec<u64>,
+    ) -> DefaultResult<Vec<OrderLineModelSet>, AppError> {
+        if product_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let pid_patt = (0..product_ids.len())
+            .map(|_| "?")
+            .collect::<Vec<_>>()
+            .join(",");
+        let mut conn = self._db.acquire().await?;
+        let rows = {
+            let sql_patt = format!(
+                "SELECT `a`.`o_id`,`a`.`usr_id`,`a`.`created_time`,`a`.`buyer_currency`,\
+                 `a`.`buyer_ex_rate` FROM `order_toplvl_meta` AS `a` INNER JOIN \
+                 `order_line_detail` AS `b` ON `a`.`o_id` = `b`.`o_id` WHERE \
+                 `b`.`store_id`=? AND `b`.`product_id` IN ({pid_patt}) AND \
+                 `b`.`qty_backordered` > 0 AND `b`.`cancel_usr_id` IS NULL \
+                 GROUP BY `a`.`o_id` ORDER BY `a`.`created_time` ASC"
+            );
+            let mut args = MySqlArguments::default();
+            args.add(store_id).unwrap();
+            product_ids
+                .iter()
+                .map(|pid| {
+                    args.add(pid).unwrap();
+                })
+                .count();
+            let stmt = conn.prepare(sql_patt.as_str()).await?;
+            let query = stmt.query_with(args);
+            let exec = &mut *conn;
+            exec.fetch_all(query).await?
+        };
+        let sql_patt = format!(
+            "{OLINE_SELECT_PREFIX} WHERE `o_id`=? AND `store_id`=? AND \
+             `product_id` IN ({pid_patt}) AND `qty_backordered` > 0 AND \
+             `cancel_usr_id` IS NULL"
+        );
+        let mut out = Vec::new();
+        for row in rows {
+            let oid_raw = row.try_get::<Vec<u8>, usize>(0)?;
+            let sellers_currency =
+                Self::_fetch_seller_exrates(conn.as_mut(), oid_raw.clone()).await?;
+            let mut ol_set: OrderLineModelSet = TopLvlMetaRow(row, sellers_currency).try_into()?;
+            let mut args = MySqlArguments::default();
+            args.add(oid_raw).unwrap();
+            args.add(store_id).unwrap();
+            product_ids
+                .iter()
+                .map(|pid| {
+                    args.add(pid).unwrap();
+                })
+                .count();
+            let stmt = conn.prepare(sql_patt.as_str()).await?;
+            let query = stmt.query_with(args);
+            let exec = &mut *conn;
+            let newlines = exec
+                .fetch_all(query)
+                .await?
+                .into_iter()
+                .map(|row| OLineRow(row).try_into())
+                .collect::<DefaultResult<Vec<OrderLineModel>, AppError>>()?;
+            ol_set.append_lines(newlines);
+            out.push(ol_set);
+        }
+        Ok(out)
+    } // end of fn fetch_backordered
+
     // TODO, cache the metadata `owner-id` and `create-time` , these records can be shared
     // among the functions : `fetch_ids_by_created_time()`, `owner_id()`, `created_time()`
     async fn fetch_ids_by_created_time(
@@ -786,6 +1041,46 @@ impl AbsOrderRepo for OrderMariaDbRepo {
         };
         Ok(o_meta)
     }
+    async fn fetch_ids_by_owner(
+        &self,
+        usr_id: u32,
+        start: DateTime<FixedOffset>,
+        end: DateTime<FixedOffset>,
+        offset: usize,
+        limit: usize,
+    ) -> DefaultResult<(usize, Vec<(String, DateTime<FixedOffset>)>), AppError> {
+        let (start, end) = (start.naive_utc(), end.naive_utc());
+        let mut conn = self._db.acquire().await?;
+        let total = {
+            let sql_patt = "SELECT COUNT(*) FROM `order_toplvl_meta` WHERE `usr_id`=? \
+                            AND `created_time` >= ? AND `created_time` <= ?";
+            let stmt = conn.prepare(sql_patt).await?;
+            let query = stmt.query().bind(usr_id).bind(start).bind(end);
+            let exec = conn.as_mut();
+            let row = exec.fetch_one(query).await?;
+            row.try_get::<i64, usize>(0)? as usize
+        };
+        let sql_patt = "SELECT `o_id`,`created_time` FROM `order_toplvl_meta` WHERE \
+                        `usr_id`=? AND `created_time` >= ? AND `created_time` <= ? \
+                        ORDER BY `created_time` DESC LIMIT ? OFFSET ?";
+        let stmt = conn.prepare(sql_patt).await?;
+        let query = stmt
+            .query()
+            .bind(usr_id)
+            .bind(start)
+            .bind(end)
+            .bind(limit as u64)
+            .bind(offset as u64);
+        let exec = conn.as_mut();
+        let rows = exec.fetch_all(query).await?;
+        let mut paged = Vec::with_capacity(rows.len());
+        for row in rows {
+            let oid = to_app_oid(&row, 0)?;
+            let ctime = row.try_get::<NaiveDateTime, usize>(1)?.and_utc().into();
+            paged.push((oid, ctime));
+        }
+        Ok((total, paged))
+    }
     async fn owner_id(&self, oid: &str) -> DefaultResult<u32, AppError> {
         let OidBytes(oid_b) = OidBytes::try_from(oid)?;
         let sql_patt = "SELECT `usr_id` FROM `order_toplvl_meta` WHERE `o_id`=?";
@@ -921,6 +1216,43 @@ impl OrderMariaDbRepo {
         Ok(())
     } // end of fn create_lines
 
+    // cancelled lines are saved only if the callback does not report any error,
+    // caller decides whether to commit the transaction
+    pub(super) async fn cancel_lines_tx(
+        tx: &mut Transaction<'_, MySql>,
+        oid: &OidBytes,
+        pids: Vec<OrderLineIdentity>,
+        cancel: OrderLineCancelModel,
+        usr_cb: AppOrderRepoCancelLinesUserFunc,
+    ) -> DefaultResult<(Vec<OrderLineModel>, Vec<OrderLineCancelErrorDto>), AppError> {
+        let mut saved_lines = Self::_fetch_lines_by_pid(tx, oid, pids.clone()).await?;
+        let errors = usr_cb(&mut saved_lines, pids, cancel);
+        if errors.is_empty() {
+            let num_affected = saved_lines.len();
+            let (sql_patt, args) = UpdateOLineCancelArg(oid, &saved_lines).into();
+            let _rs = run_query_once(tx, sql_patt, args, Some(num_affected)).await?;
+        }
+        Ok((saved_lines, errors))
+    }
+
+    pub(super) async fn update_lines_backorder(
+        tx: &mut Transaction<'_, MySql>,
+        ol_set: &OrderLineModelSet,
+        limit: usize,
+    ) -> DefaultResult<(), AppError> {
+        let oid = OidBytes::try_from(ol_set.id().as_str())?;
+        let mut data = ol_set.lines().iter().collect::<Vec<_>>();
+        while !data.is_empty() {
+            let num_batch = min(data.len(), limit);
+            let items_processing = data.split_off(data.len() - num_batch);
+            let (sql_patt, args) = UpdateOLineBackorderArg(&oid, items_processing).into();
+            // lines which could not be allocated any item stay unchanged, the
+            // number of affected rows is unknown in advance
+            let _rs = run_query_once(tx, sql_patt, args, None).await?;
+        }
+        Ok(())
+    } // end of fn update_lines_backorder
+
     async fn _save_contact(
         tx: &mut Transaction<'_, MySql>,
         oid: &OidBytes,
@@ -1085,7 +1417,7 @@ impl OrderMariaDbRepo {
         conn: &mut MySqlConnection,
         oid_b: &OidBytes,
     ) -> DefaultResult<Vec<ShippingOptionModel>, AppError> {
-        let sql_patt = "SELECT `seller_id`,`method` FROM `ship_option` WHERE `o_id`=?";
+        let sql_patt = "SELECT `seller_id`,`method`,`fee` FROM `ship_option` WHERE `o_id`=?";
         let stmt = conn.prepare(sql_patt).await?;
         let query = stmt.query().bind(oid_b.as_column());
         let rows = conn.fetch_all(query).await?;
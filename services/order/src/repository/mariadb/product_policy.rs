@@ -8,12 +8,23 @@ use sqlx::mysql::{MySqlArguments, MySqlRow};
 use sqlx::{Acquire, Arguments, Executor, MySql, Row, Statement, Transaction};
 
 use ecommerce_common::error::AppErrorCode;
+use ecommerce_common::model::BaseProductIdentity;
 
 use crate::datastore::AppMariaDbStore;
 use crate::error::AppError;
-use crate::model::{ProductPolicyModel, ProductPolicyModelSet};
+use crate::model::{ProductBackorderPolicy, ProductPolicyModel, ProductPolicyModelSet};
 use crate::repository::AbstProductPolicyRepo;
 
+use super::run_query_once;
+
+fn backorder_to_str(value: ProductBackorderPolicy) -> &'static str {
+    match value {
+        ProductBackorderPolicy::Disabled => "disabled",
+        ProductBackorderPolicy::Backorder => "backorder",
+        ProductBackorderPolicy::PreOrder => "pre-order",
+    }
+}
+
 pub(crate) struct ProductPolicyMariaDbRepo {
     db: Arc<AppMariaDbStore>,
 }
@@ -81,109 +92,99 @@ impl ProductPolicyMariaDbRepo {
         Ok(())
     } // end of fn _save
 
+    async fn lock_versions(
+        tx: &mut Transaction<'_, MySql>,
+        policies: &[ProductPolicyModel],
+    ) -> DefaultResult<(), AppError> {
+        // exclusive locking read on existing versions of the policies, concurrent
+        // transactions appending to the same policies have to wait until this one
+        // completes, so the version number computed later in the insertion will
+        // not collide. For policies without any version yet, only gap lock is
+        // acquired, conflicting insertions are still rejected by the primary key
+        // (`store_id`, `product_id`, `version`)
+        const SQL_PATTERN_BLOCKS: (&str, &str, &str) = (
+            "SELECT `version` FROM `product_policy` WHERE ",
+            "(`store_id`=? AND `product_id`=?)",
+            "OR",
+        );
+        let limit = 16_usize;
+        let mut _ids = policies
+            .iter()
+            .map(|p| (p.store_id, p.product_id))
+            .collect::<Vec<_>>();
+        while !_ids.is_empty() {
+            let num_batch = min(_ids.len(), limit);
+            let sql_patt = Self::prep_stmt_patt_read(SQL_PATTERN_BLOCKS, num_batch) + " FOR UPDATE";
+            let mut args = MySqlArguments::default();
+            _ids.drain(0..num_batch)
+                .map(|(store_id, product_id)| {
+                    args.add(store_id).unwrap();
+                    args.add(product_id).unwrap();
+                })
+                .count();
+            let _rs = run_query_once(tx, sql_patt, args, None).await?;
+        }
+        Ok(())
+    } // end of fn lock_versions
+
     fn construct_insert_args(items: Vec<ProductPolicyModel>) -> (String, MySqlArguments) {
+        // each policy is appended as next version, the version number keeps growing
+        // even when all previous versions were deleted
         const SQL_PATTERN_BLOCKS: (&str, &str, &str) = (
-            "INSERT INTO `product_policy`(`product_id`,`auto_cancel_secs`,\
-             `warranty_hours`,`max_num_rsv`,`min_num_rsv`) VALUES ",
-            "(?,?,?,?,?)",
-            ",",
+            "INSERT INTO `product_policy`(`store_id`,`product_id`,`version`,\
+             `auto_cancel_secs`,`warranty_hours`,`max_num_rsv`,`min_num_rsv`,`backorder`,\
+             `max_num_backorder`,`weight_gram`) ",
+            "SELECT ?,?,COALESCE(MAX(`version`)+1,0),?,?,?,?,?,?,? FROM `product_policy` \
+             WHERE `store_id`=? AND `product_id`=?",
+            " UNION ALL ",
         );
         let mut args = MySqlArguments::default();
         let num_batch = items
             .into_iter()
             .map(|item| {
-                let (prod_id, auto_cancel, warranty, max_rsv, min_rsv) = (
+                let (store_id, prod_id, auto_cancel, warranty, max_rsv, min_rsv) = (
+                    item.store_id,
                     item.product_id,
                     item.auto_cancel_secs,
                     item.warranty_hours,
                     item.max_num_rsv,
                     item.min_num_rsv,
                 );
+                args.add(store_id).unwrap();
                 args.add(prod_id).unwrap();
                 args.add(auto_cancel).unwrap();
                 args.add(warranty).unwrap();
                 args.add(max_rsv).unwrap();
                 args.add(min_rsv).unwrap();
+                args.add(backorder_to_str(item.backorder)).unwrap();
+                args.add(item.max_num_backorder).unwrap();
+                args.add(item.weight_gram).unwrap();
+                args.add(store_id).unwrap();
+                args.add(prod_id).unwrap();
             })
             .count();
         let sql_patt = Self::prep_stmt_patt_read(SQL_PATTERN_BLOCKS, num_batch);
         (sql_patt, args)
     } // end of fn construct_insert_args
-
-    fn construct_update_args(items: Vec<ProductPolicyModel>) -> (String, MySqlArguments) {
-        let mut args = MySqlArguments::default();
-        let mut num_batch = items
-            .iter()
-            .map(|item| {
-                let (prod_id, auto_cancel) = (item.product_id, item.auto_cancel_secs);
-                args.add(prod_id).unwrap();
-                args.add(auto_cancel).unwrap();
-            })
-            .count();
-        items
-            .iter()
-            .map(|item| {
-                let (prod_id, warranty) = (item.product_id, item.warranty_hours);
-                args.add(prod_id).unwrap();
-                args.add(warranty).unwrap();
-            })
-            .count();
-        items
-            .iter()
-            .map(|item| {
-                let (prod_id, max_rsv) = (item.product_id, item.max_num_rsv);
-                args.add(prod_id).unwrap();
-                args.add(max_rsv).unwrap();
-            })
-            .count();
-        items
-            .iter()
-            .map(|item| {
-                let (prod_id, min_rsv) = (item.product_id, item.min_num_rsv);
-                args.add(prod_id).unwrap();
-                args.add(min_rsv).unwrap();
-            })
-            .count();
-        items
-            .iter()
-            .map(|item| {
-                let prod_id = item.product_id;
-                args.add(prod_id).unwrap();
-            })
-            .count();
-        let sql_patt = {
-            let case_ops = (0..num_batch)
-                .map(|_| "WHEN (`product_id`=?) THEN ? ")
-                .collect::<Vec<_>>()
-                .join("");
-            let mut out = format!(
-                "UPDATE `product_policy` SET \
-                `auto_cancel_secs` = CASE {} ELSE `auto_cancel_secs` END,\
-                `warranty_hours` = CASE {} ELSE `warranty_hours` END, \
-                `max_num_rsv` = CASE {} ELSE `max_num_rsv` END, \
-                `min_num_rsv` = CASE {} ELSE `min_num_rsv` END \
-                WHERE ",
-                case_ops, case_ops, case_ops, case_ops
-            );
-            out += "(`product_id`=?)";
-            num_batch -= 1;
-            (0..num_batch)
-                .map(|_| {
-                    out += "OR (`product_id`=?)";
-                })
-                .count();
-            out
-        };
-        (sql_patt, args)
-    } // end of fn construct_update_args
 } // end of impl ProductPolicyMariaDbRepo
 
 #[async_trait]
 impl AbstProductPolicyRepo for ProductPolicyMariaDbRepo {
-    async fn fetch(&self, ids: Vec<u64>) -> DefaultResult<ProductPolicyModelSet, AppError> {
+    async fn fetch(
+        &self,
+        ids: Vec<BaseProductIdentity>,
+    ) -> DefaultResult<ProductPolicyModelSet, AppError> {
+        // only the latest version of each policy is selected, the policy is considered
+        // deleted if its latest version is marked as deleted
         const SQL_PATTERN_BLOCKS: (&str, &str, &str) = (
-            "SELECT `product_id`,`auto_cancel_secs`,`warranty_hours`,`max_num_rsv`,`min_num_rsv` FROM `product_policy` WHERE ",
-            "(`product_id`=?)", "OR"
+            "SELECT `a`.`store_id`,`a`.`product_id`,`a`.`version`,`a`.`auto_cancel_secs`,\
+             `a`.`warranty_hours`,`a`.`max_num_rsv`,`a`.`min_num_rsv`,`a`.`backorder`,\
+             `a`.`max_num_backorder`,`a`.`weight_gram` FROM `product_policy` AS `a` \
+             WHERE `a`.`is_deleted` = FALSE AND `a`.`version` = (SELECT MAX(`b`.`version`) \
+             FROM `product_policy` AS `b` WHERE `b`.`store_id` = `a`.`store_id` AND \
+             `b`.`product_id` = `a`.`product_id`) AND (",
+            "(`a`.`store_id`=? AND `a`.`product_id`=?)",
+            "OR",
         );
         let (limit, mut num_iter) = (16_usize, 0usize);
         let mut _ids = ids;
@@ -191,12 +192,12 @@ impl AbstProductPolicyRepo for ProductPolicyMariaDbRepo {
         let params = [];
         let mut conn = self.db.acquire().await?;
         let num_batch = min(_ids.len(), limit);
-        let mut sql = Self::prep_stmt_patt_read(SQL_PATTERN_BLOCKS, num_batch);
+        let mut sql = Self::prep_stmt_patt_read(SQL_PATTERN_BLOCKS, num_batch) + ")";
         let mut policies = vec![];
         while !_ids.is_empty() {
             let num_batch = min(_ids.len(), limit);
             sql = if num_batch == _ids.len() && num_iter > 0 {
-                Self::prep_stmt_patt_read(SQL_PATTERN_BLOCKS, num_batch)
+                Self::prep_stmt_patt_read(SQL_PATTERN_BLOCKS, num_batch) + ")"
             } else {
                 sql
             };
@@ -207,8 +208,9 @@ impl AbstProductPolicyRepo for ProductPolicyMariaDbRepo {
             let mut args = MySqlArguments::default();
             let _ = (0..num_batch)
                 .map(|_| {
-                    let prod_id = _ids.remove(0);
-                    args.add(prod_id).unwrap();
+                    let id_ = _ids.remove(0);
+                    args.add(id_.store_id).unwrap();
+                    args.add(id_.product_id).unwrap();
                 })
                 .count();
             let exec = conn.as_mut();
@@ -240,28 +242,95 @@ impl AbstProductPolicyRepo for ProductPolicyMariaDbRepo {
         Ok(ProductPolicyModelSet { policies })
     } // end of fn fetch
 
+    async fn fetch_by_version(
+        &self,
+        ids: Vec<(BaseProductIdentity, u32)>,
+    ) -> DefaultResult<ProductPolicyModelSet, AppError> {
+        const SQL_PATTERN_BLOCKS: (&str, &str, &str) = (
+            "SELECT `store_id`,`product_id`,`version`,`auto_cancel_secs`,`warranty_hours`,\
+             `max_num_rsv`,`min_num_rsv`,`backorder`,`max_num_backorder`,`weight_gram` \
+             FROM `product_policy` WHERE ",
+            "(`store_id`=? AND `product_id`=? AND `version`=?)",
+            "OR",
+        );
+        let limit = 16_usize;
+        let mut _ids = ids;
+        let mut conn = self.db.acquire().await?;
+        let mut policies = vec![];
+        while !_ids.is_empty() {
+            let num_batch = min(_ids.len(), limit);
+            let sql = Self::prep_stmt_patt_read(SQL_PATTERN_BLOCKS, num_batch);
+            let stmt = conn.prepare(sql.as_str()).await?;
+            let mut args = MySqlArguments::default();
+            _ids.drain(0..num_batch)
+                .map(|(id_, version)| {
+                    args.add(id_.store_id).unwrap();
+                    args.add(id_.product_id).unwrap();
+                    args.add(version).unwrap();
+                })
+                .count();
+            let exec = conn.as_mut();
+            let rows = stmt.query_with(args).fetch_all(exec).await?;
+            let portion = rows
+                .into_iter()
+                .map(ProductPolicyModel::try_from)
+                .collect::<DefaultResult<Vec<_>, AppError>>()?;
+            policies.extend(portion);
+        }
+        Ok(ProductPolicyModelSet { policies })
+    } // end of fn fetch_by_version
+
     async fn save(&self, ppset: ProductPolicyModelSet) -> DefaultResult<(), AppError> {
-        let (mut policies_add, mut policies_modify) = (vec![], vec![]);
-        ppset
+        // previous versions are never modified, only new versions are inserted
+        let policies_add = ppset
             .policies
             .into_iter()
-            .map(|p| {
-                if p.is_create {
-                    policies_add.push(p);
-                } else {
-                    policies_modify.push(p)
-                }
-            })
-            .count(); // TODO, swtich to feature `drain-filter` when it becomes stable
+            .filter(|p| p.is_create)
+            .collect::<Vec<_>>();
         let mut conn = self.db.acquire().await?;
         let mut tx = conn.begin().await?;
-        self._save(&mut tx, policies_modify, Self::construct_update_args)
-            .await?;
+        if !policies_add.is_empty() {
+            Self::lock_versions(&mut tx, &policies_add).await?;
+        }
         self._save(&mut tx, policies_add, Self::construct_insert_args)
             .await?;
         tx.commit().await?;
         Ok(())
     } // end of fn save
+
+    async fn delete(&self, ids: Vec<BaseProductIdentity>) -> DefaultResult<(), AppError> {
+        const SQL_PATTERN_BLOCKS: (&str, &str, &str) = (
+            "UPDATE `product_policy` SET `is_deleted` = TRUE WHERE ",
+            "(`store_id`=? AND `product_id`=?)",
+            "OR",
+        );
+        if ids.is_empty() {
+            return Err(AppError {
+                code: AppErrorCode::EmptyInputData,
+                detail: Some("delete ProductPolicyModel".to_string()),
+            });
+        }
+        let limit = 16_usize;
+        let mut _ids = ids;
+        let mut conn = self.db.acquire().await?;
+        let mut tx = conn.begin().await?;
+        while !_ids.is_empty() {
+            let num_batch = min(_ids.len(), limit);
+            let sql_patt = Self::prep_stmt_patt_read(SQL_PATTERN_BLOCKS, num_batch);
+            let mut args = MySqlArguments::default();
+            _ids.drain(0..num_batch)
+                .map(|id_| {
+                    args.add(id_.store_id).unwrap();
+                    args.add(id_.product_id).unwrap();
+                })
+                .count();
+            // all versions of a policy are marked, number of affected rows is
+            // unknown in advance
+            let _rs = run_query_once(&mut tx, sql_patt, args, None).await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    } // end of fn delete
 } // end of impl ProductPolicyMariaDbRepo
 
 impl TryFrom<MySqlRow> for ProductPolicyModel {
@@ -269,18 +338,38 @@ impl TryFrom<MySqlRow> for ProductPolicyModel {
     fn try_from(value: MySqlRow) -> DefaultResult<Self, Self::Error> {
         // note, the code here implicitly converts the error type received `sqlx::Error`
         // into the error type `AppError`, on immediately returning the error
-        let product_id = value.try_get::<u64, usize>(0)?;
-        let auto_cancel_secs = value.try_get::<u32, usize>(1)?;
-        let warranty_hours = value.try_get::<u32, usize>(2)?;
-        let max_num_rsv = value.try_get::<u16, usize>(3)?;
-        let min_num_rsv = value.try_get::<u16, usize>(4)?;
+        let store_id = value.try_get::<u32, usize>(0)?;
+        let product_id = value.try_get::<u64, usize>(1)?;
+        let version = value.try_get::<u32, usize>(2)?;
+        let auto_cancel_secs = value.try_get::<u32, usize>(3)?;
+        let warranty_hours = value.try_get::<u32, usize>(4)?;
+        let max_num_rsv = value.try_get::<u16, usize>(5)?;
+        let min_num_rsv = value.try_get::<u16, usize>(6)?;
+        let backorder = match value.try_get::<&str, usize>(7)? {
+            "disabled" => ProductBackorderPolicy::Disabled,
+            "backorder" => ProductBackorderPolicy::Backorder,
+            "pre-order" => ProductBackorderPolicy::PreOrder,
+            others => {
+                return Err(AppError {
+                    code: AppErrorCode::DataCorruption,
+                    detail: Some(format!("product-policy-backorder:{others}")),
+                });
+            }
+        };
+        let max_num_backorder = value.try_get::<u16, usize>(8)?;
+        let weight_gram = value.try_get::<u32, usize>(9)?;
         Ok(Self {
             is_create: false,
+            store_id,
             product_id,
+            version,
             auto_cancel_secs,
             warranty_hours,
             max_num_rsv,
             min_num_rsv,
+            backorder,
+            max_num_backorder,
+            weight_gram,
         })
     } // end of fn try_from
 } // end of impl ProductPolicyModel
@@ -0,0 +1,228 @@
+use std::result::Result as DefaultResult;
+use std::sync::Arc;
+use std::vec::Vec;
+
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+use sqlx::mysql::{MySqlArguments, MySqlRow};
+use sqlx::{Acquire, Arguments, Executor, MySql, Row, Statement, Transaction};
+
+use ecommerce_common::error::AppErrorCode;
+
+use crate::datastore::AppMariaDbStore;
+use crate::error::AppError;
+use crate::model::{PromotionModel, PromotionModelSet, PromotionRuleModel};
+use crate::repository::AbsPromotionRepo;
+
+use super::run_query_once;
+
+struct InsertPromotionArg(Vec<PromotionModel>);
+struct FetchPromotionArg(Vec<String>);
+struct ClaimUsageArg<'a>(&'a str);
+struct ReleaseUsageArg(Vec<String>);
+
+impl TryFrom<InsertPromotionArg> for (String, MySqlArguments) {
+    type Error = AppError;
+    fn try_from(value: InsertPromotionArg) -> DefaultResult<Self, Self::Error> {
+        // usage count is never overwritten on update, it could be modified by
+        // other orders concurrently
+        let items = (0..value.0.len())
+            .map(|_| "(?,?,?,?,?,?,?,?,?)")
+            .collect::<Vec<_>>()
+            .join(",");
+        let sql_patt = format!(
+            "INSERT INTO `promotion`(`code`,`store_id`,`product_ids`,`rule_label`,\
+             `rule_arg0`,`rule_arg1`,`start_after`,`end_before`,`max_usage`) VALUES {items} \
+             ON DUPLICATE KEY UPDATE `product_ids`=VALUE(`product_ids`),\
+             `rule_label`=VALUE(`rule_label`),`rule_arg0`=VALUE(`rule_arg0`),\
+             `rule_arg1`=VALUE(`rule_arg1`),`start_after`=VALUE(`start_after`),\
+             `end_before`=VALUE(`end_before`),`max_usage`=VALUE(`max_usage`)"
+        );
+        let mut args = MySqlArguments::default();
+        for m in value.0 {
+            let (label, arg0, arg1) = <(&str, u32, u32)>::from(&m.rule);
+            let product_ids = serde_json::to_string(&m.product_ids).map_err(|e| AppError {
+                code: AppErrorCode::DataCorruption,
+                detail: Some(format!("promotion-product-ids: {:?}", e)),
+            })?;
+            args.add(m.code).unwrap();
+            args.add(m.store_id).unwrap();
+            args.add(product_ids).unwrap();
+            args.add(label).unwrap();
+            args.add(arg0).unwrap();
+            args.add(arg1).unwrap();
+            args.add(m.start_after.naive_utc()).unwrap();
+            args.add(m.end_before.naive_utc()).unwrap();
+            args.add(m.max_usage).unwrap();
+        }
+        Ok((sql_patt, args))
+    }
+}
+
+impl From<FetchPromotionArg> for (String, MySqlArguments) {
+    fn from(value: FetchPromotionArg) -> (String, MySqlArguments) {
+        let items = (0..value.0.len())
+            .map(|_| "?")
+            .collect::<Vec<_>>()
+            .join(",");
+        let sql_patt = format!(
+            "SELECT `code`,`store_id`,`product_ids`,`rule_label`,`rule_arg0`,`rule_arg1`,\
+             `start_after`,`end_before`,`max_usage`,`num_used` FROM `promotion` \
+             WHERE `code` IN ({items})"
+        );
+        let mut args = MySqlArguments::default();
+        value
+            .0
+            .into_iter()
+            .map(|code| args.add(code).unwrap())
+            .count();
+        (sql_patt, args)
+    }
+}
+
+impl<'a> From<ClaimUsageArg<'a>> for (String, MySqlArguments) {
+    fn from(value: ClaimUsageArg<'a>) -> (String, MySqlArguments) {
+        // the condition is evaluated against the latest row, concurrent orders
+        // redeeming the same promotion are serialized by the row lock
+        let sql_patt = "UPDATE `promotion` SET `num_used`=`num_used`+1 WHERE `code`=? \
+                        AND (`max_usage` IS NULL OR `num_used` < `max_usage`)";
+        let mut args = MySqlArguments::default();
+        args.add(value.0).unwrap();
+        (sql_patt.to_string(), args)
+    }
+}
+
+impl From<ReleaseUsageArg> for (String, MySqlArguments) {
+    fn from(value: ReleaseUsageArg) -> (String, MySqlArguments) {
+        let items = (0..value.0.len())
+            .map(|_| "?")
+            .collect::<Vec<_>>()
+            .join(",");
+        let sql_patt = format!(
+            "UPDATE `promotion` SET `num_used`=`num_used`-1 WHERE `code` IN ({items}) \
+             AND `num_used` > 0"
+        );
+        let mut args = MySqlArguments::default();
+        value
+            .0
+            .into_iter()
+            .map(|code| args.add(code).unwrap())
+            .count();
+        (sql_patt, args)
+    }
+}
+
+impl TryFrom<MySqlRow> for PromotionModel {
+    type Error = AppError;
+    fn try_from(value: MySqlRow) -> DefaultResult<Self, Self::Error> {
+        let code = value.try_get::<String, usize>(0)?;
+        let store_id = value.try_get::<u32, usize>(1)?;
+        let product_ids = {
+            let raw = value.try_get::<&str, usize>(2)?;
+            serde_json::from_str::<Vec<u64>>(raw).map_err(|e| AppError {
+                code: AppErrorCode::DataCorruption,
+                detail: Some(format!("promotion-product-ids: {:?}", e)),
+            })?
+        };
+        let rule = {
+            let label = value.try_get::<&str, usize>(3)?;
+            let arg0 = value.try_get::<u32, usize>(4)?;
+            let arg1 = value.try_get::<u32, usize>(5)?;
+            PromotionRuleModel::try_from((label, arg0, arg1))?
+        };
+        let start_after = value.try_get::<NaiveDateTime, usize>(6)?.and_utc().into();
+        let end_before = value.try_get::<NaiveDateTime, usize>(7)?.and_utc().into();
+        let max_usage = value.try_get::<Option<u32>, usize>(8)?;
+        let num_used = value.try_get::<u32, usize>(9)?;
+        Ok(Self {
+            code,
+            store_id,
+            product_ids,
+            rule,
+            start_after,
+            end_before,
+            max_usage,
+            num_used,
+        })
+    }
+} // end of impl PromotionModel
+
+pub(crate) struct PromotionMariaDbRepo {
+    db: Arc<AppMariaDbStore>,
+}
+
+impl PromotionMariaDbRepo {
+    pub fn try_build(dbs: &[Arc<AppMariaDbStore>]) -> DefaultResult<Self, AppError> {
+        let db = dbs.first().cloned().ok_or(AppError {
+            code: AppErrorCode::MissingDataStore,
+            detail: Some("mariadb".to_string()),
+        })?;
+        Ok(Self { db })
+    }
+
+    // increase usage count of each promotion which has not reached its limit,
+    // the codes of exhausted or non-existent promotions are returned
+    pub(super) async fn claim_usage(
+        tx: &mut Transaction<'_, MySql>,
+        codes: &[String],
+    ) -> DefaultResult<Vec<String>, AppError> {
+        let mut exhausted = Vec::new();
+        for code in codes {
+            let (sql_patt, args) = ClaimUsageArg(code.as_str()).into();
+            let resultset = run_query_once(tx, sql_patt, args, None).await?;
+            if resultset.rows_affected() == 0 {
+                exhausted.push(code.clone());
+            }
+        }
+        Ok(exhausted)
+    }
+
+    pub(super) async fn release_usage(
+        tx: &mut Transaction<'_, MySql>,
+        codes: Vec<String>,
+    ) -> DefaultResult<(), AppError> {
+        if codes.is_empty() {
+            return Ok(());
+        }
+        let (sql_patt, args) = ReleaseUsageArg(codes).into();
+        let _rs = run_query_once(tx, sql_patt, args, None).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl AbsPromotionRepo for PromotionMariaDbRepo {
+    async fn fetch(&self, codes: Vec<String>) -> DefaultResult<PromotionModelSet, AppError> {
+        if codes.is_empty() {
+            return Ok(PromotionModelSet { promotions: vec![] });
+        }
+        let (sql_patt, args) = FetchPromotionArg(codes).into();
+        let mut conn = self.db.acquire().await?;
+        let stmt = conn.prepare(sql_patt.as_str()).await?;
+        let query = stmt.query_with(args);
+        let exec = conn.as_mut();
+        let rows = query.fetch_all(exec).await?;
+        let promotions = rows
+            .into_iter()
+            .map(PromotionModel::try_from)
+            .collect::<DefaultResult<Vec<_>, AppError>>()?;
+        Ok(PromotionModelSet { promotions })
+    }
+
+    async fn save(&self, ms: PromotionModelSet) -> DefaultResult<(), AppError> {
+        if ms.promotions.is_empty() {
+            return Err(AppError {
+                code: AppErrorCode::EmptyInputData,
+                detail: Some("save PromotionModel".to_string()),
+            });
+        }
+        let (sql_patt, args) = InsertPromotionArg(ms.promotions).try_into()?;
+        let mut conn = self.db.acquire().await?;
+        let mut tx = conn.begin().await?;
+        // number of affected rows varies, depending on whether a promotion
+        // is inserted or updated
+        let _rs = run_query_once(&mut tx, sql_patt, args, None).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+} // end of impl PromotionMariaDbRepo
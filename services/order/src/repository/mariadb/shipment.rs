@@ -0,0 +1,245 @@
+use std::result::Result as DefaultResult;
+use std::sync::Arc;
+use std::vec::Vec;
+
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+use sqlx::mysql::{MySqlArguments, MySqlRow};
+use sqlx::{Acquire, Arguments, Executor, Row, Statement};
+
+use ecommerce_common::adapter::repository::OidBytes;
+use ecommerce_common::error::AppErrorCode;
+
+use crate::api::dto::ShippingMethod;
+use crate::datastore::AppMariaDbStore;
+use crate::error::AppError;
+use crate::model::{OrderLineIdentity, ShipmentLineModel, ShipmentModel, ShipmentStatus};
+use crate::repository::AbsShipmentRepo;
+
+use super::run_query_once;
+
+struct InsertShipmentArg<'a>(&'a OidBytes, &'a ShipmentModel);
+struct InsertLineArg<'a>(&'a OidBytes, &'a ShipmentModel);
+struct UpdateShipmentArg<'a>(&'a OidBytes, &'a ShipmentModel);
+
+impl From<ShipmentStatus> for &'static str {
+    fn from(value: ShipmentStatus) -> &'static str {
+        match value {
+            ShipmentStatus::Packed => "packed",
+            ShipmentStatus::Shipped => "shipped",
+            ShipmentStatus::Delivered => "delivered",
+        }
+    }
+}
+
+impl TryFrom<&str> for ShipmentStatus {
+    type Error = AppError;
+    fn try_from(value: &str) -> DefaultResult<Self, Self::Error> {
+        match value {
+            "packed" => Ok(Self::Packed),
+            "shipped" => Ok(Self::Shipped),
+            "delivered" => Ok(Self::Delivered),
+            _others => Err(AppError {
+                code: AppErrorCode::DataCorruption,
+                detail: Some(format!("shipment-status:{value}")),
+            }),
+        }
+    }
+}
+
+impl From<InsertShipmentArg<'_>> for (String, MySqlArguments) {
+    fn from(value: InsertShipmentArg<'_>) -> (String, MySqlArguments) {
+        let (oid_b, m) = (value.0, value.1);
+        // skip if any shipment with the same or greater sequence number exists,
+        // concurrent insertions of the same number are rejected by primary key
+        let sql_patt = "INSERT INTO `order_shipment`(`o_id`,`seq`,`seller_id`,`carrier`,\
+             `tracking_num`,`status`,`packed_time`,`shipped_time`,`delivered_time`) \
+             SELECT ?,?,?,?,?,?,?,?,? FROM DUAL WHERE NOT EXISTS (SELECT 1 FROM \
+             `order_shipment` WHERE `o_id`=? AND `seq`>=?)";
+        let carrier = String::from(m.carrier.clone());
+        let status: &str = m.status.into();
+        let mut args = MySqlArguments::default();
+        args.add(oid_b.as_column()).unwrap();
+        args.add(m.seq).unwrap();
+        args.add(m.seller_id).unwrap();
+        args.add(carrier).unwrap();
+        args.add(m.tracking_number.as_deref()).unwrap();
+        args.add(status).unwrap();
+        args.add(m.packed_time.naive_utc()).unwrap();
+        args.add(m.shipped_time.map(|t| t.naive_utc())).unwrap();
+        args.add(m.delivered_time.map(|t| t.naive_utc())).unwrap();
+        args.add(oid_b.as_column()).unwrap();
+        args.add(m.seq).unwrap();
+        (sql_patt.to_string(), args)
+    }
+}
+
+impl From<InsertLineArg<'_>> for (String, MySqlArguments) {
+    fn from(value: InsertLineArg<'_>) -> (String, MySqlArguments) {
+        let (oid_b, m) = (value.0, value.1);
+        let items = (0..m.lines.len())
+            .map(|_| "(?,?,?,?,?,?)")
+            .collect::<Vec<_>>()
+            .join(",");
+        let sql_patt = format!(
+            "INSERT INTO `order_shipment_line`(`o_id`,`seq`,`store_id`,`product_id`,\
+             `attr_seq`,`qty`) VALUES {items}"
+        );
+        let mut args = MySqlArguments::default();
+        m.lines
+            .iter()
+            .map(|l| {
+                args.add(oid_b.as_column()).unwrap();
+                args.add(m.seq).unwrap();
+                args.add(l.id_.store_id()).unwrap();
+                args.add(l.id_.product_id()).unwrap();
+                args.add(l.id_.attrs_seq_num()).unwrap();
+                args.add(l.qty).unwrap();
+            })
+            .count();
+        (sql_patt, args)
+    }
+}
+
+impl From<UpdateShipmentArg<'_>> for (String, MySqlArguments) {
+    fn from(value: UpdateShipmentArg<'_>) -> (String, MySqlArguments) {
+        let (oid_b, m) = (value.0, value.1);
+        let sql_patt = "UPDATE `order_shipment` SET `tracking_num`=?,`status`=?,\
+             `shipped_time`=?,`delivered_time`=? WHERE `o_id`=? AND `seq`=?";
+        let status: &str = m.status.into();
+        let mut args = MySqlArguments::default();
+        args.add(m.tracking_number.as_deref()).unwrap();
+        args.add(status).unwrap();
+        args.add(m.shipped_time.map(|t| t.naive_utc())).unwrap();
+        args.add(m.delivered_time.map(|t| t.naive_utc())).unwrap();
+        args.add(oid_b.as_column()).unwrap();
+        args.add(m.seq).unwrap();
+        (sql_patt.to_string(), args)
+    }
+}
+
+impl TryFrom<MySqlRow> for ShipmentModel {
+    type Error = AppError;
+    fn try_from(value: MySqlRow) -> DefaultResult<Self, Self::Error> {
+        let carrier = value.try_get::<String, usize>(2)?;
+        let status = ShipmentStatus::try_from(value.try_get::<&str, usize>(4)?)?;
+        let packed_time = value.try_get::<NaiveDateTime, usize>(5)?.and_utc().into();
+        let shipped_time = value
+            .try_get::<Option<NaiveDateTime>, usize>(6)?
+            .map(|t| t.and_utc().into());
+        let delivered_time = value
+            .try_get::<Option<NaiveDateTime>, usize>(7)?
+            .map(|t| t.and_utc().into());
+        Ok(Self {
+            seq: value.try_get::<u16, usize>(0)?,
+            seller_id: value.try_get::<u32, usize>(1)?,
+            carrier: ShippingMethod::from(carrier),
+            tracking_number: value.try_get::<Option<String>, usize>(3)?,
+            status,
+            packed_time,
+            shipped_time,
+            delivered_time,
+            lines: Vec::new(),
+        })
+    }
+}
+
+pub(crate) struct ShipmentMariaDbRepo {
+    db: Arc<AppMariaDbStore>,
+}
+
+impl ShipmentMariaDbRepo {
+    pub fn try_build(dbs: &[Arc<AppMariaDbStore>]) -> DefaultResult<Self, AppError> {
+        let db = dbs.first().cloned().ok_or(AppError {
+            code: AppErrorCode::MissingDataStore,
+            detail: Some("mariadb".to_string()),
+        })?;
+        Ok(Self { db })
+    }
+
+    async fn fetch_rows(
+        &self,
+        sql_patt: &str,
+        oid_b: &OidBytes,
+    ) -> DefaultResult<Vec<MySqlRow>, AppError> {
+        let mut conn = self.db.acquire().await?;
+        let stmt = conn.prepare(sql_patt).await?;
+        let query = stmt.query().bind(oid_b.as_column());
+        let exec = conn.as_mut();
+        let rows = exec.fetch_all(query).await?;
+        Ok(rows)
+    }
+} // end of impl ShipmentMariaDbRepo
+
+#[async_trait]
+impl AbsShipmentRepo for ShipmentMariaDbRepo {
+    async fn fetch_by_oid(&self, oid: &str) -> DefaultResult<Vec<ShipmentModel>, AppError> {
+        let oid_b = OidBytes::try_from(oid)?;
+        let sql_patt = "SELECT `seq`,`seller_id`,`carrier`,`tracking_num`,`status`,\
+                        `packed_time`,`shipped_time`,`delivered_time` FROM `order_shipment` \
+                        WHERE `o_id`=? ORDER BY `seq` ASC";
+        let mut out = self
+            .fetch_rows(sql_patt, &oid_b)
+            .await?
+            .into_iter()
+            .map(ShipmentModel::try_from)
+            .collect::<DefaultResult<Vec<_>, AppError>>()?;
+        let sql_patt = "SELECT `seq`,`store_id`,`product_id`,`attr_seq`,`qty` FROM \
+                        `order_shipment_line` WHERE `o_id`=?";
+        let rows = self.fetch_rows(sql_patt, &oid_b).await?;
+        for row in rows {
+            let seq = row.try_get::<u16, usize>(0)?;
+            let store_id = row.try_get::<u32, usize>(1)?;
+            let product_id = row.try_get::<u64, usize>(2)?;
+            let attr_seq = row.try_get::<u16, usize>(3)?;
+            let qty = row.try_get::<u32, usize>(4)?;
+            let shipment = out.iter_mut().find(|m| m.seq == seq).ok_or(AppError {
+                code: AppErrorCode::DataCorruption,
+                detail: Some(format!("shipment-line, oid:{oid}, seq:{seq}")),
+            })?;
+            shipment.lines.push(ShipmentLineModel {
+                id_: OrderLineIdentity::from((store_id, product_id, attr_seq)),
+                qty,
+            });
+        }
+        Ok(out)
+    }
+
+    async fn create(&self, oid: &str, obj: ShipmentModel) -> DefaultResult<bool, AppError> {
+        if obj.lines.is_empty() {
+            return Err(AppError {
+                code: AppErrorCode::EmptyInputData,
+                detail: Some(format!("shipment-lines, oid:{oid}")),
+            });
+        }
+        let oid_b = OidBytes::try_from(oid)?;
+        let mut conn = self.db.acquire().await?;
+        let mut tx = conn.begin().await?;
+        let (sql_patt, args) = InsertShipmentArg(&oid_b, &obj).into();
+        let stmt = tx.prepare(sql_patt.as_str()).await?;
+        let query = stmt.query_with(args);
+        let exec = tx.as_mut();
+        let num_inserted = match query.execute(exec).await {
+            Ok(rs) => rs.rows_affected(),
+            Err(sqlx::Error::Database(e)) if e.is_unique_violation() => 0,
+            Err(e) => return Err(e.into()),
+        };
+        if num_inserted == 0 {
+            return Ok(false); // the transaction is rolled back on drop
+        }
+        let (sql_patt, args) = InsertLineArg(&oid_b, &obj).into();
+        let _rs = run_query_once(&mut tx, sql_patt, args, Some(obj.lines.len())).await?;
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    async fn update(&self, oid: &str, obj: ShipmentModel) -> DefaultResult<(), AppError> {
+        let oid_b = OidBytes::try_from(oid)?;
+        let mut conn = self.db.acquire().await?;
+        let mut tx = conn.begin().await?;
+        let (sql_patt, args) = UpdateShipmentArg(&oid_b, &obj).into();
+        let _rs = run_query_once(&mut tx, sql_patt, args, Some(1)).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+} // end of impl AbsShipmentRepo
@@ -0,0 +1,175 @@
+use std::result::Result as DefaultResult;
+use std::sync::Arc;
+use std::vec::Vec;
+
+use async_trait::async_trait;
+use sqlx::mysql::{MySqlArguments, MySqlRow};
+use sqlx::{Acquire, Arguments, Executor, Row, Statement};
+
+use ecommerce_common::api::dto::CountryCode;
+use ecommerce_common::error::AppErrorCode;
+
+use crate::api::dto::ShippingMethod;
+use crate::datastore::AppMariaDbStore;
+use crate::error::AppError;
+use crate::model::{ShippingMethodModel, ShippingMethodModelSet, ShippingRateModel};
+use crate::repository::AbsShippingMethodRepo;
+
+use super::run_query_once;
+
+struct DiscardRateArg<'a>(u32, &'a ShippingMethod);
+struct InsertRateArg<'a>(&'a ShippingMethodModel);
+struct FetchRateArg(Vec<u32>);
+struct RateRow(MySqlRow);
+
+impl<'a> From<DiscardRateArg<'a>> for (String, MySqlArguments) {
+    fn from(value: DiscardRateArg<'a>) -> (String, MySqlArguments) {
+        let sql_patt = "DELETE FROM `shipping_rate` WHERE `store_id`=? AND `method`=?";
+        let method: String = value.1.clone().into();
+        let mut args = MySqlArguments::default();
+        args.add(value.0).unwrap();
+        args.add(method).unwrap();
+        (sql_patt.to_string(), args)
+    }
+}
+
+impl<'a> From<InsertRateArg<'a>> for (String, MySqlArguments) {
+    fn from(value: InsertRateArg<'a>) -> (String, MySqlArguments) {
+        let m = value.0;
+        let items = (0..m.rates.len())
+            .map(|_| "(?,?,?,?,?,?,?)")
+            .collect::<Vec<_>>()
+            .join(",");
+        let sql_patt = format!(
+            "INSERT INTO `shipping_rate`(`store_id`,`method`,`seq`,`country`,`region`,\
+             `max_weight_gram`,`fee`) VALUES {items}"
+        );
+        let method: String = m.method.clone().into();
+        let mut args = MySqlArguments::default();
+        m.rates
+            .iter()
+            .enumerate()
+            .map(|(seq, r)| {
+                let country: String = r.country.clone().into();
+                args.add(m.store_id).unwrap();
+                args.add(method.as_str()).unwrap();
+                args.add(seq as u16).unwrap();
+                args.add(country).unwrap();
+                args.add(r.region.clone().unwrap_or_default()).unwrap();
+                args.add(r.max_weight_gram).unwrap();
+                args.add(r.fee).unwrap();
+            })
+            .count();
+        (sql_patt, args)
+    }
+}
+
+impl From<FetchRateArg> for (String, MySqlArguments) {
+    fn from(value: FetchRateArg) -> (String, MySqlArguments) {
+        let mut store_ids = value.0;
+        store_ids.sort();
+        store_ids.dedup();
+        let items = (0..store_ids.len())
+            .map(|_| "?")
+            .collect::<Vec<_>>()
+            .join(",");
+        let sql_patt = format!(
+            "SELECT `store_id`,`method`,`country`,`region`,`max_weight_gram`,`fee` \
+             FROM `shipping_rate` WHERE `store_id` IN ({items}) ORDER BY `store_id`,\
+             `method`,`seq`"
+        );
+        let mut args = MySqlArguments::default();
+        store_ids
+            .into_iter()
+            .map(|id| args.add(id).unwrap())
+            .count();
+        (sql_patt, args)
+    }
+}
+
+impl TryFrom<RateRow> for (u32, ShippingMethod, ShippingRateModel) {
+    type Error = AppError;
+    fn try_from(value: RateRow) -> DefaultResult<Self, Self::Error> {
+        let row = value.0;
+        let store_id = row.try_get::<u32, usize>(0)?;
+        let method = row.try_get::<String, usize>(1)?;
+        let country = row.try_get::<String, usize>(2)?;
+        let region = row.try_get::<String, usize>(3)?;
+        let rate = ShippingRateModel {
+            country: CountryCode::from(country),
+            region: Some(region).filter(|r| !r.is_empty()),
+            max_weight_gram: row.try_get::<u32, usize>(4)?,
+            fee: row.try_get::<u32, usize>(5)?,
+        };
+        Ok((store_id, ShippingMethod::from(method), rate))
+    }
+}
+
+pub(crate) struct ShippingMethodMariaDbRepo {
+    db: Arc<AppMariaDbStore>,
+}
+
+impl ShippingMethodMariaDbRepo {
+    pub fn try_build(dbs: &[Arc<AppMariaDbStore>]) -> DefaultResult<Self, AppError> {
+        let db = dbs.first().cloned().ok_or(AppError {
+            code: AppErrorCode::MissingDataStore,
+            detail: Some("mariadb".to_string()),
+        })?;
+        Ok(Self { db })
+    }
+}
+
+#[async_trait]
+impl AbsShippingMethodRepo for ShippingMethodMariaDbRepo {
+    async fn fetch(&self, store_ids: Vec<u32>) -> DefaultResult<ShippingMethodModelSet, AppError> {
+        if store_ids.is_empty() {
+            return Ok(ShippingMethodModelSet { methods: vec![] });
+        }
+        let (sql_patt, args) = FetchRateArg(store_ids).into();
+        let mut conn = self.db.acquire().await?;
+        let stmt = conn.prepare(sql_patt.as_str()).await?;
+        let query = stmt.query_with(args);
+        let exec = conn.as_mut();
+        let rows = query.fetch_all(exec).await?;
+        let mut methods: Vec<ShippingMethodModel> = Vec::new();
+        for row in rows {
+            let (store_id, method, rate) = RateRow(row).try_into()?;
+            let found = methods
+                .iter_mut()
+                .find(|m| m.store_id == store_id && m.method == method);
+            if let Some(m) = found {
+                m.rates.push(rate);
+            } else {
+                let rates = vec![rate];
+                methods.push(ShippingMethodModel {
+                    store_id,
+                    method,
+                    rates,
+                });
+            }
+        }
+        Ok(ShippingMethodModelSet { methods })
+    } // end of fn fetch
+
+    async fn save(&self, ms: ShippingMethodModelSet) -> DefaultResult<(), AppError> {
+        if ms.methods.is_empty() {
+            return Err(AppError {
+                code: AppErrorCode::EmptyInputData,
+                detail: Some("save ShippingMethodModel".to_string()),
+            });
+        }
+        let mut conn = self.db.acquire().await?;
+        let mut tx = conn.begin().await?;
+        for m in ms.methods.iter() {
+            let (sql_patt, args) = DiscardRateArg(m.store_id, &m.method).into();
+            let _rs = run_query_once(&mut tx, sql_patt, args, None).await?;
+            if !m.rates.is_empty() {
+                let num_batch = m.rates.len();
+                let (sql_patt, args) = InsertRateArg(m).into();
+                let _rs = run_query_once(&mut tx, sql_patt, args, Some(num_batch)).await?;
+            }
+        }
+        tx.commit().await?;
+        Ok(())
+    } // end of fn save
+} // end of impl ShippingMethodMariaDbRepo
@@ -8,26 +8,29 @@ use std::vec::Vec;
 use async_trait::async_trait;
 use chrono::{DateTime, FixedOffset, NaiveDateTime};
 use sqlx::database::Database as AbstractDatabase;
-use sqlx::mysql::{MySqlArguments, MySqlRow};
+use sqlx::mysql::{MySqlArguments, MySqlConnection, MySqlRow};
 use sqlx::{Arguments, Connection, Executor, IntoArguments, MySql, Row, Statement, Transaction};
 
 use ecommerce_common::adapter::repository::OidBytes;
 use ecommerce_common::error::AppErrorCode;
 
 use crate::api::rpc::dto::{InventoryEditStockLevelDto, StockLevelReturnDto, StockReturnErrorDto};
-use crate::api::web::dto::OrderLineCreateErrorDto;
+use crate::api::web::dto::{OrderLineCancelErrorDto, OrderLineCreateErrorDto};
 use crate::datastore::AppMariaDbStore;
 use crate::error::AppError;
 use crate::model::{
-    OrderLineModel, OrderLineModelSet, ProductStockIdentity, ProductStockModel, StockLevelModelSet,
-    StockQtyRsvModel, StockQuantityModel, StoreStockModel,
+    OrderLineCancelModel, OrderLineIdentity, OrderLineModel, OrderLineModelSet,
+    ProductStockIdentity, ProductStockIdentity2, ProductStockModel, StockAllocPolicyModel,
+    StockAllocPolicyModelSet, StockAllocStrategy, StockLevelModelSet, StockQtyRsvModel,
+    StockQuantityModel, StockWarehouseModel, StockWarehouseModelSet, StoreStockModel,
 };
 use crate::repository::{
-    AbsOrderStockRepo, AppStockRepoReserveReturn, AppStockRepoReserveUserFunc,
-    AppStockRepoReturnUserFunc,
+    stock_return_corrupted, AbsOrderStockRepo, AppOrderRepoCancelLinesUserFunc,
+    AppStockRepoReserveReturn, AppStockRepoReserveUserFunc, AppStockRepoReturnUserFunc,
 };
 
 use super::order::OrderMariaDbRepo;
+use super::promotion::PromotionMariaDbRepo;
 use super::{run_query_once, to_app_oid};
 
 struct InsertQtyArg(Vec<(u32, ProductStockModel)>);
@@ -36,9 +39,24 @@ struct ReserveArg(Vec<(u32, ProductStockModel)>);
 struct ReturnArg(Vec<(u32, ProductStockModel)>);
 
 struct FetchQtyArg(Vec<ProductStockIdentity>);
+struct FetchQtyByProductArg(Vec<ProductStockIdentity2>, DateTime<FixedOffset>);
+struct FetchQtyByStoreArg {
+    store_id: u32,
+    product_ids: Vec<u64>,
+    expiry_after: Option<DateTime<FixedOffset>>,
+    expiry_before: Option<DateTime<FixedOffset>>,
+}
 struct FetchQtyForRsvArg<'a>(&'a [OrderLineModel]); // TODO, add current time for expiry filtering
 struct FetchRsvOrderArg<'a>(OidBytes, &'a Vec<InventoryEditStockLevelDto>);
 
+// product ID zero in the table indicates default policy of the store
+struct InsertAllocPolicyArg(Vec<StockAllocPolicyModel>);
+struct FetchAllocPolicyArg(Vec<u32>);
+struct StkAllocPolicyRow(MySqlRow);
+struct InsertWarehouseArg(Vec<StockWarehouseModel>);
+struct FetchWarehouseArg(Vec<u32>);
+struct StkWarehouseRow(MySqlRow);
+
 struct StkProdRows(Vec<MySqlRow>);
 struct StkProdRow(MySqlRow);
 struct StkRsvDetailRows(Vec<MySqlRow>);
@@ -46,8 +64,11 @@ struct StkRsvDetailRow(MySqlRow);
 
 impl InsertQtyArg {
     fn sql_pattern(num_batch: usize) -> String {
-        let col_seq = "`store_id`,`product_id`,`expiry`,`qty_total`,`qty_cancelled`";
-        let items = (0..num_batch).map(|_| "(?,?,?,?,?)").collect::<Vec<_>>();
+        let col_seq = "`store_id`,`product_id`,`warehouse_id`,`expiry`,`qty_total`,\
+                       `qty_cancelled`,`arrival`";
+        let items = (0..num_batch)
+            .map(|_| "(?,?,?,?,?,?,?)")
+            .collect::<Vec<_>>();
         format!(
             "INSERT INTO `stock_level_inventory`({}) VALUES {}",
             col_seq,
@@ -67,11 +88,15 @@ impl<'q> IntoArguments<'q, MySql> for InsertQtyArg {
                     p.quantity.total,
                     p.quantity.cancelled,
                 );
+                // arrival time has been assigned before insertion
+                let arrival = p.arrival.map(|t| t.naive_utc());
                 out.add(store_id).unwrap();
                 out.add(prod_id).unwrap();
+                out.add(p.warehouse_id).unwrap();
                 out.add(expiry).unwrap();
                 out.add(q_total).unwrap();
                 out.add(q_cancelled).unwrap();
+                out.add(arrival).unwrap();
             })
             .count();
         out
@@ -89,7 +114,7 @@ impl From<InsertQtyArg> for Vec<(String, MySqlArguments)> {
 
 impl UpdateQtyArg {
     fn sql_pattern(num_batch: usize) -> String {
-        let condition = "(`store_id`=? AND `product_id`=? AND `expiry`=?)";
+        let condition = "(`store_id`=? AND `product_id`=? AND `warehouse_id`=? AND `expiry`=?)";
         let case_ops = (0..num_batch)
             .flat_map(|_| ["WHEN", condition, "THEN", "?"])
             .collect::<Vec<_>>()
@@ -121,6 +146,7 @@ impl<'q> IntoArguments<'q, MySql> for UpdateQtyArg {
                 );
                 out.add(store_id).unwrap();
                 out.add(prod_id).unwrap();
+                out.add(p.warehouse_id).unwrap();
                 out.add(expiry).unwrap();
                 out.add(q_total).unwrap();
             })
@@ -135,6 +161,7 @@ impl<'q> IntoArguments<'q, MySql> for UpdateQtyArg {
                 );
                 out.add(store_id).unwrap();
                 out.add(prod_id).unwrap();
+                out.add(p.warehouse_id).unwrap();
                 out.add(expiry).unwrap();
                 out.add(q_cancelled).unwrap();
             })
@@ -145,6 +172,7 @@ impl<'q> IntoArguments<'q, MySql> for UpdateQtyArg {
                 let (expiry, prod_id) = (p.expiry_without_millis().naive_utc(), p.id_);
                 out.add(store_id).unwrap();
                 out.add(prod_id).unwrap();
+                out.add(p.warehouse_id).unwrap();
                 out.add(expiry).unwrap();
             })
             .count();
@@ -163,7 +191,7 @@ impl From<UpdateQtyArg> for Vec<(String, MySqlArguments)> {
 
 impl ReserveArg {
     fn pattern_update_block(num_batch: usize) -> (String, String) {
-        let condition = "(`store_id`=? AND `product_id`=? AND `expiry`=?)";
+        let condition = "(`store_id`=? AND `product_id`=? AND `warehouse_id`=? AND `expiry`=?)";
         let case_ops = (0..num_batch)
             .flat_map(|_| ["WHEN", condition, "THEN", "?"])
             .collect::<Vec<_>>()
@@ -182,8 +210,8 @@ impl ReserveArg {
         )
     }
     fn pattern_add_order_rsv(num_batch: usize) -> String {
-        let col_seq = "`store_id`,`product_id`,`expiry`,`order_id`,`qty_reserved`";
-        let items = (0..num_batch).map(|_| "(?,?,?,?,?)").collect::<Vec<_>>();
+        let col_seq = "`store_id`,`product_id`,`warehouse_id`,`expiry`,`order_id`,`qty_reserved`";
+        let items = (0..num_batch).map(|_| "(?,?,?,?,?,?)").collect::<Vec<_>>();
         format!(
             "INSERT INTO `stock_rsv_detail`({col_seq}) VALUES {}",
             items.join(",")
@@ -201,6 +229,7 @@ impl ReserveArg {
                 );
                 out.add(store_id).unwrap();
                 out.add(prod_id).unwrap();
+                out.add(p.warehouse_id).unwrap();
                 out.add(expiry).unwrap();
                 out.add(q_booked).unwrap();
             })
@@ -211,6 +240,7 @@ impl ReserveArg {
                 let (expiry, prod_id) = (p.expiry_without_millis().naive_utc(), p.id_);
                 out.add(store_id).unwrap();
                 out.add(prod_id).unwrap();
+                out.add(p.warehouse_id).unwrap();
                 out.add(expiry).unwrap();
             })
             .count();
@@ -228,6 +258,7 @@ impl ReserveArg {
                 );
                 out.add(store_id).unwrap();
                 out.add(prod_id).unwrap();
+                out.add(p.warehouse_id).unwrap();
                 out.add(expiry).unwrap();
                 let (oid, rsv_per_item) = (detail.oid, detail.reserved);
                 // TODO, move to beginning of `reserve()`
@@ -277,6 +308,7 @@ impl ReturnArg {
                 let qty_rsv_o = _rsv_detail.reserved;
                 out.add(store_id).unwrap();
                 out.add(prod_id).unwrap();
+                out.add(p.warehouse_id).unwrap();
                 out.add(expiry).unwrap();
                 out.add(qty_rsv_o).unwrap();
             })
@@ -288,6 +320,7 @@ impl ReturnArg {
                 let (expiry, prod_id) = (p.expiry_without_millis().naive_utc(), p.id_);
                 out.add(store_id).unwrap();
                 out.add(prod_id).unwrap();
+                out.add(p.warehouse_id).unwrap();
                 out.add(expiry).unwrap();
             })
             .count();
@@ -312,9 +345,10 @@ impl From<ReturnArg> for Vec<(String, MySqlArguments)> {
 
 impl FetchQtyArg {
     fn sql_pattern(num_batch: usize) -> String {
-        let condition = "(`store_id`=? AND `product_id`=? AND `expiry`=?)";
+        let condition = "(`store_id`=? AND `product_id`=? AND `warehouse_id`=? AND `expiry`=?)";
         let pid_cmps = (0..num_batch).map(|_| condition).collect::<Vec<_>>();
-        let col_seq = "`store_id`,`product_id`,`expiry`,`qty_total`,`qty_cancelled`,`qty_tot_rsv`";
+        let col_seq = "`store_id`,`product_id`,`expiry`,`qty_total`,`qty_cancelled`,\
+                       `qty_tot_rsv`,`arrival`,`warehouse_id`";
         format!(
             "SELECT {col_seq} FROM `stock_level_inventory` WHERE {}",
             pid_cmps.join("OR")
@@ -331,6 +365,7 @@ impl<'q> IntoArguments<'q, MySql> for FetchQtyArg {
                 let (store_id, prod_id) = (co.store_id, co.product_id);
                 out.add(store_id).unwrap();
                 out.add(prod_id).unwrap();
+                out.add(co.warehouse_id).unwrap();
                 out.add(expiry).unwrap();
             })
             .count();
@@ -346,11 +381,97 @@ impl From<FetchQtyArg> for (String, MySqlArguments) {
     }
 }
 
+impl FetchQtyByProductArg {
+    fn sql_pattern(num_batch: usize) -> String {
+        let condition = "(`store_id`=? AND `product_id`=?)";
+        let pid_cmps = (0..num_batch).map(|_| condition).collect::<Vec<_>>();
+        let col_seq = "`store_id`,`product_id`,`expiry`,`qty_total`,`qty_cancelled`,\
+                       `qty_tot_rsv`,`arrival`,`warehouse_id`";
+        format!(
+            "SELECT {col_seq} FROM `stock_level_inventory` WHERE `expiry` > ? AND ({})",
+            pid_cmps.join("OR")
+        )
+    }
+}
+impl<'q> IntoArguments<'q, MySql> for FetchQtyByProductArg {
+    fn into_arguments(self) -> <MySql as AbstractDatabase>::Arguments<'q> {
+        let (pids, time_now) = (self.0, self.1);
+        let mut out = MySqlArguments::default();
+        out.add(time_now.naive_utc()).unwrap();
+        pids.into_iter()
+            .map(|d| {
+                out.add(d.store_id).unwrap();
+                out.add(d.product_id).unwrap();
+            })
+            .count();
+        out
+    }
+}
+impl From<FetchQtyByProductArg> for (String, MySqlArguments) {
+    fn from(value: FetchQtyByProductArg) -> (String, MySqlArguments) {
+        (
+            FetchQtyByProductArg::sql_pattern(value.0.len()),
+            value.into_arguments(),
+        )
+    }
+}
+
+impl FetchQtyByStoreArg {
+    fn sql_pattern(&self) -> String {
+        let mut conditions = vec!["`store_id`=?".to_string()];
+        if !self.product_ids.is_empty() {
+            let items = (0..self.product_ids.len())
+                .map(|_| "?")
+                .collect::<Vec<_>>()
+                .join(",");
+            conditions.push(format!("`product_id` IN ({items})"));
+        }
+        if self.expiry_after.is_some() {
+            conditions.push("`expiry` >= ?".to_string());
+        }
+        if self.expiry_before.is_some() {
+            conditions.push("`expiry` <= ?".to_string());
+        }
+        let col_seq = "`store_id`,`product_id`,`expiry`,`qty_total`,`qty_cancelled`,\
+                       `qty_tot_rsv`,`arrival`,`warehouse_id`";
+        format!(
+            "SELECT {col_seq} FROM `stock_level_inventory` WHERE {}",
+            conditions.join(" AND ")
+        )
+    }
+}
+impl<'q> IntoArguments<'q, MySql> for FetchQtyByStoreArg {
+    fn into_arguments(self) -> <MySql as AbstractDatabase>::Arguments<'q> {
+        let mut out = MySqlArguments::default();
+        out.add(self.store_id).unwrap();
+        self.product_ids
+            .into_iter()
+            .map(|pid| {
+                out.add(pid).unwrap();
+            })
+            .count();
+        [self.expiry_after, self.expiry_before]
+            .into_iter()
+            .flatten()
+            .map(|t| {
+                out.add(t.naive_utc()).unwrap();
+            })
+            .count();
+        out
+    }
+}
+impl From<FetchQtyByStoreArg> for (String, MySqlArguments) {
+    fn from(value: FetchQtyByStoreArg) -> (String, MySqlArguments) {
+        (value.sql_pattern(), value.into_arguments())
+    }
+}
+
 impl<'a> FetchQtyForRsvArg<'a> {
     fn sql_pattern(num_batch: usize) -> String {
         let condition = "(`store_id`=? AND `product_id`=?)";
         let pid_cmps = (0..num_batch).map(|_| condition).collect::<Vec<_>>();
-        let col_seq = "`store_id`,`product_id`,`expiry`,`qty_total`, `qty_cancelled`,`qty_tot_rsv`";
+        let col_seq = "`store_id`,`product_id`,`expiry`,`qty_total`,`qty_cancelled`,\
+                       `qty_tot_rsv`,`arrival`,`warehouse_id`";
         format!(
             "SELECT {col_seq} FROM `stock_level_inventory` WHERE {}",
             pid_cmps.join("OR")
@@ -384,11 +505,13 @@ impl<'a> FetchRsvOrderArg<'a> {
         let condition = "(`a`.`store_id`=? AND `a`.`product_id`=?)";
         let pid_cmps = (0..num_batch).map(|_| condition).collect::<Vec<_>>();
         let col_seq = "`a`.`store_id`,`a`.`product_id`,`a`.`expiry`,`a`.`order_id`,\
-            `a`.`qty_reserved`,`b`.`qty_total`,`b`.`qty_cancelled`,`b`.`qty_tot_rsv`";
+            `a`.`qty_reserved`,`b`.`qty_total`,`b`.`qty_cancelled`,`b`.`qty_tot_rsv`,\
+            `b`.`arrival`,`a`.`warehouse_id`";
         format!(
             "SELECT {col_seq} FROM `stock_rsv_detail` AS `a` INNER JOIN \
             `stock_level_inventory` AS `b` ON (`a`.`store_id`=`b`.`store_id` AND \
-            `a`.`product_id`=`b`.`product_id` AND `a`.`expiry`=`b`.`expiry`) \
+            `a`.`product_id`=`b`.`product_id` AND `a`.`warehouse_id`=`b`.`warehouse_id` AND \
+            `a`.`expiry`=`b`.`expiry`) \
              WHERE `a`.`order_id`=? AND ({})",
             pid_cmps.join("OR")
         )
@@ -419,6 +542,145 @@ impl<'a> From<FetchRsvOrderArg<'a>> for (String, MySqlArguments) {
     }
 }
 
+impl From<InsertAllocPolicyArg> for (String, MySqlArguments) {
+    fn from(value: InsertAllocPolicyArg) -> (String, MySqlArguments) {
+        let items = (0..value.0.len())
+            .map(|_| "(?,?,?,?)")
+            .collect::<Vec<_>>()
+            .join(",");
+        let sql_patt = format!(
+            "INSERT INTO `stock_alloc_policy`(`store_id`,`product_id`,`strategy`,\
+             `min_shelf_days`) VALUES {items} ON DUPLICATE KEY UPDATE \
+             `strategy`=VALUE(`strategy`),`min_shelf_days`=VALUE(`min_shelf_days`)"
+        );
+        let mut args = MySqlArguments::default();
+        value
+            .0
+            .into_iter()
+            .map(|m| {
+                let strategy = match m.strategy {
+                    StockAllocStrategy::Fefo => "FEFO",
+                    StockAllocStrategy::Fifo => "FIFO",
+                };
+                args.add(m.store_id).unwrap();
+                args.add(m.product_id.unwrap_or(0)).unwrap();
+                args.add(strategy).unwrap();
+                args.add(m.min_shelf_days).unwrap();
+            })
+            .count();
+        (sql_patt, args)
+    }
+}
+impl From<FetchAllocPolicyArg> for (String, MySqlArguments) {
+    fn from(value: FetchAllocPolicyArg) -> (String, MySqlArguments) {
+        let items = (0..value.0.len())
+            .map(|_| "?")
+            .collect::<Vec<_>>()
+            .join(",");
+        let sql_patt = format!(
+            "SELECT `store_id`,`product_id`,`strategy`,`min_shelf_days` FROM \
+             `stock_alloc_policy` WHERE `store_id` IN ({items})"
+        );
+        let mut args = MySqlArguments::default();
+        value
+            .0
+            .into_iter()
+            .map(|store_id| {
+                args.add(store_id).unwrap();
+            })
+            .count();
+        (sql_patt, args)
+    }
+}
+impl TryInto<StockAllocPolicyModel> for StkAllocPolicyRow {
+    type Error = AppError;
+    fn try_into(self) -> DefaultResult<StockAllocPolicyModel, Self::Error> {
+        let row = self.0;
+        let store_id = row.try_get::<u32, usize>(0)?;
+        let product_id = Some(row.try_get::<u64, usize>(1)?).filter(|v| *v != 0);
+        let strategy = match row.try_get::<&str, usize>(2)? {
+            "FEFO" => StockAllocStrategy::Fefo,
+            "FIFO" => StockAllocStrategy::Fifo,
+            others => {
+                return Err(AppError {
+                    code: AppErrorCode::DataCorruption,
+                    detail: Some(format!("stock-alloc-strategy:{others}")),
+                });
+            }
+        };
+        let min_shelf_days = row.try_get::<u16, usize>(3)?;
+        Ok(StockAllocPolicyModel {
+            store_id,
+            product_id,
+            strategy,
+            min_shelf_days,
+        })
+    }
+}
+
+impl From<InsertWarehouseArg> for (String, MySqlArguments) {
+    fn from(value: InsertWarehouseArg) -> (String, MySqlArguments) {
+        let items = (0..value.0.len())
+            .map(|_| "(?,?,?,?,?)")
+            .collect::<Vec<_>>()
+            .join(",");
+        let sql_patt = format!(
+            "INSERT INTO `stock_warehouse`(`store_id`,`warehouse_id`,`country`,`region`,\
+             `city`) VALUES {items} ON DUPLICATE KEY UPDATE `country`=VALUE(`country`),\
+             `region`=VALUE(`region`),`city`=VALUE(`city`)"
+        );
+        let mut args = MySqlArguments::default();
+        value
+            .0
+            .into_iter()
+            .map(|m| {
+                let country: String = m.country.into();
+                args.add(m.store_id).unwrap();
+                args.add(m.warehouse_id).unwrap();
+                args.add(country).unwrap();
+                args.add(m.region).unwrap();
+                args.add(m.city).unwrap();
+            })
+            .count();
+        (sql_patt, args)
+    }
+}
+impl From<FetchWarehouseArg> for (String, MySqlArguments) {
+    fn from(value: FetchWarehouseArg) -> (String, MySqlArguments) {
+        let items = (0..value.0.len())
+            .map(|_| "?")
+            .collect::<Vec<_>>()
+            .join(",");
+        let sql_patt = format!(
+            "SELECT `store_id`,`warehouse_id`,`country`,`region`,`city` FROM \
+             `stock_warehouse` WHERE `store_id` IN ({items})"
+        );
+        let mut args = MySqlArguments::default();
+        value
+            .0
+            .into_iter()
+            .map(|store_id| {
+                args.add(store_id).unwrap();
+            })
+            .count();
+        (sql_patt, args)
+    }
+}
+impl TryInto<StockWarehouseModel> for StkWarehouseRow {
+    type Error = AppError;
+    fn try_into(self) -> DefaultResult<StockWarehouseModel, Self::Error> {
+        let row = self.0;
+        let country = row.try_get::<String, usize>(2)?;
+        Ok(StockWarehouseModel {
+            store_id: row.try_get::<u32, usize>(0)?,
+            warehouse_id: row.try_get::<u32, usize>(1)?,
+            country: country.into(),
+            region: row.try_get::<String, usize>(3)?,
+            city: row.try_get::<String, usize>(4)?,
+        })
+    }
+}
+
 macro_rules! rows_to_stklvl_mset {
     ($rows:expr, $convertor:ident) => {{
         let mut errors: Vec<AppError> = Vec::new();
@@ -477,13 +739,17 @@ impl TryInto<ProductStockModel> for StkProdRow {
         let total = row.try_get::<u32, usize>(3)?;
         let cancelled = row.try_get::<u32, usize>(4)?;
         let booked = row.try_get::<u32, usize>(5)?;
+        let arrival = row.try_get::<NaiveDateTime, usize>(6)?.and_utc();
+        let warehouse_id = row.try_get::<u32, usize>(7)?;
         // Note, the conversion does not include reservation detail
         let quantity = StockQuantityModel::new(total, cancelled, booked, None);
         Ok(ProductStockModel {
             id_: prod_id,
+            warehouse_id,
             expiry,
             quantity,
             is_create: false,
+            arrival: Some(arrival),
         })
     }
 }
@@ -515,11 +781,15 @@ impl TryInto<ProductStockModel> for StkRsvDetailRow {
             let booked = row.try_get::<u32, usize>(7)?;
             StockQuantityModel::new(total, cancelled, booked, Some(rsv_detail))
         };
+        let arrival = row.try_get::<NaiveDateTime, usize>(8)?.and_utc();
+        let warehouse_id = row.try_get::<u32, usize>(9)?;
         Ok(ProductStockModel {
             id_: prod_id,
+            warehouse_id,
             expiry,
             quantity,
             is_create: false,
+            arrival: Some(arrival),
         })
     }
 }
@@ -550,6 +820,44 @@ impl AbsOrderStockRepo for StockMariaDbRepo {
         let msets = StkProdRows(rows).try_into()?;
         Ok(msets)
     }
+    async fn fetch_by_product(
+        &self,
+        pids: Vec<ProductStockIdentity2>,
+    ) -> DefaultResult<StockLevelModelSet, AppError> {
+        if pids.is_empty() {
+            return Ok(StockLevelModelSet { stores: vec![] });
+        }
+        let (sql_patt, args) = FetchQtyByProductArg(pids, self._time_now).into();
+        let mut conn = self._db.acquire().await?;
+        let stmt = conn.prepare(sql_patt.as_str()).await?;
+        let query = stmt.query_with(args);
+        let exec = conn.as_mut();
+        let rows = query.fetch_all(exec).await?;
+        let msets = StkProdRows(rows).try_into()?;
+        Ok(msets)
+    }
+    async fn fetch_by_store(
+        &self,
+        store_id: u32,
+        product_ids: Vec<u64>,
+        expiry_after: Option<DateTime<FixedOffset>>,
+        expiry_before: Option<DateTime<FixedOffset>>,
+    ) -> DefaultResult<StockLevelModelSet, AppError> {
+        let arg = FetchQtyByStoreArg {
+            store_id,
+            product_ids,
+            expiry_after,
+            expiry_before,
+        };
+        let (sql_patt, args) = arg.into();
+        let mut conn = self._db.acquire().await?;
+        let stmt = conn.prepare(sql_patt.as_str()).await?;
+        let query = stmt.query_with(args);
+        let exec = conn.as_mut();
+        let rows = query.fetch_all(exec).await?;
+        let msets = StkProdRows(rows).try_into()?;
+        Ok(msets)
+    }
     async fn save(&self, slset: StockLevelModelSet) -> DefaultResult<(), AppError> {
         // Note, the difference from `save()` in-memory repository is that this
         // function does not save reservation records.
@@ -561,8 +869,11 @@ impl AbsOrderStockRepo for StockMariaDbRepo {
                 let (store_id, products) = (s.store_id, s.products);
                 products
                     .into_iter()
-                    .map(|p| {
+                    .map(|mut p| {
                         let chosen = if p.is_create {
+                            if p.arrival.is_none() {
+                                p.arrival = Some(self._time_now.to_utc());
+                            }
                             &mut stk_add
                         } else {
                             &mut stk_modify
@@ -580,10 +891,76 @@ impl AbsOrderStockRepo for StockMariaDbRepo {
         Ok(())
     }
 
+    async fn fetch_alloc_policy(
+        &self,
+        store_ids: Vec<u32>,
+    ) -> DefaultResult<StockAllocPolicyModelSet, AppError> {
+        let mut conn = self._db.acquire().await?;
+        let exec = conn.as_mut();
+        Self::_fetch_alloc_policy(exec, store_ids).await
+    }
+
+    async fn save_alloc_policy(
+        &self,
+        ms: Vec<StockAllocPolicyModel>,
+    ) -> DefaultResult<(), AppError> {
+        if ms.is_empty() {
+            return Err(AppError {
+                code: AppErrorCode::EmptyInputData,
+                detail: Some("save StockAllocPolicyModel".to_string()),
+            });
+        }
+        let (sql_patt, args) = InsertAllocPolicyArg(ms).into();
+        let mut conn = self._db.acquire().await?;
+        let mut tx = conn.begin().await?;
+        // number of affected rows is not checked, an updated row is counted twice
+        let _rs = run_query_once(&mut tx, sql_patt, args, None).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn fetch_warehouses(
+        &self,
+        mut store_ids: Vec<u32>,
+    ) -> DefaultResult<StockWarehouseModelSet, AppError> {
+        store_ids.sort();
+        store_ids.dedup();
+        if store_ids.is_empty() {
+            return Ok(StockWarehouseModelSet { warehouses: vec![] });
+        }
+        let (sql_patt, args) = FetchWarehouseArg(store_ids).into();
+        let mut conn = self._db.acquire().await?;
+        let stmt = conn.prepare(sql_patt.as_str()).await?;
+        let query = stmt.query_with(args);
+        let exec = conn.as_mut();
+        let rows = query.fetch_all(exec).await?;
+        let warehouses = rows
+            .into_iter()
+            .map(|r| StkWarehouseRow(r).try_into())
+            .collect::<DefaultResult<Vec<_>, AppError>>()?;
+        Ok(StockWarehouseModelSet { warehouses })
+    }
+
+    async fn save_warehouses(&self, ms: Vec<StockWarehouseModel>) -> DefaultResult<(), AppError> {
+        if ms.is_empty() {
+            return Err(AppError {
+                code: AppErrorCode::EmptyInputData,
+                detail: Some("save StockWarehouseModel".to_string()),
+            });
+        }
+        let (sql_patt, args) = InsertWarehouseArg(ms).into();
+        let mut conn = self._db.acquire().await?;
+        let mut tx = conn.begin().await?;
+        // number of affected rows is not checked, an updated row is counted twice
+        let _rs = run_query_once(&mut tx, sql_patt, args, None).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
     async fn try_reserve(
         &self,
         cb: AppStockRepoReserveUserFunc,
-        order_req: &OrderLineModelSet,
+        order_req: &mut OrderLineModelSet,
     ) -> AppStockRepoReserveReturn {
         // TODO, figure out how to send `sqlx` transaction object between tasks (hard)
         match self._try_reserve(cb, order_req).await {
@@ -598,6 +975,23 @@ impl AbsOrderStockRepo for StockMariaDbRepo {
         }
     } // end of fn try_reserve
 
+    async fn try_fulfil_backorder(
+        &self,
+        cb: AppStockRepoReserveUserFunc,
+        order_req: &mut OrderLineModelSet,
+    ) -> AppStockRepoReserveReturn {
+        match self._try_fulfil_backorder(cb, order_req).await {
+            Ok(c) => {
+                if c.is_empty() {
+                    Ok(())
+                } else {
+                    Err(Ok(c))
+                }
+            }
+            Err(e) => Err(Err(e)),
+        }
+    }
+
     async fn try_return(
         &self,
         cb: AppStockRepoReturnUserFunc,
@@ -630,6 +1024,59 @@ impl AbsOrderStockRepo for StockMariaDbRepo {
         }
         Ok(errors)
     } // end of fn try_return
+
+    async fn try_cancel_return(
+        &self,
+        oid: &str,
+        pids: Vec<OrderLineIdentity>,
+        cancel: OrderLineCancelModel,
+        cancel_cb: AppOrderRepoCancelLinesUserFunc,
+        return_cb: AppStockRepoReturnUserFunc,
+    ) -> DefaultResult<Vec<OrderLineCancelErrorDto>, AppError> {
+        let oid_b = OidBytes::try_from(oid)?;
+        let mut objconn = self._db.acquire().await?;
+        let conn = objconn.as_mut();
+        let mut tx = conn.begin().await?;
+        let (lines, errors) =
+            OrderMariaDbRepo::cancel_lines_tx(&mut tx, &oid_b, pids, cancel, cancel_cb).await?;
+        if !errors.is_empty() {
+            return Ok(errors); // the transaction is rolled back on drop
+        }
+        let data = OrderLineModel::cancelled_stock_return(oid, &lines);
+        if !data.items.is_empty() {
+            let mut mset = {
+                let (sql_patt, args) = FetchRsvOrderArg(oid_b, &data.items).into();
+                let stmt = tx.prepare(sql_patt.as_str()).await?;
+                let query = stmt.query_with(args);
+                let exec = &mut *tx;
+                let rows = exec.fetch_all(query).await?;
+                StkRsvDetailRows(rows).try_into()?
+            };
+            let return_errors = return_cb(&mut mset, data);
+            if !return_errors.is_empty() {
+                return Err(stock_return_corrupted(return_errors));
+            }
+            let stk = mset
+                .stores
+                .into_iter()
+                .flat_map(|s| {
+                    let store_id = s.store_id;
+                    s.products.into_iter().map(move |p| (store_id, p))
+                })
+                .collect();
+            Self::_save_base_qty("return", 20, &mut tx, stk).await?;
+        }
+        tx.commit().await?;
+        Ok(errors)
+    } // end of fn try_cancel_return
+
+    async fn release_promotion_usage(&self, codes: Vec<String>) -> DefaultResult<(), AppError> {
+        let mut conn = self._db.acquire().await?;
+        let mut tx = conn.begin().await?;
+        PromotionMariaDbRepo::release_usage(&mut tx, codes).await?;
+        tx.commit().await?;
+        Ok(())
+    }
 } // end of impl AbsOrderStockRepo for StockMariaDbRepo
 
 impl StockMariaDbRepo {
@@ -666,13 +1113,41 @@ impl StockMariaDbRepo {
         Ok(())
     } // end of fn _save_base_qty
 
+    async fn _fetch_alloc_policy(
+        exec: &mut MySqlConnection,
+        mut store_ids: Vec<u32>,
+    ) -> DefaultResult<StockAllocPolicyModelSet, AppError> {
+        store_ids.sort();
+        store_ids.dedup();
+        if store_ids.is_empty() {
+            return Ok(StockAllocPolicyModelSet { policies: vec![] });
+        }
+        let (sql_patt, args) = FetchAllocPolicyArg(store_ids).into();
+        let stmt = exec.prepare(sql_patt.as_str()).await?;
+        let query = stmt.query_with(args);
+        let rows = query.fetch_all(exec).await?;
+        let policies = rows
+            .into_iter()
+            .map(|r| StkAllocPolicyRow(r).try_into())
+            .collect::<DefaultResult<Vec<_>, AppError>>()?;
+        Ok(StockAllocPolicyModelSet { policies })
+    }
+
     async fn _try_reserve(
         &self,
         usr_cb: AppStockRepoReserveUserFunc,
-        order_req: &OrderLineModelSet,
+        order_req: &mut OrderLineModelSet,
     ) -> DefaultResult<Vec<OrderLineCreateErrorDto>, AppError> {
         let mut conn = self._db.acquire().await?;
         let mut tx = conn.begin().await?;
+        let policies = {
+            let store_ids = order_req
+                .lines()
+                .iter()
+                .map(|d| d.id().store_id())
+                .collect();
+            Self::_fetch_alloc_policy(tx.deref_mut(), store_ids).await?
+        };
         let mut mset = {
             let (sql_patt, args) = FetchQtyForRsvArg(order_req.lines()).into();
             let stmt = tx.prepare(sql_patt.as_str()).await?;
@@ -681,9 +1156,15 @@ impl StockMariaDbRepo {
             let rows = exec.fetch_all(query).await?;
             StkProdRows(rows).try_into()?
         };
-        if let Err(e) = usr_cb(&mut mset, order_req) {
+        if let Err(e) = usr_cb(&mut mset, &policies, order_req) {
             e
         } else {
+            let codes = order_req.redeemed_promotions();
+            let exhausted = PromotionMariaDbRepo::claim_usage(&mut tx, &codes).await?;
+            if !exhausted.is_empty() {
+                // the transaction is rolled back on drop
+                return Ok(order_req.promotion_exhausted_errors(&exhausted));
+            }
             let stk = mset
                 .stores
                 .into_iter()
@@ -704,4 +1185,98 @@ impl StockMariaDbRepo {
             Ok(vec![])
         }
     } // end of fn _try_reserve
+
+    async fn _try_fulfil_backorder(
+        &self,
+        usr_cb: AppStockRepoReserveUserFunc,
+        order_req: &mut OrderLineModelSet,
+    ) -> DefaultResult<Vec<OrderLineCreateErrorDto>, AppError> {
+        let mut conn = self._db.acquire().await?;
+        let mut tx = conn.begin().await?;
+        let policies = {
+            let store_ids = order_req
+                .lines()
+                .iter()
+                .map(|d| d.id().store_id())
+                .collect();
+            Self::_fetch_alloc_policy(tx.deref_mut(), store_ids).await?
+        };
+        let mut mset: StockLevelModelSet = {
+            let (sql_patt, args) = FetchQtyForRsvArg(order_req.lines()).into();
+            let stmt = tx.prepare(sql_patt.as_str()).await?;
+            let query = stmt.query_with(args);
+            let exec = tx.deref_mut();
+            let rows = exec.fetch_all(query).await?;
+            StkProdRows(rows).try_into()?
+        };
+        // the order may already hold some items of the same batches, new items
+        // have to be added to the existing reservation
+        let saved_rsv: StockLevelModelSet = {
+            let oid_b = OidBytes::try_from(order_req.id().as_str())?;
+            let items = order_req
+                .lines()
+                .iter()
+                .map(InventoryEditStockLevelDto::from)
+                .collect::<Vec<_>>();
+            let (sql_patt, args) = FetchRsvOrderArg(oid_b, &items).into();
+            let stmt = tx.prepare(sql_patt.as_str()).await?;
+            let query = stmt.query_with(args);
+            let exec = tx.deref_mut();
+            let rows = exec.fetch_all(query).await?;
+            StkRsvDetailRows(rows).try_into()?
+        };
+        let mut orig_rsv = HashMap::new();
+        saved_rsv
+            .stores
+            .into_iter()
+            .flat_map(|s| {
+                let store_id = s.store_id;
+                s.products.into_iter().map(move |p| (store_id, p))
+            })
+            .map(|(store_id, saved)| {
+                let found = mset
+                    .stores
+                    .iter_mut()
+                    .find(|s| s.store_id == store_id)
+                    .and_then(|s| {
+                        s.products.iter_mut().find(|p| {
+                            p.id_ == saved.id_
+                                && p.warehouse_id == saved.warehouse_id
+                                && p.expiry == saved.expiry
+                        })
+                    });
+                if let Some(p) = found {
+                    let detail = saved.quantity.rsv_detail.unwrap();
+                    let key = (store_id, p.id_, p.warehouse_id, p.expiry);
+                    orig_rsv.insert(key, detail.reserved);
+                    p.quantity.rsv_detail = Some(detail);
+                }
+            })
+            .count();
+        if let Err(e) = usr_cb(&mut mset, &policies, order_req) {
+            return e;
+        }
+        let (existing, created): (Vec<_>, Vec<_>) = mset
+            .stores
+            .into_iter()
+            .flat_map(|s| {
+                let store_id = s.store_id;
+                s.products.into_iter().map(move |p| (store_id, p))
+            })
+            .filter(|(store_id, p)| {
+                let key = (*store_id, p.id_, p.warehouse_id, p.expiry);
+                let num_rsv = p.quantity.rsv_detail.as_ref().map(|d| d.reserved);
+                num_rsv.is_some() && num_rsv != orig_rsv.get(&key).copied()
+            })
+            .partition(|(store_id, p)| {
+                orig_rsv.contains_key(&(*store_id, p.id_, p.warehouse_id, p.expiry))
+            });
+        // statements for returning items also update existing reservations of
+        // the order to the latest quantities
+        Self::_save_base_qty("return", 20, &mut tx, existing).await?;
+        Self::_save_base_qty("reserve", 20, &mut tx, created).await?;
+        OrderMariaDbRepo::update_lines_backorder(&mut tx, order_req, 20).await?;
+        tx.commit().await?;
+        Ok(vec![])
+    } // end of fn _try_fulfil_backorder
 } // end of impl StockMariaDbRepo
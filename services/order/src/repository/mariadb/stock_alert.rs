@@ -0,0 +1,265 @@
+use std::result::Result as DefaultResult;
+use std::sync::Arc;
+use std::vec::Vec;
+
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use sqlx::mysql::{MySqlArguments, MySqlRow};
+use sqlx::{Acquire, Arguments, Executor, Row, Statement};
+
+use ecommerce_common::error::AppErrorCode;
+
+use crate::datastore::AppMariaDbStore;
+use crate::error::AppError;
+use crate::model::{StockAlertKind, StockAlertModel, StockAlertThresholdModel};
+use crate::repository::AbsStockAlertRepo;
+
+use super::run_query_once;
+
+struct InsertThresholdArg(Vec<StockAlertThresholdModel>);
+struct DiscardSentArg<'a>(u32, &'a [StockAlertModel]);
+struct InsertSentArg<'a>(u32, &'a StockAlertModel);
+struct FetchSentArg(u32);
+
+// the alert which is not for specific batch is saved with this expiry instead
+// of null, so the unique key of sent alerts can reject duplicate claims
+const SENT_EXPIRY_ABSENT: &str = "1970-01-01 00:00:01";
+
+fn sent_expiry_absent() -> NaiveDateTime {
+    NaiveDateTime::parse_from_str(SENT_EXPIRY_ABSENT, "%Y-%m-%d %H:%M:%S").unwrap()
+}
+
+fn sent_expiry_to_db(value: Option<DateTime<Utc>>) -> NaiveDateTime {
+    value
+        .map(|t| t.naive_utc())
+        .unwrap_or_else(sent_expiry_absent)
+}
+
+impl From<StockAlertKind> for &'static str {
+    fn from(value: StockAlertKind) -> &'static str {
+        match value {
+            StockAlertKind::LowStock => "low-stock",
+            StockAlertKind::ExpiringBatch => "expiring-batch",
+        }
+    }
+}
+
+impl TryFrom<&str> for StockAlertKind {
+    type Error = AppError;
+    fn try_from(value: &str) -> DefaultResult<Self, Self::Error> {
+        match value {
+            "low-stock" => Ok(Self::LowStock),
+            "expiring-batch" => Ok(Self::ExpiringBatch),
+            _others => Err(AppError {
+                code: AppErrorCode::DataCorruption,
+                detail: Some(format!("stock-alert-kind:{value}")),
+            }),
+        }
+    }
+}
+
+impl From<InsertThresholdArg> for (String, MySqlArguments) {
+    fn from(value: InsertThresholdArg) -> (String, MySqlArguments) {
+        let items = (0..value.0.len())
+            .map(|_| "(?,?,?)")
+            .collect::<Vec<_>>()
+            .join(",");
+        let sql_patt = format!(
+            "INSERT INTO `stock_alert_threshold`(`store_id`,`low_stock_qty`,\
+             `expiry_within_days`) VALUES {items} ON DUPLICATE KEY UPDATE \
+             `low_stock_qty`=VALUE(`low_stock_qty`),\
+             `expiry_within_days`=VALUE(`expiry_within_days`)"
+        );
+        let mut args = MySqlArguments::default();
+        value
+            .0
+            .into_iter()
+            .map(|m| {
+                args.add(m.store_id).unwrap();
+                args.add(m.low_stock_qty).unwrap();
+                args.add(m.expiry_within_days).unwrap();
+            })
+            .count();
+        (sql_patt, args)
+    }
+}
+
+impl<'a> From<DiscardSentArg<'a>> for (String, MySqlArguments) {
+    fn from(value: DiscardSentArg<'a>) -> (String, MySqlArguments) {
+        let (store_id, alerts) = (value.0, value.1);
+        let items = (0..alerts.len())
+            .map(|_| "(`kind`=? AND `product_id`=? AND `expiry`=?)")
+            .collect::<Vec<_>>()
+            .join(" OR ");
+        let sql_patt = format!("DELETE FROM `stock_alert_sent` WHERE `store_id`=? AND ({items})");
+        let mut args = MySqlArguments::default();
+        args.add(store_id).unwrap();
+        alerts
+            .iter()
+            .map(|m| {
+                let kind: &str = m.kind.into();
+                args.add(kind).unwrap();
+                args.add(m.product_id).unwrap();
+                args.add(sent_expiry_to_db(m.expiry)).unwrap();
+            })
+            .count();
+        (sql_patt, args)
+    }
+}
+
+impl From<FetchSentArg> for (String, MySqlArguments) {
+    fn from(value: FetchSentArg) -> (String, MySqlArguments) {
+        let sql_patt = "SELECT `kind`,`product_id`,`expiry`,`num_avail` FROM \
+                        `stock_alert_sent` WHERE `store_id`=?";
+        let mut args = MySqlArguments::default();
+        args.add(value.0).unwrap();
+        (sql_patt.to_string(), args)
+    }
+}
+
+impl<'a> From<InsertSentArg<'a>> for (String, MySqlArguments) {
+    fn from(value: InsertSentArg<'a>) -> (String, MySqlArguments) {
+        let (store_id, m) = (value.0, value.1);
+        // the alert already recorded by others is skipped
+        let sql_patt = "INSERT IGNORE INTO `stock_alert_sent`(`store_id`,`kind`,`product_id`,\
+                        `expiry`,`num_avail`) VALUES (?,?,?,?,?)";
+        let kind: &str = m.kind.into();
+        let mut args = MySqlArguments::default();
+        args.add(store_id).unwrap();
+        args.add(kind).unwrap();
+        args.add(m.product_id).unwrap();
+        args.add(sent_expiry_to_db(m.expiry)).unwrap();
+        args.add(m.num_avail).unwrap();
+        (sql_patt.to_string(), args)
+    }
+}
+
+impl TryFrom<MySqlRow> for StockAlertThresholdModel {
+    type Error = AppError;
+    fn try_from(value: MySqlRow) -> DefaultResult<Self, Self::Error> {
+        Ok(Self {
+            store_id: value.try_get::<u32, usize>(0)?,
+            low_stock_qty: value.try_get::<u32, usize>(1)?,
+            expiry_within_days: value.try_get::<u16, usize>(2)?,
+        })
+    }
+}
+
+impl TryFrom<MySqlRow> for StockAlertModel {
+    type Error = AppError;
+    fn try_from(value: MySqlRow) -> DefaultResult<Self, Self::Error> {
+        let kind = StockAlertKind::try_from(value.try_get::<&str, usize>(0)?)?;
+        let product_id = value.try_get::<u64, usize>(1)?;
+        let expiry = Some(value.try_get::<NaiveDateTime, usize>(2)?)
+            .filter(|t| *t != sent_expiry_absent())
+            .map(|t| t.and_utc());
+        let num_avail = value.try_get::<u32, usize>(3)?;
+        Ok(Self {
+            kind,
+            product_id,
+            expiry,
+            num_avail,
+        })
+    }
+}
+
+pub(crate) struct StockAlertMariaDbRepo {
+    db: Arc<AppMariaDbStore>,
+}
+
+impl StockAlertMariaDbRepo {
+    pub fn try_build(dbs: &[Arc<AppMariaDbStore>]) -> DefaultResult<Self, AppError> {
+        let db = dbs.first().cloned().ok_or(AppError {
+            code: AppErrorCode::MissingDataStore,
+            detail: Some("mariadb".to_string()),
+        })?;
+        Ok(Self { db })
+    }
+
+    async fn fetch_common<T>(
+        &self,
+        sql_patt: &str,
+        args: MySqlArguments,
+    ) -> DefaultResult<Vec<T>, AppError>
+    where
+        T: TryFrom<MySqlRow, Error = AppError>,
+    {
+        let mut conn = self.db.acquire().await?;
+        let stmt = conn.prepare(sql_patt).await?;
+        let query = stmt.query_with(args);
+        let exec = conn.as_mut();
+        let rows = query.fetch_all(exec).await?;
+        rows.into_iter().map(T::try_from).collect()
+    }
+}
+
+#[async_trait]
+impl AbsStockAlertRepo for StockAlertMariaDbRepo {
+    async fn save_thresholds(
+        &self,
+        ms: Vec<StockAlertThresholdModel>,
+    ) -> DefaultResult<(), AppError> {
+        if ms.is_empty() {
+            return Err(AppError {
+                code: AppErrorCode::EmptyInputData,
+                detail: Some("save StockAlertThresholdModel".to_string()),
+            });
+        }
+        let (sql_patt, args) = InsertThresholdArg(ms).into();
+        let mut conn = self.db.acquire().await?;
+        let mut tx = conn.begin().await?;
+        let _rs = run_query_once(&mut tx, sql_patt, args, None).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn fetch_all_thresholds(&self) -> DefaultResult<Vec<StockAlertThresholdModel>, AppError> {
+        let sql_patt = "SELECT `store_id`,`low_stock_qty`,`expiry_within_days` FROM \
+                        `stock_alert_threshold` WHERE `low_stock_qty` > 0 OR \
+                        `expiry_within_days` > 0";
+        self.fetch_common(sql_patt, MySqlArguments::default()).await
+    }
+
+    async fn fetch_sent(&self, store_id: u32) -> DefaultResult<Vec<StockAlertModel>, AppError> {
+        let (sql_patt, args) = FetchSentArg(store_id).into();
+        self.fetch_common(sql_patt.as_str(), args).await
+    }
+
+    async fn claim_sent(
+        &self,
+        store_id: u32,
+        ms: Vec<StockAlertModel>,
+    ) -> DefaultResult<Vec<StockAlertModel>, AppError> {
+        if ms.is_empty() {
+            return Ok(ms);
+        }
+        let mut conn = self.db.acquire().await?;
+        let mut tx = conn.begin().await?;
+        let mut claimed = Vec::new();
+        for m in ms {
+            let (sql_patt, args) = InsertSentArg(store_id, &m).into();
+            let rs = run_query_once(&mut tx, sql_patt, args, None).await?;
+            if rs.rows_affected() == 1 {
+                claimed.push(m);
+            }
+        }
+        tx.commit().await?;
+        Ok(claimed)
+    }
+
+    async fn discard_sent(
+        &self,
+        store_id: u32,
+        ms: Vec<StockAlertModel>,
+    ) -> DefaultResult<(), AppError> {
+        if ms.is_empty() {
+            return Ok(());
+        }
+        let mut conn = self.db.acquire().await?;
+        let mut tx = conn.begin().await?;
+        let (sql_patt, args) = DiscardSentArg(store_id, &ms).into();
+        let _rs = run_query_once(&mut tx, sql_patt, args, None).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+} // end of impl StockAlertMariaDbRepo
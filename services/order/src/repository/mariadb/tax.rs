@@ -0,0 +1,142 @@
+use std::result::Result as DefaultResult;
+use std::sync::Arc;
+use std::vec::Vec;
+
+use async_trait::async_trait;
+use rust_decimal::Decimal;
+use sqlx::mysql::{MySqlArguments, MySqlRow};
+use sqlx::{Acquire, Arguments, Executor, Row, Statement};
+
+use ecommerce_common::api::dto::CountryCode;
+use ecommerce_common::error::AppErrorCode;
+
+use crate::datastore::AppMariaDbStore;
+use crate::error::AppError;
+use crate::model::{TaxRateModel, TaxRateModelSet};
+use crate::repository::AbsTaxRateRepo;
+
+use super::run_query_once;
+
+struct InsertTaxRateArg(Vec<TaxRateModel>);
+struct FetchTaxRateArg(CountryCode, String, Vec<u32>);
+
+impl From<InsertTaxRateArg> for (String, MySqlArguments) {
+    fn from(value: InsertTaxRateArg) -> (String, MySqlArguments) {
+        let items = (0..value.0.len())
+            .map(|_| "(?,?,?,?)")
+            .collect::<Vec<_>>()
+            .join(",");
+        let sql_patt = format!(
+            "INSERT INTO `tax_rate`(`store_id`,`country`,`region`,`rate`) VALUES {items} \
+             ON DUPLICATE KEY UPDATE `rate`=VALUE(`rate`)"
+        );
+        let mut args = MySqlArguments::default();
+        value
+            .0
+            .into_iter()
+            .map(|m| {
+                let country: String = m.country.into();
+                args.add(m.store_id.unwrap_or(0)).unwrap();
+                args.add(country).unwrap();
+                args.add(m.region.unwrap_or_default()).unwrap();
+                args.add(m.rate).unwrap();
+            })
+            .count();
+        (sql_patt, args)
+    }
+}
+
+impl From<FetchTaxRateArg> for (String, MySqlArguments) {
+    fn from(value: FetchTaxRateArg) -> (String, MySqlArguments) {
+        let (country, region, mut store_ids) = (value.0, value.1, value.2);
+        store_ids.push(0);
+        store_ids.sort();
+        store_ids.dedup();
+        let items = (0..store_ids.len())
+            .map(|_| "?")
+            .collect::<Vec<_>>()
+            .join(",");
+        let sql_patt = format!(
+            "SELECT `store_id`,`country`,`region`,`rate` FROM `tax_rate` WHERE \
+             `country`=? AND `region` IN (?,'') AND `store_id` IN ({items})"
+        );
+        let country: String = country.into();
+        let mut args = MySqlArguments::default();
+        args.add(country).unwrap();
+        args.add(region).unwrap();
+        store_ids
+            .into_iter()
+            .map(|id| args.add(id).unwrap())
+            .count();
+        (sql_patt, args)
+    }
+}
+
+impl TryFrom<MySqlRow> for TaxRateModel {
+    type Error = AppError;
+    fn try_from(value: MySqlRow) -> DefaultResult<Self, Self::Error> {
+        let store_id = value.try_get::<u32, usize>(0)?;
+        let country = value.try_get::<&str, usize>(1)?;
+        let region = value.try_get::<String, usize>(2)?;
+        let rate = value.try_get::<Decimal, usize>(3)?;
+        Ok(Self {
+            country: CountryCode::from(country.to_string()),
+            region: Some(region).filter(|r| !r.is_empty()),
+            store_id: Some(store_id).filter(|v| *v != 0),
+            rate,
+        })
+    }
+}
+
+pub(crate) struct TaxRateMariaDbRepo {
+    db: Arc<AppMariaDbStore>,
+}
+
+impl TaxRateMariaDbRepo {
+    pub fn try_build(dbs: &[Arc<AppMariaDbStore>]) -> DefaultResult<Self, AppError> {
+        let db = dbs.first().cloned().ok_or(AppError {
+            code: AppErrorCode::MissingDataStore,
+            detail: Some("mariadb".to_string()),
+        })?;
+        Ok(Self { db })
+    }
+}
+
+#[async_trait]
+impl AbsTaxRateRepo for TaxRateMariaDbRepo {
+    async fn fetch(
+        &self,
+        country: CountryCode,
+        region: String,
+        store_ids: Vec<u32>,
+    ) -> DefaultResult<TaxRateModelSet, AppError> {
+        let (sql_patt, args) = FetchTaxRateArg(country, region, store_ids).into();
+        let mut conn = self.db.acquire().await?;
+        let stmt = conn.prepare(sql_patt.as_str()).await?;
+        let query = stmt.query_with(args);
+        let exec = conn.as_mut();
+        let rows = query.fetch_all(exec).await?;
+        let rates = rows
+            .into_iter()
+            .map(TaxRateModel::try_from)
+            .collect::<DefaultResult<Vec<_>, AppError>>()?;
+        Ok(TaxRateModelSet { rates })
+    }
+
+    async fn save(&self, ms: TaxRateModelSet) -> DefaultResult<(), AppError> {
+        if ms.rates.is_empty() {
+            return Err(AppError {
+                code: AppErrorCode::EmptyInputData,
+                detail: Some("save TaxRateModel".to_string()),
+            });
+        }
+        let (sql_patt, args) = InsertTaxRateArg(ms.rates).into();
+        let mut conn = self.db.acquire().await?;
+        let mut tx = conn.begin().await?;
+        // number of affected rows varies, depending on whether a rate
+        // is inserted or updated
+        let _rs = run_query_once(&mut tx, sql_patt, args, None).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+} // end of impl TaxRateMariaDbRepo
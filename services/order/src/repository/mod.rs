@@ -7,7 +7,7 @@ use std::result::Result as DefaultResult;
 use std::sync::Arc;
 use std::vec::Vec;
 
-use ecommerce_common::api::dto::CurrencyDto;
+use ecommerce_common::api::dto::{CountryCode, CurrencyDto};
 use ecommerce_common::api::rpc::dto::{
     OrderLinePayUpdateErrorDto, OrderPaymentUpdateDto, OrderPaymentUpdateErrorDto,
 };
@@ -16,12 +16,15 @@ use ecommerce_common::model::order::BillingModel;
 use ecommerce_common::model::BaseProductIdentity;
 
 use crate::api::rpc::dto::{ProductPriceDeleteDto, StockLevelReturnDto, StockReturnErrorDto};
-use crate::api::web::dto::OrderLineCreateErrorDto;
+use crate::api::web::dto::{OrderLineCancelErrorDto, OrderLineCreateErrorDto};
 use crate::error::AppError;
 use crate::model::{
-    CartModel, CurrencyModelSet, OrderCurrencyModel, OrderLineIdentity, OrderLineModel,
-    OrderLineModelSet, OrderReturnModel, ProductPolicyModelSet, ProductPriceModelSet,
-    ProductStockIdentity, ShippingModel, StockLevelModelSet,
+    CartModel, CurrencyModelSet, CurrencyRateHistoryModel, OrderCurrencyModel,
+    OrderLineCancelModel, OrderLineIdentity, OrderLineModel, OrderLineModelSet, OrderReturnModel,
+    ProductPolicyModelSet, ProductPriceModelSet, ProductStockIdentity, ProductStockIdentity2,
+    PromotionModelSet, ShipmentModel, ShippingMethodModelSet, ShippingModel, StockAlertModel,
+    StockAlertThresholdModel, StockAllocPolicyModel, StockAllocPolicyModelSet, StockLevelModelSet,
+    StockWarehouseModel, StockWarehouseModelSet, TaxRateModelSet,
 };
 use crate::AppDataStoreContext;
 
@@ -33,6 +36,11 @@ pub use in_mem::oline_return::OrderReturnInMemRepo;
 pub use in_mem::order::OrderInMemRepo;
 pub use in_mem::product_policy::ProductPolicyInMemRepo;
 pub use in_mem::product_price::ProductPriceInMemRepo;
+pub use in_mem::promotion::PromotionInMemRepo;
+pub use in_mem::shipment::ShipmentInMemRepo;
+pub use in_mem::shipping_method::ShippingMethodInMemRepo;
+pub use in_mem::stock_alert::StockAlertInMemRepo;
+pub use in_mem::tax::TaxRateInMemRepo;
 
 #[cfg(feature = "mariadb")]
 mod mariadb;
@@ -52,17 +60,45 @@ use mariadb::order::OrderMariaDbRepo;
 #[cfg(feature = "mariadb")]
 use mariadb::oline_return::OrderReturnMariaDbRepo;
 
+#[cfg(feature = "mariadb")]
+use mariadb::shipment::ShipmentMariaDbRepo;
+
+#[cfg(feature = "mariadb")]
+use mariadb::shipping_method::ShippingMethodMariaDbRepo;
+
 #[cfg(feature = "mariadb")]
 use mariadb::cart::CartMariaDbRepo;
 
+#[cfg(feature = "mariadb")]
+use mariadb::promotion::PromotionMariaDbRepo;
+
+#[cfg(feature = "mariadb")]
+use mariadb::tax::TaxRateMariaDbRepo;
+
+#[cfg(feature = "mariadb")]
+use mariadb::stock_alert::StockAlertMariaDbRepo;
+
 // the repository instance may be used across an await,
 // the future created by app callers has to be able to pass to different threads
 // , it is the reason to add `Send` and `Sync` as super-traits
 #[async_trait]
 pub trait AbstProductPolicyRepo: Sync + Send {
-    async fn fetch(&self, ids: Vec<u64>) -> DefaultResult<ProductPolicyModelSet, AppError>;
+    // only the latest version of each policy is returned, deleted policies are excluded
+    async fn fetch(
+        &self,
+        ids: Vec<BaseProductIdentity>,
+    ) -> DefaultResult<ProductPolicyModelSet, AppError>;
+    // fetch exact versions of the policies, e.g. the versions applied to existing
+    // order lines, the versions are returned even when the policy was deleted
+    async fn fetch_by_version(
+        &self,
+        ids: Vec<(BaseProductIdentity, u32)>,
+    ) -> DefaultResult<ProductPolicyModelSet, AppError>;
+    // append the policies marked as `is_create` as next version, previous versions
+    // are never modified
     async fn save(&self, ppset: ProductPolicyModelSet) -> DefaultResult<(), AppError>;
-    // TODO, delete operation
+    // soft delete, the version history is kept for existing order lines
+    async fn delete(&self, ids: Vec<BaseProductIdentity>) -> DefaultResult<(), AppError>;
 }
 
 #[async_trait]
@@ -89,15 +125,33 @@ pub trait AbsProductPriceRepo: Sync + Send {
 }
 
 /// Note:
-/// in this project the base currency is always USD due to the constraint of 3rd party exchange
-/// rate service I apply, it is free plan and not allow to change base currency, this should not
-/// be huge problem since the application can convert the rate between different specific
-/// currencies.
+/// in this project the saved rates always use USD as the base currency, the exchange rate
+/// providers are free to apply any other base currency, the refreshed rates have to be rebased
+/// to USD before saving, this should not be huge problem since the application can convert the
+/// rate between different specific currencies.
+///
+/// Each time the rates are saved, they are also appended to the rate history with the saving
+/// time, which can be used for looking up the rate in force at any past moment.
 #[async_trait]
 pub trait AbsCurrencyRepo: Sync + Send {
     async fn fetch(&self, chosen: Vec<CurrencyDto>) -> DefaultResult<CurrencyModelSet, AppError>;
 
     async fn save(&self, ms: CurrencyModelSet) -> DefaultResult<(), AppError>;
+
+    /// the latest rates of chosen currencies saved no later than the given time
+    async fn fetch_as_of(
+        &self,
+        chosen: Vec<CurrencyDto>,
+        time: DateTime<FixedOffset>,
+    ) -> DefaultResult<CurrencyModelSet, AppError>;
+
+    /// rates of chosen currencies saved after the start time, no later than the end time
+    async fn fetch_history(
+        &self,
+        chosen: Vec<CurrencyDto>,
+        start: DateTime<FixedOffset>,
+        end: DateTime<FixedOffset>,
+    ) -> DefaultResult<Vec<CurrencyRateHistoryModel>, AppError>;
 }
 
 #[async_trait]
@@ -123,6 +177,16 @@ pub trait AbsOrderRepo: Sync + Send {
         cb: AppOrderRepoUpdateLinesUserFunc,
     ) -> DefaultResult<OrderPaymentUpdateErrorDto, AppError>;
 
+    // the callback decides which order lines can be cancelled, the repository
+    // saves the cancelled lines only if the callback does not report any error
+    async fn cancel_lines(
+        &self,
+        oid: &str,
+        pids: Vec<OrderLineIdentity>,
+        cancel: OrderLineCancelModel,
+        usr_cb: AppOrderRepoCancelLinesUserFunc,
+    ) -> DefaultResult<Vec<OrderLineCancelErrorDto>, AppError>;
+
     async fn fetch_lines_by_rsvtime(
         &self,
         time_start: DateTime<FixedOffset>,
@@ -142,6 +206,27 @@ pub trait AbsOrderRepo: Sync + Send {
         end: DateTime<FixedOffset>,
     ) -> DefaultResult<Vec<String>, AppError>;
 
+    // fetch orders created by the given user within the time range, the most
+    // recent order comes first, the result includes total number of the orders
+    // in the range, and IDs with creation time of the orders in specified page
+    async fn fetch_ids_by_owner(
+        &self,
+        usr_id: u32,
+        start: DateTime<FixedOffset>,
+        end: DateTime<FixedOffset>,
+        offset: usize,
+        limit: usize,
+    ) -> DefaultResult<(usize, Vec<(String, DateTime<FixedOffset>)>), AppError>;
+
+    // fetch uncancelled order lines which still have backordered items of given
+    // products in a store, each returned set contains only such lines, the sets
+    // are sorted by the time the orders were created, earliest first
+    async fn fetch_backordered(
+        &self,
+        store_id: u32,
+        product_ids: Vec<u64>,
+    ) -> DefaultResult<Vec<OrderLineModelSet>, AppError>;
+
     async fn owner_id(&self, order_id: &str) -> DefaultResult<u32, AppError>;
     async fn created_time(&self, order_id: &str) -> DefaultResult<DateTime<FixedOffset>, AppError>;
 
@@ -154,6 +239,12 @@ pub trait AbsOrderRepo: Sync + Send {
 pub type AppOrderRepoUpdateLinesUserFunc =
     fn(&mut Vec<OrderLineModel>, OrderPaymentUpdateDto) -> Vec<OrderLinePayUpdateErrorDto>;
 
+pub type AppOrderRepoCancelLinesUserFunc = fn(
+    &mut [OrderLineModel],
+    Vec<OrderLineIdentity>,
+    OrderLineCancelModel,
+) -> Vec<OrderLineCancelErrorDto>;
+
 // declare a callback function type which can easily be passed,
 // - I made the return type to be `Future` trait object wrapped in `Pin` type
 //   because `Future` (generated by async block expression) does not implement `Unpin` trait,
@@ -172,31 +263,101 @@ pub type AppOrderFetchRangeCallback =
 pub type AppStockRepoReserveReturn =
     DefaultResult<(), DefaultResult<Vec<OrderLineCreateErrorDto>, AppError>>;
 
-pub type AppStockRepoReserveUserFunc =
-    fn(&mut StockLevelModelSet, &OrderLineModelSet) -> AppStockRepoReserveReturn;
+// allocation policies of the stores in the order are loaded by the repository
+// within the same operation as stock levels, the callback may update number of
+// backordered items in the order lines
+pub type AppStockRepoReserveUserFunc = fn(
+    &mut StockLevelModelSet,
+    &StockAllocPolicyModelSet,
+    &mut OrderLineModelSet,
+) -> AppStockRepoReserveReturn;
 
 // if the function pointer type is declared directly in function signature of a
 // trait method, the function pointer will be viewed as closure block
 pub type AppStockRepoReturnUserFunc =
     fn(&mut StockLevelModelSet, StockLevelReturnDto) -> Vec<StockReturnErrorDto>;
 
+fn stock_return_corrupted(errors: Vec<StockReturnErrorDto>) -> AppError {
+    AppError {
+        code: AppErrorCode::DataCorruption,
+        detail: Some(format!("stock-return: {:?}", errors)),
+    }
+}
+
 #[async_trait]
 pub trait AbsOrderStockRepo: Sync + Send {
     async fn fetch(
         &self,
         pids: Vec<ProductStockIdentity>,
     ) -> DefaultResult<StockLevelModelSet, AppError>;
+    // fetch all unexpired stock records of given products, caller does not
+    // need to know the expiry time, records are not locked for reservation
+    async fn fetch_by_product(
+        &self,
+        pids: Vec<ProductStockIdentity2>,
+    ) -> DefaultResult<StockLevelModelSet, AppError>;
+    // fetch stock records of a store for inspection by the seller, optionally
+    // narrowed down to specific products and range of expiry time, expired
+    // records are also included
+    async fn fetch_by_store(
+        &self,
+        store_id: u32,
+        product_ids: Vec<u64>,
+        expiry_after: Option<DateTime<FixedOffset>>,
+        expiry_before: Option<DateTime<FixedOffset>>,
+    ) -> DefaultResult<StockLevelModelSet, AppError>;
     async fn save(&self, slset: StockLevelModelSet) -> DefaultResult<(), AppError>;
+    // fetch allocation policies of given stores, including default policy of
+    // each store and product-specific ones
+    async fn fetch_alloc_policy(
+        &self,
+        store_ids: Vec<u32>,
+    ) -> DefaultResult<StockAllocPolicyModelSet, AppError>;
+    // existing policy with the same store and product is overwritten
+    async fn save_alloc_policy(
+        &self,
+        ms: Vec<StockAllocPolicyModel>,
+    ) -> DefaultResult<(), AppError>;
+    async fn fetch_warehouses(
+        &self,
+        store_ids: Vec<u32>,
+    ) -> DefaultResult<StockWarehouseModelSet, AppError>;
+    // existing warehouse with the same store and warehouse ID is overwritten
+    async fn save_warehouses(&self, ms: Vec<StockWarehouseModel>) -> DefaultResult<(), AppError>;
+    // usage count of the promotions redeemed by the order lines is increased in
+    // the same operation, the reservation fails with client errors if any of the
+    // promotions reached its usage limit
     async fn try_reserve(
         &self,
         cb: AppStockRepoReserveUserFunc,
-        order_req: &OrderLineModelSet,
+        order_req: &mut OrderLineModelSet,
+    ) -> AppStockRepoReserveReturn;
+    // allocate stock to backordered lines of an existing order, the stock levels
+    // and the order lines are saved together if the callback succeeds
+    async fn try_fulfil_backorder(
+        &self,
+        cb: AppStockRepoReserveUserFunc,
+        order_req: &mut OrderLineModelSet,
     ) -> AppStockRepoReserveReturn;
     async fn try_return(
         &self,
         cb: AppStockRepoReturnUserFunc,
         data: StockLevelReturnDto,
     ) -> DefaultResult<Vec<StockReturnErrorDto>, AppError>;
+    // cancel the order lines and return their reserved items to stock in the
+    // same operation, nothing is saved if the cancel callback reports any error,
+    // errors from the return callback are reported as data corruption
+    async fn try_cancel_return(
+        &self,
+        oid: &str,
+        pids: Vec<OrderLineIdentity>,
+        cancel: OrderLineCancelModel,
+        cancel_cb: AppOrderRepoCancelLinesUserFunc,
+        return_cb: AppStockRepoReturnUserFunc,
+    ) -> DefaultResult<Vec<OrderLineCancelErrorDto>, AppError>;
+    // decrease usage count of each given promotion by one, for the orders which
+    // no longer redeem them
+    async fn release_promotion_usage(&self, codes: Vec<String>) -> DefaultResult<(), AppError>;
 }
 
 #[async_trait]
@@ -229,6 +390,31 @@ pub trait AbsOrderReturnRepo: Sync + Send {
     ) -> DefaultResult<usize, AppError>;
 }
 
+#[async_trait]
+pub trait AbsShipmentRepo: Sync + Send {
+    // all shipments of an order, sorted by sequence number
+    async fn fetch_by_oid(&self, oid: &str) -> DefaultResult<Vec<ShipmentModel>, AppError>;
+
+    // the shipment is saved only if its sequence number is still the greatest in
+    // the order, return `false` if another shipment was created with the same or
+    // greater sequence number since the caller loaded the shipments
+    async fn create(&self, oid: &str, obj: ShipmentModel) -> DefaultResult<bool, AppError>;
+
+    // only status, tracking number and the time of each status are updated,
+    // lines of a shipment never change once created
+    async fn update(&self, oid: &str, obj: ShipmentModel) -> DefaultResult<(), AppError>;
+}
+
+#[async_trait]
+pub trait AbsShippingMethodRepo: Sync + Send {
+    // all the delivery services registered by the given stores
+    async fn fetch(&self, store_ids: Vec<u32>) -> DefaultResult<ShippingMethodModelSet, AppError>;
+
+    // rate table of each method is replaced entirely, the method with empty
+    // rate table is no longer supported by the store
+    async fn save(&self, ms: ShippingMethodModelSet) -> DefaultResult<(), AppError>;
+}
+
 #[async_trait]
 pub trait AbsCartRepo: Sync + Send {
     async fn update(&self, obj: CartModel) -> DefaultResult<usize, AppError>;
@@ -245,6 +431,84 @@ pub trait AbsCartRepo: Sync + Send {
         seq: u8,
         pids: Vec<BaseProductIdentity>,
     ) -> DefaultResult<CartModel, AppError>;
+
+    // save only the sharable flag and collaborator list, lines are not touched
+    async fn update_collaborators(&self, obj: &CartModel) -> DefaultResult<(), AppError>;
+
+    // remove only the specified lines, the rest of the cart is kept
+    async fn discard_lines(
+        &self,
+        owner: u32,
+        seq: u8,
+        pids: Vec<BaseProductIdentity>,
+    ) -> DefaultResult<usize, AppError>;
+
+    // claim the cart exclusively for checkout until the given expiry, return false
+    // if the cart has been claimed by another checkout request which is not expired
+    async fn try_claim_checkout(
+        &self,
+        owner: u32,
+        seq: u8,
+        time_now: DateTime<FixedOffset>,
+        expiry: DateTime<FixedOffset>,
+    ) -> DefaultResult<bool, AppError>;
+
+    async fn release_checkout(&self, owner: u32, seq: u8) -> DefaultResult<(), AppError>;
+}
+
+#[async_trait]
+pub trait AbsPromotionRepo: Sync + Send {
+    // codes which do not exist are silently skipped
+    async fn fetch(&self, codes: Vec<String>) -> DefaultResult<PromotionModelSet, AppError>;
+
+    // existing promotions with the same codes are overwritten, except the usage count
+    async fn save(&self, ms: PromotionModelSet) -> DefaultResult<(), AppError>;
+}
+
+#[async_trait]
+pub trait AbsTaxRateRepo: Sync + Send {
+    // load the rates which might apply to the given region and stores, that includes
+    // the rates for whole country and the rates for all stores
+    async fn fetch(
+        &self,
+        country: CountryCode,
+        region: String,
+        store_ids: Vec<u32>,
+    ) -> DefaultResult<TaxRateModelSet, AppError>;
+
+    // existing rates for the same country, region and store are overwritten
+    async fn save(&self, ms: TaxRateModelSet) -> DefaultResult<(), AppError>;
+}
+
+#[async_trait]
+pub trait AbsStockAlertRepo: Sync + Send {
+    // existing thresholds of the same stores are overwritten
+    async fn save_thresholds(
+        &self,
+        ms: Vec<StockAlertThresholdModel>,
+    ) -> DefaultResult<(), AppError>;
+
+    // load thresholds of all the stores which enable the alerts
+    async fn fetch_all_thresholds(&self) -> DefaultResult<Vec<StockAlertThresholdModel>, AppError>;
+
+    // alerts which have been published for the store and still in effect
+    async fn fetch_sent(&self, store_id: u32) -> DefaultResult<Vec<StockAlertModel>, AppError>;
+
+    // record the given alerts as sent, the alerts already recorded by others are
+    // skipped, return the alerts recorded in this call, which the caller should
+    // publish exclusively
+    async fn claim_sent(
+        &self,
+        store_id: u32,
+        ms: Vec<StockAlertModel>,
+    ) -> DefaultResult<Vec<StockAlertModel>, AppError>;
+
+    // remove the given alerts from the sent list of the store
+    async fn discard_sent(
+        &self,
+        store_id: u32,
+        ms: Vec<StockAlertModel>,
+    ) -> DefaultResult<(), AppError>;
 }
 
 pub async fn app_repo_product_policy(
@@ -371,6 +635,54 @@ pub async fn app_repo_order_return(
         })
     }
 }
+pub async fn app_repo_shipment(
+    ds: Arc<AppDataStoreContext>,
+) -> DefaultResult<Box<dyn AbsShipmentRepo>, AppError> {
+    #[cfg(feature = "mariadb")]
+    if let Some(dbs) = ds.sql_dbs.as_ref() {
+        let obj = ShipmentMariaDbRepo::try_build(dbs)?;
+        Ok(Box::new(obj))
+    } else {
+        Err(AppError {
+            code: AppErrorCode::FeatureDisabled,
+            detail: Some("mariadb".to_string()),
+        })
+    }
+    #[cfg(not(feature = "mariadb"))]
+    if let Some(m) = &ds.in_mem {
+        let obj = ShipmentInMemRepo::new(m.clone()).await?;
+        Ok(Box::new(obj))
+    } else {
+        Err(AppError {
+            code: AppErrorCode::MissingDataStore,
+            detail: Some("unknown-type".to_string()),
+        })
+    }
+}
+pub async fn app_repo_shipping_method(
+    ds: Arc<AppDataStoreContext>,
+) -> DefaultResult<Box<dyn AbsShippingMethodRepo>, AppError> {
+    #[cfg(feature = "mariadb")]
+    if let Some(dbs) = ds.sql_dbs.as_ref() {
+        let obj = ShippingMethodMariaDbRepo::try_build(dbs)?;
+        Ok(Box::new(obj))
+    } else {
+        Err(AppError {
+            code: AppErrorCode::FeatureDisabled,
+            detail: Some("mariadb".to_string()),
+        })
+    }
+    #[cfg(not(feature = "mariadb"))]
+    if let Some(m) = &ds.in_mem {
+        let obj = ShippingMethodInMemRepo::new(m.clone()).await?;
+        Ok(Box::new(obj))
+    } else {
+        Err(AppError {
+            code: AppErrorCode::MissingDataStore,
+            detail: Some("unknown-type".to_string()),
+        })
+    }
+}
 pub async fn app_repo_cart(
     ds: Arc<AppDataStoreContext>,
 ) -> DefaultResult<Box<dyn AbsCartRepo>, AppError> {
@@ -395,3 +707,78 @@ pub async fn app_repo_cart(
         })
     }
 }
+
+pub async fn app_repo_promotion(
+    ds: Arc<AppDataStoreContext>,
+) -> DefaultResult<Box<dyn AbsPromotionRepo>, AppError> {
+    #[cfg(feature = "mariadb")]
+    if let Some(dbs) = ds.sql_dbs.as_ref() {
+        let obj = PromotionMariaDbRepo::try_build(dbs)?;
+        Ok(Box::new(obj))
+    } else {
+        Err(AppError {
+            code: AppErrorCode::FeatureDisabled,
+            detail: Some("mariadb".to_string()),
+        })
+    }
+    #[cfg(not(feature = "mariadb"))]
+    if let Some(m) = ds.in_mem.as_ref() {
+        let obj = PromotionInMemRepo::new(m.clone()).await?;
+        Ok(Box::new(obj))
+    } else {
+        Err(AppError {
+            code: AppErrorCode::MissingDataStore,
+            detail: Some("unknwon-type".to_string()),
+        })
+    }
+}
+
+pub async fn app_repo_tax_rate(
+    ds: Arc<AppDataStoreContext>,
+) -> DefaultResult<Box<dyn AbsTaxRateRepo>, AppError> {
+    #[cfg(feature = "mariadb")]
+    if let Some(dbs) = ds.sql_dbs.as_ref() {
+        let obj = TaxRateMariaDbRepo::try_build(dbs)?;
+        Ok(Box::new(obj))
+    } else {
+        Err(AppError {
+            code: AppErrorCode::FeatureDisabled,
+            detail: Some("mariadb".to_string()),
+        })
+    }
+    #[cfg(not(feature = "mariadb"))]
+    if let Some(m) = ds.in_mem.as_ref() {
+        let obj = TaxRateInMemRepo::new(m.clone()).await?;
+        Ok(Box::new(obj))
+    } else {
+        Err(AppError {
+            code: AppErrorCode::MissingDataStore,
+            detail: Some("unknown-type".to_string()),
+        })
+    }
+}
+
+pub async fn app_repo_stock_alert(
+    ds: Arc<AppDataStoreContext>,
+) -> DefaultResult<Box<dyn AbsStockAlertRepo>, AppError> {
+    #[cfg(feature = "mariadb")]
+    if let Some(dbs) = ds.sql_dbs.as_ref() {
+        let obj = StockAlertMariaDbRepo::try_build(dbs)?;
+        Ok(Box::new(obj))
+    } else {
+        Err(AppError {
+            code: AppErrorCode::FeatureDisabled,
+            detail: Some("mariadb".to_string()),
+        })
+    }
+    #[cfg(not(feature = "mariadb"))]
+    if let Some(m) = ds.in_mem.as_ref() {
+        let obj = StockAlertInMemRepo::new(m.clone()).await?;
+        Ok(Box::new(obj))
+    } else {
+        Err(AppError {
+            code: AppErrorCode::MissingDataStore,
+            detail: Some("unknown-type".to_string()),
+        })
+    }
+}
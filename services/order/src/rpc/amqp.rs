@@ -426,15 +426,12 @@ impl AbstractRpcClient for AmqpRpcClientHandler {
     ) -> DefaultResult<Box<dyn AbstractRpcClient>, AppError> {
         let (route, content, t_start) = (req.route, req.msgbody, req.start_time);
         let bind_cfg = Self::try_get_binding(self.bindings.as_ref(), route.as_str())?;
-        let (reply_q_name, corr_id_prefix) = if let Some(r_cfg) = &bind_cfg.reply {
-            (r_cfg.queue.as_str(), r_cfg.correlation_id_prefix.as_str())
-        } else {
-            let e = AppError {
-                code: AppErrorCode::InvalidInput,
-                detail: Some("rpc-client-seq, empty-reply-cfg".to_string()),
-            };
-            return Err(e);
-        };
+        // the binding without reply configuration is for publishing event, the
+        // caller does not expect any reply from remote consumer
+        let reply_cfg = bind_cfg.reply.as_ref();
+        let corr_id_prefix = reply_cfg
+            .map(|r| r.correlation_id_prefix.as_str())
+            .unwrap_or(bind_cfg.routing_key.as_str());
         let mut corr_id = generate_custom_uid(app_meta::MACHINE_CODE)
             .into_bytes()
             .into_iter()
@@ -448,10 +445,12 @@ impl AbstractRpcClient for AmqpRpcClientHandler {
             .with_content_type(HTTP_CONTENT_TYPE_JSON)
             .with_content_encoding("utf-8")
             .with_persistence(bind_cfg.durable)
-            .with_reply_to(reply_q_name)
             .with_correlation_id(corr_id.as_str())
             .with_timestamp(t_start.timestamp() as u64)
             .finish();
+        if let Some(r_cfg) = reply_cfg {
+            properties = properties.with_reply_to(r_cfg.queue.as_str()).finish();
+        }
         let properties = if let Some(py_tsk_path) = &bind_cfg.python_celery_task {
             let mut extra_headers = FieldTable::new();
             extra_headers.insert(
@@ -488,6 +487,10 @@ impl AbstractRpcClient for AmqpRpcClientHandler {
             }
             return Err(e);
         }
+        if reply_cfg.is_none() {
+            self.as_mut().reply_evt = None;
+            return Ok(self);
+        }
         // update at the end , due to borrow / mutability constraint at compile time
         self.recv_reply.claim(corr_id.as_str()).await?;
         self.as_mut().reply_evt = {
@@ -3,10 +3,15 @@ use std::result::Result;
 use std::sync::Arc;
 
 use ecommerce_common::api::dto::CurrencyDto;
+use ecommerce_common::error::AppErrorCode;
 use ecommerce_common::logging::{app_log_event, AppLogContext, AppLogLevel};
 
 use crate::adapter::thirdparty::AbstractCurrencyExchange;
+use crate::api::rpc::dto::{
+    CurrencyPairRateDto, CurrencyRateHistoryReqDto, CurrencyRateHistoryRespDto,
+};
 use crate::error::AppError;
+use crate::model::CurrencyPairRateModel;
 use crate::repository::AbsCurrencyRepo;
 
 pub struct CurrencyRateRefreshUseCase;
@@ -17,17 +22,23 @@ impl CurrencyRateRefreshUseCase {
         exrate_ctx: Arc<Box<dyn AbstractCurrencyExchange>>,
         logctx: Arc<AppLogContext>,
     ) -> Result<(), AppError> {
-        let chosen = vec![
-            CurrencyDto::USD,
-            CurrencyDto::IDR,
-            CurrencyDto::INR,
-            CurrencyDto::TWD,
-            CurrencyDto::THB,
-        ];
+        let chosen = CurrencyDto::supported();
         let mut ms = exrate_ctx.refresh(chosen).await.map_err(|e| {
             app_log_event!(logctx, AppLogLevel::ERROR, "{:?}", e);
             e
         })?;
+        app_log_event!(
+            logctx,
+            AppLogLevel::INFO,
+            "provider:{:?}, base:{}",
+            ms.provider,
+            ms.base.to_string()
+        );
+        // the rates are always saved with USD as the base currency
+        ms.rebase(CurrencyDto::USD).map_err(|e| {
+            app_log_event!(logctx, AppLogLevel::ERROR, "{:?}", e);
+            e
+        })?;
         ms.trunc_rate_fraction();
         repo.save(ms).await.map_err(|e| {
             app_log_event!(logctx, AppLogLevel::ERROR, "{:?}", e);
@@ -35,3 +46,51 @@ impl CurrencyRateRefreshUseCase {
         })
     }
 } // end of impl CurrencyRateRefreshUseCase
+
+pub struct CurrencyRateHistoryUseCase;
+
+impl CurrencyRateHistoryUseCase {
+    pub async fn execute(
+        repo: Box<dyn AbsCurrencyRepo>,
+        req: CurrencyRateHistoryReqDto,
+        logctx: Arc<AppLogContext>,
+    ) -> Result<CurrencyRateHistoryRespDto, AppError> {
+        let CurrencyRateHistoryReqDto {
+            base,
+            quote,
+            start,
+            end,
+        } = req;
+        let detail = if [&base, &quote].contains(&&CurrencyDto::Unknown) {
+            Some("currency-label".to_string())
+        } else if start >= end {
+            Some(format!("time-range, start:{start}, end:{end}"))
+        } else {
+            None
+        };
+        if let Some(d) = detail {
+            return Err(AppError {
+                code: AppErrorCode::InvalidInput,
+                detail: Some(d),
+            });
+        }
+        let chosen = vec![base.clone(), quote.clone()];
+        let initial = repo.fetch_as_of(chosen.clone(), start).await;
+        let records = repo.fetch_history(chosen, start, end).await;
+        let (initial, records) = match (initial, records) {
+            (Ok(i), Ok(r)) => (i, r),
+            (Err(e), _) | (_, Err(e)) => {
+                app_log_event!(logctx, AppLogLevel::ERROR, "{:?}", e);
+                return Err(e);
+            }
+        };
+        let rates = CurrencyPairRateModel::collect((&base, &quote), start, &initial, records)
+            .into_iter()
+            .map(|m| CurrencyPairRateDto {
+                time: m.time.to_rfc3339(),
+                rate: m.rate.round_dp(8).to_string(),
+            })
+            .collect();
+        Ok(CurrencyRateHistoryRespDto { base, quote, rates })
+    }
+} // end of impl CurrencyRateHistoryUseCase
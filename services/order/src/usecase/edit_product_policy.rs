@@ -11,16 +11,19 @@ use serde::{Deserialize, Serialize};
 use ecommerce_common::api::web::dto::QuotaResourceErrorDto;
 use ecommerce_common::error::AppErrorCode;
 use ecommerce_common::logging::{app_log_event, AppLogContext, AppLogLevel};
+use ecommerce_common::model::BaseProductIdentity;
 
 use crate::error::AppError;
 use crate::model::ProductPolicyModelSet;
-use crate::repository::app_repo_product_policy;
+use crate::repository::{app_repo_product_policy, AbstProductPolicyRepo};
 use crate::rpc::{AbstractRpcContext, AppRpcClientReqProperty};
 use crate::{AppAuthPermissionCode, AppAuthQuotaMatCode, AppAuthedClaim, AppDataStoreContext};
 
-use crate::api::web::dto::{ProductPolicyClientErrorDto, ProductPolicyDto};
+use crate::api::web::dto::{
+    ProductPolicyClientErrorDto, ProductPolicyDeleteReqDto, ProductPolicyDto,
+};
 
-use super::{initiate_rpc_request, AppUCrunRPCfn, AppUseKsRPCreply};
+use super::{initiate_rpc_request, AppUCrunRPCfn, AppUseKsRPCreply, VerifyStoreStaffUseCase};
 
 // the product info types below represent message body to remote product service
 #[derive(Serialize)]
@@ -88,6 +91,19 @@ impl EditProductPolicyUseCase {
             return EditProductPolicyResult::ClientError(ce);
         }
         let usr_prof_id = authed_usr.profile;
+        let store_ids = data.iter().map(|d| d.seller_id).collect::<Vec<_>>();
+        let verifier = VerifyStoreStaffUseCase {
+            rpc_ctx: rpc_ctx.clone(),
+            logctx: log.clone(),
+        };
+        match verifier.execute(usr_prof_id, store_ids).await {
+            Ok(true) => {}
+            Ok(false) => return EditProductPolicyResult::PermissionDeny,
+            Err(e) => {
+                app_log_event!(log, AppLogLevel::ERROR, "store-staff-check:{:?}", e);
+                return EditProductPolicyResult::Other(e.code);
+            }
+        }
         let rpctype = rpc_ctx.label();
         let result = Self::check_product_existence(
             &data,
@@ -116,10 +132,12 @@ impl EditProductPolicyUseCase {
                     "missing_prod_ids:{:?}",
                     missing_prod_ids
                 );
-                let c_err = missing_prod_ids
-                    .into_iter()
-                    .map(|product_id| ProductPolicyClientErrorDto {
-                        product_id,
+                let c_err = data
+                    .iter()
+                    .filter(|d| missing_prod_ids.contains(&d.product_id))
+                    .map(|d| ProductPolicyClientErrorDto {
+                        seller_id: d.seller_id,
+                        product_id: d.product_id,
                         err_type: format!("{:?}", AppErrorCode::ProductNotExist),
                         warranty_hours: None,
                         auto_cancel_secs: None,
@@ -138,9 +156,6 @@ impl EditProductPolicyUseCase {
         }
     } // end of _execute
 
-    // TODO, check whether the user has permission to edit specific product, this relies
-    // on validation by RPC call to `storefront` service
-
     pub async fn check_product_existence(
         data: &[ProductPolicyDto],
         usr_prof_id: u32,
@@ -199,7 +214,13 @@ impl EditProductPolicyUseCase {
         data: Vec<ProductPolicyDto>,
     ) -> DefaultResult<(), AppError> {
         let repo = app_repo_product_policy(ds).await?;
-        let ids = data.iter().map(|d| d.product_id).collect();
+        let ids = data
+            .iter()
+            .map(|d| BaseProductIdentity {
+                store_id: d.seller_id,
+                product_id: d.product_id,
+            })
+            .collect();
         let previous_saved = repo.fetch(ids).await?;
         let updated = previous_saved.update(data)?;
         repo.save(updated).await?;
@@ -216,3 +237,88 @@ pub struct EditProductPolicyUseCase {
     pub rpc_serialize_msg: fn(ProductInfoReq) -> DefaultResult<Vec<u8>, AppError>,
     pub rpc_deserialize_msg: fn(&Vec<u8>) -> DefaultResult<ProductInfoResp, AppError>,
 }
+
+pub struct DeleteProductPolicyUseCase {
+    pub log: Arc<AppLogContext>,
+    pub repo: Box<dyn AbstProductPolicyRepo>,
+    pub rpc_ctx: Arc<Box<dyn AbstractRpcContext>>,
+    pub authed_usr: AppAuthedClaim,
+}
+
+impl DeleteProductPolicyUseCase {
+    pub async fn execute(self, data: Vec<ProductPolicyDeleteReqDto>) -> EditProductPolicyResult {
+        let Self {
+            log,
+            repo,
+            rpc_ctx,
+            authed_usr,
+        } = self;
+        let perm_allowed =
+            authed_usr.contain_permission(AppAuthPermissionCode::can_create_product_policy);
+        if !perm_allowed {
+            return EditProductPolicyResult::PermissionDeny;
+        }
+        if data.is_empty() {
+            let ce = Self::client_error(0, 0, AppErrorCode::EmptyInputData);
+            return EditProductPolicyResult::ClientError(vec![ce]);
+        }
+        let ids = data
+            .iter()
+            .map(|d| BaseProductIdentity {
+                store_id: d.seller_id,
+                product_id: d.product_id,
+            })
+            .collect::<Vec<_>>();
+        let verifier = VerifyStoreStaffUseCase {
+            rpc_ctx,
+            logctx: log.clone(),
+        };
+        let store_ids = ids.iter().map(|id_| id_.store_id).collect::<Vec<_>>();
+        match verifier.execute(authed_usr.profile, store_ids).await {
+            Ok(true) => {}
+            Ok(false) => return EditProductPolicyResult::PermissionDeny,
+            Err(e) => {
+                app_log_event!(log, AppLogLevel::ERROR, "store-staff-check:{:?}", e);
+                return EditProductPolicyResult::Other(e.code);
+            }
+        }
+        let saved = match repo.fetch(ids.clone()).await {
+            Ok(v) => v,
+            Err(e) => {
+                app_log_event!(log, AppLogLevel::ERROR, "error:{:?}", e);
+                return EditProductPolicyResult::Other(e.code);
+            }
+        };
+        let c_err = ids
+            .iter()
+            .filter(|id_| !saved.policies.iter().any(|m| &m.id() == *id_))
+            .map(|id_| {
+                Self::client_error(id_.store_id, id_.product_id, AppErrorCode::ProductNotExist)
+            })
+            .collect::<Vec<_>>();
+        if !c_err.is_empty() {
+            return EditProductPolicyResult::ClientError(c_err);
+        }
+        if let Err(e) = repo.delete(ids).await {
+            app_log_event!(log, AppLogLevel::ERROR, "error:{:?}", e);
+            EditProductPolicyResult::Other(e.code)
+        } else {
+            EditProductPolicyResult::OK
+        }
+    } // end of fn execute
+
+    fn client_error(
+        seller_id: u32,
+        product_id: u64,
+        code: AppErrorCode,
+    ) -> ProductPolicyClientErrorDto {
+        ProductPolicyClientErrorDto {
+            seller_id,
+            product_id,
+            err_type: format!("{:?}", code),
+            warranty_hours: None,
+            auto_cancel_secs: None,
+            num_rsv: None,
+        }
+    }
+} // end of impl DeleteProductPolicyUseCase
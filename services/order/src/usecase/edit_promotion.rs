@@ -0,0 +1,78 @@
+use std::sync::Arc;
+use std::vec::Vec;
+
+use ecommerce_common::error::AppErrorCode;
+use ecommerce_common::logging::{app_log_event, AppLogContext, AppLogLevel};
+
+use crate::api::web::dto::{PromotionClientErrorDto, PromotionDto};
+use crate::model::PromotionModelSet;
+use crate::repository::AbsPromotionRepo;
+use crate::rpc::AbstractRpcContext;
+use crate::{AppAuthPermissionCode, AppAuthedClaim};
+
+use super::VerifyStoreStaffUseCase;
+
+#[derive(PartialEq, Debug)]
+pub enum EditPromotionUsKsResult {
+    OK,
+    PermissionDeny,
+    ClientError(Vec<PromotionClientErrorDto>),
+    Other(AppErrorCode),
+}
+
+pub struct EditPromotionUseCase {
+    pub log: Arc<AppLogContext>,
+    pub repo: Box<dyn AbsPromotionRepo>,
+    pub rpc_ctx: Arc<Box<dyn AbstractRpcContext>>,
+    pub authed_usr: AppAuthedClaim,
+}
+
+impl EditPromotionUseCase {
+    pub async fn execute(self, data: Vec<PromotionDto>) -> EditPromotionUsKsResult {
+        let Self {
+            log,
+            repo,
+            rpc_ctx,
+            authed_usr,
+        } = self;
+        let perm_allowed =
+            authed_usr.contain_permission(AppAuthPermissionCode::can_create_promotion);
+        if !perm_allowed {
+            return EditPromotionUsKsResult::PermissionDeny;
+        }
+        if let Err(ce) = PromotionModelSet::validate(&data) {
+            return EditPromotionUsKsResult::ClientError(ce);
+        }
+        let verifier = VerifyStoreStaffUseCase {
+            rpc_ctx,
+            logctx: log.clone(),
+        };
+        let store_ids = data.iter().map(|d| d.seller_id).collect::<Vec<_>>();
+        match verifier.execute(authed_usr.profile, store_ids).await {
+            Ok(true) => {}
+            Ok(false) => return EditPromotionUsKsResult::PermissionDeny,
+            Err(e) => {
+                app_log_event!(log, AppLogLevel::ERROR, "store-staff-check:{:?}", e);
+                return EditPromotionUsKsResult::Other(e.code);
+            }
+        }
+        let codes = data.iter().map(|d| d.code.clone()).collect::<Vec<_>>();
+        let saved = match repo.fetch(codes).await {
+            Ok(v) => v,
+            Err(e) => {
+                app_log_event!(log, AppLogLevel::ERROR, "error:{:?}", e);
+                return EditPromotionUsKsResult::Other(e.code);
+            }
+        };
+        let updated = match saved.update(data) {
+            Ok(v) => v,
+            Err(ce) => return EditPromotionUsKsResult::ClientError(ce),
+        };
+        if let Err(e) = repo.save(updated).await {
+            app_log_event!(log, AppLogLevel::ERROR, "error:{:?}", e);
+            EditPromotionUsKsResult::Other(e.code)
+        } else {
+            EditPromotionUsKsResult::OK
+        }
+    } // end of fn execute
+} // end of impl EditPromotionUseCase
@@ -0,0 +1,88 @@
+use std::boxed::Box;
+use std::result::Result as DefaultResult;
+use std::sync::Arc;
+use std::vec::Vec;
+
+use ecommerce_common::error::AppErrorCode;
+use ecommerce_common::logging::{app_log_event, AppLogContext, AppLogLevel};
+
+use crate::api::web::dto::{ShippingMethodClientErrorDto, ShippingMethodDto};
+use crate::error::AppError;
+use crate::model::ShippingMethodModelSet;
+use crate::repository::AbsShippingMethodRepo;
+use crate::rpc::AbstractRpcContext;
+use crate::{AppAuthPermissionCode, AppAuthedClaim};
+
+use super::VerifyStoreStaffUseCase;
+
+#[derive(PartialEq, Debug)]
+pub enum EditShippingMethodUsKsResult {
+    OK,
+    PermissionDeny,
+    ClientError(Vec<ShippingMethodClientErrorDto>),
+    Other(AppErrorCode),
+}
+
+pub struct EditShippingMethodUseCase {
+    pub log: Arc<AppLogContext>,
+    pub repo: Box<dyn AbsShippingMethodRepo>,
+    pub rpc_ctx: Arc<Box<dyn AbstractRpcContext>>,
+    pub authed_usr: AppAuthedClaim,
+}
+
+pub struct ReadShippingMethodUseCase {
+    pub repo: Box<dyn AbsShippingMethodRepo>,
+}
+
+impl EditShippingMethodUseCase {
+    pub async fn execute(self, data: Vec<ShippingMethodDto>) -> EditShippingMethodUsKsResult {
+        let Self {
+            log,
+            repo,
+            rpc_ctx,
+            authed_usr,
+        } = self;
+        let perm_allowed =
+            authed_usr.contain_permission(AppAuthPermissionCode::can_manage_shipping_method);
+        if !perm_allowed {
+            return EditShippingMethodUsKsResult::PermissionDeny;
+        }
+        let ms = match ShippingMethodModelSet::try_from(data) {
+            Ok(v) => v,
+            Err(ce) => return EditShippingMethodUsKsResult::ClientError(ce),
+        };
+        let verifier = VerifyStoreStaffUseCase {
+            rpc_ctx,
+            logctx: log.clone(),
+        };
+        let mut store_ids = ms.methods.iter().map(|m| m.store_id).collect::<Vec<_>>();
+        store_ids.sort_unstable();
+        store_ids.dedup();
+        match verifier.execute(authed_usr.profile, store_ids).await {
+            Ok(true) => {}
+            Ok(false) => return EditShippingMethodUsKsResult::PermissionDeny,
+            Err(e) => {
+                app_log_event!(log, AppLogLevel::ERROR, "store-staff-check:{:?}", e);
+                return EditShippingMethodUsKsResult::Other(e.code);
+            }
+        }
+        if let Err(e) = repo.save(ms).await {
+            app_log_event!(log, AppLogLevel::ERROR, "error:{:?}", e);
+            EditShippingMethodUsKsResult::Other(e.code)
+        } else {
+            EditShippingMethodUsKsResult::OK
+        }
+    } // end of fn execute
+} // end of impl EditShippingMethodUseCase
+
+impl ReadShippingMethodUseCase {
+    pub async fn execute(self, store_id: u32) -> DefaultResult<Vec<ShippingMethodDto>, AppError> {
+        let ms = self.repo.fetch(vec![store_id]).await?;
+        let out = ms
+            .methods
+            .into_iter()
+            .map(ShippingMethodDto::from)
+            .collect();
+        Ok(out)
+    }
+}
@@ -0,0 +1,25 @@
+use std::boxed::Box;
+use std::result::Result as DefaultResult;
+use std::sync::Arc;
+use std::vec::Vec;
+
+use ecommerce_common::logging::AppLogContext;
+
+use crate::api::rpc::dto::TaxRateDto;
+use crate::error::AppError;
+use crate::model::TaxRateModelSet;
+use crate::repository::AbsTaxRateRepo;
+
+use super::try_convert_save;
+
+pub struct EditTaxRateUseCase {}
+
+impl EditTaxRateUseCase {
+    pub async fn execute(
+        repo: Box<dyn AbsTaxRateRepo>,
+        data: Vec<TaxRateDto>,
+        logctx: Arc<AppLogContext>,
+    ) -> DefaultResult<(), AppError> {
+        try_convert_save(data, logctx, TaxRateModelSet::try_from, |ms| repo.save(ms)).await
+    }
+} // end of impl EditTaxRateUseCase
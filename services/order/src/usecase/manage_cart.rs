@@ -2,16 +2,25 @@ use std::boxed::Box;
 use std::result::Result as DefaultResult;
 use std::sync::Arc;
 
+use chrono::{Duration, Local};
+
+use ecommerce_common::api::dto::CurrencyDto;
 use ecommerce_common::api::web::dto::QuotaResourceErrorDto;
 use ecommerce_common::logging::{app_log_event, AppLogContext, AppLogLevel};
 use ecommerce_common::model::BaseProductIdentity;
 
-use crate::api::web::dto::CartDto;
+use crate::api::web::dto::{
+    CartCheckoutReqData, CartCollaboratorDto, CartCollaboratorReqDto, CartDto, CartQuoteDto,
+    OrderCreateReqData, OrderCreateRespOkDto,
+};
 use crate::constant::hard_limit;
 use crate::error::AppError;
-use crate::repository::AbsCartRepo;
+use crate::model::StockLevelModelSet;
+use crate::repository::{AbsCartRepo, AbsCurrencyRepo, AbsOrderStockRepo, AbsProductPriceRepo};
 use crate::{AppAuthQuotaMatCode, AppAuthedClaim};
 
+use super::{CreateOrderUsKsErr, CreateOrderUseCase};
+
 pub(crate) struct ModifyCartLineUseCase {
     pub repo: Box<dyn AbsCartRepo>,
     pub log_ctx: Arc<AppLogContext>,
@@ -25,44 +34,91 @@ pub(crate) struct RetrieveCartUseCase {
     pub repo: Box<dyn AbsCartRepo>,
     pub authed_usr: AppAuthedClaim,
 }
+pub(crate) struct EditCartCollaboratorUseCase {
+    pub repo: Box<dyn AbsCartRepo>,
+    pub authed_usr: AppAuthedClaim,
+}
+
+pub(crate) struct CheckoutCartUseCase {
+    pub repo: Box<dyn AbsCartRepo>,
+    // validation, quota check and stock reservation are delegated to the
+    // use case which creates new order
+    pub uc_order: CreateOrderUseCase,
+    pub log_ctx: Arc<AppLogContext>,
+}
+
+// read-only estimation, nothing is reserved in stock-level or currency repository
+pub(crate) struct QuoteCartUseCase {
+    pub repo: Box<dyn AbsCartRepo>,
+    pub repo_price: Box<dyn AbsProductPriceRepo>,
+    pub repo_stock: Arc<Box<dyn AbsOrderStockRepo>>,
+    pub repo_currex: Box<dyn AbsCurrencyRepo>,
+    pub authed_usr: AppAuthedClaim,
+}
 
 pub(crate) enum ModifyCartUsKsResult {
     Success,
     NotFound,
+    PermissionDenied,
     QuotaExceed(QuotaResourceErrorDto),
     ServerError(AppError),
 }
 pub(crate) enum DiscardCartUsKsResult {
     Success,
     NotFound,
+    PermissionDenied,
     ServerError(AppError),
 }
 pub(crate) enum RetrieveCartUsKsResult {
     Success(CartDto),
     NotFound,
+    PermissionDenied,
+    ServerError(AppError),
+}
+pub(crate) enum CheckoutCartUsKsResult {
+    Success(OrderCreateRespOkDto),
+    NotFound,
+    PermissionDenied,
+    EmptyCart,
+    InProgress,
+    CreateOrder(CreateOrderUsKsErr),
+    ServerError(AppError),
+}
+pub(crate) enum QuoteCartUsKsResult {
+    Success(CartQuoteDto),
+    NotFound,
+    PermissionDenied,
+    ServerError(Vec<AppError>),
+}
+pub(crate) enum EditCartCollabUsKsResult {
+    Success(CartCollaboratorDto),
+    NotFound,
+    QuotaExceed(QuotaResourceErrorDto),
     ServerError(AppError),
 }
 
 impl ModifyCartLineUseCase {
-    pub(crate) async fn execute(self, seq_num: u8, data: CartDto) -> ModifyCartUsKsResult {
+    pub(crate) async fn execute(
+        self,
+        owner: u32,
+        seq_num: u8,
+        data: CartDto,
+    ) -> ModifyCartUsKsResult {
         if seq_num >= hard_limit::MAX_NUM_CARTS_PER_USER {
             return ModifyCartUsKsResult::NotFound;
         }
-        match self.validate_update(seq_num, data).await {
-            Ok(v) => match v {
-                Some(e) => ModifyCartUsKsResult::QuotaExceed(e),
-                None => ModifyCartUsKsResult::Success,
-            },
+        match self.validate_update(owner, seq_num, data).await {
+            Ok(v) => v,
             Err(e) => ModifyCartUsKsResult::ServerError(e),
         }
     }
 
     async fn validate_update(
         &self,
+        owner: u32,
         seq_num: u8,
         data: CartDto,
-    ) -> DefaultResult<Option<QuotaResourceErrorDto>, AppError> {
-        let owner = self.authed_usr.profile;
+    ) -> DefaultResult<ModifyCartUsKsResult, AppError> {
         let pids = data
             .lines
             .iter()
@@ -72,54 +128,66 @@ impl ModifyCartLineUseCase {
             })
             .collect::<Vec<_>>();
         let mut obj = self.repo.fetch_lines_by_pid(owner, seq_num, pids).await?;
+        if !obj.accessible(self.authed_usr.profile) {
+            return Ok(ModifyCartUsKsResult::PermissionDenied);
+        }
         obj.update(data);
         let logctx = &self.log_ctx;
         app_log_event!(
             logctx,
             AppLogLevel::DEBUG,
-            "seq_num:{seq_num}, num-adding:{},\
+            "owner:{owner}, seq_num:{seq_num}, num-adding:{},\
                        num-updating:{}",
             obj.saved_lines.len(),
             obj.new_lines.len()
         );
         let num_saved = self.repo.num_lines_saved(owner, seq_num).await?;
         let total_num_lines = num_saved + obj.new_lines.len();
-        let max_limit = self
+        let usr_limit = self
             .authed_usr
             .quota_limit(AppAuthQuotaMatCode::NumOrderLines);
+        let max_limit = obj.max_num_lines(self.authed_usr.profile, usr_limit);
         if total_num_lines < (max_limit as usize) {
             let _num_updated = self.repo.update(obj).await?;
-            Ok(None)
+            Ok(ModifyCartUsKsResult::Success)
         } else {
             let e = QuotaResourceErrorDto {
                 given: total_num_lines,
                 max_: max_limit,
             };
-            Ok(Some(e))
+            Ok(ModifyCartUsKsResult::QuotaExceed(e))
         }
     }
 } // end of impl ModifyCartLineUseCase
 
 impl DiscardCartUseCase {
-    pub(crate) async fn execute(self, seq_num: u8) -> DiscardCartUsKsResult {
-        if seq_num < hard_limit::MAX_NUM_CARTS_PER_USER {
-            let owner = self.authed_usr.profile;
-            match self.repo.discard(owner, seq_num).await {
-                Ok(_v) => DiscardCartUsKsResult::Success,
-                Err(e) => DiscardCartUsKsResult::ServerError(e),
-            }
-        } else {
-            DiscardCartUsKsResult::NotFound
+    pub(crate) async fn execute(self, owner: u32, seq_num: u8) -> DiscardCartUsKsResult {
+        if seq_num >= hard_limit::MAX_NUM_CARTS_PER_USER {
+            return DiscardCartUsKsResult::NotFound;
+        }
+        // collaborators can edit lines of a shared cart, but only the owner
+        // can discard the entire cart
+        if owner != self.authed_usr.profile {
+            return DiscardCartUsKsResult::PermissionDenied;
+        }
+        match self.repo.discard(owner, seq_num).await {
+            Ok(_v) => DiscardCartUsKsResult::Success,
+            Err(e) => DiscardCartUsKsResult::ServerError(e),
         }
     }
 }
 
 impl RetrieveCartUseCase {
-    pub(crate) async fn execute(self, seq_num: u8) -> RetrieveCartUsKsResult {
+    pub(crate) async fn execute(self, owner: u32, seq_num: u8) -> RetrieveCartUsKsResult {
         if seq_num < hard_limit::MAX_NUM_CARTS_PER_USER {
-            let owner = self.authed_usr.profile;
             match self.repo.fetch_cart(owner, seq_num).await {
-                Ok(m) => RetrieveCartUsKsResult::Success(m.into()),
+                Ok(m) => {
+                    if m.accessible(self.authed_usr.profile) {
+                        RetrieveCartUsKsResult::Success(m.into())
+                    } else {
+                        RetrieveCartUsKsResult::PermissionDenied
+                    }
+                }
                 Err(e) => RetrieveCartUsKsResult::ServerError(e),
             }
         } else {
@@ -127,3 +195,209 @@ impl RetrieveCartUseCase {
         }
     }
 }
+
+impl EditCartCollaboratorUseCase {
+    // only the owner can manage the collaborators of its own carts
+    pub(crate) async fn invite(
+        self,
+        seq_num: u8,
+        data: CartCollaboratorReqDto,
+    ) -> EditCartCollabUsKsResult {
+        if seq_num >= hard_limit::MAX_NUM_CARTS_PER_USER {
+            return EditCartCollabUsKsResult::NotFound;
+        }
+        let owner = self.authed_usr.profile;
+        let mut obj = match self.repo.fetch_cart(owner, seq_num).await {
+            Ok(m) => m,
+            Err(e) => return EditCartCollabUsKsResult::ServerError(e),
+        };
+        if let Err(e) = obj.invite(data.usr_ids) {
+            return EditCartCollabUsKsResult::QuotaExceed(e);
+        }
+        obj.owner_quota_lines = self
+            .authed_usr
+            .quota_limit(AppAuthQuotaMatCode::NumOrderLines);
+        match self.repo.update_collaborators(&obj).await {
+            Ok(()) => EditCartCollabUsKsResult::Success(CartCollaboratorDto::from(&obj)),
+            Err(e) => EditCartCollabUsKsResult::ServerError(e),
+        }
+    }
+
+    pub(crate) async fn revoke(
+        self,
+        seq_num: u8,
+        data: CartCollaboratorReqDto,
+    ) -> EditCartCollabUsKsResult {
+        if seq_num >= hard_limit::MAX_NUM_CARTS_PER_USER {
+            return EditCartCollabUsKsResult::NotFound;
+        }
+        let owner = self.authed_usr.profile;
+        let mut obj = match self.repo.fetch_cart(owner, seq_num).await {
+            Ok(m) => m,
+            Err(e) => return EditCartCollabUsKsResult::ServerError(e),
+        };
+        obj.revoke(data.usr_ids);
+        obj.owner_quota_lines = self
+            .authed_usr
+            .quota_limit(AppAuthQuotaMatCode::NumOrderLines);
+        match self.repo.update_collaborators(&obj).await {
+            Ok(()) => EditCartCollabUsKsResult::Success(CartCollaboratorDto::from(&obj)),
+            Err(e) => EditCartCollabUsKsResult::ServerError(e),
+        }
+    }
+} // end of impl EditCartCollaboratorUseCase
+
+impl CheckoutCartUseCase {
+    pub(crate) async fn execute(
+        self,
+        owner: u32,
+        seq_num: u8,
+        data: CartCheckoutReqData,
+    ) -> CheckoutCartUsKsResult {
+        if seq_num >= hard_limit::MAX_NUM_CARTS_PER_USER {
+            return CheckoutCartUsKsResult::NotFound;
+        }
+        // concurrent checkout requests on the same cart would create duplicate
+        // orders and reserve the stock twice, the cart is claimed exclusively
+        // before creating the order
+        let time_now = Local::now().fixed_offset();
+        let expiry = time_now + Duration::seconds(hard_limit::CART_CHECKOUT_CLAIM_SECS);
+        match self
+            .repo
+            .try_claim_checkout(owner, seq_num, time_now, expiry)
+            .await
+        {
+            Ok(true) => {}
+            Ok(false) => return CheckoutCartUsKsResult::InProgress,
+            Err(e) => return CheckoutCartUsKsResult::ServerError(e),
+        }
+        let Self {
+            repo,
+            uc_order,
+            log_ctx,
+        } = self;
+        let result =
+            Self::checkout_claimed(repo.as_ref(), uc_order, &log_ctx, owner, seq_num, data).await;
+        if let Err(e) = repo.release_checkout(owner, seq_num).await {
+            app_log_event!(
+                log_ctx,
+                AppLogLevel::ERROR,
+                "owner:{owner}, seq_num:{seq_num}, release-claim-error:{:?}",
+                e
+            );
+        } // the claim will expire anyway
+        result
+    } // end of fn execute
+
+    async fn checkout_claimed(
+        repo: &dyn AbsCartRepo,
+        uc_order: CreateOrderUseCase,
+        log_ctx: &Arc<AppLogContext>,
+        owner: u32,
+        seq_num: u8,
+        data: CartCheckoutReqData,
+    ) -> CheckoutCartUsKsResult {
+        let cart = match repo.fetch_cart(owner, seq_num).await {
+            Ok(m) => m,
+            Err(e) => return CheckoutCartUsKsResult::ServerError(e),
+        };
+        if !cart.accessible(uc_order.auth_claim.profile) {
+            return CheckoutCartUsKsResult::PermissionDenied;
+        }
+        if cart.saved_lines.is_empty() {
+            return CheckoutCartUsKsResult::EmptyCart;
+        }
+        let pids = cart
+            .saved_lines
+            .iter()
+            .map(|l| BaseProductIdentity {
+                store_id: l.id_.store_id,
+                product_id: l.id_.product_id,
+            })
+            .collect::<Vec<_>>();
+        let CartCheckoutReqData {
+            currency,
+            billing,
+            shipping,
+            coupons,
+        } = data;
+        let req = OrderCreateReqData {
+            order_lines: CartDto::from(cart).lines,
+            currency,
+            billing,
+            shipping,
+            coupons,
+        };
+        let resp = match uc_order.execute(req).await {
+            Ok(v) => v,
+            Err(e) => return CheckoutCartUsKsResult::CreateOrder(e),
+        };
+        // cart lines are removed only after the stock reservation succeeded, the
+        // order has been created at this point, so any error here is not reported
+        // to client, the buyer can still discard the lines manually
+        if let Err(e) = repo.discard_lines(owner, seq_num, pids).await {
+            app_log_event!(
+                log_ctx,
+                AppLogLevel::ERROR,
+                "owner:{owner}, seq_num:{seq_num}, oid:{}, error:{:?}",
+                resp.order_id,
+                e
+            );
+        }
+        CheckoutCartUsKsResult::Success(resp)
+    } // end of fn checkout_claimed
+} // end of impl CheckoutCartUseCase
+
+impl QuoteCartUseCase {
+    pub(crate) async fn execute(
+        self,
+        owner: u32,
+        seq_num: u8,
+        currency: CurrencyDto,
+    ) -> QuoteCartUsKsResult {
+        if seq_num >= hard_limit::MAX_NUM_CARTS_PER_USER {
+            return QuoteCartUsKsResult::NotFound;
+        }
+        let cart = match self.repo.fetch_cart(owner, seq_num).await {
+            Ok(m) => m,
+            Err(e) => return QuoteCartUsKsResult::ServerError(vec![e]),
+        };
+        if !cart.accessible(self.authed_usr.profile) {
+            return QuoteCartUsKsResult::PermissionDenied;
+        }
+        let pids = cart
+            .saved_lines
+            .iter()
+            .map(|l| BaseProductIdentity {
+                store_id: l.id_.store_id,
+                product_id: l.id_.product_id,
+            })
+            .collect::<Vec<_>>();
+        let (ms_price, ms_stock) = if pids.is_empty() {
+            (Vec::new(), StockLevelModelSet { stores: vec![] })
+        } else {
+            let price_ids = pids.iter().map(|d| (d.store_id, d.product_id)).collect();
+            let results = (
+                self.repo_price.fetch_many(price_ids).await,
+                self.repo_stock.fetch_by_product(pids).await,
+            );
+            match results {
+                (Ok(v0), Ok(v1)) => (v0, v1),
+                (r0, r1) => {
+                    let es = [r0.err(), r1.err()].into_iter().flatten().collect();
+                    return QuoteCartUsKsResult::ServerError(es);
+                }
+            }
+        };
+        let repo_currex = self.repo_currex.as_ref();
+        let o_currency =
+            match CreateOrderUseCase::snapshot_currencies(repo_currex, currency, &ms_price).await {
+                Ok(v) => v,
+                Err(es) => return QuoteCartUsKsResult::ServerError(es),
+            };
+        match cart.quote(&ms_price, &ms_stock, o_currency) {
+            Ok(v) => QuoteCartUsKsResult::Success(v),
+            Err(e) => QuoteCartUsKsResult::ServerError(vec![e]),
+        }
+    } // end of fn execute
+} // end of impl QuoteCartUseCase
@@ -5,7 +5,7 @@ use std::pin::Pin;
 use std::result::Result as DefaultResult;
 use std::sync::Arc;
 
-use chrono::{DateTime, FixedOffset, Local as LocalTime};
+use chrono::{DateTime, Duration, FixedOffset, Local as LocalTime};
 use ecommerce_common::api::dto::{CurrencyDto, GenericRangeErrorDto};
 use ecommerce_common::api::rpc::dto::{
     OrderLinePayUpdateErrorDto, OrderLineReplicaRefundDto, OrderPaymentUpdateDto,
@@ -21,25 +21,29 @@ use crate::api::rpc::dto::{
     StockLevelReturnDto, StockReturnErrorDto,
 };
 use crate::api::web::dto::{
-    BillingReqDto, OrderCreateReqData, OrderCreateRespErrorDto, OrderCreateRespOkDto,
-    OrderLineCreateErrNonExistDto, OrderLineCreateErrorDto, OrderLineCreateErrorReason,
-    OrderLineReturnErrorDto, OrderLineReturnReqDto, OrderLineRsvReqDto, ShippingErrorDto,
-    ShippingReqDto,
+    BillingReqDto, CouponErrorDto, OrderCreateReqData, OrderCreateRespErrorDto,
+    OrderCreateRespOkDto, OrderInfoReadDto, OrderInfoReadReqFlag, OrderLineCancelErrorDto,
+    OrderLineCancelReqDto, OrderLineCreateErrNonExistDto, OrderLineCreateErrorDto,
+    OrderLineCreateErrorReason, OrderLineReturnErrorDto, OrderLineReturnReqDto, OrderLineRsvReqDto,
+    OrderListReqQuery, OrderListRespDto, OrderSummaryDto, ShippingErrorDto, ShippingReqDto,
 };
 
 use ecommerce_common::error::AppErrorCode;
 use ecommerce_common::logging::{app_log_event, AppLogContext, AppLogLevel};
 use ecommerce_common::model::order::BillingModel;
+use ecommerce_common::model::BaseProductIdentity;
 
-use crate::constant::app_meta;
+use crate::constant::{app_meta, hard_limit};
 use crate::error::AppError;
 use crate::model::{
-    OlineDupError, OrderCurrencyModel, OrderLineIdentity, OrderLineModel, OrderLineModelSet,
-    OrderReturnModel, ProductPolicyModelSet, ProductPriceModelSet, ShippingModel,
-    StockLevelModelSet,
+    OlineDupError, OrderCurrencyModel, OrderLineCancelModel, OrderLineIdentity, OrderLineModel,
+    OrderLineModelSet, OrderReturnModel, ProductPolicyModelSet, ProductPriceModelSet,
+    PromotionModel, PromotionModelSet, ShippingModel, ShippingOptionModel,
+    StockAllocPolicyModelSet, StockLevelModelSet,
 };
 use crate::repository::{
-    AbsCurrencyRepo, AbsOrderRepo, AbsOrderReturnRepo, AbsProductPriceRepo, AbstProductPolicyRepo,
+    AbsCurrencyRepo, AbsOrderRepo, AbsOrderReturnRepo, AbsProductPriceRepo, AbsPromotionRepo,
+    AbsShipmentRepo, AbsShippingMethodRepo, AbsTaxRateRepo, AbstProductPolicyRepo,
     AppStockRepoReserveReturn,
 };
 use crate::{AppAuthPermissionCode, AppAuthQuotaMatCode, AppAuthedClaim, AppSharedState};
@@ -58,6 +62,9 @@ pub struct CreateOrderUseCase {
     pub repo_order: Box<dyn AbsOrderRepo>,
     pub repo_price: Box<dyn AbsProductPriceRepo>,
     pub repo_policy: Box<dyn AbstProductPolicyRepo>,
+    pub repo_promo: Box<dyn AbsPromotionRepo>,
+    pub repo_tax: Box<dyn AbsTaxRateRepo>,
+    pub repo_ship: Box<dyn AbsShippingMethodRepo>,
     pub auth_claim: AppAuthedClaim,
 }
 
@@ -84,6 +91,39 @@ pub struct ReturnLinesReqUseCase {
     pub authed_claim: AppAuthedClaim,
     pub o_repo: Box<dyn AbsOrderRepo>,
     pub or_repo: Box<dyn AbsOrderReturnRepo>,
+    pub sh_repo: Box<dyn AbsShipmentRepo>,
+    pub logctx: Arc<AppLogContext>,
+}
+
+pub enum ListOwnedOrdersUcOutput {
+    Success(OrderListRespDto),
+    InvalidTimeRange,
+    InvalidPagination,
+}
+pub struct ListOwnedOrdersUseCase {
+    pub authed_claim: AppAuthedClaim,
+    pub repo: Box<dyn AbsOrderRepo>,
+}
+
+pub enum ReadOrderInfoUcOutput {
+    Success(Box<OrderInfoReadDto>),
+    NotFound,
+    InvalidOwner,
+}
+pub struct ReadOrderInfoUseCase {
+    pub authed_claim: AppAuthedClaim,
+    pub repo: Box<dyn AbsOrderRepo>,
+}
+
+pub enum CancelLinesReqUcOutput {
+    Success,
+    NotFound,
+    InvalidOwner,
+    InvalidRequest(Vec<OrderLineCancelErrorDto>),
+}
+pub struct CancelLinesReqUseCase {
+    pub authed_claim: AppAuthedClaim,
+    pub repo: Box<dyn AbsOrderRepo>,
     pub logctx: Arc<AppLogContext>,
 }
 
@@ -98,6 +138,7 @@ impl CreateOrderUseCase {
             shipping: sh_d,
             order_lines: ol_d,
             currency: currency_buyer,
+            coupons,
         } = req;
         Self::validate_quota(
             &self.auth_claim,
@@ -107,8 +148,11 @@ impl CreateOrderUseCase {
             bl_d.contact.phones.len(),
             ol_d.len(),
         )?;
-        let (o_bl, o_sh) = Self::validate_metadata(sh_d, bl_d)?;
+        let (o_bl, mut o_sh) = Self::validate_metadata(sh_d, bl_d)?;
         let (ms_policy, ms_price) = self.load_product_properties(&ol_d).await?;
+        let weights = ms_policy.total_weight(&ol_d);
+        self.apply_shipping_fee(&mut o_sh, &weights).await?;
+        let ms_promo = self.load_promotions(coupons).await?;
         let o_currency =
             Self::snapshot_currencies(self.repo_currex.as_ref(), currency_buyer, &ms_price)
                 .await
@@ -116,16 +160,19 @@ impl CreateOrderUseCase {
                     app_log_event!(logctx_p, AppLogLevel::ERROR, "error: {:?}", es);
                     CreateOrderUsKsErr::Server(es)
                 })?;
-        let o_items = Self::validate_orderline(ms_policy, ms_price, ol_d)?;
+        let mut o_items =
+            Self::validate_orderline(ms_policy, ms_price, &ms_promo.promotions, ol_d)?;
+        self.apply_tax(&mut o_items, &o_sh, &o_bl).await?;
+        self.rank_warehouses(&mut o_items, &o_sh).await?;
         let oid = OrderLineModel::generate_order_id(app_meta::MACHINE_CODE);
         let timenow = LocalTime::now().fixed_offset();
         let usr_id = self.auth_claim.profile;
         let args = (oid, usr_id, timenow, o_currency, o_items);
-        let ol_set = OrderLineModelSet::try_from(args).map_err(Self::handle_toplvl_error)?;
-        // repository implementation should treat order-line reservation and
-        // stock-level update as a single atomic operation
-        self.try_reserve_stock(&ol_set).await?;
-
+        let mut ol_set = OrderLineModelSet::try_from(args).map_err(Self::handle_toplvl_error)?;
+        // repository implementation should treat order-line reservation, stock-level
+        // update and promotion usage count as a single atomic operation
+        self.try_reserve_stock(&mut ol_set).await?;
+        let sh_opts = o_sh.option.clone();
         // Contact info might be lost after order lines were saved, if power outage happenes
         // at here. TODO: Improve the code here
         self.repo_order
@@ -135,7 +182,8 @@ impl CreateOrderUseCase {
                 app_log_event!(logctx_p, AppLogLevel::ERROR, "repo-fail-save: {e}");
                 CreateOrderUsKsErr::Server(vec![e])
             })?;
-        let resp = OrderCreateRespOkDto::try_from(ol_set).map_err(CreateOrderUsKsErr::Server)?;
+        let resp = OrderCreateRespOkDto::try_from((ol_set, sh_opts))
+            .map_err(CreateOrderUsKsErr::Server)?;
         Ok(resp)
     } // end of fn execute
 
@@ -236,7 +284,13 @@ impl CreateOrderUseCase {
         &self,
         data: &[OrderLineRsvReqDto],
     ) -> DefaultResult<(ProductPolicyModelSet, Vec<ProductPriceModelSet>), CreateOrderUsKsErr> {
-        let req_ids_policy = data.iter().map(|d| d.product_id).collect::<Vec<u64>>();
+        let req_ids_policy = data
+            .iter()
+            .map(|d| BaseProductIdentity {
+                store_id: d.seller_id,
+                product_id: d.product_id,
+            })
+            .collect::<Vec<_>>();
         let req_ids_price = data
             .iter()
             .map(|d| (d.seller_id, d.product_id))
@@ -276,6 +330,115 @@ impl CreateOrderUseCase {
         }
     } // end of load_product_properties
 
+    async fn load_promotions(
+        &self,
+        mut codes: Vec<String>,
+    ) -> DefaultResult<PromotionModelSet, CreateOrderUsKsErr> {
+        codes.sort();
+        codes.dedup();
+        if codes.is_empty() {
+            return Ok(PromotionModelSet { promotions: vec![] });
+        }
+        let ms = self.repo_promo.fetch(codes.clone()).await.map_err(|e| {
+            let logctx_p = self.glb_state.log_context();
+            app_log_event!(logctx_p, AppLogLevel::ERROR, "promotion: {e}");
+            CreateOrderUsKsErr::Server(vec![e])
+        })?;
+        let timenow = LocalTime::now().fixed_offset();
+        ms.check_redeemable(&codes, timenow)
+            .map_err(Self::handle_coupon_error)?;
+        Ok(ms)
+    } // end of fn load_promotions
+
+    // tax depends on the shipping address, or the billing address if buyer
+    // picks up the items without delivery, no tax is charged if both are absent
+    async fn apply_tax(
+        &self,
+        lines: &mut [OrderLineModel],
+        shipping: &ShippingModel,
+        billing: &BillingModel,
+    ) -> DefaultResult<(), CreateOrderUsKsErr> {
+        let addr = match shipping.address.as_ref().or(billing.address.as_ref()) {
+            Some(v) => v,
+            None => return Ok(()),
+        };
+        let store_ids = lines.iter().map(|l| l.id().store_id()).collect::<Vec<_>>();
+        let (country, region) = (addr.country.clone(), addr.region.clone());
+        let ms = self
+            .repo_tax
+            .fetch(country, region, store_ids)
+            .await
+            .map_err(|e| {
+                let logctx_p = self.glb_state.log_context();
+                app_log_event!(logctx_p, AppLogLevel::ERROR, "tax-rate: {e}");
+                CreateOrderUsKsErr::Server(vec![e])
+            })?;
+        OrderLineModel::apply_tax(lines, &ms, addr);
+        Ok(())
+    } // end of fn apply_tax
+
+    async fn apply_shipping_fee(
+        &self,
+        shipping: &mut ShippingModel,
+        weights: &HashMap<u32, u32>,
+    ) -> DefaultResult<(), CreateOrderUsKsErr> {
+        if shipping.option.is_empty() {
+            return Ok(());
+        }
+        let store_ids = shipping.option.iter().map(|o| o.seller_id).collect();
+        let ms = self.repo_ship.fetch(store_ids).await.map_err(|e| {
+            let logctx_p = self.glb_state.log_context();
+            app_log_event!(logctx_p, AppLogLevel::ERROR, "shipping-method: {e}");
+            CreateOrderUsKsErr::Server(vec![e])
+        })?;
+        let addr = shipping.address.as_ref();
+        ShippingOptionModel::apply_fee(&mut shipping.option, &ms, addr, weights).map_err(|es| {
+            let error = OrderCreateRespErrorDto {
+                shipping: Some(ShippingErrorDto {
+                    contact: None,
+                    address: None,
+                    option: Some(es),
+                }),
+                ..Default::default()
+            };
+            CreateOrderUsKsErr::ReqContent(Box::new(error))
+        })
+    } // end of fn apply_shipping_fee
+
+    // without shipping address, the items are reserved from any warehouse
+    // which has enough stock
+    async fn rank_warehouses(
+        &self,
+        lines: &mut [OrderLineModel],
+        shipping: &ShippingModel,
+    ) -> DefaultResult<(), CreateOrderUsKsErr> {
+        let addr = match shipping.address.as_ref() {
+            Some(v) => v,
+            None => return Ok(()),
+        };
+        let store_ids = lines.iter().map(|l| l.id().store_id()).collect::<Vec<_>>();
+        let ms = self
+            .repo_order
+            .stock()
+            .fetch_warehouses(store_ids)
+            .await
+            .map_err(|e| {
+                let logctx_p = self.glb_state.log_context();
+                app_log_event!(logctx_p, AppLogLevel::ERROR, "warehouse: {e}");
+                CreateOrderUsKsErr::Server(vec![e])
+            })?;
+        OrderLineModel::rank_warehouses(lines, &ms, addr);
+        Ok(())
+    } // end of fn rank_warehouses
+
+    fn handle_coupon_error(es: Vec<CouponErrorDto>) -> CreateOrderUsKsErr {
+        let error = OrderCreateRespErrorDto {
+            coupons: Some(es),
+            ..Default::default()
+        };
+        CreateOrderUsKsErr::ReqContent(Box::new(error))
+    }
+
     pub async fn snapshot_currencies(
         repo_currex_p: &dyn AbsCurrencyRepo,
         label_buyer: CurrencyDto,
@@ -298,6 +461,7 @@ impl CreateOrderUseCase {
     pub fn validate_orderline(
         ms_policy: ProductPolicyModelSet,
         ms_price: Vec<ProductPriceModelSet>,
+        promos: &[PromotionModel],
         data: Vec<OrderLineRsvReqDto>,
     ) -> DefaultResult<Vec<OrderLineModel>, CreateOrderUsKsErr> {
         let (mut client_errors, mut server_errors) = (vec![], vec![]);
@@ -307,12 +471,12 @@ impl CreateOrderUseCase {
                 let result1 = ms_policy
                     .policies
                     .iter()
-                    .find(|m| m.product_id == d.product_id);
+                    .find(|m| m.store_id == d.seller_id && m.product_id == d.product_id);
                 let result2 = ms_price.iter().find_map(|ms| ms.find_product(&d));
                 let (plc_nonexist, price_nonexist) = (result1.is_none(), result2.is_none());
                 if let (Some(plc), Some(price)) = (result1, result2) {
                     let (seller_id, product_id, req_qty) = (d.seller_id, d.product_id, d.quantity);
-                    OrderLineModel::try_from(d, plc, price)
+                    OrderLineModel::try_from(d, plc, price, promos)
                         .map_err(|e| {
                             if e.code == AppErrorCode::ExceedingMaxLimit {
                                 let rsv_limit = GenericRangeErrorDto {
@@ -375,13 +539,14 @@ impl CreateOrderUseCase {
             billing: None,
             shipping: None,
             quota_olines: None,
+            coupons: None,
         };
         CreateOrderUsKsErr::ReqContent(Box::new(error))
     }
 
     async fn try_reserve_stock(
         &self,
-        req: &OrderLineModelSet,
+        req: &mut OrderLineModelSet,
     ) -> DefaultResult<(), CreateOrderUsKsErr> {
         let logctx_p = self.glb_state.log_context().clone();
         let repo_st = self.repo_order.stock();
@@ -406,9 +571,10 @@ impl CreateOrderUseCase {
 
     fn try_reserve_stock_cb(
         ms: &mut StockLevelModelSet,
-        req: &OrderLineModelSet,
+        policies: &StockAllocPolicyModelSet,
+        req: &mut OrderLineModelSet,
     ) -> AppStockRepoReserveReturn {
-        let result = ms.try_reserve(req);
+        let result = ms.try_reserve(req, policies);
         if result.is_empty() {
             Ok(())
         } else {
@@ -427,7 +593,15 @@ impl OrderReplicaPaymentUseCase {
         // TODO, lock billing instance so customers are no longer able to update
         let usr_id = self.repo.owner_id(oid.as_str()).await?;
         let billing = self.repo.fetch_billing(oid.clone()).await?;
-        OrderLineModelSet::replica_paym_dto(oid, usr_id, olines, currency_m, billing)
+        let shipping = self.repo.fetch_shipping(oid.clone()).await?;
+        OrderLineModelSet::replica_paym_dto(
+            oid,
+            usr_id,
+            olines,
+            currency_m,
+            billing,
+            shipping.option,
+        )
     }
 } // end of impl OrderReplicaPaymentUseCase
 
@@ -642,14 +816,35 @@ impl OrderDiscardUnpaidItemsUseCase {
                 Ok(()) // all items have been paid, nothing to discard for now.
             } else {
                 let st_repo = o_repo.stock();
+                let voided = unpaid_lines
+                    .iter()
+                    .filter(|m| m.qty.paid == 0)
+                    .copied()
+                    .collect::<Vec<_>>();
+                // backordered items were never taken from stock
                 let items = unpaid_lines
                     .into_iter()
                     .map(InventoryEditStockLevelDto::from)
-                    .collect();
-                let data = StockLevelReturnDto { items, order_id };
-                let _return_result = st_repo.try_return(Self::read_stocklvl_cb, data).await?;
-                Ok(()) // TODO, logging the stock-return result, the result may not be able
-                       // to pass to the output of the method `fetch_lines_by_rsvtime`
+                    .filter(|d| d.qty_add > 0)
+                    .collect::<Vec<_>>();
+                if !items.is_empty() {
+                    let data = StockLevelReturnDto {
+                        items,
+                        order_id: order_id.clone(),
+                    };
+                    let _return_result = st_repo.try_return(Self::read_stocklvl_cb, data).await?;
+                    // TODO, logging the stock-return result, the result may not be able
+                    // to pass to the output of the method `fetch_lines_by_rsvtime`
+                }
+                if !voided.is_empty() {
+                    let all_lines = o_repo.fetch_all_lines(order_id).await?;
+                    let time_now = LocalTime::now().fixed_offset();
+                    let codes = OrderLineModel::released_promotions(&voided, &all_lines, time_now);
+                    if !codes.is_empty() {
+                        st_repo.release_promotion_usage(codes).await?;
+                    }
+                }
+                Ok(())
             }
         }; // lifetime of the Future trait object must outlive `'static`
         Box::pin(fut)
@@ -694,7 +889,8 @@ impl ReturnLinesReqUseCase {
             .fetch_lines_by_pid(oid.as_str(), pids.clone())
             .await?;
         let o_returned = self.or_repo.fetch_by_pid(oid.as_str(), pids).await?;
-        match OrderReturnModel::filter_requests(data, o_lines, o_returned) {
+        let o_shipments = self.sh_repo.fetch_by_oid(oid.as_str()).await?;
+        match OrderReturnModel::filter_requests(data, o_lines, o_returned, o_shipments) {
             Ok(modified) => {
                 let _num = self.or_repo.create(oid.as_str(), modified).await?;
                 Ok(ReturnLinesReqUcOutput::Success)
@@ -703,3 +899,183 @@ impl ReturnLinesReqUseCase {
         }
     }
 } // end of impl ReturnLinesReqUseCase
+
+impl ListOwnedOrdersUseCase {
+    pub async fn execute(
+        self,
+        req: OrderListReqQuery,
+    ) -> DefaultResult<ListOwnedOrdersUcOutput, AppError> {
+        let max_days = Duration::days(hard_limit::MAX_DAYS_ORDER_HISTORY_QUERY);
+        let t_end = req.end.unwrap_or(LocalTime::now().fixed_offset());
+        let t_start = req.start.unwrap_or(t_end - max_days);
+        if t_start > t_end || (t_end - t_start) > max_days {
+            return Ok(ListOwnedOrdersUcOutput::InvalidTimeRange);
+        }
+        let page = req.page.unwrap_or(1);
+        let size = req.size.unwrap_or(hard_limit::MAX_ORDERS_PER_PAGE);
+        if page == 0 || size == 0 || size > hard_limit::MAX_ORDERS_PER_PAGE {
+            return Ok(ListOwnedOrdersUcOutput::InvalidPagination);
+        }
+        let usr_id = self.authed_claim.profile;
+        let offset = (page as usize - 1) * size as usize;
+        let (total, owned) = self
+            .repo
+            .fetch_ids_by_owner(usr_id, t_start, t_end, offset, size as usize)
+            .await?;
+        let orders = owned
+            .into_iter()
+            .map(|(order_id, ctime)| OrderSummaryDto {
+                order_id,
+                time: ctime.to_rfc3339(),
+            })
+            .collect::<Vec<_>>();
+        let resp = OrderListRespDto {
+            total,
+            page,
+            size,
+            orders,
+        };
+        Ok(ListOwnedOrdersUcOutput::Success(resp))
+    } // end of fn execute
+} // end of impl ListOwnedOrdersUseCase
+
+impl ReadOrderInfoUseCase {
+    pub async fn execute(
+        self,
+        oid: String,
+        flag: OrderInfoReadReqFlag,
+    ) -> DefaultResult<ReadOrderInfoUcOutput, AppError> {
+        let o_usr_id = match self.repo.owner_id(oid.as_str()).await {
+            Ok(v) => v,
+            Err(e) => {
+                return match &e.code {
+                    AppErrorCode::InvalidInput
+                    | AppErrorCode::IOerror(std::io::ErrorKind::NotFound) => {
+                        Ok(ReadOrderInfoUcOutput::NotFound)
+                    }
+                    _others => Err(e),
+                };
+            }
+        };
+        if o_usr_id != self.authed_claim.profile {
+            return Ok(ReadOrderInfoUcOutput::InvalidOwner);
+        }
+        let load_lines = flag.lines.unwrap_or(true);
+        let ctime = self.repo.created_time(oid.as_str()).await?;
+        let currency = self.repo.currency_exrates(oid.as_str()).await?;
+        let lines = if load_lines {
+            self.repo.fetch_all_lines(oid.clone()).await?
+        } else {
+            Vec::new()
+        };
+        let billing = if flag.billing.unwrap_or(true) {
+            Some(self.repo.fetch_billing(oid.clone()).await?)
+        } else {
+            None
+        };
+        let shipping = if flag.shipping.unwrap_or(true) {
+            Some(self.repo.fetch_shipping(oid.clone()).await?)
+        } else {
+            None
+        };
+        let args = (oid, o_usr_id, ctime, currency, lines);
+        let ol_set = OrderLineModelSet::try_from_repo(args).map_err(|es| {
+            let detail = es
+                .into_iter()
+                .map(|e| e.to_string())
+                .collect::<Vec<_>>()
+                .join(", ");
+            AppError {
+                code: AppErrorCode::DataCorruption,
+                detail: Some(detail),
+            }
+        })?;
+        let resp = ol_set.into_read_dto(load_lines, billing, shipping)?;
+        Ok(ReadOrderInfoUcOutput::Success(Box::new(resp)))
+    } // end of fn execute
+} // end of impl ReadOrderInfoUseCase
+
+impl CancelLinesReqUseCase {
+    pub async fn execute(
+        self,
+        oid: String,
+        data: Vec<OrderLineCancelReqDto>,
+    ) -> DefaultResult<CancelLinesReqUcOutput, AppError> {
+        if data.is_empty() {
+            return Ok(CancelLinesReqUcOutput::InvalidRequest(Vec::new()));
+        }
+        let o_usr_id = match self.repo.owner_id(oid.as_str()).await {
+            Ok(v) => v,
+            Err(e) => {
+                return match &e.code {
+                    AppErrorCode::InvalidInput
+                    | AppErrorCode::IOerror(std::io::ErrorKind::NotFound) => {
+                        Ok(CancelLinesReqUcOutput::NotFound)
+                    }
+                    _others => Err(e),
+                };
+            }
+        };
+        if o_usr_id != self.authed_claim.profile {
+            return Ok(CancelLinesReqUcOutput::InvalidOwner);
+        }
+        let pids = data
+            .iter()
+            .map(OrderLineIdentity::from)
+            .collect::<Vec<OrderLineIdentity>>();
+        let cancel = OrderLineCancelModel {
+            usr_id: self.authed_claim.profile,
+            time: LocalTime::now().fixed_offset(),
+        };
+        let time_cancel = cancel.time;
+        // reserved items of the cancelled lines go back to stock in the same
+        // operation, the cancellation is discarded if the stock cannot be returned
+        let errors = self
+            .repo
+            .stock()
+            .try_cancel_return(
+                oid.as_str(),
+                pids.clone(),
+                cancel,
+                OrderLineModel::cancel_unpaid,
+                Self::read_stocklvl_cb,
+            )
+            .await?;
+        if !errors.is_empty() {
+            return Ok(CancelLinesReqUcOutput::InvalidRequest(errors));
+        }
+        // the lines have been cancelled, failing to give back the promotion usage
+        // should not be reported to the client
+        if let Err(e) = self.release_promotions(oid, pids, time_cancel).await {
+            let logctx = &self.logctx;
+            app_log_event!(logctx, AppLogLevel::ERROR, "promotion-release: {e}");
+        }
+        Ok(CancelLinesReqUcOutput::Success)
+    } // end of fn execute
+
+    async fn release_promotions(
+        &self,
+        oid: String,
+        pids: Vec<OrderLineIdentity>,
+        time_now: DateTime<FixedOffset>,
+    ) -> DefaultResult<(), AppError> {
+        let all_lines = self.repo.fetch_all_lines(oid).await?;
+        let voided = all_lines
+            .iter()
+            .filter(|m| pids.contains(m.id()))
+            .collect::<Vec<_>>();
+        let codes = OrderLineModel::released_promotions(&voided, &all_lines, time_now);
+        if codes.is_empty() {
+            Ok(())
+        } else {
+            self.repo.stock().release_promotion_usage(codes).await
+        }
+    }
+
+    fn read_stocklvl_cb(
+        ms: &mut StockLevelModelSet,
+        data: StockLevelReturnDto,
+    ) -> Vec<StockReturnErrorDto> {
+        ms.return_across_expiry(data)
+    }
+} // end of impl CancelLinesReqUseCase
@@ -0,0 +1,181 @@
+use std::boxed::Box;
+use std::result::Result as DefaultResult;
+use std::sync::Arc;
+
+use chrono::Local as LocalTime;
+
+use ecommerce_common::error::AppErrorCode;
+use ecommerce_common::logging::{app_log_event, AppLogContext, AppLogLevel};
+
+use crate::api::web::dto::{
+    ShipmentCreateReqDto, ShipmentErrorDto, ShipmentReadDto, ShipmentUpdateReqDto,
+};
+use crate::error::AppError;
+use crate::model::ShipmentModel;
+use crate::repository::{AbsOrderRepo, AbsShipmentRepo};
+use crate::rpc::AbstractRpcContext;
+use crate::{AppAuthPermissionCode, AppAuthedClaim};
+
+use super::VerifyStoreStaffUseCase;
+
+// another shipment of the same order may be created concurrently, the request
+// is validated again with the latest shipments
+const NUM_CREATE_ATTEMPTS: usize = 3;
+
+pub enum ShipmentUcOutput {
+    Success(ShipmentReadDto),
+    PermissionDeny,
+    NotFound,
+    InvalidRequest(ShipmentErrorDto),
+}
+
+pub enum ReadShipmentsUcOutput {
+    Success(Vec<ShipmentReadDto>),
+    InvalidOwner,
+}
+
+pub struct CreateShipmentUseCase {
+    pub authed_claim: AppAuthedClaim,
+    pub o_repo: Box<dyn AbsOrderRepo>,
+    pub sh_repo: Box<dyn AbsShipmentRepo>,
+    pub rpc_ctx: Arc<Box<dyn AbstractRpcContext>>,
+    pub logctx: Arc<AppLogContext>,
+}
+
+pub struct UpdateShipmentUseCase {
+    pub authed_claim: AppAuthedClaim,
+    pub sh_repo: Box<dyn AbsShipmentRepo>,
+    pub rpc_ctx: Arc<Box<dyn AbstractRpcContext>>,
+    pub logctx: Arc<AppLogContext>,
+}
+
+pub struct ReadShipmentsUseCase {
+    pub authed_claim: AppAuthedClaim,
+    pub o_repo: Box<dyn AbsOrderRepo>,
+    pub sh_repo: Box<dyn AbsShipmentRepo>,
+}
+
+// the user has to be staff of the seller's store
+async fn verify_seller_staff(
+    authed_claim: &AppAuthedClaim,
+    seller_id: u32,
+    rpc_ctx: Arc<Box<dyn AbstractRpcContext>>,
+    logctx: Arc<AppLogContext>,
+) -> DefaultResult<bool, AppError> {
+    if !authed_claim.contain_permission(AppAuthPermissionCode::can_manage_shipment) {
+        return Ok(false);
+    }
+    let verifier = VerifyStoreStaffUseCase {
+        rpc_ctx,
+        logctx: logctx.clone(),
+    };
+    let result = verifier
+        .execute(authed_claim.profile, vec![seller_id])
+        .await;
+    if let Err(e) = result.as_ref() {
+        app_log_event!(logctx, AppLogLevel::ERROR, "store-staff-check:{:?}", e);
+    }
+    result
+}
+
+impl CreateShipmentUseCase {
+    pub async fn execute(
+        self,
+        oid: String,
+        req: ShipmentCreateReqDto,
+    ) -> DefaultResult<ShipmentUcOutput, AppError> {
+        let seller_id = req.seller_id;
+        let (rpc_ctx, logctx) = (self.rpc_ctx.clone(), self.logctx.clone());
+        if !verify_seller_staff(&self.authed_claim, seller_id, rpc_ctx, logctx).await? {
+            return Ok(ShipmentUcOutput::PermissionDeny);
+        }
+        let o_lines = self
+            .o_repo
+            .fetch_all_lines(oid.clone())
+            .await?
+            .into_iter()
+            .filter(|ol| ol.id().store_id() == seller_id)
+            .collect::<Vec<_>>();
+        if o_lines.is_empty() {
+            return Ok(ShipmentUcOutput::NotFound);
+        }
+        let logctx = &self.logctx;
+        for _ in 0..NUM_CREATE_ATTEMPTS {
+            let saved = self.sh_repo.fetch_by_oid(oid.as_str()).await?;
+            let time_now = LocalTime::now().fixed_offset();
+            let obj = match ShipmentModel::try_create(req.clone(), &o_lines, &saved, time_now) {
+                Ok(v) => v,
+                Err(e) => return Ok(ShipmentUcOutput::InvalidRequest(e)),
+            };
+            let seq = obj.seq;
+            let resp = ShipmentReadDto::from(obj.clone());
+            if self.sh_repo.create(oid.as_str(), obj).await? {
+                app_log_event!(
+                    logctx,
+                    AppLogLevel::INFO,
+                    "oid:{oid}, seq:{seq}, seller:{seller_id}"
+                );
+                return Ok(ShipmentUcOutput::Success(resp));
+            }
+            app_log_event!(
+                logctx,
+                AppLogLevel::WARNING,
+                "oid:{oid}, seq:{seq}, conflict"
+            );
+        }
+        Err(AppError {
+            code: AppErrorCode::AcquireLockFailure,
+            detail: Some(format!("shipment-create, oid:{oid}")),
+        })
+    } // end of fn execute
+}
+
+impl UpdateShipmentUseCase {
+    pub async fn execute(
+        self,
+        oid: String,
+        seq: u16,
+        req: ShipmentUpdateReqDto,
+    ) -> DefaultResult<ShipmentUcOutput, AppError> {
+        let seller_id = req.seller_id;
+        let (rpc_ctx, logctx) = (self.rpc_ctx.clone(), self.logctx.clone());
+        if !verify_seller_staff(&self.authed_claim, seller_id, rpc_ctx, logctx).await? {
+            return Ok(ShipmentUcOutput::PermissionDeny);
+        }
+        let saved = self.sh_repo.fetch_by_oid(oid.as_str()).await?;
+        let mut obj = match saved
+            .into_iter()
+            .find(|m| m.seq == seq && m.seller_id == seller_id)
+        {
+            Some(v) => v,
+            None => return Ok(ShipmentUcOutput::NotFound),
+        };
+        let time_now = LocalTime::now().fixed_offset();
+        if let Err(e) = obj.update(req, time_now) {
+            return Ok(ShipmentUcOutput::InvalidRequest(e));
+        }
+        let status = obj.status;
+        let resp = ShipmentReadDto::from(obj.clone());
+        self.sh_repo.update(oid.as_str(), obj).await?;
+        let logctx = &self.logctx;
+        app_log_event!(
+            logctx,
+            AppLogLevel::INFO,
+            "oid:{oid}, seq:{seq}, seller:{seller_id}, status:{:?}",
+            status
+        );
+        Ok(ShipmentUcOutput::Success(resp))
+    } // end of fn execute
+}
+
+impl ReadShipmentsUseCase {
+    pub async fn execute(self, oid: String) -> DefaultResult<ReadShipmentsUcOutput, AppError> {
+        let o_usr_id = self.o_repo.owner_id(oid.as_str()).await?;
+        if o_usr_id != self.authed_claim.profile {
+            return Ok(ReadShipmentsUcOutput::InvalidOwner);
+        }
+        let ms = self.sh_repo.fetch_by_oid(oid.as_str()).await?;
+        let out = ms.into_iter().map(ShipmentReadDto::from).collect();
+        Ok(ReadShipmentsUcOutput::Success(out))
+    }
+}
@@ -1,29 +1,56 @@
 mod currency;
 mod edit_product_policy;
 mod edit_product_price;
+mod edit_promotion;
+mod edit_shipping_method;
+mod edit_tax_rate;
 mod manage_cart;
 mod manage_order;
+mod manage_shipment;
+mod stock_alert;
 mod stock_level;
+mod verify_store;
 
 use std::boxed::Box;
+use std::future::Future;
 use std::result::Result as DefaultResult;
 use std::sync::Arc;
 
-pub use currency::CurrencyRateRefreshUseCase;
+use ecommerce_common::logging::{app_log_event, AppLogContext, AppLogLevel};
+
+pub use currency::{CurrencyRateHistoryUseCase, CurrencyRateRefreshUseCase};
 pub use edit_product_policy::{
-    EditProductPolicyResult, EditProductPolicyUseCase, ProductInfoReq, ProductInfoResp,
+    DeleteProductPolicyUseCase, EditProductPolicyResult, EditProductPolicyUseCase, ProductInfoReq,
+    ProductInfoResp,
 };
 pub use edit_product_price::EditProductPriceUseCase;
+pub use edit_promotion::{EditPromotionUsKsResult, EditPromotionUseCase};
+pub use edit_shipping_method::{
+    EditShippingMethodUsKsResult, EditShippingMethodUseCase, ReadShippingMethodUseCase,
+};
+pub use edit_tax_rate::EditTaxRateUseCase;
 pub(crate) use manage_cart::{
-    DiscardCartUsKsResult, DiscardCartUseCase, ModifyCartLineUseCase, ModifyCartUsKsResult,
-    RetrieveCartUsKsResult, RetrieveCartUseCase,
+    CheckoutCartUsKsResult, CheckoutCartUseCase, DiscardCartUsKsResult, DiscardCartUseCase,
+    EditCartCollabUsKsResult, EditCartCollaboratorUseCase, ModifyCartLineUseCase,
+    ModifyCartUsKsResult, QuoteCartUsKsResult, QuoteCartUseCase, RetrieveCartUsKsResult,
+    RetrieveCartUseCase,
 };
 pub use manage_order::{
-    CreateOrderUsKsErr, CreateOrderUseCase, OrderDiscardUnpaidItemsUseCase,
+    CancelLinesReqUcOutput, CancelLinesReqUseCase, CreateOrderUsKsErr, CreateOrderUseCase,
+    ListOwnedOrdersUcOutput, ListOwnedOrdersUseCase, OrderDiscardUnpaidItemsUseCase,
     OrderPaymentUpdateUseCase, OrderReplicaInventoryUseCase, OrderReplicaPaymentUseCase,
-    OrderReplicaRefundUseCase, ReturnLinesReqUcOutput, ReturnLinesReqUseCase,
+    OrderReplicaRefundUseCase, ReadOrderInfoUcOutput, ReadOrderInfoUseCase, ReturnLinesReqUcOutput,
+    ReturnLinesReqUseCase,
+};
+pub use manage_shipment::{
+    CreateShipmentUseCase, ReadShipmentsUcOutput, ReadShipmentsUseCase, ShipmentUcOutput,
+    UpdateShipmentUseCase,
+};
+pub use stock_alert::{EditStockAlertThresholdUseCase, StockAlertCheckUseCase};
+pub use stock_level::{
+    ReadStockLevelUcOutput, ReadStockLevelUseCase, StockLevelEditUcOutput, StockLevelUseCase,
 };
-pub use stock_level::StockLevelUseCase;
+pub use verify_store::VerifyStoreStaffUseCase;
 
 use crate::error::AppError;
 use crate::rpc::{AbsRpcClientCtx, AbstractRpcContext, AppRpcClientReqProperty, AppRpcReply};
@@ -50,3 +77,41 @@ pub async fn initiate_rpc_request(
     let mut evt = client.send_request(prop).await?;
     evt.receive_response().await
 }
+
+// convert the items received from other services to models then save them
+// through the given repository function, errors from either step are logged
+// with number of the items
+async fn try_convert_save<D, M, C, S, F>(
+    data: Vec<D>,
+    logctx: Arc<AppLogContext>,
+    convert: C,
+    save: S,
+) -> DefaultResult<(), AppError>
+where
+    C: FnOnce(Vec<D>) -> DefaultResult<M, AppError>,
+    S: FnOnce(M) -> F,
+    F: Future<Output = DefaultResult<(), AppError>>,
+{
+    let num_items = data.len();
+    let result = match convert(data) {
+        Ok(ms) => save(ms).await,
+        Err(e) => Err(e),
+    };
+    if let Err(e) = &result {
+        app_log_event!(
+            logctx,
+            AppLogLevel::ERROR,
+            "detail:{}, num_items:{}",
+            e,
+            num_items
+        );
+    }
+    result
+}
+
+fn try_into_models<D, M>(data: Vec<D>) -> DefaultResult<Vec<M>, AppError>
+where
+    M: TryFrom<D, Error = AppError>,
+{
+    data.into_iter().map(M::try_from).collect()
+}
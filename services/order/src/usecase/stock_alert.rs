@@ -0,0 +1,147 @@
+use std::boxed::Box;
+use std::result::Result as DefaultResult;
+use std::sync::Arc;
+use std::vec::Vec;
+
+use chrono::{DateTime, FixedOffset, Local};
+
+use ecommerce_common::adapter::rpc::py_celery::serialize_msg_body;
+use ecommerce_common::logging::{app_log_event, AppLogContext, AppLogLevel};
+
+use crate::api::rpc::dto::{StockAlertDto, StockAlertPublishDto, StockAlertThresholdDto};
+use crate::error::AppError;
+use crate::model::{StockAlertModel, StockAlertThresholdModel};
+use crate::repository::{AbsOrderRepo, AbsStockAlertRepo};
+use crate::rpc::{AbsRpcClientCtx, AbstractRpcContext, AppRpcClientReqProperty};
+
+use super::{try_convert_save, try_into_models};
+
+const RPC_ROUTE_PUBLISH_ALERT: &str = "rpc.storefront.stock_level_alert";
+
+pub struct EditStockAlertThresholdUseCase {}
+
+impl EditStockAlertThresholdUseCase {
+    pub async fn execute(
+        repo: Box<dyn AbsStockAlertRepo>,
+        data: Vec<StockAlertThresholdDto>,
+        logctx: Arc<AppLogContext>,
+    ) -> DefaultResult<(), AppError> {
+        let convert = try_into_models::<_, StockAlertThresholdModel>;
+        try_convert_save(data, logctx, convert, |ms| repo.save_thresholds(ms)).await
+    }
+} // end of impl EditStockAlertThresholdUseCase
+
+pub struct StockAlertCheckUseCase {
+    pub logctx: Arc<AppLogContext>,
+    pub alert_repo: Box<dyn AbsStockAlertRepo>,
+    pub order_repo: Box<dyn AbsOrderRepo>,
+    pub rpc_ctx: Arc<Box<dyn AbstractRpcContext>>,
+}
+
+impl StockAlertCheckUseCase {
+    // scan stock levels of all the stores which configured the thresholds, publish
+    // alerts which have not been sent yet, return total number of alerts published.
+    //
+    // Each alert is sent only once as long as the condition persists, once stock
+    // level recovers (e.g. more items added to the batch) the alert is removed from
+    // the sent list, so it can be raised again next time the threshold is crossed.
+    pub async fn execute(self, time_now: DateTime<FixedOffset>) -> DefaultResult<usize, AppError> {
+        let logctx = self.logctx.clone();
+        let thresholds = self.alert_repo.fetch_all_thresholds().await?;
+        let mut num_published = 0usize;
+        for threshold in thresholds {
+            let store_id = threshold.store_id;
+            match self.check_store(threshold, time_now).await {
+                Ok(n) => {
+                    num_published += n;
+                }
+                Err(e) => {
+                    app_log_event!(
+                        logctx,
+                        AppLogLevel::ERROR,
+                        "store:{}, detail:{:?}",
+                        store_id,
+                        e
+                    );
+                }
+            }
+        }
+        Ok(num_published)
+    }
+
+    async fn check_store(
+        &self,
+        threshold: StockAlertThresholdModel,
+        time_now: DateTime<FixedOffset>,
+    ) -> DefaultResult<usize, AppError> {
+        let logctx = &self.logctx;
+        let store_id = threshold.store_id;
+        let mset = self
+            .order_repo
+            .stock()
+            .fetch_by_store(store_id, Vec::new(), None, None)
+            .await?;
+        let detected = mset
+            .stores
+            .iter()
+            .find(|s| s.store_id == store_id)
+            .map(|s| threshold.detect(s, time_now.to_utc()))
+            .unwrap_or_default();
+        let sent = self.alert_repo.fetch_sent(store_id).await?;
+        let (still_sent, unsent) = StockAlertModel::filter_sent(detected, &sent);
+        if still_sent.len() != sent.len() {
+            // conditions of some previously sent alerts no longer hold
+            let (_, recovered) = StockAlertModel::filter_sent(sent, &still_sent);
+            self.alert_repo.discard_sent(store_id, recovered).await?;
+        }
+        // other replicas may check the same store concurrently, only the alerts
+        // claimed by this replica are published
+        let claimed = self.alert_repo.claim_sent(store_id, unsent).await?;
+        let num_claimed = claimed.len();
+        if num_claimed == 0 {
+            return Ok(0);
+        }
+        match self.publish(store_id, claimed.clone()).await {
+            Ok(()) => Ok(num_claimed),
+            Err(e) => {
+                // give up the claim, the alerts will be published in next round
+                app_log_event!(
+                    logctx,
+                    AppLogLevel::WARNING,
+                    "store:{}, num-unsent:{}, detail:{:?}",
+                    store_id,
+                    num_claimed,
+                    e
+                );
+                self.alert_repo.discard_sent(store_id, claimed).await?;
+                Ok(0)
+            }
+        }
+    } // end of fn check_store
+
+    async fn publish(
+        &self,
+        store_id: u32,
+        alerts: Vec<StockAlertModel>,
+    ) -> DefaultResult<(), AppError> {
+        let msg = StockAlertPublishDto {
+            store_id,
+            alerts: alerts.into_iter().map(StockAlertDto::from).collect(),
+        };
+        let msgbody = serialize_msg_body(msg).map_err(|(code, detail)| AppError {
+            code,
+            detail: Some(detail),
+        })?;
+        let props = AppRpcClientReqProperty {
+            msgbody,
+            correlation_id: None,
+            start_time: Local::now().fixed_offset(),
+            route: RPC_ROUTE_PUBLISH_ALERT.to_string(),
+        };
+        let client = AbsRpcClientCtx::acquire(self.rpc_ctx.as_ref(), 3u8).await?;
+        // no reply expected from the consumer, the alert is regarded as published
+        // once the message broker accepts it
+        let _client = client.send_request(props).await?;
+        Ok(())
+    }
+} // end of impl StockAlertCheckUseCase
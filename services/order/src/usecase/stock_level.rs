@@ -1,32 +1,62 @@
 use std::boxed::Box;
+use std::collections::HashMap;
 use std::result::Result as DefaultResult;
 use std::sync::Arc;
 
+use chrono::Local as LocalTime;
+
 use ecommerce_common::logging::{app_log_event, AppLogContext, AppLogLevel};
 
 use crate::api::rpc::dto::{
-    InventoryEditStockLevelDto, StockLevelPresentDto, StockLevelReturnDto, StockReturnErrorDto,
+    InventoryEditStockLevelDto, StockAllocPolicyDto, StockBackorderErrorDto, StockLevelPresentDto,
+    StockLevelReturnDto, StockReturnErrorDto, StockWarehouseDto,
 };
+use crate::api::web::dto::{StockLevelReadReqQuery, StockLevelReadRespDto};
+use crate::constant::hard_limit;
 use crate::error::AppError;
-use crate::model::{ProductStockIdentity, StockLevelModelSet};
-use crate::repository::AbsOrderRepo;
+use crate::model::{
+    OrderLineModelSet, ProductStockIdentity, StockAllocPolicyModel, StockAllocPolicyModelSet,
+    StockLevelModelSet, StockWarehouseModel, StoreStockModel,
+};
+use crate::repository::{AbsOrderRepo, AppStockRepoReserveReturn};
+use crate::rpc::AbstractRpcContext;
+use crate::{AppAuthPermissionCode, AppAuthedClaim};
+
+use super::{try_convert_save, try_into_models, VerifyStoreStaffUseCase};
 
 pub struct StockLevelUseCase {}
 
+// the stock levels are saved even if some backorders cannot be allocated
+pub struct StockLevelEditUcOutput {
+    pub levels: Vec<StockLevelPresentDto>,
+    pub backorder_errors: Vec<StockBackorderErrorDto>,
+}
+
 impl StockLevelUseCase {
     pub async fn try_edit(
         data: Vec<InventoryEditStockLevelDto>,
         repo: Box<dyn AbsOrderRepo>,
         logctx: Arc<AppLogContext>,
-    ) -> DefaultResult<Vec<StockLevelPresentDto>, AppError> {
+    ) -> DefaultResult<StockLevelEditUcOutput, AppError> {
         let ids = data
             .iter()
             .map(|d| ProductStockIdentity {
                 store_id: d.store_id,
                 product_id: d.product_id,
+                warehouse_id: d.warehouse_id,
                 expiry: d.expiry.into(),
             })
             .collect();
+        let mut restocked: HashMap<u32, Vec<u64>> = HashMap::new();
+        data.iter()
+            .filter(|d| d.qty_add > 0)
+            .map(|d| {
+                let pids = restocked.entry(d.store_id).or_default();
+                if !pids.contains(&d.product_id) {
+                    pids.push(d.product_id);
+                }
+            })
+            .count();
         let stockrepo = repo.stock();
         let saved = stockrepo.fetch(ids).await?;
         app_log_event!(
@@ -37,7 +67,61 @@ impl StockLevelUseCase {
         );
         let updated = saved.update(data)?;
         stockrepo.save(updated.clone()).await?;
-        Ok(updated.into())
+        let backorder_errors = Self::fulfil_backorders(repo.as_ref(), restocked, logctx).await;
+        Ok(StockLevelEditUcOutput {
+            levels: updated.into(),
+            backorder_errors,
+        })
+    }
+
+    // new stock is allocated to backordered order lines, earliest order first,
+    // failure in this step does not revert the stock level already saved
+    async fn fulfil_backorders(
+        repo: &dyn AbsOrderRepo,
+        restocked: HashMap<u32, Vec<u64>>,
+        logctx: Arc<AppLogContext>,
+    ) -> Vec<StockBackorderErrorDto> {
+        let stockrepo = repo.stock();
+        let mut errors = Vec::new();
+        for (store_id, product_ids) in restocked {
+            let ol_sets = match repo.fetch_backordered(store_id, product_ids).await {
+                Ok(v) => v,
+                Err(e) => {
+                    app_log_event!(logctx, AppLogLevel::ERROR, "store:{store_id}, {e}");
+                    errors.push(StockBackorderErrorDto {
+                        seller_id: store_id,
+                        order_id: None,
+                        detail: e.to_string(),
+                    });
+                    continue;
+                }
+            };
+            for mut ol_set in ol_sets {
+                let result = stockrepo
+                    .try_fulfil_backorder(Self::fulfil_backorder_cb, &mut ol_set)
+                    .await;
+                // the callback never reports client error
+                if let Err(Err(e)) = result {
+                    app_log_event!(logctx, AppLogLevel::ERROR, "oid:{}, {e}", ol_set.id());
+                    errors.push(StockBackorderErrorDto {
+                        seller_id: store_id,
+                        order_id: Some(ol_set.id().clone()),
+                        detail: e.to_string(),
+                    });
+                }
+            }
+        }
+        errors
+    }
+
+    fn fulfil_backorder_cb(
+        ms: &mut StockLevelModelSet,
+        policies: &StockAllocPolicyModelSet,
+        req: &mut OrderLineModelSet,
+    ) -> AppStockRepoReserveReturn {
+        let time_now = LocalTime::now().fixed_offset();
+        let _num_allocated = ms.fulfil_backorder(req, policies, time_now);
+        Ok(())
     }
 
     pub async fn try_return(
@@ -57,6 +141,26 @@ impl StockLevelUseCase {
         }
         result
     }
+    pub async fn try_edit_alloc_policy(
+        data: Vec<StockAllocPolicyDto>,
+        repo: Box<dyn AbsOrderRepo>,
+        logctx: Arc<AppLogContext>,
+    ) -> DefaultResult<(), AppError> {
+        let convert = try_into_models::<_, StockAllocPolicyModel>;
+        let stockrepo = repo.stock();
+        try_convert_save(data, logctx, convert, |ms| stockrepo.save_alloc_policy(ms)).await
+    }
+
+    pub async fn try_edit_warehouse(
+        data: Vec<StockWarehouseDto>,
+        repo: Box<dyn AbsOrderRepo>,
+        logctx: Arc<AppLogContext>,
+    ) -> DefaultResult<(), AppError> {
+        let convert = try_into_models::<_, StockWarehouseModel>;
+        let stockrepo = repo.stock();
+        try_convert_save(data, logctx, convert, |ms| stockrepo.save_warehouses(ms)).await
+    }
+
     fn read_stocklvl_cb(
         ms: &mut StockLevelModelSet,
         data: StockLevelReturnDto,
@@ -64,3 +168,91 @@ impl StockLevelUseCase {
         ms.return_by_expiry(data)
     }
 } // end of impl StockLevelUseCase
+
+pub enum ReadStockLevelUcOutput {
+    Success(StockLevelReadRespDto),
+    PermissionDeny,
+    InvalidProductId,
+    TooManyProducts,
+    InvalidExpiryRange,
+}
+
+pub struct ReadStockLevelUseCase {
+    pub authed_claim: AppAuthedClaim,
+    pub repo: Box<dyn AbsOrderRepo>,
+    pub rpc_ctx: Arc<Box<dyn AbstractRpcContext>>,
+    pub logctx: Arc<AppLogContext>,
+}
+
+impl ReadStockLevelUseCase {
+    pub async fn execute(
+        self,
+        store_id: u32,
+        req: StockLevelReadReqQuery,
+    ) -> DefaultResult<ReadStockLevelUcOutput, AppError> {
+        let perm_allowed = self
+            .authed_claim
+            .contain_permission(AppAuthPermissionCode::can_view_stock_level);
+        if !perm_allowed {
+            return Ok(ReadStockLevelUcOutput::PermissionDeny);
+        }
+        let StockLevelReadReqQuery {
+            product_ids,
+            expiry_after,
+            expiry_before,
+        } = req;
+        let product_ids = match product_ids {
+            Some(raw) => match Self::parse_product_ids(raw.as_str()) {
+                Some(v) => v,
+                None => return Ok(ReadStockLevelUcOutput::InvalidProductId),
+            },
+            None => Vec::new(),
+        };
+        if product_ids.len() > hard_limit::MAX_PRODUCTS_STOCK_LEVEL_READ {
+            return Ok(ReadStockLevelUcOutput::TooManyProducts);
+        }
+        if let (Some(t0), Some(t1)) = (expiry_after.as_ref(), expiry_before.as_ref()) {
+            if t0 > t1 {
+                return Ok(ReadStockLevelUcOutput::InvalidExpiryRange);
+            }
+        }
+        let verifier = VerifyStoreStaffUseCase {
+            rpc_ctx: self.rpc_ctx,
+            logctx: self.logctx.clone(),
+        };
+        let logctx = self.logctx;
+        match verifier
+            .execute(self.authed_claim.profile, vec![store_id])
+            .await
+        {
+            Ok(true) => {}
+            Ok(false) => return Ok(ReadStockLevelUcOutput::PermissionDeny),
+            Err(e) => {
+                app_log_event!(logctx, AppLogLevel::ERROR, "store-staff-check:{:?}", e);
+                return Err(e);
+            }
+        }
+        let mset = self
+            .repo
+            .stock()
+            .fetch_by_store(store_id, product_ids, expiry_after, expiry_before)
+            .await?;
+        let store = mset
+            .stores
+            .into_iter()
+            .find(|s| s.store_id == store_id)
+            .unwrap_or(StoreStockModel {
+                store_id,
+                products: Vec::new(),
+            });
+        Ok(ReadStockLevelUcOutput::Success(store.into()))
+    } // end of fn execute
+
+    fn parse_product_ids(raw: &str) -> Option<Vec<u64>> {
+        raw.split(',')
+            .map(|v| v.trim())
+            .filter(|v| !v.is_empty())
+            .map(|v| v.parse::<u64>().ok())
+            .collect()
+    }
+} // end of impl ReadStockLevelUseCase
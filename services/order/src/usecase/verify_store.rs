@@ -0,0 +1,94 @@
+use std::boxed::Box;
+use std::result::Result as DefaultResult;
+use std::sync::Arc;
+use std::vec::Vec;
+
+use chrono::{DateTime, FixedOffset, Local};
+
+use ecommerce_common::adapter::rpc::py_celery::{deserialize_reply, serialize_msg_body};
+use ecommerce_common::api::rpc::dto::{StoreProfileReplicaDto, StoreProfileReplicaReqDto};
+use ecommerce_common::error::AppErrorCode;
+use ecommerce_common::logging::{app_log_event, AppLogContext, AppLogLevel};
+
+use crate::error::AppError;
+use crate::rpc::{AbstractRpcContext, AppRpcClientReqProperty};
+
+use super::initiate_rpc_request;
+
+const RPC_ROUTE_STORE_PROFILE: &str = "rpc.storefront.get_profile";
+
+pub struct VerifyStoreStaffUseCase {
+    pub rpc_ctx: Arc<Box<dyn AbstractRpcContext>>,
+    pub logctx: Arc<AppLogContext>,
+}
+
+impl VerifyStoreStaffUseCase {
+    // return true only if the user is allowed to manage all the given stores, that
+    // is, the user is the supervisor or one of the staff whose employment period
+    // covers current time, in each of the active stores
+    pub async fn execute(
+        &self,
+        usr_id: u32,
+        mut store_ids: Vec<u32>,
+    ) -> DefaultResult<bool, AppError> {
+        if self.rpc_ctx.label() == "dummy" {
+            // pass, for mocking purpose, same as the product existence check
+            let logctx = &self.logctx;
+            app_log_event!(logctx, AppLogLevel::WARNING, "dummy-rpc-applied");
+            return Ok(true);
+        }
+        store_ids.sort();
+        store_ids.dedup();
+        let time_now = Local::now().fixed_offset();
+        for store_id in store_ids {
+            let profile = self.load_profile(store_id).await?;
+            if !Self::is_staff(&profile, usr_id, time_now) {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    async fn load_profile(&self, store_id: u32) -> DefaultResult<StoreProfileReplicaDto, AppError> {
+        let msgbody = serialize_msg_body(StoreProfileReplicaReqDto { store_id }).map_err(
+            |(code, detail)| AppError {
+                code,
+                detail: Some(detail),
+            },
+        )?;
+        let props = AppRpcClientReqProperty {
+            msgbody,
+            correlation_id: None,
+            start_time: Local::now().fixed_offset(),
+            route: RPC_ROUTE_STORE_PROFILE.to_string(),
+        };
+        let reply = initiate_rpc_request(self.rpc_ctx.clone(), props).await?;
+        deserialize_reply::<StoreProfileReplicaDto>(&reply.body).map_err(|(_code, detail)| {
+            let detail = format!("store:{}, {}", store_id, detail);
+            AppError {
+                code: AppErrorCode::RpcRemoteInvalidReply,
+                detail: Some(detail),
+            }
+        })
+    }
+
+    fn is_staff(
+        profile: &StoreProfileReplicaDto,
+        usr_id: u32,
+        time_now: DateTime<FixedOffset>,
+    ) -> bool {
+        if !profile.active {
+            false
+        } else if profile.supervisor_id == usr_id {
+            true
+        } else if let Some(staff) = profile.staff.as_ref() {
+            staff.iter().filter(|s| s.staff_id == usr_id).any(|s| {
+                let t0 = DateTime::parse_from_rfc3339(s.start_after.as_str());
+                let t1 = DateTime::parse_from_rfc3339(s.end_before.as_str());
+                matches!((t0, t1), (Ok(t0), Ok(t1)) if t0 <= time_now && time_now < t1)
+            })
+        } else {
+            false
+        }
+    }
+} // end of impl VerifyStoreStaffUseCase
@@ -12,7 +12,8 @@ use ecommerce_common::api::web::dto::{ContactErrorReason, PhoneNumNationErrorRea
 
 use order::api::rpc;
 use order::api::web::dto::{
-    OrderCreateReqData, OrderCreateRespErrorDto, OrderCreateRespOkDto, OrderEditReqData,
+    CartQuoteDto, OrderCreateReqData, OrderCreateRespErrorDto, OrderCreateRespOkDto,
+    OrderEditReqData,
 };
 use order::constant::{app_meta, hard_limit};
 use order::error::AppError;
@@ -248,6 +249,67 @@ async fn itest_return_olines_request(
     resp_body.into_data().unwrap().to_vec()
 } // end of fn itest_return_olines_request
 
+async fn itest_shipment_request(
+    srv: Arc<Mutex<WebServiceRoute>>,
+    uri: String,
+    method: &str,
+    req_body: JsnVal,
+    authed_claim: AppAuthedClaim,
+) -> JsnVal {
+    let mut req = Request::builder()
+        .uri(uri)
+        .method(method)
+        .header("content-type", "application/json")
+        .body(AxumBody::from(req_body.to_string()))
+        .unwrap();
+    let _ = req.extensions_mut().insert(authed_claim);
+    let mut response = TestWebServer::consume(&srv, req).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    TestWebServer::to_custom_type::<JsnVal>(response.body_mut())
+        .await
+        .unwrap()
+}
+
+// seller packs the given items in a new shipment, which is then delivered
+async fn itest_deliver_shipment(
+    cfg: Arc<AppConfig>,
+    srv: Arc<Mutex<WebServiceRoute>>,
+    oid: &str,
+    seller_id: u32,
+    lines: Vec<(u64, u16, u32)>,
+    mut authed_claim: AppAuthedClaim,
+) {
+    {
+        let perm = AppAuthClaimPermission {
+            app_code: app_meta::RESOURCE_QUOTA_AP_CODE,
+            codename: AppAuthPermissionCode::can_manage_shipment,
+        };
+        authed_claim.perms.push(perm);
+    }
+    let api_ver = cfg.api_server.listen.api_version.as_str();
+    let lines = lines
+        .into_iter()
+        .map(|(product_id, attr_set_seq, quantity)| {
+            serde_json::json!({
+                "product_id": product_id, "attr_set_seq": attr_set_seq, "quantity": quantity
+            })
+        })
+        .collect::<Vec<_>>();
+    let req_body = serde_json::json!({
+        "seller_id": seller_id, "carrier": "FedEx", "tracking_number": null, "lines": lines
+    });
+    let uri = format!("/{api_ver}/order/{oid}/shipments");
+    let claim = itest_clone_authed_claim(&authed_claim);
+    let created = itest_shipment_request(srv.clone(), uri, "POST", req_body, claim).await;
+    let seq = created.get("seq").unwrap().as_u64().unwrap();
+    let req_body = serde_json::json!({
+        "seller_id": seller_id, "status": "Delivered", "tracking_number": "FX-20340918"
+    });
+    let uri = format!("/{api_ver}/order/{oid}/shipment/{seq}");
+    let updated = itest_shipment_request(srv, uri, "PATCH", req_body, authed_claim).await;
+    assert_eq!(updated.get("status").unwrap().as_str(), Some("Delivered"));
+}
+
 #[tokio::test]
 async fn new_order_then_return() -> DefaultResult<(), AppError> {
     const FPATH_EDIT_PRODUCTPOLICY_OK: &str =
@@ -287,6 +349,23 @@ async fn new_order_then_return() -> DefaultResult<(), AppError> {
         itest_clone_authed_claim(&authed_claim),
     )
     .await?;
+    let mock_seller = 18830;
+    itest_update_payment_status(
+        shr_state.clone(),
+        oid.clone(),
+        Local::now().fixed_offset() + Duration::seconds(5),
+        vec![(mock_seller, 270118, 0, 2), (mock_seller, 270119, 0, 3)],
+    )
+    .await;
+    itest_deliver_shipment(
+        top_lvl_cfg.clone(),
+        srv.clone(),
+        oid.as_str(),
+        mock_seller,
+        vec![(270118, 0, 2), (270119, 0, 3)],
+        itest_clone_authed_claim(&authed_claim),
+    )
+    .await;
     let _ = itest_return_olines_request(
         top_lvl_cfg.clone(),
         srv.clone(),
@@ -809,6 +888,47 @@ async fn add_product_policy_request_error() -> DefaultResult<(), AppError> {
     Ok(())
 } // end of fn add_product_policy_request_error
 
+#[tokio::test]
+async fn edit_promotion_ok() -> DefaultResult<(), AppError> {
+    const FPATH_EDIT_PROMOTION_OK: &str = "/tests/integration/examples/promotion_edit_ok_1.json";
+    let shr_state = test_setup_shr_state()?;
+    let srv = TestWebServer::setup(shr_state.clone());
+    let top_lvl_cfg = shr_state.config();
+    let uri = format!("/{}/promotions", top_lvl_cfg.api_server.listen.api_version);
+    let req_body_template =
+        deserialize_json_template::<JsnVal>(&top_lvl_cfg.basepath, FPATH_EDIT_PROMOTION_OK)?;
+    let mut mock_auth_claim = setup_mock_authed_claim(18837);
+    // ---- subcase 1, missing permission ----
+    let reqbody = AxumBody::from(req_body_template.to_string());
+    let mut req = Request::builder()
+        .uri(uri.clone())
+        .method("POST")
+        .header("content-type", "application/json")
+        .body(reqbody)
+        .unwrap();
+    let _ = req
+        .extensions_mut()
+        .insert(itest_clone_authed_claim(&mock_auth_claim));
+    let response = TestWebServer::consume(&srv, req).await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    // ---- subcase 2 ----
+    mock_auth_claim.perms.push(AppAuthClaimPermission {
+        app_code: app_meta::RESOURCE_QUOTA_AP_CODE,
+        codename: AppAuthPermissionCode::can_create_promotion,
+    });
+    let reqbody = AxumBody::from(req_body_template.to_string());
+    let mut req = Request::builder()
+        .uri(uri.clone())
+        .method("POST")
+        .header("content-type", "application/json")
+        .body(reqbody)
+        .unwrap();
+    let _ = req.extensions_mut().insert(mock_auth_claim);
+    let response = TestWebServer::consume(&srv, req).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    Ok(())
+} // end of fn edit_promotion_ok
+
 async fn itest_setup_create_order(
     shrstate: AppSharedState,
     srv: Arc<Mutex<WebServiceRoute>>,
@@ -1546,3 +1666,359 @@ async fn modify_retrieve_cart_ok() -> DefaultResult<(), AppError> {
     ).await;
     Ok(())
 } // end of fn modify_retrieve_cart_ok
+
+async fn itest_shared_cart_request(
+    cfg: Arc<AppConfig>,
+    srv: Arc<Mutex<WebServiceRoute>>,
+    authed_claim: &AppAuthedClaim,
+    uri_suffix: String,
+    method: &str,
+    req_body: Option<JsnVal>,
+    expect_status: StatusCode,
+) -> Option<JsnVal> {
+    let uri = format!("/{}/cart/{}", cfg.api_server.listen.api_version, uri_suffix);
+    let mut authed_claim_cpy = itest_clone_authed_claim(authed_claim);
+    authed_claim_cpy.quota.push(AppAuthClaimQuota {
+        app_code: app_meta::RESOURCE_QUOTA_AP_CODE,
+        mat_code: AppAuthQuotaMatCode::NumOrderLines,
+        maxnum: 14,
+    });
+    let body = if let Some(v) = req_body {
+        AxumBody::from(serde_json::to_string(&v).unwrap())
+    } else {
+        AxumBody::empty()
+    };
+    let mut req = Request::builder()
+        .uri(uri)
+        .method(method)
+        .header("content-type", "application/json")
+        .body(body)
+        .unwrap();
+    let _ = req.extensions_mut().insert(authed_claim_cpy);
+    let mut response = TestWebServer::consume(&srv, req).await;
+    assert_eq!(response.status(), expect_status);
+    if expect_status == StatusCode::OK {
+        TestWebServer::to_custom_type::<JsnVal>(response.body_mut())
+            .await
+            .ok()
+    } else {
+        None
+    }
+} // end of fn itest_shared_cart_request
+
+#[rustfmt::skip]
+#[tokio::test]
+async fn share_cart_with_collaborators_ok() -> DefaultResult<(), AppError> {
+    let shrstate = test_setup_shr_state()?;
+    let srv = TestWebServer::setup(shrstate.clone());
+    let cfg = shrstate.config().clone();
+    let (owner, seq_num) = (125u32, 1u8);
+    let owner_claim = setup_mock_authed_claim(owner);
+    let collab_claims = [setup_mock_authed_claim(126), setup_mock_authed_claim(127)];
+    let _resp_body = itest_cart_modify_request(
+        cfg.clone(), srv.clone(),
+        "/tests/integration/examples/cartline_update_1.json",
+        &owner_claim, seq_num, StatusCode::OK,
+    ).await;
+    let shared_uri = format!("{seq_num}?owner={owner}");
+    let collab_uri = format!("{seq_num}/collaborators");
+    // ------ subcase, the cart is not shared yet
+    let _ = itest_shared_cart_request(
+        cfg.clone(), srv.clone(), &collab_claims[0], shared_uri.clone(),
+        "GET", None, StatusCode::FORBIDDEN,
+    ).await;
+    // ------ subcase, owner invites other users
+    let req_body = serde_json::json!({"usr_ids": [126, 127, owner]});
+    let resp_body = itest_shared_cart_request(
+        cfg.clone(), srv.clone(), &owner_claim, collab_uri.clone(),
+        "POST", Some(req_body), StatusCode::OK,
+    ).await.unwrap();
+    assert_eq!(resp_body, serde_json::json!({"sharable": true, "usr_ids": [126, 127]}));
+    // ------ subcase, collaborators read and edit the cart
+    let resp_body = itest_shared_cart_request(
+        cfg.clone(), srv.clone(), &collab_claims[0], shared_uri.clone(),
+        "GET", None, StatusCode::OK,
+    ).await.unwrap();
+    assert_eq!(resp_body.get("title").unwrap().as_str().unwrap(), "DIY drone gears");
+    let req_body = serde_json::json!({
+        "title": "shared drone gears",
+        "lines": [{"seller_id":57, "product_id":1948, "quantity":6, "applied_attr": null}]
+    });
+    let _ = itest_shared_cart_request(
+        cfg.clone(), srv.clone(), &collab_claims[0], shared_uri.clone(),
+        "PATCH", Some(req_body), StatusCode::OK,
+    ).await;
+    let resp_body = itest_shared_cart_request(
+        cfg.clone(), srv.clone(), &owner_claim, seq_num.to_string(),
+        "GET", None, StatusCode::OK,
+    ).await.unwrap();
+    assert_eq!(resp_body.get("title").unwrap().as_str().unwrap(), "shared drone gears");
+    let lines = resp_body.get("lines").unwrap().as_array().unwrap();
+    assert_eq!(lines.len(), 3);
+    let found = lines.iter().any(|l| {
+        l.get("seller_id").unwrap().as_u64() == Some(57)
+            && l.get("product_id").unwrap().as_u64() == Some(1948)
+            && l.get("quantity").unwrap().as_u64() == Some(6)
+    });
+    assert!(found);
+    // ------ subcase, owner revokes one of collaborators
+    let req_body = serde_json::json!({"usr_ids": [126]});
+    let resp_body = itest_shared_cart_request(
+        cfg.clone(), srv.clone(), &owner_claim, collab_uri.clone(),
+        "DELETE", Some(req_body), StatusCode::OK,
+    ).await.unwrap();
+    assert_eq!(resp_body, serde_json::json!({"sharable": true, "usr_ids": [127]}));
+    let _ = itest_shared_cart_request(
+        cfg.clone(), srv.clone(), &collab_claims[0], shared_uri.clone(),
+        "GET", None, StatusCode::FORBIDDEN,
+    ).await;
+    let _ = itest_shared_cart_request(
+        cfg.clone(), srv.clone(), &collab_claims[0], shared_uri.clone(),
+        "DELETE", None, StatusCode::FORBIDDEN,
+    ).await;
+    // ------ subcase, only the owner can discard the entire cart
+    let _ = itest_shared_cart_request(
+        cfg.clone(), srv.clone(), &collab_claims[1], shared_uri.clone(),
+        "DELETE", None, StatusCode::FORBIDDEN,
+    ).await;
+    let _ = itest_shared_cart_request(
+        cfg.clone(), srv.clone(), &owner_claim, seq_num.to_string(),
+        "DELETE", None, StatusCode::NO_CONTENT,
+    ).await;
+    Ok(())
+} // end of fn share_cart_with_collaborators_ok
+
+async fn itest_cart_checkout_request(
+    cfg: Arc<AppConfig>,
+    srv: Arc<Mutex<WebServiceRoute>>,
+    authed_claim: &AppAuthedClaim,
+    seq_num: u8,
+    req_fpath: &'static str,
+    expect_status: StatusCode,
+) -> JsnVal {
+    let uri = format!(
+        "/{}/cart/{}/checkout",
+        cfg.api_server.listen.api_version, seq_num
+    );
+    let reqbody = {
+        let result = deserialize_json_template::<JsnVal>(&cfg.basepath, req_fpath);
+        let rb = serde_json::to_string(&result.unwrap()).unwrap();
+        AxumBody::from(rb)
+    };
+    let mut authed_claim_cpy = itest_clone_authed_claim(authed_claim);
+    authed_claim_cpy.quota = [
+        (AppAuthQuotaMatCode::NumEmails, 51),
+        (AppAuthQuotaMatCode::NumPhones, 52),
+        (AppAuthQuotaMatCode::NumOrderLines, 53),
+    ]
+    .into_iter()
+    .map(|(mat_code, maxnum)| AppAuthClaimQuota {
+        mat_code,
+        maxnum,
+        app_code: app_meta::RESOURCE_QUOTA_AP_CODE,
+    })
+    .collect::<Vec<_>>();
+    let mut req = Request::builder()
+        .uri(uri)
+        .method("POST")
+        .header("content-type", "application/json")
+        .header("accept", "application/json")
+        .body(reqbody)
+        .unwrap();
+    let _ = req.extensions_mut().insert(authed_claim_cpy);
+    let mut response = TestWebServer::consume(&srv, req).await;
+    assert_eq!(response.status(), expect_status);
+    TestWebServer::to_custom_type::<JsnVal>(response.body_mut())
+        .await
+        .unwrap()
+} // end of fn itest_cart_checkout_request
+
+#[tokio::test]
+async fn checkout_cart_ok() -> DefaultResult<(), AppError> {
+    const FPATH_EDIT_PRODUCTPOLICY_OK: &str =
+        "/tests/integration/examples/policy_product_edit_ok_2.json";
+    const FPATH_EDIT_PRODUCTPRICE_OK: &str =
+        "/tests/integration/examples/product_price_celery_ok_4.json";
+    const FPATH_EDIT_STOCK_LVL_OK: &str = "/tests/integration/examples/stock_level_edit_ok_3.json";
+    const FPATH_MODIFY_CART: &str = "/tests/integration/examples/cartline_update_4.json";
+    const FPATH_CHECKOUT_CART: &str = "/tests/integration/examples/cart_checkout_ok_1.json";
+    let shr_state = test_setup_shr_state()?;
+    let srv = TestWebServer::setup(shr_state.clone());
+    let cfg = shr_state.config().clone();
+    let (mock_authed_usr, seq_num) = (186u32, 3u8);
+    let authed_claim = setup_mock_authed_claim(mock_authed_usr);
+    let _ = itest_setup_product_policy(
+        cfg.clone(),
+        srv.clone(),
+        FPATH_EDIT_PRODUCTPOLICY_OK,
+        itest_clone_authed_claim(&authed_claim),
+        StatusCode::OK,
+    )
+    .await;
+    itest_setup_product_price(shr_state.clone(), FPATH_EDIT_PRODUCTPRICE_OK).await;
+    {
+        let expiry = Local::now().fixed_offset() + Duration::minutes(1);
+        let _resp_body =
+            itest_setup_stock_level(shr_state.clone(), expiry, FPATH_EDIT_STOCK_LVL_OK).await;
+    }
+    itest_setup_currency_exrate(shr_state.clone()).await;
+    let _resp_body = itest_cart_modify_request(
+        cfg.clone(),
+        srv.clone(),
+        FPATH_MODIFY_CART,
+        &authed_claim,
+        seq_num,
+        StatusCode::OK,
+    )
+    .await;
+    let resp_body = itest_cart_checkout_request(
+        cfg.clone(),
+        srv.clone(),
+        &authed_claim,
+        seq_num,
+        FPATH_CHECKOUT_CART,
+        StatusCode::CREATED,
+    )
+    .await;
+    let resp_body = serde_json::from_value::<OrderCreateRespOkDto>(resp_body).unwrap();
+    assert!(!resp_body.order_id.is_empty());
+    assert_eq!(resp_body.usr_id, mock_authed_usr);
+    assert_eq!(resp_body.reserved_lines.len(), 2);
+    // converted lines are removed from the cart
+    let resp_body = itest_shared_cart_request(
+        cfg.clone(),
+        srv.clone(),
+        &authed_claim,
+        seq_num.to_string(),
+        "GET",
+        None,
+        StatusCode::OK,
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        resp_body.get("title").unwrap().as_str().unwrap(),
+        "office refill"
+    );
+    assert!(resp_body
+        .get("lines")
+        .unwrap()
+        .as_array()
+        .unwrap()
+        .is_empty());
+    // ------ subcase, nothing to check out
+    let resp_body = itest_cart_checkout_request(
+        cfg.clone(),
+        srv.clone(),
+        &authed_claim,
+        seq_num,
+        FPATH_CHECKOUT_CART,
+        StatusCode::BAD_REQUEST,
+    )
+    .await;
+    assert_eq!(resp_body, serde_json::json!({"reason": "empty-cart"}));
+    Ok(())
+} // end of fn checkout_cart_ok
+
+#[tokio::test]
+async fn quote_cart_ok() -> DefaultResult<(), AppError> {
+    const FPATH_EDIT_PRODUCTPRICE_OK: &str =
+        "/tests/integration/examples/product_price_celery_ok_11.json";
+    const FPATH_EDIT_STOCK_LVL_OK: &str = "/tests/integration/examples/stock_level_edit_ok_9.json";
+    const FPATH_MODIFY_CART: &str = "/tests/integration/examples/cartline_update_5.json";
+    let shr_state = test_setup_shr_state()?;
+    let srv = TestWebServer::setup(shr_state.clone());
+    let cfg = shr_state.config().clone();
+    let (mock_authed_usr, seq_num) = (187u32, 2u8);
+    let authed_claim = setup_mock_authed_claim(mock_authed_usr);
+    itest_setup_product_price(shr_state.clone(), FPATH_EDIT_PRODUCTPRICE_OK).await;
+    {
+        let expiry = Local::now().fixed_offset() + Duration::minutes(1);
+        let _resp_body =
+            itest_setup_stock_level(shr_state.clone(), expiry, FPATH_EDIT_STOCK_LVL_OK).await;
+    }
+    itest_setup_currency_exrate(shr_state.clone()).await;
+    let _resp_body = itest_cart_modify_request(
+        cfg.clone(),
+        srv.clone(),
+        FPATH_MODIFY_CART,
+        &authed_claim,
+        seq_num,
+        StatusCode::OK,
+    )
+    .await;
+    let resp_body = itest_shared_cart_request(
+        cfg.clone(),
+        srv.clone(),
+        &authed_claim,
+        format!("{seq_num}/quote?currency=TWD"),
+        "GET",
+        None,
+        StatusCode::OK,
+    )
+    .await
+    .unwrap();
+    let resp_body = serde_json::from_value::<CartQuoteDto>(resp_body).unwrap();
+    assert_eq!(resp_body.title.as_str(), "desk lamp");
+    assert_eq!(resp_body.lines.len(), 3);
+    let expect_lines = [
+        (270118u64, 22u32, Some(("11.00", "137.00", "274.00"))),
+        (1270118, 50, Some(("0.00", "1038.00", "52938.00"))),
+        (270119, 38, None), // chosen attribute is not priced by the seller
+    ];
+    expect_lines
+        .into_iter()
+        .map(|(product_id, qty_avail, expect_amount)| {
+            let line = resp_body
+                .lines
+                .iter()
+                .find(|l| l.seller_id == 18836 && l.product_id == product_id)
+                .unwrap();
+            assert_eq!(line.qty_avail, qty_avail);
+            let actual_amount = line.price.as_ref().map(|p| {
+                let (surcharge, amount) = (p.attr_surcharge.as_str(), &p.amount);
+                (surcharge, amount.unit.as_str(), amount.total.as_str())
+            });
+            assert_eq!(actual_amount, expect_amount);
+        })
+        .count();
+    // lines out of stock or without price are still listed, only priced lines
+    // contribute to grand total
+    assert_eq!(resp_body.total.as_str(), "53212.00");
+    Ok(())
+} // end of fn quote_cart_ok
+
+#[tokio::test]
+async fn currency_rate_history_ok() -> DefaultResult<(), AppError> {
+    let shrstate = test_setup_shr_state()?;
+    let t0 = Local::now().fixed_offset() - Duration::seconds(5);
+    itest_setup_currency_exrate(shrstate.clone()).await;
+    let t1 = Local::now().fixed_offset() + Duration::seconds(5);
+    let reqbody = serde_json::json!({
+        "base": "TWD", "quote": "INR",
+        "start": t0.to_rfc3339(), "end": t1.to_rfc3339(),
+    });
+    let req = AppRpcClientReqProperty {
+        start_time: Local::now().fixed_offset(),
+        msgbody: reqbody.to_string().into_bytes(),
+        route: "rpc.order.currency_exrate_history".to_string(),
+        correlation_id: Some("xyz1235".to_string()),
+    };
+    let result = rpc::route_to_handler(req, shrstate).await;
+    assert!(result.is_ok());
+    let respbody = _itest_process_rpc_response(result.unwrap())?;
+    assert_eq!(respbody.get("base").unwrap().as_str(), Some("TWD"));
+    assert_eq!(respbody.get("quote").unwrap().as_str(), Some("INR"));
+    // other test cases may refresh the rates concurrently, only check that
+    // at least one rate was recorded within the time range
+    let rates = respbody.get("rates").unwrap().as_array().unwrap();
+    assert!(!rates.is_empty());
+    rates
+        .iter()
+        .map(|item| {
+            let rate = item.get("rate").unwrap().as_str().unwrap();
+            assert!(rate.parse::<f64>().unwrap() > 0.0);
+        })
+        .count();
+    Ok(())
+} // end of fn currency_rate_history_ok
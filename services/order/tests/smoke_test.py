@@ -168,6 +168,7 @@ def verify_product_policy_api(client: OrderApiTestClient) -> bool:
         endpoint = "/1.2.0/policy/products"
         request_data = [
             {
+                "seller_id": VALID_STORE_ID,
                 "product_id": VALID_PRODUCT_ID,
                 "auto_cancel_secs": 4200,
                 "warranty_hours": 720,
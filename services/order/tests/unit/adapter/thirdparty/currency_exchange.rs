@@ -8,7 +8,7 @@ use ecommerce_common::confidentiality::UserSpaceConfidentiality;
 use ecommerce_common::constant::env_vars::SYS_BASEPATH;
 use order::AppSharedState;
 
-use crate::ut_setup_share_state;
+use crate::{ut_setup_share_state, MockConfidential};
 
 fn ut_appstate_setup() -> AppSharedState {
     let cfdntl = {
@@ -77,8 +77,39 @@ async fn refresh_ok() {
                     assert!(c.rate < hi);
                     assert!(c.rate > lo);
                 }
-                CurrencyDto::Unknown => assert!(false),
+                _others => assert!(false),
             };
         })
         .count();
 } // end of fn refresh_ok
+
+#[tokio::test]
+async fn refresh_fallback_ok() {
+    // the first provider is unreachable, then the refresh falls back to
+    // the fixed-rate provider which applies different base currency
+    let shrstate = ut_setup_share_state(
+        "config_ok_currency_fallback.json",
+        Box::new(MockConfidential {}),
+    );
+    let currency_ctx = shrstate.currency();
+    let chosen = vec![CurrencyDto::TWD, CurrencyDto::INR, CurrencyDto::USD];
+    let result = currency_ctx.refresh(chosen).await;
+    assert!(result.is_ok());
+    let mut ms = result.unwrap();
+    assert_eq!(ms.provider.as_deref(), Some("fixedrate"));
+    assert_eq!(ms.base, CurrencyDto::TWD);
+    assert_eq!(ms.exchange_rates.len(), 3);
+    let result = ms.rebase(CurrencyDto::USD);
+    assert!(result.is_ok());
+    [
+        (CurrencyDto::TWD, Decimal::new(32, 0)),
+        (CurrencyDto::INR, Decimal::new(80, 0)),
+        (CurrencyDto::USD, Decimal::ONE),
+    ]
+    .into_iter()
+    .map(|(name, expect)| {
+        let m = ms.exchange_rates.iter().find(|m| m.name == name).unwrap();
+        assert_eq!(m.rate, expect);
+    })
+    .count();
+} // end of fn refresh_fallback_ok
@@ -0,0 +1,86 @@
+use ecommerce_common::api::web::dto::QuotaResourceErrorDto;
+
+use order::constant::hard_limit;
+use order::model::CartModel;
+
+fn ut_setup_cart(owner: u32, collaborators: Vec<u32>) -> CartModel {
+    CartModel {
+        owner,
+        seq_num: 1,
+        title: "office supplies".to_string(),
+        saved_lines: Vec::new(),
+        new_lines: Vec::new(),
+        sharable: !collaborators.is_empty(),
+        collaborators,
+        owner_quota_lines: 0,
+    }
+}
+
+#[test]
+fn accessible_owner_only() {
+    let cart = ut_setup_cart(125, vec![]);
+    assert!(cart.accessible(125));
+    assert!(!cart.accessible(126));
+}
+
+#[test]
+fn accessible_collaborators() {
+    let mut cart = ut_setup_cart(125, vec![126, 127]);
+    assert!(cart.accessible(125));
+    assert!(cart.accessible(126));
+    assert!(cart.accessible(127));
+    assert!(!cart.accessible(128));
+    // collaborator list is ignored if the cart is not sharable
+    cart.sharable = false;
+    assert!(cart.accessible(125));
+    assert!(!cart.accessible(126));
+}
+
+#[test]
+fn invite_ok() {
+    let mut cart = ut_setup_cart(125, vec![126]);
+    // owner and existing collaborators are skipped, duplicates are removed
+    let result = cart.invite(vec![125, 126, 129, 128, 129]);
+    assert!(result.is_ok());
+    assert!(cart.sharable);
+    assert_eq!(cart.collaborators, vec![126, 128, 129]);
+    assert!(cart.accessible(128));
+}
+
+#[test]
+fn invite_exceed_limit() {
+    let mut cart = ut_setup_cart(125, vec![126]);
+    let num_max = hard_limit::MAX_NUM_CART_COLLABORATORS;
+    let usr_ids = (0..num_max as u32).map(|n| 200 + n).collect::<Vec<_>>();
+    let result = cart.invite(usr_ids);
+    let expect = QuotaResourceErrorDto {
+        given: num_max + 1,
+        max_: num_max as u32,
+    };
+    assert_eq!(result.unwrap_err(), expect);
+    assert_eq!(cart.collaborators, vec![126]);
+}
+
+#[test]
+fn revoke_ok() {
+    let mut cart = ut_setup_cart(125, vec![126, 127]);
+    cart.revoke(vec![126, 130]);
+    assert!(cart.sharable);
+    assert_eq!(cart.collaborators, vec![127]);
+    assert!(!cart.accessible(126));
+    // the cart is no longer sharable once all collaborators are revoked
+    cart.revoke(vec![127]);
+    assert!(!cart.sharable);
+    assert!(cart.collaborators.is_empty());
+    assert!(!cart.accessible(127));
+    assert!(cart.accessible(125));
+}
+
+#[test]
+fn max_num_lines_follow_owner_quota() {
+    let mut cart = ut_setup_cart(125, vec![126]);
+    cart.owner_quota_lines = 14;
+    assert_eq!(cart.max_num_lines(125, 20), 20);
+    assert_eq!(cart.max_num_lines(126, 50), 14);
+    assert_eq!(cart.max_num_lines(126, 3), 14);
+}
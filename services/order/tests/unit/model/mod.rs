@@ -1,18 +1,29 @@
+mod cart;
 mod oorder;
 mod product_policy;
 mod product_price;
+mod promotion;
+mod shipment;
+mod shipping_rate;
+pub(crate) mod stock_alert;
 mod stock_level;
+mod tax;
 
 use order::model::{ProductPolicyModel, ProductStockModel, StockLevelModelSet};
 
 pub(crate) fn ut_clone_productpolicy(src: &ProductPolicyModel) -> ProductPolicyModel {
     ProductPolicyModel {
+        store_id: src.store_id,
         product_id: src.product_id,
+        version: src.version,
         auto_cancel_secs: src.auto_cancel_secs,
         warranty_hours: src.warranty_hours,
         is_create: src.is_create,
         max_num_rsv: src.max_num_rsv,
         min_num_rsv: src.min_num_rsv,
+        backorder: src.backorder,
+        max_num_backorder: src.max_num_backorder,
+        weight_gram: src.weight_gram,
     }
 }
 
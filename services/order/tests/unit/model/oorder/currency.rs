@@ -3,8 +3,12 @@ use rust_decimal::Decimal;
 
 use ecommerce_common::api::dto::{CurrencyDto, OrderCurrencySnapshotDto};
 use ecommerce_common::error::AppErrorCode;
+use order::api::dto::ShippingMethod;
 use order::api::web::dto::OrderCreateRespOkDto;
-use order::model::{CurrencyModel, CurrencyModelSet, OrderCurrencyModel, OrderLineModelSet};
+use order::model::{
+    CurrencyModel, CurrencyModelSet, CurrencyPairRateModel, CurrencyRateHistoryModel,
+    OrderCurrencyModel, OrderLineModelSet, ShippingOptionModel,
+};
 
 use super::order_line::ut_setup_order_lines;
 
@@ -20,6 +24,7 @@ fn ut_setup_currency_mset(data: Vec<(CurrencyDto, i64, u32)>) -> CurrencyModelSe
     CurrencyModelSet {
         base,
         exchange_rates,
+        provider: None,
     }
 }
 
@@ -77,6 +82,92 @@ pub(super) fn ut_common_order_currency(seller_ids: [u32; 3]) -> OrderCurrencyMod
     result.unwrap()
 }
 
+#[test]
+fn currency_rebase_ok() {
+    let mut ms = ut_setup_currency_mset(vec![
+        (CurrencyDto::TWD, 320, 1),
+        (CurrencyDto::INR, 800, 1),
+        (CurrencyDto::USD, 1, 0),
+    ]);
+    let result = ms.rebase(CurrencyDto::TWD);
+    assert!(result.is_ok());
+    assert_eq!(ms.base, CurrencyDto::TWD);
+    [
+        (CurrencyDto::TWD, Decimal::ONE),
+        (CurrencyDto::INR, Decimal::new(25, 1)),
+        (CurrencyDto::USD, Decimal::new(3125, 5)),
+    ]
+    .into_iter()
+    .map(|(name, expect)| {
+        let m = ms.exchange_rates.iter().find(|m| m.name == name).unwrap();
+        assert_eq!(m.rate, expect);
+    })
+    .count();
+    // ------ subcase, rebase back
+    let result = ms.rebase(CurrencyDto::USD);
+    assert!(result.is_ok());
+    let m = ms
+        .exchange_rates
+        .iter()
+        .find(|m| m.name == CurrencyDto::INR)
+        .unwrap();
+    assert_eq!(m.rate, Decimal::new(80, 0));
+}
+
+#[test]
+fn currency_rebase_error() {
+    let mut ms = ut_setup_currency_mset(vec![(CurrencyDto::TWD, 320, 1), (CurrencyDto::IDR, 0, 0)]);
+    let result = ms.rebase(CurrencyDto::THB);
+    assert!(result.is_err());
+    let e = result.unwrap_err();
+    assert_eq!(e.code, AppErrorCode::InvalidInput);
+    let result = ms.rebase(CurrencyDto::IDR);
+    assert!(result.is_err());
+    let e = result.unwrap_err();
+    assert_eq!(e.code, AppErrorCode::DataCorruption);
+    assert_eq!(ms.base, CurrencyDto::USD);
+}
+
+#[test]
+fn currency_pair_rate_collect_ok() {
+    let t0 = Local::now().fixed_offset();
+    let initial =
+        ut_setup_currency_mset(vec![(CurrencyDto::TWD, 320, 1), (CurrencyDto::INR, 800, 1)]);
+    let records = [
+        (CurrencyDto::INR, 840, 1, 20),
+        (CurrencyDto::TWD, 300, 1, 10),
+        (CurrencyDto::IDR, 15000, 0, 10),
+        (CurrencyDto::TWD, 336, 1, 20),
+    ]
+    .into_iter()
+    .map(|(name, num, scale, secs)| CurrencyRateHistoryModel {
+        name,
+        rate: Decimal::new(num, scale),
+        provider: None,
+        time: t0 + Duration::seconds(secs),
+    })
+    .collect::<Vec<_>>();
+    let pair = (&CurrencyDto::TWD, &CurrencyDto::INR);
+    let actual = CurrencyPairRateModel::collect(pair, t0, &initial, records);
+    assert_eq!(actual.len(), 3);
+    [
+        (0i64, Decimal::new(25, 1)),
+        (10, Decimal::new(8, 0) / Decimal::new(3, 0)),
+        (20, Decimal::new(25, 1)),
+    ]
+    .into_iter()
+    .zip(actual.iter())
+    .map(|((secs, expect), item)| {
+        assert_eq!(item.time, t0 + Duration::seconds(secs));
+        assert_eq!(item.rate, expect);
+    })
+    .count();
+    // ------ subcase, quote currency unknown at the start time
+    let pair = (&CurrencyDto::TWD, &CurrencyDto::THB);
+    let actual = CurrencyPairRateModel::collect(pair, t0, &initial, Vec::new());
+    assert!(actual.is_empty());
+}
+
 #[test]
 fn currency_estimate_buyer_rate_ok() {
     let mock_seller_ids = [2603u32, 9442, 8901];
@@ -113,6 +204,43 @@ fn currency_estimate_buyer_rate_err_div0() {
     }
 } // end of fn currency_estimate_buyer_rate_err_div0
 
+#[test]
+fn currency_convert_amount_precision() {
+    let (seller_jp, seller_vn, seller_kw) = (1032u32, 1033, 1034);
+    let search_scope = {
+        let data = vec![
+            (CurrencyDto::JPY, 1495200, 4),
+            (CurrencyDto::VND, 254305000, 4),
+            (CurrencyDto::KWD, 3075, 4),
+        ];
+        ut_setup_currency_mset(data)
+    };
+    let seller_labels = vec![
+        (seller_jp, CurrencyDto::JPY),
+        (seller_vn, CurrencyDto::VND),
+        (seller_kw, CurrencyDto::KWD),
+    ];
+    let args = (search_scope, CurrencyDto::KWD, seller_labels.clone());
+    let curr_m = OrderCurrencyModel::try_from(args).unwrap();
+    // rate far below the fraction scale of stored rates should not be truncated
+    let actual = curr_m.to_buyer_rate(seller_vn).unwrap();
+    assert_eq!(actual.name, CurrencyDto::KWD);
+    assert!(actual.rate > Decimal::ZERO);
+    let amount = actual.convert_amount(Decimal::new(10000, 0));
+    assert_eq!(amount.to_string().as_str(), "0.120");
+    // zero-decimal currency on buyer side
+    let search_scope = {
+        let data = vec![(CurrencyDto::JPY, 1495200, 4), (CurrencyDto::KWD, 3075, 4)];
+        ut_setup_currency_mset(data)
+    };
+    let seller_labels = vec![(seller_kw, CurrencyDto::KWD)];
+    let args = (search_scope, CurrencyDto::JPY, seller_labels);
+    let curr_m = OrderCurrencyModel::try_from(args).unwrap();
+    let actual = curr_m.to_buyer_rate(seller_kw).unwrap();
+    let amount = actual.convert_amount(Decimal::new(3, 0));
+    assert_eq!(amount.to_string().as_str(), "1458");
+} // end of fn currency_convert_amount_precision
+
 #[test]
 fn currency_to_rpc_replica_dto() {
     let mock_seller_ids = [2615u32, 8299, 1031];
@@ -174,7 +302,15 @@ fn order_to_web_resp_dto_ok() {
         let args = (order_id, owner_id, mock_ctime, currency, lines);
         OrderLineModelSet::try_from(args).unwrap()
     };
-    let result = OrderCreateRespOkDto::try_from(model);
+    let sh_opts = [(mock_seller_ids[2], 35u32), (mock_seller_ids[0], 60)]
+        .into_iter()
+        .map(|(seller_id, fee)| ShippingOptionModel {
+            seller_id,
+            method: ShippingMethod::UPS,
+            fee,
+        })
+        .collect::<Vec<_>>();
+    let result = OrderCreateRespOkDto::try_from((model, sh_opts));
     assert!(result.is_ok());
     if let Ok(v) = result {
         let OrderCreateRespOkDto {
@@ -183,7 +319,15 @@ fn order_to_web_resp_dto_ok() {
             time: _,
             currency: exrate_applied,
             reserved_lines,
+            backorders: _,
+            shipping,
         } = v;
+        // shipping fees are converted to buyer's currency as well
+        assert_eq!(shipping.len(), 2);
+        assert_eq!(shipping[0].seller_id, 9982);
+        assert_eq!(shipping[0].amount.as_str(), "13.44");
+        assert_eq!(shipping[1].seller_id, 2379);
+        assert_eq!(shipping[1].amount.as_str(), "60.00");
         reserved_lines
             .into_iter()
             .map(|item| {
@@ -2,11 +2,13 @@ use std::collections::HashMap;
 
 use chrono::{DateTime, Duration, FixedOffset, Local};
 
+use order::api::dto::ShippingMethod;
 use order::api::web::dto::{OrderLineReturnErrorReason, OrderLineReturnReqDto};
 use order::constant::hard_limit;
 use order::model::{
     OrderLineAppliedPolicyModel, OrderLineIdentity, OrderLineModel, OrderLinePriceModel,
-    OrderLineQuantityModel, OrderReturnModel, ProdAttriPriceModel,
+    OrderLineQuantityModel, OrderReturnModel, ProdAttriPriceModel, ShipmentLineModel,
+    ShipmentModel, ShipmentStatus,
 };
 
 #[rustfmt::skip]
@@ -30,10 +32,11 @@ fn ut_saved_orderline_setup(dt_now: DateTime<FixedOffset>, store_id: u32) -> Vec
                 OrderLineIdentity::from((store_id, product_id, attr_seq)),
                 OrderLinePriceModel::from((unit, total)),
                 OrderLineAppliedPolicyModel {
-                    reserved_until, warranty_until,
+                    reserved_until, warranty_until, version: 0,
                 },
                 OrderLineQuantityModel {
                     reserved, paid, paid_last_update,
+                    backordered: 0,
                 },
                 ProdAttriPriceModel::from((attr_lastupdate, None)),
             );
@@ -78,6 +81,50 @@ fn ut_saved_oline_return_setup(
     ]
 } // end of fn ut_saved_oline_return_setup
 
+fn ut_saved_shipment_setup(
+    dt_now: DateTime<FixedOffset>,
+    store_id: u32,
+    status: ShipmentStatus,
+    lines: Vec<(u64, u16, u32)>,
+) -> ShipmentModel {
+    let lines = lines
+        .into_iter()
+        .map(|(product_id, attr_seq, qty)| ShipmentLineModel {
+            id_: OrderLineIdentity::from((store_id, product_id, attr_seq)),
+            qty,
+        })
+        .collect();
+    let delivered_time = if status == ShipmentStatus::Delivered {
+        Some(dt_now - Duration::days(1))
+    } else {
+        None
+    };
+    ShipmentModel {
+        seq: 0,
+        seller_id: store_id,
+        carrier: ShippingMethod::UPS,
+        tracking_number: Some("1Z999AA10123456784".to_string()),
+        status,
+        packed_time: dt_now - Duration::days(3),
+        shipped_time: Some(dt_now - Duration::days(2)),
+        delivered_time,
+        lines,
+    }
+}
+
+// all the paid items have been delivered
+fn ut_saved_delivered_setup(dt_now: DateTime<FixedOffset>, store_id: u32) -> Vec<ShipmentModel> {
+    let lines = vec![
+        (890, 0, 7),
+        (574, 0, 12),
+        (574, 1, 7),
+        (574, 2, 3),
+        (257, 0, 10),
+    ];
+    let obj = ut_saved_shipment_setup(dt_now, store_id, ShipmentStatus::Delivered, lines);
+    vec![obj]
+}
+
 #[test]
 fn filter_request_ok() {
     let seller_id = 145;
@@ -91,6 +138,7 @@ fn filter_request_ok() {
         assert_eq!(num_returned, 1u32);
         objs
     };
+    let o_shipments = ut_saved_delivered_setup(dt_now, seller_id);
     let data = [(890, 0, 4), (574, 1, 4), (574, 0, 1), (257, 0, 3)]
         .into_iter()
         .map(|(product_id, attr_seq, quantity)| OrderLineReturnReqDto {
@@ -100,7 +148,7 @@ fn filter_request_ok() {
             attr_set_seq: attr_seq,
         })
         .collect::<Vec<_>>();
-    let result = OrderReturnModel::filter_requests(data, o_lines, o_returns);
+    let result = OrderReturnModel::filter_requests(data, o_lines, o_returns, o_shipments);
     assert!(result.is_ok());
     if let Ok(modified) = result {
         assert_eq!(modified.len(), 4);
@@ -129,6 +177,7 @@ fn filter_request_err_nonexist() {
     let dt_now = Local::now().fixed_offset();
     let o_lines = ut_saved_orderline_setup(dt_now.clone(), seller_id);
     let o_returns = ut_saved_oline_return_setup(dt_now.clone(), seller_id);
+    let o_shipments = ut_saved_delivered_setup(dt_now, seller_id);
     let data = [(890, 0, 4), (574, 0, 1), (574, 5, 666)]
         .into_iter()
         .map(|(product_id, attr_seq, quantity)| OrderLineReturnReqDto {
@@ -138,7 +187,7 @@ fn filter_request_err_nonexist() {
             attr_set_seq: attr_seq,
         })
         .collect::<Vec<_>>();
-    let result = OrderReturnModel::filter_requests(data, o_lines, o_returns);
+    let result = OrderReturnModel::filter_requests(data, o_lines, o_returns, o_shipments);
     assert!(result.is_err());
     if let Err(es) = result {
         assert_eq!(es.len(), 1);
@@ -163,6 +212,7 @@ fn filter_request_warranty_expired() {
         objs
     };
     let o_returns = ut_saved_oline_return_setup(dt_now.clone(), seller_id);
+    let o_shipments = ut_saved_delivered_setup(dt_now, seller_id);
     let data = [(890, 1), (574, 1), (257, 2)]
         .into_iter()
         .map(|(product_id, quantity)| OrderLineReturnReqDto {
@@ -172,7 +222,7 @@ fn filter_request_warranty_expired() {
             attr_set_seq: 0,
         })
         .collect::<Vec<_>>();
-    let result = OrderReturnModel::filter_requests(data, o_lines, o_returns);
+    let result = OrderReturnModel::filter_requests(data, o_lines, o_returns, o_shipments);
     assert!(result.is_err());
     if let Err(es) = result {
         assert_eq!(es.len(), 1);
@@ -190,6 +240,7 @@ fn filter_request_invalid_qty() {
     let dt_now = Local::now().fixed_offset();
     let o_lines = ut_saved_orderline_setup(dt_now.clone(), seller_id);
     let o_returns = ut_saved_oline_return_setup(dt_now.clone(), seller_id);
+    let o_shipments = ut_saved_delivered_setup(dt_now, seller_id);
     let data = [(9999, 1), (890, 3), (574, 16), (257, 2)]
         .into_iter()
         .map(|(product_id, quantity)| OrderLineReturnReqDto {
@@ -199,7 +250,7 @@ fn filter_request_invalid_qty() {
             attr_set_seq: 0,
         })
         .collect::<Vec<_>>();
-    let result = OrderReturnModel::filter_requests(data, o_lines, o_returns);
+    let result = OrderReturnModel::filter_requests(data, o_lines, o_returns, o_shipments);
     assert!(result.is_err());
     if let Err(es) = result {
         assert_eq!(es.len(), 2);
@@ -231,6 +282,7 @@ fn filter_request_err_duplicate() {
         assert_eq!(objs[1].qty.len(), 2);
         objs
     }; // assume the record is already added to the return model
+    let o_shipments = ut_saved_delivered_setup(dt_now, seller_id);
     let data = [(890, 3), (574, 1), (257, 1)]
         .into_iter()
         .map(|(product_id, quantity)| OrderLineReturnReqDto {
@@ -240,7 +292,7 @@ fn filter_request_err_duplicate() {
             attr_set_seq: 0,
         })
         .collect::<Vec<_>>();
-    let result = OrderReturnModel::filter_requests(data, o_lines, o_returns);
+    let result = OrderReturnModel::filter_requests(data, o_lines, o_returns, o_shipments);
     assert!(result.is_err());
     if let Err(es) = result {
         assert_eq!(es.len(), 1);
@@ -251,3 +303,47 @@ fn filter_request_err_duplicate() {
         ));
     }
 }
+
+#[test]
+fn filter_request_err_not_delivered() {
+    let seller_id = 145;
+    let dt_now = Local::now().fixed_offset();
+    let o_lines = ut_saved_orderline_setup(dt_now, seller_id);
+    let o_returns = ut_saved_oline_return_setup(dt_now, seller_id);
+    let o_shipments = vec![
+        ut_saved_shipment_setup(
+            dt_now,
+            seller_id,
+            ShipmentStatus::Delivered,
+            vec![(890, 0, 2), (574, 0, 12)],
+        ),
+        ut_saved_shipment_setup(
+            dt_now,
+            seller_id,
+            ShipmentStatus::Shipped,
+            vec![(890, 0, 5), (574, 1, 7)],
+        ),
+    ];
+    // the line 257 is not in any shipment, sellers may not track its parcels
+    let data = [(890, 0, 3), (574, 0, 2), (574, 1, 1), (257, 0, 3)]
+        .into_iter()
+        .map(|(product_id, attr_seq, quantity)| OrderLineReturnReqDto {
+            seller_id,
+            product_id,
+            quantity,
+            attr_set_seq: attr_seq,
+        })
+        .collect::<Vec<_>>();
+    let result = OrderReturnModel::filter_requests(data, o_lines, o_returns, o_shipments);
+    assert!(result.is_err());
+    if let Err(es) = result {
+        assert_eq!(es.len(), 2);
+        es.iter()
+            .map(|e| {
+                let combo = (e.product_id, e.attr_set_seq);
+                assert!(matches!(combo, (890, 0) | (574, 1)));
+                assert!(matches!(e.reason, OrderLineReturnErrorReason::NotDelivered));
+            })
+            .count();
+    }
+}
@@ -2,19 +2,22 @@ use std::collections::HashMap;
 
 use chrono::{DateTime, Duration, FixedOffset, Local as LocalTime};
 
-use ecommerce_common::api::dto::CurrencyDto;
+use ecommerce_common::api::dto::{CountryCode, CurrencyDto};
 use ecommerce_common::api::rpc::dto::{OrderLinePaidUpdateDto, OrderLinePayUpdateErrorReason};
 use ecommerce_common::error::AppErrorCode;
+use ecommerce_common::model::order::PhyAddrModel;
 
 use order::api::dto::ProdAttrValueDto;
+use order::api::rpc::dto::TaxRateDto;
 use order::api::web::dto::{
-    OlineProductAttrDto, OrderCreateRespOkDto, OrderLineCreateErrorDto, OrderLineCreateErrorReason,
-    OrderLineRsvReqDto,
+    OlineProductAttrDto, OrderCreateRespOkDto, OrderLineCancelErrorReason, OrderLineCreateErrorDto,
+    OrderLineCreateErrorReason, OrderLineRsvReqDto,
 };
 use order::model::{
-    OrderLineAppliedPolicyModel, OrderLineIdentity, OrderLineModel, OrderLineModelSet,
-    OrderLinePriceModel, OrderLineQuantityModel, ProdAttriPriceModel, ProductPolicyModel,
-    ProductPriceModel,
+    OrderLineAppliedPolicyModel, OrderLineCancelModel, OrderLineIdentity, OrderLineModel,
+    OrderLineModelSet, OrderLinePriceModel, OrderLineQuantityModel, ProdAttriPriceModel,
+    ProductBackorderPolicy, ProductPolicyModel, ProductPriceModel, PromotionModel,
+    PromotionRuleModel, TaxRateModelSet,
 };
 
 use super::currency::ut_common_order_currency;
@@ -35,9 +38,10 @@ pub(super) fn ut_setup_order_lines(
             let price= OrderLinePriceModel::from(d.1);
             let qty = OrderLineQuantityModel {
                 reserved: d.2, paid: d.3, paid_last_update: d.4,
+                backordered: 0,
             };
             let policy = OrderLineAppliedPolicyModel {
-                reserved_until: d.5, warranty_until: d.6,
+                reserved_until: d.5, warranty_until: d.6, version: 0,
             };
             let attr_lastupdate = d.5 - Duration::days(15);
             let attrs_charge = ProdAttriPriceModel::from((attr_lastupdate, d.7));
@@ -50,12 +54,17 @@ pub(super) fn ut_setup_order_lines(
 fn convert_from_req_dto_without_rsv_limit_ok() {
     let (seller_id, product_id) = (19, 146);
     let policym = ProductPolicyModel {
+        store_id: seller_id,
         product_id,
+        version: 0,
         is_create: false,
         auto_cancel_secs: 69,
         warranty_hours: 23,
         max_num_rsv: 0,
         min_num_rsv: 0,
+        backorder: ProductBackorderPolicy::Disabled,
+        max_num_backorder: 0,
+        weight_gram: 0,
     };
     let pricem = {
         let start_after = DateTime::parse_from_rfc3339("2023-07-31T10:16:54+05:00").unwrap();
@@ -70,7 +79,7 @@ fn convert_from_req_dto_without_rsv_limit_ok() {
         quantity: 26,
         applied_attr: None,
     };
-    let result = OrderLineModel::try_from(data, &policym, &pricem);
+    let result = OrderLineModel::try_from(data, &policym, &pricem, &[]);
     let m = result.unwrap();
     assert_eq!(m.price().unit(), 1015u32);
     assert_eq!(m.price().total(), 1015u32 * 26u32);
@@ -84,12 +93,17 @@ fn convert_from_req_dto_without_rsv_limit_ok() {
 fn convert_from_req_dto_with_rsv_limit_ok() {
     let (seller_id, product_id) = (19, 146);
     let policym = ProductPolicyModel {
+        store_id: seller_id,
         product_id,
+        version: 3,
         is_create: false,
         auto_cancel_secs: 69,
         warranty_hours: 23,
         max_num_rsv: 10,
         min_num_rsv: 2,
+        backorder: ProductBackorderPolicy::Disabled,
+        max_num_backorder: 0,
+        weight_gram: 0,
     };
     let pricem = {
         let start_after = DateTime::parse_from_rfc3339("2022-10-28T10:16:54+05:00").unwrap();
@@ -104,23 +118,116 @@ fn convert_from_req_dto_with_rsv_limit_ok() {
         quantity: 9,
         applied_attr: None,
     };
-    let result = OrderLineModel::try_from(data, &policym, &pricem);
+    let result = OrderLineModel::try_from(data, &policym, &pricem, &[]);
     let m = result.unwrap();
     assert_eq!(m.price().unit(), 987u32);
     assert_eq!(m.price().total(), 987u32 * 9u32);
     assert_eq!(m.qty.reserved, 9u32);
+    assert_eq!(m.policy.version, 3);
+}
+
+#[test]
+fn convert_from_req_dto_with_promotion_ok() {
+    let (seller_id, product_id) = (19, 146);
+    let policym = ProductPolicyModel {
+        store_id: seller_id,
+        product_id,
+        version: 0,
+        is_create: false,
+        auto_cancel_secs: 69,
+        warranty_hours: 23,
+        max_num_rsv: 0,
+        min_num_rsv: 0,
+        backorder: ProductBackorderPolicy::Disabled,
+        max_num_backorder: 0,
+        weight_gram: 0,
+    };
+    let now = LocalTime::now().fixed_offset();
+    let pricem = {
+        let ts = [
+            now - Duration::days(3),
+            now + Duration::days(3),
+            now - Duration::days(5),
+        ];
+        ProductPriceModel::from((product_id, 1000, ts, None))
+    };
+    let promos = [
+        ("PCT-OFF", vec![], PromotionRuleModel::PercentOff(10)),
+        (
+            "FIX-OFF",
+            vec![product_id],
+            PromotionRuleModel::FixedOff(130),
+        ),
+        (
+            "OTHER-PROD",
+            vec![product_id + 1],
+            PromotionRuleModel::FixedOff(500),
+        ),
+    ]
+    .into_iter()
+    .map(|(code, product_ids, rule)| PromotionModel {
+        code: code.to_string(),
+        store_id: seller_id,
+        product_ids,
+        rule,
+        start_after: now - Duration::days(1),
+        end_before: now + Duration::days(1),
+        max_usage: None,
+        num_used: 0,
+    })
+    .collect::<Vec<_>>();
+    let data = OrderLineRsvReqDto {
+        seller_id,
+        product_id,
+        quantity: 4,
+        applied_attr: None,
+    };
+    let result = OrderLineModel::try_from(data, &policym, &pricem, &promos);
+    let m = result.unwrap();
+    assert_eq!(m.price().unit(), 870u32);
+    assert_eq!(m.price().total(), 870u32 * 4u32);
+    let discount = m.discount.as_ref().unwrap();
+    assert_eq!(discount.code.as_str(), "FIX-OFF");
+    assert_eq!(discount.unit, 130u32);
+    let data = OrderLineRsvReqDto {
+        seller_id,
+        product_id: product_id + 2,
+        quantity: 4,
+        applied_attr: None,
+    };
+    let pricem = {
+        let ts = [
+            now - Duration::days(3),
+            now + Duration::days(3),
+            now - Duration::days(5),
+        ];
+        ProductPriceModel::from((product_id + 2, 1000, ts, None))
+    };
+    let policym = ProductPolicyModel {
+        product_id: product_id + 2,
+        ..policym
+    };
+    let result = OrderLineModel::try_from(data, &policym, &pricem, &promos);
+    let m = result.unwrap();
+    assert_eq!(m.price().unit(), 900u32);
+    assert_eq!(m.discount.as_ref().unwrap().code.as_str(), "PCT-OFF");
 }
 
 #[test]
 fn convert_from_req_dto_violate_rsv_limit() {
     let (seller_id, product_id) = (19, 146);
     let policym = ProductPolicyModel {
+        store_id: seller_id,
         product_id,
+        version: 0,
         is_create: false,
         auto_cancel_secs: 180,
         warranty_hours: 48,
         max_num_rsv: 10,
         min_num_rsv: 0,
+        backorder: ProductBackorderPolicy::Disabled,
+        max_num_backorder: 0,
+        weight_gram: 0,
     };
     let pricem = {
         let start_after = DateTime::parse_from_rfc3339("2022-10-28T10:16:54+05:00").unwrap();
@@ -135,7 +242,7 @@ fn convert_from_req_dto_violate_rsv_limit() {
         quantity: 11,
         applied_attr: None,
     };
-    let result = OrderLineModel::try_from(data, &policym, &pricem);
+    let result = OrderLineModel::try_from(data, &policym, &pricem, &[]);
     assert!(result.is_err());
     if let Err(e) = result {
         assert_eq!(e.code, AppErrorCode::ExceedingMaxLimit);
@@ -146,12 +253,17 @@ fn convert_from_req_dto_violate_rsv_limit() {
 fn convert_from_req_with_attributes_ok() {
     let (seller_id, product_id) = (19, 146);
     let policym = ProductPolicyModel {
+        store_id: seller_id,
         product_id,
+        version: 0,
         is_create: false,
         auto_cancel_secs: 69,
         warranty_hours: 23,
         max_num_rsv: 29,
         min_num_rsv: 5,
+        backorder: ProductBackorderPolicy::Disabled,
+        max_num_backorder: 0,
+        weight_gram: 0,
     };
     let pricem = {
         let start_after = DateTime::parse_from_rfc3339("2022-10-28T10:16:54+05:00").unwrap();
@@ -188,7 +300,7 @@ fn convert_from_req_with_attributes_ok() {
             quantity: 11,
             applied_attr: Some(applied_attr),
         };
-        let result = OrderLineModel::try_from(data, &policym, &pricem);
+        let result = OrderLineModel::try_from(data, &policym, &pricem, &[]);
         let m = result.unwrap();
         assert_eq!(m.price().unit(), expect_unitprice);
         assert_eq!(m.price().total(), expect_unitprice * 11u32);
@@ -201,12 +313,17 @@ fn convert_from_req_with_attributes_ok() {
 fn convert_from_req_with_attributes_error() {
     let (seller_id, product_id) = (19, 146);
     let policym = ProductPolicyModel {
+        store_id: seller_id,
         product_id,
+        version: 0,
         is_create: false,
         auto_cancel_secs: 69,
         warranty_hours: 23,
         max_num_rsv: 29,
         min_num_rsv: 5,
+        backorder: ProductBackorderPolicy::Disabled,
+        max_num_backorder: 0,
+        weight_gram: 0,
     };
     let pricem = {
         let start_after = DateTime::parse_from_rfc3339("2022-10-28T10:16:54+05:00").unwrap();
@@ -252,7 +369,7 @@ fn convert_from_req_with_attributes_error() {
             quantity: 11,
             applied_attr: Some(applied_attr),
         };
-        let result = OrderLineModel::try_from(data, &policym, &pricem);
+        let result = OrderLineModel::try_from(data, &policym, &pricem, &[]);
         assert!(result.is_err());
         if let Err(e) = result {
             assert_eq!(e.code, expect_ecode);
@@ -265,12 +382,17 @@ fn convert_from_req_with_attributes_error() {
 fn convert_from_req_dto_product_id_mismatch() {
     let (seller_id, product_id) = (19, 146);
     let policym = ProductPolicyModel {
+        store_id: seller_id,
         product_id,
+        version: 0,
         is_create: false,
         auto_cancel_secs: 180,
         warranty_hours: 48,
         max_num_rsv: 10,
         min_num_rsv: 0,
+        backorder: ProductBackorderPolicy::Disabled,
+        max_num_backorder: 0,
+        weight_gram: 0,
     };
     let pricem = {
         let start_after = DateTime::parse_from_rfc3339("2022-10-28T10:16:54+05:00").unwrap();
@@ -285,7 +407,7 @@ fn convert_from_req_dto_product_id_mismatch() {
         quantity: 2,
         applied_attr: None,
     };
-    let result = OrderLineModel::try_from(data, &policym, &pricem);
+    let result = OrderLineModel::try_from(data, &policym, &pricem, &[]);
     assert!(result.is_err());
     if let Err(e) = result {
         assert_eq!(e.code, AppErrorCode::DataCorruption);
@@ -498,6 +620,118 @@ fn update_payments_old_record_omitted() {
     );
 } // end of fn update_payments_old_record_omitted
 
+#[rustfmt::skip]
+#[test]
+fn cancel_unpaid_ok() {
+    let dt_now = LocalTime::now().fixed_offset();
+    let rsved_until = dt_now + Duration::hours(1);
+    let warranty_until = dt_now + Duration::days(1);
+    let seller_id = 123;
+    let mocked_data = vec![
+        ((seller_id, 812u64, 0u16), (7u32, 70u32), 10u32, 0u32, None, rsved_until, warranty_until, None),
+        ((seller_id, 890, 0), (10, 90), 9, 0, None, rsved_until, warranty_until, None),
+        ((seller_id, 890, 1), (11, 77), 7, 0, None, rsved_until, warranty_until, None),
+    ];
+    let mut models = ut_setup_order_lines(mocked_data);
+    let pids = [(seller_id, 812u64, 0u16), (seller_id, 890, 1)]
+        .into_iter().map(OrderLineIdentity::from).collect::<Vec<_>>();
+    let cancel = OrderLineCancelModel {usr_id: 1234, time: dt_now};
+    let errors = OrderLineModel::cancel_unpaid(&mut models, pids, cancel);
+    assert!(errors.is_empty());
+    models.iter().map(|m| {
+        let combo = (m.id().product_id(), m.id().attrs_seq_num());
+        let actual = m.cancelled.as_ref().map(|c| (c.usr_id, c.time));
+        let expect = match combo {
+            (812u64, 0u16) | (890, 1) => Some((1234u32, dt_now)),
+            _others => None,
+        };
+        assert_eq!(actual, expect);
+    }).count();
+    // cancelled lines can no longer be paid
+    let d_lines = vec![OrderLinePaidUpdateDto {
+        seller_id, product_id: 812, attr_set_seq: 0, qty: 1,
+    }];
+    let errors = OrderLineModel::update_payments(&mut models, d_lines, dt_now);
+    assert_eq!(errors.len(), 1);
+    assert!(matches!(errors[0].reason, OrderLinePayUpdateErrorReason::InvalidQuantity));
+} // end of fn cancel_unpaid_ok
+
+#[rustfmt::skip]
+#[test]
+fn cancel_unpaid_error() {
+    let dt_now = LocalTime::now().fixed_offset();
+    let rsved_until = dt_now + Duration::hours(1);
+    let warranty_until = dt_now + Duration::days(1);
+    let paid_last_update = dt_now - Duration::minutes(10);
+    let seller_id = 123;
+    let mocked_data = vec![
+        ((seller_id, 812u64, 0u16), (7u32, 70u32), 10u32, 0u32, None, rsved_until, warranty_until, None),
+        ((seller_id, 890, 0), (10, 90), 9, 1, Some(paid_last_update), rsved_until, warranty_until, None),
+        ((seller_id, 890, 1), (11, 77), 7, 0, None, dt_now - Duration::seconds(3), warranty_until, None),
+    ];
+    let mut models = ut_setup_order_lines(mocked_data);
+    models[0].cancelled = Some(OrderLineCancelModel {
+        usr_id: 1234, time: dt_now - Duration::minutes(1),
+    });
+    let pids = [(seller_id, 812u64, 0u16), (seller_id, 890, 0), (seller_id, 890, 1), (seller_id, 890, 2)]
+        .into_iter().map(OrderLineIdentity::from).collect::<Vec<_>>();
+    let cancel = OrderLineCancelModel {usr_id: 1234, time: dt_now};
+    let errors = OrderLineModel::cancel_unpaid(&mut models, pids, cancel);
+    assert_eq!(errors.len(), 4);
+    errors.iter().map(|e| {
+        let combo = (e.product_id, e.attr_set_seq);
+        let matched = match combo {
+            (812u64, 0u16) => matches!(e.reason, OrderLineCancelErrorReason::AlreadyCancelled),
+            (890, 0) => matches!(e.reason, OrderLineCancelErrorReason::AlreadyPaid),
+            (890, 1) => matches!(e.reason, OrderLineCancelErrorReason::ReservationExpired),
+            (890, 2) => matches!(e.reason, OrderLineCancelErrorReason::NotExist),
+            _others => false,
+        };
+        assert!(matched);
+    }).count();
+    assert!(models[1].cancelled.is_none());
+    assert!(models[2].cancelled.is_none());
+} // end of fn cancel_unpaid_error
+
+#[rustfmt::skip]
+#[test]
+fn backordered_line_payment_held() {
+    let dt_now = LocalTime::now().fixed_offset();
+    let rsved_until = dt_now - Duration::seconds(5);
+    let warranty_until = dt_now + Duration::days(1);
+    let seller_id = 123;
+    let mocked_data = vec![
+        ((seller_id, 812u64, 0u16), (7u32, 70u32), 10u32, 0u32, None, rsved_until, warranty_until, None),
+        ((seller_id, 890, 0), (10, 90), 9, 0, None, dt_now + Duration::hours(1), warranty_until, None),
+    ];
+    let mut models = ut_setup_order_lines(mocked_data);
+    models[0].qty.backordered = 4;
+    assert!(models[0].qty.is_backordered());
+    let d_lines = vec![
+        OrderLinePaidUpdateDto {seller_id, product_id: 812, attr_set_seq: 0, qty: 6},
+        OrderLinePaidUpdateDto {seller_id, product_id: 890, attr_set_seq: 0, qty: 9},
+    ];
+    let errors = OrderLineModel::update_payments(&mut models, d_lines, dt_now);
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].product_id, 812);
+    assert!(matches!(errors[0].reason, OrderLinePayUpdateErrorReason::InvalidQuantity));
+    assert_eq!(models[0].qty.paid, 0);
+    assert_eq!(models[1].qty.paid, 9);
+    // the buyer waited too long for new stock, the line will be discarded
+    let pids = vec![OrderLineIdentity::from((seller_id, 812u64, 0u16))];
+    let cancel = OrderLineCancelModel {usr_id: 1234, time: dt_now};
+    let errors = OrderLineModel::cancel_unpaid(&mut models, pids.clone(), cancel);
+    assert_eq!(errors.len(), 1);
+    assert!(matches!(errors[0].reason, OrderLineCancelErrorReason::ReservationExpired));
+    assert!(models[0].cancelled.is_none());
+    // backordered line can be cancelled while waiting for new stock
+    models[0].policy.reserved_until = dt_now + Duration::days(1);
+    let cancel = OrderLineCancelModel {usr_id: 1234, time: dt_now};
+    let errors = OrderLineModel::cancel_unpaid(&mut models, pids, cancel);
+    assert!(errors.is_empty());
+    assert!(models[0].cancelled.is_some());
+} // end of fn backordered_line_payment_held
+
 #[rustfmt::skip]
 fn convert_to_olset_common() -> OrderLineModelSet {
     let create_time = LocalTime::now().fixed_offset();
@@ -538,7 +772,7 @@ fn convert_to_olset_common() -> OrderLineModelSet {
 #[test]
 fn convert_to_pay_dto_ok() {
     let olset = convert_to_olset_common();
-    let result = OrderCreateRespOkDto::try_from(olset);
+    let result = OrderCreateRespOkDto::try_from((olset, vec![]));
     assert!(result.is_ok());
     if let Ok(dto) = result {
         assert_eq!(dto.order_id.as_str(), "allahbomarcasm");
@@ -567,6 +801,55 @@ fn convert_to_pay_dto_ok() {
     }
 } // end of fn convert_to_pay_dto_ok
 
+#[rustfmt::skip]
+#[test]
+fn convert_to_pay_dto_with_tax_ok() {
+    let create_time = LocalTime::now().fixed_offset();
+    let reserved_t = create_time + Duration::hours(1);
+    let warranty_t = create_time + Duration::days(1);
+    let mock_seller_ids = [123, 124, 125];
+    let mocked_linedata = vec![
+        ((mock_seller_ids[0], 812u64, 0u16), (7u32, 70u32), 10u32, 0u32, None, reserved_t, warranty_t, None),
+        ((mock_seller_ids[0], 813, 0), (25, 50), 2, 0, None, reserved_t, warranty_t, None),
+        ((mock_seller_ids[1], 890, 0), (10, 90), 9, 0, None, reserved_t, warranty_t, None),
+    ];
+    let mut olines = ut_setup_order_lines(mocked_linedata);
+    let rates = vec![TaxRateDto {
+        country: CountryCode::TW, region: None, store_id: Some(mock_seller_ids[0]),
+        rate: "0.1".to_string(),
+    }];
+    let ms = TaxRateModelSet::try_from(rates).unwrap();
+    let addr = PhyAddrModel {
+        country: CountryCode::TW, region: "Taipei".to_string(), city: "Taipei".to_string(),
+        distinct: "Da-an".to_string(), street_name: None, detail: "4F".to_string(),
+    };
+    OrderLineModel::apply_tax(&mut olines, &ms, &addr);
+    let tax = olines[1].tax.as_ref().unwrap();
+    assert_eq!(tax.unit, 3u32);
+    assert!(olines[2].tax.is_none());
+    let currency = ut_common_order_currency(mock_seller_ids);
+    let args = ("ordertaxcomb".to_string(), 299u32, create_time, currency, olines);
+    let olset = OrderLineModelSet::try_from(args).unwrap();
+    let dto = OrderCreateRespOkDto::try_from((olset, vec![])).unwrap();
+    assert_eq!(dto.reserved_lines.len(), 3);
+    dto.reserved_lines.iter().map(|l| {
+        let actual = (
+            l.amount.unit.as_str(), l.amount.total.as_str(),
+            l.tax.as_ref().map(|t| (t.unit.as_str(), t.total.as_str())),
+        );
+        let option_chk = match l.product_id {
+            812 => Some(("8.00", "80.00", Some(("1.00", "10.00")))),
+            813 => Some(("28.00", "56.00", Some(("3.00", "6.00")))),
+            _others => None,
+        };
+        if let Some(expect) = option_chk {
+            assert_eq!(actual, expect);
+        } else {
+            assert!(actual.2.is_none());
+        }
+    }).count();
+} // end of fn convert_to_pay_dto_with_tax_ok
+
 #[rustfmt::skip]
 #[test]
 fn convert_to_olset_dup_error() {
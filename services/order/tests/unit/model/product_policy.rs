@@ -1,6 +1,6 @@
 use super::ut_clone_productpolicy;
 use order::api::web::dto::ProductPolicyDto;
-use order::model::{ProductPolicyModel, ProductPolicyModelSet};
+use order::model::{ProductBackorderPolicy, ProductPolicyModel, ProductPolicyModelSet};
 
 #[test]
 fn validate_newdata_ok() {
@@ -10,11 +10,15 @@ fn validate_newdata_ok() {
     ]
     .into_iter()
     .map(|d| ProductPolicyDto {
+        seller_id: 1001,
         product_id: d.0,
         min_num_rsv: d.1,
         warranty_hours: d.2,
         auto_cancel_secs: d.3,
         max_num_rsv: d.4,
+        backorder: None,
+        max_num_backorder: None,
+        weight_gram: None,
     })
     .collect::<Vec<_>>();
     let result = ProductPolicyModelSet::validate(&newdata);
@@ -31,11 +35,15 @@ fn validate_newdata_error_limit() {
     ]
     .into_iter()
     .map(|d| ProductPolicyDto {
+        seller_id: 1001,
         product_id: d.0,
         min_num_rsv: d.1,
         warranty_hours: d.2,
         auto_cancel_secs: d.3,
         max_num_rsv: d.4,
+        backorder: None,
+        max_num_backorder: None,
+        weight_gram: None,
     })
     .collect::<Vec<_>>();
 
@@ -65,35 +73,61 @@ fn validate_newdata_error_limit() {
 #[test]
 fn update_instance_ok() {
     let init_data = [
-        (20903u64, 731u32, 271u32, false, 0u16, 0u16),
-        (144, 380, 30098, false, 8, 0),
-        (144, 3597, 478, false, 0, 0),
-        (123, 3600, 480, true, 26, 15),
+        (20903u64, 731u32, 271u32, false, 0u16, 0u16, 4u32),
+        (144, 380, 30098, false, 8, 0, 2),
+        (144, 3597, 478, true, 0, 0, 3),
+        (123, 3600, 480, true, 26, 15, 0),
+        (144, 3597, 478, true, 0, 0, 0),
     ]
     .into_iter()
-    .map(|d| ProductPolicyModel {
+    .enumerate()
+    .map(|(idx, d)| ProductPolicyModel {
+        store_id: if idx < 4 { 1001 } else { 1002 },
         product_id: d.0,
+        version: d.6,
         auto_cancel_secs: d.1,
         warranty_hours: d.2,
         is_create: d.3,
         max_num_rsv: d.4,
         min_num_rsv: d.5,
+        backorder: ProductBackorderPolicy::Disabled,
+        max_num_backorder: 0,
+        weight_gram: 0,
     })
     .collect::<Vec<_>>();
     let newdata = vec![
         ProductPolicyDto {
+            seller_id: 1001,
             product_id: 123,
             warranty_hours: 480,
             auto_cancel_secs: 3600,
             max_num_rsv: Some(26),
             min_num_rsv: Some(15),
+            backorder: None,
+            max_num_backorder: None,
+            weight_gram: None,
         },
         ProductPolicyDto {
+            seller_id: 1001,
             product_id: 144,
             warranty_hours: 478,
             auto_cancel_secs: 3597,
             max_num_rsv: None,
             min_num_rsv: None,
+            backorder: None,
+            max_num_backorder: None,
+            weight_gram: None,
+        },
+        ProductPolicyDto {
+            seller_id: 1002,
+            product_id: 144,
+            warranty_hours: 478,
+            auto_cancel_secs: 3597,
+            max_num_rsv: None,
+            min_num_rsv: None,
+            backorder: None,
+            max_num_backorder: None,
+            weight_gram: None,
         },
     ];
     let ms = ProductPolicyModelSet {
@@ -106,19 +140,29 @@ fn update_instance_ok() {
     assert_eq!(result.is_ok(), true);
     let updated = result.unwrap();
     {
-        assert_eq!(updated.policies.len(), 3);
+        assert_eq!(updated.policies.len(), 4);
         let actual = updated
             .policies
             .iter()
             .find(|m| m.product_id == 20903)
             .unwrap();
         assert_eq!(actual, &init_data[0]);
+        assert!(!actual.is_create);
+        // modified policy is appended as next version
         let actual = updated
             .policies
             .iter()
-            .find(|m| m.product_id == 144)
+            .find(|m| m.store_id == 1001 && m.product_id == 144)
             .unwrap();
         assert_eq!(actual, &init_data[2]);
+        assert!(actual.is_create);
+        // same product in different store is kept as separate policy
+        let actual = updated
+            .policies
+            .iter()
+            .find(|m| m.store_id == 1002 && m.product_id == 144)
+            .unwrap();
+        assert_eq!(actual, &init_data[4]);
         let actual = updated
             .policies
             .iter()
@@ -0,0 +1,195 @@
+use chrono::{DateTime, Duration, FixedOffset, Local as LocalTime};
+
+use order::api::web::dto::{
+    CouponErrorReason, PromotionDto, PromotionErrorReason, PromotionRuleDto,
+};
+use order::model::{PromotionModel, PromotionModelSet, PromotionRuleModel};
+
+fn ut_promotion_dto(
+    code: &str,
+    seller_id: u32,
+    rule: PromotionRuleDto,
+    period: (DateTime<FixedOffset>, DateTime<FixedOffset>),
+) -> PromotionDto {
+    PromotionDto {
+        code: code.to_string(),
+        seller_id,
+        product_ids: vec![],
+        rule,
+        start_after: period.0,
+        end_before: period.1,
+        max_usage: None,
+    }
+}
+
+fn ut_promotion_model(rule: PromotionRuleModel) -> PromotionModel {
+    let now = LocalTime::now().fixed_offset();
+    PromotionModel {
+        code: "utest-promo".to_string(),
+        store_id: 1024,
+        product_ids: vec![],
+        rule,
+        start_after: now - Duration::days(1),
+        end_before: now + Duration::days(1),
+        max_usage: None,
+        num_used: 0,
+    }
+}
+
+#[test]
+fn validate_dto_error() {
+    let now = LocalTime::now().fixed_offset();
+    let period = (now, now + Duration::hours(3));
+    let data = vec![
+        ut_promotion_dto("BLACK-FRI", 1024, PromotionRuleDto::PercentOff(20), period),
+        ut_promotion_dto("bad code", 1024, PromotionRuleDto::FixedOff(5), period),
+        ut_promotion_dto("BLACK-FRI", 1024, PromotionRuleDto::FixedOff(5), period),
+        ut_promotion_dto("OVER-100", 1024, PromotionRuleDto::PercentOff(101), period),
+        ut_promotion_dto(
+            "NO-FREE",
+            1024,
+            PromotionRuleDto::BuyXGetY { buy: 3, free: 0 },
+            period,
+        ),
+        ut_promotion_dto(
+            "REVERSED",
+            1024,
+            PromotionRuleDto::FixedOff(5),
+            (period.1, period.0),
+        ),
+    ];
+    let result = PromotionModelSet::validate(&data);
+    let errors = result.unwrap_err();
+    let actual = errors
+        .iter()
+        .map(|e| (e.code.as_str(), &e.reason))
+        .collect::<Vec<_>>();
+    let expect = [
+        ("bad code", &PromotionErrorReason::InvalidCode),
+        ("BLACK-FRI", &PromotionErrorReason::DuplicateCode),
+        ("OVER-100", &PromotionErrorReason::InvalidRule),
+        ("NO-FREE", &PromotionErrorReason::InvalidRule),
+        ("REVERSED", &PromotionErrorReason::InvalidPeriod),
+    ];
+    assert_eq!(actual, expect);
+    let result = PromotionModelSet::validate(&[]);
+    let errors = result.unwrap_err();
+    assert_eq!(errors[0].reason, PromotionErrorReason::EmptyInput);
+}
+
+#[test]
+fn calc_unit_discount_ok() {
+    let m = ut_promotion_model(PromotionRuleModel::PercentOff(15));
+    assert_eq!(m.unit_discount(1999, 3), 299);
+    let m = ut_promotion_model(PromotionRuleModel::PercentOff(100));
+    assert_eq!(m.unit_discount(1999, 3), 1999);
+    let m = ut_promotion_model(PromotionRuleModel::FixedOff(250));
+    assert_eq!(m.unit_discount(1999, 3), 250);
+    assert_eq!(m.unit_discount(180, 3), 180);
+    let m = ut_promotion_model(PromotionRuleModel::BuyXGetY { buy: 2, free: 1 });
+    // 7 items contain 2 groups, 2 of them are free, (2 * 300) / 7 = 85.71
+    assert_eq!(m.unit_discount(300, 7), 85);
+    assert_eq!(m.unit_discount(300, 6), 100);
+    assert_eq!(m.unit_discount(300, 2), 0);
+    assert_eq!(m.unit_discount(300, 0), 0);
+}
+
+#[test]
+fn check_applicable_products() {
+    let mut m = ut_promotion_model(PromotionRuleModel::FixedOff(1));
+    assert!(m.is_applicable(1024, 8801));
+    assert!(m.is_applicable(1024, 8802));
+    assert!(!m.is_applicable(1025, 8801));
+    m.product_ids = vec![8802, 8803];
+    assert!(!m.is_applicable(1024, 8801));
+    assert!(m.is_applicable(1024, 8802));
+    assert!(!m.is_applicable(1025, 8803));
+}
+
+#[test]
+fn update_keep_usage_count() {
+    let mut saved = ut_promotion_model(PromotionRuleModel::FixedOff(30));
+    saved.num_used = 17;
+    let saved_code = saved.code.clone();
+    let ms = PromotionModelSet {
+        promotions: vec![saved],
+    };
+    let now = LocalTime::now().fixed_offset();
+    let period = (now, now + Duration::hours(3));
+    let data = vec![
+        ut_promotion_dto(
+            saved_code.as_str(),
+            1024,
+            PromotionRuleDto::PercentOff(12),
+            period,
+        ),
+        ut_promotion_dto("NEW-ONE", 1024, PromotionRuleDto::FixedOff(9), period),
+    ];
+    let result = ms.update(data);
+    let ms = result.unwrap();
+    assert_eq!(ms.promotions.len(), 2);
+    let m = ms.promotions.iter().find(|m| m.code == saved_code).unwrap();
+    assert_eq!(m.num_used, 17);
+    assert_eq!(m.rule, PromotionRuleModel::PercentOff(12));
+    let m = ms.promotions.iter().find(|m| m.code == "NEW-ONE").unwrap();
+    assert_eq!(m.num_used, 0);
+}
+
+#[test]
+fn update_code_occupied() {
+    let saved = ut_promotion_model(PromotionRuleModel::FixedOff(30));
+    let saved_code = saved.code.clone();
+    let ms = PromotionModelSet {
+        promotions: vec![saved],
+    };
+    let now = LocalTime::now().fixed_offset();
+    let period = (now, now + Duration::hours(3));
+    let data = vec![ut_promotion_dto(
+        saved_code.as_str(),
+        1025,
+        PromotionRuleDto::PercentOff(12),
+        period,
+    )];
+    let result = ms.update(data);
+    let errors = result.err().unwrap();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].code, saved_code);
+    assert_eq!(errors[0].reason, PromotionErrorReason::CodeOccupied);
+}
+
+#[test]
+fn check_redeemable_error() {
+    let now = LocalTime::now().fixed_offset();
+    let mut m0 = ut_promotion_model(PromotionRuleModel::FixedOff(30));
+    m0.code = "EXPIRED".to_string();
+    m0.end_before = now - Duration::minutes(1);
+    let mut m1 = ut_promotion_model(PromotionRuleModel::FixedOff(30));
+    m1.code = "USED-UP".to_string();
+    m1.max_usage = Some(5);
+    m1.num_used = 5;
+    let mut m2 = ut_promotion_model(PromotionRuleModel::FixedOff(30));
+    m2.code = "AVAIL".to_string();
+    m2.max_usage = Some(5);
+    m2.num_used = 4;
+    let ms = PromotionModelSet {
+        promotions: vec![m0, m1, m2],
+    };
+    let codes = ["EXPIRED", "USED-UP", "AVAIL", "MISSING"]
+        .into_iter()
+        .map(String::from)
+        .collect::<Vec<_>>();
+    let result = ms.check_redeemable(&codes, now);
+    let errors = result.unwrap_err();
+    let actual = errors
+        .iter()
+        .map(|e| (e.code.as_str(), &e.reason))
+        .collect::<Vec<_>>();
+    let expect = [
+        ("EXPIRED", &CouponErrorReason::NotInPeriod),
+        ("USED-UP", &CouponErrorReason::UsageExhausted),
+        ("MISSING", &CouponErrorReason::NotExist),
+    ];
+    assert_eq!(actual, expect);
+    let result = ms.check_redeemable(&codes[2..3], now);
+    assert!(result.is_ok());
+}
@@ -0,0 +1,186 @@
+use chrono::{Duration, Local};
+
+use order::api::dto::ShippingMethod;
+use order::api::web::dto::{
+    ShipmentCreateReqDto, ShipmentErrorReason, ShipmentLineErrorReason, ShipmentLineReqDto,
+    ShipmentStatusDto, ShipmentUpdateReqDto,
+};
+use order::constant::hard_limit;
+use order::model::{
+    OrderLineAppliedPolicyModel, OrderLineIdentity, OrderLineModel, OrderLinePriceModel,
+    OrderLineQuantityModel, ProdAttriPriceModel, ShipmentModel, ShipmentStatus,
+};
+
+#[rustfmt::skip]
+fn ut_setup_orderlines(seller_id: u32) -> Vec<OrderLineModel> {
+    let time_now = Local::now().fixed_offset();
+    let reserved_until = time_now + Duration::minutes(5);
+    let warranty_until = time_now + Duration::days(14);
+    [((501u64, 0u16), 8u32, 6u32), ((501, 1), 3, 3), ((502, 0), 4, 0)]
+        .into_iter()
+        .map(|((product_id, attr_seq), reserved, paid)| {
+            let id_ = OrderLineIdentity::from((seller_id, product_id, attr_seq));
+            let price = OrderLinePriceModel::from((10, 10 * reserved));
+            let qty = OrderLineQuantityModel {
+                reserved, paid, paid_last_update: Some(time_now), backordered: 0,
+            };
+            let policy = OrderLineAppliedPolicyModel {reserved_until, warranty_until, version: 0};
+            let attrs_charge = ProdAttriPriceModel::from((time_now, None));
+            OrderLineModel::from((id_, price, policy, qty, attrs_charge))
+        })
+        .collect()
+}
+
+fn ut_create_req(
+    seller_id: u32,
+    tracking_number: Option<&str>,
+    lines: Vec<(u64, u16, u32)>,
+) -> ShipmentCreateReqDto {
+    let lines = lines
+        .into_iter()
+        .map(|(product_id, attr_set_seq, quantity)| ShipmentLineReqDto {
+            product_id,
+            attr_set_seq,
+            quantity,
+        })
+        .collect();
+    ShipmentCreateReqDto {
+        seller_id,
+        carrier: ShippingMethod::UPS,
+        tracking_number: tracking_number.map(|v| v.to_string()),
+        lines,
+    }
+}
+
+#[test]
+fn create_ok() {
+    let seller_id = 37u32;
+    let time_now = Local::now().fixed_offset();
+    let o_lines = ut_setup_orderlines(seller_id);
+    let req = ut_create_req(seller_id, None, vec![(501, 0, 4), (501, 1, 3)]);
+    let result = ShipmentModel::try_create(req, &o_lines, &[], time_now);
+    assert!(result.is_ok());
+    let saved = vec![result.unwrap()];
+    assert_eq!(saved[0].seq, 0);
+    assert_eq!(saved[0].status, ShipmentStatus::Packed);
+    assert_eq!(ShipmentModel::num_shipped(&saved, o_lines[0].id()), 4);
+    assert_eq!(ShipmentModel::num_delivered(&saved, o_lines[0].id()), 0);
+    // ------ subcase, the same line appears twice in the request
+    let req = ut_create_req(seller_id, Some("1Z-88"), vec![(501, 0, 1), (501, 0, 1)]);
+    let result = ShipmentModel::try_create(req, &o_lines, &saved, time_now);
+    assert!(result.is_ok());
+    let obj = result.unwrap();
+    assert_eq!(obj.seq, 1);
+    assert_eq!(obj.tracking_number.as_deref(), Some("1Z-88"));
+}
+
+#[test]
+fn create_err_lines() {
+    let seller_id = 37u32;
+    let time_now = Local::now().fixed_offset();
+    let o_lines = ut_setup_orderlines(seller_id);
+    let req = ut_create_req(seller_id, None, vec![]);
+    let result = ShipmentModel::try_create(req, &o_lines, &[], time_now);
+    assert!(matches!(
+        result.err().unwrap().reason,
+        ShipmentErrorReason::EmptyLines
+    ));
+    let req = ut_create_req(
+        seller_id,
+        None,
+        vec![
+            (501, 0, 4),
+            (501, 0, 3),
+            (501, 1, 0),
+            (502, 0, 1),
+            (503, 0, 1),
+        ],
+    );
+    let result = ShipmentModel::try_create(req, &o_lines, &[], time_now);
+    let e = result.err().unwrap();
+    assert!(matches!(e.reason, ShipmentErrorReason::InvalidLines));
+    let lines = e.lines.unwrap();
+    assert_eq!(lines.len(), 5);
+    lines
+        .iter()
+        .map(|l| {
+            let combo = (l.product_id, l.attr_set_seq);
+            match combo {
+                (501, 0) | (502, 0) => {
+                    assert!(matches!(l.reason, ShipmentLineErrorReason::QtyLimitExceed))
+                }
+                (501, 1) => assert!(matches!(l.reason, ShipmentLineErrorReason::InvalidQuantity)),
+                (503, 0) => assert!(matches!(l.reason, ShipmentLineErrorReason::NotExist)),
+                _others => panic!("unexpected line"),
+            }
+        })
+        .count();
+}
+
+#[test]
+fn create_err_tracking_number() {
+    let seller_id = 37u32;
+    let time_now = Local::now().fixed_offset();
+    let o_lines = ut_setup_orderlines(seller_id);
+    let too_long = "9".repeat(hard_limit::MAX_LEN_TRACKING_NUMBER + 1);
+    for invalid in ["", "AB 1234", "順豐0039", too_long.as_str()] {
+        let req = ut_create_req(seller_id, Some(invalid), vec![(501, 0, 1)]);
+        let result = ShipmentModel::try_create(req, &o_lines, &[], time_now);
+        assert!(matches!(
+            result.err().unwrap().reason,
+            ShipmentErrorReason::InvalidTrackingNumber
+        ));
+    }
+}
+
+#[test]
+fn update_status_transition() {
+    let seller_id = 37u32;
+    let time_now = Local::now().fixed_offset();
+    let o_lines = ut_setup_orderlines(seller_id);
+    let req = ut_create_req(seller_id, None, vec![(501, 0, 6)]);
+    let mut obj = ShipmentModel::try_create(req, &o_lines, &[], time_now).unwrap();
+    // tracking number is required after the parcel leaves the seller
+    let req = ShipmentUpdateReqDto {
+        seller_id,
+        status: ShipmentStatusDto::Shipped,
+        tracking_number: None,
+    };
+    let result = obj.update(req, time_now);
+    assert!(matches!(
+        result.err().unwrap().reason,
+        ShipmentErrorReason::MissingTrackingNumber
+    ));
+    let req = ShipmentUpdateReqDto {
+        seller_id,
+        status: ShipmentStatusDto::Shipped,
+        tracking_number: Some("FX-01928".to_string()),
+    };
+    assert!(obj.update(req, time_now).is_ok());
+    assert_eq!(obj.status, ShipmentStatus::Shipped);
+    assert_eq!(obj.shipped_time, Some(time_now));
+    // ------ subcase, status cannot go back
+    for status in [ShipmentStatusDto::Packed, ShipmentStatusDto::Shipped] {
+        let req = ShipmentUpdateReqDto {
+            seller_id,
+            status,
+            tracking_number: None,
+        };
+        let result = obj.update(req, time_now);
+        assert!(matches!(
+            result.err().unwrap().reason,
+            ShipmentErrorReason::InvalidTransition
+        ));
+    }
+    let later = time_now + Duration::days(2);
+    let req = ShipmentUpdateReqDto {
+        seller_id,
+        status: ShipmentStatusDto::Delivered,
+        tracking_number: None,
+    };
+    assert!(obj.update(req, later).is_ok());
+    assert_eq!(obj.shipped_time, Some(time_now));
+    assert_eq!(obj.delivered_time, Some(later));
+    let saved = vec![obj];
+    assert_eq!(ShipmentModel::num_delivered(&saved, o_lines[0].id()), 6);
+}
@@ -0,0 +1,273 @@
+use std::collections::HashMap;
+
+use ecommerce_common::api::dto::CountryCode;
+use ecommerce_common::model::order::PhyAddrModel;
+
+use order::api::dto::ShippingMethod;
+use order::api::web::dto::{
+    OrderLineRsvReqDto, ShipOptionMethodErrorReason, ShipOptionSellerErrorReason,
+    ShippingMethodDto, ShippingMethodErrorReason, ShippingRateDto,
+};
+use order::constant::hard_limit;
+use order::model::{
+    ProductBackorderPolicy, ProductPolicyModel, ProductPolicyModelSet, ShippingMethodModelSet,
+    ShippingOptionModel,
+};
+
+fn ut_rate_dto(
+    country: CountryCode,
+    region: Option<&str>,
+    weight: u32,
+    fee: u32,
+) -> ShippingRateDto {
+    ShippingRateDto {
+        country,
+        region: region.map(String::from),
+        max_weight_gram: weight,
+        fee,
+    }
+}
+
+fn ut_setup_methods() -> ShippingMethodModelSet {
+    let data = vec![
+        ShippingMethodDto {
+            seller_id: 1013,
+            method: ShippingMethod::UPS,
+            rates: vec![
+                ut_rate_dto(CountryCode::TW, None, 1000, 90),
+                ut_rate_dto(CountryCode::TW, None, 5000, 160),
+                ut_rate_dto(CountryCode::TW, Some("Hualien"), 1000, 150),
+                ut_rate_dto(CountryCode::JP, None, 2000, 480),
+            ],
+        },
+        ShippingMethodDto {
+            seller_id: 1013,
+            method: ShippingMethod::BlackCatExpress,
+            rates: vec![ut_rate_dto(CountryCode::TW, None, 3000, 70)],
+        },
+        ShippingMethodDto {
+            seller_id: 1014,
+            method: ShippingMethod::FedEx,
+            rates: vec![ut_rate_dto(CountryCode::ID, Some(""), 800, 30)],
+        },
+    ];
+    let result = ShippingMethodModelSet::try_from(data);
+    assert!(result.is_ok());
+    result.unwrap()
+}
+
+fn ut_setup_address(country: CountryCode, region: &str) -> PhyAddrModel {
+    PhyAddrModel {
+        country,
+        region: region.to_string(),
+        city: "Shoufeng".to_string(),
+        distinct: "Zhixue".to_string(),
+        street_name: None,
+        detail: "university road 2-123".to_string(),
+    }
+}
+
+#[test]
+fn convert_from_dto_ok() {
+    let ms = ut_setup_methods();
+    assert_eq!(ms.methods.len(), 3);
+    let m = ms.find(1013, &ShippingMethod::UPS).unwrap();
+    assert_eq!(m.rates.len(), 4);
+    assert_eq!(m.rates[2].region.as_deref(), Some("Hualien"));
+    // empty region is treated as whole country
+    let m = ms.find(1014, &ShippingMethod::FedEx).unwrap();
+    assert!(m.rates[0].region.is_none());
+    assert!(ms.find(1014, &ShippingMethod::UPS).is_none());
+    assert!(ms.contains_store(1014));
+    assert!(!ms.contains_store(1015));
+}
+
+#[test]
+fn convert_from_dto_error() {
+    let result = ShippingMethodModelSet::try_from(vec![]);
+    let errors = result.err().unwrap();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].reason, ShippingMethodErrorReason::EmptyInput);
+
+    let too_many_rates = (0..=hard_limit::MAX_NUM_SHIPPING_RATES)
+        .map(|n| ut_rate_dto(CountryCode::TW, None, 100 + n as u32, 50))
+        .collect();
+    #[rustfmt::skip]
+    let data = vec![
+        ShippingMethodDto { seller_id: 0, method: ShippingMethod::UPS, rates: vec![] },
+        ShippingMethodDto { seller_id: 1013, method: ShippingMethod::Unknown, rates: vec![] },
+        ShippingMethodDto {
+            seller_id: 1013, method: ShippingMethod::UPS,
+            rates: vec![ut_rate_dto(CountryCode::Unknown, None, 100, 50)],
+        },
+        ShippingMethodDto {
+            seller_id: 1013, method: ShippingMethod::UPS, rates: vec![],
+        },
+        ShippingMethodDto {
+            seller_id: 1014, method: ShippingMethod::UPS,
+            rates: vec![ut_rate_dto(CountryCode::TW, Some(&"x".repeat(33)), 100, 50)],
+        },
+        ShippingMethodDto {
+            seller_id: 1014, method: ShippingMethod::FedEx,
+            rates: vec![ut_rate_dto(CountryCode::TW, None, 0, 50)],
+        },
+        ShippingMethodDto {
+            seller_id: 1015, method: ShippingMethod::FedEx,
+            rates: vec![
+                ut_rate_dto(CountryCode::TW, None, 100, 50),
+                ut_rate_dto(CountryCode::TW, Some(""), 100, 55),
+            ],
+        },
+        ShippingMethodDto { seller_id: 1016, method: ShippingMethod::FedEx, rates: too_many_rates },
+        ShippingMethodDto {
+            seller_id: 1017, method: ShippingMethod::FedEx,
+            rates: vec![ut_rate_dto(CountryCode::TW, None, 100, 50)],
+        },
+    ];
+    let result = ShippingMethodModelSet::try_from(data);
+    let errors = result.err().unwrap();
+    let actual = errors
+        .into_iter()
+        .map(|e| (e.seller_id, e.reason))
+        .collect::<Vec<_>>();
+    let expect = vec![
+        (0, ShippingMethodErrorReason::InvalidSeller),
+        (1013, ShippingMethodErrorReason::InvalidMethod),
+        (1013, ShippingMethodErrorReason::InvalidCountry),
+        (1013, ShippingMethodErrorReason::DuplicateMethod),
+        (1014, ShippingMethodErrorReason::InvalidRegion),
+        (1014, ShippingMethodErrorReason::InvalidWeight),
+        (1015, ShippingMethodErrorReason::DuplicateRate),
+        (1016, ShippingMethodErrorReason::TooManyRates),
+    ];
+    assert_eq!(actual, expect);
+} // end of fn convert_from_dto_error
+
+#[test]
+fn estimate_fee_ok() {
+    let ms = ut_setup_methods();
+    let m = ms.find(1013, &ShippingMethod::UPS).unwrap();
+    let addr = ut_setup_address(CountryCode::TW, "Taipei");
+    assert_eq!(m.estimate_fee(&addr, 0), Some(90));
+    assert_eq!(m.estimate_fee(&addr, 1000), Some(90));
+    assert_eq!(m.estimate_fee(&addr, 1001), Some(160));
+    assert_eq!(m.estimate_fee(&addr, 5001), None);
+    // the rate specific to the region takes precedence
+    let addr = ut_setup_address(CountryCode::TW, "Hualien");
+    assert_eq!(m.estimate_fee(&addr, 700), Some(150));
+    assert_eq!(m.estimate_fee(&addr, 1700), Some(160));
+    let addr = ut_setup_address(CountryCode::JP, "Osaka");
+    assert_eq!(m.estimate_fee(&addr, 1700), Some(480));
+    let addr = ut_setup_address(CountryCode::US, "Ohio");
+    assert_eq!(m.estimate_fee(&addr, 1), None);
+}
+
+#[test]
+fn apply_fee_ok() {
+    let ms = ut_setup_methods();
+    let mut options = vec![
+        ShippingOptionModel {
+            seller_id: 1013,
+            method: ShippingMethod::BlackCatExpress,
+            fee: 0,
+        },
+        ShippingOptionModel {
+            seller_id: 1014,
+            method: ShippingMethod::FedEx,
+            fee: 0,
+        },
+    ];
+    let weights = HashMap::from([(1013u32, 2500u32), (1014, 0)]);
+    let addr = ut_setup_address(CountryCode::TW, "Taichung");
+    let result = ShippingOptionModel::apply_fee(&mut options, &ms, Some(&addr), &weights);
+    // the seller 1014 does not deliver to the address
+    let errors = result.err().unwrap();
+    assert_eq!(errors.len(), 2);
+    assert!(errors[0].is_none());
+    let e = errors[1].as_ref().unwrap();
+    assert!(e.seller_id.is_none());
+    assert!(matches!(
+        e.method,
+        Some(ShipOptionMethodErrorReason::NotSupport)
+    ));
+
+    let addr = ut_setup_address(CountryCode::ID, "Bali");
+    let result = ShippingOptionModel::apply_fee(&mut options, &ms, Some(&addr), &weights);
+    let errors = result.err().unwrap();
+    assert!(errors[0].is_some());
+    assert!(errors[1].is_none());
+    assert_eq!(options[1].fee, 30);
+
+    let weights = HashMap::from([(1013u32, 2500u32)]);
+    let result = ShippingOptionModel::apply_fee(&mut options[..1], &ms, None, &weights);
+    assert!(result.is_ok());
+    assert_eq!(options[0].fee, 0);
+    let addr = ut_setup_address(CountryCode::TW, "Taichung");
+    let result = ShippingOptionModel::apply_fee(&mut options[..1], &ms, Some(&addr), &weights);
+    assert!(result.is_ok());
+    assert_eq!(options[0].fee, 70);
+} // end of fn apply_fee_ok
+
+#[test]
+fn apply_fee_unsupported() {
+    let ms = ut_setup_methods();
+    #[rustfmt::skip]
+    let mut options = [
+        (1013u32, ShippingMethod::FedEx), (1015, ShippingMethod::UPS), (1014, ShippingMethod::UPS),
+    ]
+    .into_iter()
+    .map(|(seller_id, method)| ShippingOptionModel { seller_id, method, fee: 0 })
+    .collect::<Vec<_>>();
+    let weights = HashMap::from([(1013u32, 100u32), (1015, 100)]);
+    let result = ShippingOptionModel::apply_fee(&mut options, &ms, None, &weights);
+    let errors = result.err().unwrap();
+    assert_eq!(errors.len(), 3);
+    let e = errors[0].as_ref().unwrap();
+    assert!(matches!(
+        e.method,
+        Some(ShipOptionMethodErrorReason::NotSupport)
+    ));
+    // the seller has not registered any shipping method, delivery is free
+    assert!(errors[1].is_none());
+    assert_eq!(options[1].fee, 0);
+    // no line is purchased from the seller
+    let e = errors[2].as_ref().unwrap();
+    assert!(matches!(
+        e.seller_id,
+        Some(ShipOptionSellerErrorReason::NotExist)
+    ));
+}
+
+#[test]
+fn total_weight_by_seller() {
+    let policies = [(1013u32, 80u64, 250u32), (1013, 81, 1200), (1014, 90, 0)]
+        .into_iter()
+        .map(|(store_id, product_id, weight_gram)| ProductPolicyModel {
+            store_id,
+            product_id,
+            version: 0,
+            auto_cancel_secs: 600,
+            warranty_hours: 48,
+            is_create: false,
+            max_num_rsv: 0,
+            min_num_rsv: 0,
+            backorder: ProductBackorderPolicy::Disabled,
+            max_num_backorder: 0,
+            weight_gram,
+        })
+        .collect();
+    let ms_policy = ProductPolicyModelSet { policies };
+    let lines = [(1013u32, 80u64, 3u32), (1013, 81, 2), (1014, 90, 7)]
+        .into_iter()
+        .map(|(seller_id, product_id, quantity)| OrderLineRsvReqDto {
+            seller_id,
+            product_id,
+            quantity,
+            applied_attr: None,
+        })
+        .collect::<Vec<_>>();
+    let actual = ms_policy.total_weight(&lines);
+    assert_eq!(actual.len(), 2);
+    assert_eq!(actual.get(&1013), Some(&3150));
+    assert_eq!(actual.get(&1014), Some(&0));
+}
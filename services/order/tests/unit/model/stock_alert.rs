@@ -0,0 +1,191 @@
+use chrono::{DateTime, Duration, Utc};
+
+use ecommerce_common::error::AppErrorCode;
+
+use order::api::rpc::dto::StockAlertThresholdDto;
+use order::model::{
+    ProductStockModel, StockAlertKind, StockAlertModel, StockAlertThresholdModel,
+    StockQuantityModel, StoreStockModel,
+};
+
+pub(crate) fn ut_setup_store_stock(
+    store_id: u32,
+    time_now: DateTime<Utc>,
+    data: Vec<(u64, i64, u32, u32, u32)>,
+) -> StoreStockModel {
+    // each tuple contains product ID, number of days to expiry, total, cancelled,
+    // and booked quantities
+    let products = data
+        .into_iter()
+        .map(|(id_, days, total, cancelled, booked)| ProductStockModel {
+            warehouse_id: 0,
+            id_,
+            expiry: time_now + Duration::days(days),
+            quantity: StockQuantityModel::new(total, cancelled, booked, None),
+            is_create: false,
+            arrival: None,
+        })
+        .collect();
+    StoreStockModel { store_id, products }
+}
+
+#[test]
+fn threshold_convert_from_dto() {
+    let data = StockAlertThresholdDto {
+        store_id: 1012,
+        low_stock_qty: 5,
+        expiry_within_days: 14,
+    };
+    let result = StockAlertThresholdModel::try_from(data);
+    assert!(result.is_ok());
+    let m = result.unwrap();
+    assert_eq!(m.store_id, 1012);
+    assert_eq!(m.low_stock_qty, 5);
+    assert_eq!(m.expiry_within_days, 14);
+    let data = StockAlertThresholdDto {
+        store_id: 1012,
+        low_stock_qty: 5,
+        expiry_within_days: 366,
+    };
+    let result = StockAlertThresholdModel::try_from(data);
+    assert!(result.is_err());
+    if let Err(e) = result {
+        assert_eq!(e.code, AppErrorCode::InvalidInput);
+    }
+}
+
+#[test]
+fn detect_low_stock_expiring_ok() {
+    let time_now = DateTime::parse_from_rfc3339("2024-03-01T09:00:00+00:00")
+        .unwrap()
+        .to_utc();
+    let store = ut_setup_store_stock(
+        1012,
+        time_now,
+        vec![
+            // total available 3 (1 + 2), one of the batches is about to expire
+            (2001, 3, 4, 1, 2),
+            (2001, 60, 2, 0, 0),
+            // total available 10, no alert
+            (2002, 90, 10, 0, 0),
+            // expired batch is excluded, the remaining batch is sold out
+            (2003, -1, 20, 0, 0),
+            (2003, 5, 9, 0, 9),
+        ],
+    );
+    let threshold = StockAlertThresholdModel {
+        store_id: 1012,
+        low_stock_qty: 4,
+        expiry_within_days: 7,
+    };
+    let alerts = threshold.detect(&store, time_now);
+    assert_eq!(alerts.len(), 3);
+    let expect = [
+        (StockAlertKind::LowStock, 2001, None, 3),
+        (
+            StockAlertKind::ExpiringBatch,
+            2001,
+            Some(time_now + Duration::days(3)),
+            1,
+        ),
+        (StockAlertKind::LowStock, 2003, None, 0),
+    ];
+    alerts
+        .iter()
+        .zip(expect.iter())
+        .map(|(actual, expect)| {
+            assert_eq!(actual.kind, expect.0);
+            assert_eq!(actual.product_id, expect.1);
+            assert_eq!(actual.expiry, expect.2);
+            assert_eq!(actual.num_avail, expect.3);
+        })
+        .count();
+}
+
+#[test]
+fn detect_low_stock_all_expired() {
+    let time_now = DateTime::parse_from_rfc3339("2024-03-01T09:00:00+00:00")
+        .unwrap()
+        .to_utc();
+    let store = ut_setup_store_stock(
+        1012,
+        time_now,
+        vec![
+            // all batches expired, considered as out of stock
+            (2004, -1, 20, 0, 0),
+            (2004, -3, 6, 0, 1),
+            (2005, 30, 8, 0, 0),
+        ],
+    );
+    let threshold = StockAlertThresholdModel {
+        store_id: 1012,
+        low_stock_qty: 4,
+        expiry_within_days: 7,
+    };
+    let alerts = threshold.detect(&store, time_now);
+    assert_eq!(alerts.len(), 1);
+    assert_eq!(alerts[0].kind, StockAlertKind::LowStock);
+    assert_eq!(alerts[0].product_id, 2004);
+    assert_eq!(alerts[0].expiry, None);
+    assert_eq!(alerts[0].num_avail, 0);
+}
+
+#[test]
+fn detect_disabled() {
+    let time_now = Utc::now();
+    let store = ut_setup_store_stock(1013, time_now, vec![(2001, 1, 4, 1, 2)]);
+    let threshold = StockAlertThresholdModel {
+        store_id: 1013,
+        low_stock_qty: 0,
+        expiry_within_days: 0,
+    };
+    let alerts = threshold.detect(&store, time_now);
+    assert!(alerts.is_empty());
+}
+
+#[test]
+fn filter_sent_ok() {
+    let time_now = Utc::now();
+    let detected = vec![
+        StockAlertModel {
+            kind: StockAlertKind::LowStock,
+            product_id: 2001,
+            expiry: None,
+            num_avail: 2,
+        },
+        StockAlertModel {
+            kind: StockAlertKind::ExpiringBatch,
+            product_id: 2001,
+            expiry: Some(time_now),
+            num_avail: 2,
+        },
+        StockAlertModel {
+            kind: StockAlertKind::ExpiringBatch,
+            product_id: 2002,
+            expiry: Some(time_now),
+            num_avail: 5,
+        },
+    ];
+    let sent = vec![
+        StockAlertModel {
+            kind: StockAlertKind::LowStock,
+            product_id: 2001,
+            expiry: None,
+            num_avail: 3, // quantity changed after the alert was sent
+        },
+        StockAlertModel {
+            kind: StockAlertKind::ExpiringBatch,
+            product_id: 2001,
+            expiry: Some(time_now + Duration::days(1)),
+            num_avail: 2,
+        },
+    ];
+    let (still_sent, unsent) = StockAlertModel::filter_sent(detected, &sent);
+    assert_eq!(still_sent.len(), 1);
+    assert_eq!(still_sent[0].kind, StockAlertKind::LowStock);
+    assert_eq!(still_sent[0].num_avail, 2);
+    assert_eq!(unsent.len(), 2);
+    assert!(unsent
+        .iter()
+        .all(|a| a.kind == StockAlertKind::ExpiringBatch));
+}
@@ -1,21 +1,23 @@
 use std::collections::HashMap;
 
-use chrono::{DateTime, Duration};
+use chrono::{DateTime, Duration, FixedOffset};
 use rust_decimal::Decimal;
 
-use ecommerce_common::api::dto::CurrencyDto;
+use ecommerce_common::api::dto::{CountryCode, CurrencyDto};
 use ecommerce_common::error::AppErrorCode;
+use ecommerce_common::model::order::PhyAddrModel;
 
 use order::api::rpc::dto::{
-    InventoryEditStockLevelDto, StockLevelPresentDto, StockLevelReturnDto, StockQuantityPresentDto,
-    StockReturnErrorReason,
+    InventoryEditStockLevelDto, StockAllocPolicyDto, StockAllocStrategyDto, StockLevelPresentDto,
+    StockLevelReturnDto, StockQuantityPresentDto, StockReturnErrorReason, StockWarehouseDto,
 };
 use order::api::web::dto::OrderLineCreateErrorReason;
 use order::model::{
     CurrencyModel, OrderCurrencyModel, OrderLineAppliedPolicyModel, OrderLineIdentity,
     OrderLineModel, OrderLineModelSet, OrderLinePriceModel, OrderLineQuantityModel,
-    ProdAttriPriceModel, ProductStockModel, StockLevelModelSet, StockQtyRsvModel,
-    StockQuantityModel, StoreStockModel,
+    ProdAttriPriceModel, ProductStockModel, StockAllocPolicyModel, StockAllocPolicyModelSet,
+    StockAllocStrategy, StockLevelModelSet, StockQtyRsvModel, StockQuantityModel,
+    StockWarehouseModel, StockWarehouseModelSet, StoreStockModel,
 };
 
 use crate::model::verify_stocklvl_model;
@@ -36,9 +38,10 @@ fn ut_mock_saved_product() -> [ProductStockModel; 11] {
         (9002, false, "2023-10-09T07:58:30.1008+09:00", (10, 1, 1, None)),
     ];
     rawdata.map(|(id_, is_create, expiry, (available, reserved, booked, location))| {
-        ProductStockModel {
+        ProductStockModel { warehouse_id: 0,
             id_,
             is_create,
+            arrival: None,
             expiry: DateTime::parse_from_rfc3339(expiry).unwrap().into(),
             quantity: StockQuantityModel::new(available, reserved, booked, location),
         }
@@ -73,7 +76,7 @@ fn add_update_mix_ok() {
             (-2, 1014, saved_products[3].id_, saved_products[3].expiry.fixed_offset()),
             (23, 1014, saved_products[3].id_, dt2.fixed_offset()),
         ].into_iter()
-            .map(|(qty_add, store_id, product_id, expiry)| InventoryEditStockLevelDto {
+            .map(|(qty_add, store_id, product_id, expiry)| InventoryEditStockLevelDto { warehouse_id: 0,
                 qty_add, store_id, product_id, expiry,
             }).collect()
     };
@@ -82,15 +85,19 @@ fn add_update_mix_ok() {
         out.stores[0].products[0].quantity.total += 1;
         out.stores[1].products[0].quantity.cancelled += 2;
         out.stores[0].products.push(ProductStockModel {
+            warehouse_id: 0,
             id_: 5501,
             expiry: saved_products[0].expiry.clone(),
             is_create: true,
+            arrival: None,
             quantity: StockQuantityModel::new(12, 0, 0, None),
         });
         out.stores[1].products.push(ProductStockModel {
+            warehouse_id: 0,
             id_: saved_products[3].id_,
             expiry: dt2,
             is_create: true,
+            arrival: None,
             quantity: StockQuantityModel::new(23, 0, 0, None),
         });
         out.stores.push(StoreStockModel {
@@ -98,9 +105,11 @@ fn add_update_mix_ok() {
             products: vec![],
         });
         out.stores[2].products.push(ProductStockModel {
+            warehouse_id: 0,
             id_: 5502,
             expiry: saved_products[1].expiry.clone(),
             is_create: true,
+            arrival: None,
             quantity: StockQuantityModel::new(19, 0, 0, None),
         });
         out
@@ -130,6 +139,7 @@ fn update_cancelled_more_than_total() {
         }],
     };
     let newdata = vec![InventoryEditStockLevelDto {
+        warehouse_id: 0,
         qty_add: -3,
         store_id: 1013,
         product_id: saved_products[4].id_,
@@ -141,14 +151,17 @@ fn update_cancelled_more_than_total() {
     assert!(result.is_ok());
     let mset = result.unwrap();
     let expect = ProductStockModel {
+        warehouse_id: 0,
         id_: saved_products[4].id_,
         is_create: false,
+        arrival: None,
         expiry: saved_products[4].expiry.clone(),
         quantity: StockQuantityModel::new(14, 3, 0, None),
     };
     assert_eq!(mset.stores[0].products[0], expect);
     // ----------------
     let newdata = vec![InventoryEditStockLevelDto {
+        warehouse_id: 0,
         qty_add: -13,
         store_id: 1013,
         product_id: saved_products[4].id_,
@@ -158,8 +171,10 @@ fn update_cancelled_more_than_total() {
     assert!(result.is_ok());
     let mset = result.unwrap();
     let expect = ProductStockModel {
+        warehouse_id: 0,
         id_: saved_products[4].id_,
         is_create: false,
+        arrival: None,
         expiry: saved_products[4].expiry.clone(),
         quantity: StockQuantityModel::new(14, 14, 0, None),
     };
@@ -171,6 +186,7 @@ fn add_instance_error() {
     let saved_products = ut_mock_saved_product();
     let mset = StockLevelModelSet { stores: vec![] };
     let newdata = vec![InventoryEditStockLevelDto {
+        warehouse_id: 0,
         qty_add: -3,
         store_id: 1013,
         product_id: 234,
@@ -202,6 +218,7 @@ fn present_instance_ok() {
     };
     let expect = vec![
         StockLevelPresentDto {
+            warehouse_id: 0,
             expiry: DateTime::parse_from_rfc3339("2023-11-07T08:12:05.008+02:00").unwrap(),
             quantity: StockQuantityPresentDto {
                 total: 11,
@@ -212,6 +229,7 @@ fn present_instance_ok() {
             product_id: 9002,
         },
         StockLevelPresentDto {
+            warehouse_id: 0,
             expiry: DateTime::parse_from_rfc3339("2024-11-11T09:22:01.005+08:00").unwrap(),
             quantity: StockQuantityPresentDto {
                 total: 8,
@@ -295,17 +313,19 @@ fn reserve_ok_1() {
         let policy = OrderLineAppliedPolicyModel {
             reserved_until: mock_warranty.clone(),
             warranty_until: mock_warranty.clone(),
+            version: 0,
         };
         let qty = OrderLineQuantityModel {
             reserved,
             paid: 0,
             paid_last_update: None,
+            backordered: 0,
         };
         let attrs_charge = ProdAttriPriceModel::from((attr_lastupdate, None));
         OrderLineModel::from((id_, price, policy, qty, attrs_charge))
     })
     .collect();
-    let ol_set = {
+    let mut ol_set = {
         let order_id = "AliceOrdered".to_string();
         let lines = reqs;
         let owner_id = 123;
@@ -313,7 +333,8 @@ fn reserve_ok_1() {
         let args = (order_id, owner_id, create_time, currency, lines);
         OrderLineModelSet::try_from(args).unwrap()
     };
-    let error = mset.try_reserve(&ol_set);
+    let policies = StockAllocPolicyModelSet { policies: vec![] };
+    let error = mset.try_reserve(&mut ol_set, &policies);
     assert!(error.is_empty());
     [
         ut_get_curr_qty(&mset.stores[1], &ol_set.lines()[0]),
@@ -392,11 +413,13 @@ fn reserve_ok_2() {
             OrderLineAppliedPolicyModel {
                 reserved_until: mock_warranty.clone(),
                 warranty_until: mock_warranty.clone(),
+                version: 0,
             },
             OrderLineQuantityModel {
                 reserved,
                 paid: 0,
                 paid_last_update: None,
+                backordered: 0,
             },
             ProdAttriPriceModel::from((attr_lastupdate, None)),
         );
@@ -404,7 +427,7 @@ fn reserve_ok_2() {
     })
     .collect::<Vec<_>>();
 
-    let ol_set = {
+    let mut ol_set = {
         let order_id = "BobCart".to_string();
         let lines = reqs;
         let owner_id = 321;
@@ -412,7 +435,8 @@ fn reserve_ok_2() {
         let args = (order_id, owner_id, create_time, currency, lines);
         OrderLineModelSet::try_from(args).unwrap()
     };
-    let error = mset.try_reserve(&ol_set);
+    let policies = StockAllocPolicyModelSet { policies: vec![] };
+    let error = mset.try_reserve(&mut ol_set, &policies);
     assert!(error.is_empty());
     [
         ut_get_curr_qty(&mset.stores[1], &ol_set.lines()[0]),
@@ -477,17 +501,19 @@ fn reserve_shortage() {
             OrderLineAppliedPolicyModel {
                 reserved_until: mock_warranty.clone(),
                 warranty_until: mock_warranty.clone(),
+                version: 0,
             },
             OrderLineQuantityModel {
                 reserved,
                 paid: 0,
                 paid_last_update: None,
+                backordered: 0,
             },
             ProdAttriPriceModel::from((attr_lastupdate, None)),
         ))
     })
     .collect();
-    let ol_set = {
+    let mut ol_set = {
         let order_id = "xx1".to_string();
         let lines = reqs;
         let owner_id = 123;
@@ -496,7 +522,8 @@ fn reserve_shortage() {
         let args = (order_id, owner_id, create_time, currency, lines);
         OrderLineModelSet::try_from(args).unwrap()
     };
-    let error = mset.try_reserve(&ol_set);
+    let policies = StockAllocPolicyModelSet { policies: vec![] };
+    let error = mset.try_reserve(&mut ol_set, &policies);
     assert_eq!(error.len(), 2);
     {
         let (expect, actual) = (&ol_set.lines()[0], &error[0]);
@@ -540,18 +567,20 @@ fn reserve_seller_nonexist() {
             OrderLineAppliedPolicyModel {
                 reserved_until: mock_warranty.clone(),
                 warranty_until: mock_warranty,
+                version: 0,
             },
             OrderLineQuantityModel {
                 reserved,
                 paid: 0,
                 paid_last_update: None,
+                backordered: 0,
             },
             ProdAttriPriceModel::from((attr_lastupdate, None)),
         ))
     })
     .collect::<Vec<_>>();
 
-    let ol_set = {
+    let mut ol_set = {
         let order_id = "xx1".to_string();
         let lines = reqs;
         let owner_id = 321;
@@ -560,7 +589,8 @@ fn reserve_seller_nonexist() {
         let args = (order_id, owner_id, create_time, currency, lines);
         OrderLineModelSet::try_from(args).unwrap()
     };
-    let error = mset.try_reserve(&ol_set);
+    let policies = StockAllocPolicyModelSet { policies: vec![] };
+    let error = mset.try_reserve(&mut ol_set, &policies);
     assert_eq!(error.len(), 1);
     {
         let (expect, actual) = (&ol_set.lines()[1], &error[0]);
@@ -630,12 +660,14 @@ fn return_across_expiry_ok() {
         order_id: format!("ChadBookedThis"),
         items: vec![
             InventoryEditStockLevelDto {
+                warehouse_id: 0,
                 store_id: 1014,
                 product_id: 9002,
                 qty_add: 2,
                 expiry: mock_warranty,
             },
             InventoryEditStockLevelDto {
+                warehouse_id: 0,
                 store_id: 1014,
                 product_id: 9006,
                 qty_add: 1,
@@ -671,12 +703,14 @@ fn return_across_expiry_nonexist() {
         order_id: format!("ChadBookedThis"),
         items: vec![
             InventoryEditStockLevelDto {
+                warehouse_id: 0,
                 store_id: 1014,
                 product_id: 9006,
                 qty_add: 1,
                 expiry: mock_warranty,
             },
             InventoryEditStockLevelDto {
+                warehouse_id: 0,
                 store_id: 1014,
                 product_id: 9999,
                 qty_add: 2,
@@ -706,18 +740,21 @@ fn return_across_expiry_invalid_qty() {
         order_id: format!("ChadBookedThis"),
         items: vec![
             InventoryEditStockLevelDto {
+                warehouse_id: 0,
                 store_id: 1014,
                 product_id: 9006,
                 qty_add: 1,
                 expiry: mock_warranty,
             },
             InventoryEditStockLevelDto {
+                warehouse_id: 0,
                 store_id: 1014,
                 product_id: 9002,
                 qty_add: 3,
                 expiry: mock_warranty,
             },
             InventoryEditStockLevelDto {
+                warehouse_id: 0,
                 store_id: 1014,
                 product_id: 9006,
                 qty_add: 3,
@@ -786,6 +823,7 @@ fn return_by_expiry_ok() {
         order_id: mock_oid.to_string(),
         items: vec![
             InventoryEditStockLevelDto {
+                warehouse_id: 0,
                 store_id: 1014,
                 product_id: 9002,
                 qty_add: 2,
@@ -793,6 +831,7 @@ fn return_by_expiry_ok() {
                     + Duration::milliseconds(13),
             },
             InventoryEditStockLevelDto {
+                warehouse_id: 0,
                 store_id: 1013,
                 product_id: 9006,
                 qty_add: 2,
@@ -843,12 +882,14 @@ fn return_by_expiry_nonexist() {
         order_id: mock_oid.to_string(),
         items: vec![
             InventoryEditStockLevelDto {
+                warehouse_id: 0,
                 store_id: 1014,
                 product_id: 9002,
                 qty_add: 1,
                 expiry: mset.stores[1].products[0].expiry.fixed_offset() + Duration::seconds(3),
             },
             InventoryEditStockLevelDto {
+                warehouse_id: 0,
                 store_id: 1013,
                 product_id: 9006,
                 qty_add: 1,
@@ -874,6 +915,7 @@ fn return_by_expiry_invalid_qty() {
         order_id: mock_oid.to_string(),
         items: vec![
             InventoryEditStockLevelDto {
+                warehouse_id: 0,
                 store_id: 1014,
                 product_id: 9002,
                 qty_add: 6,
@@ -881,6 +923,7 @@ fn return_by_expiry_invalid_qty() {
                     + Duration::milliseconds(55),
             },
             InventoryEditStockLevelDto {
+                warehouse_id: 0,
                 store_id: 1013,
                 product_id: 9006,
                 qty_add: 7,
@@ -902,3 +945,473 @@ fn return_by_expiry_invalid_qty() {
         StockReturnErrorReason::InvalidQuantity
     ));
 }
+
+fn ut_setup_alloc_oline(
+    store_id: u32,
+    product_id: u64,
+    num_rsv: u32,
+    backorder_limit: u32,
+    create_time: DateTime<FixedOffset>,
+) -> OrderLineModel {
+    let mock_warranty = create_time + Duration::days(90);
+    let id_ = OrderLineIdentity::from((store_id, product_id, 0));
+    let price = OrderLinePriceModel::from((10, 10 * num_rsv));
+    let policy = OrderLineAppliedPolicyModel {
+        reserved_until: create_time + Duration::minutes(30),
+        warranty_until: mock_warranty,
+        version: 0,
+    };
+    let qty = OrderLineQuantityModel {
+        reserved: num_rsv,
+        paid: 0,
+        paid_last_update: None,
+        backordered: 0,
+    };
+    let attrs_charge = ProdAttriPriceModel::from((create_time, None));
+    let mut line = OrderLineModel::from((id_, price, policy, qty, attrs_charge));
+    line.backorder_limit = backorder_limit;
+    line
+}
+
+fn ut_setup_alloc_olineset(
+    store_id: u32,
+    product_id: u64,
+    num_rsv: u32,
+    backorder_limit: u32,
+    create_time: DateTime<FixedOffset>,
+) -> OrderLineModelSet {
+    let line = ut_setup_alloc_oline(store_id, product_id, num_rsv, backorder_limit, create_time);
+    ut_setup_alloc_olineset_from(vec![line], create_time)
+}
+
+fn ut_setup_alloc_olineset_from(
+    lines: Vec<OrderLineModel>,
+    create_time: DateTime<FixedOffset>,
+) -> OrderLineModelSet {
+    let seller_ids = lines.iter().map(|l| l.id().store_id()).collect::<Vec<_>>();
+    let currency = ut_setup_order_currency(seller_ids);
+    let args = ("BobOrdered".to_string(), 126, create_time, currency, lines);
+    OrderLineModelSet::try_from(args).unwrap()
+}
+
+// each tuple contains days to expire from given time, days since arrival, and
+// total quantity of a batch
+fn ut_setup_alloc_store(
+    store_id: u32,
+    product_id: u64,
+    time_now: DateTime<FixedOffset>,
+    data: Vec<(i64, i64, u32)>,
+) -> StockLevelModelSet {
+    let products = data
+        .into_iter()
+        .map(|(exp_days, arrived_days, total)| ProductStockModel {
+            warehouse_id: 0,
+            id_: product_id,
+            expiry: (time_now + Duration::days(exp_days)).to_utc(),
+            quantity: StockQuantityModel::new(total, 0, 0, None),
+            is_create: false,
+            arrival: Some((time_now - Duration::days(arrived_days)).to_utc()),
+        })
+        .collect();
+    StockLevelModelSet {
+        stores: vec![StoreStockModel { store_id, products }],
+    }
+}
+
+fn ut_reserved_per_batch(mset: &StockLevelModelSet) -> Vec<(i64, u32)> {
+    let mut out = mset.stores[0]
+        .products
+        .iter()
+        .filter_map(|p| {
+            p.quantity
+                .rsv_detail
+                .as_ref()
+                .map(|r| (p.expiry.timestamp(), r.reserved))
+        })
+        .collect::<Vec<_>>();
+    out.sort();
+    out
+}
+
+#[test]
+fn reserve_alloc_fefo_min_shelf_life() {
+    let create_time = DateTime::parse_from_rfc3339("2023-04-16T14:59:00+08:00").unwrap();
+    let batches = vec![(3, 5, 10), (40, 30, 10), (20, 2, 4)];
+    let policies = StockAllocPolicyModelSet {
+        policies: vec![
+            StockAllocPolicyModel {
+                store_id: 1015,
+                product_id: None,
+                strategy: StockAllocStrategy::Fifo,
+                min_shelf_days: 0,
+            },
+            StockAllocPolicyModel {
+                store_id: 1015,
+                product_id: Some(9007),
+                strategy: StockAllocStrategy::Fefo,
+                min_shelf_days: 7,
+            },
+        ],
+    };
+    let mut mset = ut_setup_alloc_store(1015, 9007, create_time, batches.clone());
+    let mut ol_set = ut_setup_alloc_olineset(1015, 9007, 6, 0, create_time);
+    let error = mset.try_reserve(&mut ol_set, &policies);
+    assert!(error.is_empty());
+    // the batch expiring within 7 days is skipped
+    let expect = vec![
+        ((create_time + Duration::days(20)).timestamp(), 4),
+        ((create_time + Duration::days(40)).timestamp(), 2),
+    ];
+    assert_eq!(ut_reserved_per_batch(&mset), expect);
+    // ---- subcase, the skipped batch is not counted as available
+    let mut mset = ut_setup_alloc_store(1015, 9007, create_time, batches);
+    let mut ol_set = ut_setup_alloc_olineset(1015, 9007, 15, 0, create_time);
+    let error = mset.try_reserve(&mut ol_set, &policies);
+    assert_eq!(error.len(), 1);
+    assert!(matches!(
+        error[0].reason,
+        OrderLineCreateErrorReason::NotEnoughToClaim
+    ));
+    assert_eq!(error[0].shortage, Some(1));
+    assert!(ut_reserved_per_batch(&mset).is_empty());
+} // end of fn reserve_alloc_fefo_min_shelf_life
+
+#[test]
+fn reserve_alloc_fifo() {
+    let create_time = DateTime::parse_from_rfc3339("2023-04-16T14:59:00+08:00").unwrap();
+    let batches = vec![(3, 5, 10), (40, 30, 10), (20, 2, 4)];
+    let policies = StockAllocPolicyModelSet {
+        policies: vec![StockAllocPolicyModel {
+            store_id: 1015,
+            product_id: None,
+            strategy: StockAllocStrategy::Fifo,
+            min_shelf_days: 0,
+        }],
+    };
+    let mut mset = ut_setup_alloc_store(1015, 9007, create_time, batches.clone());
+    let mut ol_set = ut_setup_alloc_olineset(1015, 9007, 12, 0, create_time);
+    let error = mset.try_reserve(&mut ol_set, &policies);
+    assert!(error.is_empty());
+    // the batch which arrived earliest is taken first regardless of its expiry
+    let expect = vec![
+        ((create_time + Duration::days(3)).timestamp(), 2),
+        ((create_time + Duration::days(40)).timestamp(), 10),
+    ];
+    assert_eq!(ut_reserved_per_batch(&mset), expect);
+    // ---- subcase, default FEFO applies to other stores
+    let mut mset = ut_setup_alloc_store(1016, 9007, create_time, batches);
+    let mut ol_set = ut_setup_alloc_olineset(1016, 9007, 12, 0, create_time);
+    let error = mset.try_reserve(&mut ol_set, &policies);
+    assert!(error.is_empty());
+    let expect = vec![
+        ((create_time + Duration::days(3)).timestamp(), 10),
+        ((create_time + Duration::days(20)).timestamp(), 2),
+    ];
+    assert_eq!(ut_reserved_per_batch(&mset), expect);
+} // end of fn reserve_alloc_fifo
+
+#[test]
+fn alloc_policy_resolve_ok() {
+    let policies = StockAllocPolicyModelSet {
+        policies: vec![
+            StockAllocPolicyModel {
+                store_id: 1017,
+                product_id: Some(9008),
+                strategy: StockAllocStrategy::Fefo,
+                min_shelf_days: 14,
+            },
+            StockAllocPolicyModel {
+                store_id: 1017,
+                product_id: None,
+                strategy: StockAllocStrategy::Fifo,
+                min_shelf_days: 0,
+            },
+        ],
+    };
+    let actual = policies.resolve(1017, 9008);
+    assert_eq!(actual.strategy, StockAllocStrategy::Fefo);
+    assert_eq!(actual.min_shelf_days, 14);
+    let actual = policies.resolve(1017, 9009);
+    assert_eq!(actual.strategy, StockAllocStrategy::Fifo);
+    assert_eq!(actual.product_id, None);
+    let actual = policies.resolve(1018, 9008);
+    assert_eq!(actual.strategy, StockAllocStrategy::Fefo);
+    assert_eq!(actual.min_shelf_days, 0);
+}
+
+#[test]
+fn alloc_policy_convert_from_dto() {
+    let d = StockAllocPolicyDto {
+        store_id: 1017,
+        product_id: Some(9008),
+        strategy: StockAllocStrategyDto::FIFO,
+        min_shelf_days: 30,
+    };
+    let result = StockAllocPolicyModel::try_from(d);
+    assert!(result.is_ok());
+    let m = result.unwrap();
+    assert_eq!(m.strategy, StockAllocStrategy::Fifo);
+    assert_eq!(m.product_id, Some(9008));
+    let d = StockAllocPolicyDto {
+        store_id: 1017,
+        product_id: None,
+        strategy: StockAllocStrategyDto::FEFO,
+        min_shelf_days: 366,
+    };
+    let result = StockAllocPolicyModel::try_from(d);
+    assert!(result.is_err());
+    let e = result.unwrap_err();
+    assert_eq!(e.code, AppErrorCode::InvalidInput);
+}
+
+#[test]
+fn reserve_with_backorder() {
+    let create_time = DateTime::parse_from_rfc3339("2023-04-16T14:59:00+08:00").unwrap();
+    let batches = vec![(3, 5, 4), (40, 30, 2)];
+    let policies = StockAllocPolicyModelSet { policies: vec![] };
+    let mut mset = ut_setup_alloc_store(1015, 9007, create_time, batches.clone());
+    let mut ol_set = ut_setup_alloc_olineset(1015, 9007, 10, 5, create_time);
+    let error = mset.try_reserve(&mut ol_set, &policies);
+    assert!(error.is_empty());
+    // all available items are reserved, the rest is backordered
+    let expect = vec![
+        ((create_time + Duration::days(3)).timestamp(), 4),
+        ((create_time + Duration::days(40)).timestamp(), 2),
+    ];
+    assert_eq!(ut_reserved_per_batch(&mset), expect);
+    let line = &ol_set.lines()[0];
+    assert_eq!(line.qty.reserved, 10);
+    assert_eq!(line.qty.backordered, 4);
+    assert!(line.qty.is_backordered());
+    let expect = create_time + Duration::minutes(30) + Duration::days(30);
+    assert_eq!(line.policy.reserved_until, expect);
+    // ---- subcase, shortage exceeds the backorder limit
+    let mut mset = ut_setup_alloc_store(1015, 9007, create_time, batches);
+    let mut ol_set = ut_setup_alloc_olineset(1015, 9007, 10, 3, create_time);
+    let error = mset.try_reserve(&mut ol_set, &policies);
+    assert_eq!(error.len(), 1);
+    assert!(matches!(
+        error[0].reason,
+        OrderLineCreateErrorReason::NotEnoughToClaim
+    ));
+    assert_eq!(error[0].shortage, Some(4));
+    assert!(ut_reserved_per_batch(&mset).is_empty());
+    assert_eq!(ol_set.lines()[0].qty.backordered, 0);
+    // ---- subcase, pre-order a product which has never been stocked
+    let mut mset = StockLevelModelSet { stores: vec![] };
+    let mut ol_set = ut_setup_alloc_olineset(1015, 9007, 10, 10, create_time);
+    let error = mset.try_reserve(&mut ol_set, &policies);
+    assert!(error.is_empty());
+    assert_eq!(ol_set.lines()[0].qty.backordered, 10);
+} // end of fn reserve_with_backorder
+
+#[test]
+fn fulfil_backorder_ok() {
+    let create_time = DateTime::parse_from_rfc3339("2023-04-16T14:59:00+08:00").unwrap();
+    let policies = StockAllocPolicyModelSet { policies: vec![] };
+    let mut mset = ut_setup_alloc_store(1015, 9007, create_time, vec![(3, 5, 4)]);
+    let mut ol_set = ut_setup_alloc_olineset(1015, 9007, 10, 8, create_time);
+    let error = mset.try_reserve(&mut ol_set, &policies);
+    assert!(error.is_empty());
+    assert_eq!(ol_set.lines()[0].qty.backordered, 6);
+    let orig_rsv_until = ol_set.lines()[0].policy.reserved_until;
+    // new batch arrived but still not enough
+    let time_now = create_time + Duration::days(2);
+    let newbatch = ut_setup_alloc_store(1015, 9007, time_now, vec![(60, 0, 2)]);
+    mset.stores[0]
+        .products
+        .extend(newbatch.stores[0].products.clone());
+    let num_taken = mset.fulfil_backorder(&mut ol_set, &policies, time_now);
+    assert_eq!(num_taken, 2);
+    let line = &ol_set.lines()[0];
+    assert_eq!(line.qty.backordered, 4);
+    assert_eq!(line.policy.reserved_until, orig_rsv_until);
+    // the rest of items are allocated, reservation time starts over
+    let time_now = create_time + Duration::days(5);
+    let newbatch = ut_setup_alloc_store(1015, 9007, time_now, vec![(90, 0, 7)]);
+    mset.stores[0]
+        .products
+        .extend(newbatch.stores[0].products.clone());
+    let num_taken = mset.fulfil_backorder(&mut ol_set, &policies, time_now);
+    assert_eq!(num_taken, 4);
+    let line = &ol_set.lines()[0];
+    assert_eq!(line.qty.backordered, 0);
+    assert_eq!(line.policy.reserved_until, time_now + Duration::minutes(30));
+    let total_rsv: u32 = ut_reserved_per_batch(&mset).iter().map(|(_, n)| *n).sum();
+    assert_eq!(total_rsv, 10);
+    // nothing left to allocate
+    let num_taken = mset.fulfil_backorder(&mut ol_set, &policies, time_now);
+    assert_eq!(num_taken, 0);
+} // end of fn fulfil_backorder_ok
+
+#[test]
+fn fulfil_backorder_expired() {
+    let create_time = DateTime::parse_from_rfc3339("2023-04-16T14:59:00+08:00").unwrap();
+    let policies = StockAllocPolicyModelSet { policies: vec![] };
+    let mut mset = StockLevelModelSet { stores: vec![] };
+    let mut ol_set = ut_setup_alloc_olineset(1015, 9007, 5, 5, create_time);
+    let error = mset.try_reserve(&mut ol_set, &policies);
+    assert!(error.is_empty());
+    assert_eq!(ol_set.lines()[0].qty.backordered, 5);
+    // the buyer has waited too long, new stock is no longer allocated
+    let time_now = create_time + Duration::days(31);
+    let mut mset = ut_setup_alloc_store(1015, 9007, time_now, vec![(60, 0, 9)]);
+    let num_taken = mset.fulfil_backorder(&mut ol_set, &policies, time_now);
+    assert_eq!(num_taken, 0);
+    assert_eq!(ol_set.lines()[0].qty.backordered, 5);
+    assert!(ut_reserved_per_batch(&mset).is_empty());
+}
+
+fn ut_setup_warehouse_dto(
+    store_id: u32,
+    warehouse_id: u32,
+    country: CountryCode,
+    region: &str,
+    city: &str,
+) -> StockWarehouseDto {
+    StockWarehouseDto {
+        store_id,
+        warehouse_id,
+        country,
+        region: region.to_string(),
+        city: city.to_string(),
+    }
+}
+
+fn ut_setup_warehouses() -> StockWarehouseModelSet {
+    let warehouses = [
+        (1015, 0, CountryCode::TW, "Taipei", "Taipei"),
+        (1015, 2, CountryCode::TW, "Kaohsiung", "Kaohsiung"),
+        (1015, 5, CountryCode::JP, "Osaka", "Osaka"),
+        (1015, 7, CountryCode::TW, "Kaohsiung", "Fengshan"),
+        (1016, 1, CountryCode::TW, "Kaohsiung", "Kaohsiung"),
+    ]
+    .into_iter()
+    .map(|(store_id, warehouse_id, country, region, city)| {
+        let d = ut_setup_warehouse_dto(store_id, warehouse_id, country, region, city);
+        StockWarehouseModel::try_from(d).unwrap()
+    })
+    .collect();
+    StockWarehouseModelSet { warehouses }
+}
+
+fn ut_setup_shipping_addr(country: CountryCode, region: &str, city: &str) -> PhyAddrModel {
+    PhyAddrModel {
+        country,
+        region: region.to_string(),
+        city: city.to_string(),
+        distinct: "Lingya".to_string(),
+        street_name: None,
+        detail: "3F, No. 19".to_string(),
+    }
+}
+
+#[test]
+fn warehouse_convert_from_dto() {
+    let d = ut_setup_warehouse_dto(1015, 3, CountryCode::TW, "Taipei", "Taipei");
+    let result = StockWarehouseModel::try_from(d);
+    assert!(result.is_ok());
+    let d = ut_setup_warehouse_dto(1015, 3, CountryCode::Unknown, "Taipei", "Taipei");
+    let result = StockWarehouseModel::try_from(d);
+    assert!(result.is_err());
+    if let Err(e) = result {
+        assert_eq!(e.code, AppErrorCode::InvalidInput);
+        assert!(e.detail.unwrap().contains("country"));
+    }
+    let long_city = "Kaohsiung".repeat(4);
+    let d = ut_setup_warehouse_dto(1015, 3, CountryCode::TW, "Kaohsiung", long_city.as_str());
+    let result = StockWarehouseModel::try_from(d);
+    assert!(result.is_err());
+    if let Err(e) = result {
+        assert_eq!(e.code, AppErrorCode::InvalidInput);
+        assert!(e.detail.unwrap().contains("city-length"));
+    }
+}
+
+#[test]
+fn warehouse_rank_by_address() {
+    let ms = ut_setup_warehouses();
+    let addr = ut_setup_shipping_addr(CountryCode::TW, "Kaohsiung", "Kaohsiung");
+    assert_eq!(ms.rank(1015, &addr), vec![2, 7, 0, 5]);
+    assert_eq!(ms.rank(1016, &addr), vec![1]);
+    assert!(ms.rank(1017, &addr).is_empty());
+    let addr = ut_setup_shipping_addr(CountryCode::JP, "Tokyo", "Shinjuku");
+    assert_eq!(ms.rank(1015, &addr), vec![5, 0, 2, 7]);
+}
+
+#[test]
+fn reserve_from_nearest_warehouse() {
+    let create_time = DateTime::parse_from_rfc3339("2023-04-16T14:59:00+08:00").unwrap();
+    let policies = StockAllocPolicyModelSet { policies: vec![] };
+    // each warehouse keeps a batch of the same product
+    let setup_store = || {
+        let batches = vec![(30, 2, 8), (30, 2, 3), (30, 2, 5)];
+        let mut mset = ut_setup_alloc_store(1015, 9007, create_time, batches);
+        let products = mset.stores[0].products.iter_mut();
+        products
+            .zip([0u32, 2, 7])
+            .map(|(p, warehouse_id)| {
+                p.warehouse_id = warehouse_id;
+            })
+            .count();
+        mset
+    };
+    let ms_wh = ut_setup_warehouses();
+    let addr = ut_setup_shipping_addr(CountryCode::TW, "Kaohsiung", "Kaohsiung");
+    let reserved_per_warehouse = |mset: &StockLevelModelSet| {
+        let mut out = mset.stores[0]
+            .products
+            .iter()
+            .filter_map(|p| {
+                p.quantity
+                    .rsv_detail
+                    .as_ref()
+                    .map(|r| (p.warehouse_id, r.reserved))
+            })
+            .collect::<Vec<_>>();
+        out.sort();
+        out
+    };
+    // the nearest warehouse doesn't have enough stock, the next one is chosen
+    let mut mset = setup_store();
+    let mut lines = vec![ut_setup_alloc_oline(1015, 9007, 4, 0, create_time)];
+    OrderLineModel::rank_warehouses(&mut lines, &ms_wh, &addr);
+    let mut ol_set = ut_setup_alloc_olineset_from(lines, create_time);
+    let error = mset.try_reserve(&mut ol_set, &policies);
+    assert!(error.is_empty());
+    assert_eq!(ol_set.lines()[0].warehouse_id, Some(7));
+    assert_eq!(reserved_per_warehouse(&mset), vec![(7, 4)]);
+    // ---- subcase, items are never split across warehouses
+    let mut mset = setup_store();
+    let mut lines = vec![ut_setup_alloc_oline(1015, 9007, 9, 0, create_time)];
+    OrderLineModel::rank_warehouses(&mut lines, &ms_wh, &addr);
+    let mut ol_set = ut_setup_alloc_olineset_from(lines, create_time);
+    let error = mset.try_reserve(&mut ol_set, &policies);
+    assert_eq!(error.len(), 1);
+    assert!(matches!(
+        error[0].reason,
+        OrderLineCreateErrorReason::NotEnoughToClaim
+    ));
+    // shortage is estimated in the warehouse with most items available
+    assert_eq!(error[0].shortage, Some(1));
+    assert!(reserved_per_warehouse(&mset).is_empty());
+    assert_eq!(ol_set.lines()[0].warehouse_id, None);
+    // ---- subcase, backorder stays with the warehouse with most items
+    let mut mset = setup_store();
+    let mut lines = vec![ut_setup_alloc_oline(1015, 9007, 9, 2, create_time)];
+    OrderLineModel::rank_warehouses(&mut lines, &ms_wh, &addr);
+    let mut ol_set = ut_setup_alloc_olineset_from(lines, create_time);
+    let error = mset.try_reserve(&mut ol_set, &policies);
+    assert!(error.is_empty());
+    let line = &ol_set.lines()[0];
+    assert_eq!(line.warehouse_id, Some(0));
+    assert_eq!(line.qty.backordered, 1);
+    assert_eq!(reserved_per_warehouse(&mset), vec![(0, 8)]);
+    // ---- subcase, no shipping address, the default warehouse goes first
+    let mut mset = setup_store();
+    let mut ol_set = ut_setup_alloc_olineset(1015, 9007, 2, 0, create_time);
+    let error = mset.try_reserve(&mut ol_set, &policies);
+    assert!(error.is_empty());
+    assert_eq!(ol_set.lines()[0].warehouse_id, Some(0));
+    assert_eq!(reserved_per_warehouse(&mset), vec![(0, 2)]);
+} // end of fn reserve_from_nearest_warehouse
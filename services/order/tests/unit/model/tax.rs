@@ -0,0 +1,142 @@
+use rust_decimal::Decimal;
+
+use ecommerce_common::api::dto::CountryCode;
+use ecommerce_common::error::AppErrorCode;
+use ecommerce_common::model::order::PhyAddrModel;
+
+use order::api::rpc::dto::TaxRateDto;
+use order::model::{TaxRateModel, TaxRateModelSet};
+
+fn ut_tax_rate_dto(
+    country: CountryCode,
+    region: Option<&str>,
+    store_id: Option<u32>,
+    rate: &str,
+) -> TaxRateDto {
+    TaxRateDto {
+        country,
+        region: region.map(String::from),
+        store_id,
+        rate: rate.to_string(),
+    }
+}
+
+fn ut_phy_addr(country: CountryCode, region: &str) -> PhyAddrModel {
+    PhyAddrModel {
+        country,
+        region: region.to_string(),
+        city: "Kaohsiung".to_string(),
+        distinct: "Lingya".to_string(),
+        street_name: None,
+        detail: "3F, No. 19".to_string(),
+    }
+}
+
+#[test]
+fn convert_from_dto_ok() {
+    let data = vec![
+        ut_tax_rate_dto(CountryCode::TW, None, None, "0.05"),
+        ut_tax_rate_dto(CountryCode::US, Some("CA"), Some(0), "0.0725"),
+        ut_tax_rate_dto(CountryCode::US, Some(""), Some(1029), "0"),
+    ];
+    let result = TaxRateModelSet::try_from(data);
+    assert!(result.is_ok());
+    let ms = result.unwrap();
+    assert_eq!(ms.rates.len(), 3);
+    let m = &ms.rates[1];
+    assert_eq!(m.region.as_deref(), Some("CA"));
+    assert_eq!(m.store_id, None);
+    assert_eq!(m.rate, Decimal::new(725, 4));
+    let m = &ms.rates[2];
+    assert_eq!(m.region, None);
+    assert_eq!(m.store_id, Some(1029));
+    assert!(m.rate.is_zero());
+}
+
+#[test]
+fn convert_from_dto_error() {
+    let long_region = "x".repeat(33);
+    [
+        (CountryCode::Unknown, None, "0.05", "country"),
+        (
+            CountryCode::TH,
+            Some(long_region.as_str()),
+            "0.07",
+            "region-length:33",
+        ),
+        (CountryCode::IN, None, "-0.18", "rate-range:-0.18"),
+        (CountryCode::IN, None, "1.0", "rate-range:1.0"),
+        (CountryCode::ID, None, "0.11025", "rate-range:0.11025"),
+    ]
+    .into_iter()
+    .map(|(country, region, rate, expect_detail)| {
+        let data = ut_tax_rate_dto(country, region, None, rate);
+        let result = TaxRateModel::try_from(data);
+        assert!(result.is_err());
+        let e = result.unwrap_err();
+        assert_eq!(e.code, AppErrorCode::InvalidInput);
+        assert_eq!(e.detail.as_deref(), Some(expect_detail));
+    })
+    .count();
+    let data = ut_tax_rate_dto(CountryCode::TW, None, None, "five-percent");
+    let result = TaxRateModel::try_from(data);
+    assert!(result.is_err());
+    let e = result.unwrap_err();
+    assert_eq!(e.code, AppErrorCode::InvalidInput);
+    // ------ subcase, empty list
+    let result = TaxRateModelSet::try_from(Vec::new());
+    assert!(result.is_err());
+    let e = result.err().unwrap();
+    assert_eq!(e.code, AppErrorCode::EmptyInputData);
+}
+
+#[test]
+fn unit_tax_rounding() {
+    let dto = ut_tax_rate_dto(CountryCode::TW, None, None, "0.05");
+    let m = TaxRateModel::try_from(dto).unwrap();
+    [
+        (100u32, 5u32),
+        (109, 5),
+        (110, 6),
+        (129, 6),
+        (130, 7),
+        (0, 0),
+    ]
+    .into_iter()
+    .map(|(unit_price, expect)| {
+        assert_eq!(m.unit_tax(unit_price), expect);
+    })
+    .count();
+}
+
+#[test]
+fn find_rate_precedence() {
+    let data = vec![
+        ut_tax_rate_dto(CountryCode::US, None, None, "0.04"),
+        ut_tax_rate_dto(CountryCode::US, Some("NY"), None, "0.08"),
+        ut_tax_rate_dto(CountryCode::US, None, Some(1029), "0.03"),
+        ut_tax_rate_dto(CountryCode::US, Some("NY"), Some(1030), "0.06"),
+        ut_tax_rate_dto(CountryCode::TW, None, None, "0.05"),
+    ];
+    let ms = TaxRateModelSet::try_from(data).unwrap();
+    let addr_ny = ut_phy_addr(CountryCode::US, "NY");
+    let addr_ca = ut_phy_addr(CountryCode::US, "CA");
+    let addr_tw = ut_phy_addr(CountryCode::TW, "Kaohsiung");
+    let addr_th = ut_phy_addr(CountryCode::TH, "Bangkok");
+    [
+        (1028u32, &addr_ny, Some(Decimal::new(8, 2))),
+        (1028, &addr_ca, Some(Decimal::new(4, 2))),
+        (1029, &addr_ny, Some(Decimal::new(3, 2))),
+        (1029, &addr_ca, Some(Decimal::new(3, 2))),
+        (1030, &addr_ny, Some(Decimal::new(6, 2))),
+        (1030, &addr_ca, Some(Decimal::new(4, 2))),
+        (1030, &addr_tw, Some(Decimal::new(5, 2))),
+        (1030, &addr_th, None),
+    ]
+    .into_iter()
+    .map(|(store_id, addr, expect)| {
+        let actual = ms.find(store_id, addr).map(|m| m.rate);
+        assert_eq!(actual, expect);
+    })
+    .count();
+}
@@ -1,8 +1,11 @@
+use std::time::Duration;
+
+use chrono::Local;
 use rust_decimal::Decimal;
 
 use ecommerce_common::api::dto::CurrencyDto;
 use order::datastore::AppInMemoryDStore;
-use order::model::{CurrencyModel, CurrencyModelSet};
+use order::model::{CurrencyModel, CurrencyModelSet, CurrencyRateHistoryModel};
 use order::repository::{AbsCurrencyRepo, CurrencyInMemRepo};
 
 use crate::repository::in_mem::in_mem_ds_ctx_setup;
@@ -27,6 +30,7 @@ pub(crate) fn ut_setup_currency_ms(data: Vec<(CurrencyDto, i64, u32)>) -> Curren
     CurrencyModelSet {
         base,
         exchange_rates,
+        provider: None,
     }
 }
 
@@ -87,3 +91,87 @@ async fn save_fetch_ok() {
             .count();
     }
 } // end of fn save_fetch_ok
+
+fn ut_verify_rates(ms: CurrencyModelSet, expect: Vec<(CurrencyDto, &str)>) {
+    assert!(matches!(ms.base, CurrencyDto::USD));
+    assert_eq!(ms.exchange_rates.len(), expect.len());
+    expect
+        .into_iter()
+        .map(|(name, rate)| {
+            let found = ms.exchange_rates.iter().find(|m| m.name == name).unwrap();
+            assert_eq!(found.rate.to_string().as_str(), rate);
+        })
+        .count();
+}
+
+fn ut_verify_history(
+    records: Vec<CurrencyRateHistoryModel>,
+    expect: Vec<(CurrencyDto, &str, &str)>,
+) {
+    assert_eq!(records.len(), expect.len());
+    records
+        .into_iter()
+        .zip(expect)
+        .map(|(r, (name, rate, provider))| {
+            assert_eq!(r.name, name);
+            assert_eq!(r.rate.to_string().as_str(), rate);
+            assert_eq!(r.provider.as_deref(), Some(provider));
+        })
+        .count();
+}
+
+// the caller has to ensure no other test case saves rates of THB and USD
+// concurrently to the same data store
+pub(crate) async fn save_fetch_history_common(repo: Box<dyn AbsCurrencyRepo>) {
+    // the granularity of time column in some database is 1 second
+    let pause = Duration::from_millis(1100);
+    let t0 = Local::now().fixed_offset();
+    tokio::time::sleep(pause).await;
+    let mut ms = ut_setup_currency_ms(vec![(CurrencyDto::THB, 3829, 2), (CurrencyDto::USD, 1, 0)]);
+    ms.provider = Some("utest-a".to_string());
+    let result = repo.save(ms).await;
+    assert!(result.is_ok());
+    tokio::time::sleep(pause).await;
+    let t1 = Local::now().fixed_offset();
+    tokio::time::sleep(pause).await;
+    let mut ms = ut_setup_currency_ms(vec![(CurrencyDto::THB, 3641, 2)]);
+    ms.provider = Some("utest-b".to_string());
+    let result = repo.save(ms).await;
+    assert!(result.is_ok());
+    tokio::time::sleep(pause).await;
+    let t2 = Local::now().fixed_offset();
+
+    let keys = vec![CurrencyDto::THB, CurrencyDto::USD];
+    let result = repo.fetch_as_of(keys.clone(), t1).await;
+    assert!(result.is_ok());
+    let expect = vec![(CurrencyDto::THB, "38.29"), (CurrencyDto::USD, "1")];
+    ut_verify_rates(result.unwrap(), expect);
+    let result = repo.fetch_as_of(keys.clone(), t2).await;
+    assert!(result.is_ok());
+    let expect = vec![(CurrencyDto::THB, "36.41"), (CurrencyDto::USD, "1")];
+    ut_verify_rates(result.unwrap(), expect);
+
+    let result = repo.fetch_history(keys.clone(), t1, t2).await;
+    assert!(result.is_ok());
+    let expect = vec![(CurrencyDto::THB, "36.41", "utest-b")];
+    ut_verify_history(result.unwrap(), expect);
+    let result = repo.fetch_history(vec![CurrencyDto::THB], t0, t2).await;
+    assert!(result.is_ok());
+    let expect = vec![
+        (CurrencyDto::THB, "38.29", "utest-a"),
+        (CurrencyDto::THB, "36.41", "utest-b"),
+    ];
+    ut_verify_history(result.unwrap(), expect);
+    // ---- subcase, nothing saved within the time range ----
+    let result = repo
+        .fetch_history(keys, t2, Local::now().fixed_offset())
+        .await;
+    assert!(result.is_ok());
+    assert!(result.unwrap().is_empty());
+} // end of fn save_fetch_history_common
+
+#[tokio::test]
+async fn save_fetch_history_ok() {
+    let repo = in_mem_repo_ds_setup(20).await;
+    save_fetch_history_common(Box::new(repo)).await;
+}
@@ -2,6 +2,11 @@ pub(super) mod currency;
 pub(super) mod oorder;
 pub(super) mod product_policy;
 mod product_price;
+pub(super) mod promotion;
+pub(super) mod shipment;
+pub(super) mod shipping_method;
+pub(super) mod stock_alert;
+pub(super) mod tax;
 
 use async_trait::async_trait;
 use std::boxed::Box;
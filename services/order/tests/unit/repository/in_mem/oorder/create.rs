@@ -11,7 +11,8 @@ use order::api::dto::ShippingMethod;
 use order::datastore::AppInMemoryDStore;
 use order::model::{
     CurrencyModel, OrderCurrencyModel, OrderLineIdentity, OrderLineModel, OrderLineModelSet,
-    ProductStockModel, ShippingModel, StockLevelModelSet, StockQuantityModel, StoreStockModel,
+    ProductStockModel, ShippingModel, StockAllocPolicyModelSet, StockLevelModelSet,
+    StockQuantityModel, StoreStockModel,
 };
 use order::repository::{
     AbsOrderRepo, AbsOrderStockRepo, AppStockRepoReserveReturn, OrderInMemRepo,
@@ -40,8 +41,10 @@ fn ut_setup_olines_gen_stock(
             }
             let store = stores.get_mut(&store_id).unwrap();
             let value = ProductStockModel {
+                warehouse_id: 0,
                 id_: ol.id().product_id(),
                 is_create: true,
+                arrival: None,
                 expiry: mock_expiry.into(),
                 quantity: StockQuantityModel {
                     total: ol.qty.reserved,
@@ -70,9 +73,10 @@ pub(super) async fn ut_setup_save_stock(
 
 pub(super) fn ut_setup_stock_rsv_cb(
     sl_set: &mut StockLevelModelSet,
-    ol_set: &OrderLineModelSet,
+    policies: &StockAllocPolicyModelSet,
+    ol_set: &mut OrderLineModelSet,
 ) -> AppStockRepoReserveReturn {
-    let errors = sl_set.try_reserve(ol_set);
+    let errors = sl_set.try_reserve(ol_set, policies);
     // for e1 in errors.iter() {
     //     println!("[utest][ERROR] stock reserve {:?}", e1);
     // }
@@ -115,14 +119,16 @@ async fn ut_verify_create_order(
             },
             sellers: seller_currencies,
         };
-        let ol_set = {
+        let mut ol_set = {
             let order_id = mock_oid[idx].clone();
             let owner_id = bcdata.0;
             let create_time = DateTime::parse_from_rfc3339(mock_create_time[idx]).unwrap();
             let args = (order_id, owner_id, create_time, currency, lines);
             OrderLineModelSet::try_from(args).unwrap()
         };
-        let result = stockrepo.try_reserve(ut_setup_stock_rsv_cb, &ol_set).await;
+        let result = stockrepo
+            .try_reserve(ut_setup_stock_rsv_cb, &mut ol_set)
+            .await;
         assert!(result.is_ok());
         let result = o_repo
             .save_contact(
@@ -367,6 +373,26 @@ async fn ut_verify_seller_currencies(
     }
 }
 
+async fn ut_verify_fetch_ids_by_owner(
+    time_range: (&str, &str),
+    data: Vec<(u32, usize, usize, usize, Vec<String>)>,
+    o_repo: &OrderInMemRepo,
+) {
+    let t_start = DateTime::parse_from_rfc3339(time_range.0).unwrap();
+    let t_end = DateTime::parse_from_rfc3339(time_range.1).unwrap();
+    for (usr_id, offset, limit, expect_total, expect_ids) in data {
+        let result = o_repo
+            .fetch_ids_by_owner(usr_id, t_start, t_end, offset, limit)
+            .await;
+        assert!(result.is_ok());
+        if let Ok((total, paged)) = result {
+            assert_eq!(total, expect_total);
+            let actual_ids = paged.into_iter().map(|(oid, _)| oid).collect::<Vec<_>>();
+            assert_eq!(actual_ids, expect_ids);
+        }
+    }
+}
+
 #[cfg_attr(rustfmt, rustfmt_skip)]
 #[tokio::test]
 async fn in_mem_create_ok() {
@@ -451,6 +477,37 @@ async fn in_mem_create_ok() {
         &o_repo,
     )
     .await;
+    ut_verify_fetch_ids_by_owner(
+        ("2022-11-07T03:58:17.001-01:00", "2022-11-09T22:13:18.409+04:00"),
+        vec![
+            (
+                mock_buyer_ids[0],
+                0,
+                5,
+                2,
+                vec![mock_oid[2].clone(), mock_oid[0].clone()],
+            ),
+            (mock_buyer_ids[0], 1, 5, 2, vec![mock_oid[0].clone()]),
+            (mock_buyer_ids[1], 0, 1, 1, vec![mock_oid[1].clone()]),
+        ],
+        &o_repo,
+    )
+    .await;
+    // the orders created exactly at both bounds of the time range are included
+    ut_verify_fetch_ids_by_owner(
+        (mock_create_time[0], mock_create_time[2]),
+        vec![
+            (
+                mock_buyer_ids[0],
+                0,
+                5,
+                2,
+                vec![mock_oid[2].clone(), mock_oid[0].clone()],
+            ),
+        ],
+        &o_repo,
+    )
+    .await;
     ut_verify_seller_currencies(
         mock_oid.clone(),
         mock_buyer_meta,
@@ -193,10 +193,12 @@ pub(crate) fn ut_setup_shipping(mock_seller_ids: &[u32; 2]) -> Vec<ShippingModel
             ShippingOptionModel {
                 seller_id: mock_seller_ids[0],
                 method: ShippingMethod::FedEx,
+                fee: 120,
             },
             ShippingOptionModel {
                 seller_id: mock_seller_ids[1],
                 method: ShippingMethod::UPS,
+                fee: 85,
             },
         ];
         ShippingModel {
@@ -224,6 +226,7 @@ pub(crate) fn ut_setup_shipping(mock_seller_ids: &[u32; 2]) -> Vec<ShippingModel
         let option = vec![ShippingOptionModel {
             seller_id: mock_seller_ids[0],
             method: ShippingMethod::FedEx,
+            fee: 0,
         }];
         ShippingModel {
             contact,
@@ -259,10 +262,12 @@ fn ut_setup_orderlines(seller_ids: &[u32; 2]) -> Vec<OrderLineModel> {
             reserved: d.4,
             paid: 0,
             paid_last_update: None,
+            backordered: 0,
         };
         let policy = OrderLineAppliedPolicyModel {
             reserved_until,
             warranty_until,
+            version: 0,
         };
         let attrprice = d.5.map(|v| HashMap::from([("duglas-567".to_string(), v)]));
         let attrs_charge = ProdAttriPriceModel::from((attr_lastupdate, attrprice));
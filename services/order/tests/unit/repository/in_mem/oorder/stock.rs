@@ -2,10 +2,10 @@ use std::collections::HashMap;
 use std::ptr;
 use std::sync::Arc;
 
-use chrono::{DateTime, Duration, FixedOffset};
+use chrono::{DateTime, Duration, FixedOffset, Local};
 use rust_decimal::Decimal;
 
-use ecommerce_common::api::dto::CurrencyDto;
+use ecommerce_common::api::dto::{CountryCode, CurrencyDto};
 use ecommerce_common::error::AppErrorCode;
 
 use order::api::rpc::dto::{
@@ -15,16 +15,19 @@ use order::api::web::dto::{OrderLineCreateErrorDto, OrderLineCreateErrorReason};
 use order::datastore::AppInMemoryDStore;
 use order::error::AppError;
 use order::model::{
-    CurrencyModel, OrderCurrencyModel, OrderLineAppliedPolicyModel, OrderLineIdentity,
-    OrderLineModel, OrderLineModelSet, OrderLinePriceModel, OrderLineQuantityModel,
-    ProdAttriPriceModel, ProductStockIdentity, ProductStockModel, StockLevelModelSet,
-    StockQtyRsvModel, StockQuantityModel, StoreStockModel,
+    CurrencyModel, OrderCurrencyModel, OrderLineAppliedPolicyModel, OrderLineDiscountModel,
+    OrderLineIdentity, OrderLineModel, OrderLineModelSet, OrderLinePriceModel,
+    OrderLineQuantityModel, ProdAttriPriceModel, ProductStockIdentity, ProductStockIdentity2,
+    ProductStockModel, PromotionModel, PromotionModelSet, PromotionRuleModel,
+    StockAllocPolicyModel, StockAllocPolicyModelSet, StockAllocStrategy, StockLevelModelSet,
+    StockQtyRsvModel, StockQuantityModel, StockWarehouseModel, StoreStockModel,
 };
 use order::repository::{
-    AbsOrderRepo, AbsOrderStockRepo, AppStockRepoReserveReturn, AppStockRepoReserveUserFunc,
+    AbsOrderRepo, AbsOrderStockRepo, AbsPromotionRepo, AppStockRepoReserveReturn,
+    AppStockRepoReserveUserFunc, OrderInMemRepo, PromotionInMemRepo,
 };
 
-use super::super::MockInMemDeadDataStore;
+use super::super::{in_mem_ds_ctx_setup, MockInMemDeadDataStore};
 use super::in_mem_repo_ds_setup;
 use crate::model::verify_stocklvl_model;
 
@@ -46,8 +49,10 @@ fn ut_init_data_product() -> [ProductStockModel; 10] {
 
     rawdata.map(
         |(id_, is_create, expiry, total, booked, cancelled)| ProductStockModel {
+            warehouse_id: 0,
             id_,
             is_create,
+            arrival: None,
             expiry: DateTime::parse_from_rfc3339(expiry).unwrap().into(),
             quantity: StockQuantityModel::new(total, booked, cancelled, None),
         },
@@ -95,6 +100,7 @@ async fn save_fetch_ok() {
         .iter()
         .flat_map(|m1| {
             m1.products.iter().map(|m2| ProductStockIdentity {
+                warehouse_id: 0,
                 store_id: m1.store_id,
                 product_id: m2.id_,
                 expiry: m2.expiry_without_millis(),
@@ -133,11 +139,13 @@ async fn update_existing_ok() {
         let chosen_store = &expect_slset.stores[0];
         vec![
             ProductStockIdentity {
+                warehouse_id: 0,
                 store_id: chosen_store.store_id,
                 product_id: chosen_store.products[2].id_,
                 expiry: chosen_store.products[2].expiry,
             },
             ProductStockIdentity {
+                warehouse_id: 0,
                 store_id: chosen_store.store_id,
                 product_id: chosen_store.products[4].id_,
                 expiry: chosen_store.products[4].expiry,
@@ -200,6 +208,7 @@ async fn same_product_diff_expiry() {
     let pids = {
         let chosen_store = &expect_slset.stores[0];
         vec![ProductStockIdentity {
+            warehouse_id: 0,
             store_id: chosen_store.store_id,
             product_id: chosen_store.products[0].id_,
             expiry: chosen_store.products[0].expiry,
@@ -228,11 +237,13 @@ async fn same_product_diff_expiry() {
         let chosen_store = &expect_slset_ks2.stores[0];
         vec![
             ProductStockIdentity {
+                warehouse_id: 0,
                 store_id: chosen_store.store_id,
                 product_id: chosen_store.products[0].id_,
                 expiry: chosen_store.products[0].expiry,
             },
             ProductStockIdentity {
+                warehouse_id: 0,
                 store_id: chosen_store.store_id,
                 product_id: chosen_store.products[1].id_,
                 expiry: chosen_store.products[1].expiry,
@@ -250,11 +261,96 @@ async fn same_product_diff_expiry() {
     }
 } // end of fn same_product_diff_expiry
 
+#[tokio::test]
+async fn fetch_by_product_ok() {
+    let mock_curr_time = DateTime::parse_from_rfc3339("2023-11-09T10:00:00-01:00").unwrap();
+    let repo = in_mem_repo_ds_setup::<AppInMemoryDStore>(8, Some(mock_curr_time)).await;
+    let stockrepo = repo.stock();
+    let all_products = ut_init_data_product();
+    let expect_slset = {
+        let mut store = UT_INIT_DATA_STORE[0].clone();
+        [0usize, 2, 8, 9]
+            .into_iter()
+            .map(|idx| store.products.push(all_products[idx].clone()))
+            .count();
+        StockLevelModelSet {
+            stores: vec![store],
+        }
+    };
+    let result = stockrepo.save(expect_slset.clone()).await;
+    assert!(result.is_ok());
+    let pids = vec![ProductStockIdentity2 {
+        store_id: expect_slset.stores[0].store_id,
+        product_id: all_products[2].id_,
+    }];
+    let result = stockrepo.fetch_by_product(pids).await;
+    assert!(result.is_ok());
+    if let Ok(actual) = result {
+        // only one of the stock records for the product hasn't expired yet
+        assert_eq!(actual.stores.len(), 1);
+        assert_eq!(actual.stores[0].products.len(), 1);
+        verify_stocklvl_model(&actual, &expect_slset, [0, 2], true);
+    }
+} // end of fn fetch_by_product_ok
+
+#[tokio::test]
+async fn fetch_by_store_ok() {
+    let repo = in_mem_repo_ds_setup::<AppInMemoryDStore>(10, None).await;
+    let stockrepo = repo.stock();
+    let all_products = ut_init_data_product();
+    let expect_slset = {
+        let mut stores = UT_INIT_DATA_STORE[..2].to_vec();
+        stores[0].products.extend_from_slice(&all_products[0..4]);
+        stores[1].products.extend_from_slice(&all_products[4..5]);
+        StockLevelModelSet { stores }
+    };
+    let result = stockrepo.save(expect_slset.clone()).await;
+    assert!(result.is_ok());
+    let store_id = expect_slset.stores[0].store_id;
+    // all products in the store, records in other stores excluded
+    let result = stockrepo.fetch_by_store(store_id, vec![], None, None).await;
+    assert!(result.is_ok());
+    if let Ok(actual) = result {
+        assert_eq!(actual.stores.len(), 1);
+        assert_eq!(actual.stores[0].store_id, store_id);
+        assert_eq!(actual.stores[0].products.len(), 4);
+        verify_stocklvl_model(&actual, &expect_slset, [0, 1], true);
+        verify_stocklvl_model(&actual, &expect_slset, [0, 3], true);
+    }
+    let pids = vec![all_products[1].id_, all_products[2].id_];
+    let result = stockrepo.fetch_by_store(store_id, pids, None, None).await;
+    assert!(result.is_ok());
+    if let Ok(actual) = result {
+        assert_eq!(actual.stores[0].products.len(), 2);
+        verify_stocklvl_model(&actual, &expect_slset, [0, 1], true);
+        verify_stocklvl_model(&actual, &expect_slset, [0, 2], true);
+    }
+    let (t0, t1) = (
+        DateTime::parse_from_rfc3339("2023-11-01T00:00:00+00:00").unwrap(),
+        DateTime::parse_from_rfc3339("2023-12-31T00:00:00+00:00").unwrap(),
+    );
+    let result = stockrepo
+        .fetch_by_store(store_id, vec![], Some(t0), Some(t1))
+        .await;
+    assert!(result.is_ok());
+    if let Ok(actual) = result {
+        assert_eq!(actual.stores[0].products.len(), 2);
+        verify_stocklvl_model(&actual, &expect_slset, [0, 1], true);
+        verify_stocklvl_model(&actual, &expect_slset, [0, 2], true);
+    }
+    let result = stockrepo.fetch_by_store(1999, vec![], None, None).await;
+    assert!(result.is_ok());
+    if let Ok(actual) = result {
+        assert!(actual.stores.is_empty());
+    }
+} // end of fn fetch_by_store_ok
+
 #[tokio::test]
 async fn save_dstore_error() {
     let repo = in_mem_repo_ds_setup::<MockInMemDeadDataStore>(4, None).await;
     let stockrepo = repo.stock();
     let pids = vec![ProductStockIdentity {
+        warehouse_id: 0,
         store_id: 1001,
         product_id: 9001,
         expiry: DateTime::parse_from_rfc3339("2023-11-09T09:16:01.035-01:00")
@@ -276,6 +372,7 @@ async fn fetch_dstore_error() {
     let pids = {
         let chosen_store = &UT_INIT_DATA_STORE[0];
         vec![ProductStockIdentity {
+            warehouse_id: 0,
             store_id: chosen_store.store_id,
             product_id: all_products[0].id_,
             expiry: all_products[0].expiry,
@@ -294,6 +391,7 @@ pub(crate) async fn ut_retrieve_stocklvl_qty(
     product: &ProductStockModel,
 ) -> (u32, u32, u32) {
     let pid = ProductStockIdentity {
+        warehouse_id: 0,
         store_id,
         product_id: product.id_,
         expiry: product.expiry,
@@ -316,7 +414,8 @@ pub(crate) async fn ut_retrieve_stocklvl_qty(
 
 fn mock_reserve_usr_cb_0(
     ms: &mut StockLevelModelSet,
-    req: &OrderLineModelSet,
+    _policies: &StockAllocPolicyModelSet,
+    req: &mut OrderLineModelSet,
 ) -> AppStockRepoReserveReturn {
     assert_eq!(ms.stores.len(), 1);
     assert_eq!(req.lines().len(), 1);
@@ -351,7 +450,8 @@ fn mock_reserve_usr_cb_0(
 
 pub(crate) fn mock_reserve_usr_cb_1(
     ms: &mut StockLevelModelSet,
-    req: &OrderLineModelSet,
+    policies: &StockAllocPolicyModelSet,
+    req: &mut OrderLineModelSet,
 ) -> AppStockRepoReserveReturn {
     for om in req.lines().iter() {
         let result = ms
@@ -360,8 +460,9 @@ pub(crate) fn mock_reserve_usr_cb_1(
             .find(|m| om.id().store_id() == m.store_id);
         assert!(result.is_some());
         if let Some(s) = result {
-            let result = s.try_reserve(req.id().as_str(), om);
-            assert!(result.is_none());
+            let policy = policies.resolve(s.store_id, om.id().product_id());
+            let result = s.try_reserve(req.id().as_str(), om, &policy, Local::now().to_utc());
+            assert!(result.is_ok());
         }
     }
     Ok(())
@@ -397,24 +498,26 @@ pub(crate) async fn ut_reserve_init_setup(
             reserved: num_req,
             paid: 0,
             paid_last_update: None,
+            backordered: 0,
         };
         let policy = OrderLineAppliedPolicyModel {
             reserved_until: mock_warranty.clone(),
             warranty_until: mock_warranty,
+            version: 0,
         };
         let price = OrderLinePriceModel::from((4, 4 * num_req));
         let attr_lastupdate = mock_warranty - Duration::days(14);
         let attrs_charge = ProdAttriPriceModel::from((attr_lastupdate, None));
         OrderLineModel::from((id_, price, policy, qty, attrs_charge))
     }];
-    let ol_set = {
+    let mut ol_set = {
         let order_id = order_id.to_string();
         let currency = ut_setup_order_currency(vec![store_id]);
         let create_time = DateTime::parse_from_rfc3339("2022-11-07T04:00:00.519-01:00").unwrap();
         let args = (order_id, 123, create_time, currency, olines_req);
         OrderLineModelSet::try_from(args).unwrap()
     };
-    let result = stockrepo.try_reserve(usr_cb, &ol_set).await;
+    let result = stockrepo.try_reserve(usr_cb, &mut ol_set).await;
     assert!(result.is_ok());
 } // end of fn ut_reserve_init_setup
 
@@ -483,10 +586,12 @@ async fn try_reserve_ok() {
                 reserved,
                 paid: 0,
                 paid_last_update: None,
+                backordered: 0,
             };
             let policy = OrderLineAppliedPolicyModel {
                 reserved_until: mock_warranty,
                 warranty_until: mock_warranty,
+                version: 0,
             };
             let price = OrderLinePriceModel::from((unit, total));
             let attrs_charge = ProdAttriPriceModel::from((attr_lastupdate, None));
@@ -494,7 +599,7 @@ async fn try_reserve_ok() {
         })
         .collect();
 
-    let ol_set = {
+    let mut ol_set = {
         let order_id = "AnotherMan".to_string();
         let lines = order_req;
         let owner_id = 123;
@@ -503,7 +608,9 @@ async fn try_reserve_ok() {
         let args = (order_id, owner_id, create_time, currency, lines);
         OrderLineModelSet::try_from(args).unwrap()
     };
-    let result = stockrepo.try_reserve(mock_reserve_usr_cb_1, &ol_set).await;
+    let result = stockrepo
+        .try_reserve(mock_reserve_usr_cb_1, &mut ol_set)
+        .await;
     assert!(result.is_ok());
     {
         // after reservation
@@ -521,9 +628,126 @@ async fn try_reserve_ok() {
     }
 } // end of try_reserve_ok
 
+fn ut_setup_promo_order(
+    order_id: &str,
+    store_id: u32,
+    product_id: u64,
+    promo_code: &str,
+    reserved_until: DateTime<FixedOffset>,
+) -> OrderLineModelSet {
+    let mut line = {
+        let id_ = OrderLineIdentity::from((store_id, product_id, 0));
+        let qty = OrderLineQuantityModel {
+            reserved: 1,
+            paid: 0,
+            paid_last_update: None,
+            backordered: 0,
+        };
+        let policy = OrderLineAppliedPolicyModel {
+            reserved_until,
+            warranty_until: reserved_until,
+            version: 0,
+        };
+        let price = OrderLinePriceModel::from((9, 9));
+        let attrs_charge = ProdAttriPriceModel::from((reserved_until - Duration::days(3), None));
+        OrderLineModel::from((id_, price, policy, qty, attrs_charge))
+    };
+    line.discount = Some(OrderLineDiscountModel {
+        code: promo_code.to_string(),
+        unit: 2,
+    });
+    let currency = ut_setup_order_currency(vec![store_id]);
+    let create_time = DateTime::parse_from_rfc3339("2022-11-07T04:00:00.519-01:00").unwrap();
+    let args = (order_id.to_string(), 124, create_time, currency, vec![line]);
+    OrderLineModelSet::try_from(args).unwrap()
+}
+
+#[tokio::test]
+async fn try_reserve_promotion_limit() {
+    let mock_curr_time = DateTime::parse_from_rfc3339("2022-01-01T18:49:08.035+08:00").unwrap();
+    let mock_warranty = DateTime::parse_from_rfc3339("2024-11-28T18:46:08.519-08:00").unwrap();
+    let ds = in_mem_ds_ctx_setup::<AppInMemoryDStore>(30);
+    let mem = ds.in_mem.as_ref().unwrap().clone();
+    let repo = OrderInMemRepo::new(mem.clone(), mock_curr_time)
+        .await
+        .unwrap();
+    let promo_repo = PromotionInMemRepo::new(mem).await.unwrap();
+    let stockrepo = repo.stock();
+    let all_products = ut_init_data_product();
+    let slset = {
+        let mut stores = UT_INIT_DATA_STORE[..1].to_vec();
+        stores[0].products.push(all_products[2].clone());
+        StockLevelModelSet { stores }
+    };
+    let result = stockrepo.save(slset).await;
+    assert!(result.is_ok());
+    let promo_code = "utest-rsv-limit";
+    let promotion = PromotionModel {
+        code: promo_code.to_string(),
+        store_id: 1001,
+        product_ids: vec![all_products[2].id_],
+        rule: PromotionRuleModel::FixedOff(2),
+        start_after: mock_curr_time,
+        end_before: mock_warranty,
+        max_usage: Some(1),
+        num_used: 0,
+    };
+    let result = promo_repo
+        .save(PromotionModelSet {
+            promotions: vec![promotion],
+        })
+        .await;
+    assert!(result.is_ok());
+    let product_id = all_products[2].id_;
+    // ------ subcase, the first order claims the promotion
+    let mut ol_set = ut_setup_promo_order("BigHero", 1001, product_id, promo_code, mock_warranty);
+    let result = stockrepo
+        .try_reserve(mock_reserve_usr_cb_1, &mut ol_set)
+        .await;
+    assert!(result.is_ok());
+    let ms = promo_repo
+        .fetch(vec![promo_code.to_string()])
+        .await
+        .unwrap();
+    assert_eq!(ms.promotions[0].num_used, 1);
+    // ------ subcase, nothing is reserved once the usage limit is reached
+    let mut ol_set = ut_setup_promo_order("BigZero", 1001, product_id, promo_code, mock_warranty);
+    let result = stockrepo
+        .try_reserve(mock_reserve_usr_cb_1, &mut ol_set)
+        .await;
+    let errors = match result {
+        Err(Ok(v)) => v,
+        _others => Vec::new(),
+    };
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].product_id, product_id);
+    assert!(matches!(
+        errors[0].reason,
+        OrderLineCreateErrorReason::PromotionExhausted
+    ));
+    let actual = ut_retrieve_stocklvl_qty(stockrepo.clone(), 1001, &all_products[2]).await;
+    assert_eq!(actual, (1, 0, 15));
+    let ms = promo_repo
+        .fetch(vec![promo_code.to_string()])
+        .await
+        .unwrap();
+    assert_eq!(ms.promotions[0].num_used, 1);
+    // ------ subcase, usage given back by the discarded order
+    let result = stockrepo
+        .release_promotion_usage(vec![promo_code.to_string()])
+        .await;
+    assert!(result.is_ok());
+    let ms = promo_repo
+        .fetch(vec![promo_code.to_string()])
+        .await
+        .unwrap();
+    assert_eq!(ms.promotions[0].num_used, 0);
+} // end of fn try_reserve_promotion_limit
+
 fn mock_reserve_usr_cb_2(
     ms: &mut StockLevelModelSet,
-    req: &OrderLineModelSet,
+    _policies: &StockAllocPolicyModelSet,
+    req: &mut OrderLineModelSet,
 ) -> AppStockRepoReserveReturn {
     assert_eq!(ms.stores.len(), 1);
     assert_eq!(ms.stores[0].products.len(), 2);
@@ -604,17 +828,19 @@ async fn try_reserve_shortage() {
             OrderLineAppliedPolicyModel {
                 reserved_until: mock_warranty,
                 warranty_until: mock_warranty,
+                version: 0,
             },
             OrderLineQuantityModel {
                 reserved,
                 paid: 0,
                 paid_last_update: None,
+                backordered: 0,
             },
             ProdAttriPriceModel::from((attr_lastupdate, None)),
         ))
     })
     .collect();
-    let ol_set = {
+    let mut ol_set = {
         let order_id = "xx1".to_string();
         let lines = order_req;
         let owner_id = 123;
@@ -623,7 +849,9 @@ async fn try_reserve_shortage() {
         let args = (order_id, owner_id, create_time, currency, lines);
         OrderLineModelSet::try_from(args).unwrap()
     };
-    let result = stockrepo.try_reserve(mock_reserve_usr_cb_2, &ol_set).await;
+    let result = stockrepo
+        .try_reserve(mock_reserve_usr_cb_2, &mut ol_set)
+        .await;
     assert!(result.is_err());
     if let Err(e) = result {
         assert!(e.is_ok());
@@ -654,7 +882,8 @@ async fn try_reserve_shortage() {
 
 fn mock_reserve_usr_cb_3(
     _ms: &mut StockLevelModelSet,
-    _req: &OrderLineModelSet,
+    _policies: &StockAllocPolicyModelSet,
+    _req: &mut OrderLineModelSet,
 ) -> AppStockRepoReserveReturn {
     let detail = Some(format!("unit-test"));
     let e = AppError {
@@ -686,16 +915,18 @@ async fn try_reserve_user_cb_err() {
             reserved: 9,
             paid: 0,
             paid_last_update: None,
+            backordered: 0,
         };
         let policy = OrderLineAppliedPolicyModel {
             reserved_until: mock_warranty,
             warranty_until: mock_warranty,
+            version: 0,
         };
         let price = OrderLinePriceModel::from((20, 179));
         let attrs_charge = ProdAttriPriceModel::from((attr_lastupdate, None));
         OrderLineModel::from((id_, price, policy, qty, attrs_charge))
     }];
-    let ol_set = {
+    let mut ol_set = {
         let order_id = "xx1".to_string();
         let lines = order_req;
         let owner_id = 321;
@@ -704,7 +935,9 @@ async fn try_reserve_user_cb_err() {
         let args = (order_id, owner_id, create_time, currency, lines);
         OrderLineModelSet::try_from(args).unwrap()
     };
-    let result = stockrepo.try_reserve(mock_reserve_usr_cb_3, &ol_set).await;
+    let result = stockrepo
+        .try_reserve(mock_reserve_usr_cb_3, &mut ol_set)
+        .await;
     assert!(result.is_err());
     if let Err(e) = result {
         assert!(e.is_err());
@@ -781,6 +1014,7 @@ async fn try_return_ok() {
     let data = StockLevelReturnDto {
         order_id: format!("AceMan"),
         items: vec![InventoryEditStockLevelDto {
+            warehouse_id: 0,
             qty_add: 1,
             expiry: mock_rsv_expiry,
             store_id: 1001,
@@ -795,6 +1029,7 @@ async fn try_return_ok() {
     let data = StockLevelReturnDto {
         order_id: format!("BatMan"),
         items: vec![InventoryEditStockLevelDto {
+            warehouse_id: 0,
             qty_add: 1,
             expiry: mock_rsv_expiry,
             store_id: 1001,
@@ -809,6 +1044,7 @@ async fn try_return_ok() {
     {
         // after stock return
         let pid = ProductStockIdentity {
+            warehouse_id: 0,
             store_id: 1001,
             product_id: 9004,
             expiry: expect_slset.stores[0].products[2].expiry.clone(),
@@ -881,12 +1117,14 @@ async fn try_return_input_err() {
         order_id: format!("AceMan"),
         items: vec![
             InventoryEditStockLevelDto {
+                warehouse_id: 0,
                 qty_add: 7,
                 expiry: mock_warranty,
                 store_id: 1001,
                 product_id: expect_slset.stores[0].products[3].id_,
             },
             InventoryEditStockLevelDto {
+                warehouse_id: 0,
                 qty_add: 8,
                 expiry: mock_warranty,
                 store_id: 1003,
@@ -899,6 +1137,7 @@ async fn try_return_input_err() {
     if let Ok(v) = result {
         assert_eq!(v.len(), 2);
         let pid = ProductStockIdentity {
+            warehouse_id: 0,
             store_id: 1003,
             product_id: 9004,
             expiry: expect_slset.stores[2].products[3].expiry.clone(),
@@ -911,3 +1150,137 @@ async fn try_return_input_err() {
         } // should not be modified
     }
 } // end of fn  try_return_input_err
+
+#[tokio::test]
+async fn save_fetch_alloc_policy_ok() {
+    let repo = in_mem_repo_ds_setup::<AppInMemoryDStore>(12, None).await;
+    let stockrepo = repo.stock();
+    let result = stockrepo.save_alloc_policy(vec![]).await;
+    assert!(result.is_err());
+    if let Err(e) = result {
+        assert_eq!(e.code, AppErrorCode::EmptyInputData);
+    }
+    let data = vec![
+        StockAllocPolicyModel {
+            store_id: 1023,
+            product_id: None,
+            strategy: StockAllocStrategy::Fifo,
+            min_shelf_days: 0,
+        },
+        StockAllocPolicyModel {
+            store_id: 1023,
+            product_id: Some(2603),
+            strategy: StockAllocStrategy::Fefo,
+            min_shelf_days: 21,
+        },
+        StockAllocPolicyModel {
+            store_id: 1024,
+            product_id: Some(2603),
+            strategy: StockAllocStrategy::Fifo,
+            min_shelf_days: 3,
+        },
+    ];
+    let result = stockrepo.save_alloc_policy(data.clone()).await;
+    assert!(result.is_ok());
+    // ---- overwrite existing policy
+    let modified = StockAllocPolicyModel {
+        store_id: 1023,
+        product_id: Some(2603),
+        strategy: StockAllocStrategy::Fefo,
+        min_shelf_days: 45,
+    };
+    let result = stockrepo.save_alloc_policy(vec![modified.clone()]).await;
+    assert!(result.is_ok());
+    let result = stockrepo.fetch_alloc_policy(vec![1023, 1025]).await;
+    assert!(result.is_ok());
+    let mut actual = result.unwrap().policies;
+    actual.sort_by_key(|m| m.product_id);
+    assert_eq!(actual, vec![data[0].clone(), modified]);
+    let result = stockrepo.fetch_alloc_policy(vec![1024]).await;
+    assert!(result.is_ok());
+    let actual = result.unwrap().policies;
+    assert_eq!(actual, vec![data[2].clone()]);
+} // end of fn save_fetch_alloc_policy_ok
+
+#[tokio::test]
+async fn save_fetch_warehouse_ok() {
+    let repo = in_mem_repo_ds_setup::<AppInMemoryDStore>(12, None).await;
+    let stockrepo = repo.stock();
+    let result = stockrepo.save_warehouses(vec![]).await;
+    assert!(result.is_err());
+    if let Err(e) = result {
+        assert_eq!(e.code, AppErrorCode::EmptyInputData);
+    }
+    let data = [
+        (1023, 0, CountryCode::TW, "Taipei", "Taipei"),
+        (1023, 4, CountryCode::TW, "Kaohsiung", "Fengshan"),
+        (1024, 0, CountryCode::JP, "Osaka", "Osaka"),
+    ]
+    .into_iter()
+    .map(
+        |(store_id, warehouse_id, country, region, city)| StockWarehouseModel {
+            store_id,
+            warehouse_id,
+            country,
+            region: region.to_string(),
+            city: city.to_string(),
+        },
+    )
+    .collect::<Vec<_>>();
+    let result = stockrepo.save_warehouses(data.clone()).await;
+    assert!(result.is_ok());
+    // ---- overwrite existing warehouse
+    let mut modified = data[1].clone();
+    modified.city = "Kaohsiung".to_string();
+    let result = stockrepo.save_warehouses(vec![modified.clone()]).await;
+    assert!(result.is_ok());
+    let result = stockrepo.fetch_warehouses(vec![1023, 1025]).await;
+    assert!(result.is_ok());
+    let mut actual = result.unwrap().warehouses;
+    actual.sort_by_key(|m| m.warehouse_id);
+    assert_eq!(actual, vec![data[0].clone(), modified]);
+    let result = stockrepo.fetch_warehouses(vec![1024]).await;
+    assert!(result.is_ok());
+    let actual = result.unwrap().warehouses;
+    assert_eq!(actual, vec![data[2].clone()]);
+} // end of fn save_fetch_warehouse_ok
+
+#[tokio::test]
+async fn same_product_diff_warehouse() {
+    let repo = in_mem_repo_ds_setup::<AppInMemoryDStore>(12, None).await;
+    let stockrepo = repo.stock();
+    let expiry = DateTime::parse_from_rfc3339("2023-10-09T18:30:01+08:00").unwrap();
+    let data = [(0u32, 11i32), (3, 5)]
+        .into_iter()
+        .map(|(warehouse_id, qty_add)| InventoryEditStockLevelDto {
+            qty_add,
+            store_id: 1023,
+            product_id: 2603,
+            expiry,
+            warehouse_id,
+        })
+        .collect::<Vec<_>>();
+    let slset = StockLevelModelSet { stores: vec![] }.update(data).unwrap();
+    let result = stockrepo.save(slset).await;
+    assert!(result.is_ok());
+    let pids = [0u32, 3, 7]
+        .into_iter()
+        .map(|warehouse_id| ProductStockIdentity {
+            store_id: 1023,
+            product_id: 2603,
+            warehouse_id,
+            expiry: expiry.to_utc(),
+        })
+        .collect();
+    let result = stockrepo.fetch(pids).await;
+    assert!(result.is_ok());
+    let actual = result.unwrap();
+    assert_eq!(actual.stores.len(), 1);
+    let mut found = actual.stores[0]
+        .products
+        .iter()
+        .map(|p| (p.warehouse_id, p.quantity.total))
+        .collect::<Vec<_>>();
+    found.sort();
+    assert_eq!(found, vec![(0, 11), (3, 5)]);
+} // end of fn same_product_diff_warehouse
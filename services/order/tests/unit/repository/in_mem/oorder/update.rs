@@ -14,9 +14,14 @@ use ecommerce_common::api::rpc::dto::{
 };
 use ecommerce_common::error::AppErrorCode;
 
+use order::api::rpc::dto::{StockLevelReturnDto, StockReturnErrorDto, StockReturnErrorReason};
+use order::api::web::dto::OrderLineCancelErrorReason;
 use order::datastore::AppInMemoryDStore;
 use order::error::AppError;
-use order::model::{CurrencyModel, OrderCurrencyModel, OrderLineModel, OrderLineModelSet};
+use order::model::{
+    CurrencyModel, OrderCurrencyModel, OrderLineCancelModel, OrderLineIdentity, OrderLineModel,
+    OrderLineModelSet, StockLevelModelSet,
+};
 use order::repository::{AbsOrderRepo, OrderInMemRepo};
 
 use super::create::{ut_setup_save_stock, ut_setup_stock_rsv_cb};
@@ -52,7 +57,7 @@ async fn ut_setup_saved_order(
     assert!(lines.len() >= 3);
     assert!(!billings.is_empty());
     assert!(!shippings.is_empty());
-    let ol_set = {
+    let mut ol_set = {
         let order_id = mock_oid.to_string();
         let currency = ut_setup_order_currency(mock_seller_ids);
         let create_time = DateTime::parse_from_rfc3339("2022-11-07T04:00:00.519-01:00").unwrap();
@@ -60,7 +65,9 @@ async fn ut_setup_saved_order(
         OrderLineModelSet::try_from(args).unwrap()
     };
     let stockrepo = o_repo.stock();
-    let result = stockrepo.try_reserve(ut_setup_stock_rsv_cb, &ol_set).await;
+    let result = stockrepo
+        .try_reserve(ut_setup_stock_rsv_cb, &mut ol_set)
+        .await;
     assert!(result.is_ok());
     let result = o_repo
         .save_contact(
@@ -406,3 +413,207 @@ async fn in_mem_scheduled_job_time_ok() {
     assert!(time3 > time2);
     assert_eq!(time3, time4);
 }
+
+#[tokio::test]
+async fn in_mem_cancel_lines_ok() {
+    let mock_seller_ids = [20u32, 44];
+    let oid = OrderLineModel::generate_order_id(7);
+    let mock_repo_time = DateTime::parse_from_rfc3339("2023-11-14T14:30:41+02:00").unwrap();
+    let o_repo = in_mem_repo_ds_setup::<AppInMemoryDStore>(30, Some(mock_repo_time)).await;
+    let lines = ut_setup_orderlines(&mock_seller_ids);
+    ut_setup_save_stock(o_repo.stock(), mock_repo_time, &lines).await;
+    ut_setup_saved_order(&o_repo, oid.as_str(), 124, lines, mock_seller_ids).await;
+    let cancel_time = DateTime::parse_from_rfc3339("2023-11-15T08:51:07+02:00").unwrap();
+    let pids = [
+        (mock_seller_ids[1], 190u64, 0u16),
+        (mock_seller_ids[0], 193, 0),
+    ]
+    .into_iter()
+    .map(OrderLineIdentity::from)
+    .collect::<Vec<_>>();
+    let cancel = OrderLineCancelModel {
+        usr_id: 124,
+        time: cancel_time,
+    };
+    let result = o_repo
+        .cancel_lines(
+            oid.as_str(),
+            pids.clone(),
+            cancel,
+            OrderLineModel::cancel_unpaid,
+        )
+        .await;
+    assert!(result.is_ok());
+    assert!(result.unwrap().is_empty());
+    let result = o_repo.fetch_lines_by_pid(oid.as_str(), pids.clone()).await;
+    assert!(result.is_ok());
+    let saved_lines = result.unwrap();
+    assert_eq!(saved_lines.len(), 2);
+    saved_lines
+        .iter()
+        .map(|line| {
+            let c = line.cancelled.as_ref().unwrap();
+            assert_eq!(c.usr_id, 124);
+            assert_eq!(c.time, cancel_time);
+        })
+        .count();
+    // cannot cancel the same lines twice
+    let cancel = OrderLineCancelModel {
+        usr_id: 124,
+        time: cancel_time + ChronoDuration::seconds(5),
+    };
+    let result = o_repo
+        .cancel_lines(oid.as_str(), pids, cancel, OrderLineModel::cancel_unpaid)
+        .await;
+    assert!(result.is_ok());
+    let errors = result.unwrap();
+    assert_eq!(errors.len(), 2);
+    assert!(errors
+        .iter()
+        .all(|e| matches!(e.reason, OrderLineCancelErrorReason::AlreadyCancelled)));
+} // end of fn in_mem_cancel_lines_ok
+
+#[tokio::test]
+async fn in_mem_cancel_lines_usr_cb_err() {
+    let mock_seller_ids = [21u32, 45];
+    let oid = OrderLineModel::generate_order_id(7);
+    let mock_repo_time = DateTime::parse_from_rfc3339("2023-11-14T14:30:41+02:00").unwrap();
+    let o_repo = in_mem_repo_ds_setup::<AppInMemoryDStore>(30, Some(mock_repo_time)).await;
+    let lines = ut_setup_orderlines(&mock_seller_ids);
+    ut_setup_save_stock(o_repo.stock(), mock_repo_time, &lines).await;
+    ut_setup_saved_order(&o_repo, oid.as_str(), 124, lines, mock_seller_ids).await;
+    let cancel_time = DateTime::parse_from_rfc3339("2023-11-15T08:51:07+02:00").unwrap();
+    let pids = [
+        (mock_seller_ids[1], 192u64, 0u16),
+        (mock_seller_ids[0], 9999, 0),
+    ]
+    .into_iter()
+    .map(OrderLineIdentity::from)
+    .collect::<Vec<_>>();
+    let cancel = OrderLineCancelModel {
+        usr_id: 124,
+        time: cancel_time,
+    };
+    let result = o_repo
+        .cancel_lines(
+            oid.as_str(),
+            pids.clone(),
+            cancel,
+            OrderLineModel::cancel_unpaid,
+        )
+        .await;
+    assert!(result.is_ok());
+    let errors = result.unwrap();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].product_id, 9999);
+    assert!(matches!(
+        errors[0].reason,
+        OrderLineCancelErrorReason::NotExist
+    ));
+    // the valid line in the same request should not be cancelled
+    let result = o_repo.fetch_lines_by_pid(oid.as_str(), pids).await;
+    assert!(result.is_ok());
+    let saved_lines = result.unwrap();
+    assert_eq!(saved_lines.len(), 1);
+    assert!(saved_lines[0].cancelled.is_none());
+} // end of fn in_mem_cancel_lines_usr_cb_err
+
+async fn ut_sum_booked_stock(o_repo: &OrderInMemRepo, store_id: u32, product_id: u64) -> u32 {
+    let result = o_repo
+        .stock()
+        .fetch_by_store(store_id, vec![product_id], None, None)
+        .await;
+    let ms = result.unwrap();
+    ms.stores
+        .iter()
+        .flat_map(|s| s.products.iter())
+        .filter(|p| p.id_ == product_id)
+        .map(|p| p.quantity.booked)
+        .sum()
+}
+
+fn ut_return_stock_cb(
+    ms: &mut StockLevelModelSet,
+    data: StockLevelReturnDto,
+) -> Vec<StockReturnErrorDto> {
+    ms.return_across_expiry(data)
+}
+
+fn ut_return_stock_err_cb(
+    _ms: &mut StockLevelModelSet,
+    data: StockLevelReturnDto,
+) -> Vec<StockReturnErrorDto> {
+    data.items
+        .into_iter()
+        .map(|d| StockReturnErrorDto {
+            seller_id: d.store_id,
+            product_id: d.product_id,
+            reason: StockReturnErrorReason::InvalidQuantity,
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn in_mem_cancel_return_stock_ok() {
+    let mock_seller_ids = [22u32, 46];
+    let oid = OrderLineModel::generate_order_id(7);
+    let mock_repo_time = DateTime::parse_from_rfc3339("2023-11-14T14:30:41+02:00").unwrap();
+    let o_repo = in_mem_repo_ds_setup::<AppInMemoryDStore>(30, Some(mock_repo_time)).await;
+    let lines = ut_setup_orderlines(&mock_seller_ids);
+    ut_setup_save_stock(o_repo.stock(), mock_repo_time, &lines).await;
+    ut_setup_saved_order(&o_repo, oid.as_str(), 124, lines, mock_seller_ids).await;
+    let pids = vec![OrderLineIdentity::from((mock_seller_ids[1], 190u64, 0u16))];
+    let num_rsved = {
+        let lines = o_repo.fetch_lines_by_pid(oid.as_str(), pids.clone()).await;
+        lines.unwrap()[0].qty.reserved
+    };
+    let booked_before = ut_sum_booked_stock(&o_repo, mock_seller_ids[1], 190).await;
+    assert!(booked_before >= num_rsved);
+    let cancel_time = DateTime::parse_from_rfc3339("2023-11-15T08:51:07+02:00").unwrap();
+    // stock cannot be returned, the cancellation is discarded as well
+    let cancel = OrderLineCancelModel {
+        usr_id: 124,
+        time: cancel_time,
+    };
+    let result = o_repo
+        .stock()
+        .try_cancel_return(
+            oid.as_str(),
+            pids.clone(),
+            cancel,
+            OrderLineModel::cancel_unpaid,
+            ut_return_stock_err_cb,
+        )
+        .await;
+    assert!(matches!(
+        result,
+        Err(AppError {
+            code: AppErrorCode::DataCorruption,
+            ..
+        })
+    ));
+    let saved_lines = o_repo.fetch_lines_by_pid(oid.as_str(), pids.clone()).await;
+    assert!(saved_lines.unwrap()[0].cancelled.is_none());
+    let booked = ut_sum_booked_stock(&o_repo, mock_seller_ids[1], 190).await;
+    assert_eq!(booked, booked_before);
+    // stock returned together with the cancellation
+    let cancel = OrderLineCancelModel {
+        usr_id: 124,
+        time: cancel_time,
+    };
+    let result = o_repo
+        .stock()
+        .try_cancel_return(
+            oid.as_str(),
+            pids.clone(),
+            cancel,
+            OrderLineModel::cancel_unpaid,
+            ut_return_stock_cb,
+        )
+        .await;
+    assert!(result.unwrap().is_empty());
+    let saved_lines = o_repo.fetch_lines_by_pid(oid.as_str(), pids).await;
+    assert!(saved_lines.unwrap()[0].cancelled.is_some());
+    let booked = ut_sum_booked_stock(&o_repo, mock_seller_ids[1], 190).await;
+    assert_eq!(booked, booked_before - num_rsved);
+} // end of fn in_mem_cancel_return_stock_ok
@@ -1,73 +1,131 @@
 use std::boxed::Box;
 
 use ecommerce_common::error::AppErrorCode;
+use ecommerce_common::model::BaseProductIdentity;
 
 use order::datastore::{AbstInMemoryDStore, AppInMemoryDStore};
-use order::model::{ProductPolicyModel, ProductPolicyModelSet};
+use order::model::{ProductBackorderPolicy, ProductPolicyModel, ProductPolicyModelSet};
 use order::repository::{AbstProductPolicyRepo, ProductPolicyInMemRepo};
 
 use super::{in_mem_ds_ctx_setup, MockInMemDeadDataStore};
 use crate::model::ut_clone_productpolicy;
 
-const UTEST_INIT_DATA: [ProductPolicyModel; 7] = [
+const UTEST_INIT_DATA: [ProductPolicyModel; 8] = [
     ProductPolicyModel {
+        store_id: 1001,
         product_id: 1556,
+        version: 0,
         min_num_rsv: 0,
         auto_cancel_secs: 309,
         warranty_hours: 7400,
         is_create: true,
         max_num_rsv: 2,
+        backorder: ProductBackorderPolicy::Disabled,
+        max_num_backorder: 0,
+        weight_gram: 0,
     },
     ProductPolicyModel {
+        store_id: 1001,
         product_id: 9273,
+        version: 0,
         min_num_rsv: 3,
         auto_cancel_secs: 900,
         warranty_hours: 7209,
         is_create: true,
         max_num_rsv: 6,
+        backorder: ProductBackorderPolicy::Disabled,
+        max_num_backorder: 0,
+        weight_gram: 0,
     },
     ProductPolicyModel {
+        store_id: 1001,
         product_id: 40051,
+        version: 0,
         min_num_rsv: 0,
         auto_cancel_secs: 707,
         warranty_hours: 1295,
         is_create: true,
         max_num_rsv: 0,
+        backorder: ProductBackorderPolicy::Disabled,
+        max_num_backorder: 0,
+        weight_gram: 0,
     },
     ProductPolicyModel {
+        store_id: 1001,
         product_id: 1620,
+        version: 0,
         min_num_rsv: 3,
         auto_cancel_secs: 1645,
         warranty_hours: 1918,
         is_create: true,
         max_num_rsv: 20,
+        backorder: ProductBackorderPolicy::Disabled,
+        max_num_backorder: 0,
+        weight_gram: 0,
     },
     ProductPolicyModel {
+        store_id: 1001,
         product_id: 14005,
+        version: 0,
         min_num_rsv: 0,
         auto_cancel_secs: 77,
         warranty_hours: 5129,
         is_create: true,
         max_num_rsv: 91,
+        backorder: ProductBackorderPolicy::Disabled,
+        max_num_backorder: 0,
+        weight_gram: 0,
     },
     ProductPolicyModel {
+        store_id: 1001,
         product_id: 1622,
+        version: 0,
         min_num_rsv: 15,
         auto_cancel_secs: 6451,
         warranty_hours: 9181,
         is_create: true,
         max_num_rsv: 57,
+        backorder: ProductBackorderPolicy::Disabled,
+        max_num_backorder: 0,
+        weight_gram: 0,
     },
     ProductPolicyModel {
+        store_id: 1001,
         product_id: 1622,
+        version: 1,
         min_num_rsv: 6,
         auto_cancel_secs: 1178,
         warranty_hours: 11086,
-        is_create: false,
+        is_create: true,
         max_num_rsv: 60,
+        backorder: ProductBackorderPolicy::Disabled,
+        max_num_backorder: 0,
+        weight_gram: 0,
+    },
+    ProductPolicyModel {
+        store_id: 1002,
+        product_id: 1556,
+        version: 0,
+        min_num_rsv: 1,
+        auto_cancel_secs: 486,
+        warranty_hours: 2017,
+        is_create: true,
+        max_num_rsv: 5,
+        backorder: ProductBackorderPolicy::Disabled,
+        max_num_backorder: 0,
+        weight_gram: 0,
     },
 ]; // end of UTEST_INIT_DATA
 
+fn ut_policy_ids(data: &[(u32, u64)]) -> Vec<BaseProductIdentity> {
+    data.iter()
+        .map(|(store_id, product_id)| BaseProductIdentity {
+            store_id: *store_id,
+            product_id: *product_id,
+        })
+        .collect()
+}
+
 async fn in_mem_repo_ds_setup<T: AbstInMemoryDStore + 'static>(
     max_items: u32,
 ) -> Box<dyn AbstProductPolicyRepo> {
@@ -90,7 +148,7 @@ pub(crate) async fn save_fetch_ok_common(repo: Box<dyn AbstProductPolicyRepo>) {
     };
     let result = repo.save(ppset).await;
     assert_eq!(result.is_ok(), true);
-    let chosen_ids = vec![14005, 1556, 40051];
+    let chosen_ids = ut_policy_ids(&[(1001, 14005), (1001, 1556), (1002, 1556), (1001, 40051)]);
     let result = repo.fetch(chosen_ids).await;
     {
         assert_eq!(result.is_ok(), true);
@@ -98,40 +156,115 @@ pub(crate) async fn save_fetch_ok_common(repo: Box<dyn AbstProductPolicyRepo>) {
         assert_eq!(modelset.policies.len(), 2);
         let exists = modelset.policies.iter().find(|m| m.product_id == 1556);
         assert_eq!(exists.unwrap(), &UTEST_INIT_DATA[0]);
+        let exists = modelset.policies.iter().any(|m| m.store_id == 1002);
+        assert!(!exists);
         let exists = modelset.policies.iter().find(|m| m.product_id == 40051);
         assert_eq!(exists.unwrap(), &UTEST_INIT_DATA[2]);
         let exists = modelset.policies.iter().any(|m| m.product_id == 14005);
         assert_eq!(exists, false);
     }
-    // ------ subcase, second bulk update
+    // ------ subcase, second bulk update, including policy of the same product
+    // in different store
     let ppset = {
         let items = UTEST_INIT_DATA[3..6]
             .iter()
+            .chain(UTEST_INIT_DATA[7..].iter())
             .map(ut_clone_productpolicy)
             .collect();
         ProductPolicyModelSet { policies: items }
     };
     let result = repo.save(ppset).await;
     assert_eq!(result.is_ok(), true);
-    let chosen_ids = vec![1622, 1620, 9273];
+    let chosen_ids = ut_policy_ids(&[
+        (1001, 1622),
+        (1001, 1620),
+        (1001, 9273),
+        (1001, 1556),
+        (1002, 1556),
+    ]);
     let result = repo.fetch(chosen_ids).await;
     let modelset = result.unwrap();
+    assert_eq!(modelset.policies.len(), 5);
     [
-        (9273, &UTEST_INIT_DATA[1]),
-        (1620, &UTEST_INIT_DATA[3]),
-        (1622, &UTEST_INIT_DATA[5]),
+        ((1001, 9273), &UTEST_INIT_DATA[1]),
+        ((1001, 1620), &UTEST_INIT_DATA[3]),
+        ((1001, 1622), &UTEST_INIT_DATA[5]),
+        ((1001, 1556), &UTEST_INIT_DATA[0]),
+        ((1002, 1556), &UTEST_INIT_DATA[7]),
     ]
     .into_iter()
-    .map(|(given_prod_id, expect_model)| {
+    .map(|((given_store_id, given_prod_id), expect_model)| {
         let exists = modelset
             .policies
             .iter()
-            .find(|m| m.product_id == given_prod_id);
+            .find(|m| m.store_id == given_store_id && m.product_id == given_prod_id);
         assert_eq!(exists.unwrap(), expect_model);
     })
     .count();
 } // end of fn save_fetch_ok_common
 
+pub(crate) async fn delete_fetch_ok_common(repo: Box<dyn AbstProductPolicyRepo>) {
+    let mock_policy = ProductPolicyModel {
+        store_id: 1003,
+        product_id: 1556,
+        version: 0,
+        min_num_rsv: 0,
+        auto_cancel_secs: 512,
+        warranty_hours: 1440,
+        is_create: true,
+        max_num_rsv: 9,
+        backorder: ProductBackorderPolicy::Disabled,
+        max_num_backorder: 0,
+        weight_gram: 0,
+    };
+    let chosen_ids = ut_policy_ids(&[(1003, 1556)]);
+    // ------ subcase, save 2 versions
+    for auto_cancel_secs in [512u32, 600] {
+        let mut item = ut_clone_productpolicy(&mock_policy);
+        item.auto_cancel_secs = auto_cancel_secs;
+        let ppset = ProductPolicyModelSet {
+            policies: vec![item],
+        };
+        let result = repo.save(ppset).await;
+        assert!(result.is_ok());
+    }
+    let modelset = repo.fetch(chosen_ids.clone()).await.unwrap();
+    assert_eq!(modelset.policies.len(), 1);
+    assert_eq!(modelset.policies[0].version, 1);
+    assert_eq!(modelset.policies[0].auto_cancel_secs, 600);
+    // ------ subcase, deleted policy is no longer visible
+    let result = repo.delete(chosen_ids.clone()).await;
+    assert!(result.is_ok());
+    let modelset = repo.fetch(chosen_ids.clone()).await.unwrap();
+    assert!(modelset.policies.is_empty());
+    // ------ subcase, create the policy again, version number continues
+    let ppset = ProductPolicyModelSet {
+        policies: vec![ut_clone_productpolicy(&mock_policy)],
+    };
+    let result = repo.save(ppset).await;
+    assert!(result.is_ok());
+    let modelset = repo.fetch(chosen_ids).await.unwrap();
+    assert_eq!(modelset.policies.len(), 1);
+    let actual = &modelset.policies[0];
+    assert_eq!(actual.version, 2);
+    assert_eq!(actual.auto_cancel_secs, 512);
+    assert!(!actual.is_create);
+    // ------ subcase, previous versions are still available after deletion
+    let versioned_ids = [0u32, 1, 9999]
+        .into_iter()
+        .map(|version| (ut_policy_ids(&[(1003, 1556)]).remove(0), version))
+        .collect::<Vec<_>>();
+    let modelset = repo.fetch_by_version(versioned_ids).await.unwrap();
+    assert_eq!(modelset.policies.len(), 2);
+    [(0u32, 512u32), (1, 600)]
+        .into_iter()
+        .map(|(version, expect_auto_cancel)| {
+            let found = modelset.policies.iter().find(|m| m.version == version);
+            assert_eq!(found.unwrap().auto_cancel_secs, expect_auto_cancel);
+        })
+        .count();
+} // end of fn delete_fetch_ok_common
+
 #[tokio::test]
 async fn save_fetch_ok_1() {
     let repo = in_mem_repo_ds_setup::<AppInMemoryDStore>(20).await;
@@ -158,7 +291,7 @@ async fn save_fetch_ok_2() {
     let result = repo.save(ppset).await;
     assert_eq!(result.is_ok(), true);
 
-    let result = repo.fetch(vec![1622u64]).await;
+    let result = repo.fetch(ut_policy_ids(&[(1001, 1622)])).await;
     {
         assert_eq!(result.is_ok(), true);
         let modelset = result.unwrap();
@@ -172,6 +305,12 @@ async fn save_fetch_ok_2() {
     }
 } // end of fn save_fetch_ok_2
 
+#[tokio::test]
+async fn delete_fetch_ok() {
+    let repo = in_mem_repo_ds_setup::<AppInMemoryDStore>(20).await;
+    delete_fetch_ok_common(repo).await;
+}
+
 #[tokio::test]
 async fn save_empty_input() {
     let repo = in_mem_repo_ds_setup::<AppInMemoryDStore>(9).await;
@@ -196,14 +335,15 @@ async fn save_dstore_error() {
     let result = repo.save(ppset).await;
     assert_eq!(result.is_err(), true);
     let error = result.err().unwrap();
-    assert_eq!(error.code, AppErrorCode::DataTableNotExist);
+    // previous version is always read before saving
+    assert_eq!(error.code, AppErrorCode::AcquireLockFailure);
     assert_eq!(error.detail, Some("utest".to_string()));
 }
 
 #[tokio::test]
 async fn fetch_dstore_error() {
     let repo = in_mem_repo_ds_setup::<MockInMemDeadDataStore>(10).await;
-    let result = repo.fetch(vec![1622u64]).await;
+    let result = repo.fetch(ut_policy_ids(&[(1001, 1622)])).await;
     assert_eq!(result.is_err(), true);
     let error = result.err().unwrap();
     assert_eq!(error.code, AppErrorCode::AcquireLockFailure);
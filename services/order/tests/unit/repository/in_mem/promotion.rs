@@ -0,0 +1,151 @@
+use std::boxed::Box;
+
+use chrono::DateTime;
+
+use order::datastore::{AbstInMemoryDStore, AppInMemoryDStore};
+use order::model::{PromotionModel, PromotionModelSet, PromotionRuleModel};
+use order::repository::{AbsPromotionRepo, PromotionInMemRepo};
+
+use super::in_mem_ds_ctx_setup;
+
+fn ut_setup_promotion(
+    code: &str,
+    store_id: u32,
+    product_ids: Vec<u64>,
+    rule: PromotionRuleModel,
+    max_usage: Option<u32>,
+) -> PromotionModel {
+    let start_after = DateTime::parse_from_rfc3339("2023-10-05T18:05:26+08:00").unwrap();
+    let end_before = DateTime::parse_from_rfc3339("2023-11-30T09:01:50+08:00").unwrap();
+    PromotionModel {
+        code: code.to_string(),
+        store_id,
+        product_ids,
+        rule,
+        start_after,
+        end_before,
+        max_usage,
+        num_used: 0,
+    }
+}
+
+async fn in_mem_repo_ds_setup<T: AbstInMemoryDStore + 'static>(
+    max_items: u32,
+) -> Box<dyn AbsPromotionRepo> {
+    let ds_ctx = in_mem_ds_ctx_setup::<T>(max_items);
+    let in_mem_ds = ds_ctx.in_mem.as_ref().unwrap().clone();
+    let result = PromotionInMemRepo::new(in_mem_ds).await;
+    assert!(result.is_ok());
+    let repo = result.unwrap();
+    Box::new(repo)
+}
+
+pub(crate) async fn save_fetch_ok_common(repo: Box<dyn AbsPromotionRepo>, code_prefix: &str) {
+    let codes = ["pct", "fix", "bxgy"]
+        .into_iter()
+        .map(|c| format!("{code_prefix}-{c}"))
+        .collect::<Vec<_>>();
+    let ms = PromotionModelSet {
+        promotions: vec![
+            ut_setup_promotion(
+                codes[0].as_str(),
+                1034,
+                vec![],
+                PromotionRuleModel::PercentOff(15),
+                None,
+            ),
+            ut_setup_promotion(
+                codes[1].as_str(),
+                1034,
+                vec![2803, 2805],
+                PromotionRuleModel::FixedOff(120),
+                Some(30),
+            ),
+            ut_setup_promotion(
+                codes[2].as_str(),
+                1035,
+                vec![3810],
+                PromotionRuleModel::BuyXGetY { buy: 3, free: 1 },
+                Some(2),
+            ),
+        ],
+    };
+    let result = repo.save(ms).await;
+    assert!(result.is_ok());
+    let mut fetch_codes = codes.clone();
+    fetch_codes.push(format!("{code_prefix}-missing"));
+    let result = repo.fetch(fetch_codes.clone()).await;
+    let ms = result.unwrap();
+    assert_eq!(ms.promotions.len(), 3);
+    [
+        (
+            0usize,
+            1034u32,
+            0usize,
+            PromotionRuleModel::PercentOff(15),
+            None,
+            0u32,
+        ),
+        (
+            1,
+            1034,
+            2,
+            PromotionRuleModel::FixedOff(120),
+            Some(30u32),
+            0,
+        ),
+        (
+            2,
+            1035,
+            1,
+            PromotionRuleModel::BuyXGetY { buy: 3, free: 1 },
+            Some(2),
+            0,
+        ),
+    ]
+    .into_iter()
+    .map(|(idx, store_id, num_prods, rule, max_usage, num_used)| {
+        let m = ms.promotions.iter().find(|m| m.code == codes[idx]).unwrap();
+        assert_eq!(m.store_id, store_id);
+        assert_eq!(m.product_ids.len(), num_prods);
+        assert_eq!(m.rule, rule);
+        assert_eq!(m.max_usage, max_usage);
+        assert_eq!(m.num_used, num_used);
+    })
+    .count();
+    // ------ subcase, update existing promotion, the usage count has to be kept
+    let mut m = ms
+        .promotions
+        .into_iter()
+        .find(|m| m.code == codes[1])
+        .unwrap();
+    m.rule = PromotionRuleModel::PercentOff(8);
+    m.max_usage = None;
+    m.num_used = 5;
+    let result = repo
+        .save(PromotionModelSet {
+            promotions: vec![m],
+        })
+        .await;
+    assert!(result.is_ok());
+    let result = repo.fetch(codes[1..2].to_vec()).await;
+    let ms = result.unwrap();
+    assert_eq!(ms.promotions.len(), 1);
+    let m = &ms.promotions[0];
+    assert_eq!(m.rule, PromotionRuleModel::PercentOff(8));
+    assert_eq!(m.max_usage, None);
+    assert_eq!(m.num_used, 0);
+} // end of fn save_fetch_ok_common
+
+#[tokio::test]
+async fn save_fetch_ok() {
+    let repo = in_mem_repo_ds_setup::<AppInMemoryDStore>(30).await;
+    save_fetch_ok_common(repo, "utest-inmem").await;
+}
+
+#[tokio::test]
+async fn save_empty_error() {
+    let repo = in_mem_repo_ds_setup::<AppInMemoryDStore>(30).await;
+    let result = repo.save(PromotionModelSet { promotions: vec![] }).await;
+    assert!(result.is_err());
+}
@@ -0,0 +1,121 @@
+use std::boxed::Box;
+
+use chrono::{Duration, Local};
+
+use ecommerce_common::error::AppErrorCode;
+
+use order::api::dto::ShippingMethod;
+use order::datastore::{AbstInMemoryDStore, AppInMemoryDStore};
+use order::model::{OrderLineIdentity, ShipmentLineModel, ShipmentModel, ShipmentStatus};
+use order::repository::{AbsShipmentRepo, ShipmentInMemRepo};
+
+use super::in_mem_ds_ctx_setup;
+
+async fn in_mem_repo_ds_setup<T: AbstInMemoryDStore + 'static>(
+    max_items: u32,
+) -> Box<dyn AbsShipmentRepo> {
+    let ds_ctx = in_mem_ds_ctx_setup::<T>(max_items);
+    let in_mem_ds = ds_ctx.in_mem.as_ref().unwrap().clone();
+    let result = ShipmentInMemRepo::new(in_mem_ds).await;
+    assert!(result.is_ok());
+    Box::new(result.unwrap())
+}
+
+fn ut_setup_shipment(seq: u16, seller_id: u32, lines: Vec<(u64, u16, u32)>) -> ShipmentModel {
+    // the database server may not keep fraction of a second
+    let packed_time = Local::now().fixed_offset() - Duration::hours(5);
+    let packed_time =
+        packed_time - Duration::nanoseconds(packed_time.timestamp_subsec_nanos() as i64);
+    let lines = lines
+        .into_iter()
+        .map(|(product_id, attr_seq, qty)| ShipmentLineModel {
+            id_: OrderLineIdentity::from((seller_id, product_id, attr_seq)),
+            qty,
+        })
+        .collect();
+    ShipmentModel {
+        seq,
+        seller_id,
+        carrier: ShippingMethod::BlackCatExpress,
+        tracking_number: None,
+        status: ShipmentStatus::Packed,
+        packed_time,
+        shipped_time: None,
+        delivered_time: None,
+        lines,
+    }
+}
+
+pub(crate) async fn create_update_fetch_common(repo: &dyn AbsShipmentRepo, oids: [&str; 2]) {
+    let objs = [
+        ut_setup_shipment(0, 1074, vec![(9801, 0, 2), (9801, 1, 3)]),
+        ut_setup_shipment(1, 1075, vec![(9802, 0, 1)]),
+        ut_setup_shipment(0, 1076, vec![(9803, 0, 5)]),
+    ];
+    let expect_packed_time = objs[0].packed_time;
+    let oid_seq = [oids[0], oids[0], oids[1]];
+    for (oid, obj) in oid_seq.into_iter().zip(objs) {
+        let result = repo.create(oid, obj).await;
+        assert!(result.unwrap());
+    }
+    let ms = repo.fetch_by_oid(oids[0]).await.unwrap();
+    assert_eq!(ms.len(), 2);
+    assert_eq!((ms[0].seq, ms[0].seller_id), (0, 1074));
+    assert_eq!((ms[1].seq, ms[1].seller_id), (1, 1075));
+    assert_eq!(ms[0].lines.len(), 2);
+    assert_eq!(ms[0].packed_time, expect_packed_time);
+    assert!(matches!(ms[0].carrier, ShippingMethod::BlackCatExpress));
+    let line = ms[0]
+        .lines
+        .iter()
+        .find(|l| l.id_.attrs_seq_num() == 1)
+        .unwrap();
+    assert_eq!((line.id_.product_id(), line.qty), (9801, 3));
+    // ------ subcase, duplicate sequence number
+    let dup = ut_setup_shipment(1, 1075, vec![(9802, 0, 1)]);
+    let result = repo.create(oids[0], dup).await;
+    assert!(!result.unwrap());
+    // ------ subcase, sequence number from outdated shipments
+    let stale = ut_setup_shipment(0, 1075, vec![(9802, 0, 1)]);
+    let result = repo.create(oids[0], stale).await;
+    assert!(!result.unwrap());
+    let ms = repo.fetch_by_oid(oids[0]).await.unwrap();
+    assert_eq!(ms.len(), 2);
+    assert_eq!(ms[0].seller_id, 1074);
+    // ------ subcase, update status
+    let mut m = ms.into_iter().next().unwrap();
+    let time_now = Local::now().fixed_offset();
+    let time_now = time_now - Duration::nanoseconds(time_now.timestamp_subsec_nanos() as i64);
+    m.status = ShipmentStatus::Delivered;
+    m.tracking_number = Some("BC-0038271".to_string());
+    m.shipped_time = Some(time_now);
+    m.delivered_time = Some(time_now);
+    let result = repo.update(oids[0], m).await;
+    assert!(result.is_ok());
+    let ms = repo.fetch_by_oid(oids[0]).await.unwrap();
+    assert_eq!(ms[0].status, ShipmentStatus::Delivered);
+    assert_eq!(ms[0].tracking_number.as_deref(), Some("BC-0038271"));
+    assert_eq!(ms[0].delivered_time, Some(time_now));
+    assert_eq!(ms[0].lines.len(), 2);
+    assert_eq!(ms[1].status, ShipmentStatus::Packed);
+    let ms = repo.fetch_by_oid(oids[1]).await.unwrap();
+    assert_eq!(ms.len(), 1);
+    assert_eq!(ms[0].lines[0].qty, 5);
+} // end of fn create_update_fetch_common
+
+#[tokio::test]
+async fn create_update_fetch_ok() {
+    let repo = in_mem_repo_ds_setup::<AppInMemoryDStore>(30).await;
+    create_update_fetch_common(repo.as_ref(), ["8e01a7", "8e01a8"]).await;
+}
+
+#[tokio::test]
+async fn update_nonexist_error() {
+    let repo = in_mem_repo_ds_setup::<AppInMemoryDStore>(30).await;
+    let obj = ut_setup_shipment(3, 1074, vec![(9801, 0, 2)]);
+    let result = repo.update("8e01a9", obj).await;
+    assert!(result.is_err());
+    if let Err(e) = result {
+        assert!(matches!(e.code, AppErrorCode::IOerror(_)));
+    }
+}
@@ -0,0 +1,108 @@
+use std::boxed::Box;
+
+use ecommerce_common::api::dto::CountryCode;
+use ecommerce_common::error::AppErrorCode;
+
+use order::api::dto::ShippingMethod;
+use order::datastore::{AbstInMemoryDStore, AppInMemoryDStore};
+use order::model::{ShippingMethodModel, ShippingMethodModelSet, ShippingRateModel};
+use order::repository::{AbsShippingMethodRepo, ShippingMethodInMemRepo};
+
+use super::in_mem_ds_ctx_setup;
+
+async fn in_mem_repo_ds_setup<T: AbstInMemoryDStore + 'static>(
+    max_items: u32,
+) -> Box<dyn AbsShippingMethodRepo> {
+    let ds_ctx = in_mem_ds_ctx_setup::<T>(max_items);
+    let in_mem_ds = ds_ctx.in_mem.as_ref().unwrap().clone();
+    let result = ShippingMethodInMemRepo::new(in_mem_ds).await;
+    assert!(result.is_ok());
+    Box::new(result.unwrap())
+}
+
+#[rustfmt::skip]
+fn ut_setup_method(
+    store_id: u32, method: ShippingMethod, rates: Vec<(CountryCode, Option<&str>, u32, u32)>,
+) -> ShippingMethodModel {
+    let rates = rates
+        .into_iter()
+        .map(|(country, region, max_weight_gram, fee)| ShippingRateModel {
+            country, region: region.map(String::from), max_weight_gram, fee,
+        })
+        .collect();
+    ShippingMethodModel { store_id, method, rates }
+}
+
+pub(crate) async fn save_fetch_common(repo: &dyn AbsShippingMethodRepo, store_ids: [u32; 2]) {
+    let methods = vec![
+        ut_setup_method(
+            store_ids[0],
+            ShippingMethod::UPS,
+            vec![
+                (CountryCode::TW, None, 1000, 90),
+                (CountryCode::TW, Some("Penghu"), 1000, 210),
+                (CountryCode::JP, None, 2000, 480),
+            ],
+        ),
+        ut_setup_method(
+            store_ids[0],
+            ShippingMethod::FedEx,
+            vec![(CountryCode::US, None, 3000, 950)],
+        ),
+        ut_setup_method(
+            store_ids[1],
+            ShippingMethod::BlackCatExpress,
+            vec![(CountryCode::TW, None, 5000, 120)],
+        ),
+    ];
+    let result = repo.save(ShippingMethodModelSet { methods }).await;
+    assert!(result.is_ok());
+    let ms = repo.fetch(vec![store_ids[0]]).await.unwrap();
+    assert_eq!(ms.methods.len(), 2);
+    let m = ms.find(store_ids[0], &ShippingMethod::UPS).unwrap();
+    assert_eq!(m.rates.len(), 3);
+    assert_eq!(m.rates[1].region.as_deref(), Some("Penghu"));
+    assert_eq!((m.rates[1].max_weight_gram, m.rates[1].fee), (1000, 210));
+    assert_eq!(m.rates[2].country, CountryCode::JP);
+    assert!(m.rates[2].region.is_none());
+    assert!(!ms.contains_store(store_ids[1]));
+    // ------ subcase, replace rate table, remove a method
+    let methods = vec![
+        ut_setup_method(
+            store_ids[0],
+            ShippingMethod::UPS,
+            vec![(CountryCode::TW, None, 4000, 175)],
+        ),
+        ut_setup_method(store_ids[0], ShippingMethod::FedEx, vec![]),
+    ];
+    let result = repo.save(ShippingMethodModelSet { methods }).await;
+    assert!(result.is_ok());
+    let ms = repo.fetch(store_ids.to_vec()).await.unwrap();
+    assert_eq!(ms.methods.len(), 2);
+    let m = ms.find(store_ids[0], &ShippingMethod::UPS).unwrap();
+    assert_eq!(m.rates.len(), 1);
+    assert_eq!((m.rates[0].max_weight_gram, m.rates[0].fee), (4000, 175));
+    assert!(ms.find(store_ids[0], &ShippingMethod::FedEx).is_none());
+    let m = ms
+        .find(store_ids[1], &ShippingMethod::BlackCatExpress)
+        .unwrap();
+    assert_eq!(m.rates[0].fee, 120);
+} // end of fn save_fetch_common
+
+#[tokio::test]
+async fn save_fetch_ok() {
+    let repo = in_mem_repo_ds_setup::<AppInMemoryDStore>(30).await;
+    save_fetch_common(repo.as_ref(), [1052, 1053]).await;
+    let ms = repo.fetch(vec![1054]).await.unwrap();
+    assert!(ms.methods.is_empty());
+}
+
+#[tokio::test]
+async fn save_empty_error() {
+    let repo = in_mem_repo_ds_setup::<AppInMemoryDStore>(30).await;
+    let result = repo.save(ShippingMethodModelSet { methods: vec![] }).await;
+    assert!(result.is_err());
+    if let Err(e) = result {
+        assert_eq!(e.code, AppErrorCode::EmptyInputData);
+    }
+}
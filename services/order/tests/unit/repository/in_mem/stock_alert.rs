@@ -0,0 +1,141 @@
+use std::boxed::Box;
+
+use chrono::{DateTime, Duration};
+
+use order::datastore::{AbstInMemoryDStore, AppInMemoryDStore};
+use order::model::{StockAlertKind, StockAlertModel, StockAlertThresholdModel};
+use order::repository::{AbsStockAlertRepo, StockAlertInMemRepo};
+
+use super::in_mem_ds_ctx_setup;
+
+async fn in_mem_repo_ds_setup<T: AbstInMemoryDStore + 'static>(
+    max_items: u32,
+) -> Box<dyn AbsStockAlertRepo> {
+    let ds_ctx = in_mem_ds_ctx_setup::<T>(max_items);
+    let in_mem_ds = ds_ctx.in_mem.as_ref().unwrap().clone();
+    let result = StockAlertInMemRepo::new(in_mem_ds).await;
+    assert!(result.is_ok());
+    let repo = result.unwrap();
+    Box::new(repo)
+}
+
+pub(crate) async fn save_fetch_threshold_common(repo: &dyn AbsStockAlertRepo, store_ids: [u32; 3]) {
+    let ms = vec![
+        StockAlertThresholdModel {
+            store_id: store_ids[0],
+            low_stock_qty: 5,
+            expiry_within_days: 14,
+        },
+        StockAlertThresholdModel {
+            store_id: store_ids[1],
+            low_stock_qty: 12,
+            expiry_within_days: 0,
+        },
+        StockAlertThresholdModel {
+            store_id: store_ids[2],
+            low_stock_qty: 0,
+            expiry_within_days: 0,
+        },
+    ];
+    let result = repo.save_thresholds(ms).await;
+    assert!(result.is_ok());
+    let result = repo.fetch_all_thresholds().await;
+    let ms = result.unwrap();
+    // the store which disables all alerts is skipped
+    assert!(!ms.iter().any(|m| m.store_id == store_ids[2]));
+    let m = ms.iter().find(|m| m.store_id == store_ids[0]).unwrap();
+    assert_eq!((m.low_stock_qty, m.expiry_within_days), (5, 14));
+    // ------ subcase, update existing threshold
+    let ms = vec![StockAlertThresholdModel {
+        store_id: store_ids[1],
+        low_stock_qty: 3,
+        expiry_within_days: 30,
+    }];
+    let result = repo.save_thresholds(ms).await;
+    assert!(result.is_ok());
+    let ms = repo.fetch_all_thresholds().await.unwrap();
+    let m = ms.iter().find(|m| m.store_id == store_ids[1]).unwrap();
+    assert_eq!((m.low_stock_qty, m.expiry_within_days), (3, 30));
+} // end of fn save_fetch_threshold_common
+
+pub(crate) async fn claim_discard_sent_common(repo: &dyn AbsStockAlertRepo, store_ids: [u32; 2]) {
+    let expiry = DateTime::parse_from_rfc3339("2024-09-10T18:30:15+08:00")
+        .unwrap()
+        .to_utc();
+    let ms = vec![
+        StockAlertModel {
+            kind: StockAlertKind::LowStock,
+            product_id: 3001,
+            expiry: None,
+            num_avail: 2,
+        },
+        StockAlertModel {
+            kind: StockAlertKind::ExpiringBatch,
+            product_id: 3001,
+            expiry: Some(expiry),
+            num_avail: 1,
+        },
+    ];
+    let result = repo.claim_sent(store_ids[0], ms.clone()).await;
+    assert_eq!(result.unwrap().len(), 2);
+    let result = repo.claim_sent(store_ids[1], ms[..1].to_vec()).await;
+    assert_eq!(result.unwrap().len(), 1);
+    let actual = repo.fetch_sent(store_ids[0]).await.unwrap();
+    assert_eq!(actual.len(), 2);
+    ms.iter()
+        .map(|expect| {
+            let found = actual.iter().find(|a| a.is_same(expect)).unwrap();
+            assert_eq!(found.num_avail, expect.num_avail);
+        })
+        .count();
+    // ------ subcase, the alerts already claimed are skipped
+    let new_m = StockAlertModel {
+        kind: StockAlertKind::ExpiringBatch,
+        product_id: 3002,
+        expiry: Some(expiry + Duration::days(2)),
+        num_avail: 7,
+    };
+    let mut new_ms = ms.clone();
+    new_ms.push(new_m);
+    let result = repo.claim_sent(store_ids[0], new_ms).await;
+    let claimed = result.unwrap();
+    assert_eq!(claimed.len(), 1);
+    assert_eq!(claimed[0].product_id, 3002);
+    let actual = repo.fetch_sent(store_ids[0]).await.unwrap();
+    assert_eq!(actual.len(), 3);
+    // ------ subcase, discard part of the sent alerts in a store
+    let result = repo.discard_sent(store_ids[0], ms.clone()).await;
+    assert!(result.is_ok());
+    let actual = repo.fetch_sent(store_ids[0]).await.unwrap();
+    assert_eq!(actual.len(), 1);
+    assert_eq!(actual[0].product_id, 3002);
+    assert_eq!(actual[0].expiry, Some(expiry + Duration::days(2)));
+    // alerts in other store are not affected
+    let actual = repo.fetch_sent(store_ids[1]).await.unwrap();
+    assert_eq!(actual.len(), 1);
+    assert_eq!(actual[0].kind, StockAlertKind::LowStock);
+    // ------ subcase, the discarded alert can be claimed again
+    let result = repo.claim_sent(store_ids[0], ms[..1].to_vec()).await;
+    assert_eq!(result.unwrap().len(), 1);
+    let actual = repo.fetch_sent(store_ids[0]).await.unwrap();
+    assert_eq!(actual.len(), 2);
+} // end of fn claim_discard_sent_common
+
+#[tokio::test]
+async fn save_fetch_threshold_ok() {
+    let repo = in_mem_repo_ds_setup::<AppInMemoryDStore>(30).await;
+    save_fetch_threshold_common(repo.as_ref(), [1089, 1090, 1091]).await;
+}
+
+#[tokio::test]
+async fn claim_discard_sent_ok() {
+    let repo = in_mem_repo_ds_setup::<AppInMemoryDStore>(30).await;
+    claim_discard_sent_common(repo.as_ref(), [1089, 1090]).await;
+}
+
+#[tokio::test]
+async fn save_threshold_empty_error() {
+    let repo = in_mem_repo_ds_setup::<AppInMemoryDStore>(30).await;
+    let result = repo.save_thresholds(vec![]).await;
+    assert!(result.is_err());
+}
@@ -0,0 +1,108 @@
+use std::boxed::Box;
+
+use rust_decimal::Decimal;
+
+use ecommerce_common::api::dto::CountryCode;
+
+use order::datastore::{AbstInMemoryDStore, AppInMemoryDStore};
+use order::model::{TaxRateModel, TaxRateModelSet};
+use order::repository::{AbsTaxRateRepo, TaxRateInMemRepo};
+
+use super::in_mem_ds_ctx_setup;
+
+fn ut_setup_tax_rate(
+    country: CountryCode,
+    region: Option<&str>,
+    store_id: Option<u32>,
+    rate: (i64, u32),
+) -> TaxRateModel {
+    TaxRateModel {
+        country,
+        region: region.map(String::from),
+        store_id,
+        rate: Decimal::new(rate.0, rate.1),
+    }
+}
+
+async fn in_mem_repo_ds_setup<T: AbstInMemoryDStore + 'static>(
+    max_items: u32,
+) -> Box<dyn AbsTaxRateRepo> {
+    let ds_ctx = in_mem_ds_ctx_setup::<T>(max_items);
+    let in_mem_ds = ds_ctx.in_mem.as_ref().unwrap().clone();
+    let result = TaxRateInMemRepo::new(in_mem_ds).await;
+    assert!(result.is_ok());
+    let repo = result.unwrap();
+    Box::new(repo)
+}
+
+pub(crate) async fn save_fetch_ok_common(repo: Box<dyn AbsTaxRateRepo>, store_ids: [u32; 2]) {
+    let ms = TaxRateModelSet {
+        rates: vec![
+            ut_setup_tax_rate(CountryCode::TH, None, None, (7, 2)),
+            ut_setup_tax_rate(CountryCode::TH, Some("Phuket"), None, (65, 3)),
+            ut_setup_tax_rate(CountryCode::TH, None, Some(store_ids[0]), (3, 2)),
+            ut_setup_tax_rate(CountryCode::TH, Some("Phuket"), Some(store_ids[1]), (1, 2)),
+            ut_setup_tax_rate(CountryCode::TH, Some("Chiang Mai"), None, (6, 2)),
+            ut_setup_tax_rate(CountryCode::ID, None, None, (11, 2)),
+        ],
+    };
+    let result = repo.save(ms).await;
+    assert!(result.is_ok());
+    let result = repo
+        .fetch(CountryCode::TH, "Phuket".to_string(), vec![store_ids[0]])
+        .await;
+    let ms = result.unwrap();
+    assert_eq!(ms.rates.len(), 3);
+    [
+        (None, None, Decimal::new(7, 2)),
+        (Some("Phuket"), None, Decimal::new(65, 3)),
+        (None, Some(store_ids[0]), Decimal::new(3, 2)),
+    ]
+    .into_iter()
+    .map(|(region, store_id, expect)| {
+        let m = ms
+            .rates
+            .iter()
+            .find(|m| m.region.as_deref() == region && m.store_id == store_id)
+            .unwrap();
+        assert_eq!(m.country, CountryCode::TH);
+        assert_eq!(m.rate, expect);
+    })
+    .count();
+    // ------ subcase, update existing rate
+    let ms = TaxRateModelSet {
+        rates: vec![ut_setup_tax_rate(
+            CountryCode::TH,
+            Some("Phuket"),
+            Some(store_ids[1]),
+            (2, 2),
+        )],
+    };
+    let result = repo.save(ms).await;
+    assert!(result.is_ok());
+    let result = repo
+        .fetch(CountryCode::TH, "Phuket".to_string(), store_ids.to_vec())
+        .await;
+    let ms = result.unwrap();
+    assert_eq!(ms.rates.len(), 4);
+    let m = ms
+        .rates
+        .iter()
+        .find(|m| m.store_id == Some(store_ids[1]))
+        .unwrap();
+    assert_eq!(m.region.as_deref(), Some("Phuket"));
+    assert_eq!(m.rate, Decimal::new(2, 2));
+} // end of fn save_fetch_ok_common
+
+#[tokio::test]
+async fn save_fetch_ok() {
+    let repo = in_mem_repo_ds_setup::<AppInMemoryDStore>(30).await;
+    save_fetch_ok_common(repo, [1089, 1090]).await;
+}
+
+#[tokio::test]
+async fn save_empty_error() {
+    let repo = in_mem_repo_ds_setup::<AppInMemoryDStore>(30).await;
+    let result = repo.save(TaxRateModelSet { rates: vec![] }).await;
+    assert!(result.is_err());
+}
@@ -1,5 +1,9 @@
+use chrono::{Duration, Local};
+
 use ecommerce_common::model::BaseProductIdentity;
 
+use order::api::dto::ProdAttrValueDto;
+use order::api::web::dto::OlineProductAttrDto;
 use order::model::{CartLineModel, CartModel};
 use order::repository::app_repo_cart;
 
@@ -12,9 +16,26 @@ fn ut_gen_line_model(value: (u32, u64, u32)) -> CartLineModel {
             product_id: value.1,
         },
         qty_req: value.2,
+        chosen_attr: None,
     }
 }
 
+fn ut_gen_line_attrs(data: &[(&str, ProdAttrValueDto)]) -> Vec<OlineProductAttrDto> {
+    data.iter()
+        .map(|(label, value)| {
+            let value = match value {
+                ProdAttrValueDto::Int(v) => ProdAttrValueDto::Int(*v),
+                ProdAttrValueDto::Str(v) => ProdAttrValueDto::Str(v.clone()),
+                ProdAttrValueDto::Bool(v) => ProdAttrValueDto::Bool(*v),
+            };
+            OlineProductAttrDto {
+                label_id: label.to_string(),
+                value,
+            }
+        })
+        .collect()
+}
+
 fn ut_verify_cart_model(actual: CartModel, expect: (u32, u8, &str, Vec<(u32, u64, u32)>)) {
     assert_eq!(actual.owner, expect.0);
     assert_eq!(actual.seq_num, expect.1);
@@ -72,6 +93,9 @@ async fn save_different_user_carts() {
             title,
             saved_lines: Vec::new(),
             new_lines,
+            sharable: false,
+            collaborators: Vec::new(),
+            owner_quota_lines: 0,
         };
         let result = repo.update(obj).await;
         assert!(result.is_ok());
@@ -115,6 +139,9 @@ async fn save_different_user_carts() {
             title,
             saved_lines,
             new_lines,
+            sharable: false,
+            collaborators: Vec::new(),
+            owner_quota_lines: 0,
         };
         let result = repo.update(obj).await;
         assert!(result.is_ok());
@@ -186,6 +213,9 @@ async fn discard_cart_ok() {
             title,
             saved_lines: Vec::new(),
             new_lines,
+            sharable: false,
+            collaborators: Vec::new(),
+            owner_quota_lines: 0,
         };
         let result = repo.update(obj).await;
         assert!(result.is_ok());
@@ -208,3 +238,160 @@ async fn discard_cart_ok() {
         ut_verify_cart_model(actual, item);
     }
 } // end of fn discard_cart_ok
+
+#[tokio::test]
+async fn save_fetch_chosen_attr_ok() {
+    let ds = dstore_ctx_setup();
+    let repo = app_repo_cart(ds).await.unwrap();
+    let (owner, seq_num) = (131u32, 5u8);
+    let new_lines = {
+        let mut line0 = ut_gen_line_model((3, 108, 24));
+        line0.chosen_attr = Some(ut_gen_line_attrs(&[
+            ("color", ProdAttrValueDto::Str("navy".to_string())),
+            ("length", ProdAttrValueDto::Int(38)),
+        ]));
+        let line1 = ut_gen_line_model((3, 991, 25));
+        vec![line0, line1]
+    };
+    let obj = CartModel {
+        owner,
+        seq_num,
+        title: "wardrobe".to_string(),
+        saved_lines: Vec::new(),
+        new_lines,
+        sharable: false,
+        collaborators: Vec::new(),
+        owner_quota_lines: 0,
+    };
+    let result = repo.update(obj).await;
+    assert!(result.is_ok());
+    let actual = repo.fetch_cart(owner, seq_num).await.unwrap();
+    assert_eq!(actual.saved_lines.len(), 2);
+    let line = actual
+        .saved_lines
+        .iter()
+        .find(|l| l.id_.product_id == 108)
+        .unwrap();
+    let attrs = line.chosen_attr.as_ref().unwrap();
+    assert_eq!(attrs.len(), 2);
+    assert_eq!(attrs[0].label_id.as_str(), "color");
+    assert!(matches!(&attrs[0].value, ProdAttrValueDto::Str(v) if v == "navy"));
+    assert!(matches!(attrs[1].value, ProdAttrValueDto::Int(38)));
+    let line = actual
+        .saved_lines
+        .iter()
+        .find(|l| l.id_.product_id == 991)
+        .unwrap();
+    assert!(line.chosen_attr.is_none());
+
+    // ------ subcase, modify chosen attributes of saved lines
+    let saved_lines = {
+        let mut line0 = ut_gen_line_model((3, 108, 26));
+        line0.chosen_attr = Some(ut_gen_line_attrs(&[(
+            "gift-wrap",
+            ProdAttrValueDto::Bool(true),
+        )]));
+        let line1 = ut_gen_line_model((3, 991, 27));
+        vec![line0, line1]
+    };
+    let obj = CartModel {
+        owner,
+        seq_num,
+        title: "wardrobe".to_string(),
+        saved_lines,
+        new_lines: Vec::new(),
+        sharable: false,
+        collaborators: Vec::new(),
+        owner_quota_lines: 0,
+    };
+    let result = repo.update(obj).await;
+    assert!(result.is_ok());
+    let actual = repo.fetch_cart(owner, seq_num).await.unwrap();
+    let line = actual
+        .saved_lines
+        .iter()
+        .find(|l| l.id_.product_id == 108)
+        .unwrap();
+    assert_eq!(line.qty_req, 26);
+    let attrs = line.chosen_attr.as_ref().unwrap();
+    assert_eq!(attrs.len(), 1);
+    assert_eq!(attrs[0].label_id.as_str(), "gift-wrap");
+    assert!(matches!(attrs[0].value, ProdAttrValueDto::Bool(true)));
+} // end of fn save_fetch_chosen_attr_ok
+
+#[tokio::test]
+async fn update_collaborators_ok() {
+    let ds = dstore_ctx_setup();
+    let repo = app_repo_cart(ds).await.unwrap();
+    let (owner, seq_num) = (132u32, 2u8);
+    let obj = CartModel {
+        owner,
+        seq_num,
+        title: "office supply".to_string(),
+        saved_lines: Vec::new(),
+        new_lines: vec![ut_gen_line_model((3, 108, 24))],
+        sharable: false,
+        collaborators: Vec::new(),
+        owner_quota_lines: 0,
+    };
+    let result = repo.update(obj).await;
+    assert!(result.is_ok());
+    let mut obj = repo.fetch_cart(owner, seq_num).await.unwrap();
+    assert!(!obj.sharable);
+    assert!(obj.collaborators.is_empty());
+    // ------ subcase, invite
+    let result = obj.invite(vec![133, 134, 132, 133]);
+    assert!(result.is_ok());
+    let result = repo.update_collaborators(&obj).await;
+    assert!(result.is_ok());
+    let mut obj = repo.fetch_cart(owner, seq_num).await.unwrap();
+    assert!(obj.sharable);
+    obj.collaborators.sort();
+    assert_eq!(obj.collaborators, vec![133, 134]);
+    assert_eq!(obj.title.as_str(), "office supply");
+    assert_eq!(obj.saved_lines.len(), 1);
+    assert!(obj.accessible(134));
+    assert!(!obj.accessible(135));
+    // ------ subcase, revoke
+    obj.revoke(vec![133, 134]);
+    let result = repo.update_collaborators(&obj).await;
+    assert!(result.is_ok());
+    let obj = repo.fetch_cart(owner, seq_num).await.unwrap();
+    assert!(!obj.sharable);
+    assert!(obj.collaborators.is_empty());
+    assert!(!obj.accessible(133));
+} // end of fn update_collaborators_ok
+
+#[tokio::test]
+async fn checkout_claim_ok() {
+    let ds = dstore_ctx_setup();
+    let repo = app_repo_cart(ds).await.unwrap();
+    let (owner, seq_num) = (136u32, 2u8);
+    let time_now = Local::now().fixed_offset();
+    let expiry = time_now + Duration::seconds(30);
+    let result = repo
+        .try_claim_checkout(owner, seq_num, time_now, expiry)
+        .await;
+    assert!(result.unwrap());
+    // ------ subcase, the cart is still claimed by previous request
+    let t1 = time_now + Duration::seconds(5);
+    let result = repo
+        .try_claim_checkout(owner, seq_num, t1, t1 + Duration::seconds(30))
+        .await;
+    assert!(!result.unwrap());
+    // ------ subcase, the claim expired
+    let t2 = time_now + Duration::seconds(31);
+    let result = repo
+        .try_claim_checkout(owner, seq_num, t2, t2 + Duration::seconds(30))
+        .await;
+    assert!(result.unwrap());
+    // ------ subcase, the claim released
+    let result = repo.release_checkout(owner, seq_num).await;
+    assert!(result.is_ok());
+    let result = repo
+        .try_claim_checkout(owner, seq_num, t2, t2 + Duration::seconds(30))
+        .await;
+    assert!(result.unwrap());
+    let result = repo.release_checkout(owner, seq_num).await;
+    assert!(result.is_ok());
+} // end of fn checkout_claim_ok
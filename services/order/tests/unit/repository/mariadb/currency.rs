@@ -3,7 +3,7 @@ use ecommerce_common::error::AppErrorCode;
 
 use order::repository::app_repo_currency;
 
-use super::super::in_mem::currency::ut_setup_currency_ms;
+use super::super::in_mem::currency::{save_fetch_history_common, ut_setup_currency_ms};
 use super::dstore_ctx_setup;
 
 #[tokio::test]
@@ -78,3 +78,10 @@ async fn save_error_range() {
         assert_eq!(e.code, AppErrorCode::ExceedingMaxLimit);
     }
 } // end of fn save_error_range
+
+#[tokio::test]
+async fn save_fetch_history_ok() {
+    let ds = dstore_ctx_setup();
+    let repo = app_repo_currency(ds).await.unwrap();
+    save_fetch_history_common(repo).await;
+}
@@ -3,6 +3,11 @@ mod currency;
 mod oorder;
 mod product_policy;
 mod product_price;
+mod promotion;
+mod shipment;
+mod shipping_method;
+mod stock_alert;
+mod tax;
 
 use std::env;
 use std::sync::Arc;
@@ -10,7 +10,9 @@ use ecommerce_common::api::dto::{CountryCode, CurrencyDto};
 
 use order::api::dto::ShippingMethod;
 use order::error::AppError;
-use order::model::{OrderCurrencyModel, OrderLineModelSet, StockLevelModelSet};
+use order::model::{
+    OrderCurrencyModel, OrderLineModelSet, StockAllocPolicyModelSet, StockLevelModelSet,
+};
 use order::repository::{app_repo_order, AbsOrderRepo, AppStockRepoReserveReturn};
 
 use super::super::super::in_mem::oorder::stock::ut_reserve_init_setup;
@@ -75,9 +77,10 @@ pub(super) async fn ut_verify_fetch_all_olines_ok(o_repo: &Box<dyn AbsOrderRepo>
 
 fn mock_reserve_usr_cb_0(
     ms: &mut StockLevelModelSet,
-    req: &OrderLineModelSet,
+    policies: &StockAllocPolicyModelSet,
+    req: &mut OrderLineModelSet,
 ) -> AppStockRepoReserveReturn {
-    let errors = ms.try_reserve(req);
+    let errors = ms.try_reserve(req, policies);
     assert!(errors.is_empty());
     Ok(())
 }
@@ -224,10 +227,10 @@ async fn fetch_lines_by_rsvtime_ok() {
                 v.name = mock_currency_label;
                 v.rate = mock_currency_rate;
             });
-        let ol_set = ut_oline_init_setup(mock_oid, 123, create_time, currency, lines);
+        let mut ol_set = ut_oline_init_setup(mock_oid, 123, create_time, currency, lines);
         let result = o_repo
             .stock()
-            .try_reserve(mock_reserve_usr_cb_0, &ol_set)
+            .try_reserve(mock_reserve_usr_cb_0, &mut ol_set)
             .await;
         assert!(result.is_ok());
         mock_rsv_qty += 2;
@@ -247,7 +250,8 @@ async fn fetch_lines_by_rsvtime_ok() {
 async fn fetch_toplvl_meta_ok() {
     let ds = dstore_ctx_setup();
     let o_repo = app_repo_order(ds).await.unwrap();
-    let now = Local::now().fixed_offset();
+    // same precision as the create time saved in database
+    let now = Local::now().fixed_offset().trunc_subsecs(3);
     let mut create_time = now.clone();
     let (mock_seller, mut mock_usr_id, mock_rsv_qty) = (1033, 126u32, 1u32);
     let mock_oids = ["0e927d76", "0e927d00", "0e927d78", "0e927d79", "0e927d8a"];
@@ -259,8 +263,8 @@ async fn fetch_toplvl_meta_ok() {
             (mock_seller, 9014), mock_rsv_qty, 29, Some(("bolu",5)), rsv_time,
         )];
         let currency = ut_default_order_currency(vec![mock_seller]);
-        let ol_set = ut_oline_init_setup(mock_oid, mock_usr_id, create_time, currency, lines);
-        let result = o_repo.stock().try_reserve(mock_reserve_usr_cb_0, &ol_set).await;
+        let mut ol_set = ut_oline_init_setup(mock_oid, mock_usr_id, create_time, currency, lines);
+        let result = o_repo.stock().try_reserve(mock_reserve_usr_cb_0, &mut ol_set).await;
         assert!(result.is_ok());
         mock_usr_id += 10;
     }
@@ -273,6 +277,25 @@ async fn fetch_toplvl_meta_ok() {
         assert!(oids.contains(&"0e927d00".to_string()));
         assert!(oids.contains(&"0e927d78".to_string()));
     }
+    let result = o_repo.fetch_ids_by_owner(146, time_start, time_end, 0, 5).await;
+    let (total, paged) = result.unwrap();
+    assert_eq!(total, 1);
+    assert_eq!(paged.len(), 1);
+    assert_eq!(paged[0].0.as_str(), "0e927d78");
+    let result = o_repo.fetch_ids_by_owner(126, time_start, time_end, 0, 5).await;
+    let (total, paged) = result.unwrap();
+    assert_eq!(total, 0);
+    assert!(paged.is_empty());
+    // the orders created exactly at both bounds of the time range are included
+    let (time_start, time_end) = (now + Duration::minutes(6), now + Duration::minutes(9));
+    let result = o_repo.fetch_ids_by_owner(136, time_start, time_end, 0, 5).await;
+    let (total, paged) = result.unwrap();
+    assert_eq!(total, 1);
+    assert_eq!(paged[0].0.as_str(), "0e927d00");
+    let result = o_repo.fetch_ids_by_owner(146, time_start, time_end, 0, 5).await;
+    let (total, paged) = result.unwrap();
+    assert_eq!(total, 1);
+    assert_eq!(paged[0].0.as_str(), "0e927d78");
     let result = o_repo.owner_id("0e927d8a").await;
     assert_eq!(result.unwrap(), 166);
     let result = o_repo.owner_id("0e927d78").await;
@@ -326,10 +349,10 @@ async fn fetch_seller_currency_ok() {
         });
         c
     };
-    let ol_set = ut_oline_init_setup(mock_oid, mock_buyer_id, create_time, currency, lines);
+    let mut ol_set = ut_oline_init_setup(mock_oid, mock_buyer_id, create_time, currency, lines);
     let result = o_repo
         .stock()
-        .try_reserve(mock_reserve_usr_cb_0, &ol_set)
+        .try_reserve(mock_reserve_usr_cb_0, &mut ol_set)
         .await;
     assert!(result.is_ok());
     let result = o_repo.currency_exrates(mock_oid).await;
@@ -26,12 +26,14 @@ async fn ut_setup_stock_product(
     init_qty: u32,
 ) {
     let product = ProductStockModel {
+        warehouse_id: 0,
         id_: mock_product_id,
         expiry: DateTime::parse_from_rfc3339("2023-11-07T08:12:05.008+02:00")
             .unwrap()
             .into(),
         quantity: StockQuantityModel::new(init_qty, 0, 0, None),
         is_create: true,
+        arrival: None,
     };
     let store = StoreStockModel {
         store_id: mock_store_id,
@@ -80,11 +82,13 @@ fn ut_oline_init_setup(
                 reserved: d.1,
                 paid: 0,
                 paid_last_update: None,
+                backordered: 0,
             };
             let price = OrderLinePriceModel::from((d.2, d.2 * d.1));
             let policy = OrderLineAppliedPolicyModel {
                 reserved_until: d.4 + Duration::minutes(2),
                 warranty_until: d.4 + Duration::minutes(4),
+                version: 0,
             };
             let att_lastupdate = d.4 - Duration::minutes(35);
             let attr_price = d.3.map(|v| HashMap::from([(v.0.to_string(), v.1)]));
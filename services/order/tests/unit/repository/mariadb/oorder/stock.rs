@@ -5,8 +5,8 @@ use chrono::{DateTime, Duration, Local};
 use order::api::rpc::dto::{InventoryEditStockLevelDto, StockLevelReturnDto, StockReturnErrorDto};
 use order::api::web::dto::{OrderLineCreateErrorDto, OrderLineCreateErrorReason};
 use order::model::{
-    OrderLineModelSet, ProductStockIdentity, ProductStockModel, StockLevelModelSet,
-    StockQtyRsvModel, StockQuantityModel, StoreStockModel,
+    OrderLineModelSet, ProductStockIdentity, ProductStockModel, StockAllocPolicyModelSet,
+    StockLevelModelSet, StockQtyRsvModel, StockQuantityModel, StoreStockModel,
 };
 use order::repository::{app_repo_order, AbsOrderStockRepo, AppStockRepoReserveReturn};
 
@@ -37,9 +37,10 @@ fn ut_init_data_product() -> [ProductStockModel; 12] {
         (9008, true, "2095-09-12T19:17:36.8492-08:00", 49, 0, 0),
         (9009, true, "2092-09-22T18:07:00.2015+05:00", 46, 1, 0),
     ];
-    rawdata.map(|(id_, is_create, expiry, total, booked, cancelled)| ProductStockModel {
+    rawdata.map(|(id_, is_create, expiry, total, booked, cancelled)| ProductStockModel { warehouse_id: 0,
         id_,
         is_create,
+        arrival: None,
         expiry: DateTime::parse_from_rfc3339(expiry).unwrap().into(),
         quantity: StockQuantityModel::new(total, booked, cancelled, None),
     })
@@ -85,6 +86,7 @@ async fn insert_base_qty_ok(
         .iter()
         .flat_map(|m1| {
             m1.products.iter().map(|m2| ProductStockIdentity {
+                warehouse_id: 0,
                 store_id: m1.store_id,
                 product_id: m2.id_,
                 expiry: m2.expiry,
@@ -122,6 +124,7 @@ async fn update_base_qty_ok(
         .iter()
         .flat_map(|m1| {
             m1.products.iter().map(|m2| ProductStockIdentity {
+                warehouse_id: 0,
                 store_id: m1.store_id,
                 product_id: m2.id_,
                 expiry: m2.expiry,
@@ -155,7 +158,8 @@ async fn save_fetch_ok() {
 
 fn mock_reserve_usr_cb_0(
     ms: &mut StockLevelModelSet,
-    req: &OrderLineModelSet,
+    _policies: &StockAllocPolicyModelSet,
+    req: &mut OrderLineModelSet,
 ) -> AppStockRepoReserveReturn {
     assert_eq!(ms.stores.len(), 1);
     assert_eq!(req.lines().len(), 1);
@@ -233,7 +237,7 @@ async fn try_reserve_ok() {
         let actual = ut_retrieve_stocklvl_qty(stockrepo.clone(), 1014, &all_products[11]).await;
         assert_eq!(actual, ((3 + 1), 1, 46));
     }
-    let ol_set = {
+    let mut ol_set = {
         let create_time = DateTime::parse_from_rfc3339("2022-11-29T07:29:01.027-03:00").unwrap();
         let lines = vec![
             ((1013, 9004), 2, 3, None, mock_warranty + Duration::minutes(1)),
@@ -247,7 +251,7 @@ async fn try_reserve_ok() {
         let currency = ut_default_order_currency(vec![1013, 1014]);
         ut_oline_init_setup("800eff40", 123, create_time, currency, lines)
     };
-    let result = stockrepo.try_reserve(mock_reserve_usr_cb_1, &ol_set).await;
+    let result = stockrepo.try_reserve(mock_reserve_usr_cb_1, &mut ol_set).await;
     assert!(result.is_ok());
     {
         let actual = ut_retrieve_stocklvl_qty(stockrepo.clone(), 1013, &all_products[2]).await;
@@ -267,7 +271,8 @@ async fn try_reserve_ok() {
 
 fn mock_reserve_usr_cb_2(
     ms: &mut StockLevelModelSet,
-    req: &OrderLineModelSet,
+    _policies: &StockAllocPolicyModelSet,
+    req: &mut OrderLineModelSet,
 ) -> AppStockRepoReserveReturn {
     assert_eq!(req.lines().len(), 2);
     assert_eq!(ms.stores.len(), 1);
@@ -333,7 +338,7 @@ async fn try_reserve_shortage() {
         "f1726b0e",
     )
     .await;
-    let ol_set = {
+    let mut ol_set = {
         let create_time = DateTime::parse_from_rfc3339("2022-11-29T06:35:00.519-02:00").unwrap();
         let lines = vec![
             ((1015, 9003), 12, 3, None, mock_warranty),
@@ -342,7 +347,9 @@ async fn try_reserve_shortage() {
         let currency = ut_default_order_currency(vec![1015]);
         ut_oline_init_setup("8100ffe0", 123, create_time, currency, lines)
     };
-    let result = stockrepo.try_reserve(mock_reserve_usr_cb_2, &ol_set).await;
+    let result = stockrepo
+        .try_reserve(mock_reserve_usr_cb_2, &mut ol_set)
+        .await;
     assert!(result.is_err());
     let error = result.unwrap_err();
     let mut input_errs = error.unwrap();
@@ -367,7 +374,8 @@ async fn try_reserve_shortage() {
 
 fn mock_reserve_usr_cb_3(
     ms: &mut StockLevelModelSet,
-    req: &OrderLineModelSet,
+    _policies: &StockAllocPolicyModelSet,
+    req: &mut OrderLineModelSet,
 ) -> AppStockRepoReserveReturn {
     macro_rules! inner_try_reserve {
         ($prod_id:literal, $expect_tot_qty_1:literal,
@@ -472,7 +480,7 @@ async fn try_return_ok() {
         let result = stockrepo.save(slset.clone()).await;
         assert!(result.is_ok());
     }
-    let ol_set = {
+    let mut ol_set = {
         let create_time = Local::now().fixed_offset();
         let mock_warranty = create_time + Duration::days(7);
         let lines = vec![
@@ -482,7 +490,7 @@ async fn try_return_ok() {
         let currency = ut_default_order_currency(vec![mock_seller]);
         ut_oline_init_setup(mock_oid, mock_usr_id, create_time, currency, lines)
     };
-    let result = stockrepo.try_reserve(mock_reserve_usr_cb_3, &ol_set).await;
+    let result = stockrepo.try_reserve(mock_reserve_usr_cb_3, &mut ol_set).await;
     assert!(result.is_ok());
     let data = {
         let items = [
@@ -491,7 +499,7 @@ async fn try_return_ok() {
             (mock_seller, 9008, 5, all_products[10].expiry.clone()),
         ]
         .into_iter()
-        .map(|d| InventoryEditStockLevelDto {
+        .map(|d| InventoryEditStockLevelDto { warehouse_id: 0,
             qty_add: d.2,
             store_id: d.0,
             product_id: d.1,
@@ -510,7 +518,7 @@ async fn try_return_ok() {
         .into_iter()
         .map(|idx| {
             let m = &all_products[idx];
-            ProductStockIdentity {
+            ProductStockIdentity { warehouse_id: 0,
                 store_id: mock_seller,
                 product_id: m.id_,
                 expiry: m.expiry_without_millis(),
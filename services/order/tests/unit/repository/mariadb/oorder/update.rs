@@ -4,7 +4,11 @@ use tokio::time::{sleep, Duration as TokioDuration};
 use ecommerce_common::api::rpc::dto::{
     OrderLinePaidUpdateDto, OrderLinePayUpdateErrorDto, OrderPaymentUpdateDto,
 };
-use order::model::{OrderLineIdentity, OrderLineModel, OrderLineModelSet, StockLevelModelSet};
+use order::api::web::dto::OrderLineCancelErrorReason;
+use order::model::{
+    OrderLineCancelModel, OrderLineIdentity, OrderLineModel, OrderLineModelSet,
+    StockAllocPolicyModelSet, StockLevelModelSet,
+};
 use order::repository::{app_repo_order, AppStockRepoReserveReturn};
 
 use super::super::dstore_ctx_setup;
@@ -12,9 +16,10 @@ use super::{ut_default_order_currency, ut_oline_init_setup, ut_setup_stock_produ
 
 fn mock_reserve_usr_cb_0(
     ms: &mut StockLevelModelSet,
-    req: &OrderLineModelSet,
+    policies: &StockAllocPolicyModelSet,
+    req: &mut OrderLineModelSet,
 ) -> AppStockRepoReserveReturn {
-    let errors = ms.try_reserve(req);
+    let errors = ms.try_reserve(req, policies);
     assert!(errors.is_empty());
     Ok(())
 }
@@ -50,10 +55,10 @@ async fn update_payment_ok() {
             ((1032, 9011), 15, 110, Some(("bolu", 3)), create_time),
         ];
         let currency = ut_default_order_currency(vec![1032, 1031]);
-        let ol_set = ut_oline_init_setup(mock_oid, 123, create_time, currency, lines);
+        let mut ol_set = ut_oline_init_setup(mock_oid, 123, create_time, currency, lines);
         let result = o_repo
             .stock()
-            .try_reserve(mock_reserve_usr_cb_0, &ol_set)
+            .try_reserve(mock_reserve_usr_cb_0, &mut ol_set)
             .await;
         assert!(result.is_ok());
     }
@@ -127,6 +132,86 @@ async fn update_payment_ok() {
     }
 } // end of fn update_payment_ok
 
+#[cfg(feature = "mariadb")]
+#[tokio::test]
+async fn cancel_lines_ok() {
+    let ds = dstore_ctx_setup();
+    let o_repo = app_repo_order(ds).await.unwrap();
+    let mock_oid = "0e927003718c";
+    let create_time = Local::now().fixed_offset();
+    ut_setup_stock_product(o_repo.stock(), 1034, 9030, 25).await;
+    ut_setup_stock_product(o_repo.stock(), 1034, 9031, 27).await;
+    ut_setup_stock_product(o_repo.stock(), 1035, 9032, 15).await;
+    {
+        let lines = vec![
+            ((1034, 9030), 3, 99, None, create_time),
+            ((1034, 9031), 4, 100, None, create_time),
+            ((1035, 9032), 5, 110, None, create_time),
+        ];
+        let currency = ut_default_order_currency(vec![1034, 1035]);
+        let mut ol_set = ut_oline_init_setup(mock_oid, 123, create_time, currency, lines);
+        let result = o_repo
+            .stock()
+            .try_reserve(mock_reserve_usr_cb_0, &mut ol_set)
+            .await;
+        assert!(result.is_ok());
+    }
+    let cancel_time = create_time + Duration::seconds(5);
+    let pids = vec![(1034u32, 9031u64), (1035, 9032)]
+        .into_iter()
+        .map(|d| OrderLineIdentity::from((d.0, d.1, 0)))
+        .collect::<Vec<_>>();
+    let cancel = OrderLineCancelModel {
+        usr_id: 123,
+        time: cancel_time,
+    };
+    let result = o_repo
+        .cancel_lines(
+            mock_oid,
+            pids.clone(),
+            cancel,
+            OrderLineModel::cancel_unpaid,
+        )
+        .await;
+    assert!(result.is_ok());
+    assert!(result.unwrap().is_empty());
+    let cancel = OrderLineCancelModel {
+        usr_id: 123,
+        time: cancel_time + Duration::seconds(1),
+    };
+    let result = o_repo
+        .cancel_lines(
+            mock_oid,
+            pids.clone(),
+            cancel,
+            OrderLineModel::cancel_unpaid,
+        )
+        .await;
+    assert!(result.is_ok());
+    let errors = result.unwrap();
+    assert_eq!(errors.len(), 2);
+    assert!(errors
+        .iter()
+        .all(|e| matches!(e.reason, OrderLineCancelErrorReason::AlreadyCancelled)));
+
+    let result = o_repo.fetch_all_lines(mock_oid.to_string()).await;
+    assert!(result.is_ok());
+    let lines = result.unwrap();
+    assert_eq!(lines.len(), 3);
+    lines
+        .iter()
+        .map(|line| {
+            if line.id().product_id() == 9030 {
+                assert!(line.cancelled.is_none());
+            } else {
+                let c = line.cancelled.as_ref().unwrap();
+                assert_eq!(c.usr_id, 123);
+                assert_eq!(c.time.round_subsecs(0), cancel_time.round_subsecs(0));
+            }
+        })
+        .count();
+} // end of fn cancel_lines_ok
+
 #[cfg(feature = "mariadb")]
 #[tokio::test]
 async fn cancel_unpaid_job_time_ok() {
@@ -1,6 +1,6 @@
 use order::repository::app_repo_product_policy;
 
-use super::super::in_mem::product_policy::save_fetch_ok_common;
+use super::super::in_mem::product_policy::{delete_fetch_ok_common, save_fetch_ok_common};
 use crate::repository::mariadb::dstore_ctx_setup;
 
 #[tokio::test]
@@ -9,3 +9,10 @@ async fn save_fetch_ok() {
     let repo = app_repo_product_policy(ds).await.unwrap();
     save_fetch_ok_common(repo).await;
 }
+
+#[tokio::test]
+async fn delete_fetch_ok() {
+    let ds = dstore_ctx_setup();
+    let repo = app_repo_product_policy(ds).await.unwrap();
+    delete_fetch_ok_common(repo).await;
+}
@@ -0,0 +1,16 @@
+use chrono::Local as LocalTime;
+
+use order::repository::app_repo_promotion;
+
+use super::super::in_mem::promotion::save_fetch_ok_common;
+use crate::repository::mariadb::dstore_ctx_setup;
+
+#[tokio::test]
+async fn save_fetch_ok() {
+    let ds = dstore_ctx_setup();
+    let repo = app_repo_promotion(ds).await.unwrap();
+    // usage count is never reset in the database, different code prefix is
+    // required in each test run
+    let code_prefix = format!("utest-{}", LocalTime::now().timestamp());
+    save_fetch_ok_common(repo, code_prefix.as_str()).await;
+}
@@ -0,0 +1,16 @@
+use chrono::Local;
+
+use order::repository::app_repo_shipment;
+
+use super::super::in_mem::shipment::create_update_fetch_common;
+use crate::repository::mariadb::dstore_ctx_setup;
+
+#[tokio::test]
+async fn create_update_fetch_ok() {
+    let ds = dstore_ctx_setup();
+    let repo = app_repo_shipment(ds).await.unwrap();
+    // shipments are never removed, avoid conflict with previous test runs
+    let ts = Local::now().timestamp_micros();
+    let oids = [format!("{:016x}", ts), format!("{:016x}", ts + 1)];
+    create_update_fetch_common(repo.as_ref(), [oids[0].as_str(), oids[1].as_str()]).await;
+}
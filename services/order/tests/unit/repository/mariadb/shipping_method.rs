@@ -0,0 +1,11 @@
+use order::repository::app_repo_shipping_method;
+
+use super::super::in_mem::shipping_method::save_fetch_common;
+use crate::repository::mariadb::dstore_ctx_setup;
+
+#[tokio::test]
+async fn save_fetch_ok() {
+    let ds = dstore_ctx_setup();
+    let repo = app_repo_shipping_method(ds).await.unwrap();
+    save_fetch_common(repo.as_ref(), [1052, 1053]).await;
+}
@@ -0,0 +1,18 @@
+use order::repository::app_repo_stock_alert;
+
+use super::super::in_mem::stock_alert::{claim_discard_sent_common, save_fetch_threshold_common};
+use crate::repository::mariadb::dstore_ctx_setup;
+
+#[tokio::test]
+async fn save_fetch_threshold_ok() {
+    let ds = dstore_ctx_setup();
+    let repo = app_repo_stock_alert(ds).await.unwrap();
+    save_fetch_threshold_common(repo.as_ref(), [1089, 1090, 1091]).await;
+}
+
+#[tokio::test]
+async fn claim_discard_sent_ok() {
+    let ds = dstore_ctx_setup();
+    let repo = app_repo_stock_alert(ds).await.unwrap();
+    claim_discard_sent_common(repo.as_ref(), [1089, 1090]).await;
+}
@@ -0,0 +1,11 @@
+use order::repository::app_repo_tax_rate;
+
+use super::super::in_mem::tax::save_fetch_ok_common;
+use crate::repository::mariadb::dstore_ctx_setup;
+
+#[tokio::test]
+async fn save_fetch_ok() {
+    let ds = dstore_ctx_setup();
+    let repo = app_repo_tax_rate(ds).await.unwrap();
+    save_fetch_ok_common(repo, [1089, 1090]).await;
+}
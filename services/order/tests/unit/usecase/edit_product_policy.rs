@@ -6,17 +6,25 @@ use std::sync::Arc;
 
 use ecommerce_common::error::AppErrorCode;
 
-use order::api::web::dto::ProductPolicyDto;
+use order::api::web::dto::{ProductPolicyDeleteReqDto, ProductPolicyDto};
+use order::constant::app_meta;
+use order::datastore::{AbstInMemoryDStore, AppInMemoryDStore};
 use order::error::AppError;
+use order::model::{ProductBackorderPolicy, ProductPolicyModel, ProductPolicyModelSet};
+use order::repository::{AbstProductPolicyRepo, ProductPolicyInMemRepo};
 use order::usecase::{
-    AppUseKsRPCreply, EditProductPolicyResult, EditProductPolicyUseCase, ProductInfoReq,
-    ProductInfoResp,
+    AppUseKsRPCreply, DeleteProductPolicyUseCase, EditProductPolicyResult,
+    EditProductPolicyUseCase, ProductInfoReq, ProductInfoResp,
 };
 use order::{
-    AbsRpcClientCtx, AbsRpcServerCtx, AbstractRpcClient, AbstractRpcContext, AppRpcCfg,
+    AbsRpcClientCtx, AbsRpcServerCtx, AbstractRpcClient, AbstractRpcContext,
+    AppAuthClaimPermission, AppAuthPermissionCode, AppAuthedClaim, AppInMemoryDbCfg, AppRpcCfg,
     AppRpcClientReqProperty, AppRpcReply, AppRpcRouteHdlrFn, AppSharedState,
 };
 
+use super::verify_store::ut_setup_store_supervisor_rpc;
+use crate::{ut_setup_share_state, MockConfidential};
+
 const UTEST_USR_PROF_ID: u32 = 99674;
 struct UTestDummyRpcContext {}
 
@@ -70,10 +78,10 @@ impl UTestDummyRpcContext {
 fn setup_data() -> Vec<ProductPolicyDto> {
     let raw = r#"
         [
-            {"product_id":22, "auto_cancel_secs":600, "warranty_hours":1800 },
-            {"product_id":168, "auto_cancel_secs":610, "warranty_hours":1700 },
-            {"product_id":79, "auto_cancel_secs":630, "warranty_hours":1600 },
-            {"product_id":19, "auto_cancel_secs":660, "warranty_hours":1500 }
+            {"seller_id":1015, "product_id":22, "auto_cancel_secs":600, "warranty_hours":1800 },
+            {"seller_id":1015, "product_id":168, "auto_cancel_secs":610, "warranty_hours":1700 },
+            {"seller_id":1015, "product_id":79, "auto_cancel_secs":630, "warranty_hours":1600 },
+            {"seller_id":1015, "product_id":19, "auto_cancel_secs":660, "warranty_hours":1500 }
         ]
     "#;
     deserialize_json(raw).unwrap()
@@ -215,3 +223,115 @@ async fn check_product_existence_found_nonexist_item() {
     let missing_product_ids = result.unwrap();
     assert_eq!(missing_product_ids, vec![168]);
 }
+
+async fn ut_setup_policy_repo(policies: Vec<(u32, u64)>) -> Box<dyn AbstProductPolicyRepo> {
+    let cfg = AppInMemoryDbCfg {
+        alias: "utest".to_string(),
+        max_items: 20,
+    };
+    let dstore: Box<dyn AbstInMemoryDStore> = Box::new(AppInMemoryDStore::new(&cfg));
+    let repo = ProductPolicyInMemRepo::new(Arc::new(dstore)).await.unwrap();
+    let policies = policies
+        .into_iter()
+        .map(|(store_id, product_id)| ProductPolicyModel {
+            store_id,
+            product_id,
+            version: 0,
+            auto_cancel_secs: 600,
+            warranty_hours: 1800,
+            max_num_rsv: 0,
+            min_num_rsv: 0,
+            is_create: true,
+            backorder: ProductBackorderPolicy::Disabled,
+            max_num_backorder: 0,
+            weight_gram: 0,
+        })
+        .collect();
+    repo.save(ProductPolicyModelSet { policies }).await.unwrap();
+    Box::new(repo)
+}
+
+fn ut_setup_delete_usecase(
+    repo: Box<dyn AbstProductPolicyRepo>,
+    perm_codes: Vec<AppAuthPermissionCode>,
+    store_supervisor_id: u32,
+) -> DeleteProductPolicyUseCase {
+    let shr_state = ut_setup_share_state("config_ok_no_sqldb.json", Box::new(MockConfidential {}));
+    let perms = perm_codes
+        .into_iter()
+        .map(|codename| AppAuthClaimPermission {
+            app_code: app_meta::RESOURCE_QUOTA_AP_CODE,
+            codename,
+        })
+        .collect();
+    let authed_usr = AppAuthedClaim {
+        profile: UTEST_USR_PROF_ID,
+        iat: 0,
+        exp: 0,
+        aud: Vec::new(),
+        quota: vec![],
+        perms,
+    };
+    DeleteProductPolicyUseCase {
+        log: shr_state.log_context().clone(),
+        repo,
+        rpc_ctx: ut_setup_store_supervisor_rpc(store_supervisor_id),
+        authed_usr,
+    }
+}
+
+fn ut_delete_req(data: Vec<(u32, u64)>) -> Vec<ProductPolicyDeleteReqDto> {
+    data.into_iter()
+        .map(|(seller_id, product_id)| ProductPolicyDeleteReqDto {
+            seller_id,
+            product_id,
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn delete_policy_ok() {
+    let repo = ut_setup_policy_repo(vec![(1015, 22), (1015, 168), (1016, 22)]).await;
+    let perms = vec![AppAuthPermissionCode::can_create_product_policy];
+    let uc = ut_setup_delete_usecase(repo, perms, UTEST_USR_PROF_ID);
+    let req = ut_delete_req(vec![(1015, 22), (1015, 168)]);
+    let result = uc.execute(req).await;
+    assert_eq!(result, EditProductPolicyResult::OK);
+}
+
+#[tokio::test]
+async fn delete_policy_nonexist() {
+    let repo = ut_setup_policy_repo(vec![(1015, 22), (1016, 168)]).await;
+    let perms = vec![AppAuthPermissionCode::can_create_product_policy];
+    let uc = ut_setup_delete_usecase(repo, perms, UTEST_USR_PROF_ID);
+    let req = ut_delete_req(vec![(1015, 22), (1015, 168)]);
+    let result = uc.execute(req).await;
+    let errors = match result {
+        EditProductPolicyResult::ClientError(v) => v,
+        _others => panic!("unexpected result: {:?}", _others),
+    };
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].seller_id, 1015);
+    assert_eq!(errors[0].product_id, 168);
+    assert_eq!(errors[0].err_type.as_str(), "ProductNotExist");
+}
+
+#[tokio::test]
+async fn delete_policy_permission_denied() {
+    let repo = ut_setup_policy_repo(vec![(1015, 22)]).await;
+    let perms = vec![AppAuthPermissionCode::can_create_return_req];
+    let uc = ut_setup_delete_usecase(repo, perms, UTEST_USR_PROF_ID);
+    let req = ut_delete_req(vec![(1015, 22)]);
+    let result = uc.execute(req).await;
+    assert_eq!(result, EditProductPolicyResult::PermissionDeny);
+}
+
+#[tokio::test]
+async fn delete_policy_other_store_denied() {
+    let repo = ut_setup_policy_repo(vec![(1015, 22)]).await;
+    let perms = vec![AppAuthPermissionCode::can_create_product_policy];
+    let uc = ut_setup_delete_usecase(repo, perms, UTEST_USR_PROF_ID + 1);
+    let req = ut_delete_req(vec![(1015, 22)]);
+    let result = uc.execute(req).await;
+    assert_eq!(result, EditProductPolicyResult::PermissionDeny);
+}
@@ -0,0 +1,74 @@
+use std::boxed::Box;
+use std::sync::Arc;
+
+use order::api::web::dto::PromotionDto;
+use order::constant::app_meta;
+use order::datastore::{AbstInMemoryDStore, AppInMemoryDStore};
+use order::repository::{AbsPromotionRepo, PromotionInMemRepo};
+use order::usecase::{EditPromotionUsKsResult, EditPromotionUseCase};
+use order::{AppAuthClaimPermission, AppAuthPermissionCode, AppAuthedClaim, AppInMemoryDbCfg};
+
+use super::verify_store::ut_setup_store_supervisor_rpc;
+use crate::{ut_setup_share_state, MockConfidential};
+
+const UTEST_USR_PROF_ID: u32 = 99675;
+
+async fn ut_setup_promotion_repo() -> Box<dyn AbsPromotionRepo> {
+    let cfg = AppInMemoryDbCfg {
+        alias: "utest".to_string(),
+        max_items: 20,
+    };
+    let dstore: Box<dyn AbstInMemoryDStore> = Box::new(AppInMemoryDStore::new(&cfg));
+    let repo = PromotionInMemRepo::new(Arc::new(dstore)).await.unwrap();
+    Box::new(repo)
+}
+
+fn ut_setup_usecase(
+    repo: Box<dyn AbsPromotionRepo>,
+    store_supervisor_id: u32,
+) -> EditPromotionUseCase {
+    let shr_state = ut_setup_share_state("config_ok_no_sqldb.json", Box::new(MockConfidential {}));
+    let perm = AppAuthClaimPermission {
+        app_code: app_meta::RESOURCE_QUOTA_AP_CODE,
+        codename: AppAuthPermissionCode::can_create_promotion,
+    };
+    let authed_usr = AppAuthedClaim {
+        profile: UTEST_USR_PROF_ID,
+        iat: 0,
+        exp: 0,
+        aud: Vec::new(),
+        quota: vec![],
+        perms: vec![perm],
+    };
+    EditPromotionUseCase {
+        log: shr_state.log_context().clone(),
+        repo,
+        rpc_ctx: ut_setup_store_supervisor_rpc(store_supervisor_id),
+        authed_usr,
+    }
+}
+
+fn ut_setup_data(seller_id: u32) -> Vec<PromotionDto> {
+    let raw = format!(
+        r#"[{{"code":"utest-autumn-sale", "seller_id":{seller_id}, "product_ids":[],
+        "rule":{{"PercentOff":15}}, "start_after":"2023-10-05T18:05:26+08:00",
+        "end_before":"2023-11-30T09:01:50+08:00", "max_usage":100}}]"#
+    );
+    serde_json::from_str(raw.as_str()).unwrap()
+}
+
+#[tokio::test]
+async fn edit_ok() {
+    let repo = ut_setup_promotion_repo().await;
+    let uc = ut_setup_usecase(repo, UTEST_USR_PROF_ID);
+    let result = uc.execute(ut_setup_data(1034)).await;
+    assert_eq!(result, EditPromotionUsKsResult::OK);
+}
+
+#[tokio::test]
+async fn edit_other_store_denied() {
+    let repo = ut_setup_promotion_repo().await;
+    let uc = ut_setup_usecase(repo, UTEST_USR_PROF_ID + 1);
+    let result = uc.execute(ut_setup_data(1034)).await;
+    assert_eq!(result, EditPromotionUsKsResult::PermissionDeny);
+}
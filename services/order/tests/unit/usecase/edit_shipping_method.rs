@@ -0,0 +1,139 @@
+use std::boxed::Box;
+use std::sync::Arc;
+
+use ecommerce_common::api::dto::CountryCode;
+
+use order::api::dto::ShippingMethod;
+use order::api::web::dto::{ShippingMethodDto, ShippingMethodErrorReason, ShippingRateDto};
+use order::constant::app_meta;
+use order::datastore::{AbstInMemoryDStore, AppInMemoryDStore};
+use order::repository::{AbsShippingMethodRepo, ShippingMethodInMemRepo};
+use order::usecase::{
+    EditShippingMethodUsKsResult, EditShippingMethodUseCase, ReadShippingMethodUseCase,
+};
+use order::{AppAuthClaimPermission, AppAuthPermissionCode, AppAuthedClaim, AppInMemoryDbCfg};
+
+use super::verify_store::ut_setup_store_supervisor_rpc;
+use crate::{ut_setup_share_state, MockConfidential};
+
+async fn ut_setup_repo() -> Arc<Box<dyn AbstInMemoryDStore>> {
+    let cfg = AppInMemoryDbCfg {
+        alias: "utest".to_string(),
+        max_items: 20,
+    };
+    let dstore: Box<dyn AbstInMemoryDStore> = Box::new(AppInMemoryDStore::new(&cfg));
+    Arc::new(dstore)
+}
+
+async fn ut_build_repo(
+    dstore: &Arc<Box<dyn AbstInMemoryDStore>>,
+) -> Box<dyn AbsShippingMethodRepo> {
+    let repo = ShippingMethodInMemRepo::new(dstore.clone()).await.unwrap();
+    Box::new(repo)
+}
+
+fn ut_authed_claim(perm: Option<AppAuthPermissionCode>) -> AppAuthedClaim {
+    let perms = perm
+        .map(|codename| AppAuthClaimPermission {
+            app_code: app_meta::RESOURCE_QUOTA_AP_CODE,
+            codename,
+        })
+        .into_iter()
+        .collect();
+    AppAuthedClaim {
+        profile: 1017,
+        iat: 0,
+        exp: 0,
+        aud: Vec::new(),
+        quota: vec![],
+        perms,
+    }
+}
+
+fn ut_setup_data(seller_id: u32, max_weight_gram: u32) -> Vec<ShippingMethodDto> {
+    vec![ShippingMethodDto {
+        seller_id,
+        method: ShippingMethod::FedEx,
+        rates: vec![ShippingRateDto {
+            country: CountryCode::TH,
+            region: None,
+            max_weight_gram,
+            fee: 380,
+        }],
+    }]
+}
+
+#[tokio::test]
+async fn edit_ok() {
+    let shr_state = ut_setup_share_state("config_ok_no_sqldb.json", Box::new(MockConfidential {}));
+    let dstore = ut_setup_repo().await;
+    let uc = EditShippingMethodUseCase {
+        log: shr_state.log_context().clone(),
+        repo: ut_build_repo(&dstore).await,
+        rpc_ctx: ut_setup_store_supervisor_rpc(1017),
+        authed_usr: ut_authed_claim(Some(AppAuthPermissionCode::can_manage_shipping_method)),
+    };
+    let result = uc.execute(ut_setup_data(1017, 2500)).await;
+    assert_eq!(result, EditShippingMethodUsKsResult::OK);
+    let uc = ReadShippingMethodUseCase {
+        repo: ut_build_repo(&dstore).await,
+    };
+    let result = uc.execute(1017).await;
+    assert!(result.is_ok());
+    let v = result.unwrap();
+    assert_eq!(v.len(), 1);
+    assert_eq!(v[0].method, ShippingMethod::FedEx);
+    assert_eq!(v[0].rates[0].max_weight_gram, 2500);
+    assert_eq!(v[0].rates[0].fee, 380);
+}
+
+#[tokio::test]
+async fn edit_permission_denied() {
+    let shr_state = ut_setup_share_state("config_ok_no_sqldb.json", Box::new(MockConfidential {}));
+    let dstore = ut_setup_repo().await;
+    let uc = EditShippingMethodUseCase {
+        log: shr_state.log_context().clone(),
+        repo: ut_build_repo(&dstore).await,
+        rpc_ctx: ut_setup_store_supervisor_rpc(1017),
+        authed_usr: ut_authed_claim(Some(AppAuthPermissionCode::can_create_product_policy)),
+    };
+    let result = uc.execute(ut_setup_data(1017, 2500)).await;
+    assert_eq!(result, EditShippingMethodUsKsResult::PermissionDeny);
+}
+
+#[tokio::test]
+async fn edit_other_store_denied() {
+    let shr_state = ut_setup_share_state("config_ok_no_sqldb.json", Box::new(MockConfidential {}));
+    let dstore = ut_setup_repo().await;
+    let uc = EditShippingMethodUseCase {
+        log: shr_state.log_context().clone(),
+        repo: ut_build_repo(&dstore).await,
+        rpc_ctx: ut_setup_store_supervisor_rpc(1018),
+        authed_usr: ut_authed_claim(Some(AppAuthPermissionCode::can_manage_shipping_method)),
+    };
+    let result = uc.execute(ut_setup_data(1017, 2500)).await;
+    assert_eq!(result, EditShippingMethodUsKsResult::PermissionDeny);
+    let repo = ut_build_repo(&dstore).await;
+    let ms = repo.fetch(vec![1017]).await.unwrap();
+    assert!(ms.methods.is_empty());
+}
+
+#[tokio::test]
+async fn edit_client_error() {
+    let shr_state = ut_setup_share_state("config_ok_no_sqldb.json", Box::new(MockConfidential {}));
+    let dstore = ut_setup_repo().await;
+    let uc = EditShippingMethodUseCase {
+        log: shr_state.log_context().clone(),
+        repo: ut_build_repo(&dstore).await,
+        rpc_ctx: ut_setup_store_supervisor_rpc(1017),
+        authed_usr: ut_authed_claim(Some(AppAuthPermissionCode::can_manage_shipping_method)),
+    };
+    let result = uc.execute(ut_setup_data(1017, 0)).await;
+    if let EditShippingMethodUsKsResult::ClientError(errors) = result {
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].seller_id, 1017);
+        assert_eq!(errors[0].reason, ShippingMethodErrorReason::InvalidWeight);
+    } else {
+        panic!("unexpected result");
+    }
+}
@@ -11,46 +11,57 @@ use ecommerce_common::api::rpc::dto::OrderReplicaRefundReqDto;
 use ecommerce_common::error::AppErrorCode;
 use ecommerce_common::logging::AppLogContext;
 
-use order::api::dto::ProdAttrValueDto;
+use order::api::dto::{ProdAttrValueDto, ShippingMethod};
 use order::api::rpc::dto::{
     OrderReplicaInventoryDto, OrderReplicaInventoryReqDto, StockReturnErrorDto,
 };
-use order::api::web::dto::{OlineProductAttrDto, OrderLineReturnReqDto, OrderLineRsvReqDto};
+use order::api::web::dto::{
+    OlineProductAttrDto, OrderInfoReadReqFlag, OrderLineCancelErrorReason, OrderLineCancelReqDto,
+    OrderLineReturnReqDto, OrderLineRsvReqDto, OrderListReqQuery,
+};
 use order::constant::app_meta;
 use order::error::AppError;
 use order::model::{
     CurrencyModel, CurrencyModelSet, OrderCurrencyModel, OrderLineAppliedPolicyModel,
     OrderLineIdentity, OrderLineModel, OrderLineModelSet, OrderLinePriceModel,
-    OrderLineQuantityModel, OrderReturnModel, ProdAttriPriceModel, ProductPolicyModel,
-    ProductPolicyModelSet, ProductPriceModel, ProductPriceModelSet,
+    OrderLineQuantityModel, OrderReturnModel, ProdAttriPriceModel, ProductBackorderPolicy,
+    ProductPolicyModel, ProductPolicyModelSet, ProductPriceModel, ProductPriceModelSet,
+    ShipmentLineModel, ShipmentModel, ShipmentStatus,
 };
-use order::repository::{AbsOrderRepo, AbsOrderReturnRepo};
+use order::repository::{AbsOrderRepo, AbsOrderReturnRepo, AbsShipmentRepo};
 use order::usecase::{
-    CreateOrderUsKsErr, CreateOrderUseCase, OrderDiscardUnpaidItemsUseCase,
-    OrderReplicaInventoryUseCase, OrderReplicaRefundUseCase, ReturnLinesReqUcOutput,
-    ReturnLinesReqUseCase,
+    CancelLinesReqUcOutput, CancelLinesReqUseCase, CreateOrderUsKsErr, CreateOrderUseCase,
+    ListOwnedOrdersUcOutput, ListOwnedOrdersUseCase, OrderDiscardUnpaidItemsUseCase,
+    OrderReplicaInventoryUseCase, OrderReplicaRefundUseCase, ReadOrderInfoUcOutput,
+    ReadOrderInfoUseCase, ReturnLinesReqUcOutput, ReturnLinesReqUseCase,
 };
 use order::{AppAuthClaimPermission, AppAuthPermissionCode, AppAuthedClaim};
 
-use super::{MockCurrencyRepo, MockOrderRepo, MockOrderReturnRepo};
+use super::{MockCurrencyRepo, MockOrderRepo, MockOrderReturnRepo, MockShipmentRepo};
 use crate::{ut_setup_share_state, MockConfidential};
 
 fn ut_setup_prod_policies() -> ProductPolicyModelSet {
     let policies = [
         #[cfg_attr(rustfmt, rustfmt_skip)]
-        (1168u64, 0u16, 127u32, 1008u32, false, 0u16),
-        (168, 0, 20000, 1250, false, 0),
-        (174, 0, 30000, 2255, false, 0),
-        (169, 1, 21000, 150, false, 5),
+        (51u32, 1168u64, 0u16, 127u32, 1008u32, false, 0u16),
+        (51, 168, 0, 20000, 1250, false, 0),
+        (52, 168, 0, 19000, 1340, false, 0),
+        (52, 174, 0, 30000, 2255, false, 0),
+        (51, 169, 1, 21000, 150, false, 5),
     ]
     .into_iter()
     .map(|d| ProductPolicyModel {
-        product_id: d.0,
-        min_num_rsv: d.1,
-        warranty_hours: d.2,
-        auto_cancel_secs: d.3,
-        is_create: d.4,
-        max_num_rsv: d.5,
+        store_id: d.0,
+        product_id: d.1,
+        version: 0,
+        min_num_rsv: d.2,
+        warranty_hours: d.3,
+        auto_cancel_secs: d.4,
+        is_create: d.5,
+        max_num_rsv: d.6,
+        backorder: ProductBackorderPolicy::Disabled,
+        max_num_backorder: 0,
+        weight_gram: 0,
     })
     .collect::<Vec<_>>();
     ProductPolicyModelSet { policies }
@@ -106,7 +117,7 @@ fn ut_setup_currency_mset(data : Vec<(CurrencyDto, i64, u32)>) -> CurrencyModelS
             rate: Decimal::new(d.1, d.2)
         })
         .collect::<Vec<_>>();
-    CurrencyModelSet { base, exchange_rates }
+    CurrencyModelSet { base, exchange_rates, provider: None }
 }
 
 fn ut_setup_order_currency(seller_ids: Vec<u32>) -> OrderCurrencyModel {
@@ -154,7 +165,7 @@ fn validate_orderline_ok() {
         }
     })
     .collect::<Vec<_>>();
-    let result = CreateOrderUseCase::validate_orderline(ms_policy, ms_price, data);
+    let result = CreateOrderUseCase::validate_orderline(ms_policy, ms_price, &[], data);
     assert!(result.is_ok());
     if let Ok(v) = result {
         assert_eq!(v.len(), 3);
@@ -202,7 +213,7 @@ fn validate_orderline_client_errors() {
         }
     })
     .collect::<Vec<_>>();
-    let result = CreateOrderUseCase::validate_orderline(ms_policy, ms_price, data);
+    let result = CreateOrderUseCase::validate_orderline(ms_policy, ms_price, &[], data);
     assert!(result.is_err());
     if let Err(CreateOrderUsKsErr::ReqContent(v)) = result {
         let errs = v.order_lines.unwrap();
@@ -325,8 +336,8 @@ fn ut_setup_orderlines() -> Vec<OrderLineModel> {
     .map(|d| {
         let id_ = OrderLineIdentity::from((d.0, d.1, 0));
         let price = OrderLinePriceModel::from((d.2, d.3));
-        let qty = OrderLineQuantityModel {reserved: d.4, paid: d.5, paid_last_update};
-        let policy = OrderLineAppliedPolicyModel {reserved_until, warranty_until};
+        let qty = OrderLineQuantityModel {reserved: d.4, paid: d.5, paid_last_update, backordered: 0};
+        let policy = OrderLineAppliedPolicyModel {reserved_until, warranty_until, version: 0};
         let attrs_charge = ProdAttriPriceModel::from((attr_lastupdate, None));
         OrderLineModel::from((id_, price, policy, qty, attrs_charge))
     })
@@ -486,6 +497,30 @@ fn ut_oreturn_setup_repository_2(
     Box::new(repo)
 }
 
+// all the paid items in the given order lines have been delivered
+fn ut_oreturn_setup_delivered(o_lines: &[OrderLineModel]) -> Box<dyn AbsShipmentRepo> {
+    let time_now = Local::now().fixed_offset();
+    let shipments = o_lines
+        .iter()
+        .enumerate()
+        .map(|(seq, ol)| ShipmentModel {
+            seq: seq as u16,
+            seller_id: ol.id().store_id(),
+            carrier: ShippingMethod::FedEx,
+            tracking_number: Some(format!("FX00391{seq}")),
+            status: ShipmentStatus::Delivered,
+            packed_time: time_now - Duration::days(2),
+            shipped_time: Some(time_now - Duration::days(1)),
+            delivered_time: Some(time_now),
+            lines: vec![ShipmentLineModel {
+                id_: ol.id().clone(),
+                qty: ol.qty.paid,
+            }],
+        })
+        .collect::<Vec<_>>();
+    Box::new(MockShipmentRepo::build(shipments))
+}
+
 async fn return_lines_request_common(
     fetched_olines: Vec<OrderLineModel>,
     fetched_returns: DefaultResult<Vec<OrderReturnModel>, AppError>,
@@ -500,6 +535,7 @@ async fn return_lines_request_common(
         .map(|v| v.id().store_id())
         .collect::<Vec<_>>();
     let currency_rate = ut_setup_order_currency(mocked_seller_ids);
+    let sh_repo = ut_oreturn_setup_delivered(&fetched_olines);
     let o_repo = ut_oreturn_setup_repository_1(
         fetched_olines,
         vec![],
@@ -531,6 +567,7 @@ async fn return_lines_request_common(
         authed_claim,
         o_repo,
         or_repo,
+        sh_repo,
     };
     uc.execute(mock_order_id, mock_return_req).await
 } // end of fnf return_lines_request_common
@@ -782,3 +819,332 @@ async fn replica_refund_ok() {
         assert_eq!(diff_cnt, 0);
     }
 } // end of fn replica_refund_ok
+
+fn ut_setup_buyer_claim(usr_id: u32) -> AppAuthedClaim {
+    AppAuthedClaim {
+        profile: usr_id,
+        iat: 0,
+        exp: 0,
+        aud: Vec::new(),
+        quota: vec![],
+        perms: vec![],
+    }
+}
+
+#[tokio::test]
+async fn list_owned_orders_ok() {
+    let owner_usr_id = 1710u32;
+    let order_ctime = DateTime::parse_from_rfc3339("2023-11-18T02:39:04+02:00").unwrap();
+    let mock_oids = ["0a1b", "2c3d", "4e5f", "6a7b", "8c9d"]
+        .into_iter()
+        .map(String::from)
+        .collect::<Vec<_>>();
+    let repo =
+        ut_oreturn_setup_repository_1(vec![], mock_oids, owner_usr_id, Some(order_ctime), None);
+    let uc = ListOwnedOrdersUseCase {
+        authed_claim: ut_setup_buyer_claim(owner_usr_id),
+        repo,
+    };
+    let req = OrderListReqQuery {
+        start: Some(order_ctime - Duration::days(1)),
+        end: Some(order_ctime + Duration::days(1)),
+        page: Some(3),
+        size: Some(2),
+    };
+    let result = uc.execute(req).await;
+    assert!(result.is_ok());
+    if let Ok(ListOwnedOrdersUcOutput::Success(resp)) = result {
+        assert_eq!(resp.total, 5);
+        assert_eq!(resp.page, 3);
+        assert_eq!(resp.orders.len(), 1);
+        let actual_time = DateTime::parse_from_rfc3339(resp.orders[0].time.as_str()).unwrap();
+        assert_eq!(actual_time, order_ctime);
+    } else {
+        assert!(false);
+    }
+} // end of fn list_owned_orders_ok
+
+#[tokio::test]
+async fn list_owned_orders_other_buyer() {
+    let order_ctime = DateTime::parse_from_rfc3339("2023-11-18T02:39:04+02:00").unwrap();
+    let mock_oids = vec!["0a1b".to_string(), "2c3d".to_string()];
+    let repo = ut_oreturn_setup_repository_1(vec![], mock_oids, 1710, Some(order_ctime), None);
+    let uc = ListOwnedOrdersUseCase {
+        authed_claim: ut_setup_buyer_claim(1711),
+        repo,
+    };
+    let req = OrderListReqQuery {
+        start: Some(order_ctime - Duration::hours(3)),
+        end: Some(order_ctime + Duration::hours(3)),
+        page: None,
+        size: None,
+    };
+    let result = uc.execute(req).await;
+    assert!(result.is_ok());
+    if let Ok(ListOwnedOrdersUcOutput::Success(resp)) = result {
+        assert_eq!(resp.total, 0);
+        assert!(resp.orders.is_empty());
+    } else {
+        assert!(false);
+    }
+}
+
+#[tokio::test]
+async fn list_owned_orders_invalid_req() {
+    let time_now = Local::now().fixed_offset();
+    let data = [
+        (
+            Some(time_now),
+            Some(time_now - Duration::seconds(1)),
+            None,
+            true,
+        ),
+        (
+            Some(time_now - Duration::days(400)),
+            Some(time_now),
+            None,
+            true,
+        ),
+        (None, None, Some(0u16), false),
+    ];
+    for (start, end, page, expect_time_err) in data {
+        let repo = ut_oreturn_setup_repository_1(vec![], vec![], 1710, None, None);
+        let uc = ListOwnedOrdersUseCase {
+            authed_claim: ut_setup_buyer_claim(1710),
+            repo,
+        };
+        let req = OrderListReqQuery {
+            start,
+            end,
+            page,
+            size: None,
+        };
+        let result = uc.execute(req).await;
+        assert!(result.is_ok());
+        let output = result.unwrap();
+        if expect_time_err {
+            assert!(matches!(output, ListOwnedOrdersUcOutput::InvalidTimeRange));
+        } else {
+            assert!(matches!(output, ListOwnedOrdersUcOutput::InvalidPagination));
+        }
+    }
+}
+
+async fn read_order_info_common(
+    req_usr_id: u32,
+    owner_usr_id: u32,
+    flag: OrderInfoReadReqFlag,
+) -> DefaultResult<ReadOrderInfoUcOutput, AppError> {
+    let order_ctime = DateTime::parse_from_rfc3339("2023-11-18T02:39:04+02:00").unwrap();
+    let fetched_olines = ut_setup_orderlines();
+    let mocked_seller_ids = fetched_olines
+        .iter()
+        .map(|v| v.id().store_id())
+        .collect::<Vec<_>>();
+    let currency_rate = ut_setup_order_currency(mocked_seller_ids);
+    let repo = ut_oreturn_setup_repository_1(
+        fetched_olines,
+        vec![],
+        owner_usr_id,
+        Some(order_ctime),
+        Some(currency_rate),
+    );
+    let uc = ReadOrderInfoUseCase {
+        authed_claim: ut_setup_buyer_claim(req_usr_id),
+        repo,
+    };
+    uc.execute("SomebodyOrderedThis".to_string(), flag).await
+}
+
+#[tokio::test]
+async fn read_order_info_ok() {
+    let flag = OrderInfoReadReqFlag {
+        lines: Some(true),
+        billing: Some(false),
+        shipping: None,
+    };
+    let result = read_order_info_common(1710, 1710, flag).await;
+    assert!(result.is_ok());
+    if let Ok(ReadOrderInfoUcOutput::Success(resp)) = result {
+        assert_eq!(resp.usr_id, 1710);
+        assert!(resp.billing.is_none());
+        let shipping = resp.shipping.as_ref().unwrap();
+        assert_eq!(shipping.contact.first_name.as_str(), "Llama");
+        let lines = resp.lines.as_ref().unwrap();
+        assert_eq!(lines.len(), 3);
+        let line = lines.iter().find(|l| l.product_id == 191).unwrap();
+        assert_eq!(line.seller_id, 800);
+        assert_eq!(line.qty.reserved, 15);
+        assert_eq!(line.qty.paid, 15);
+        assert!(line.qty.paid_last_update.is_some());
+        assert_eq!(line.amount.unit.as_str(), "12.00");
+        assert_eq!(line.amount.total.as_str(), "180.00");
+    } else {
+        assert!(false);
+    }
+}
+
+#[tokio::test]
+async fn read_order_info_skip_lines() {
+    let flag = OrderInfoReadReqFlag {
+        lines: Some(false),
+        billing: Some(false),
+        shipping: Some(false),
+    };
+    let result = read_order_info_common(1710, 1710, flag).await;
+    assert!(result.is_ok());
+    if let Ok(ReadOrderInfoUcOutput::Success(resp)) = result {
+        assert!(resp.lines.is_none());
+        assert!(resp.shipping.is_none());
+        assert!(matches!(resp.currency.buyer, CurrencyDto::TWD));
+    } else {
+        assert!(false);
+    }
+}
+
+#[tokio::test]
+async fn read_order_info_invalid_owner() {
+    let flag = OrderInfoReadReqFlag {
+        lines: None,
+        billing: None,
+        shipping: None,
+    };
+    let result = read_order_info_common(1711, 1710, flag).await;
+    assert!(result.is_ok());
+    assert!(matches!(
+        result.unwrap(),
+        ReadOrderInfoUcOutput::InvalidOwner
+    ));
+}
+
+#[rustfmt::skip]
+fn ut_setup_unpaid_orderlines() -> Vec<OrderLineModel> {
+    let base_time = Local::now().fixed_offset();
+    let attr_lastupdate = base_time - Duration::minutes(6);
+    let warranty_until = base_time + Duration::days(14);
+    [
+        (108u32, 190u64, 10u32, 140u32, 14u32, 0u32, base_time + Duration::minutes(5)),
+        (800, 191, 12, 180, 15, 0, base_time + Duration::minutes(5)),
+        (426, 192, 12, 216, 18, 3, base_time + Duration::minutes(5)),
+        (426, 193, 13, 26, 2, 0, base_time - Duration::seconds(1)),
+    ]
+    .into_iter()
+    .map(|d| {
+        let paid_last_update = if d.5 > 0 { Some(base_time) } else { None };
+        let id_ = OrderLineIdentity::from((d.0, d.1, 0));
+        let price = OrderLinePriceModel::from((d.2, d.3));
+        let qty = OrderLineQuantityModel {reserved: d.4, paid: d.5, paid_last_update, backordered: 0};
+        let policy = OrderLineAppliedPolicyModel {reserved_until: d.6, warranty_until, version: 0};
+        let attrs_charge = ProdAttriPriceModel::from((attr_lastupdate, None));
+        OrderLineModel::from((id_, price, policy, qty, attrs_charge))
+    })
+    .collect::<Vec<_>>()
+}
+
+async fn cancel_lines_common(
+    owner_usr_id: u32,
+    authed_usr_id: u32,
+    stock_return_results: Vec<DefaultResult<Vec<StockReturnErrorDto>, AppError>>,
+    req: Vec<(u32, u64, u16)>,
+) -> DefaultResult<CancelLinesReqUcOutput, AppError> {
+    let shr_state = ut_setup_share_state("config_ok_no_sqldb.json", Box::new(MockConfidential {}));
+    let not_impl_err = AppError {
+        detail: None,
+        code: AppErrorCode::NotImplemented,
+    };
+    let repo = MockOrderRepo::build(
+        Err(not_impl_err.clone()),
+        Err(not_impl_err.clone()),
+        stock_return_results,
+        vec![],
+        ut_setup_unpaid_orderlines(),
+        vec![],
+        Some(owner_usr_id),
+        None,
+        None,
+    );
+    let uc = CancelLinesReqUseCase {
+        authed_claim: ut_setup_buyer_claim(authed_usr_id),
+        repo: Box::new(repo),
+        logctx: shr_state.log_context().clone(),
+    };
+    let req = req
+        .into_iter()
+        .map(|d| OrderLineCancelReqDto {
+            seller_id: d.0,
+            product_id: d.1,
+            attr_set_seq: d.2,
+        })
+        .collect::<Vec<_>>();
+    uc.execute("0a1b2c3d".to_string(), req).await
+}
+
+#[tokio::test]
+async fn cancel_lines_ok() {
+    let req = vec![(108, 190, 0), (800, 191, 0)];
+    let result = cancel_lines_common(1710, 1710, vec![Ok(vec![])], req).await;
+    assert!(result.is_ok());
+    assert!(matches!(result.unwrap(), CancelLinesReqUcOutput::Success));
+}
+
+#[tokio::test]
+async fn cancel_lines_stock_return_failure() {
+    // the cancellation is discarded when the stock cannot be returned
+    let req = vec![(800, 191, 0)];
+    let e = AppError {
+        code: AppErrorCode::DatabaseServerBusy,
+        detail: None,
+    };
+    let result = cancel_lines_common(1710, 1710, vec![Err(e)], req).await;
+    assert!(matches!(
+        result,
+        Err(AppError {
+            code: AppErrorCode::DatabaseServerBusy,
+            ..
+        })
+    ));
+}
+
+#[tokio::test]
+async fn cancel_lines_invalid_request() {
+    let req = vec![(108, 190, 0), (426, 192, 0), (426, 193, 0), (426, 194, 0)];
+    let result = cancel_lines_common(1710, 1710, vec![], req).await;
+    assert!(result.is_ok());
+    if let Ok(CancelLinesReqUcOutput::InvalidRequest(errors)) = result {
+        assert_eq!(errors.len(), 3);
+        let expect = [
+            (192u64, OrderLineCancelErrorReason::AlreadyPaid),
+            (193, OrderLineCancelErrorReason::ReservationExpired),
+            (194, OrderLineCancelErrorReason::NotExist),
+        ];
+        expect
+            .into_iter()
+            .map(|(prod_id, reason)| {
+                let found = errors.iter().find(|e| e.product_id == prod_id).unwrap();
+                assert_eq!(found.seller_id, 426);
+                assert_eq!(
+                    std::mem::discriminant(&found.reason),
+                    std::mem::discriminant(&reason)
+                );
+            })
+            .count();
+    } else {
+        assert!(false);
+    }
+    let result = cancel_lines_common(1710, 1710, vec![], vec![]).await;
+    assert!(matches!(
+        result,
+        Ok(CancelLinesReqUcOutput::InvalidRequest(es)) if es.is_empty()
+    ));
+}
+
+#[tokio::test]
+async fn cancel_lines_invalid_owner() {
+    let req = vec![(108, 190, 0)];
+    let result = cancel_lines_common(1711, 1710, vec![], req).await;
+    assert!(result.is_ok());
+    assert!(matches!(
+        result.unwrap(),
+        CancelLinesReqUcOutput::InvalidOwner
+    ));
+}
@@ -0,0 +1,311 @@
+use std::boxed::Box;
+
+use chrono::{Duration, Local};
+
+use ecommerce_common::error::AppErrorCode;
+
+use order::api::dto::ShippingMethod;
+use order::api::web::dto::{
+    ShipmentCreateReqDto, ShipmentErrorReason, ShipmentLineErrorReason, ShipmentLineReqDto,
+    ShipmentStatusDto, ShipmentUpdateReqDto,
+};
+use order::constant::app_meta;
+use order::error::AppError;
+use order::model::{
+    OrderLineAppliedPolicyModel, OrderLineIdentity, OrderLineModel, OrderLinePriceModel,
+    OrderLineQuantityModel, ProdAttriPriceModel, ShipmentLineModel, ShipmentModel, ShipmentStatus,
+};
+use order::repository::AbsOrderRepo;
+use order::usecase::{
+    CreateShipmentUseCase, ReadShipmentsUcOutput, ReadShipmentsUseCase, ShipmentUcOutput,
+    UpdateShipmentUseCase,
+};
+use order::{AppAuthClaimPermission, AppAuthPermissionCode, AppAuthedClaim};
+
+use super::verify_store::ut_setup_store_supervisor_rpc;
+use super::{MockOrderRepo, MockShipmentRepo};
+use crate::{ut_setup_share_state, MockConfidential};
+
+fn ut_authed_claim(usr_id: u32, perm: Option<AppAuthPermissionCode>) -> AppAuthedClaim {
+    let perms = perm
+        .map(|codename| AppAuthClaimPermission {
+            app_code: app_meta::RESOURCE_QUOTA_AP_CODE,
+            codename,
+        })
+        .into_iter()
+        .collect();
+    AppAuthedClaim {
+        profile: usr_id,
+        iat: 0,
+        exp: 0,
+        aud: Vec::new(),
+        quota: vec![],
+        perms,
+    }
+}
+
+#[rustfmt::skip]
+fn ut_setup_orderlines(seller_id: u32) -> Vec<OrderLineModel> {
+    let time_now = Local::now().fixed_offset();
+    let reserved_until = time_now + Duration::minutes(5);
+    let warranty_until = time_now + Duration::days(14);
+    [(3801u64, 6u32, 5u32), (3802, 4, 4), (3803, 2, 0)]
+        .into_iter()
+        .map(|(product_id, reserved, paid)| {
+            let id_ = OrderLineIdentity::from((seller_id, product_id, 0));
+            let price = OrderLinePriceModel::from((10, 10 * reserved));
+            let qty = OrderLineQuantityModel {
+                reserved, paid, paid_last_update: Some(time_now), backordered: 0,
+            };
+            let policy = OrderLineAppliedPolicyModel {reserved_until, warranty_until, version: 0};
+            let attrs_charge = ProdAttriPriceModel::from((time_now, None));
+            OrderLineModel::from((id_, price, policy, qty, attrs_charge))
+        })
+        .collect()
+}
+
+fn ut_setup_shipment(seq: u16, seller_id: u32, lines: Vec<(u64, u32)>) -> ShipmentModel {
+    let time_now = Local::now().fixed_offset();
+    let lines = lines
+        .into_iter()
+        .map(|(product_id, qty)| ShipmentLineModel {
+            id_: OrderLineIdentity::from((seller_id, product_id, 0)),
+            qty,
+        })
+        .collect();
+    ShipmentModel {
+        seq,
+        seller_id,
+        carrier: ShippingMethod::UPS,
+        tracking_number: None,
+        status: ShipmentStatus::Packed,
+        packed_time: time_now - Duration::hours(3),
+        shipped_time: None,
+        delivered_time: None,
+        lines,
+    }
+}
+
+fn ut_setup_order_repo(olines: Vec<OrderLineModel>, owner_id: u32) -> Box<dyn AbsOrderRepo> {
+    let not_impl_err = AppError {
+        detail: None,
+        code: AppErrorCode::NotImplemented,
+    };
+    let repo = MockOrderRepo::build(
+        Err(not_impl_err.clone()),
+        Err(not_impl_err),
+        vec![],
+        vec![],
+        olines,
+        vec![],
+        Some(owner_id),
+        None,
+        None,
+    );
+    Box::new(repo)
+}
+
+fn ut_create_req(seller_id: u32, lines: Vec<(u64, u32)>) -> ShipmentCreateReqDto {
+    let lines = lines
+        .into_iter()
+        .map(|(product_id, quantity)| ShipmentLineReqDto {
+            product_id,
+            attr_set_seq: 0,
+            quantity,
+        })
+        .collect();
+    ShipmentCreateReqDto {
+        seller_id,
+        carrier: ShippingMethod::FedEx,
+        tracking_number: None,
+        lines,
+    }
+}
+
+#[tokio::test]
+async fn create_ok() {
+    let shr_state = ut_setup_share_state("config_ok_no_sqldb.json", Box::new(MockConfidential {}));
+    let seller_id = 126u32;
+    let saved = vec![ut_setup_shipment(0, seller_id, vec![(3801, 2)])];
+    let uc = CreateShipmentUseCase {
+        authed_claim: ut_authed_claim(1009, Some(AppAuthPermissionCode::can_manage_shipment)),
+        o_repo: ut_setup_order_repo(ut_setup_orderlines(seller_id), 2345),
+        sh_repo: Box::new(MockShipmentRepo::build(saved)),
+        rpc_ctx: ut_setup_store_supervisor_rpc(1009),
+        logctx: shr_state.log_context().clone(),
+    };
+    let req = ut_create_req(seller_id, vec![(3801, 3), (3802, 4)]);
+    let result = uc.execute("ab0123cd".to_string(), req).await;
+    assert!(result.is_ok());
+    if let Ok(ShipmentUcOutput::Success(v)) = result {
+        assert_eq!(v.seq, 1);
+        assert_eq!(v.lines.len(), 2);
+        assert_eq!(v.status, ShipmentStatusDto::Packed);
+        assert!(v.shipped_time.is_none());
+    } else {
+        panic!("unexpected output");
+    }
+}
+
+#[tokio::test]
+async fn create_err_qty_exceed() {
+    let shr_state = ut_setup_share_state("config_ok_no_sqldb.json", Box::new(MockConfidential {}));
+    let seller_id = 126u32;
+    let saved = vec![ut_setup_shipment(0, seller_id, vec![(3801, 2)])];
+    let uc = CreateShipmentUseCase {
+        authed_claim: ut_authed_claim(1009, Some(AppAuthPermissionCode::can_manage_shipment)),
+        o_repo: ut_setup_order_repo(ut_setup_orderlines(seller_id), 2345),
+        sh_repo: Box::new(MockShipmentRepo::build(saved)),
+        rpc_ctx: ut_setup_store_supervisor_rpc(1009),
+        logctx: shr_state.log_context().clone(),
+    };
+    // the last line has not been paid yet
+    let req = ut_create_req(seller_id, vec![(3801, 4), (3802, 4), (3803, 1), (3804, 1)]);
+    let result = uc.execute("ab0123cd".to_string(), req).await;
+    assert!(result.is_ok());
+    if let Ok(ShipmentUcOutput::InvalidRequest(e)) = result {
+        assert!(matches!(e.reason, ShipmentErrorReason::InvalidLines));
+        let lines = e.lines.unwrap();
+        assert_eq!(lines.len(), 3);
+        lines
+            .iter()
+            .map(|l| match l.product_id {
+                3801 | 3803 => assert!(matches!(l.reason, ShipmentLineErrorReason::QtyLimitExceed)),
+                3804 => assert!(matches!(l.reason, ShipmentLineErrorReason::NotExist)),
+                _others => panic!("unexpected line"),
+            })
+            .count();
+    } else {
+        panic!("unexpected output");
+    }
+}
+
+#[tokio::test]
+async fn create_err_permission() {
+    let shr_state = ut_setup_share_state("config_ok_no_sqldb.json", Box::new(MockConfidential {}));
+    let seller_id = 126u32;
+    let uc = CreateShipmentUseCase {
+        authed_claim: ut_authed_claim(1009, None),
+        o_repo: ut_setup_order_repo(ut_setup_orderlines(seller_id), 2345),
+        sh_repo: Box::new(MockShipmentRepo::build(vec![])),
+        rpc_ctx: ut_setup_store_supervisor_rpc(1009),
+        logctx: shr_state.log_context().clone(),
+    };
+    let req = ut_create_req(seller_id, vec![(3801, 1)]);
+    let result = uc.execute("ab0123cd".to_string(), req).await;
+    assert!(matches!(result, Ok(ShipmentUcOutput::PermissionDeny)));
+}
+
+#[tokio::test]
+async fn create_err_other_store() {
+    let shr_state = ut_setup_share_state("config_ok_no_sqldb.json", Box::new(MockConfidential {}));
+    let seller_id = 126u32;
+    let uc = CreateShipmentUseCase {
+        authed_claim: ut_authed_claim(1009, Some(AppAuthPermissionCode::can_manage_shipment)),
+        o_repo: ut_setup_order_repo(ut_setup_orderlines(seller_id), 2345),
+        sh_repo: Box::new(MockShipmentRepo::build(vec![])),
+        rpc_ctx: ut_setup_store_supervisor_rpc(1010),
+        logctx: shr_state.log_context().clone(),
+    };
+    let req = ut_create_req(seller_id, vec![(3801, 1)]);
+    let result = uc.execute("ab0123cd".to_string(), req).await;
+    assert!(matches!(result, Ok(ShipmentUcOutput::PermissionDeny)));
+}
+
+#[tokio::test]
+async fn update_ok() {
+    let shr_state = ut_setup_share_state("config_ok_no_sqldb.json", Box::new(MockConfidential {}));
+    let seller_id = 126u32;
+    let saved = vec![
+        ut_setup_shipment(0, seller_id, vec![(3801, 2)]),
+        ut_setup_shipment(1, seller_id, vec![(3802, 4)]),
+    ];
+    let uc = UpdateShipmentUseCase {
+        authed_claim: ut_authed_claim(1009, Some(AppAuthPermissionCode::can_manage_shipment)),
+        sh_repo: Box::new(MockShipmentRepo::build(saved)),
+        rpc_ctx: ut_setup_store_supervisor_rpc(1009),
+        logctx: shr_state.log_context().clone(),
+    };
+    let req = ShipmentUpdateReqDto {
+        seller_id,
+        status: ShipmentStatusDto::Delivered,
+        tracking_number: Some("1Z-9903A".to_string()),
+    };
+    let result = uc.execute("ab0123cd".to_string(), 1, req).await;
+    assert!(result.is_ok());
+    if let Ok(ShipmentUcOutput::Success(v)) = result {
+        assert_eq!(v.seq, 1);
+        assert_eq!(v.status, ShipmentStatusDto::Delivered);
+        assert_eq!(v.tracking_number.as_deref(), Some("1Z-9903A"));
+        assert!(v.shipped_time.is_some());
+        assert!(v.delivered_time.is_some());
+    } else {
+        panic!("unexpected output");
+    }
+}
+
+#[tokio::test]
+async fn update_err_other_seller() {
+    let shr_state = ut_setup_share_state("config_ok_no_sqldb.json", Box::new(MockConfidential {}));
+    let saved = vec![ut_setup_shipment(0, 126, vec![(3801, 2)])];
+    let uc = UpdateShipmentUseCase {
+        authed_claim: ut_authed_claim(1009, Some(AppAuthPermissionCode::can_manage_shipment)),
+        sh_repo: Box::new(MockShipmentRepo::build(saved)),
+        rpc_ctx: ut_setup_store_supervisor_rpc(1009),
+        logctx: shr_state.log_context().clone(),
+    };
+    let req = ShipmentUpdateReqDto {
+        seller_id: 127,
+        status: ShipmentStatusDto::Shipped,
+        tracking_number: Some("1Z-9903A".to_string()),
+    };
+    let result = uc.execute("ab0123cd".to_string(), 0, req).await;
+    assert!(matches!(result, Ok(ShipmentUcOutput::NotFound)));
+}
+
+#[tokio::test]
+async fn update_err_other_store() {
+    let shr_state = ut_setup_share_state("config_ok_no_sqldb.json", Box::new(MockConfidential {}));
+    let saved = vec![ut_setup_shipment(0, 126, vec![(3801, 2)])];
+    let uc = UpdateShipmentUseCase {
+        authed_claim: ut_authed_claim(1009, Some(AppAuthPermissionCode::can_manage_shipment)),
+        sh_repo: Box::new(MockShipmentRepo::build(saved)),
+        rpc_ctx: ut_setup_store_supervisor_rpc(1010),
+        logctx: shr_state.log_context().clone(),
+    };
+    let req = ShipmentUpdateReqDto {
+        seller_id: 126,
+        status: ShipmentStatusDto::Shipped,
+        tracking_number: Some("1Z-9903A".to_string()),
+    };
+    let result = uc.execute("ab0123cd".to_string(), 0, req).await;
+    assert!(matches!(result, Ok(ShipmentUcOutput::PermissionDeny)));
+}
+
+#[tokio::test]
+async fn read_by_owner() {
+    let seller_id = 126u32;
+    let saved = vec![
+        ut_setup_shipment(0, seller_id, vec![(3801, 2)]),
+        ut_setup_shipment(1, seller_id, vec![(3802, 4)]),
+    ];
+    let uc = ReadShipmentsUseCase {
+        authed_claim: ut_authed_claim(2345, None),
+        o_repo: ut_setup_order_repo(vec![], 2345),
+        sh_repo: Box::new(MockShipmentRepo::build(saved)),
+    };
+    let result = uc.execute("ab0123cd".to_string()).await;
+    if let Ok(ReadShipmentsUcOutput::Success(v)) = result {
+        assert_eq!(v.len(), 2);
+        assert_eq!(v[1].lines[0].product_id, 3802);
+    } else {
+        panic!("unexpected output");
+    }
+    let uc = ReadShipmentsUseCase {
+        authed_claim: ut_authed_claim(2346, None),
+        o_repo: ut_setup_order_repo(vec![], 2345),
+        sh_repo: Box::new(MockShipmentRepo::build(vec![])),
+    };
+    let result = uc.execute("ab0123cd".to_string()).await;
+    assert!(matches!(result, Ok(ReadShipmentsUcOutput::InvalidOwner)));
+}
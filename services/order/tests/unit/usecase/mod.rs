@@ -1,7 +1,12 @@
 mod edit_product_policy;
 mod edit_product_price;
+mod edit_promotion;
+mod edit_shipping_method;
 mod manage_order;
+mod manage_shipment;
+mod stock_alert;
 mod stock_level;
+mod verify_store;
 
 use std::boxed::Box;
 use std::cell::{Cell, RefCell};
@@ -22,15 +27,19 @@ use ecommerce_common::model::order::{BillingModel, ContactModel};
 
 use order::api::dto::ShippingMethod;
 use order::api::rpc::dto::{StockLevelReturnDto, StockReturnErrorDto};
+use order::api::web::dto::OrderLineCancelErrorDto;
 use order::error::AppError;
 use order::model::{
-    CurrencyModelSet, OrderCurrencyModel, OrderLineIdentity, OrderLineModel, OrderLineModelSet,
-    OrderReturnModel, ProductStockIdentity, ShippingModel, ShippingOptionModel, StockLevelModelSet,
+    CurrencyModelSet, CurrencyRateHistoryModel, OrderCurrencyModel, OrderLineCancelModel,
+    OrderLineIdentity, OrderLineModel, OrderLineModelSet, OrderReturnModel, ProductStockIdentity,
+    ProductStockIdentity2, ShipmentModel, ShippingModel, ShippingOptionModel,
+    StockAllocPolicyModel, StockAllocPolicyModelSet, StockLevelModelSet, StockWarehouseModel,
+    StockWarehouseModelSet,
 };
 use order::repository::{
-    AbsCurrencyRepo, AbsOrderRepo, AbsOrderReturnRepo, AbsOrderStockRepo,
-    AppOrderFetchRangeCallback, AppOrderRepoUpdateLinesUserFunc, AppStockRepoReserveReturn,
-    AppStockRepoReserveUserFunc, AppStockRepoReturnUserFunc,
+    AbsCurrencyRepo, AbsOrderRepo, AbsOrderReturnRepo, AbsOrderStockRepo, AbsShipmentRepo,
+    AppOrderFetchRangeCallback, AppOrderRepoCancelLinesUserFunc, AppOrderRepoUpdateLinesUserFunc,
+    AppStockRepoReserveReturn, AppStockRepoReserveUserFunc, AppStockRepoReturnUserFunc,
 };
 use order::usecase::initiate_rpc_request;
 use order::{
@@ -48,13 +57,14 @@ struct MockStockRepo {
     _mocked_save_r: DefaultResult<(), AppError>,
     _mocked_fetch_r: DefaultResult<StockLevelModelSet, AppError>,
     _mocked_stk_return: AsyncMutex<Cell<Vec<DefaultResult<Vec<StockReturnErrorDto>, AppError>>>>,
+    _mocked_olines: Arc<AsyncMutex<Vec<OrderLineModel>>>,
 }
 struct MockOrderRepo {
     _mocked_stock_save: DefaultResult<(), AppError>,
     _mocked_stock_fetch: DefaultResult<StockLevelModelSet, AppError>,
     _mocked_stock_return: Mutex<Cell<Vec<DefaultResult<Vec<StockReturnErrorDto>, AppError>>>>,
     _mocked_ol_sets: AsyncMutex<Cell<Vec<OrderLineModelSet>>>,
-    _mocked_olines: AsyncMutex<Vec<OrderLineModel>>,
+    _mocked_olines: Arc<AsyncMutex<Vec<OrderLineModel>>>,
     _mocked_currency_exrate: AsyncMutex<Option<OrderCurrencyModel>>,
     _mock_oids_ctime: AsyncMutex<Vec<String>>,
     _mock_usr_id: Option<u32>,
@@ -66,6 +76,10 @@ struct MockOrderReturnRepo {
         AsyncMutex<Option<DefaultResult<Vec<(String, OrderReturnModel)>, AppError>>>,
     _mocked_save_result: AsyncMutex<Option<DefaultResult<usize, AppError>>>,
 }
+struct MockShipmentRepo {
+    _mocked_fetched: AsyncMutex<Vec<ShipmentModel>>,
+    _mocked_saved: AsyncMutex<Vec<ShipmentModel>>,
+}
 
 #[async_trait]
 impl AbsCurrencyRepo for MockCurrencyRepo {
@@ -81,6 +95,19 @@ impl AbsCurrencyRepo for MockCurrencyRepo {
     async fn save(&self, _ms: CurrencyModelSet) -> DefaultResult<(), AppError> {
         Err(AppError {code: AppErrorCode::NotImplemented, detail: None})
     }
+    #[rustfmt::skip]
+    async fn fetch_as_of(
+        &self, _chosen: Vec<CurrencyDto>, _time: DateTime<FixedOffset>,
+    ) -> DefaultResult<CurrencyModelSet, AppError> {
+        Err(AppError {code: AppErrorCode::NotImplemented, detail: None})
+    }
+    #[rustfmt::skip]
+    async fn fetch_history(
+        &self, _chosen: Vec<CurrencyDto>, _start: DateTime<FixedOffset>,
+        _end: DateTime<FixedOffset>,
+    ) -> DefaultResult<Vec<CurrencyRateHistoryModel>, AppError> {
+        Err(AppError {code: AppErrorCode::NotImplemented, detail: None})
+    }
 }
 impl MockCurrencyRepo {
     fn build(mock_curr_mset: Option<CurrencyModelSet>) -> Self {
@@ -98,13 +125,60 @@ impl AbsOrderStockRepo for MockStockRepo {
     ) -> DefaultResult<StockLevelModelSet, AppError> {
         self._mocked_fetch_r.clone()
     }
+    async fn fetch_by_product(
+        &self,
+        _pids: Vec<ProductStockIdentity2>,
+    ) -> DefaultResult<StockLevelModelSet, AppError> {
+        self._mocked_fetch_r.clone()
+    }
+    async fn fetch_by_store(
+        &self,
+        _store_id: u32,
+        _product_ids: Vec<u64>,
+        _expiry_after: Option<DateTime<FixedOffset>>,
+        _expiry_before: Option<DateTime<FixedOffset>>,
+    ) -> DefaultResult<StockLevelModelSet, AppError> {
+        self._mocked_fetch_r.clone()
+    }
     async fn save(&self, _slset: StockLevelModelSet) -> DefaultResult<(), AppError> {
         self._mocked_save_r.clone()
     }
+    async fn fetch_alloc_policy(
+        &self,
+        _store_ids: Vec<u32>,
+    ) -> DefaultResult<StockAllocPolicyModelSet, AppError> {
+        Ok(StockAllocPolicyModelSet { policies: vec![] })
+    }
+    async fn save_alloc_policy(
+        &self,
+        _ms: Vec<StockAllocPolicyModel>,
+    ) -> DefaultResult<(), AppError> {
+        self._mocked_save_r.clone()
+    }
+    async fn fetch_warehouses(
+        &self,
+        _store_ids: Vec<u32>,
+    ) -> DefaultResult<StockWarehouseModelSet, AppError> {
+        Ok(StockWarehouseModelSet { warehouses: vec![] })
+    }
+    async fn save_warehouses(&self, _ms: Vec<StockWarehouseModel>) -> DefaultResult<(), AppError> {
+        self._mocked_save_r.clone()
+    }
     async fn try_reserve(
         &self,
         _cb: AppStockRepoReserveUserFunc,
-        _order_req: &OrderLineModelSet,
+        _order_req: &mut OrderLineModelSet,
+    ) -> AppStockRepoReserveReturn {
+        let e = AppError {
+            code: AppErrorCode::NotImplemented,
+            detail: None,
+        };
+        Err(Err(e))
+    }
+    async fn try_fulfil_backorder(
+        &self,
+        _cb: AppStockRepoReserveUserFunc,
+        _order_req: &mut OrderLineModelSet,
     ) -> AppStockRepoReserveReturn {
         let e = AppError {
             code: AppErrorCode::NotImplemented,
@@ -129,6 +203,47 @@ impl AbsOrderStockRepo for MockStockRepo {
             returns.remove(0)
         }
     }
+    async fn try_cancel_return(
+        &self,
+        oid: &str,
+        pids: Vec<OrderLineIdentity>,
+        cancel: OrderLineCancelModel,
+        cancel_cb: AppOrderRepoCancelLinesUserFunc,
+        _return_cb: AppStockRepoReturnUserFunc,
+    ) -> DefaultResult<Vec<OrderLineCancelErrorDto>, AppError> {
+        let mut g = self._mocked_olines.lock().await;
+        let errors = cancel_cb(g.as_mut_slice(), pids, cancel);
+        if !errors.is_empty() {
+            return Ok(errors);
+        }
+        let data = OrderLineModel::cancelled_stock_return(oid, g.as_slice());
+        if data.items.is_empty() {
+            return Ok(errors);
+        }
+        let mut rg = self._mocked_stk_return.lock().await;
+        let returns = rg.get_mut();
+        if returns.is_empty() {
+            let detail = "MockStockRepo::try_cancel_return".to_string();
+            Err(AppError {
+                code: AppErrorCode::InvalidInput,
+                detail: Some(detail),
+            })
+        } else {
+            let return_errors = returns.remove(0)?;
+            if return_errors.is_empty() {
+                Ok(errors)
+            } else {
+                let detail = format!("{:?}", return_errors);
+                Err(AppError {
+                    code: AppErrorCode::DataCorruption,
+                    detail: Some(detail),
+                })
+            }
+        }
+    }
+    async fn release_promotion_usage(&self, _codes: Vec<String>) -> DefaultResult<(), AppError> {
+        Ok(())
+    }
 }
 
 #[async_trait]
@@ -148,6 +263,7 @@ impl AbsOrderRepo for MockOrderRepo {
             _mocked_save_r: self._mocked_stock_save.clone(),
             _mocked_fetch_r: self._mocked_stock_fetch.clone(),
             _mocked_stk_return: AsyncMutex::new(Cell::new(mock_return)),
+            _mocked_olines: self._mocked_olines.clone(),
         };
         Arc::new(Box::new(obj))
     }
@@ -190,6 +306,7 @@ impl AbsOrderRepo for MockOrderRepo {
         let option = vec![ShippingOptionModel {
             seller_id: 123,
             method: ShippingMethod::FedEx,
+            fee: 0,
         }];
         let obj = ShippingModel {
             contact,
@@ -208,6 +325,19 @@ impl AbsOrderRepo for MockOrderRepo {
         Err(AppError {code: AppErrorCode::NotImplemented, detail: None})
     }
 
+    async fn cancel_lines(
+        &self,
+        _oid: &str,
+        pids: Vec<OrderLineIdentity>,
+        cancel: OrderLineCancelModel,
+        usr_cb: AppOrderRepoCancelLinesUserFunc,
+    ) -> DefaultResult<Vec<OrderLineCancelErrorDto>, AppError> {
+        // the mocked lines are kept for subsequent fetch in the same test case
+        let mut g = self._mocked_olines.lock().await;
+        let errors = usr_cb(g.as_mut_slice(), pids, cancel);
+        Ok(errors)
+    }
+
     async fn fetch_lines_by_rsvtime(
         &self,
         _time_start: DateTime<FixedOffset>,
@@ -238,6 +368,13 @@ impl AbsOrderRepo for MockOrderRepo {
             Ok(dst)
         }
     }
+    async fn fetch_backordered(
+        &self,
+        _store_id: u32,
+        _product_ids: Vec<u64>,
+    ) -> DefaultResult<Vec<OrderLineModelSet>, AppError> {
+        Ok(Vec::new())
+    }
     async fn fetch_ids_by_created_time(
         &self,
         _start: DateTime<FixedOffset>,
@@ -254,6 +391,35 @@ impl AbsOrderRepo for MockOrderRepo {
             Ok(g.drain(..).collect())
         }
     }
+    async fn fetch_ids_by_owner(
+        &self,
+        usr_id: u32,
+        _start: DateTime<FixedOffset>,
+        _end: DateTime<FixedOffset>,
+        offset: usize,
+        limit: usize,
+    ) -> DefaultResult<(usize, Vec<(String, DateTime<FixedOffset>)>), AppError> {
+        let oids = self
+            ._mock_oids_ctime
+            .lock()
+            .await
+            .drain(..)
+            .collect::<Vec<_>>();
+        if self._mock_usr_id != Some(usr_id) {
+            return Ok((0, Vec::new()));
+        }
+        let ctime = self
+            ._mock_ctime
+            .unwrap_or(chrono::Local::now().fixed_offset());
+        let total = oids.len();
+        let paged = oids
+            .into_iter()
+            .skip(offset)
+            .take(limit)
+            .map(|oid| (oid, ctime))
+            .collect();
+        Ok((total, paged))
+    }
     async fn owner_id(&self, _order_id: &str) -> DefaultResult<u32, AppError> {
         if let Some(usr_id) = self._mock_usr_id.as_ref() {
             Ok(usr_id.clone())
@@ -314,7 +480,7 @@ impl MockOrderRepo {
             _mocked_stock_fetch: stk_fetch_r,
             _mocked_stock_return: Mutex::new(Cell::new(stk_returns)),
             _mocked_ol_sets: AsyncMutex::new(Cell::new(ol_sets)),
-            _mocked_olines: AsyncMutex::new(olines),
+            _mocked_olines: Arc::new(AsyncMutex::new(olines)),
             _mock_oids_ctime: AsyncMutex::new(oids_ctime),
             _mock_ctime: create_time,
             _mock_usr_id: usr_id,
@@ -384,6 +550,36 @@ impl AbsOrderReturnRepo for MockOrderReturnRepo {
     }
 } // end of impl MockOrderReturnRepo
 
+#[async_trait]
+impl AbsShipmentRepo for MockShipmentRepo {
+    async fn fetch_by_oid(&self, _oid: &str) -> DefaultResult<Vec<ShipmentModel>, AppError> {
+        let g = self._mocked_fetched.lock().await;
+        Ok(g.clone())
+    }
+    async fn create(&self, _oid: &str, obj: ShipmentModel) -> DefaultResult<bool, AppError> {
+        let mut g = self._mocked_saved.lock().await;
+        if g.iter().any(|m| m.seq >= obj.seq) {
+            return Ok(false);
+        }
+        g.push(obj);
+        Ok(true)
+    }
+    async fn update(&self, _oid: &str, obj: ShipmentModel) -> DefaultResult<(), AppError> {
+        let mut g = self._mocked_saved.lock().await;
+        g.push(obj);
+        Ok(())
+    }
+}
+
+impl MockShipmentRepo {
+    fn build(fetched: Vec<ShipmentModel>) -> Self {
+        Self {
+            _mocked_fetched: AsyncMutex::new(fetched),
+            _mocked_saved: AsyncMutex::new(Vec::new()),
+        }
+    }
+}
+
 impl MockOrderReturnRepo {
     fn build(
         fetched_returns: DefaultResult<Vec<OrderReturnModel>, AppError>,
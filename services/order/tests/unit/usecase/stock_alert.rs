@@ -0,0 +1,256 @@
+use std::boxed::Box;
+use std::result::Result as DefaultResult;
+use std::sync::{Arc, Mutex};
+use std::vec::Vec;
+
+use async_trait::async_trait;
+use chrono::{DateTime, FixedOffset};
+use serde_json::Value as JsnVal;
+
+use ecommerce_common::error::AppErrorCode;
+
+use order::datastore::{AbstInMemoryDStore, AppInMemoryDStore};
+use order::error::AppError;
+use order::model::{StockAlertThresholdModel, StockLevelModelSet};
+use order::repository::{AbsStockAlertRepo, StockAlertInMemRepo};
+use order::usecase::StockAlertCheckUseCase;
+use order::{
+    AbsRpcClientCtx, AbsRpcServerCtx, AbstractRpcClient, AbstractRpcContext, AppInMemoryDbCfg,
+    AppRpcClientReqProperty, AppRpcReply, AppRpcRouteHdlrFn, AppSharedState,
+};
+
+use super::MockOrderRepo;
+use crate::model::stock_alert::ut_setup_store_stock;
+use crate::{ut_setup_share_state, MockConfidential};
+
+type UTestPublishedMsgs = Arc<Mutex<Vec<(String, Vec<u8>)>>>;
+
+struct UTestAlertRpcClient {
+    published: UTestPublishedMsgs,
+}
+
+struct UTestAlertRpcContext {
+    published: UTestPublishedMsgs,
+    available: bool,
+}
+
+#[async_trait]
+impl AbstractRpcClient for UTestAlertRpcClient {
+    async fn send_request(
+        mut self: Box<Self>,
+        props: AppRpcClientReqProperty,
+    ) -> DefaultResult<Box<dyn AbstractRpcClient>, AppError> {
+        let mut g = self.published.lock().unwrap();
+        g.push((props.route, props.msgbody));
+        drop(g);
+        Ok(self)
+    }
+    async fn receive_response(&mut self) -> DefaultResult<AppRpcReply, AppError> {
+        Err(AppError {
+            code: AppErrorCode::NotImplemented,
+            detail: None,
+        })
+    }
+}
+
+#[async_trait]
+impl AbsRpcClientCtx for UTestAlertRpcContext {
+    async fn acquire(&self, _num_retry: u8) -> DefaultResult<Box<dyn AbstractRpcClient>, AppError> {
+        if self.available {
+            let obj = UTestAlertRpcClient {
+                published: self.published.clone(),
+            };
+            Ok(Box::new(obj))
+        } else {
+            Err(AppError {
+                code: AppErrorCode::RpcRemoteUnavail,
+                detail: Some("unit-test".to_string()),
+            })
+        }
+    }
+}
+#[async_trait]
+impl AbsRpcServerCtx for UTestAlertRpcContext {
+    async fn server_start(
+        &self,
+        _shr_state: AppSharedState,
+        _route_hdlr: AppRpcRouteHdlrFn,
+    ) -> DefaultResult<(), AppError> {
+        Ok(())
+    }
+}
+impl AbstractRpcContext for UTestAlertRpcContext {
+    fn label(&self) -> &'static str {
+        "unit-test"
+    }
+}
+
+fn ut_setup_alert_dstore() -> Arc<Box<dyn AbstInMemoryDStore>> {
+    let cfg = AppInMemoryDbCfg {
+        alias: "utest".to_string(),
+        max_items: 20,
+    };
+    let obj: Box<dyn AbstInMemoryDStore> = Box::new(AppInMemoryDStore::new(&cfg));
+    Arc::new(obj)
+}
+
+async fn ut_run_check(
+    dstore: Arc<Box<dyn AbstInMemoryDStore>>,
+    published: UTestPublishedMsgs,
+    rpc_available: bool,
+    stock_data: Vec<(u64, i64, u32, u32, u32)>,
+    time_now: DateTime<FixedOffset>,
+) -> usize {
+    let shr_state = ut_setup_share_state("config_ok_no_sqldb.json", Box::new(MockConfidential {}));
+    let store = ut_setup_store_stock(1012, time_now.to_utc(), stock_data);
+    let order_repo = MockOrderRepo::build(
+        Ok(()),
+        Ok(StockLevelModelSet {
+            stores: vec![store],
+        }),
+        vec![],
+        vec![],
+        vec![],
+        vec![],
+        None,
+        None,
+        None,
+    );
+    let alert_repo = StockAlertInMemRepo::new(dstore).await.unwrap();
+    let rpc_ctx: Box<dyn AbstractRpcContext> = Box::new(UTestAlertRpcContext {
+        published,
+        available: rpc_available,
+    });
+    let uc = StockAlertCheckUseCase {
+        logctx: shr_state.log_context().clone(),
+        alert_repo: Box::new(alert_repo),
+        order_repo: Box::new(order_repo),
+        rpc_ctx: Arc::new(rpc_ctx),
+    };
+    let result = uc.execute(time_now).await;
+    assert!(result.is_ok());
+    result.unwrap()
+}
+
+async fn ut_save_threshold(dstore: Arc<Box<dyn AbstInMemoryDStore>>) {
+    let repo = StockAlertInMemRepo::new(dstore).await.unwrap();
+    let ms = vec![StockAlertThresholdModel {
+        store_id: 1012,
+        low_stock_qty: 4,
+        expiry_within_days: 7,
+    }];
+    let result = repo.save_thresholds(ms).await;
+    assert!(result.is_ok());
+}
+
+fn ut_verify_published(published: &UTestPublishedMsgs, idx: usize, expect_num_alerts: usize) {
+    let g = published.lock().unwrap();
+    let (route, msgbody) = &g[idx];
+    assert_eq!(route.as_str(), "rpc.storefront.stock_level_alert");
+    // python celery message format, the payload is in keyword arguments
+    let msg = serde_json::from_slice::<JsnVal>(msgbody).unwrap();
+    let kwargs = msg.as_array().unwrap().get(1).unwrap();
+    assert_eq!(kwargs["store_id"].as_u64(), Some(1012));
+    let alerts = kwargs["alerts"].as_array().unwrap();
+    assert_eq!(alerts.len(), expect_num_alerts);
+}
+
+#[tokio::test]
+async fn publish_once_ok() {
+    let time_now = DateTime::parse_from_rfc3339("2024-03-01T09:00:00+00:00").unwrap();
+    let dstore = ut_setup_alert_dstore();
+    let published: UTestPublishedMsgs = Arc::new(Mutex::new(Vec::new()));
+    ut_save_threshold(dstore.clone()).await;
+    let stock_data = vec![
+        (2001, 3, 4, 1, 2),
+        (2001, 60, 2, 0, 0),
+        (2002, 90, 10, 0, 0),
+        (2003, 5, 9, 0, 9),
+    ];
+    let num_published = ut_run_check(
+        dstore.clone(),
+        published.clone(),
+        true,
+        stock_data.clone(),
+        time_now,
+    )
+    .await;
+    assert_eq!(num_published, 3);
+    assert_eq!(published.lock().unwrap().len(), 1);
+    ut_verify_published(&published, 0, 3);
+    // ------ subcase, the same conditions still hold, nothing published again
+    let num_published = ut_run_check(
+        dstore.clone(),
+        published.clone(),
+        true,
+        stock_data.clone(),
+        time_now,
+    )
+    .await;
+    assert_eq!(num_published, 0);
+    assert_eq!(published.lock().unwrap().len(), 1);
+    // ------ subcase, product 2001 restocked, the low-stock alert is no longer
+    // in effect, the other alerts remain silent
+    let restocked = vec![
+        (2001, 3, 4, 1, 2),
+        (2001, 60, 8, 0, 0),
+        (2002, 90, 10, 0, 0),
+        (2003, 5, 9, 0, 9),
+    ];
+    let num_published =
+        ut_run_check(dstore.clone(), published.clone(), true, restocked, time_now).await;
+    assert_eq!(num_published, 0);
+    assert_eq!(published.lock().unwrap().len(), 1);
+    // ------ subcase, product 2001 drops below the threshold again
+    let num_published = ut_run_check(
+        dstore.clone(),
+        published.clone(),
+        true,
+        stock_data,
+        time_now,
+    )
+    .await;
+    assert_eq!(num_published, 1);
+    assert_eq!(published.lock().unwrap().len(), 2);
+    ut_verify_published(&published, 1, 1);
+} // end of fn publish_once_ok
+
+#[tokio::test]
+async fn publish_retry_after_failure() {
+    let time_now = DateTime::parse_from_rfc3339("2024-03-01T09:00:00+00:00").unwrap();
+    let dstore = ut_setup_alert_dstore();
+    let published: UTestPublishedMsgs = Arc::new(Mutex::new(Vec::new()));
+    ut_save_threshold(dstore.clone()).await;
+    let stock_data = vec![(2001, 3, 4, 1, 2), (2002, 90, 10, 0, 0)];
+    let num_published = ut_run_check(
+        dstore.clone(),
+        published.clone(),
+        false,
+        stock_data.clone(),
+        time_now,
+    )
+    .await;
+    assert_eq!(num_published, 0);
+    assert!(published.lock().unwrap().is_empty());
+    // the alerts are not recorded as sent, they are published in next round
+    let num_published = ut_run_check(
+        dstore.clone(),
+        published.clone(),
+        true,
+        stock_data.clone(),
+        time_now,
+    )
+    .await;
+    assert_eq!(num_published, 2);
+    ut_verify_published(&published, 0, 2);
+    let num_published = ut_run_check(
+        dstore.clone(),
+        published.clone(),
+        true,
+        stock_data,
+        time_now,
+    )
+    .await;
+    assert_eq!(num_published, 0);
+    assert_eq!(published.lock().unwrap().len(), 1);
+}
@@ -1,37 +1,48 @@
 use chrono::DateTime;
+use std::result::Result as DefaultResult;
 use std::vec::Vec;
 
 use ecommerce_common::error::AppErrorCode;
 
-use order::api::rpc::dto::InventoryEditStockLevelDto;
+use order::api::rpc::dto::{
+    InventoryEditStockLevelDto, StockAllocPolicyDto, StockAllocStrategyDto,
+};
+use order::api::web::dto::StockLevelReadReqQuery;
+use order::constant::{app_meta, hard_limit};
 use order::error::AppError;
 use order::model::{ProductStockModel, StockLevelModelSet, StockQuantityModel, StoreStockModel};
-use order::usecase::StockLevelUseCase;
+use order::usecase::{ReadStockLevelUcOutput, ReadStockLevelUseCase, StockLevelUseCase};
+use order::{AppAuthClaimPermission, AppAuthPermissionCode, AppAuthedClaim};
 
+use super::verify_store::ut_setup_store_supervisor_rpc;
 use super::MockOrderRepo;
 use crate::{ut_setup_share_state, MockConfidential};
 
 fn ut_setup_data() -> Vec<InventoryEditStockLevelDto> {
     vec![
         InventoryEditStockLevelDto {
+            warehouse_id: 0,
             qty_add: 13,
             store_id: 91,
             product_id: 210094,
             expiry: DateTime::parse_from_rfc3339("2023-01-19T06:05:39.001+05:00").unwrap(),
         },
         InventoryEditStockLevelDto {
+            warehouse_id: 0,
             qty_add: 2,
             store_id: 91,
             product_id: 210095,
             expiry: DateTime::parse_from_rfc3339("2023-01-19T06:05:39.002+05:00").unwrap(),
         },
         InventoryEditStockLevelDto {
+            warehouse_id: 0,
             qty_add: -9,
             store_id: 99,
             product_id: 210096,
             expiry: DateTime::parse_from_rfc3339("2023-01-19T06:05:40.003+05:00").unwrap(),
         },
         InventoryEditStockLevelDto {
+            warehouse_id: 0,
             qty_add: 5,
             store_id: 101,
             product_id: 210097,
@@ -48,8 +59,10 @@ async fn edit_ok() {
         stores: vec![StoreStockModel {
             store_id: init_data[2].store_id,
             products: vec![ProductStockModel {
+                warehouse_id: 0,
                 id_: init_data[2].product_id,
                 is_create: false,
+                arrival: None,
                 expiry: init_data[2].expiry.into(),
                 quantity: StockQuantityModel::new(2, 0, 0, None),
             }],
@@ -71,7 +84,8 @@ async fn edit_ok() {
         StockLevelUseCase::try_edit(init_data, Box::new(repo), app_state.log_context().clone())
             .await;
     assert!(result.is_ok());
-    let _stock_lvl_rd = result.unwrap();
+    let out = result.unwrap();
+    assert!(out.backorder_errors.is_empty());
     // TODO, verify present data from model set
 }
 
@@ -115,9 +129,11 @@ async fn edit_save_error() {
         stores: vec![StoreStockModel {
             store_id: init_data[2].store_id,
             products: vec![ProductStockModel {
+                warehouse_id: 0,
                 id_: init_data[2].product_id,
                 expiry: init_data[2].expiry.into(),
                 is_create: false,
+                arrival: None,
                 quantity: StockQuantityModel::new(2, 0, 0, None),
             }],
         }],
@@ -148,3 +164,309 @@ async fn edit_save_error() {
         }
     }
 }
+
+fn ut_setup_read_claim(perms: Vec<AppAuthPermissionCode>) -> AppAuthedClaim {
+    let perms = perms
+        .into_iter()
+        .map(|codename| AppAuthClaimPermission {
+            app_code: app_meta::RESOURCE_QUOTA_AP_CODE,
+            codename,
+        })
+        .collect();
+    AppAuthedClaim {
+        profile: 1234,
+        iat: 0,
+        exp: 0,
+        aud: Vec::new(),
+        quota: vec![],
+        perms,
+    }
+}
+
+fn ut_setup_read_repo(fetch_res: DefaultResult<StockLevelModelSet, AppError>) -> MockOrderRepo {
+    MockOrderRepo::build(
+        Ok(()),
+        fetch_res,
+        vec![],
+        vec![],
+        vec![],
+        vec![],
+        None,
+        None,
+        None,
+    )
+}
+
+fn ut_setup_read_usecase(
+    authed_claim: AppAuthedClaim,
+    fetch_res: DefaultResult<StockLevelModelSet, AppError>,
+    store_supervisor_id: u32,
+) -> ReadStockLevelUseCase {
+    let shr_state = ut_setup_share_state("config_ok_no_sqldb.json", Box::new(MockConfidential {}));
+    ReadStockLevelUseCase {
+        authed_claim,
+        repo: Box::new(ut_setup_read_repo(fetch_res)),
+        rpc_ctx: ut_setup_store_supervisor_rpc(store_supervisor_id),
+        logctx: shr_state.log_context().clone(),
+    }
+}
+
+#[tokio::test]
+async fn read_ok() {
+    let expiries = [
+        DateTime::parse_from_rfc3339("2023-01-19T06:05:39.001+05:00").unwrap(),
+        DateTime::parse_from_rfc3339("2023-02-21T11:05:39.001+05:00").unwrap(),
+    ];
+    let fetch_res = Ok(StockLevelModelSet {
+        stores: vec![StoreStockModel {
+            store_id: 91,
+            products: vec![
+                ProductStockModel {
+                    warehouse_id: 0,
+                    id_: 210095,
+                    is_create: false,
+                    arrival: None,
+                    expiry: expiries[1].into(),
+                    quantity: StockQuantityModel::new(12, 3, 1, None),
+                },
+                ProductStockModel {
+                    warehouse_id: 0,
+                    id_: 210094,
+                    is_create: false,
+                    arrival: None,
+                    expiry: expiries[0].into(),
+                    quantity: StockQuantityModel::new(7, 0, 2, None),
+                },
+                ProductStockModel {
+                    warehouse_id: 0,
+                    id_: 210095,
+                    is_create: false,
+                    arrival: None,
+                    expiry: expiries[0].into(),
+                    quantity: StockQuantityModel::new(5, 5, 0, None),
+                },
+            ],
+        }],
+    });
+    let uc = ut_setup_read_usecase(
+        ut_setup_read_claim(vec![AppAuthPermissionCode::can_view_stock_level]),
+        fetch_res,
+        1234,
+    );
+    let req = StockLevelReadReqQuery {
+        product_ids: Some("210094, 210095".to_string()),
+        expiry_after: None,
+        expiry_before: None,
+    };
+    let result = uc.execute(91, req).await;
+    assert!(matches!(result, Ok(ReadStockLevelUcOutput::Success(_))));
+    if let Ok(ReadStockLevelUcOutput::Success(resp)) = result {
+        assert_eq!(resp.store_id, 91);
+        assert_eq!(resp.products.len(), 2);
+        let p = &resp.products[0];
+        assert_eq!(p.product_id, 210094);
+        assert_eq!((p.total, p.booked, p.cancelled, p.remaining), (7, 2, 0, 5));
+        assert_eq!(p.batches.len(), 1);
+        let p = &resp.products[1];
+        assert_eq!(p.product_id, 210095);
+        assert_eq!((p.total, p.booked, p.cancelled, p.remaining), (17, 1, 8, 8));
+        assert_eq!(p.batches.len(), 2);
+        assert!(p.batches[0].expiry < p.batches[1].expiry);
+        assert_eq!(p.batches[0].remaining, 0);
+        assert_eq!(p.batches[1].remaining, 8);
+    }
+} // end of fn read_ok
+
+#[tokio::test]
+async fn read_permission_deny() {
+    let uc = ut_setup_read_usecase(
+        ut_setup_read_claim(vec![AppAuthPermissionCode::can_create_product_policy]),
+        Ok(StockLevelModelSet { stores: vec![] }),
+        1234,
+    );
+    let req = StockLevelReadReqQuery {
+        product_ids: None,
+        expiry_after: None,
+        expiry_before: None,
+    };
+    let result = uc.execute(91, req).await;
+    assert!(matches!(result, Ok(ReadStockLevelUcOutput::PermissionDeny)));
+}
+
+#[tokio::test]
+async fn read_invalid_input() {
+    let perms = vec![AppAuthPermissionCode::can_view_stock_level];
+    let uc = ut_setup_read_usecase(
+        ut_setup_read_claim(perms.clone()),
+        Ok(StockLevelModelSet { stores: vec![] }),
+        1234,
+    );
+    let req = StockLevelReadReqQuery {
+        product_ids: Some("210094,abc".to_string()),
+        expiry_after: None,
+        expiry_before: None,
+    };
+    let result = uc.execute(91, req).await;
+    assert!(matches!(
+        result,
+        Ok(ReadStockLevelUcOutput::InvalidProductId)
+    ));
+    let uc = ut_setup_read_usecase(
+        ut_setup_read_claim(perms),
+        Ok(StockLevelModelSet { stores: vec![] }),
+        1234,
+    );
+    let req = StockLevelReadReqQuery {
+        product_ids: None,
+        expiry_after: Some(DateTime::parse_from_rfc3339("2023-03-01T00:00:00+00:00").unwrap()),
+        expiry_before: Some(DateTime::parse_from_rfc3339("2023-02-01T00:00:00+00:00").unwrap()),
+    };
+    let result = uc.execute(91, req).await;
+    assert!(matches!(
+        result,
+        Ok(ReadStockLevelUcOutput::InvalidExpiryRange)
+    ));
+}
+
+#[tokio::test]
+async fn read_too_many_products() {
+    let product_ids = (0..=hard_limit::MAX_PRODUCTS_STOCK_LEVEL_READ)
+        .map(|n| (210000 + n).to_string())
+        .collect::<Vec<_>>()
+        .join(",");
+    let uc = ut_setup_read_usecase(
+        ut_setup_read_claim(vec![AppAuthPermissionCode::can_view_stock_level]),
+        Ok(StockLevelModelSet { stores: vec![] }),
+        1234,
+    );
+    let req = StockLevelReadReqQuery {
+        product_ids: Some(product_ids),
+        expiry_after: None,
+        expiry_before: None,
+    };
+    let result = uc.execute(91, req).await;
+    assert!(matches!(
+        result,
+        Ok(ReadStockLevelUcOutput::TooManyProducts)
+    ));
+}
+
+#[tokio::test]
+async fn read_other_store_denied() {
+    let uc = ut_setup_read_usecase(
+        ut_setup_read_claim(vec![AppAuthPermissionCode::can_view_stock_level]),
+        Ok(StockLevelModelSet { stores: vec![] }),
+        5678,
+    );
+    let req = StockLevelReadReqQuery {
+        product_ids: None,
+        expiry_after: None,
+        expiry_before: None,
+    };
+    let result = uc.execute(91, req).await;
+    assert!(matches!(result, Ok(ReadStockLevelUcOutput::PermissionDeny)));
+}
+
+#[tokio::test]
+async fn read_fetch_error() {
+    let fetch_res = Err(AppError {
+        code: AppErrorCode::DataTableNotExist,
+        detail: Some("unit-test".to_string()),
+    });
+    let uc = ut_setup_read_usecase(
+        ut_setup_read_claim(vec![AppAuthPermissionCode::can_view_stock_level]),
+        fetch_res,
+        1234,
+    );
+    let req = StockLevelReadReqQuery {
+        product_ids: None,
+        expiry_after: None,
+        expiry_before: None,
+    };
+    let result = uc.execute(91, req).await;
+    assert!(result.is_err());
+    if let Err(e) = result {
+        assert_eq!(e.code, AppErrorCode::DataTableNotExist);
+    }
+}
+
+fn ut_setup_alloc_policy_data() -> Vec<StockAllocPolicyDto> {
+    vec![
+        StockAllocPolicyDto {
+            store_id: 1013,
+            product_id: None,
+            strategy: StockAllocStrategyDto::FIFO,
+            min_shelf_days: 0,
+        },
+        StockAllocPolicyDto {
+            store_id: 1013,
+            product_id: Some(8299),
+            strategy: StockAllocStrategyDto::FEFO,
+            min_shelf_days: 10,
+        },
+    ]
+}
+
+fn ut_setup_alloc_policy_repo(save_res: DefaultResult<(), AppError>) -> MockOrderRepo {
+    MockOrderRepo::build(
+        save_res,
+        Ok(StockLevelModelSet { stores: vec![] }),
+        vec![],
+        vec![],
+        vec![],
+        vec![],
+        None,
+        None,
+        None,
+    )
+}
+
+#[tokio::test]
+async fn edit_alloc_policy_ok() {
+    let app_state = ut_setup_share_state("config_ok_no_sqldb.json", Box::new(MockConfidential {}));
+    let repo = ut_setup_alloc_policy_repo(Ok(()));
+    let result = StockLevelUseCase::try_edit_alloc_policy(
+        ut_setup_alloc_policy_data(),
+        Box::new(repo),
+        app_state.log_context().clone(),
+    )
+    .await;
+    assert!(result.is_ok());
+}
+
+#[tokio::test]
+async fn edit_alloc_policy_invalid_shelf_life() {
+    let app_state = ut_setup_share_state("config_ok_no_sqldb.json", Box::new(MockConfidential {}));
+    let repo = ut_setup_alloc_policy_repo(Ok(()));
+    let mut data = ut_setup_alloc_policy_data();
+    data[1].min_shelf_days = 400;
+    let result = StockLevelUseCase::try_edit_alloc_policy(
+        data,
+        Box::new(repo),
+        app_state.log_context().clone(),
+    )
+    .await;
+    assert!(result.is_err());
+    if let Err(e) = result {
+        assert_eq!(e.code, AppErrorCode::InvalidInput);
+    }
+}
+
+#[tokio::test]
+async fn edit_alloc_policy_save_error() {
+    let app_state = ut_setup_share_state("config_ok_no_sqldb.json", Box::new(MockConfidential {}));
+    let repo = ut_setup_alloc_policy_repo(Err(AppError {
+        code: AppErrorCode::DataTableNotExist,
+        detail: Some("unit-test".to_string()),
+    }));
+    let result = StockLevelUseCase::try_edit_alloc_policy(
+        ut_setup_alloc_policy_data(),
+        Box::new(repo),
+        app_state.log_context().clone(),
+    )
+    .await;
+    assert!(result.is_err());
+    if let Err(e) = result {
+        assert_eq!(e.code, AppErrorCode::DataTableNotExist);
+    }
+}
@@ -0,0 +1,120 @@
+use std::boxed::Box;
+use std::sync::Arc;
+
+use chrono::{Duration, Local};
+
+use ecommerce_common::error::AppErrorCode;
+
+use order::usecase::VerifyStoreStaffUseCase;
+use order::{AbstractRpcClient, AbstractRpcContext, AppRpcCfg, AppRpcReply};
+
+use super::{MockRpcContext, MockRpcHandler};
+use crate::{ut_setup_share_state, MockConfidential};
+
+fn ut_setup_rpc_ctx(reply_result: &str) -> Arc<Box<dyn AbstractRpcContext>> {
+    let cfg = AppRpcCfg::dummy;
+    let _ctx = MockRpcContext::_build(&cfg);
+    let hdlr = {
+        let body = format!(
+            r#"{{"task_id":"unit-test","status":"SUCCESS","result":{}}}"#,
+            reply_result
+        );
+        let reply = AppRpcReply {
+            body: body.into_bytes(),
+        };
+        let h2 = MockRpcHandler::default().mock_c_reply(Ok(reply));
+        MockRpcHandler::default().mock_c_pub(Ok(Box::new(h2)))
+    };
+    let a: Box<dyn AbstractRpcClient> = Box::new(hdlr);
+    _ctx.mock_c(Ok(a));
+    Arc::new(Box::new(_ctx))
+}
+
+// RPC context which replies profile of a store managed only by the given user
+pub(super) fn ut_setup_store_supervisor_rpc(
+    supervisor_id: u32,
+) -> Arc<Box<dyn AbstractRpcContext>> {
+    let reply = ut_store_profile(true, supervisor_id, vec![]);
+    ut_setup_rpc_ctx(reply.as_str())
+}
+
+fn ut_setup_verifier(reply_result: &str) -> VerifyStoreStaffUseCase {
+    let shr_state = ut_setup_share_state("config_ok_no_sqldb.json", Box::new(MockConfidential {}));
+    let rpc_ctx = ut_setup_rpc_ctx(reply_result);
+    VerifyStoreStaffUseCase {
+        rpc_ctx,
+        logctx: shr_state.log_context().clone(),
+    }
+}
+
+fn ut_store_profile(active: bool, supervisor_id: u32, staff: Vec<(u32, i64, i64)>) -> String {
+    let now = Local::now().fixed_offset();
+    let staff = staff
+        .into_iter()
+        .map(|(staff_id, start_mins, end_mins)| {
+            let t0 = now + Duration::minutes(start_mins);
+            let t1 = now + Duration::minutes(end_mins);
+            format!(
+                r#"{{"staff_id":{},"start_after":"{}","end_before":"{}"}}"#,
+                staff_id,
+                t0.to_rfc3339(),
+                t1.to_rfc3339()
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(",");
+    format!(
+        r#"{{"label":"unit-test-shop","active":{},"supervisor_id":{},"emails":null,
+           "phones":null,"location":null,"staff":[{}]}}"#,
+        active, supervisor_id, staff
+    )
+}
+
+#[tokio::test]
+async fn verify_supervisor_ok() {
+    let reply = ut_store_profile(true, 1710, vec![]);
+    let uc = ut_setup_verifier(reply.as_str());
+    let result = uc.execute(1710, vec![1015]).await;
+    assert!(matches!(result, Ok(true)));
+}
+
+#[tokio::test]
+async fn verify_active_staff_ok() {
+    let reply = ut_store_profile(true, 1710, vec![(1711, -30, 30), (1712, -60, -10)]);
+    let uc = ut_setup_verifier(reply.as_str());
+    let result = uc.execute(1711, vec![1015]).await;
+    assert!(matches!(result, Ok(true)));
+}
+
+#[tokio::test]
+async fn verify_expired_staff_denied() {
+    let reply = ut_store_profile(true, 1710, vec![(1711, -30, 30), (1712, -60, -10)]);
+    let uc = ut_setup_verifier(reply.as_str());
+    let result = uc.execute(1712, vec![1015]).await;
+    assert!(matches!(result, Ok(false)));
+}
+
+#[tokio::test]
+async fn verify_other_user_denied() {
+    let reply = ut_store_profile(true, 1710, vec![(1711, -30, 30)]);
+    let uc = ut_setup_verifier(reply.as_str());
+    let result = uc.execute(1799, vec![1015]).await;
+    assert!(matches!(result, Ok(false)));
+}
+
+#[tokio::test]
+async fn verify_inactive_store_denied() {
+    let reply = ut_store_profile(false, 1710, vec![]);
+    let uc = ut_setup_verifier(reply.as_str());
+    let result = uc.execute(1710, vec![1015]).await;
+    assert!(matches!(result, Ok(false)));
+}
+
+#[tokio::test]
+async fn verify_corrupted_reply() {
+    let uc = ut_setup_verifier(r#"{"label":"unit-test-shop"}"#);
+    let result = uc.execute(1710, vec![1015]).await;
+    assert!(result.is_err());
+    let e = result.unwrap_err();
+    assert_eq!(e.code, AppErrorCode::RpcRemoteInvalidReply);
+}
@@ -1,22 +1,74 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
 use async_trait::async_trait;
+use chrono::{DateTime, Duration, Local, Utc};
 
-use super::{AbstractOrderSyncLockCache, OrderSyncLockError};
+use super::{AbstractOrderSyncLockCache, OrderSyncLockError, OrderSyncLockToken};
 
-pub(super) struct InMemOrderSyncLockCache {}
+/// locks are kept in the memory of current process, this is sufficient
+/// only when a single instance of this application is running.
+pub(super) struct InMemOrderSyncLockCache {
+    _max_items: usize,
+    _ttl: Duration,
+    // key is combination of buyer ID and order ID, value is expiry time
+    // and the token issued to current holder
+    _locks: Mutex<HashMap<(u32, String), (DateTime<Utc>, String)>>,
+    _num_issued: AtomicU64,
+}
 
-impl Default for InMemOrderSyncLockCache {
-    fn default() -> Self {
-        Self {}
+impl InMemOrderSyncLockCache {
+    pub(super) fn new(max_items: u32, ttl_secs: u16) -> Self {
+        Self {
+            _max_items: (max_items as usize).max(1),
+            _ttl: Duration::seconds(ttl_secs.max(1) as i64),
+            _locks: Mutex::new(HashMap::new()),
+            _num_issued: AtomicU64::new(0),
+        }
     }
 }
 
 #[async_trait]
 impl AbstractOrderSyncLockCache for InMemOrderSyncLockCache {
-    async fn acquire(&self, _usr_id: u32, _oid: &str) -> Result<bool, OrderSyncLockError> {
-        Ok(true) // TODO, finish implementation
+    async fn acquire(
+        &self,
+        usr_id: u32,
+        oid: &str,
+    ) -> Result<Option<OrderSyncLockToken>, OrderSyncLockError> {
+        let now = Local::now().to_utc();
+        let mut guard = self._locks.lock().map_err(|_e| OrderSyncLockError)?;
+        let key = (usr_id, oid.to_string());
+        if let Some((expiry, _token)) = guard.get(&key) {
+            if *expiry > now {
+                return Ok(None);
+            }
+        }
+        if guard.len() >= self._max_items {
+            guard.retain(|_k, (expiry, _token)| *expiry > now);
+            if guard.len() >= self._max_items {
+                return Err(OrderSyncLockError);
+            }
+        }
+        let seq = self._num_issued.fetch_add(1, Ordering::Relaxed);
+        let token = format!("{:x}", seq);
+        let _old = guard.insert(key, (now + self._ttl, token.clone()));
+        Ok(Some(OrderSyncLockToken(token)))
     }
 
-    async fn release(&self, _usr_id: u32, _oid: &str) -> Result<(), OrderSyncLockError> {
+    async fn release(
+        &self,
+        usr_id: u32,
+        oid: &str,
+        token: OrderSyncLockToken,
+    ) -> Result<(), OrderSyncLockError> {
+        let mut guard = self._locks.lock().map_err(|_e| OrderSyncLockError)?;
+        let key = (usr_id, oid.to_string());
+        // the lock might have expired and been taken by others
+        let owned = guard.get(&key).is_some_and(|(_expiry, t)| *t == token.0);
+        if owned {
+            let _old = guard.remove(&key);
+        }
         Ok(())
     }
 }
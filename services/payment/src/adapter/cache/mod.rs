@@ -1,25 +1,77 @@
 mod in_mem;
+mod redis;
 
 use std::boxed::Box;
 use std::marker::{Send, Sync};
 use std::result::Result;
+use std::sync::Arc;
 
 use async_trait::async_trait;
 
+use ecommerce_common::confidentiality::AbstractConfidentiality;
+use ecommerce_common::config::AppCacheCfg;
+use ecommerce_common::error::AppConfidentialityError;
+use ecommerce_common::logging::AppLogContext;
+
 use in_mem::InMemOrderSyncLockCache;
+use redis::RedisOrderSyncLockCache;
+
+// applied when the cache is not specified in configuration
+const DEFAULT_INMEM_MAX_ITEMS: u32 = 1024;
+const DEFAULT_LOCK_TTL_SECS: u16 = 30;
 
 pub struct OrderSyncLockError;
 
+/// issued to the holder on each successful acquisition, the lock can be
+/// released only with the same token, so a holder whose lock has expired
+/// and been taken by others cannot remove the lock by mistake.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderSyncLockToken(pub String);
+
+#[derive(Debug)]
+pub enum AppCacheInitError {
+    ConfidentialLoad(AppConfidentialityError),
+    ConfidentialResolve(String),
+}
+
+/// Each lock is identified by the buyer and the order, the lock expires
+/// automatically after the time-to-live, in case the holder fails to release
+/// it, e.g. the application crashes in the middle of synchronisation.
 #[async_trait]
 pub trait AbstractOrderSyncLockCache: Send + Sync {
-    async fn acquire(&self, usr_id: u32, oid: &str) -> Result<bool, OrderSyncLockError>;
+    /// return `None` if the lock is currently held by others
+    async fn acquire(
+        &self,
+        usr_id: u32,
+        oid: &str,
+    ) -> Result<Option<OrderSyncLockToken>, OrderSyncLockError>;
 
-    async fn release(&self, usr_id: u32, oid: &str) -> Result<(), OrderSyncLockError>;
+    async fn release(
+        &self,
+        usr_id: u32,
+        oid: &str,
+        token: OrderSyncLockToken,
+    ) -> Result<(), OrderSyncLockError>;
 }
 
-// TODO, pass config object that allows users to switch between
-// different caches e.g. Redis in the future
-pub fn app_cache_order_sync_lock() -> Box<dyn AbstractOrderSyncLockCache> {
-    let cch = InMemOrderSyncLockCache::default();
-    Box::new(cch)
+pub fn app_cache_order_sync_lock(
+    cfg: Option<&AppCacheCfg>,
+    cfdntl: Arc<Box<dyn AbstractConfidentiality>>,
+    logctx: Arc<AppLogContext>,
+) -> Result<Box<dyn AbstractOrderSyncLockCache>, AppCacheInitError> {
+    let obj: Box<dyn AbstractOrderSyncLockCache> = match cfg {
+        Some(AppCacheCfg::InMemory(c)) => {
+            let cch = InMemOrderSyncLockCache::new(c.max_items, c.ttl_secs);
+            Box::new(cch)
+        }
+        Some(AppCacheCfg::Redis(c)) => {
+            let cch = RedisOrderSyncLockCache::try_build(c, cfdntl, logctx)?;
+            Box::new(cch)
+        }
+        None => {
+            let cch = InMemOrderSyncLockCache::new(DEFAULT_INMEM_MAX_ITEMS, DEFAULT_LOCK_TTL_SECS);
+            Box::new(cch)
+        }
+    };
+    Ok(obj)
 }
@@ -0,0 +1,317 @@
+use std::boxed::Box;
+use std::future::Future;
+use std::pin::Pin;
+use std::result::Result;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration as StdDuration;
+
+use async_trait::async_trait;
+use chrono::Local;
+use serde::Deserialize;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufStream};
+use tokio::net::TcpStream;
+use tokio::sync::{Mutex as AsyncMutex, Semaphore};
+use tokio::time::timeout;
+
+use ecommerce_common::confidentiality::AbstractConfidentiality;
+use ecommerce_common::config::AppCacheServerCfg;
+use ecommerce_common::logging::{app_log_event, AppLogContext, AppLogLevel};
+
+use super::{
+    AbstractOrderSyncLockCache, AppCacheInitError, OrderSyncLockError, OrderSyncLockToken,
+};
+
+const KEY_PREFIX: &str = "payment:ordersync";
+// for connecting to the server and each round trip of a command
+const OPERATION_TIMEOUT_SECS: u64 = 3;
+
+#[allow(non_snake_case)]
+#[derive(Deserialize)]
+struct CacheSecret {
+    HOST: String,
+    PORT: u16,
+    PASSWORD: Option<String>,
+}
+
+// detail of each variant is read only when logging the error
+#[allow(dead_code)]
+#[derive(Debug)]
+enum RespError {
+    Io(String),
+    Timeout,
+    Protocol(String),
+    Server(String),
+}
+
+/// values defined in the serialisation protocol (RESP2) of Redis
+#[allow(dead_code)]
+#[derive(Debug)]
+enum RespValue {
+    Simple(String),
+    Error(String),
+    Integer(i64),
+    Bulk(Option<Vec<u8>>),
+    Array(Option<Vec<RespValue>>),
+}
+
+struct RespConnection {
+    stream: BufStream<TcpStream>,
+}
+
+type RespFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T, RespError>> + Send + 'a>>;
+
+impl From<std::io::Error> for RespError {
+    fn from(value: std::io::Error) -> Self {
+        Self::Io(value.to_string())
+    }
+}
+
+impl RespConnection {
+    async fn connect(secret: &CacheSecret) -> Result<Self, RespError> {
+        let addr = (secret.HOST.as_str(), secret.PORT);
+        let t = StdDuration::from_secs(OPERATION_TIMEOUT_SECS);
+        let tcp = timeout(t, TcpStream::connect(addr))
+            .await
+            .map_err(|_e| RespError::Timeout)??;
+        let mut out = Self {
+            stream: BufStream::new(tcp),
+        };
+        if let Some(p) = secret.PASSWORD.as_ref() {
+            let v = out.command(&[b"AUTH", p.as_bytes()]).await?;
+            if !matches!(v, RespValue::Simple(_)) {
+                let msg = format!("auth-unexpected-reply: {:?}", v);
+                return Err(RespError::Protocol(msg));
+            }
+        }
+        Ok(out)
+    }
+
+    async fn command(&mut self, args: &[&[u8]]) -> Result<RespValue, RespError> {
+        let t = StdDuration::from_secs(OPERATION_TIMEOUT_SECS);
+        let v = timeout(t, self._command(args))
+            .await
+            .map_err(|_e| RespError::Timeout)??;
+        if let RespValue::Error(msg) = v {
+            Err(RespError::Server(msg))
+        } else {
+            Ok(v)
+        }
+    }
+
+    async fn _command(&mut self, args: &[&[u8]]) -> Result<RespValue, RespError> {
+        // client always sends a command as an array of bulk strings
+        let mut buf = format!("*{}\r\n", args.len()).into_bytes();
+        for a in args {
+            buf.extend(format!("${}\r\n", a.len()).into_bytes());
+            buf.extend_from_slice(a);
+            buf.extend_from_slice(b"\r\n");
+        }
+        self.stream.write_all(buf.as_slice()).await?;
+        self.stream.flush().await?;
+        self.read_value().await
+    }
+
+    fn read_value(&mut self) -> RespFuture<'_, RespValue> {
+        Box::pin(async move {
+            let line = self.read_line().await?;
+            let (prefix, content) = line.split_at(1);
+            let value = match prefix {
+                "+" => RespValue::Simple(content.to_string()),
+                "-" => RespValue::Error(content.to_string()),
+                ":" => RespValue::Integer(Self::parse_int(content)?),
+                "$" => {
+                    let sz = Self::parse_int(content)?;
+                    if sz < 0 {
+                        RespValue::Bulk(None)
+                    } else {
+                        let mut data = vec![0u8; sz as usize + 2];
+                        self.stream.read_exact(data.as_mut_slice()).await?;
+                        data.truncate(sz as usize);
+                        RespValue::Bulk(Some(data))
+                    }
+                }
+                "*" => {
+                    let num = Self::parse_int(content)?;
+                    if num < 0 {
+                        RespValue::Array(None)
+                    } else {
+                        let mut items = Vec::new();
+                        for _ in 0..num {
+                            items.push(self.read_value().await?);
+                        }
+                        RespValue::Array(Some(items))
+                    }
+                }
+                _others => {
+                    let msg = format!("unknown-type: {}", line);
+                    return Err(RespError::Protocol(msg));
+                }
+            };
+            Ok(value)
+        })
+    } // end of fn read_value
+
+    async fn read_line(&mut self) -> Result<String, RespError> {
+        let mut line = String::new();
+        let nread = self.stream.read_line(&mut line).await?;
+        if nread == 0 {
+            return Err(RespError::Io("connection-closed".to_string()));
+        }
+        let line = line.trim_end_matches(['\r', '\n']);
+        if line.is_empty() {
+            Err(RespError::Protocol("empty-line".to_string()))
+        } else {
+            Ok(line.to_string())
+        }
+    }
+
+    fn parse_int(content: &str) -> Result<i64, RespError> {
+        content
+            .parse::<i64>()
+            .map_err(|e| RespError::Protocol(e.to_string()))
+    }
+} // end of impl RespConnection
+
+/// The lock is shared among multiple instances of this application through
+/// a cache server which speaks Redis protocol. Each lock is saved with a
+/// random token returned to the holder, so that a holder never removes the
+/// lock which expired and has been acquired by others.
+pub(super) struct RedisOrderSyncLockCache {
+    _secret: CacheSecret,
+    _ttl_millis: u64,
+    _conn_limit: Semaphore,
+    _idle_conns: AsyncMutex<Vec<RespConnection>>,
+    _num_issued: AtomicU64,
+    _logctx: Arc<AppLogContext>,
+}
+
+impl RedisOrderSyncLockCache {
+    pub(super) fn try_build(
+        cfg: &AppCacheServerCfg,
+        cfdntl: Arc<Box<dyn AbstractConfidentiality>>,
+        logctx: Arc<AppLogContext>,
+    ) -> Result<Self, AppCacheInitError> {
+        let serial = cfdntl
+            .try_get_payload(cfg.confidentiality_path.as_str())
+            .map_err(AppCacheInitError::ConfidentialLoad)?;
+        let _secret = serde_json::from_str::<CacheSecret>(serial.as_str())
+            .map_err(|e| AppCacheInitError::ConfidentialResolve(e.to_string()))?;
+        let max_conns = (cfg.max_conns as usize).max(1);
+        Ok(Self {
+            _secret,
+            _ttl_millis: (cfg.ttl_secs.max(1) as u64) * 1000,
+            _conn_limit: Semaphore::new(max_conns),
+            _idle_conns: AsyncMutex::new(Vec::new()),
+            _num_issued: AtomicU64::new(0),
+            _logctx: logctx,
+        })
+    }
+
+    fn lock_key(usr_id: u32, oid: &str) -> String {
+        format!("{KEY_PREFIX}:{usr_id}:{oid}")
+    }
+
+    fn issue_token(&self) -> String {
+        let seq = self._num_issued.fetch_add(1, Ordering::Relaxed);
+        let t = Local::now().timestamp_nanos_opt().unwrap_or(0);
+        format!("{:x}-{:x}-{:x}", std::process::id(), t, seq)
+    }
+
+    async fn run<F, T>(&self, op: F) -> Result<T, OrderSyncLockError>
+    where
+        F: for<'c> FnOnce(&'c mut RespConnection) -> RespFuture<'c, T> + Send,
+        T: Send,
+    {
+        let _permit = self
+            ._conn_limit
+            .acquire()
+            .await
+            .map_err(|_e| OrderSyncLockError)?;
+        let maybe_idle = self._idle_conns.lock().await.pop();
+        let result = match maybe_idle {
+            Some(c) => Ok(c),
+            None => RespConnection::connect(&self._secret).await,
+        };
+        let result = match result {
+            Ok(mut conn) => {
+                let out = op(&mut conn).await;
+                if out.is_ok() {
+                    // the connection might be in undefined state on error
+                    self._idle_conns.lock().await.push(conn);
+                }
+                out
+            }
+            Err(e) => Err(e),
+        };
+        result.map_err(|e| {
+            let logctx = &self._logctx;
+            app_log_event!(logctx, AppLogLevel::ERROR, "{:?}", e);
+            OrderSyncLockError
+        })
+    } // end of fn run
+} // end of impl RedisOrderSyncLockCache
+
+#[async_trait]
+impl AbstractOrderSyncLockCache for RedisOrderSyncLockCache {
+    async fn acquire(
+        &self,
+        usr_id: u32,
+        oid: &str,
+    ) -> Result<Option<OrderSyncLockToken>, OrderSyncLockError> {
+        let key = Self::lock_key(usr_id, oid);
+        let token = self.issue_token();
+        let ttl = self._ttl_millis.to_string();
+        let args = (key, token.clone(), ttl);
+        let success = self
+            .run(move |conn| {
+                Box::pin(async move {
+                    let (key, token, ttl) = args;
+                    let cmd: [&[u8]; 6] = [
+                        b"SET",
+                        key.as_bytes(),
+                        token.as_bytes(),
+                        b"NX",
+                        b"PX",
+                        ttl.as_bytes(),
+                    ];
+                    match conn.command(&cmd).await? {
+                        RespValue::Simple(_) => Ok(true),
+                        RespValue::Bulk(None) => Ok(false),
+                        other => Err(RespError::Protocol(format!("set-reply: {:?}", other))),
+                    }
+                })
+            })
+            .await?;
+        Ok(success.then_some(OrderSyncLockToken(token)))
+    } // end of fn acquire
+
+    async fn release(
+        &self,
+        usr_id: u32,
+        oid: &str,
+        token: OrderSyncLockToken,
+    ) -> Result<(), OrderSyncLockError> {
+        let OrderSyncLockToken(token) = token;
+        let key = Self::lock_key(usr_id, oid);
+        self.run(move |conn| {
+            Box::pin(async move {
+                // delete the key only if it still keeps the same token, the
+                // transaction is discarded if other client modifies the key
+                // after `WATCH` command
+                let _ = conn.command(&[b"WATCH", key.as_bytes()]).await?;
+                let saved = conn.command(&[b"GET", key.as_bytes()]).await?;
+                let owned = matches!(&saved, RespValue::Bulk(Some(v)) if v == token.as_bytes());
+                if owned {
+                    let _ = conn.command(&[b"MULTI"]).await?;
+                    let _ = conn.command(&[b"DEL", key.as_bytes()]).await?;
+                    let _ = conn.command(&[b"EXEC"]).await?;
+                } else {
+                    let _ = conn.command(&[b"UNWATCH"]).await?;
+                }
+                Ok(())
+            })
+        })
+        .await
+    } // end of fn release
+} // end of impl RedisOrderSyncLockCache
@@ -1,5 +1,6 @@
 mod base_client;
 mod stripe;
+mod xendit;
 
 use std::boxed::Box;
 use std::marker::{Send, Sync};
@@ -15,12 +16,13 @@ use ecommerce_common::logging::AppLogContext;
 
 pub use self::base_client::{BaseClientError, BaseClientErrorReason};
 use self::stripe::{AbstStripeContext, AppProcessorStripeCtx, MockProcessorStripeCtx};
+use self::xendit::{AbstXenditContext, AppProcessorXenditCtx, MockProcessorXenditCtx};
 use crate::api::web::dto::{
     CapturePay3partyRespDto, CapturePayRespDto, ChargeCreateRespDto, PaymentMethodErrorReason,
     PaymentMethodReqDto, PaymentMethodRespDto, StoreOnboardReqDto, StoreOnboardRespDto,
 };
 use crate::model::{
-    BuyerPayInState, Charge3partyModel, ChargeBuyerMetaModel, ChargeBuyerModel,
+    BuyerPayInState, Charge3partyModel, ChargeBuyerMetaModel, ChargeBuyerModel, Label3party,
     Merchant3partyModel, Payout3partyModel, PayoutModel, RefundReqResolutionModel,
 };
 
@@ -58,10 +60,21 @@ pub trait AbstractPaymentProcessor: Send + Sync {
         &self,
         resolve_m: RefundReqResolutionModel,
     ) -> Result<RefundReqResolutionModel, AppProcessorError>;
+
+    /// verify signature of the raw payload sent from the given 3rd party, then
+    /// convert it to event object which is independent of any 3rd party
+    fn parse_webhook_event(
+        &self,
+        label3pty: Label3party,
+        signature: &str,
+        payload: &[u8],
+    ) -> Result<AppProcessorWebhookEvent, AppProcessorError>;
 } // end of trait AbstractPaymentProcessor
 
 struct AppProcessorContext {
     _stripe: Box<dyn AbstStripeContext>,
+    // optional, requests to Xendit are refused if it is not configured
+    _xendit: Option<Box<dyn AbstXenditContext>>,
     _logctx: Arc<AppLogContext>,
 }
 
@@ -78,6 +91,8 @@ pub enum AppProcessorErrorReason {
     InvalidStoreProfileDto(Vec<String>),
     CorruptedTimeStamp(String, i64), // label and given incorrect timestamp
     AmountOverflow(String),
+    InvalidSignature(String),
+    ProcessorUnavailable(String), // label of the processor which is not configured
     ThirdParty(String),
 }
 
@@ -90,6 +105,7 @@ pub enum AppProcessorFnLabel {
     OnboardMerchant,
     RefreshOnboardStatus,
     Refund,
+    ParseWebhookEvent,
 }
 
 #[derive(Debug)]
@@ -115,6 +131,37 @@ pub struct AppProcessorPayoutResult {
     model: PayoutModel,
 }
 
+pub enum AppProcessorWebhookDetail {
+    /// client completed pay-in session, the charge is located by the buyer
+    /// ID, the order ID and the session ID from the 3rd party
+    PayInCompleted {
+        buyer_id: u32,
+        order_id: String,
+        session_id: String,
+    },
+    ChargeRefunded {
+        charge_id: String,   // serialised charge token
+        payment_ref: String, // e.g. payment-intent ID in Stripe
+    },
+    MerchantUpdated {
+        account_id: String,
+        model: Merchant3partyModel,
+    },
+    PayoutUpdated {
+        charge_id: String,
+        account_id: String,
+        transfer_id: String,
+        reversed: bool,
+    },
+    Unhandled(String), // event type not applied in this application
+}
+
+pub struct AppProcessorWebhookEvent {
+    pub id: String, // event identifier assigned by the 3rd party
+    pub label: Label3party,
+    pub detail: AppProcessorWebhookDetail,
+}
+
 impl From<AppProcessorPayInResult> for ChargeCreateRespDto {
     fn from(value: AppProcessorPayInResult) -> Self {
         let id = value
@@ -143,9 +190,9 @@ impl From<BaseClientError> for AppProcessorErrorReason {
 impl From<AppProcessorErrorReason> for PaymentMethodErrorReason {
     fn from(value: AppProcessorErrorReason) -> Self {
         match value {
-            AppProcessorErrorReason::InvalidMethod(_) | AppProcessorErrorReason::NotImplemented => {
-                Self::OperationRefuse
-            }
+            AppProcessorErrorReason::InvalidMethod(_)
+            | AppProcessorErrorReason::NotImplemented
+            | AppProcessorErrorReason::ProcessorUnavailable(_) => Self::OperationRefuse,
             _others => Self::ProcessorFailure,
         }
     } // TODO, finish implementation
@@ -185,6 +232,7 @@ impl AppProcessorContext {
     ) -> Result<Self, AppProcessorError> {
         let mut errors = Vec::new();
         let mut result_stripe = None;
+        let mut result_xendit = None;
         cfgs3pt
             .into_iter()
             .map(|c| match c.as_ref() {
@@ -205,17 +253,39 @@ impl AppProcessorContext {
                         .map_err(|e| errors.push(e))
                         .ok();
                     }
+                    if result_xendit.is_none() && name.as_str().to_lowercase() == "xendit" {
+                        result_xendit = AppProcessorXenditCtx::try_build(
+                            host.as_str(),
+                            *port,
+                            confidentiality_path.as_str(),
+                            cfdntl.clone(),
+                            _logctx.clone(),
+                        )
+                        .map_err(|e| errors.push(e))
+                        .ok();
+                    }
                 }
                 App3rdPartyCfg::test { name, data_src: _ } => {
                     if result_stripe.is_none() && name.as_str().to_lowercase() == "stripe" {
                         result_stripe = Some(MockProcessorStripeCtx::build());
                     }
+                    if result_xendit.is_none() && name.as_str().to_lowercase() == "xendit" {
+                        result_xendit = Some(MockProcessorXenditCtx::build());
+                    }
                 }
+                App3rdPartyCfg::local {
+                    name: _,
+                    data_src: _,
+                } => {}
             })
             .count();
         if errors.is_empty() {
             if let Some(_stripe) = result_stripe {
-                Ok(Self { _logctx, _stripe })
+                Ok(Self {
+                    _logctx,
+                    _stripe,
+                    _xendit: result_xendit,
+                })
             } else {
                 Err(AppProcessorError {
                     reason: AppProcessorErrorReason::InvalidConfig,
@@ -229,6 +299,14 @@ impl AppProcessorContext {
             })
         }
     } // end of fn new
+
+    fn xendit(&self) -> Result<&dyn AbstXenditContext, AppProcessorErrorReason> {
+        self._xendit
+            .as_deref()
+            .ok_or(AppProcessorErrorReason::ProcessorUnavailable(
+                Label3party::Xendit.to_string(),
+            ))
+    }
 } // end of impl AppProcessorContext
 
 #[async_trait]
@@ -240,6 +318,10 @@ impl AbstractPaymentProcessor for AppProcessorContext {
     ) -> Result<(AppProcessorPayInResult, Charge3partyModel), AppProcessorError> {
         let result = match req_mthd {
             PaymentMethodReqDto::Stripe(c) => self._stripe.pay_in_start(&c, charge_m).await,
+            PaymentMethodReqDto::Xendit(c) => match self.xendit() {
+                Ok(x) => x.pay_in_start(&c, charge_m).await,
+                Err(e) => Err(e),
+            },
         };
         result.map_err(|reason| AppProcessorError {
             reason,
@@ -257,6 +339,10 @@ impl AbstractPaymentProcessor for AppProcessorContext {
                 .pay_in_progress(c)
                 .await
                 .map(Charge3partyModel::Stripe),
+            Charge3partyModel::Xendit(c) => match self.xendit() {
+                Ok(x) => x.pay_in_progress(c).await.map(Charge3partyModel::Xendit),
+                Err(e) => Err(e),
+            },
             Charge3partyModel::Unknown => Err(AppProcessorErrorReason::InvalidMethod(
                 "unknown".to_string(),
             )),
@@ -274,6 +360,10 @@ impl AbstractPaymentProcessor for AppProcessorContext {
     ) -> Result<AppProcessorMerchantResult, AppProcessorError> {
         let result = match req_3pt {
             StoreOnboardReqDto::Stripe(req) => self._stripe.onboard_merchant(profile, req).await,
+            StoreOnboardReqDto::Xendit => match self.xendit() {
+                Ok(x) => x.onboard_merchant(profile).await,
+                Err(e) => Err(e),
+            },
         };
         result.map_err(|reason| AppProcessorError {
             reason,
@@ -290,6 +380,10 @@ impl AbstractPaymentProcessor for AppProcessorContext {
             (Merchant3partyModel::Stripe(ms), StoreOnboardReqDto::Stripe(ds)) => {
                 self._stripe.refresh_onboard_status(ms, ds).await
             }
+            (Merchant3partyModel::Xendit(mx), StoreOnboardReqDto::Xendit) => match self.xendit() {
+                Ok(x) => x.refresh_onboard_status(mx).await,
+                Err(e) => Err(e),
+            },
             _others => {
                 let msg = "given-model-dto-mismatch".to_string();
                 Err(AppProcessorErrorReason::InvalidMethod(msg))
@@ -312,6 +406,10 @@ impl AbstractPaymentProcessor for AppProcessorContext {
                 .pay_out(&p_inner, s)
                 .await
                 .map(Payout3partyModel::Stripe),
+            Payout3partyModel::Xendit(x0) => match self.xendit() {
+                Ok(x) => x.pay_out(&p_inner, x0).await.map(Payout3partyModel::Xendit),
+                Err(e) => Err(e),
+            },
         };
         result
             .map_err(|reason| AppProcessorError {
@@ -340,6 +438,11 @@ impl AbstractPaymentProcessor for AppProcessorContext {
             Charge3partyModel::Stripe(s0) =>
                 self._stripe.refund(&r_inner, s0).await
                     .map(Charge3partyModel::Stripe),
+            Charge3partyModel::Xendit(x0) => match self.xendit() {
+                Ok(x) => x.refund(&r_inner, x0).await
+                    .map(Charge3partyModel::Xendit),
+                Err(e) => Err(e),
+            },
             Charge3partyModel::Unknown =>
                 Err(AppProcessorErrorReason::InvalidMethod("unknown".to_string())),
         };
@@ -348,6 +451,24 @@ impl AbstractPaymentProcessor for AppProcessorContext {
                 reason, fn_label: AppProcessorFnLabel::Refund,
             })
     }
+
+    fn parse_webhook_event(
+        &self,
+        label3pty: Label3party,
+        signature: &str,
+        payload: &[u8],
+    ) -> Result<AppProcessorWebhookEvent, AppProcessorError> {
+        let result = match label3pty {
+            Label3party::Stripe => self._stripe.parse_webhook_event(signature, payload),
+            Label3party::Xendit => self
+                .xendit()
+                .and_then(|x| x.parse_webhook_event(signature, payload)),
+        };
+        result.map_err(|reason| AppProcessorError {
+            reason,
+            fn_label: AppProcessorFnLabel::ParseWebhookEvent,
+        })
+    }
 } // end of impl AppProcessorContext
 
 pub(crate) fn app_processor_context(
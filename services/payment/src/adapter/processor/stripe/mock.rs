@@ -19,8 +19,14 @@ use crate::model::{
     StripeSessionStatusModel,
 };
 
-use super::super::{AppProcessorErrorReason, AppProcessorMerchantResult, AppProcessorPayInResult};
-use super::AbstStripeContext;
+use super::super::{
+    AppProcessorErrorReason, AppProcessorMerchantResult, AppProcessorPayInResult,
+    AppProcessorWebhookEvent,
+};
+use super::{parse_webhook_common, AbstStripeContext};
+
+// test cases sign the webhook payload with the same secret
+const MOCK_WEBHOOK_SECRET: &str = "whsec_mock_unit_test_1a2b3c";
 
 // TODO, conditional compilation for test
 pub(crate) struct MockProcessorStripeCtx;
@@ -167,4 +173,12 @@ impl AbstStripeContext for MockProcessorStripeCtx {
     ) -> Result<Charge3partyStripeModel, AppProcessorErrorReason> {
         Ok(detail3pty)
     }
+
+    fn parse_webhook_event(
+        &self,
+        signature: &str,
+        payload: &[u8],
+    ) -> Result<AppProcessorWebhookEvent, AppProcessorErrorReason> {
+        parse_webhook_common(MOCK_WEBHOOK_SECRET, signature, payload)
+    }
 } // end of impl MockProcessorStripeCtx
@@ -12,29 +12,33 @@ use http_body_util::{Empty, Full};
 use hyper::body::Bytes;
 use hyper::header::{HeaderName, HeaderValue};
 use hyper::Method;
+use ring::hmac;
+use serde::de::DeserializeOwned;
 use tokio_native_tls::{native_tls, TlsConnector as TlsConnectorWrapper};
 
 use ecommerce_common::api::rpc::dto::StoreProfileReplicaDto;
 use ecommerce_common::confidentiality::AbstractConfidentiality;
 use ecommerce_common::logging::{app_log_event, AppLogContext, AppLogLevel};
+use ecommerce_common::util::hex_to_octet;
 
 use self::client::AppStripeClient;
 pub(super) use self::mock::MockProcessorStripeCtx;
 use self::resources::{
-    AccountLink, AccountRequirement, AccountSettings, CheckoutSession, CheckoutSessionMode,
+    AccountLink, AccountRequirement, AccountSettings, Charge, CheckoutSession, CheckoutSessionMode,
     ConnectAccount, CreateAccountLink, CreateCheckoutSession, CreateCheckoutSessionLineItem,
     CreateCheckoutSessionPaymentIntentData, CreateConnectAccount, CreateRefund, CreateTransfer,
-    RefundResult, Transfer,
+    RefundResult, Transfer, WebhookEvent,
 };
 use super::{
-    AppProcessorErrorReason, AppProcessorMerchantResult, AppProcessorPayInResult, BaseClientError,
+    AppProcessorErrorReason, AppProcessorMerchantResult, AppProcessorPayInResult,
+    AppProcessorWebhookDetail, AppProcessorWebhookEvent, BaseClientError,
 };
 use crate::api::web::dto::{
     PaymentMethodRespDto, StoreOnboardRespDto, StoreOnboardStripeReqDto,
     StripeCheckoutSessionReqDto, StripeCheckoutSessionRespDto, StripeCheckoutUImodeDto,
 };
 use crate::model::{
-    BuyerPayInState, Charge3partyModel, Charge3partyStripeModel, ChargeBuyerModel,
+    BuyerPayInState, Charge3partyModel, Charge3partyStripeModel, ChargeBuyerModel, Label3party,
     Merchant3partyModel, Merchant3partyStripeModel, Payout3partyStripeModel, PayoutInnerModel,
     RefundReqRslvInnerModel, StripeAccountLinkModel, StripeAccountSettingModel,
 };
@@ -43,6 +47,8 @@ const HEADER_NAME_IDEMPOTENCY: &str = "Idempotency-Key";
 const CHECKOUT_SESSION_MIN_SECONDS: i64 = 1800;
 const ACCOUNT_LINK_EXPIRY_MIN_DAYS: i64 = 2;
 const REFUND_INTERVAL_MIN_SECONDS: i64 = 75;
+const WEBHOOK_SECRET_PATH_SUFFIX: &str = "Webhook";
+const WEBHOOK_TOLERANCE_SECONDS: i64 = 300;
 
 #[derive(serde::Serialize)]
 struct InnerEmptyBody;
@@ -83,6 +89,12 @@ pub(super) trait AbstStripeContext: Send + Sync {
         rslv_inner: &RefundReqRslvInnerModel,
         detail3pty: Charge3partyStripeModel,
     ) -> Result<Charge3partyStripeModel, AppProcessorErrorReason>;
+
+    fn parse_webhook_event(
+        &self,
+        signature: &str,
+        payload: &[u8],
+    ) -> Result<AppProcessorWebhookEvent, AppProcessorErrorReason>;
 } // end of trait AbstStripeContext
 
 pub(super) struct AppProcessorStripeCtx {
@@ -90,6 +102,9 @@ pub(super) struct AppProcessorStripeCtx {
     port: u16,
     secure_connector: TlsConnectorWrapper,
     api_key: String,
+    // optional, the processor context still works without receiving events
+    // from Stripe, webhook requests will be rejected in such case.
+    webhook_secret: Option<String>,
     logctx: Arc<AppLogContext>,
 }
 
@@ -106,6 +121,20 @@ impl AppProcessorStripeCtx {
             .map_err(|_e| AppProcessorErrorReason::MissingCredential)?;
         let api_key = serde_json::from_str::<String>(serial.as_str())
             .map_err(|_e| AppProcessorErrorReason::CredentialCorrupted)?;
+        let webhook_secret = {
+            let path = format!("{confidential_path}{WEBHOOK_SECRET_PATH_SUFFIX}");
+            match cfdntl.try_get_payload(path.as_str()) {
+                Ok(serial) => {
+                    let v = serde_json::from_str::<String>(serial.as_str())
+                        .map_err(|_e| AppProcessorErrorReason::CredentialCorrupted)?;
+                    Some(v)
+                }
+                Err(_e) => {
+                    app_log_event!(logctx, AppLogLevel::WARNING, "missing-webhook-secret");
+                    None
+                }
+            }
+        };
         let secure_connector = {
             let mut builder = native_tls::TlsConnector::builder();
             builder.min_protocol_version(Some(native_tls::Protocol::Tlsv12));
@@ -120,6 +149,7 @@ impl AppProcessorStripeCtx {
             port,
             secure_connector,
             api_key,
+            webhook_secret,
             logctx,
         };
         Ok(Box::new(m))
@@ -189,6 +219,17 @@ impl AbstStripeContext for AppProcessorStripeCtx {
             &charge_token_serial
         );
 
+        // shipping fees are sorted by seller, keep the request body the same
+        // for idempotent retry
+        let shipping_items = charge_buyer
+            .seller_ids()
+            .into_iter()
+            .filter_map(|seller_id| {
+                let amount = *charge_buyer.shipping.get(&seller_id)?;
+                let arg = (buyer_currency.label.clone(), seller_id, amount);
+                Some(CreateCheckoutSessionLineItem::from(arg))
+            })
+            .collect::<Vec<_>>();
         let body_obj = CreateCheckoutSession {
             client_reference_id: format!(
                 "{}-{}",
@@ -205,6 +246,7 @@ impl AbstStripeContext for AppProcessorStripeCtx {
                 .lines
                 .iter()
                 .map(|v| CreateCheckoutSessionLineItem::from((buyer_currency.label.clone(), v)))
+                .chain(shipping_items)
                 .collect(),
             payment_intent_data: CreateCheckoutSessionPaymentIntentData {
                 transfer_group: Some(charge_token_serial.clone()),
@@ -425,8 +467,143 @@ impl AbstStripeContext for AppProcessorStripeCtx {
         // payment application ?
         Ok(detail3pty)
     } // end of fn refund
+
+    fn parse_webhook_event(
+        &self,
+        signature: &str,
+        payload: &[u8],
+    ) -> Result<AppProcessorWebhookEvent, AppProcessorErrorReason> {
+        let secret = self
+            .webhook_secret
+            .as_deref()
+            .ok_or(AppProcessorErrorReason::MissingCredential)?;
+        let result = parse_webhook_common(secret, signature, payload);
+        if let Err(e) = result.as_ref() {
+            let logctx = &self.logctx;
+            app_log_event!(logctx, AppLogLevel::WARNING, "webhook: {:?}", e);
+        }
+        result
+    }
 } // end of impl AppProcessorStripeCtx
 
+/// [reference]
+/// https://docs.stripe.com/webhooks#verify-manually
+fn verify_webhook_signature(
+    secret: &str,
+    signature: &str,
+    payload: &[u8],
+) -> Result<(), AppProcessorErrorReason> {
+    let mut timestamp = None;
+    let mut candidates = Vec::new();
+    signature
+        .split(',')
+        .filter_map(|kv| kv.split_once('='))
+        .map(|(k, v)| match k.trim() {
+            "t" => {
+                timestamp = v.parse::<i64>().ok();
+            }
+            "v1" => candidates.push(v),
+            _others => {} // signatures of other schemes are ignored
+        })
+        .count();
+    let ts = timestamp.ok_or(AppProcessorErrorReason::InvalidSignature(
+        "missing-timestamp".to_string(),
+    ))?;
+    let t_now = Local::now().to_utc().timestamp();
+    if (t_now - ts).abs() > WEBHOOK_TOLERANCE_SECONDS {
+        let msg = format!("timestamp-out-of-tolerance:{ts}");
+        return Err(AppProcessorErrorReason::InvalidSignature(msg));
+    }
+    let key = hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes());
+    let signed_payload = [ts.to_string().as_bytes(), b".", payload].concat();
+    let matched = candidates
+        .into_iter()
+        .filter_map(|v| hex_to_octet(v).ok())
+        .any(|sig| hmac::verify(&key, &signed_payload, &sig).is_ok());
+    if matched {
+        Ok(())
+    } else {
+        let msg = "mismatch".to_string();
+        Err(AppProcessorErrorReason::InvalidSignature(msg))
+    }
+} // end of fn verify_webhook_signature
+
+fn parse_webhook_common(
+    secret: &str,
+    signature: &str,
+    payload: &[u8],
+) -> Result<AppProcessorWebhookEvent, AppProcessorErrorReason> {
+    verify_webhook_signature(secret, signature, payload)?;
+    let event = serde_json::from_slice::<WebhookEvent>(payload)
+        .map_err(|e| AppProcessorErrorReason::ThirdParty(e.to_string()))?;
+    AppProcessorWebhookEvent::try_from(event)
+}
+
+fn parse_event_object<T: DeserializeOwned>(
+    obj: serde_json::Value,
+) -> Result<T, AppProcessorErrorReason> {
+    serde_json::from_value::<T>(obj).map_err(|e| AppProcessorErrorReason::ThirdParty(e.to_string()))
+}
+
+impl TryFrom<WebhookEvent> for AppProcessorWebhookEvent {
+    type Error = AppProcessorErrorReason;
+    fn try_from(value: WebhookEvent) -> Result<Self, Self::Error> {
+        let WebhookEvent { id, type_, data } = value;
+        let detail = match type_.as_str() {
+            "checkout.session.completed" => {
+                let session = parse_event_object::<CheckoutSession>(data.object)?;
+                // see `client_reference_id` in the request for creating checkout session
+                let ref_id = session.client_reference_id.unwrap_or_default();
+                let (buyer_id, order_id) = ref_id
+                    .split_once('-')
+                    .and_then(|(b, o)| b.parse::<u32>().ok().map(|b| (b, o.to_string())))
+                    .ok_or(AppProcessorErrorReason::ThirdParty(format!(
+                        "invalid-client-reference:{ref_id}"
+                    )))?;
+                AppProcessorWebhookDetail::PayInCompleted {
+                    buyer_id,
+                    order_id,
+                    session_id: session.id,
+                }
+            }
+            "charge.refunded" => {
+                let charge = parse_event_object::<Charge>(data.object)?;
+                match (charge.transfer_group, charge.payment_intent) {
+                    (Some(charge_id), Some(payment_ref)) => {
+                        AppProcessorWebhookDetail::ChargeRefunded {
+                            charge_id,
+                            payment_ref,
+                        }
+                    }
+                    // not created by the pay-in flow of this application
+                    _others => AppProcessorWebhookDetail::Unhandled(type_.clone()),
+                }
+            }
+            "account.updated" => {
+                let acct = parse_event_object::<ConnectAccount>(data.object)?;
+                let account_id = acct.id.clone();
+                let model = Merchant3partyModel::try_from((acct, None))?;
+                AppProcessorWebhookDetail::MerchantUpdated { account_id, model }
+            }
+            t if t.starts_with("transfer.") => {
+                let transfer = parse_event_object::<Transfer>(data.object)?;
+                AppProcessorWebhookDetail::PayoutUpdated {
+                    reversed: transfer.amount_reversed > 0,
+                    charge_id: transfer.transfer_group,
+                    account_id: transfer.destination,
+                    transfer_id: transfer.id,
+                }
+            }
+            _others => AppProcessorWebhookDetail::Unhandled(type_.clone()),
+        };
+        Ok(Self {
+            id,
+            label: Label3party::Stripe,
+            detail,
+        })
+    } // end of fn try-from
+} // end of impl AppProcessorWebhookEvent
+
 impl From<(CheckoutSession, DateTime<Utc>, String)> for Charge3partyStripeModel {
     fn from(value: (CheckoutSession, DateTime<Utc>, String)) -> Self {
         let (session, time_end, transfer_group) = value;
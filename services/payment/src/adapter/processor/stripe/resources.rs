@@ -23,6 +23,7 @@ pub(super) struct CheckoutSession {
     pub payment_status: StripeCheckoutPaymentStatusModel,
     pub payment_intent: String,
     pub expires_at: i64,
+    pub client_reference_id: Option<String>,
     // TODO, record more fields for payout at later time
 }
 
@@ -221,6 +222,29 @@ pub(super) struct Transfer {
     pub destination: String,
     pub amount: i64,
     pub transfer_group: String,
+    #[serde(default)]
+    pub amount_reversed: i64,
+}
+
+#[derive(Deserialize)]
+pub(super) struct Charge {
+    // this application only reads the fields for locating the charge model
+    // and its pay-in detail saved in the repository
+    pub payment_intent: Option<String>,
+    pub transfer_group: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub(super) struct WebhookEventData {
+    pub object: serde_json::Value,
+}
+
+#[derive(Deserialize)]
+pub(super) struct WebhookEvent {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub type_: String,
+    pub data: WebhookEventData,
 }
 
 #[allow(non_camel_case_types)]
@@ -299,6 +323,27 @@ impl From<(CurrencyDto, &ChargeLineBuyerModel)> for CreateCheckoutSessionLineIte
     }
 }
 
+// shipping fee charged by a seller, presented as individual line item
+impl From<(CurrencyDto, u32, Decimal)> for CreateCheckoutSessionLineItem {
+    fn from(value: (CurrencyDto, u32, Decimal)) -> Self {
+        let (currency_label, seller_id, amount) = value;
+        // TODO, overflow error handling
+        let amt_represent =
+            Charge3partyStripeModel::amount_represent(amount, currency_label.clone()).unwrap();
+        let price_data = CreateCheckoutSessionPriceData {
+            product_data: CreateCheckoutSessionProductData {
+                name: format!("shipping-{seller_id}"),
+            },
+            currency: currency_label,
+            unit_amount_decimal: amt_represent.to_string(),
+        };
+        Self {
+            price_data,
+            quantity: 1,
+        }
+    }
+}
+
 impl Default for ConnectAccountType {
     fn default() -> Self {
         Self::express
@@ -0,0 +1,166 @@
+use std::boxed::Box;
+use std::result::Result;
+
+use async_trait::async_trait;
+use chrono::{Duration, Local};
+
+use ecommerce_common::api::rpc::dto::StoreProfileReplicaDto;
+
+use crate::api::web::dto::XenditEwalletChargeReqDto;
+use crate::model::{
+    Charge3partyModel, Charge3partyXenditModel, ChargeBuyerModel, Merchant3partyXenditModel,
+    Payout3partyXenditModel, PayoutInnerModel, RefundReqRslvInnerModel, XenditAccountStatusModel,
+    XenditChargeStatusModel,
+};
+
+use super::super::{
+    AppProcessorErrorReason, AppProcessorMerchantResult, AppProcessorPayInResult,
+    AppProcessorWebhookEvent,
+};
+use super::resources::{
+    CreateEwalletCharge, CreateEwalletRefund, CreateSubAccount, CreateTransfer, EwalletCharge,
+    EwalletChargeActions, EwalletRefund, EwalletRefundReason, EwalletRefundStatus, SubAccount,
+    SubAccountPublicProfile, SubAccountType, Transfer, TransferStatus,
+};
+use super::{parse_webhook_common, pay_in_start_result, pay_out_result, AbstXenditContext};
+
+// test cases send the webhook request with the same callback token
+const MOCK_CALLBACK_TOKEN: &str = "xnd_mock_callback_unit_test_9z8y7x";
+
+/// The mock context produces the same resource objects the Xendit API
+/// server would respond with, then applies the same conversion as the real
+/// processor context
+// TODO, conditional compilation for test
+pub(crate) struct MockProcessorXenditCtx;
+
+impl MockProcessorXenditCtx {
+    pub(crate) fn build() -> Box<dyn AbstXenditContext> {
+        Box::new(Self)
+    }
+}
+
+#[async_trait]
+impl AbstXenditContext for MockProcessorXenditCtx {
+    async fn pay_in_start(
+        &self,
+        req: &XenditEwalletChargeReqDto,
+        charge_buyer: &ChargeBuyerModel,
+    ) -> Result<(AppProcessorPayInResult, Charge3partyModel), AppProcessorErrorReason> {
+        let body_obj = CreateEwalletCharge::try_from((req, charge_buyer))?;
+        let redirect_url = body_obj
+            .channel_properties
+            .success_redirect_url
+            .as_ref()
+            .map(|_v| "https://ewallet.mock.xendit.co/checkout/abc123".to_string());
+        let resp = EwalletCharge {
+            id: "ewc_mock-8a3e-47c1-a1f4-0c2b7d9e6f51".to_string(),
+            reference_id: body_obj.reference_id.clone(),
+            status: XenditChargeStatusModel::PENDING,
+            currency: body_obj.currency.clone(),
+            charge_amount: body_obj.amount,
+            channel_code: format!("{:?}", body_obj.channel_code),
+            actions: Some(EwalletChargeActions {
+                desktop_web_checkout_url: redirect_url.clone(),
+                mobile_web_checkout_url: redirect_url,
+                mobile_deeplink_checkout_url: None,
+            }),
+            metadata: Some(body_obj.metadata),
+        };
+        let ctime = *charge_buyer.meta.create_time();
+        let time_end = ctime + Duration::seconds(35);
+        Ok(pay_in_start_result(charge_buyer, resp, ctime, time_end))
+    }
+
+    async fn pay_in_progress(
+        &self,
+        old: &Charge3partyXenditModel,
+    ) -> Result<Charge3partyXenditModel, AppProcessorErrorReason> {
+        let new_m = Charge3partyXenditModel {
+            charge_id: old.charge_id.clone(),
+            reference_id: old.reference_id.clone(),
+            channel_code: old.channel_code.clone(),
+            currency: old.currency.clone(),
+            status: XenditChargeStatusModel::SUCCEEDED,
+            expiry: old.expiry,
+        };
+        Ok(new_m)
+    }
+
+    async fn onboard_merchant(
+        &self,
+        store_profile: StoreProfileReplicaDto,
+    ) -> Result<AppProcessorMerchantResult, AppProcessorErrorReason> {
+        let body_obj = CreateSubAccount::try_from(store_profile)?;
+        let acct = SubAccount {
+            id: "5cafeb170a2b18519b1b8768".to_string(),
+            created: Local::now().to_utc(),
+            email: body_obj.email,
+            type_: body_obj.type_,
+            public_profile: body_obj.public_profile,
+            status: XenditAccountStatusModel::INVITED,
+        };
+        Ok(AppProcessorMerchantResult::from(acct))
+    }
+
+    async fn refresh_onboard_status(
+        &self,
+        old_m3pty: Merchant3partyXenditModel,
+    ) -> Result<AppProcessorMerchantResult, AppProcessorErrorReason> {
+        // assume the merchant has completed the verification
+        let acct = SubAccount {
+            id: old_m3pty.id,
+            created: old_m3pty.created,
+            email: old_m3pty.email,
+            type_: SubAccountType::MANAGED,
+            public_profile: SubAccountPublicProfile {
+                business_name: old_m3pty.business_name,
+            },
+            status: XenditAccountStatusModel::LIVE,
+        };
+        Ok(AppProcessorMerchantResult::from(acct))
+    }
+
+    async fn pay_out(
+        &self,
+        p_inner: &PayoutInnerModel,
+        p3pty: Payout3partyXenditModel,
+    ) -> Result<Payout3partyXenditModel, AppProcessorErrorReason> {
+        let req_body = CreateTransfer::try_from((p_inner, &p3pty, "mock-platform-account"))?;
+        let transfer_obj = Transfer {
+            transfer_id: "mock-xendit-transfer-id".to_string(),
+            reference: req_body.reference.clone(),
+            source_user_id: req_body.source_user_id.clone(),
+            destination_user_id: req_body.destination_user_id.clone(),
+            status: TransferStatus::SUCCESSFUL,
+            amount: req_body.amount,
+        };
+        transfer_obj.validate(&req_body)?;
+        pay_out_result(p3pty, transfer_obj)
+    }
+
+    async fn refund(
+        &self,
+        rslv_inner: &RefundReqRslvInnerModel,
+        detail3pty: Charge3partyXenditModel,
+    ) -> Result<Charge3partyXenditModel, AppProcessorErrorReason> {
+        let req_body = CreateEwalletRefund::try_from((rslv_inner, &detail3pty))?;
+        let rfd_obj = EwalletRefund {
+            id: "ewr_mock-5d1c-4e0b-9f3a-7b6e2c8a1d04".to_string(),
+            charge_id: detail3pty.charge_id.clone(),
+            status: EwalletRefundStatus::PENDING,
+            currency: detail3pty.currency.clone(),
+            refund_amount: req_body.amount,
+            reason: EwalletRefundReason::REQUESTED_BY_CUSTOMER,
+        };
+        rfd_obj.validate(&req_body, &detail3pty)?;
+        Ok(detail3pty)
+    }
+
+    fn parse_webhook_event(
+        &self,
+        callback_token: &str,
+        payload: &[u8],
+    ) -> Result<AppProcessorWebhookEvent, AppProcessorErrorReason> {
+        parse_webhook_common(MOCK_CALLBACK_TOKEN, callback_token, payload)
+    }
+} // end of impl MockProcessorXenditCtx
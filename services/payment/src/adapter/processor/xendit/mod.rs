@@ -0,0 +1,488 @@
+mod client;
+mod mock;
+mod resources;
+
+use std::boxed::Box;
+use std::result::Result;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, DurationRound, Local, TimeDelta, Utc};
+use http_body_util::{Empty, Full};
+use hyper::body::Bytes;
+use hyper::header::{HeaderName, HeaderValue};
+use hyper::Method;
+use serde::Deserialize;
+use tokio_native_tls::{native_tls, TlsConnector as TlsConnectorWrapper};
+
+use ecommerce_common::api::rpc::dto::StoreProfileReplicaDto;
+use ecommerce_common::confidentiality::AbstractConfidentiality;
+use ecommerce_common::logging::{app_log_event, AppLogContext, AppLogLevel};
+
+use self::client::AppXenditClient;
+pub(super) use self::mock::MockProcessorXenditCtx;
+use self::resources::{
+    CreateEwalletCharge, CreateEwalletRefund, CreateSubAccount, CreateTransfer, EwalletCharge,
+    EwalletRefund, SubAccount, Transfer, WebhookEvent,
+};
+use super::{
+    AppProcessorErrorReason, AppProcessorMerchantResult, AppProcessorPayInResult,
+    AppProcessorWebhookDetail, AppProcessorWebhookEvent, BaseClientError,
+};
+use crate::api::web::dto::{
+    PaymentMethodRespDto, StoreOnboardRespDto, XenditEwalletChargeReqDto,
+    XenditEwalletChargeRespDto,
+};
+use crate::model::{
+    BuyerPayInState, Charge3partyModel, Charge3partyXenditModel, ChargeBuyerModel, Label3party,
+    Merchant3partyModel, Merchant3partyXenditModel, Payout3partyXenditModel, PayoutInnerModel,
+    RefundReqRslvInnerModel, XenditChargeStatusModel,
+};
+
+const HEADER_NAME_IDEMPOTENCY: &str = "x-idempotency-key";
+const CHARGE_EXPIRY_MIN_SECONDS: i64 = 1800;
+const REFUND_INTERVAL_MIN_SECONDS: i64 = 75;
+const WEBHOOK_SECRET_PATH_SUFFIX: &str = "Webhook";
+
+#[async_trait]
+pub(super) trait AbstXenditContext: Send + Sync {
+    async fn pay_in_start(
+        &self,
+        req: &XenditEwalletChargeReqDto,
+        meta: &ChargeBuyerModel,
+    ) -> Result<(AppProcessorPayInResult, Charge3partyModel), AppProcessorErrorReason>;
+
+    async fn pay_in_progress(
+        &self,
+        detail3pty: &Charge3partyXenditModel,
+    ) -> Result<Charge3partyXenditModel, AppProcessorErrorReason>;
+
+    async fn onboard_merchant(
+        &self,
+        store_profile: StoreProfileReplicaDto,
+    ) -> Result<AppProcessorMerchantResult, AppProcessorErrorReason>;
+
+    async fn refresh_onboard_status(
+        &self,
+        m3pty: Merchant3partyXenditModel,
+    ) -> Result<AppProcessorMerchantResult, AppProcessorErrorReason>;
+
+    async fn pay_out(
+        &self,
+        p_inner: &PayoutInnerModel,
+        p3pty: Payout3partyXenditModel,
+    ) -> Result<Payout3partyXenditModel, AppProcessorErrorReason>;
+
+    async fn refund(
+        &self,
+        rslv_inner: &RefundReqRslvInnerModel,
+        detail3pty: Charge3partyXenditModel,
+    ) -> Result<Charge3partyXenditModel, AppProcessorErrorReason>;
+
+    /// the `callback_token` is the value of header `x-callback-token` sent
+    /// along with each webhook request from Xendit
+    fn parse_webhook_event(
+        &self,
+        callback_token: &str,
+        payload: &[u8],
+    ) -> Result<AppProcessorWebhookEvent, AppProcessorErrorReason>;
+} // end of trait AbstXenditContext
+
+#[derive(Deserialize)]
+struct XenditCredential {
+    secret_key: String,
+    // the master account in xenPlatform, which receives fund from buyers
+    // then transfers to sub-accounts of merchants
+    platform_account_id: String,
+}
+
+pub(super) struct AppProcessorXenditCtx {
+    host: String,
+    port: u16,
+    secure_connector: TlsConnectorWrapper,
+    credential: XenditCredential,
+    // optional, webhook requests will be rejected if missing
+    callback_token: Option<String>,
+    logctx: Arc<AppLogContext>,
+}
+
+impl AppProcessorXenditCtx {
+    pub(super) fn try_build(
+        host: &str,
+        port: u16,
+        confidential_path: &str,
+        cfdntl: Arc<Box<dyn AbstractConfidentiality>>,
+        logctx: Arc<AppLogContext>,
+    ) -> Result<Box<dyn AbstXenditContext>, AppProcessorErrorReason> {
+        let serial = cfdntl
+            .try_get_payload(confidential_path)
+            .map_err(|_e| AppProcessorErrorReason::MissingCredential)?;
+        let credential = serde_json::from_str::<XenditCredential>(serial.as_str())
+            .map_err(|_e| AppProcessorErrorReason::CredentialCorrupted)?;
+        let callback_token = {
+            let path = format!("{confidential_path}{WEBHOOK_SECRET_PATH_SUFFIX}");
+            match cfdntl.try_get_payload(path.as_str()) {
+                Ok(serial) => {
+                    let v = serde_json::from_str::<String>(serial.as_str())
+                        .map_err(|_e| AppProcessorErrorReason::CredentialCorrupted)?;
+                    Some(v)
+                }
+                Err(_e) => {
+                    app_log_event!(logctx, AppLogLevel::WARNING, "missing-callback-token");
+                    None
+                }
+            }
+        };
+        let secure_connector = {
+            let mut builder = native_tls::TlsConnector::builder();
+            builder.min_protocol_version(Some(native_tls::Protocol::Tlsv12));
+            let c = builder
+                .build()
+                .map_err(|e| BaseClientError { reason: e.into() })
+                .map_err(AppProcessorErrorReason::from)?;
+            c.into()
+        };
+        let m = Self {
+            host: host.to_string(),
+            port,
+            secure_connector,
+            credential,
+            callback_token,
+            logctx,
+        };
+        Ok(Box::new(m))
+    } // end of fn try-build
+
+    async fn init_conn_fullbyte(
+        &self,
+    ) -> Result<AppXenditClient<Full<Bytes>>, AppProcessorErrorReason> {
+        AppXenditClient::<Full<Bytes>>::try_build(
+            self.logctx.clone(),
+            &self.secure_connector,
+            self.host.clone(),
+            self.port,
+            self.credential.secret_key.clone(),
+        )
+        .await
+        .map_err(AppProcessorErrorReason::from)
+    }
+
+    async fn init_conn_empty(
+        &self,
+    ) -> Result<AppXenditClient<Empty<Bytes>>, AppProcessorErrorReason> {
+        AppXenditClient::<Empty<Bytes>>::try_build(
+            self.logctx.clone(),
+            &self.secure_connector,
+            self.host.clone(),
+            self.port,
+            self.credential.secret_key.clone(),
+        )
+        .await
+        .map_err(AppProcessorErrorReason::from)
+    }
+
+    fn map_log_err(&self, label: &str, e: BaseClientError) -> AppProcessorErrorReason {
+        let logger = &self.logctx;
+        app_log_event!(logger, AppLogLevel::ERROR, "{label}: {:?}", &e);
+        AppProcessorErrorReason::from(e)
+    }
+} // end of impl AppProcessorXenditCtx
+
+fn idempotency_header(key: &str) -> Vec<(HeaderName, HeaderValue)> {
+    vec![(
+        HeaderName::from_static(HEADER_NAME_IDEMPOTENCY),
+        HeaderValue::from_str(key).unwrap(),
+    )]
+}
+
+#[async_trait]
+impl AbstXenditContext for AppProcessorXenditCtx {
+    async fn pay_in_start(
+        &self,
+        req: &XenditEwalletChargeReqDto,
+        charge_buyer: &ChargeBuyerModel,
+    ) -> Result<(AppProcessorPayInResult, Charge3partyModel), AppProcessorErrorReason> {
+        let body_obj = CreateEwalletCharge::try_from((req, charge_buyer))?;
+        let hdrs = idempotency_header(body_obj.reference_id.as_str());
+        let mut _client = self.init_conn_fullbyte().await?;
+        let resp = _client
+            .execute_json::<EwalletCharge, CreateEwalletCharge>(
+                "/ewallets/charges",
+                Method::POST,
+                &body_obj,
+                hdrs,
+            )
+            .await
+            .map_err(|e| self.map_log_err("new-charge", e))?;
+        resp.validate(&body_obj)?;
+        let time_now = Utc::now();
+        let time_end = time_now + Duration::seconds(CHARGE_EXPIRY_MIN_SECONDS);
+        Ok(pay_in_start_result(charge_buyer, resp, time_now, time_end))
+    } // end of fn pay_in_start
+
+    async fn pay_in_progress(
+        &self,
+        old: &Charge3partyXenditModel,
+    ) -> Result<Charge3partyXenditModel, AppProcessorErrorReason> {
+        let mut _client = self.init_conn_empty().await?;
+        let resource_path = format!("/ewallets/charges/{}", old.charge_id);
+        let resp = _client
+            .execute::<EwalletCharge>(resource_path.as_str(), Method::GET, Vec::new())
+            .await
+            .map_err(|e| self.map_log_err("refresh-charge", e))?;
+        Ok(Charge3partyXenditModel::from((resp, old.expiry)))
+    }
+
+    async fn onboard_merchant(
+        &self,
+        store_profile: StoreProfileReplicaDto,
+    ) -> Result<AppProcessorMerchantResult, AppProcessorErrorReason> {
+        let body_obj = CreateSubAccount::try_from(store_profile)?;
+        let mut _client = self.init_conn_fullbyte().await?;
+        let acct = _client
+            .execute_json::<SubAccount, CreateSubAccount>(
+                "/v2/accounts",
+                Method::POST,
+                &body_obj,
+                Vec::new(),
+            )
+            .await
+            .map_err(|e| self.map_log_err("acct-create", e))?;
+        Ok(AppProcessorMerchantResult::from(acct))
+    }
+
+    async fn refresh_onboard_status(
+        &self,
+        old_m3pty: Merchant3partyXenditModel,
+    ) -> Result<AppProcessorMerchantResult, AppProcessorErrorReason> {
+        let mut _client = self.init_conn_empty().await?;
+        let resource_path = format!("/v2/accounts/{}", old_m3pty.id);
+        let acct = _client
+            .execute::<SubAccount>(resource_path.as_str(), Method::GET, Vec::new())
+            .await
+            .map_err(|e| self.map_log_err("acct-read", e))?;
+        Ok(AppProcessorMerchantResult::from(acct))
+    }
+
+    async fn pay_out(
+        &self,
+        p_inner: &PayoutInnerModel,
+        p3pty: Payout3partyXenditModel,
+    ) -> Result<Payout3partyXenditModel, AppProcessorErrorReason> {
+        let platform_acct = self.credential.platform_account_id.as_str();
+        let req_body = CreateTransfer::try_from((p_inner, &p3pty, platform_acct))?;
+        let mut _client = self.init_conn_fullbyte().await?;
+        let transfer_obj = _client
+            .execute_json::<Transfer, CreateTransfer>(
+                "/transfers",
+                Method::POST,
+                &req_body,
+                Vec::new(),
+            )
+            .await
+            .map_err(|e| self.map_log_err("pay-out", e))?;
+        transfer_obj.validate(&req_body)?;
+        pay_out_result(p3pty, transfer_obj)
+    }
+
+    async fn refund(
+        &self,
+        rslv_inner: &RefundReqRslvInnerModel,
+        detail3pty: Charge3partyXenditModel,
+    ) -> Result<Charge3partyXenditModel, AppProcessorErrorReason> {
+        let merchant_id = rslv_inner
+            .merchant_id()
+            .map_err(|_e| AppProcessorErrorReason::MissingMerchant)?;
+        let idempotency_key = {
+            let t_now = Local::now().to_utc();
+            let digits = TimeDelta::seconds(REFUND_INTERVAL_MIN_SECONDS);
+            let req_time = t_now.duration_trunc(digits).map_err(|e| {
+                AppProcessorErrorReason::CorruptedTimeStamp(e.to_string(), t_now.timestamp())
+            })?;
+            format!(
+                "{}-{}-{}",
+                detail3pty.reference_id,
+                merchant_id,
+                req_time.timestamp()
+            )
+        };
+        let req_body = CreateEwalletRefund::try_from((rslv_inner, &detail3pty))?;
+        let resource_path = format!("/ewallets/charges/{}/refunds", detail3pty.charge_id);
+        let mut _client = self.init_conn_fullbyte().await?;
+        let rfd_obj = _client
+            .execute_json::<EwalletRefund, CreateEwalletRefund>(
+                resource_path.as_str(),
+                Method::POST,
+                &req_body,
+                idempotency_header(idempotency_key.as_str()),
+            )
+            .await
+            .map_err(|e| self.map_log_err("refund", e))?;
+        rfd_obj.validate(&req_body, &detail3pty)?;
+        Ok(detail3pty)
+    } // end of fn refund
+
+    fn parse_webhook_event(
+        &self,
+        callback_token: &str,
+        payload: &[u8],
+    ) -> Result<AppProcessorWebhookEvent, AppProcessorErrorReason> {
+        let expect = self
+            .callback_token
+            .as_deref()
+            .ok_or(AppProcessorErrorReason::MissingCredential)?;
+        let result = parse_webhook_common(expect, callback_token, payload);
+        if let Err(e) = result.as_ref() {
+            let logctx = &self.logctx;
+            app_log_event!(logctx, AppLogLevel::WARNING, "webhook: {:?}", e);
+        }
+        result
+    }
+} // end of impl AppProcessorXenditCtx
+
+fn pay_in_start_result(
+    charge_buyer: &ChargeBuyerModel,
+    resp: EwalletCharge,
+    time_now: DateTime<Utc>,
+    time_end: DateTime<Utc>,
+) -> (AppProcessorPayInResult, Charge3partyModel) {
+    let actions = resp.actions.as_ref();
+    let mthd_detail = XenditEwalletChargeRespDto {
+        id: resp.id.clone(),
+        desktop_url: actions.and_then(|a| a.desktop_web_checkout_url.clone()),
+        mobile_url: actions.and_then(|a| a.mobile_web_checkout_url.clone()),
+        deeplink_url: actions.and_then(|a| a.mobile_deeplink_checkout_url.clone()),
+    };
+    let result = AppProcessorPayInResult {
+        charge_id: charge_buyer.meta.token().0.to_vec(),
+        method: PaymentMethodRespDto::Xendit(mthd_detail),
+        state: BuyerPayInState::ProcessorAccepted(time_now),
+        completed: false,
+    };
+    let mthd_3pty = Charge3partyXenditModel::from((resp, time_end));
+    (result, Charge3partyModel::Xendit(mthd_3pty))
+}
+
+fn pay_out_result(
+    mut p3pty: Payout3partyXenditModel,
+    transfer_obj: Transfer,
+) -> Result<Payout3partyXenditModel, AppProcessorErrorReason> {
+    let finalized_amt = resources::amount_from_f64(transfer_obj.amount)?;
+    p3pty.set_amount(finalized_amt);
+    p3pty.set_transfer_id(transfer_obj.transfer_id);
+    Ok(p3pty)
+}
+
+/// compare the tokens without returning early on the first mismatched byte,
+/// so the response time does not reveal how much of the token is correct
+fn verify_callback_token(expect: &str, actual: &str) -> Result<(), AppProcessorErrorReason> {
+    let (e, a) = (expect.as_bytes(), actual.as_bytes());
+    let diff = e
+        .iter()
+        .zip(a.iter())
+        .fold(e.len() ^ a.len(), |acc, (x, y)| acc | (x ^ y) as usize);
+    if diff == 0 {
+        Ok(())
+    } else {
+        let msg = "mismatch".to_string();
+        Err(AppProcessorErrorReason::InvalidSignature(msg))
+    }
+}
+
+fn parse_webhook_common(
+    expect_token: &str,
+    callback_token: &str,
+    payload: &[u8],
+) -> Result<AppProcessorWebhookEvent, AppProcessorErrorReason> {
+    verify_callback_token(expect_token, callback_token)?;
+    let event = serde_json::from_slice::<WebhookEvent>(payload)
+        .map_err(|e| AppProcessorErrorReason::ThirdParty(e.to_string()))?;
+    AppProcessorWebhookEvent::try_from(event)
+}
+
+impl TryFrom<WebhookEvent> for AppProcessorWebhookEvent {
+    type Error = AppProcessorErrorReason;
+    fn try_from(value: WebhookEvent) -> Result<Self, Self::Error> {
+        // Xendit does not assign identifier to each callback, the event
+        // type with the resource ID is unique since a charge object is
+        // captured at most once
+        let WebhookEvent {
+            event,
+            created,
+            data,
+        } = value;
+        let (id, detail) = match event.as_str() {
+            "ewallet.capture" => {
+                let charge = serde_json::from_value::<EwalletCharge>(data)
+                    .map_err(|e| AppProcessorErrorReason::ThirdParty(e.to_string()))?;
+                let id = format!("{}-{}", event.as_str(), charge.id.as_str());
+                let detail = if matches!(charge.status, XenditChargeStatusModel::PENDING) {
+                    AppProcessorWebhookDetail::Unhandled(event.clone())
+                } else {
+                    // see the metadata in the request for creating e-wallet charge
+                    let (buyer_id, order_id) = charge
+                        .metadata
+                        .and_then(|m| m.buyer_id.parse::<u32>().ok().map(|b| (b, m.order_id)))
+                        .ok_or(AppProcessorErrorReason::ThirdParty(format!(
+                            "invalid-metadata:{}",
+                            charge.id.as_str()
+                        )))?;
+                    AppProcessorWebhookDetail::PayInCompleted {
+                        buyer_id,
+                        order_id,
+                        session_id: charge.id,
+                    }
+                };
+                (id, detail)
+            }
+            _others => {
+                let id = format!("{}-{}", event.as_str(), created.as_str());
+                (id, AppProcessorWebhookDetail::Unhandled(event.clone()))
+            }
+        };
+        Ok(Self {
+            id,
+            label: Label3party::Xendit,
+            detail,
+        })
+    } // end of fn try-from
+} // end of impl AppProcessorWebhookEvent
+
+impl From<(EwalletCharge, DateTime<Utc>)> for Charge3partyXenditModel {
+    fn from(value: (EwalletCharge, DateTime<Utc>)) -> Self {
+        let (charge, expiry) = value;
+        Self {
+            charge_id: charge.id,
+            reference_id: charge.reference_id,
+            channel_code: charge.channel_code,
+            currency: charge.currency,
+            status: charge.status,
+            expiry,
+        }
+    }
+}
+
+impl From<SubAccount> for Merchant3partyXenditModel {
+    fn from(value: SubAccount) -> Self {
+        Self {
+            id: value.id,
+            email: value.email,
+            business_name: value.public_profile.business_name,
+            status: value.status,
+            created: value.created,
+        }
+    }
+}
+
+impl From<SubAccount> for AppProcessorMerchantResult {
+    fn from(value: SubAccount) -> Self {
+        let m = Merchant3partyXenditModel::from(value);
+        let d = StoreOnboardRespDto::Xendit {
+            status: format!("{:?}", m.status),
+        };
+        Self {
+            dto: d,
+            model: Merchant3partyModel::Xendit(m),
+        }
+    }
+}
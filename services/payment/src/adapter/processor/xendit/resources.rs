@@ -0,0 +1,416 @@
+use std::result::Result;
+
+use chrono::{DateTime, Utc};
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use ecommerce_common::api::dto::CurrencyDto;
+use ecommerce_common::api::rpc::dto::StoreProfileReplicaDto;
+
+use super::AppProcessorErrorReason;
+use crate::api::web::dto::{XenditEwalletChannelDto, XenditEwalletChargeReqDto};
+use crate::model::{
+    Charge3partyXenditModel, ChargeBuyerModel, Payout3partyXenditModel, PayoutInnerModel,
+    RefundReqRslvInnerModel, XenditAccountStatusModel, XenditChargeStatusModel,
+};
+
+#[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+#[derive(Serialize)]
+pub(super) enum EwalletCheckoutMethod {
+    ONE_TIME_PAYMENT,
+    // this application does not support tokenized payment with linked account
+}
+
+#[derive(Serialize, Default)]
+pub(super) struct EwalletChannelProperties {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub success_redirect_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub failure_redirect_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mobile_number: Option<String>,
+}
+
+// Xendit accepts only string values in the metadata object
+#[derive(Serialize, Deserialize)]
+pub(super) struct EwalletChargeMetadata {
+    pub buyer_id: String,
+    pub order_id: String,
+}
+
+#[derive(Serialize)]
+pub(super) struct CreateEwalletCharge {
+    pub reference_id: String,
+    pub currency: CurrencyDto,
+    pub amount: f64,
+    pub checkout_method: EwalletCheckoutMethod,
+    pub channel_code: XenditEwalletChannelDto,
+    pub channel_properties: EwalletChannelProperties,
+    pub metadata: EwalletChargeMetadata,
+}
+
+#[derive(Serialize, Deserialize)]
+pub(super) struct EwalletChargeActions {
+    pub desktop_web_checkout_url: Option<String>,
+    pub mobile_web_checkout_url: Option<String>,
+    pub mobile_deeplink_checkout_url: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub(super) struct EwalletCharge {
+    pub id: String,
+    pub reference_id: String,
+    pub status: XenditChargeStatusModel,
+    pub currency: CurrencyDto,
+    pub charge_amount: f64,
+    pub channel_code: String,
+    pub actions: Option<EwalletChargeActions>,
+    pub metadata: Option<EwalletChargeMetadata>,
+}
+
+#[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+#[derive(Serialize, Deserialize, Debug)]
+pub(super) enum EwalletRefundReason {
+    REQUESTED_BY_CUSTOMER,
+    FRAUDULENT,
+    DUPLICATE,
+    CANCELLATION,
+    OTHERS,
+}
+
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Serialize, Deserialize, Debug)]
+pub(super) enum EwalletRefundStatus {
+    PENDING,
+    SUCCEEDED,
+    FAILED,
+}
+
+#[derive(Serialize)]
+pub(super) struct CreateEwalletRefund {
+    pub amount: f64,
+    pub reason: EwalletRefundReason,
+}
+
+#[derive(Serialize, Deserialize)]
+pub(super) struct EwalletRefund {
+    pub id: String,
+    pub charge_id: String,
+    pub status: EwalletRefundStatus,
+    pub currency: CurrencyDto,
+    pub refund_amount: f64,
+    #[allow(dead_code)]
+    pub reason: EwalletRefundReason,
+}
+
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Serialize, Deserialize)]
+pub(super) enum SubAccountType {
+    // Xendit handles the verification for the merchants, this application
+    // does not need to collect their documents
+    MANAGED,
+}
+
+#[derive(Serialize, Deserialize)]
+pub(super) struct SubAccountPublicProfile {
+    pub business_name: String,
+}
+
+#[derive(Serialize)]
+pub(super) struct CreateSubAccount {
+    pub email: String,
+    #[serde(rename = "type")]
+    pub type_: SubAccountType,
+    pub public_profile: SubAccountPublicProfile,
+}
+
+#[derive(Serialize, Deserialize)]
+pub(super) struct SubAccount {
+    pub id: String,
+    pub created: DateTime<Utc>,
+    pub email: String,
+    #[allow(dead_code)]
+    #[serde(rename = "type")]
+    pub type_: SubAccountType,
+    pub public_profile: SubAccountPublicProfile,
+    pub status: XenditAccountStatusModel,
+}
+
+/// [reference]
+/// transfer between balances of accounts in xenPlatform
+/// https://developers.xendit.co/api-reference/#transfers
+#[derive(Serialize)]
+pub(super) struct CreateTransfer {
+    // Xendit rejects the request if the reference has been used in previous
+    // transfer, which prevents duplicate payout to the same merchant
+    pub reference: String,
+    pub amount: f64,
+    pub source_user_id: String,
+    pub destination_user_id: String,
+}
+
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Serialize, Deserialize, Debug)]
+pub(super) enum TransferStatus {
+    SUCCESSFUL,
+    PENDING,
+    FAILED,
+}
+
+#[derive(Serialize, Deserialize)]
+pub(super) struct Transfer {
+    pub transfer_id: String,
+    pub reference: String,
+    pub source_user_id: String,
+    pub destination_user_id: String,
+    pub status: TransferStatus,
+    pub amount: f64,
+}
+
+#[derive(Deserialize)]
+pub(super) struct WebhookEvent {
+    pub event: String,
+    pub created: String,
+    pub data: serde_json::Value,
+}
+
+fn amount_to_f64(value: Decimal) -> Result<f64, AppProcessorErrorReason> {
+    value
+        .to_f64()
+        .ok_or(AppProcessorErrorReason::AmountOverflow(value.to_string()))
+}
+
+/// all currencies supported in Xendit have at most 2 digits in fraction part
+pub(super) fn amount_from_f64(value: f64) -> Result<Decimal, AppProcessorErrorReason> {
+    Decimal::from_f64(value)
+        .map(|d| d.round_dp(2))
+        .ok_or(AppProcessorErrorReason::AmountOverflow(value.to_string()))
+}
+
+impl<'a, 'b> TryFrom<(&'a XenditEwalletChargeReqDto, &'b ChargeBuyerModel)>
+    for CreateEwalletCharge
+{
+    type Error = AppProcessorErrorReason;
+    fn try_from(
+        value: (&'a XenditEwalletChargeReqDto, &'b ChargeBuyerModel),
+    ) -> Result<Self, Self::Error> {
+        let (req, charge_buyer) = value;
+        let channel = &req.channel_code;
+        if channel.redirect_required() && req.success_redirect_url.is_none() {
+            let msg = "missing-success-redirect-url".to_string();
+            return Err(AppProcessorErrorReason::InvalidMethod(msg));
+        }
+        if !channel.redirect_required() && req.mobile_number.is_none() {
+            let msg = "missing-mobile-number".to_string();
+            return Err(AppProcessorErrorReason::InvalidMethod(msg));
+        }
+        let buyer_currency =
+            charge_buyer
+                .get_buyer_currency()
+                .ok_or(AppProcessorErrorReason::MissingCurrency(
+                    charge_buyer.meta.owner(),
+                ))?;
+        if buyer_currency.label != channel.currency() {
+            let msg = format!(
+                "currency-mismatch, channel:{:?}, buyer:{}",
+                channel,
+                buyer_currency.label.as_str()
+            );
+            return Err(AppProcessorErrorReason::InvalidMethod(msg));
+        }
+        let amt_orig = charge_buyer.total_amount();
+        let amt_final = Charge3partyXenditModel::amount_represent(amt_orig, &buyer_currency.label)
+            .map_err(AppProcessorErrorReason::InvalidMethod)?;
+        let channel_properties = EwalletChannelProperties {
+            success_redirect_url: req.success_redirect_url.clone(),
+            failure_redirect_url: req.failure_redirect_url.clone(),
+            mobile_number: req.mobile_number.clone(),
+        };
+        let metadata = EwalletChargeMetadata {
+            buyer_id: charge_buyer.meta.owner().to_string(),
+            order_id: charge_buyer.meta.oid().clone(),
+        };
+        Ok(Self {
+            reference_id: charge_buyer.meta.token().to_string(),
+            currency: buyer_currency.label,
+            amount: amount_to_f64(amt_final)?,
+            checkout_method: EwalletCheckoutMethod::ONE_TIME_PAYMENT,
+            channel_code: channel.clone(),
+            channel_properties,
+            metadata,
+        })
+    } // end of fn try-from
+} // end of impl CreateEwalletCharge
+
+impl EwalletCharge {
+    pub(super) fn validate(
+        &self,
+        req: &CreateEwalletCharge,
+    ) -> Result<(), AppProcessorErrorReason> {
+        let result = if self.id.is_empty() {
+            Err("missing-charge-id".to_string())
+        } else if self.reference_id != req.reference_id {
+            Err(format!(
+                "corrupted-reference:{}",
+                self.reference_id.as_str()
+            ))
+        } else if self.currency != req.currency {
+            Err(format!("currency-mismatch:{}", self.currency.as_str()))
+        } else if amount_from_f64(self.charge_amount)? != amount_from_f64(req.amount)? {
+            Err(format!("amount-mismatch:{}", self.charge_amount))
+        } else {
+            Ok(())
+        };
+        result.map_err(AppProcessorErrorReason::ThirdParty)
+    }
+}
+
+impl TryFrom<StoreProfileReplicaDto> for CreateSubAccount {
+    type Error = AppProcessorErrorReason;
+    fn try_from(value: StoreProfileReplicaDto) -> Result<Self, Self::Error> {
+        let mut err_detail: Vec<&str> = Vec::new();
+        let StoreProfileReplicaDto {
+            label,
+            active,
+            supervisor_id: _,
+            emails,
+            phones: _,
+            location: _,
+            staff: _,
+        } = value;
+        if !active {
+            err_detail.push("not-active");
+        }
+        if label.is_empty() {
+            err_detail.push("label-empty");
+        }
+        let email = emails.and_then(|mut v| {
+            if v.is_empty() {
+                None
+            } else {
+                Some(v.remove(0).addr)
+            }
+        });
+        if email.is_none() {
+            err_detail.push("missing-email");
+        }
+        if err_detail.is_empty() {
+            Ok(Self {
+                email: email.unwrap(),
+                type_: SubAccountType::MANAGED,
+                public_profile: SubAccountPublicProfile {
+                    business_name: label,
+                },
+            })
+        } else {
+            let e = err_detail
+                .into_iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>();
+            Err(AppProcessorErrorReason::InvalidStoreProfileDto(e))
+        }
+    } // end of fn try-from
+} // end of impl CreateSubAccount
+
+type CreateTransferCvtArgs<'a, 'b, 'c> =
+    (&'a PayoutInnerModel, &'b Payout3partyXenditModel, &'c str);
+
+impl<'a, 'b, 'c> TryFrom<CreateTransferCvtArgs<'a, 'b, 'c>> for CreateTransfer {
+    type Error = AppProcessorErrorReason;
+    fn try_from(value: CreateTransferCvtArgs<'a, 'b, 'c>) -> Result<Self, Self::Error> {
+        let (pm, p3pt, platform_acct_id) = value;
+        if p3pt.sub_account().is_empty() {
+            let msg = "missing-sub-account".to_string();
+            return Err(AppProcessorErrorReason::InvalidMethod(msg));
+        }
+        // the fund from buyer is kept in the currency buyer chose
+        let amt_orig = pm.amount_buyer();
+        let amt_final = Payout3partyXenditModel::amount_represent(amt_orig, p3pt.currency());
+        Ok(Self {
+            reference: format!("{}-{}", p3pt.reference(), pm.merchant_id()),
+            amount: amount_to_f64(amt_final)?,
+            source_user_id: platform_acct_id.to_string(),
+            destination_user_id: p3pt.sub_account().to_string(),
+        })
+    }
+} // end of impl CreateTransfer
+
+impl Transfer {
+    pub(super) fn validate(&self, req: &CreateTransfer) -> Result<(), AppProcessorErrorReason> {
+        let result = if self.transfer_id.is_empty() {
+            Err("missing-transfer-id".to_string())
+        } else if self.source_user_id != req.source_user_id {
+            Err(format!("src-account-mismatch:{}", self.source_user_id))
+        } else if self.destination_user_id != req.destination_user_id {
+            Err(format!(
+                "dst-account-mismatch, expect:{}, actual:{}",
+                req.destination_user_id, self.destination_user_id
+            ))
+        } else if self.reference != req.reference {
+            Err(format!(
+                "reference-mismatch, expect:{}, actual:{}",
+                req.reference, self.reference
+            ))
+        } else if amount_from_f64(self.amount)? != amount_from_f64(req.amount)? {
+            Err(format!(
+                "amount-mismatch, expect:{}, actual:{}",
+                req.amount, self.amount
+            ))
+        } else if matches!(self.status, TransferStatus::FAILED) {
+            Err(format!("transfer-status:{:?}", self.status))
+        } else {
+            Ok(())
+        };
+        result.map_err(AppProcessorErrorReason::ThirdParty)
+    }
+} // end of impl Transfer
+
+type CreateRefundCvtArgs<'a, 'b> = (&'a RefundReqRslvInnerModel, &'b Charge3partyXenditModel);
+
+impl<'a, 'b> TryFrom<CreateRefundCvtArgs<'a, 'b>> for CreateEwalletRefund {
+    type Error = AppProcessorErrorReason;
+    fn try_from(value: CreateRefundCvtArgs<'a, 'b>) -> Result<Self, Self::Error> {
+        let (rslv_inner, chrg_3pty) = value;
+        if chrg_3pty.charge_id.is_empty() {
+            let msg = "missing-charge-id".to_string();
+            return Err(AppProcessorErrorReason::ThirdParty(msg));
+        }
+        let currency_buyer = &rslv_inner.currency()[0].label;
+        if currency_buyer != &chrg_3pty.currency {
+            let msg = format!("currency-mismatch:{}", currency_buyer.as_str());
+            return Err(AppProcessorErrorReason::InvalidMethod(msg));
+        }
+        let amt_orig = rslv_inner.total_amount_curr_round();
+        let amt_final = Charge3partyXenditModel::amount_truncate(amt_orig, currency_buyer);
+        Ok(Self {
+            amount: amount_to_f64(amt_final)?,
+            reason: EwalletRefundReason::REQUESTED_BY_CUSTOMER,
+        })
+    }
+} // end of impl CreateEwalletRefund
+
+impl EwalletRefund {
+    pub(super) fn validate(
+        &self,
+        req: &CreateEwalletRefund,
+        chrg_3pty: &Charge3partyXenditModel,
+    ) -> Result<(), AppProcessorErrorReason> {
+        // e-wallet refund could stay in `pending` state for a while, the
+        // request is considered accepted unless Xendit reports failure
+        let result = if self.id.is_empty() {
+            Err("missing-refund-id".to_string())
+        } else if self.charge_id != chrg_3pty.charge_id {
+            Err(format!("corrupted-charge-id:{}", self.charge_id.as_str()))
+        } else if self.currency != chrg_3pty.currency {
+            Err(format!("currency-mismatch:{}", self.currency.as_str()))
+        } else if amount_from_f64(self.refund_amount)? != amount_from_f64(req.amount)? {
+            Err(format!("amount-mismatch:{}", self.refund_amount))
+        } else if matches!(self.status, EwalletRefundStatus::FAILED) {
+            Err(format!("refund-status:{:?}", self.status))
+        } else {
+            Ok(())
+        };
+        result.map_err(AppProcessorErrorReason::ThirdParty)
+    }
+} // end of impl EwalletRefund
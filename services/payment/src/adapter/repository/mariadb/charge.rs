@@ -17,21 +17,25 @@ use ecommerce_common::model::order::BillingModel;
 use crate::adapter::datastore::{AppDStoreMariaDB, AppDataStoreContext};
 use crate::model::{
     ChargeBuyerMetaModel, ChargeBuyerModel, ChargeLineBuyerModel, ChargeRefundMap, Label3party,
-    OrderCurrencySnapshot, OrderLineModel, OrderLineModelSet, PayoutAmountModel, PayoutModel,
+    OrderCurrencySnapshot, OrderLineModel, OrderLineModelSet, OrderShippingModel,
+    PayoutAmountModel, PayoutModel, PayoutTransferStateModel,
 };
 
 use super::super::{AbstractChargeRepo, AppRepoError, AppRepoErrorDetail, AppRepoErrorFnLabel};
 use super::charge_converter::{
-    ChargeIdRowType, ChargeLineRowType, ChargeMetaRowType, FetchChargeIDsArgs, FetchChargeLineArgs,
-    FetchChargeMetaArgs, InsertChargeArgs, UpdateChargeLineRefundArgs, UpdateChargeMetaArgs,
+    ChargeIdRowType, ChargeLineRowType, ChargeMetaRowType, ChargeShippingRowType,
+    DeleteOrderSyncPendingArgs, FetchChargeIDsArgs, FetchChargeLineArgs, FetchChargeMetaArgs,
+    FetchChargeShippingArgs, FetchOrderSyncPendingArgs, InsertChargeArgs, OrderSyncPendingRowType,
+    SaveOrderSyncPendingArgs, SaveRefundConfirmedArgs, UpdateChargeLineRefundArgs,
+    UpdateChargeMetaArgs,
 };
 use super::order_replica::{
     FetchCurrencySnapshotArgs, FetchUnpaidOlineArgs, InsertOrderReplicaArgs, OrderCurrencyRowType,
-    OrderlineRowType,
+    OrderShippingRowType, OrderlineRowType,
 };
 use super::payout::{
     FetchPayout3partyArgs, FetchPayoutMetaArgs, InsertPayout3partyArgs, InsertPayoutMetaArgs,
-    PayoutMetaRowType,
+    PayoutMetaRowType, SavePayoutTransferStateArgs,
 };
 use super::raw_column_to_datetime;
 
@@ -167,6 +171,31 @@ impl MariadbChargeRepo {
     ) -> AppRepoError {
         self._map_log_err_common((code, detail), AppRepoErrorFnLabel::UpdateChargeProgress)
     }
+
+    async fn _run_stmt(
+        &self,
+        stmt: String,
+        params: Params,
+        fn_label: AppRepoErrorFnLabel,
+    ) -> Result<(), AppRepoError> {
+        let mut conn = match self._dstore.acquire().await {
+            Ok(c) => c,
+            Err(e) => {
+                let code = AppErrorCode::DatabaseServerBusy;
+                let detail = AppRepoErrorDetail::DataStore(e);
+                return Err(self._map_log_err_common((code, detail), fn_label));
+            }
+        };
+        stmt.with(params)
+            .run::<&mut Conn>(&mut conn)
+            .await
+            .map(|_result| ())
+            .map_err(|e| {
+                let code = AppErrorCode::RemoteDbServerFailure;
+                let detail = AppRepoErrorDetail::DatabaseExec(e.to_string());
+                self._map_log_err_common((code, detail), fn_label)
+            })
+    }
 } // end of impl MariadbChargeRepo
 
 #[async_trait]
@@ -249,6 +278,18 @@ impl AbstractChargeRepo for MariadbChargeRepo {
                 let oline = OrderLineModel::try_from(row)?;
                 v.lines.push(oline);
             }
+            drop(line_stream);
+            // --- shipping fees ---
+            let (stmt, param) = args_iter.next().unwrap();
+            let rows = stmt.with(param)
+                .fetch::<OrderShippingRowType, &mut Conn>(exec)
+                .await
+                .map_err(|e| {
+                    self._map_err_get_unpaid_olines(AppRepoErrorDetail::DatabaseQuery(
+                        e.to_string(),
+                    ))
+                })?;
+            v.shipping = rows.into_iter().map(OrderShippingModel::from).collect();
         }
         Ok(toplvl_result)
     } // end of fn get-unpaid-olines
@@ -383,6 +424,56 @@ impl AbstractChargeRepo for MariadbChargeRepo {
         }
     } // end of fn update_charge_progress
 
+    async fn update_charge_progress_sync_pending(
+        &self,
+        meta: ChargeBuyerMetaModel,
+        next_retry: DateTime<Utc>,
+    ) -> Result<(), AppRepoError> {
+        let (owner, ctime) = (meta.owner(), *meta.create_time());
+        let (stmt_meta, params_meta) = UpdateChargeMetaArgs::try_from(meta)
+            .map_err(|(code, detail)| self._map_err_update_charge_progress(code, detail))?
+            .into_parts();
+        let (stmt_pend, params_pend) =
+            SaveOrderSyncPendingArgs::from((owner, ctime, next_retry)).into_parts();
+        let mut conn = self._dstore.acquire().await.map_err(|e| {
+            let code = AppErrorCode::DatabaseServerBusy;
+            let detail = AppRepoErrorDetail::DataStore(e);
+            self._map_err_update_charge_progress(code, detail)
+        })?;
+        let mut options = TxOpts::new();
+        options.with_isolation_level(IsolationLevel::RepeatableRead);
+        let mut tx = conn.start_transaction(options).await.map_err(|e| {
+            let code = AppErrorCode::RemoteDbServerFailure;
+            let detail = AppRepoErrorDetail::DatabaseTxStart(e.to_string());
+            self._map_err_update_charge_progress(code, detail)
+        })?;
+        let num_affected = tx
+            .exec_iter(stmt_meta, params_meta)
+            .await
+            .map(|resultset| resultset.affected_rows())
+            .map_err(|e| {
+                let code = AppErrorCode::RemoteDbServerFailure;
+                let detail = AppRepoErrorDetail::DatabaseExec(e.to_string());
+                self._map_err_update_charge_progress(code, detail)
+            })?;
+        if num_affected != 1u64 {
+            let code = AppErrorCode::Unknown;
+            let msg = format!("num-affected-rows : {num_affected}");
+            let detail = AppRepoErrorDetail::DatabaseExec(msg);
+            return Err(self._map_err_update_charge_progress(code, detail));
+        }
+        tx.exec_drop(stmt_pend, params_pend).await.map_err(|e| {
+            let code = AppErrorCode::RemoteDbServerFailure;
+            let detail = AppRepoErrorDetail::DatabaseExec(e.to_string());
+            self._map_err_update_charge_progress(code, detail)
+        })?;
+        tx.commit().await.map_err(|e| {
+            let code = AppErrorCode::RemoteDbServerFailure;
+            let detail = AppRepoErrorDetail::DatabaseTxCommit(e.to_string());
+            self._map_err_update_charge_progress(code, detail)
+        })
+    } // end of fn update_charge_progress_sync_pending
+
     async fn update_lines_refund(&self, cl_map: ChargeRefundMap) -> Result<(), AppRepoError> {
         let mut conn = self._dstore.acquire().await.map_err(|e| {
             let code = AppErrorCode::DatabaseServerBusy;
@@ -444,6 +535,23 @@ impl AbstractChargeRepo for MariadbChargeRepo {
                 self._map_log_err_common(reason, AppRepoErrorFnLabel::FetchChargeByMerchant)
             })?;
 
+        let shipping = {
+            let create_time = create_time.trunc_subsecs(0);
+            let (stmt, params) =
+                FetchChargeShippingArgs::from((buyer_id, create_time, store_id)).into_parts();
+            stmt.with(params)
+                .fetch::<ChargeShippingRowType, &mut Conn>(&mut conn)
+                .await
+                .map_err(|e| {
+                    let code = AppErrorCode::RemoteDbServerFailure;
+                    let detail = AppRepoErrorDetail::DatabaseQuery(e.to_string());
+                    let label = AppRepoErrorFnLabel::FetchChargeByMerchant;
+                    self._map_log_err_common((code, detail), label)
+                })?
+                .into_iter()
+                .collect::<HashMap<_, _>>()
+        };
+
         let currency_snapshot = {
             let oid_ref = meta.oid().as_str();
             let args = (oid_ref, Some([buyer_id, store_id]));
@@ -466,6 +574,7 @@ impl AbstractChargeRepo for MariadbChargeRepo {
             meta,
             lines,
             currency_snapshot,
+            shipping,
         }))
     } // end of fn fetch_charge_by_merchant
 
@@ -645,4 +754,81 @@ impl AbstractChargeRepo for MariadbChargeRepo {
             self._map_log_err_common((code, detail), AppRepoErrorFnLabel::FetchChargeByMerchant)
         })
     } // end of fn create-payout
+
+    async fn save_order_sync_pending(
+        &self,
+        buyer_id: u32,
+        charge_ctime: DateTime<Utc>,
+        next_retry: DateTime<Utc>,
+    ) -> Result<(), AppRepoError> {
+        let fn_label = AppRepoErrorFnLabel::SaveOrderSyncPending;
+        let (stmt, params) =
+            SaveOrderSyncPendingArgs::from((buyer_id, charge_ctime, next_retry)).into_parts();
+        self._run_stmt(stmt, params, fn_label).await
+    }
+
+    async fn fetch_order_sync_pending(
+        &self,
+        t_due: DateTime<Utc>,
+        limit: u16,
+    ) -> Result<Vec<(u32, DateTime<Utc>, u16)>, AppRepoError> {
+        let mut conn = self._dstore.acquire().await.map_err(|e| {
+            let code = AppErrorCode::DatabaseServerBusy;
+            let detail = AppRepoErrorDetail::DataStore(e);
+            self._map_log_err_common((code, detail), AppRepoErrorFnLabel::FetchOrderSyncPending)
+        })?;
+        let (stmt, params) = FetchOrderSyncPendingArgs::from((t_due, limit)).into_parts();
+        let rows = stmt
+            .with(params)
+            .fetch::<OrderSyncPendingRowType, &mut Conn>(&mut conn)
+            .await
+            .map_err(|e| {
+                let code = AppErrorCode::RemoteDbServerFailure;
+                let detail = AppRepoErrorDetail::DatabaseQuery(e.to_string());
+                let label = AppRepoErrorFnLabel::FetchOrderSyncPending;
+                self._map_log_err_common((code, detail), label)
+            })?;
+        rows.into_iter()
+            .map(FetchOrderSyncPendingArgs::convert_row)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|reason| {
+                self._map_log_err_common(reason, AppRepoErrorFnLabel::FetchOrderSyncPending)
+            })
+    }
+
+    async fn remove_order_sync_pending(
+        &self,
+        buyer_id: u32,
+        charge_ctime: DateTime<Utc>,
+    ) -> Result<(), AppRepoError> {
+        let fn_label = AppRepoErrorFnLabel::RemoveOrderSyncPending;
+        let (stmt, params) =
+            DeleteOrderSyncPendingArgs::from((buyer_id, charge_ctime)).into_parts();
+        self._run_stmt(stmt, params, fn_label).await
+    }
+
+    async fn save_refund_confirmed(
+        &self,
+        buyer_id: u32,
+        charge_ctime: DateTime<Utc>,
+        t_confirmed: DateTime<Utc>,
+    ) -> Result<(), AppRepoError> {
+        let (stmt, params) =
+            SaveRefundConfirmedArgs::from((buyer_id, charge_ctime, t_confirmed)).into_parts();
+        self._run_stmt(stmt, params, AppRepoErrorFnLabel::SaveRefundConfirmed)
+            .await
+    }
+
+    async fn save_payout_transfer_state(
+        &self,
+        store_id: u32,
+        buyer_id: u32,
+        charge_ctime: DateTime<Utc>,
+        state: PayoutTransferStateModel,
+    ) -> Result<(), AppRepoError> {
+        let arg = (store_id, buyer_id, charge_ctime, state);
+        let (stmt, params) = SavePayoutTransferStateArgs::from(arg).into_parts();
+        self._run_stmt(stmt, params, AppRepoErrorFnLabel::SavePayoutTransferState)
+            .await
+    }
 } // end of impl MariadbChargeRepo
@@ -1,6 +1,7 @@
+use std::collections::HashMap;
 use std::result::Result;
 
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, Utc};
 use mysql_async::Params;
 use rust_decimal::Decimal;
 
@@ -11,8 +12,9 @@ use ecommerce_common::model::BaseProductIdentity;
 use super::super::{AppRepoError, AppRepoErrorDetail, AppRepoErrorFnLabel};
 use super::{inner_into_parts, raw_column_to_datetime, DATETIME_FMT_P0F, DATETIME_FMT_P3F};
 use crate::model::{
-    BuyerPayInState, Charge3partyModel, Charge3partyStripeModel, ChargeBuyerMetaModel,
-    ChargeBuyerModel, ChargeLineBuyerModel, ChargeRefundMap, PayLineAmountModel,
+    BuyerPayInState, Charge3partyModel, Charge3partyStripeModel, Charge3partyXenditModel,
+    ChargeBuyerMetaModel, ChargeBuyerModel, ChargeLineBuyerModel, ChargeRefundMap,
+    PayLineAmountModel,
 };
 
 struct InsertChargeTopLvlArgs(String, Params);
@@ -23,6 +25,7 @@ struct InsertChargeStatusArgs {
     t_order_app_synced: Option<String>,
 }
 struct InsertChargeLinesArgs(String, Vec<Params>);
+struct InsertChargeShippingArgs(String, Vec<Params>);
 struct UpdateCharge3partyArgs {
     label: String,
     detail: String,
@@ -38,8 +41,17 @@ pub(super) struct InsertChargeArgs(pub(super) Vec<(String, Vec<Params>)>);
 pub(super) struct FetchChargeMetaArgs(String, Params);
 pub(super) struct UpdateChargeMetaArgs(String, Params);
 pub(super) struct FetchChargeLineArgs(String, Params);
+pub(super) struct FetchChargeShippingArgs(String, Params);
 pub(super) struct UpdateChargeLineRefundArgs(String, Vec<Params>);
 pub(super) struct FetchChargeIDsArgs(String, Params);
+pub(super) struct SaveOrderSyncPendingArgs(String, Params);
+pub(super) struct FetchOrderSyncPendingArgs(String, Params);
+pub(super) struct DeleteOrderSyncPendingArgs(String, Params);
+pub(super) struct SaveRefundConfirmedArgs(String, Params);
+
+// delay the periodic job from picking up a new pending record, since the caller
+// is going to sync with order service right after the charge is saved
+const ORDER_SYNC_INIT_DELAY_SECS: i64 = 300;
 
 pub(super) type ChargeMetaRowType = (
     Vec<u8>,
@@ -56,8 +68,16 @@ pub(super) type ChargeLineRowType = (
     u32, u64, u16, Decimal, Decimal, u32, Decimal, Decimal, u32, u32
 );
 
+pub(super) type ChargeShippingRowType = (u32, Decimal);
+
 pub(super) type ChargeIdRowType = (u32, mysql_async::Value);
 
+pub(super) type OrderSyncPendingRowType = (
+    u32,                // `buyer_id`
+    mysql_async::Value, // `charge_ctime`
+    u16,                // `num_attempts`
+);
+
 impl TryFrom<BuyerPayInState> for InsertChargeStatusArgs {
     type Error = AppRepoError;
     fn try_from(value: BuyerPayInState) -> Result<Self, Self::Error> {
@@ -128,6 +148,15 @@ impl TryFrom<Charge3partyModel> for UpdateCharge3partyArgs {
                     ))
                     .map(|detail| Self {label, detail})
             }
+            Charge3partyModel::Xendit(m) => {
+                let label = "Xendit".to_string();
+                serde_json::to_string(&m)
+                    .map_err(|e| (
+                        AppErrorCode::DataCorruption,
+                        AppRepoErrorDetail::PayDetail(label.clone(), e.to_string()),
+                    ))
+                    .map(|detail| Self {label, detail})
+            }
             Charge3partyModel::Unknown =>
                 Err((
                     AppErrorCode::InvalidInput,
@@ -219,6 +248,27 @@ impl From<(u32, String, Vec<ChargeLineBuyerModel>)> for InsertChargeLinesArgs {
     } // end of fn from
 } // end of impl InsertChargeLinesArgs
 
+impl From<(u32, String, HashMap<u32, Decimal>)> for InsertChargeShippingArgs {
+    fn from(value: (u32, String, HashMap<u32, Decimal>)) -> Self {
+        let (buyer_id, ctime, shipping) = value;
+        let params = shipping
+            .into_iter()
+            .map(|(store_id, amount)| {
+                let arg = vec![
+                    buyer_id.into(),
+                    ctime.as_str().into(),
+                    store_id.into(),
+                    amount.into(),
+                ];
+                Params::Positional(arg)
+            })
+            .collect();
+        let stmt = "INSERT INTO `charge_shipping`(`buyer_id`,`create_time`,`store_id`,\
+                    `amount`) VALUES (?,?,?,?)";
+        Self(stmt.to_string(), params)
+    }
+}
+
 #[rustfmt::skip]
 impl TryFrom<ChargeBuyerModel> for InsertChargeArgs {
     type Error = AppRepoError;
@@ -229,12 +279,40 @@ impl TryFrom<ChargeBuyerModel> for InsertChargeArgs {
         );
         let c_lines = value.lines.split_off(0);
         assert!(value.lines.is_empty());
+        let c_shipping = std::mem::take(&mut value.shipping);
+        // the pay-in might complete right after it started, the pending record
+        // is saved within the same transaction so the sync to order service
+        // would not be lost.
+        let pending_arg = if let BuyerPayInState::ProcessorCompleted(t) = value.meta.progress() {
+            let next_retry = *t + Duration::seconds(ORDER_SYNC_INIT_DELAY_SECS);
+            let stmt = "INSERT INTO `charge_ordersync_pending`(`buyer_id`,`charge_ctime`,\
+                        `num_attempts`,`next_retry_time`) VALUES (?,?,0,?)";
+            let arg = vec![
+                buyer_id.into(), ctime.as_str().into(),
+                next_retry.format(DATETIME_FMT_P0F).to_string().into(),
+            ];
+            Some((stmt.to_string(), vec![Params::Positional(arg)]))
+        } else {
+            None
+        };
         let toplvl_arg = InsertChargeTopLvlArgs::try_from(value)?;
+        let shipping_arg = if c_shipping.is_empty() {
+            None
+        } else {
+            let a = InsertChargeShippingArgs::from((buyer_id, ctime.clone(), c_shipping));
+            Some((a.0, a.1))
+        };
         let lines_arg = InsertChargeLinesArgs::from((buyer_id, ctime, c_lines));
-        let inner = vec![
+        let mut inner = vec![
             (toplvl_arg.0, vec![toplvl_arg.1]),
             (lines_arg.0, lines_arg.1),
         ];
+        if let Some(v) = shipping_arg {
+            inner.push(v);
+        }
+        if let Some(v) = pending_arg {
+            inner.push(v);
+        }
         Ok(Self(inner))
     }
 } // end of impl InsertChargeArgs
@@ -304,6 +382,9 @@ impl TryFrom<(String, String)> for Charge3partyModel {
             "Stripe" => serde_json::from_str::<Charge3partyStripeModel>(detail.as_str())
                 .map(Charge3partyModel::Stripe)
                 .map_err(|e| e.to_string()),
+            "Xendit" => serde_json::from_str::<Charge3partyXenditModel>(detail.as_str())
+                .map(Charge3partyModel::Xendit)
+                .map_err(|e| e.to_string()),
             _others => Err(format!("unknown-3pty-method: {}", label)),
         };
         result.map_err(|msg| {
@@ -401,6 +482,21 @@ impl From<(u32, DateTime<Utc>, Option<u32>)> for FetchChargeLineArgs {
 
 inner_into_parts!(FetchChargeLineArgs);
 
+impl From<(u32, DateTime<Utc>, u32)> for FetchChargeShippingArgs {
+    fn from(value: (u32, DateTime<Utc>, u32)) -> Self {
+        let stmt = "SELECT `store_id`,`amount` FROM `charge_shipping` WHERE `buyer_id`=? \
+                    AND `create_time`=? AND `store_id`=?";
+        let args = vec![
+            value.0.into(),
+            value.1.format(DATETIME_FMT_P0F).to_string().into(),
+            value.2.into(),
+        ];
+        Self(stmt.to_string(), Params::Positional(args))
+    }
+}
+
+inner_into_parts!(FetchChargeShippingArgs);
+
 impl TryFrom<ChargeLineRowType> for ChargeLineBuyerModel {
     type Error = AppRepoErrorDetail;
     #[rustfmt::skip]
@@ -500,3 +596,77 @@ impl FetchChargeIDsArgs {
         }
     } // end of fn convert_rows
 } // end of impl FetchChargeIDsArgs
+
+impl From<(u32, DateTime<Utc>, DateTime<Utc>)> for SaveOrderSyncPendingArgs {
+    fn from(value: (u32, DateTime<Utc>, DateTime<Utc>)) -> Self {
+        let (buyer_id, charge_ctime, next_retry) = value;
+        let stmt = "INSERT INTO `charge_ordersync_pending`(`buyer_id`,`charge_ctime`,\
+                    `num_attempts`,`next_retry_time`) VALUES (?,?,1,?) ON DUPLICATE KEY \
+                    UPDATE `num_attempts`=`num_attempts`+1, `next_retry_time`=VALUES(`next_retry_time`)";
+        let args = vec![
+            buyer_id.into(),
+            charge_ctime.format(DATETIME_FMT_P0F).to_string().into(),
+            next_retry.format(DATETIME_FMT_P0F).to_string().into(),
+        ];
+        Self(stmt.to_string(), Params::Positional(args))
+    }
+}
+
+inner_into_parts!(SaveOrderSyncPendingArgs);
+
+impl From<(DateTime<Utc>, u16)> for FetchOrderSyncPendingArgs {
+    fn from(value: (DateTime<Utc>, u16)) -> Self {
+        let (t_due, limit) = value;
+        let stmt = "SELECT `buyer_id`,`charge_ctime`,`num_attempts` FROM \
+                    `charge_ordersync_pending` WHERE `next_retry_time` <= ? \
+                    ORDER BY `next_retry_time` ASC LIMIT ?";
+        let args = vec![
+            t_due.format(DATETIME_FMT_P0F).to_string().into(),
+            limit.into(),
+        ];
+        Self(stmt.to_string(), Params::Positional(args))
+    }
+}
+
+inner_into_parts!(FetchOrderSyncPendingArgs);
+
+impl FetchOrderSyncPendingArgs {
+    pub(super) fn convert_row(
+        row: OrderSyncPendingRowType,
+    ) -> Result<(u32, DateTime<Utc>, u16), (AppErrorCode, AppRepoErrorDetail)> {
+        let (buyer_id, ctime_raw, num_attempts) = row;
+        let charge_ctime = raw_column_to_datetime(ctime_raw, 0)?;
+        Ok((buyer_id, charge_ctime, num_attempts))
+    }
+}
+
+impl From<(u32, DateTime<Utc>)> for DeleteOrderSyncPendingArgs {
+    fn from(value: (u32, DateTime<Utc>)) -> Self {
+        let (buyer_id, charge_ctime) = value;
+        let stmt = "DELETE FROM `charge_ordersync_pending` WHERE `buyer_id`=? AND `charge_ctime`=?";
+        let args = vec![
+            buyer_id.into(),
+            charge_ctime.format(DATETIME_FMT_P0F).to_string().into(),
+        ];
+        Self(stmt.to_string(), Params::Positional(args))
+    }
+}
+
+inner_into_parts!(DeleteOrderSyncPendingArgs);
+
+impl From<(u32, DateTime<Utc>, DateTime<Utc>)> for SaveRefundConfirmedArgs {
+    fn from(value: (u32, DateTime<Utc>, DateTime<Utc>)) -> Self {
+        let (buyer_id, charge_ctime, t_confirmed) = value;
+        let stmt = "INSERT INTO `charge_refund_3party_confirm`(`buyer_id`,`create_time`,\
+                    `confirmed_time`) VALUES (?,?,?) ON DUPLICATE KEY UPDATE \
+                    `confirmed_time`=VALUES(`confirmed_time`)";
+        let args = vec![
+            buyer_id.into(),
+            charge_ctime.format(DATETIME_FMT_P0F).to_string().into(),
+            t_confirmed.format(DATETIME_FMT_P0F).to_string().into(),
+        ];
+        Self(stmt.to_string(), Params::Positional(args))
+    }
+}
+
+inner_into_parts!(SaveRefundConfirmedArgs);
@@ -10,7 +10,8 @@ use ecommerce_common::logging::{app_log_event, AppLogLevel};
 
 use crate::adapter::datastore::{AppDStoreMariaDB, AppDataStoreContext};
 use crate::model::{
-    Label3party, Merchant3partyModel, Merchant3partyStripeModel, MerchantProfileModel,
+    Label3party, Merchant3partyModel, Merchant3partyStripeModel, Merchant3partyXenditModel,
+    MerchantProfileModel,
 };
 
 use super::super::{AbstractMerchantRepo, AppRepoError, AppRepoErrorDetail, AppRepoErrorFnLabel};
@@ -21,6 +22,7 @@ struct Insert3partyArgs(String, Params);
 struct Update3partyArgs(String, Params);
 struct FetchProfileArgs(String, Params);
 struct Fetch3partyArgs(String, Params, String);
+struct Fetch3partyByAcctArgs(String, Params, Label3party);
 
 type MercProfRowType = (
     String,             // `name`
@@ -29,6 +31,7 @@ type MercProfRowType = (
     mysql_async::Value, // `last_update`
 );
 type Merc3ptyRowType = (Vec<u8>,);
+type Merc3ptyAcctRowType = (u32, Vec<u8>);
 
 impl From<MerchantProfileModel> for InsertUpdateProfileArgs {
     fn from(value: MerchantProfileModel) -> Self {
@@ -74,6 +77,16 @@ impl TryInto<(String, String)> for Merchant3partyModel {
                 })?;
                 Ok((label, d))
             }
+            Self::Xendit(xm) => {
+                let label = Label3party::Xendit.to_string();
+                let d = serde_json::to_string(&xm).map_err(|e| {
+                    (
+                        AppErrorCode::DataCorruption,
+                        AppRepoErrorDetail::PayDetail(label.clone(), e.to_string()),
+                    )
+                })?;
+                Ok((label, d))
+            }
             Self::Unknown => Err((
                 AppErrorCode::InvalidInput,
                 AppRepoErrorDetail::PayMethodUnsupport("unknown".to_string()),
@@ -127,6 +140,23 @@ impl From<(u32, Label3party)> for Fetch3partyArgs {
     }
 }
 
+impl<'a> From<(Label3party, &'a str)> for Fetch3partyByAcctArgs {
+    fn from(value: (Label3party, &'a str)) -> Self {
+        let (l3pt, account_id) = value;
+        // the account ID is stored as one of the fields in serialised 3rd-party
+        // detail, the JSON path varies between 3rd parties.
+        let jpath = match l3pt {
+            Label3party::Stripe => "$.id",
+            Label3party::Xendit => "$.id",
+        };
+        let stmt = "SELECT `sid`,`detail` FROM `merchant_3party` WHERE `method`=? \
+                    AND JSON_VALUE(`detail`, ?)=?";
+        let arg = vec![l3pt.to_string().into(), jpath.into(), account_id.into()];
+        let params = Params::Positional(arg);
+        Self(stmt.to_string(), params, l3pt)
+    }
+}
+
 impl TryFrom<(u32, MercProfRowType)> for MerchantProfileModel {
     type Error = (AppErrorCode, AppRepoErrorDetail);
     #[rustfmt::skip]
@@ -163,20 +193,20 @@ impl TryFrom<(Label3party, Merc3ptyRowType)> for Merchant3partyModel {
     type Error = (AppErrorCode, AppRepoErrorDetail);
     fn try_from(value: (Label3party, Merc3ptyRowType)) -> Result<Self, Self::Error> {
         let (label, (detail_raw,)) = value;
-        let out = match label {
+        let result = match label {
             Label3party::Stripe => {
-                let s = serde_json::from_slice::<Merchant3partyStripeModel>(&detail_raw).map_err(
-                    |e| {
-                        (
-                            AppErrorCode::DataCorruption,
-                            AppRepoErrorDetail::DataRowParse(e.to_string()),
-                        )
-                    },
-                )?;
-                Self::Stripe(s)
+                serde_json::from_slice::<Merchant3partyStripeModel>(&detail_raw).map(Self::Stripe)
+            }
+            Label3party::Xendit => {
+                serde_json::from_slice::<Merchant3partyXenditModel>(&detail_raw).map(Self::Xendit)
             }
         };
-        Ok(out)
+        result.map_err(|e| {
+            (
+                AppErrorCode::DataCorruption,
+                AppRepoErrorDetail::DataRowParse(e.to_string()),
+            )
+        })
     }
 } // end of impl MerchantProfileModel
 
@@ -418,4 +448,40 @@ impl AbstractMerchantRepo for MariadbMerchantRepo {
             Err(e)
         }
     } // end of fn update_3party
+
+    async fn fetch_by_3party_account(
+        &self,
+        label3pty: Label3party,
+        account_id: &str,
+    ) -> Result<Option<(u32, Merchant3partyModel)>, AppRepoError> {
+        let mut conn = self._dstore.acquire().await.map_err(|e| {
+            self._map_log_err(
+                AppErrorCode::DatabaseServerBusy,
+                AppRepoErrorDetail::DataStore(e),
+                AppRepoErrorFnLabel::FetchMerchantBy3party,
+            )
+        })?;
+        let Fetch3partyByAcctArgs(stmt, params, l3pt) =
+            Fetch3partyByAcctArgs::from((label3pty, account_id));
+        let maybe_row = stmt
+            .with(params)
+            .first::<Merc3ptyAcctRowType, &mut Conn>(&mut conn)
+            .await
+            .map_err(|e| {
+                self._map_log_err(
+                    AppErrorCode::RemoteDbServerFailure,
+                    AppRepoErrorDetail::DatabaseQuery(e.to_string()),
+                    AppRepoErrorFnLabel::FetchMerchantBy3party,
+                )
+            })?;
+        if let Some((store_id, detail_raw)) = maybe_row {
+            Merchant3partyModel::try_from((l3pt, (detail_raw,)))
+                .map(|m3pty| Some((store_id, m3pty)))
+                .map_err(|e| {
+                    self._map_log_err(e.0, e.1, AppRepoErrorFnLabel::FetchMerchantBy3party)
+                })
+        } else {
+            Ok(None)
+        }
+    } // end of fn fetch_by_3party_account
 } // end of impl MariadbMerchantRepo
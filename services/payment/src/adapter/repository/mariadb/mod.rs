@@ -3,6 +3,7 @@ mod charge_converter;
 pub(super) mod merchant;
 mod order_replica;
 mod payout;
+pub(super) mod processor_event;
 pub(super) mod refund;
 pub(super) mod reporting;
 
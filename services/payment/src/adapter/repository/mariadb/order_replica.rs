@@ -11,7 +11,10 @@ use ecommerce_common::model::BaseProductIdentity;
 
 use super::super::{AppRepoError, AppRepoErrorDetail, AppRepoErrorFnLabel};
 use super::raw_column_to_datetime;
-use crate::model::{OrderCurrencySnapshot, OrderLineModel, OrderLineModelSet, PayLineAmountModel};
+use crate::model::{
+    OrderCurrencySnapshot, OrderLineModel, OrderLineModelSet, OrderShippingModel,
+    PayLineAmountModel,
+};
 
 const DATETIME_FMT_P0F: &str = "%Y-%m-%d %H:%M:%S";
 
@@ -23,14 +26,18 @@ pub(super) type OrderlineRowType = (
 #[rustfmt::skip]
 pub(super) type OrderCurrencyRowType = (u32, String, Decimal);
 
+// seller ID, shipping fee, whether the fee has been paid
+pub(super) type OrderShippingRowType = (u32, Decimal, bool);
+
 struct InsertOrderTopLvlArgs(String, Params);
 struct InsertOrderLineArgs(String, Vec<Params>);
 struct InsertCurrencySnapshotArgs(String, Vec<Params>);
+struct InsertOrderShippingArgs(String, Vec<Params>);
 struct InsertBillContactArgs(String, Params);
 struct InsertBillPhyAddrArgs(String, Params);
 
 pub(super) struct InsertOrderReplicaArgs(pub(super) Vec<(String, Vec<Params>)>);
-pub(super) struct FetchUnpaidOlineArgs(pub(super) [(String, Params); 3]);
+pub(super) struct FetchUnpaidOlineArgs(pub(super) [(String, Params); 4]);
 pub(super) struct FetchCurrencySnapshotArgs(String, Vec<MySqlValue>);
 
 impl<'a, 'b> From<(&'a OrderLineModelSet, &'b OidBytes)> for InsertOrderTopLvlArgs {
@@ -83,6 +90,27 @@ impl<'a, 'b> From<(&'a OrderLineModelSet, &'b OidBytes)> for InsertOrderLineArgs
     } // end of fn from
 } // end of impl InsertOrderLineArgs
 
+impl<'a, 'b> From<(&'a OrderLineModelSet, &'b OidBytes)> for InsertOrderShippingArgs {
+    fn from(value: (&'a OrderLineModelSet, &'b OidBytes)) -> Self {
+        let (ol_set, oid_b) = value;
+        let params = ol_set
+            .shipping
+            .iter()
+            .map(|s| {
+                let arg = vec![
+                    oid_b.as_column().into(),
+                    s.seller_id.into(),
+                    s.amount.into(),
+                ];
+                Params::Positional(arg)
+            })
+            .collect::<Vec<_>>();
+        let stmt = "INSERT INTO `order_shipping_detail`(`o_id`,`store_id`,`amount`) \
+                    VALUES (?,?,?)";
+        Self(stmt.to_string(), params)
+    }
+}
+
 impl<'a, 'b> TryFrom<(&'a HashMap<u32, OrderCurrencySnapshot>, &'b OidBytes)>
     for InsertCurrencySnapshotArgs
 {
@@ -203,6 +231,10 @@ impl<'a, 'b> TryFrom<(&'a OrderLineModelSet, &'b BillingModel)> for InsertOrderR
             let phyaddr_arg = InsertBillPhyAddrArgs::from((a, &oid_b));
             inner.push((phyaddr_arg.0, vec![phyaddr_arg.1]));
         }
+        if !ol_set.shipping.is_empty() {
+            let shipping_arg = InsertOrderShippingArgs::from((ol_set, &oid_b));
+            inner.push((shipping_arg.0, shipping_arg.1));
+        }
         Ok(Self(inner))
     }
 } // end of impl InsertOrderReplicaArgs
@@ -251,6 +283,7 @@ impl<'a> TryFrom<(u32, &'a str)> for FetchUnpaidOlineArgs {
             value_currency,
             vec![oid_b.as_column().into(), usr_id.into()],
             vec![usr_id.into(), oid_b.0.into(), oid_b.0.into()],
+            vec![usr_id.into(), oid_b.0.into()],
         ]
         .into_iter()
         .map(Params::Positional)
@@ -273,6 +306,12 @@ impl<'a> TryFrom<(u32, &'a str)> for FetchUnpaidOlineArgs {
             (`a1`.`o_id`=`a2`.`order-id` AND `a1`.`store_id`=`a2`.`store` AND \
             `a1`.`product_id`=`a2`.`prod-id` AND `a1`.`attr_seq`=`a2`.`attr-seq`) \
             WHERE `a1`.`o_id`=? AND `a1`.`qty_rsved` > COALESCE(`a2`.`qty_orig`, 0)",
+            // shipping fee of a seller is paid if any completed charge covers it
+            "SELECT `a`.`store_id`, `a`.`amount`, EXISTS(SELECT 1 FROM `charge_buyer_toplvl` \
+            AS `b` INNER JOIN `charge_shipping` AS `c` ON (`b`.`usr_id`=`c`.`buyer_id` AND \
+            `b`.`create_time`=`c`.`create_time`) WHERE `b`.`usr_id`=? AND `b`.`order_id`=`a`.`o_id` \
+            AND `b`.`state`='OrderAppSynced' AND `c`.`store_id`=`a`.`store_id`) \
+            FROM `order_shipping_detail` AS `a` WHERE `a`.`o_id`=?",
         ]
         .into_iter()
         .map(ToString::to_string)
@@ -323,10 +362,22 @@ impl<'a>
             lines: vec![],
             num_charges,
             currency_snapshot,
+            shipping: vec![],
         })
     }
 } // end of impl OrderLineModelSet
 
+impl From<OrderShippingRowType> for OrderShippingModel {
+    fn from(value: OrderShippingRowType) -> Self {
+        let (seller_id, amount, paid) = value;
+        Self {
+            seller_id,
+            amount,
+            paid,
+        }
+    }
+}
+
 impl TryFrom<OrderlineRowType> for OrderLineModel {
     type Error = AppRepoError;
     #[rustfmt::skip]
@@ -7,16 +7,21 @@ use mysql_async::{Conn, Params};
 use rust_decimal::Decimal;
 
 use ecommerce_common::adapter::repository::OidBytes;
+use ecommerce_common::api::dto::CurrencyDto;
 use ecommerce_common::error::AppErrorCode;
 
 use super::super::AppRepoErrorDetail;
 use super::{inner_into_parts, DATETIME_FMT_P0F};
-use crate::model::{Label3party, Payout3partyModel, Payout3partyStripeModel, PayoutInnerModel};
+use crate::model::{
+    Label3party, Payout3partyModel, Payout3partyStripeModel, Payout3partyXenditModel,
+    PayoutInnerModel, PayoutTransferStateModel,
+};
 
 pub(super) struct InsertPayoutMetaArgs(String, Params);
 pub(super) struct InsertPayout3partyArgs(String, Params);
 pub(super) struct FetchPayoutMetaArgs(String, Params);
 pub(super) struct FetchPayout3partyArgs(String, Params, Label3party);
+pub(super) struct SavePayoutTransferStateArgs(String, Params);
 
 pub(super) type PayoutMetaRowType = (
     mysql_async::Value, // `create_time`
@@ -33,6 +38,14 @@ type Payout3ptyStripeRowType = (
     Decimal, // `amount-base`
 );
 
+type Payout3ptyXenditRowType = (
+    String,  // `reference`
+    String,  // `acct-id`
+    String,  // `currency`
+    String,  // `transfer-id`
+    Decimal, // `amount`
+);
+
 #[rustfmt::skip]
 impl TryFrom<(PayoutInnerModel, Label3party)> for InsertPayoutMetaArgs {
     type Error = (AppErrorCode, AppRepoErrorDetail);
@@ -80,6 +93,14 @@ impl TryFrom<Payout3partyCvtFromArg> for InsertPayout3partyArgs {
                     )
                 })
             }
+            Payout3partyModel::Xendit(x) => {
+                Self::try_from_xendit(buyer_usr_id, charged_ctime, merchant_id, x).map_err(|msg| {
+                    (
+                        AppErrorCode::InvalidInput,
+                        AppRepoErrorDetail::PayDetail(Label3party::Xendit.to_string(), msg),
+                    )
+                })
+            }
         }
     }
 }
@@ -105,6 +126,25 @@ impl InsertPayout3partyArgs {
         let params = Params::Positional(args);
         Ok(Self(stmt.to_string(), params))
     }
+    #[rustfmt::skip]
+    fn try_from_xendit(
+        buyer_usr_id: u32,
+        charged_ctime: DateTime<Utc>,
+        merchant_id: u32,
+        value: Payout3partyXenditModel
+    ) -> Result<Self, String> {
+        let amount = value.amount().ok_or("missing-amount".to_string())?;
+        let transfer_id = value.transfer_id().ok_or("missing-transfer-id".to_string())?;
+        let stmt = "INSERT INTO `payout_3party_xendit`(`buyer_usr_id`,`charged_time`,`store_id`,\
+                    `reference`,`acct_id`,`currency`,`transfer_id`,`amount`) VALUES (?,?,?,?,?,?,?,?)";
+        let args = vec![
+            buyer_usr_id.into(), charged_ctime.format(DATETIME_FMT_P0F).to_string().into(),
+            merchant_id.into(), value.reference().into(), value.sub_account().into(),
+            value.currency().as_str().into(), transfer_id.into(), amount.into(),
+        ];
+        let params = Params::Positional(args);
+        Ok(Self(stmt.to_string(), params))
+    }
 } // end of impl InsertPayout3partyArgs
 
 inner_into_parts!(InsertPayoutMetaArgs);
@@ -135,6 +175,10 @@ impl From<(u32, DateTime<Utc>, u32, Label3party)> for FetchPayout3partyArgs {
                 "SELECT `tx_grp`,`acct_id`,`transfer_id`,`amount` FROM `payout_3party_stripe`\
                 WHERE `buyer_usr_id`=? AND `charged_time`=? AND `store_id`=?"
             }
+            Label3party::Xendit => {
+                "SELECT `reference`,`acct_id`,`currency`,`transfer_id`,`amount` FROM \
+                `payout_3party_xendit` WHERE `buyer_usr_id`=? AND `charged_time`=? AND `store_id`=?"
+            }
         };
         let arg = vec![
             buyer_id.into(),
@@ -159,6 +203,13 @@ impl FetchPayout3partyArgs {
                 let s = Payout3partyStripeModel::from(arg);
                 Ok(Payout3partyModel::Stripe(s))
             }
+            Label3party::Xendit => {
+                let row = Self::lowlvl_fetch::<Payout3ptyXenditRowType>(stmt, params, conn).await?;
+                let currency = CurrencyDto::from(row.2.as_str());
+                let arg = (row.0, row.1, currency, Some(row.3), Some(row.4));
+                let x = Payout3partyXenditModel::from(arg);
+                Ok(Payout3partyModel::Xendit(x))
+            }
         }
     }
     async fn lowlvl_fetch<T: FromRow + Send + 'static>(
@@ -180,3 +231,33 @@ impl FetchPayout3partyArgs {
             ))
     }
 } // end of impl FetchPayout3partyArgs
+
+type SavePayoutTransferStateCvtArg = (u32, u32, DateTime<Utc>, PayoutTransferStateModel);
+
+impl From<SavePayoutTransferStateCvtArg> for SavePayoutTransferStateArgs {
+    fn from(value: SavePayoutTransferStateCvtArg) -> Self {
+        let (store_id, buyer_id, charge_ctime, state) = value;
+        let PayoutTransferStateModel {
+            transfer_id,
+            reversed,
+            notified_time,
+        } = state;
+        // events might arrive out of order, a reversed transfer is never
+        // switched back by the event notified earlier
+        let stmt = "INSERT INTO `payout_3party_transfer_state`(`buyer_usr_id`,`charged_time`,\
+                    `store_id`,`transfer_id`,`reversed`,`notified_time`) VALUES (?,?,?,?,?,?) \
+                    ON DUPLICATE KEY UPDATE `reversed`=(`reversed` OR VALUES(`reversed`)), \
+                    `notified_time`=GREATEST(`notified_time`, VALUES(`notified_time`))";
+        let args = vec![
+            buyer_id.into(),
+            charge_ctime.format(DATETIME_FMT_P0F).to_string().into(),
+            store_id.into(),
+            transfer_id.into(),
+            reversed.into(),
+            notified_time.format(DATETIME_FMT_P0F).to_string().into(),
+        ];
+        Self(stmt.to_string(), Params::Positional(args))
+    }
+}
+
+inner_into_parts!(SavePayoutTransferStateArgs);
@@ -0,0 +1,207 @@
+use std::result::Result;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{Duration, Local};
+use mysql_async::prelude::Queryable;
+use mysql_async::{Conn, Params};
+
+use ecommerce_common::error::AppErrorCode;
+use ecommerce_common::logging::{app_log_event, AppLogLevel};
+
+use crate::adapter::datastore::{AppDStoreMariaDB, AppDataStoreContext};
+use crate::hard_limit::SECONDS_PROCESSOR_EVENT_CLAIM;
+use crate::model::Label3party;
+
+use super::super::{
+    AbstractProcessorEventRepo, AppRepoError, AppRepoErrorDetail, AppRepoErrorFnLabel,
+    ProcessorEventClaim,
+};
+use super::DATETIME_FMT_P0F;
+
+struct InsertEventArgs(String, Params);
+struct TakeoverEventArgs(String, Params);
+struct FetchEventStateArgs(String, Params);
+struct CompleteEventArgs(String, Params);
+struct DeleteEventArgs(String, Params);
+
+impl<'a> From<(Label3party, &'a str)> for InsertEventArgs {
+    fn from(value: (Label3party, &'a str)) -> Self {
+        let (l3pt, event_id) = value;
+        let t_now = Local::now().to_utc().format(DATETIME_FMT_P0F).to_string();
+        // the event which has been claimed will not be inserted again
+        let stmt = "INSERT IGNORE INTO `processor_event`(`method`,`event_id`,`received_time`,\
+                    `state`) VALUES (?,?,?,'Processing')";
+        let arg = vec![l3pt.to_string().into(), event_id.into(), t_now.into()];
+        Self(stmt.to_string(), Params::Positional(arg))
+    }
+}
+
+impl<'a> From<(Label3party, &'a str)> for TakeoverEventArgs {
+    fn from(value: (Label3party, &'a str)) -> Self {
+        let (l3pt, event_id) = value;
+        let t_now = Local::now().to_utc();
+        let t_expired = t_now - Duration::seconds(SECONDS_PROCESSOR_EVENT_CLAIM as i64);
+        // the request which claimed the event might crash before completing
+        // or releasing the claim
+        let stmt = "UPDATE `processor_event` SET `received_time`=? WHERE `method`=? \
+                    AND `event_id`=? AND `state`='Processing' AND `received_time` < ?";
+        let arg = vec![
+            t_now.format(DATETIME_FMT_P0F).to_string().into(),
+            l3pt.to_string().into(),
+            event_id.into(),
+            t_expired.format(DATETIME_FMT_P0F).to_string().into(),
+        ];
+        Self(stmt.to_string(), Params::Positional(arg))
+    }
+}
+
+impl<'a> From<(Label3party, &'a str)> for FetchEventStateArgs {
+    fn from(value: (Label3party, &'a str)) -> Self {
+        let (l3pt, event_id) = value;
+        let stmt = "SELECT `state` FROM `processor_event` WHERE `method`=? AND `event_id`=?";
+        let arg = vec![l3pt.to_string().into(), event_id.into()];
+        Self(stmt.to_string(), Params::Positional(arg))
+    }
+}
+
+impl<'a> From<(Label3party, &'a str)> for CompleteEventArgs {
+    fn from(value: (Label3party, &'a str)) -> Self {
+        let (l3pt, event_id) = value;
+        let stmt = "UPDATE `processor_event` SET `state`='Applied' WHERE `method`=? \
+                    AND `event_id`=?";
+        let arg = vec![l3pt.to_string().into(), event_id.into()];
+        Self(stmt.to_string(), Params::Positional(arg))
+    }
+}
+
+impl<'a> From<(Label3party, &'a str)> for DeleteEventArgs {
+    fn from(value: (Label3party, &'a str)) -> Self {
+        let (l3pt, event_id) = value;
+        // the event which has been applied is never released
+        let stmt = "DELETE FROM `processor_event` WHERE `method`=? AND `event_id`=? \
+                    AND `state`='Processing'";
+        let arg = vec![l3pt.to_string().into(), event_id.into()];
+        Self(stmt.to_string(), Params::Positional(arg))
+    }
+}
+
+pub(crate) struct MariadbProcessorEventRepo {
+    _dstore: Arc<AppDStoreMariaDB>,
+}
+
+impl MariadbProcessorEventRepo {
+    pub(crate) fn new(ds: Arc<AppDataStoreContext>) -> Result<Self, AppRepoError> {
+        ds.mariadb(Some("db-write-primary"))
+            .map(|found| Self { _dstore: found })
+            .ok_or(AppRepoError {
+                fn_label: AppRepoErrorFnLabel::InitProcessorEventRepo,
+                code: AppErrorCode::MissingDataStore,
+                detail: AppRepoErrorDetail::Unknown,
+            })
+    }
+
+    #[rustfmt::skip]
+    fn _map_log_err(
+        &self, code: AppErrorCode, detail: AppRepoErrorDetail,
+        fn_label: AppRepoErrorFnLabel,
+    ) -> AppRepoError {
+        let e = AppRepoError {fn_label, code, detail};
+        let logctx = self._dstore.log_context();
+        app_log_event!(logctx, AppLogLevel::ERROR, "{:?}", e);
+        e
+    }
+
+    async fn acquire_conn(&self, fn_label: AppRepoErrorFnLabel) -> Result<Conn, AppRepoError> {
+        self._dstore.acquire().await.map_err(|e| {
+            let code = AppErrorCode::DatabaseServerBusy;
+            let detail = AppRepoErrorDetail::DataStore(e);
+            self._map_log_err(code, detail, fn_label)
+        })
+    }
+
+    async fn lowlvl_exec(
+        &self,
+        conn: &mut Conn,
+        stmt: String,
+        params: Params,
+    ) -> Result<u64, (AppErrorCode, AppRepoErrorDetail)> {
+        conn.exec_iter(stmt, params)
+            .await
+            .map(|resultset| resultset.affected_rows())
+            .map_err(|e| {
+                let code = AppErrorCode::RemoteDbServerFailure;
+                let detail = AppRepoErrorDetail::DatabaseExec(e.to_string());
+                (code, detail)
+            })
+    }
+
+    async fn _try_claim(
+        &self,
+        conn: &mut Conn,
+        label3pty: Label3party,
+        event_id: &str,
+    ) -> Result<ProcessorEventClaim, (AppErrorCode, AppRepoErrorDetail)> {
+        let InsertEventArgs(stmt, params) = InsertEventArgs::from((label3pty, event_id));
+        if self.lowlvl_exec(conn, stmt, params).await? == 1u64 {
+            return Ok(ProcessorEventClaim::Claimed);
+        }
+        let TakeoverEventArgs(stmt, params) = TakeoverEventArgs::from((label3pty, event_id));
+        if self.lowlvl_exec(conn, stmt, params).await? == 1u64 {
+            return Ok(ProcessorEventClaim::Claimed);
+        }
+        let FetchEventStateArgs(stmt, params) = FetchEventStateArgs::from((label3pty, event_id));
+        let maybe_state: Option<String> = conn.exec_first(stmt, params).await.map_err(|e| {
+            let code = AppErrorCode::RemoteDbServerFailure;
+            let detail = AppRepoErrorDetail::DatabaseQuery(e.to_string());
+            (code, detail)
+        })?;
+        // the claim might be released right after the insertion above, the
+        // 3rd party will notify again anyway.
+        let out = match maybe_state.as_deref() {
+            Some("Applied") => ProcessorEventClaim::Applied,
+            _others => ProcessorEventClaim::InProgress,
+        };
+        Ok(out)
+    } // end of fn _try_claim
+} // end of impl MariadbProcessorEventRepo
+
+#[async_trait]
+impl AbstractProcessorEventRepo for MariadbProcessorEventRepo {
+    async fn try_claim(
+        &self,
+        label3pty: Label3party,
+        event_id: &str,
+    ) -> Result<ProcessorEventClaim, AppRepoError> {
+        let mut conn = self
+            .acquire_conn(AppRepoErrorFnLabel::ClaimProcessorEvent)
+            .await?;
+        self._try_claim(&mut conn, label3pty, event_id)
+            .await
+            .map_err(|(code, detail)| {
+                self._map_log_err(code, detail, AppRepoErrorFnLabel::ClaimProcessorEvent)
+            })
+    }
+
+    async fn complete(&self, label3pty: Label3party, event_id: &str) -> Result<(), AppRepoError> {
+        let mut conn = self
+            .acquire_conn(AppRepoErrorFnLabel::CompleteProcessorEvent)
+            .await?;
+        let CompleteEventArgs(stmt, params) = CompleteEventArgs::from((label3pty, event_id));
+        let result = self.lowlvl_exec(&mut conn, stmt, params).await;
+        result.map(|_num_affected| ()).map_err(|(code, detail)| {
+            self._map_log_err(code, detail, AppRepoErrorFnLabel::CompleteProcessorEvent)
+        })
+    }
+
+    async fn release(&self, label3pty: Label3party, event_id: &str) -> Result<(), AppRepoError> {
+        let mut conn = self
+            .acquire_conn(AppRepoErrorFnLabel::ReleaseProcessorEvent)
+            .await?;
+        let DeleteEventArgs(stmt, params) = DeleteEventArgs::from((label3pty, event_id));
+        let result = self.lowlvl_exec(&mut conn, stmt, params).await;
+        result.map(|_num_affected| ()).map_err(|(code, detail)| {
+            self._map_log_err(code, detail, AppRepoErrorFnLabel::ReleaseProcessorEvent)
+        })
+    }
+} // end of impl MariadbProcessorEventRepo
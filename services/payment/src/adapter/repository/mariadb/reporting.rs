@@ -316,10 +316,12 @@ impl MariadbReportingRepo {
                     errors.push(AppRepoErrorDetail::ConstructChargeFailure(msg));
                     return None;
                 };
+                // the report only lists product items, shipping fees are excluded
                 let charge_m = ChargeBuyerModel {
                     meta,
                     lines,
                     currency_snapshot: sc,
+                    shipping: HashMap::new(),
                 };
                 Some(charge_m)
             })
@@ -16,11 +16,12 @@ use crate::api::web::dto::{RefundCompletionReqDto, ReportTimeRangeDto};
 use crate::model::{
     BuyerPayInState, ChargeBuyerMetaModel, ChargeBuyerModel, ChargeLineBuyerModel, ChargeRefundMap,
     Label3party, Merchant3partyModel, MerchantProfileModel, OrderLineModelSet, OrderRefundModel,
-    PayoutModel, RefundModelError, RefundReqResolutionModel,
+    PayoutModel, PayoutTransferStateModel, RefundModelError, RefundReqResolutionModel,
 };
 
 use self::mariadb::charge::MariadbChargeRepo;
 use self::mariadb::merchant::MariadbMerchantRepo;
+use self::mariadb::processor_event::MariadbProcessorEventRepo;
 use self::mariadb::refund::MariaDbRefundRepo;
 use self::mariadb::reporting::MariadbReportingRepo;
 use super::datastore::{AppDStoreError, AppDataStoreContext};
@@ -38,14 +39,24 @@ pub enum AppRepoErrorFnLabel {
     FetchChargeLines,
     FetchMerchant,
     FetchMerchantProf,
+    FetchMerchantBy3party,
     FetchChargeByMerchant,
     FetchPayout,
+    FetchOrderSyncPending,
+    SaveOrderSyncPending,
+    RemoveOrderSyncPending,
+    SaveRefundConfirmed,
+    SavePayoutTransferState,
     UpdateChargeProgress,
     UpdateChargeLinesRefund,
     UpdateMerchant3party,
     InitMerchantRepo,
     InitRefundRepo,
     InitReportingRepo,
+    InitProcessorEventRepo,
+    ClaimProcessorEvent,
+    CompleteProcessorEvent,
+    ReleaseProcessorEvent,
     RefundGetTimeSynced,
     RefundUpdateTimeSynced,
     RefundSaveReq,
@@ -117,6 +128,15 @@ pub trait AbstractChargeRepo: Sync + Send {
 
     async fn update_charge_progress(&self, meta: ChargeBuyerMetaModel) -> Result<(), AppRepoError>;
 
+    /// update progress of a charge and record the pending sync to order service
+    /// in the same transaction, for the pay-in completed out of the request
+    /// made by client, e.g. notified by 3rd party.
+    async fn update_charge_progress_sync_pending(
+        &self,
+        meta: ChargeBuyerMetaModel,
+        next_retry: DateTime<Utc>,
+    ) -> Result<(), AppRepoError>;
+
     async fn fetch_charge_by_merchant(
         &self,
         buyer_id: u32,
@@ -136,6 +156,46 @@ pub trait AbstractChargeRepo: Sync + Send {
     ) -> Result<Option<PayoutModel>, AppRepoError>;
 
     async fn create_payout(&self, payout_m: PayoutModel) -> Result<(), AppRepoError>;
+
+    /// record that the payment status of a charge has to be synced to order service,
+    /// the record is kept until the sync is confirmed by the order service.
+    async fn save_order_sync_pending(
+        &self,
+        buyer_id: u32,
+        charge_ctime: DateTime<Utc>,
+        next_retry: DateTime<Utc>,
+    ) -> Result<(), AppRepoError>;
+
+    /// the method `fetch_order_sync_pending()` returns the buyer ID, create time
+    /// of each charge, and number of attempts made to sync the charge.
+    async fn fetch_order_sync_pending(
+        &self,
+        t_due: DateTime<Utc>,
+        limit: u16,
+    ) -> Result<Vec<(u32, DateTime<Utc>, u16)>, AppRepoError>;
+
+    async fn remove_order_sync_pending(
+        &self,
+        buyer_id: u32,
+        charge_ctime: DateTime<Utc>,
+    ) -> Result<(), AppRepoError>;
+
+    /// record the time the 3rd party confirmed refund of a charge, the record
+    /// is overwritten by the latest confirmation.
+    async fn save_refund_confirmed(
+        &self,
+        buyer_id: u32,
+        charge_ctime: DateTime<Utc>,
+        t_confirmed: DateTime<Utc>,
+    ) -> Result<(), AppRepoError>;
+
+    async fn save_payout_transfer_state(
+        &self,
+        store_id: u32,
+        buyer_id: u32,
+        charge_ctime: DateTime<Utc>,
+        state: PayoutTransferStateModel,
+    ) -> Result<(), AppRepoError>;
 } // end of trait AbstractChargeRepo
 
 #[async_trait]
@@ -162,6 +222,14 @@ pub trait AbstractMerchantRepo: Sync + Send {
         &self,
         store_id: u32,
     ) -> Result<Option<MerchantProfileModel>, AppRepoError>;
+
+    /// look up the store by the account identifier assigned by 3rd party, the
+    /// identifier is the only reference in the events notified by 3rd party.
+    async fn fetch_by_3party_account(
+        &self,
+        label3pty: Label3party,
+        account_id: &str,
+    ) -> Result<Option<(u32, Merchant3partyModel)>, AppRepoError>;
 } // end of trait AbstractMerchantRepo
 
 #[async_trait]
@@ -230,6 +298,32 @@ pub trait AbstractReportingRepo: Send + Sync {
     ) -> Result<Vec<ChargeBuyerModel>, AppRepoError>;
 }
 
+pub enum ProcessorEventClaim {
+    Claimed,
+    /// another request is still applying the event
+    InProgress,
+    Applied,
+}
+
+/// keep track of events sent from 3rd-party processors, each event is
+/// identified by the processor label and the event ID.
+#[async_trait]
+pub trait AbstractProcessorEventRepo: Sync + Send {
+    /// claim the event before applying it, a claim which is not completed
+    /// nor released for long time can be taken over by another request.
+    async fn try_claim(
+        &self,
+        label3pty: Label3party,
+        event_id: &str,
+    ) -> Result<ProcessorEventClaim, AppRepoError>;
+
+    /// mark the claimed event as applied
+    async fn complete(&self, label3pty: Label3party, event_id: &str) -> Result<(), AppRepoError>;
+
+    /// for the events which failed to apply, so 3rd party can notify again
+    async fn release(&self, label3pty: Label3party, event_id: &str) -> Result<(), AppRepoError>;
+}
+
 pub async fn app_repo_charge(
     dstore: Arc<AppDataStoreContext>,
 ) -> Result<Box<dyn AbstractChargeRepo>, AppRepoError> {
@@ -251,6 +345,13 @@ pub async fn app_repo_refund(
     Ok(Box::new(repo))
 }
 
+pub async fn app_repo_processor_event(
+    dstore: Arc<AppDataStoreContext>,
+) -> Result<Box<dyn AbstractProcessorEventRepo>, AppRepoError> {
+    let repo = MariadbProcessorEventRepo::new(dstore)?;
+    Ok(Box::new(repo))
+}
+
 pub async fn app_repo_reporting(
     dstore: Arc<AppDataStoreContext>,
 ) -> Result<Box<dyn AbstractReportingRepo>, AppRepoError> {
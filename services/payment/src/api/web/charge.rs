@@ -42,11 +42,14 @@ pub(super) async fn create_charge(
     app_log_event!(logctx_p, AppLogLevel::DEBUG, "create-charge-api");
 
     let repo = try_creating_charge_repo(shr_state.datastore(), logctx.clone()).await?;
+    let repo_m = try_creating_merchant_repo(shr_state.datastore(), logctx.clone()).await?;
     let uc = ChargeCreateUseCase {
         repo,
+        repo_m,
         processors: shr_state.processor_context(),
         ordersync_lockset: shr_state.ordersync_lockset(),
         rpc_ctx: shr_state.rpc_context(),
+        logctx: logctx.clone(),
     };
     let req_body = req_body.into_inner();
     let resp = match uc.execute(authed_claim, req_body).await {
@@ -22,10 +22,47 @@ pub struct StripeCheckoutSessionReqDto {
     pub ui_mode: StripeCheckoutUImodeDto,
 }
 
+/// e-wallet channels available in Xendit, the prefix of each variant
+/// indicates the country the wallet operates in
+#[allow(non_camel_case_types)]
+#[rustfmt::skip]
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub enum XenditEwalletChannelDto {
+    ID_OVO, ID_DANA, ID_SHOPEEPAY, ID_LINKAJA,
+    PH_GCASH, PH_GRABPAY, PH_PAYMAYA,
+    TH_TRUEMONEY, MY_TOUCHNGO,
+}
+#[derive(Deserialize)]
+pub struct XenditEwalletChargeReqDto {
+    pub channel_code: XenditEwalletChannelDto,
+    // required by the channels which redirect the buyer to wallet app / web page
+    pub success_redirect_url: Option<String>,
+    pub failure_redirect_url: Option<String>,
+    // required by OVO, which notifies the buyer in the wallet app instead
+    pub mobile_number: Option<String>,
+}
+impl XenditEwalletChannelDto {
+    /// each wallet accepts only the currency of the country it operates in
+    pub(crate) fn currency(&self) -> CurrencyDto {
+        match self {
+            Self::ID_OVO | Self::ID_DANA | Self::ID_SHOPEEPAY | Self::ID_LINKAJA => {
+                CurrencyDto::IDR
+            }
+            Self::PH_GCASH | Self::PH_GRABPAY | Self::PH_PAYMAYA => CurrencyDto::PHP,
+            Self::TH_TRUEMONEY => CurrencyDto::THB,
+            Self::MY_TOUCHNGO => CurrencyDto::MYR,
+        }
+    }
+    pub(crate) fn redirect_required(&self) -> bool {
+        !matches!(self, Self::ID_OVO)
+    }
+}
+
 #[derive(Deserialize)]
 #[serde(tag = "label")]
 pub enum PaymentMethodReqDto {
     Stripe(StripeCheckoutSessionReqDto),
+    Xendit(XenditEwalletChargeReqDto),
 }
 #[derive(Deserialize)]
 pub struct ChargeAmountOlineDto {
@@ -33,8 +70,8 @@ pub struct ChargeAmountOlineDto {
     pub product_id: u64,
     pub attr_set_seq: u16,
     pub quantity: u32,
+    // discount is already deducted by order service, tax is included
     pub amount: PayAmountDto,
-    // TODO, tax and discount
 }
 
 #[derive(Deserialize)]
@@ -44,8 +81,6 @@ pub struct ChargeReqOrderDto {
     // currency and exchange rate should be determined on creating
     // a new order, not on charging
     pub currency: CurrencyDto,
-    // TODO,
-    // - tax and discount
 }
 #[derive(Deserialize)]
 pub struct ChargeReqDto {
@@ -66,10 +101,18 @@ pub struct StripeCheckoutSessionRespDto {
     pub client_session: Option<String>, // for Stripe.js embedded checkout
 }
 #[derive(Serialize)]
+pub struct XenditEwalletChargeRespDto {
+    pub id: String,
+    // the wallet decides which of the URLs below is present
+    pub desktop_url: Option<String>,
+    pub mobile_url: Option<String>,
+    pub deeplink_url: Option<String>,
+}
+#[derive(Serialize)]
 #[serde(tag = "label")]
 pub enum PaymentMethodRespDto {
     Stripe(StripeCheckoutSessionRespDto),
-    // TODO, integrate with Wise (TransferWise) wallet
+    Xendit(XenditEwalletChargeRespDto),
 }
 #[derive(Serialize)]
 pub struct ChargeCreateRespDto {
@@ -99,6 +142,17 @@ pub struct ChargeOlineErrorDto {
     pub expired: Option<bool>,
     pub not_exist: bool,
 }
+#[derive(Serialize)]
+pub struct ChargeSellerErrorDto {
+    pub seller_id: u32,
+    // the seller has not onboarded with the processor of the chosen method
+    pub not_onboarded: bool,
+    // the seller onboarded but the processor does not enable the account
+    // to receive fund yet
+    pub not_enabled: bool,
+    // the processor cannot collect fund in this currency for the seller
+    pub currency: Option<CurrencyDto>,
+}
 
 #[derive(Serialize, Default)]
 pub struct ChargeRespErrorDto {
@@ -107,6 +161,7 @@ pub struct ChargeRespErrorDto {
     pub lines: Option<Vec<ChargeOlineErrorDto>>,
     pub currency: Option<CurrencyDto>, // the currency chosen by buyer on creating the order
     pub num_charges_exceed: Option<GenericRangeErrorDto>,
+    pub sellers: Option<Vec<ChargeSellerErrorDto>>,
 }
 
 #[derive(Serialize)]
@@ -152,6 +207,9 @@ pub struct StoreOnboardStripeReqDto {
 #[serde(tag = "processor")]
 pub enum StoreOnboardReqDto {
     Stripe(StoreOnboardStripeReqDto),
+    // Xendit invites the merchant by the email in store profile, the merchant
+    // completes the rest of the onboarding process in Xendit dashboard
+    Xendit,
 }
 
 #[derive(Serialize)]
@@ -163,6 +221,9 @@ pub enum StoreOnboardRespDto {
         url: Option<String>,
         expiry: Option<DateTime<Utc>>,
     },
+    Xendit {
+        status: String,
+    },
     Unknown,
 }
 
@@ -179,6 +240,10 @@ pub enum CapturePay3partyRespDto {
         amount: String,
         currency: CurrencyDto,
     },
+    Xendit {
+        amount: String,
+        currency: CurrencyDto,
+    },
 }
 
 #[derive(Serialize)]
@@ -199,6 +264,7 @@ impl StoreOnboardRespDto {
                 url,
                 expiry: _,
             } => disabled_reason.is_none() && url.is_none(),
+            Self::Xendit { status } => status.as_str() == "LIVE",
             Self::Unknown => false,
         }
     }
@@ -3,6 +3,7 @@ pub mod dto;
 mod onboard;
 mod refund;
 mod reporting;
+mod webhook;
 
 use actix_http::Method;
 use actix_web::body::BoxBody;
@@ -16,6 +17,7 @@ use charge::{capture_authorized_charge, create_charge, refresh_charge_status};
 use onboard::{onboard_store, track_onboarding_status};
 use refund::mechant_complete_refund;
 use reporting::report_charge_lines;
+use webhook::{stripe_webhook, xendit_webhook};
 
 pub struct AppRouteTable {
     pub version: String,
@@ -66,6 +68,14 @@ impl AppRouteTable {
                 "report_charge_lines".to_string(),
                 Route::new().method(Method::GET).to(report_charge_lines),
             ),
+            (
+                "stripe_webhook".to_string(),
+                Route::new().method(Method::POST).to(stripe_webhook),
+            ),
+            (
+                "xendit_webhook".to_string(),
+                Route::new().method(Method::POST).to(xendit_webhook),
+            ),
         ];
         HashMap::from(data)
     }
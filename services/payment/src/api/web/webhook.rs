@@ -0,0 +1,149 @@
+use std::boxed::Box;
+use std::sync::Arc;
+
+use actix_web::error::Error as ActixError;
+use actix_web::web::{Bytes, Data as WebData};
+use actix_web::{HttpRequest, HttpResponse, Result as ActixResult};
+
+use ecommerce_common::logging::{app_log_event, AppLogContext, AppLogLevel};
+
+use crate::adapter::datastore::AppDataStoreContext;
+use crate::adapter::processor::AppProcessorErrorReason;
+use crate::adapter::repository::{app_repo_processor_event, AbstractProcessorEventRepo};
+use crate::model::Label3party;
+use crate::usecase::{ProcessorWebhookUcError, ProcessorWebhookUcOk, ProcessorWebhookUseCase};
+use crate::AppSharedState;
+
+use super::charge::try_creating_charge_repo;
+use super::onboard::try_creating_merchant_repo;
+use super::RepoInitFailure;
+
+const HEADER_NAME_STRIPE_SIGNATURE: &str = "Stripe-Signature";
+const HEADER_NAME_XENDIT_CALLBACK_TOKEN: &str = "x-callback-token";
+
+async fn try_creating_event_repo(
+    dstore: Arc<AppDataStoreContext>,
+    logctx: Arc<AppLogContext>,
+) -> ActixResult<Box<dyn AbstractProcessorEventRepo>> {
+    app_repo_processor_event(dstore).await.map_err(|e_repo| {
+        app_log_event!(logctx, AppLogLevel::ERROR, "repo-init-error {:?}", e_repo);
+        ActixError::from(RepoInitFailure)
+    })
+}
+
+// Note, the request comes from 3rd-party processor without access token, the
+// signature in the header is the only way to authenticate the request.
+pub(super) async fn stripe_webhook(
+    req: HttpRequest,
+    payload: Bytes,
+    shr_state: WebData<AppSharedState>,
+) -> ActixResult<HttpResponse> {
+    let label = Label3party::Stripe;
+    webhook_common(label, HEADER_NAME_STRIPE_SIGNATURE, req, payload, shr_state).await
+}
+
+// Xendit sends the callback token configured in its dashboard, instead of
+// signing the payload
+pub(super) async fn xendit_webhook(
+    req: HttpRequest,
+    payload: Bytes,
+    shr_state: WebData<AppSharedState>,
+) -> ActixResult<HttpResponse> {
+    let label = Label3party::Xendit;
+    webhook_common(
+        label,
+        HEADER_NAME_XENDIT_CALLBACK_TOKEN,
+        req,
+        payload,
+        shr_state,
+    )
+    .await
+}
+
+async fn webhook_common(
+    label: Label3party,
+    header_name: &str,
+    req: HttpRequest,
+    payload: Bytes,
+    shr_state: WebData<AppSharedState>,
+) -> ActixResult<HttpResponse> {
+    let logctx = shr_state.log_context();
+    let signature = match req.headers().get(header_name) {
+        Some(v) => v.to_str().unwrap_or("").to_string(),
+        None => {
+            app_log_event!(logctx, AppLogLevel::WARNING, "missing-signature");
+            return Ok(HttpResponse::BadRequest().finish());
+        }
+    };
+    let dstore = shr_state.datastore();
+    let repo_c = try_creating_charge_repo(dstore.clone(), logctx.clone()).await?;
+    let repo_m = try_creating_merchant_repo(dstore.clone(), logctx.clone()).await?;
+    let repo_evt = try_creating_event_repo(dstore, logctx.clone()).await?;
+    let uc = ProcessorWebhookUseCase {
+        processors: shr_state.processor_context(),
+        repo_c,
+        repo_m,
+        repo_evt,
+        logctx: logctx.clone(),
+    };
+    let result = uc.execute(label, signature, payload.to_vec()).await;
+    // any response with status code other than 2xx lets the 3rd party
+    // notify the same event again later
+    let resp = match result {
+        Ok(v) => {
+            match v {
+                ProcessorWebhookUcOk::Applied => {}
+                ProcessorWebhookUcOk::Duplicate => {
+                    app_log_event!(logctx, AppLogLevel::DEBUG, "duplicate-event");
+                }
+                ProcessorWebhookUcOk::Ignored(detail) => {
+                    app_log_event!(logctx, AppLogLevel::INFO, "ignored: {detail}");
+                }
+            }
+            HttpResponse::Ok().finish()
+        }
+        Err(e) => match e {
+            ProcessorWebhookUcError::ThirdParty(e) => match &e.reason {
+                AppProcessorErrorReason::InvalidSignature(_) => {
+                    app_log_event!(logctx, AppLogLevel::WARNING, "{:?}", e);
+                    HttpResponse::BadRequest().finish()
+                }
+                // missing webhook secret is deployment error, the processor
+                // should keep retrying until the secret is configured
+                _others => {
+                    app_log_event!(logctx, AppLogLevel::ERROR, "{:?}", e);
+                    HttpResponse::ServiceUnavailable().finish()
+                }
+            },
+            ProcessorWebhookUcError::RepoOpFailure(e) => {
+                app_log_event!(logctx, AppLogLevel::ERROR, "{:?}", e);
+                HttpResponse::ServiceUnavailable().finish()
+            }
+            ProcessorWebhookUcError::ChargeIdDecode(code, detail) => {
+                app_log_event!(logctx, AppLogLevel::WARNING, "{:?}, {detail}", code);
+                HttpResponse::BadRequest().finish()
+            }
+            ProcessorWebhookUcError::MissingCharge(detail) => {
+                app_log_event!(logctx, AppLogLevel::WARNING, "missing-charge: {detail}");
+                HttpResponse::NotFound().finish()
+            }
+            ProcessorWebhookUcError::MissingPayout(store_id, charge_id) => {
+                app_log_event!(
+                    logctx,
+                    AppLogLevel::WARNING,
+                    "missing-payout: {store_id}, {charge_id}"
+                );
+                HttpResponse::NotFound().finish()
+            }
+            ProcessorWebhookUcError::CorruptedEvent(detail) => {
+                app_log_event!(logctx, AppLogLevel::ERROR, "corrupted-event: {detail}");
+                HttpResponse::UnprocessableEntity().finish()
+            }
+            ProcessorWebhookUcError::EventInProgress(evt_id) => {
+                app_log_event!(logctx, AppLogLevel::INFO, "event-in-progress: {evt_id}");
+                HttpResponse::Conflict().finish()
+            }
+        },
+    };
+    Ok(resp)
+} // end of fn webhook_common
@@ -13,7 +13,9 @@ use ecommerce_common::config::AppConfig;
 use ecommerce_common::error::{AppConfidentialityError, AppErrorCode};
 use ecommerce_common::logging::AppLogContext;
 
-use crate::adapter::cache::{app_cache_order_sync_lock, AbstractOrderSyncLockCache};
+use crate::adapter::cache::{
+    app_cache_order_sync_lock, AbstractOrderSyncLockCache, AppCacheInitError,
+};
 use crate::adapter::datastore::{AppDStoreError, AppDataStoreContext};
 use crate::adapter::processor::{
     app_processor_context, AbstractPaymentProcessor, AppProcessorError,
@@ -40,6 +42,8 @@ pub mod hard_limit {
     pub const CREATE_CHARGE_SECONDS_INTERVAL: u16 = 5u16;
     pub const RPC_WAIT_FOR_REPLY: u16 = 5u16;
     pub const CURRENCY_RATE_PRECISION: u32 = 8;
+    // the claim of a processor event older than this is considered abandoned
+    pub const SECONDS_PROCESSOR_EVENT_CLAIM: u16 = 180u16;
 }
 
 pub struct AppSharedState {
@@ -58,6 +62,7 @@ pub enum ShrStateInitProgress {
     DataStore,
     RpcContext,
     ExternalProcessor,
+    Cache,
     AuthKeyStore(AuthKeystoreError),
 }
 
@@ -89,6 +94,13 @@ impl From<AppProcessorError> for ShrStateInitError {
         }
     }
 }
+impl From<AppCacheInitError> for ShrStateInitError {
+    fn from(_value: AppCacheInitError) -> Self {
+        Self {
+            progress: ShrStateInitProgress::Cache,
+        }
+    }
+}
 impl From<AuthKeystoreError> for ShrStateInitError {
     fn from(detail: AuthKeystoreError) -> Self {
         Self {
@@ -122,9 +134,13 @@ impl AppSharedState {
         )?;
         let dstore =
             AppDataStoreContext::new(&cfg.api_server.data_store, cfdntl.clone(), logctx.clone())?;
+        let ordersync_lockset = app_cache_order_sync_lock(
+            cfg.api_server.cache.as_ref(),
+            cfdntl.clone(),
+            logctx.clone(),
+        )?;
         let _processors =
             app_processor_context(&cfg.api_server.third_parties, cfdntl, logctx.clone())?;
-        let ordersync_lockset = app_cache_order_sync_lock();
         let auth_keys = AppAuthKeystore::try_create(&cfg.api_server.auth)?;
         Ok(Self {
             _config: Arc::new(cfg),
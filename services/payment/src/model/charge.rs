@@ -14,9 +14,9 @@ use ecommerce_common::error::AppErrorCode;
 use ecommerce_common::model::BaseProductIdentity;
 
 use super::{
-    Charge3partyStripeModel, OrderCurrencySnapshot, OrderLineModel, OrderLineModelSet,
-    PayLineAmountModel, PayoutAmountModel, PayoutModelError, RefundLineReqResolutionModel,
-    RefundReqResolutionModel,
+    Charge3partyStripeModel, Charge3partyXenditModel, OrderCurrencySnapshot, OrderLineModel,
+    OrderLineModelSet, PayLineAmountModel, PayoutAmountModel, PayoutModelError,
+    RefundLineReqResolutionModel, RefundReqResolutionModel,
 };
 use crate::api::web::dto::{
     ChargeAmountOlineDto, ChargeOlineErrorDto, ChargeRefreshRespDto, ChargeReqOrderDto,
@@ -27,6 +27,7 @@ use crate::hard_limit::{CREATE_CHARGE_SECONDS_INTERVAL, SECONDS_ORDERLINE_DISCAR
 pub enum Charge3partyModel {
     Unknown,
     Stripe(Charge3partyStripeModel),
+    Xendit(Charge3partyXenditModel),
 }
 
 mod token_inner {
@@ -86,6 +87,8 @@ pub struct ChargeBuyerModel {
     pub meta: ChargeBuyerMetaModel,
     pub currency_snapshot: HashMap<u32, OrderCurrencySnapshot>,
     pub lines: Vec<ChargeLineBuyerModel>,
+    // shipping fee of each seller covered by this charge, in buyer's currency
+    pub shipping: HashMap<u32, Decimal>,
 }
 
 impl BuyerPayInState {
@@ -131,18 +134,37 @@ impl Charge3partyModel {
         match self {
             Self::Unknown => Some(false),
             Self::Stripe(m) => m.pay_in_comfirmed(),
+            Self::Xendit(m) => m.pay_in_comfirmed(),
         }
     }
     fn status_dto(&self) -> ChargeStatusDto {
         match self {
             Self::Unknown => ChargeStatusDto::UnknownPsp,
             Self::Stripe(m) => m.status_dto(),
+            Self::Xendit(m) => m.status_dto(),
         }
     }
     pub(super) fn clone(&self) -> Self {
         match self {
             Self::Unknown => Self::Unknown,
             Self::Stripe(m) => Self::Stripe(m.inner_clone()),
+            Self::Xendit(m) => Self::Xendit(m.inner_clone()),
+        }
+    }
+    // the following methods check identifiers which 3rd-party processor refers
+    // to in its own events , e.g. checkout session or payment intent in Stripe
+    pub(crate) fn session_matched(&self, session_id: &str) -> bool {
+        match self {
+            Self::Unknown => false,
+            Self::Stripe(m) => m.checkout_session_id.as_str() == session_id,
+            Self::Xendit(m) => m.charge_id.as_str() == session_id,
+        }
+    }
+    pub(crate) fn payment_matched(&self, payment_ref: &str) -> bool {
+        match self {
+            Self::Unknown => false,
+            Self::Stripe(m) => m.payment_intent_id.as_str() == payment_ref,
+            Self::Xendit(m) => m.charge_id.as_str() == payment_ref,
         }
     }
 } // end of impl Charge3partyModel
@@ -261,6 +283,7 @@ impl TryFrom<(OrderLineModelSet, ChargeReqOrderDto)> for ChargeBuyerModel {
             create_time: _,
             num_charges: _,
             currency_snapshot,
+            shipping: o_shipping,
         } = ms;
         let now = Local::now().to_utc();
         if oid.as_str() != req_oid.as_str() {
@@ -296,10 +319,18 @@ impl TryFrom<(OrderLineModelSet, ChargeReqOrderDto)> for ChargeBuyerModel {
             .collect::<Vec<_>>();
 
         if err_lines.is_empty() {
+            // the shipping fee of a seller is charged along with the first
+            // successful charge which contains the seller's lines
+            let shipping = o_shipping
+                .into_iter()
+                .filter(|s| !s.paid && lines.iter().any(|l| l.pid.store_id == s.seller_id))
+                .map(|s| (s.seller_id, s.amount))
+                .collect::<HashMap<_, _>>();
             Ok(Self {
                 meta: ChargeBuyerMetaModel::from((oid, buyer_id)),
                 currency_snapshot,
                 lines,
+                shipping,
             })
         } else {
             Err(ChargeRespErrorDto {
@@ -316,6 +347,13 @@ impl ChargeBuyerModel {
         let key = self.meta.owner();
         self.currency_snapshot.get(&key).cloned()
     }
+    /// distinct sellers in the charge, in ascending order
+    pub(crate) fn seller_ids(&self) -> Vec<u32> {
+        let mut out = self.lines.iter().map(|v| v.id().0).collect::<Vec<_>>();
+        out.sort();
+        out.dedup();
+        out
+    }
     pub(super) fn get_seller_currency(&self, seller_id: u32) -> Option<OrderCurrencySnapshot> {
         self.currency_snapshot.get(&seller_id).cloned()
     }
@@ -329,6 +367,16 @@ impl ChargeBuyerModel {
             .ok_or("target-rate-overflow".to_string())
     }
 
+    /// total amount to charge in buyer's currency, including shipping fees
+    pub fn total_amount(&self) -> Decimal {
+        let amt_lines = self
+            .lines
+            .iter()
+            .map(|v| v.amount_orig().total)
+            .sum::<Decimal>();
+        amt_lines + self.shipping.values().sum::<Decimal>()
+    }
+
     fn estimate_avail_lines_amount(&self, seller_id: u32) -> Decimal {
         self.lines
             .iter()
@@ -338,6 +386,13 @@ impl ChargeBuyerModel {
             .sum::<Decimal>()
     }
 
+    fn shipping_amount(&self, seller_id: u32) -> Decimal {
+        self.shipping
+            .get(&seller_id)
+            .copied()
+            .unwrap_or(Decimal::ZERO)
+    }
+
     pub(super) fn capture_amount(
         &self,
         seller_id: u32,
@@ -350,7 +405,8 @@ impl ChargeBuyerModel {
             .get_buyer_currency()
             .ok_or("missing-currency-buyer".to_string())
             .map_err(|d| PayoutModelError::AmountEstimate(AppErrorCode::DataCorruption, d))?;
-        let tot_amt_buyer = self.estimate_avail_lines_amount(seller_id);
+        let tot_amt_buyer =
+            self.estimate_avail_lines_amount(seller_id) + self.shipping_amount(seller_id);
         let args = (tot_amt_buyer, currency_seller, currency_buyer);
         PayoutAmountModel::try_from(args)
     }
@@ -77,6 +77,50 @@ pub struct Payout3partyStripeModel {
     amount: Option<Decimal>,
 }
 
+#[allow(clippy::upper_case_acronyms)]
+#[rustfmt::skip]
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub enum XenditChargeStatusModel {
+    PENDING, SUCCEEDED, FAILED, VOIDED, REFUNDED,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Charge3partyXenditModel {
+    pub charge_id: String, // identifier of e-wallet charge object, e.g. `ewc_xxx`
+    pub reference_id: String, // serialised charge token, applied in payout workflow
+    pub channel_code: String,
+    pub currency: CurrencyDto,
+    pub status: XenditChargeStatusModel,
+    pub expiry: DateTime<Utc>,
+}
+
+#[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+#[rustfmt::skip]
+#[derive(Serialize, Deserialize, Debug)]
+pub enum XenditAccountStatusModel {
+    INVITED, REGISTERED, AWAITING_DOCS, LIVE, SUSPENDED,
+}
+#[derive(Serialize, Deserialize)]
+pub struct Merchant3partyXenditModel {
+    // map to sub-account (managed account) in xenPlatform
+    pub id: String,
+    pub email: String,
+    pub business_name: String,
+    pub status: XenditAccountStatusModel,
+    pub created: DateTime<Utc>,
+}
+
+#[derive(Clone)]
+pub struct Payout3partyXenditModel {
+    reference: String, // `reference_id` field of e-wallet charge object
+    acct_id: String,   // identifier of sub-account in xenPlatform
+    // pay-in fund is kept in platform balance with the currency buyer chose,
+    // the fund is transferred to the sub-account without conversion
+    currency: CurrencyDto,
+    transfer_id: Option<String>,
+    amount: Option<Decimal>,
+}
+
 mod stripe_common {
     use super::{CurrencyDto, Decimal};
 
@@ -85,26 +129,36 @@ mod stripe_common {
     /// subunit (minor unit) below
     /// https://en.wikipedia.org/wiki/List_of_circulating_currencies#T
     /// https://en.wikipedia.org/wiki/New_Taiwan_dollar
-    fn subunit_multiplier(given: CurrencyDto) -> i64 {
-        match given {
-            CurrencyDto::INR
-            | CurrencyDto::IDR
-            | CurrencyDto::TWD
-            | CurrencyDto::THB
-            | CurrencyDto::USD => 100,
-            CurrencyDto::Unknown => 1,
-        }
+    /// https://docs.stripe.com/currencies#zero-decimal
+    fn subunit_multiplier(given: &CurrencyDto) -> i64 {
+        10i64.pow(given.amount_fraction_scale())
     }
     pub(super) fn amount_represent(
         orig: Decimal,
         currency: CurrencyDto,
     ) -> Result<Decimal, (Decimal, i64)> {
-        let m = subunit_multiplier(currency);
+        let m = subunit_multiplier(&currency);
         let m2 = Decimal::new(m, 0);
         orig.checked_mul(m2).ok_or((orig, m))
     }
 } // end of mod stripe_common
 
+mod xendit_common {
+    use super::{CurrencyDto, Decimal};
+
+    /// Xendit accepts only integral amount in IDR, although the currency
+    /// has 2 digits of minor unit in ISO 4217
+    fn amount_scale(given: &CurrencyDto) -> u32 {
+        match given {
+            CurrencyDto::IDR => 0,
+            _others => given.amount_fraction_scale(),
+        }
+    }
+    pub(super) fn amount_truncate(orig: Decimal, currency: &CurrencyDto) -> Decimal {
+        orig.trunc_with_scale(amount_scale(currency))
+    }
+} // end of mod xendit_common
+
 impl StripeCheckoutPaymentStatusModel {
     fn status_dto(&self) -> ChargeStatusDto {
         // This service always configures payment mode to Stripe API server,
@@ -180,6 +234,11 @@ impl Merchant3partyStripeModel {
         // , this payment application hasn't supported that yet.
         self.payouts_enabled && self.tos_accepted.is_some() && tx_active
     }
+    pub(super) fn can_accept_pay_in(&self) -> bool {
+        // the fund is transferred to the connected account after the charge
+        // completes, Stripe has to enable both of charges and payouts
+        self.charges_enabled && self.payouts_enabled
+    }
 }
 
 type PayoutStripeCvtArgs = (String, String, Option<String>, Option<Decimal>);
@@ -241,3 +300,135 @@ impl Payout3partyStripeModel {
         i64::try_from(mantissa).map_err(|e| format!("amount-too-large: {mantissa}, {:?}", e))
     }
 } // end of impl Payout3partyStripeModel
+
+impl XenditChargeStatusModel {
+    fn status_dto(&self) -> ChargeStatusDto {
+        match self {
+            Self::PENDING => ChargeStatusDto::PspProcessing,
+            Self::SUCCEEDED | Self::REFUNDED => ChargeStatusDto::InternalSyncing,
+            Self::FAILED | Self::VOIDED => ChargeStatusDto::PspRefused,
+        }
+    }
+}
+impl Charge3partyXenditModel {
+    pub(super) fn status_dto(&self) -> ChargeStatusDto {
+        if self.is_expired() {
+            ChargeStatusDto::SessionExpired
+        } else {
+            self.status.status_dto()
+        }
+    }
+    pub(super) fn pay_in_comfirmed(&self) -> Option<bool> {
+        match &self.status {
+            XenditChargeStatusModel::SUCCEEDED | XenditChargeStatusModel::REFUNDED => Some(true),
+            XenditChargeStatusModel::FAILED | XenditChargeStatusModel::VOIDED => Some(false),
+            XenditChargeStatusModel::PENDING => {
+                if self.is_expired() {
+                    Some(false)
+                } else {
+                    None
+                }
+            }
+        }
+    }
+    // Xendit does not report expiry of pending e-wallet charge, this service
+    // gives up the charge after the expiry time it decided on creation
+    fn is_expired(&self) -> bool {
+        let t_now = Local::now().to_utc();
+        matches!(self.status, XenditChargeStatusModel::PENDING) && self.expiry < t_now
+    }
+    pub(crate) fn amount_represent(orig: Decimal, c: &CurrencyDto) -> Result<Decimal, String> {
+        let out = xendit_common::amount_truncate(orig, c);
+        if out == orig {
+            Ok(out)
+        } else {
+            Err(format!(
+                "fraction-unsupported, orig:{orig}, currency:{}",
+                c.as_str()
+            ))
+        }
+    }
+    pub(crate) fn amount_truncate(orig: Decimal, c: &CurrencyDto) -> Decimal {
+        xendit_common::amount_truncate(orig, c)
+    }
+    pub(super) fn inner_clone(&self) -> Self {
+        Self {
+            charge_id: self.charge_id.clone(),
+            reference_id: self.reference_id.clone(),
+            channel_code: self.channel_code.clone(),
+            currency: self.currency.clone(),
+            status: self.status.clone(),
+            expiry: self.expiry,
+        }
+    }
+} // end of impl Charge3partyXenditModel
+
+impl Merchant3partyXenditModel {
+    pub(super) fn can_perform_payout(&self) -> bool {
+        // Xendit verifies the documents submitted by the merchant, the
+        // sub-account becomes `LIVE` after the verification is done
+        matches!(self.status, XenditAccountStatusModel::LIVE)
+    }
+}
+
+type PayoutXenditCvtArgs = (String, String, CurrencyDto, Option<String>, Option<Decimal>);
+
+impl From<PayoutXenditCvtArgs> for Payout3partyXenditModel {
+    #[rustfmt::skip]
+    fn from(value: PayoutXenditCvtArgs) -> Self {
+        let (reference, acct_id, currency, transfer_id, amount) = value;
+        Self { reference, acct_id, currency, transfer_id, amount }
+    }
+}
+
+impl Payout3partyXenditModel {
+    pub(super) fn new(c3x: &Charge3partyXenditModel, m3x: &Merchant3partyXenditModel) -> Self {
+        Self {
+            reference: c3x.reference_id.clone(),
+            acct_id: m3x.id.clone(),
+            currency: c3x.currency.clone(),
+            transfer_id: None,
+            amount: None,
+        }
+    }
+    pub(super) fn validate(
+        &self,
+        c3x: &Charge3partyXenditModel,
+        m3x: &Merchant3partyXenditModel,
+    ) -> Result<(), String> {
+        if self.reference.as_str() != c3x.reference_id.as_str() {
+            Err("reference".to_string())
+        } else if self.acct_id.as_str() != m3x.id.as_str() {
+            Err("account-id".to_string())
+        } else if self.currency != c3x.currency {
+            Err("currency".to_string())
+        } else {
+            Ok(())
+        }
+    }
+    pub(crate) fn reference(&self) -> &str {
+        self.reference.as_str()
+    }
+    pub(crate) fn sub_account(&self) -> &str {
+        self.acct_id.as_str()
+    }
+    pub fn currency(&self) -> &CurrencyDto {
+        &self.currency
+    }
+    pub(crate) fn transfer_id(&self) -> Option<&str> {
+        self.transfer_id.as_deref()
+    }
+    pub(crate) fn set_transfer_id(&mut self, value: String) {
+        self.transfer_id = Some(value);
+    }
+    pub fn amount(&self) -> Option<Decimal> {
+        self.amount
+    }
+    pub(crate) fn set_amount(&mut self, value: Decimal) {
+        self.amount = Some(value);
+    }
+    pub(crate) fn amount_represent(orig: Decimal, currency: &CurrencyDto) -> Decimal {
+        // the remaining fraction is kept in the platform balance
+        xendit_common::amount_truncate(orig, currency)
+    }
+} // end of impl Payout3partyXenditModel
@@ -4,7 +4,7 @@ use chrono::{DateTime, Local, Utc};
 
 use ecommerce_common::api::rpc::dto::{StoreProfileReplicaDto, StoreStaffRepDto};
 
-use super::external_processor::Merchant3partyStripeModel;
+use super::external_processor::{Merchant3partyStripeModel, Merchant3partyXenditModel};
 
 #[derive(Debug)]
 pub enum MerchantModelError {
@@ -16,6 +16,7 @@ pub enum MerchantModelError {
 
 pub enum Merchant3partyModel {
     Stripe(Merchant3partyStripeModel),
+    Xendit(Merchant3partyXenditModel),
     Unknown,
 }
 
@@ -108,7 +109,32 @@ impl Merchant3partyModel {
     pub(super) fn can_perform_payout(&self) -> bool {
         match self {
             Self::Stripe(s) => s.can_perform_payout(),
+            Self::Xendit(s) => s.can_perform_payout(),
             Self::Unknown => false,
         }
     }
+
+    /// whether the 3rd party is ready to collect fund for the merchant
+    pub(crate) fn can_accept_pay_in(&self) -> bool {
+        match self {
+            Self::Stripe(s) => s.can_accept_pay_in(),
+            Self::Xendit(s) => s.can_perform_payout(),
+            Self::Unknown => false,
+        }
+    }
+
+    /// keep the data which is not carried in the event notified by 3rd party,
+    /// such as the link for onboarding process in Stripe, the link is still
+    /// useful until the merchant is able to receive payout.
+    pub fn merge_notified(self, saved: Self) -> Self {
+        match (self, saved) {
+            (Self::Stripe(mut s_new), Self::Stripe(s_old)) => {
+                if s_new.update_link.is_none() && !s_new.can_perform_payout() {
+                    s_new.update_link = s_old.update_link;
+                }
+                Self::Stripe(s_new)
+            }
+            (notified, _others) => notified,
+        }
+    }
 }
@@ -11,23 +11,27 @@ use std::str::FromStr;
 
 use ecommerce_common::api::dto::{CurrencyDto, PayAmountDto};
 
-use crate::api::web::dto::StoreOnboardReqDto;
+use crate::api::web::dto::{PaymentMethodReqDto, StoreOnboardReqDto};
 
 pub use self::charge::{
     BuyerPayInState, Charge3partyModel, ChargeBuyerMetaModel, ChargeBuyerModel,
     ChargeLineBuyerModel, ChargeRefundLineMap, ChargeRefundMap, ChargeToken,
 };
 pub use self::external_processor::{
-    Charge3partyStripeModel, Merchant3partyStripeModel, Payout3partyStripeModel,
+    Charge3partyStripeModel, Charge3partyXenditModel, Merchant3partyStripeModel,
+    Merchant3partyXenditModel, Payout3partyStripeModel, Payout3partyXenditModel,
     StripeAccountCapabilityModel, StripeAccountCapableState, StripeAccountLinkModel,
     StripeAccountSettingModel, StripeCheckoutPaymentStatusModel, StripeSessionStatusModel,
+    XenditAccountStatusModel, XenditChargeStatusModel,
 };
 pub use self::merchant::{Merchant3partyModel, MerchantModelError, MerchantProfileModel};
 pub use self::order_replica::{
-    OrderCurrencySnapshot, OrderLineModel, OrderLineModelSet, OrderModelError,
+    OrderCurrencySnapshot, OrderLineModel, OrderLineModelSet, OrderModelError, OrderShippingModel,
 };
 pub(crate) use self::payout::PayoutInnerModel;
-pub use self::payout::{Payout3partyModel, PayoutAmountModel, PayoutModel, PayoutModelError};
+pub use self::payout::{
+    Payout3partyModel, PayoutAmountModel, PayoutModel, PayoutModelError, PayoutTransferStateModel,
+};
 use self::refund::RefundLineReqResolutionModel;
 pub(crate) use self::refund::RefundReqRslvInnerModel;
 pub use self::refund::{
@@ -64,6 +68,7 @@ pub struct PayLineAmountModel {
 #[derive(Copy, Clone)]
 pub enum Label3party {
     Stripe,
+    Xendit,
 }
 
 impl TryFrom<(u32, PayAmountDto, CurrencyDto)> for PayLineAmountModel {
@@ -118,6 +123,7 @@ impl<'a> TryFrom<&'a str> for Label3party {
     fn try_from(value: &'a str) -> Result<Self, Self::Error> {
         match value {
             "Stripe" => Ok(Self::Stripe),
+            "Xendit" => Ok(Self::Xendit),
             _others => Err(value),
         }
     }
@@ -127,6 +133,16 @@ impl<'a> From<&'a StoreOnboardReqDto> for Label3party {
     fn from(value: &'a StoreOnboardReqDto) -> Self {
         match value {
             StoreOnboardReqDto::Stripe(_) => Self::Stripe,
+            StoreOnboardReqDto::Xendit => Self::Xendit,
+        }
+    }
+}
+
+impl<'a> From<&'a PaymentMethodReqDto> for Label3party {
+    fn from(value: &'a PaymentMethodReqDto) -> Self {
+        match value {
+            PaymentMethodReqDto::Stripe(_) => Self::Stripe,
+            PaymentMethodReqDto::Xendit(_) => Self::Xendit,
         }
     }
 }
@@ -136,6 +152,7 @@ impl<'a> TryFrom<&'a Charge3partyModel> for Label3party {
     fn try_from(value: &'a Charge3partyModel) -> Result<Self, Self::Error> {
         match value {
             Charge3partyModel::Stripe(_) => Ok(Self::Stripe),
+            Charge3partyModel::Xendit(_) => Ok(Self::Xendit),
             Charge3partyModel::Unknown => Err("unknown".to_string()),
         }
     }
@@ -145,6 +162,22 @@ impl<'a> From<&'a Payout3partyModel> for Label3party {
     fn from(value: &'a Payout3partyModel) -> Self {
         match value {
             Payout3partyModel::Stripe(_) => Self::Stripe,
+            Payout3partyModel::Xendit(_) => Self::Xendit,
+        }
+    }
+}
+
+impl Label3party {
+    /// whether the processor is able to collect fund from buyers in the
+    /// given currency, then pay out to sellers
+    pub fn support_currency(&self, given: &CurrencyDto) -> bool {
+        match self {
+            Self::Stripe => true,
+            // see the currency of each e-wallet channel
+            Self::Xendit => matches!(
+                given,
+                CurrencyDto::IDR | CurrencyDto::PHP | CurrencyDto::THB | CurrencyDto::MYR
+            ),
         }
     }
 }
@@ -153,6 +186,7 @@ impl ToString for Label3party {
     fn to_string(&self) -> String {
         let s = match self {
             Self::Stripe => "Stripe",
+            Self::Xendit => "Xendit",
         };
         s.to_string()
     }
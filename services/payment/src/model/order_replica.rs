@@ -7,7 +7,7 @@ use rust_decimal::Decimal;
 
 use ecommerce_common::api::dto::{
     CurrencyDto, CurrencySnapshotDto, GenericRangeErrorDto, OrderCurrencySnapshotDto,
-    OrderLinePayDto, OrderSellerCurrencyDto,
+    OrderLinePayDto, OrderSellerCurrencyDto, OrderShippingPayDto,
 };
 use ecommerce_common::model::BaseProductIdentity;
 
@@ -23,6 +23,8 @@ pub enum OrderModelError {
     MissingActorsCurrency(Vec<u32>),
     MissingExRate(CurrencyDto),
     CorruptedExRate(CurrencyDto, String),
+    InvalidShippingFee(u32, String),
+    ShippingSellerNotFound(u32),
 }
 
 pub struct OrderLineModel {
@@ -33,6 +35,14 @@ pub struct OrderLineModel {
     pub reserved_until: DateTime<Utc>,
 } //TODO, make all fields private
 
+// shipping fee estimated by order service for each seller, in buyer's currency
+pub struct OrderShippingModel {
+    pub seller_id: u32,
+    pub amount: Decimal,
+    // whether the fee has been paid in any completed charge of the order
+    pub paid: bool,
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct OrderCurrencySnapshot {
     pub label: CurrencyDto,
@@ -49,6 +59,7 @@ pub struct OrderLineModelSet {
     //   in buyer or sellers business.
     // - note current base currency in this project defaults to USD
     pub currency_snapshot: HashMap<u32, OrderCurrencySnapshot>,
+    pub shipping: Vec<OrderShippingModel>,
 }
 
 #[rustfmt::skip]
@@ -59,7 +70,7 @@ impl TryFrom<(OrderLinePayDto, CurrencyDto)> for OrderLineModel {
         let (oline, currency_label) = value;
         let OrderLinePayDto {
             seller_id, product_id, reserved_until, quantity,
-            amount: amount_dto, attr_set_seq,
+            amount: amount_dto, attr_set_seq, discount: _, tax: _,
         } = oline;
         let pid = BaseProductIdentity {store_id: seller_id, product_id};
         let rsv_parse_result = DateTime::parse_from_rfc3339(reserved_until.as_str());
@@ -109,6 +120,22 @@ impl OrderLineModel {
     }
 } // end of impl OrderLineModel
 
+impl TryFrom<OrderShippingPayDto> for OrderShippingModel {
+    type Error = OrderModelError;
+    fn try_from(value: OrderShippingPayDto) -> Result<Self, Self::Error> {
+        let OrderShippingPayDto { seller_id, amount } = value;
+        let amount = Decimal::from_str(amount.as_str())
+            .ok()
+            .filter(|v| !v.is_sign_negative())
+            .ok_or(OrderModelError::InvalidShippingFee(seller_id, amount))?;
+        Ok(Self {
+            seller_id,
+            amount,
+            paid: false,
+        })
+    }
+}
+
 impl TryFrom<(CurrencyDto, &Vec<CurrencySnapshotDto>)> for OrderCurrencySnapshot {
     type Error = OrderModelError;
     fn try_from(value: (CurrencyDto, &Vec<CurrencySnapshotDto>)) -> Result<Self, Self::Error> {
@@ -213,9 +240,42 @@ impl TryFrom<(String, u32, Vec<OrderLinePayDto>, OrderCurrencySnapshotDto)> for
                 currency_snapshot,
                 create_time: Local::now().to_utc(),
                 num_charges: 0,
+                shipping: Vec::new(),
             })
         } else {
             Err(errors)
         }
     } // end of fn try-from
 } // end of impl try-from for OrderLineModelSet
+
+impl OrderLineModelSet {
+    // each shipping fee has to refer to a seller who has lines in the order,
+    // free delivery is not recorded
+    pub fn try_update_shipping(
+        &mut self,
+        data: Vec<OrderShippingPayDto>,
+    ) -> Result<(), Vec<OrderModelError>> {
+        let mut errors = Vec::new();
+        let shipping = data
+            .into_iter()
+            .filter_map(|d| {
+                let seller_id = d.seller_id;
+                if self.lines.iter().any(|l| l.pid.store_id == seller_id) {
+                    OrderShippingModel::try_from(d)
+                        .map_err(|e| errors.push(e))
+                        .ok()
+                } else {
+                    errors.push(OrderModelError::ShippingSellerNotFound(seller_id));
+                    None
+                }
+            })
+            .filter(|m| !m.amount.is_zero())
+            .collect::<Vec<_>>();
+        if errors.is_empty() {
+            self.shipping = shipping;
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+} // end of impl OrderLineModelSet
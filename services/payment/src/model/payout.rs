@@ -6,7 +6,7 @@ use ecommerce_common::error::AppErrorCode;
 
 use super::{
     Charge3partyModel, ChargeBuyerMetaModel, ChargeBuyerModel, Merchant3partyModel,
-    MerchantProfileModel, OrderCurrencySnapshot, Payout3partyStripeModel,
+    MerchantProfileModel, OrderCurrencySnapshot, Payout3partyStripeModel, Payout3partyXenditModel,
 };
 use crate::api::web::dto::CapturePay3partyRespDto;
 use crate::hard_limit::CURRENCY_RATE_PRECISION;
@@ -26,6 +26,7 @@ pub enum PayoutModelError {
 
 pub enum Payout3partyModel {
     Stripe(Payout3partyStripeModel),
+    Xendit(Payout3partyXenditModel),
 }
 
 pub struct PayoutAmountModel {
@@ -55,6 +56,13 @@ pub struct PayoutModel {
     _p3pty: Payout3partyModel,
 }
 
+/// transfer state notified by 3rd party after the payout was made
+pub struct PayoutTransferStateModel {
+    pub transfer_id: String,
+    pub reversed: bool,
+    pub notified_time: DateTime<Utc>,
+}
+
 #[rustfmt::skip]
 type PayoutModelCvtArgs2 = (
     u32, DateTime<Utc>, u32, DateTime<Utc>, String,
@@ -130,6 +138,13 @@ impl TryFrom<PayoutModelCvtArgs> for PayoutModel {
 } // end of impl PayoutModel
 
 impl PayoutModel {
+    pub fn transfer_matched(&self, transfer_id: &str) -> bool {
+        match &self._p3pty {
+            Payout3partyModel::Stripe(s) => s.transfer_id() == Some(transfer_id),
+            Payout3partyModel::Xendit(s) => s.transfer_id() == Some(transfer_id),
+        }
+    }
+
     fn validate_charge_meta(&self, c_meta: &ChargeBuyerMetaModel) -> Result<(), PayoutModelError> {
         let id0 = self._inner.buyer_id;
         let id1 = c_meta.owner();
@@ -306,6 +321,10 @@ impl<'a, 'b> TryFrom<Payout3ptyCvtArgs<'a, 'b>> for Payout3partyModel {
                 let inner = Payout3partyStripeModel::new(cs, ms);
                 Ok(Self::Stripe(inner))
             }
+            (Charge3partyModel::Xendit(cx), Merchant3partyModel::Xendit(mx)) => {
+                let inner = Payout3partyXenditModel::new(cx, mx);
+                Ok(Self::Xendit(inner))
+            }
             _others => Err(PayoutModelError::Init3partyFailure),
         }
     }
@@ -317,6 +336,10 @@ impl<'a> From<&'a Payout3partyModel> for CapturePay3partyRespDto {
                 amount: s.amount().unwrap().to_string(),
                 currency: CurrencyDto::USD,
             },
+            Payout3partyModel::Xendit(s) => Self::Xendit {
+                amount: s.amount().unwrap().to_string(),
+                currency: s.currency().clone(),
+            },
         }
     }
 }
@@ -333,6 +356,11 @@ impl Payout3partyModel {
                     .map_err(PayoutModelError::Invalid3partyParams)?;
                 Ok(Self::Stripe(ps.clone()))
             }
+            (Self::Xendit(px), Charge3partyModel::Xendit(cx), Merchant3partyModel::Xendit(mx)) => {
+                px.validate(cx, mx)
+                    .map_err(PayoutModelError::Invalid3partyParams)?;
+                Ok(Self::Xendit(px.clone()))
+            }
             _others => {
                 let d = "mismatch".to_string();
                 Err(PayoutModelError::Invalid3partyParams(d))
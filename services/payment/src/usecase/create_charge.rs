@@ -1,15 +1,16 @@
 use std::boxed::Box;
 use std::sync::Arc;
 
-use chrono::{DurationRound, Local, TimeDelta};
-use ecommerce_common::api::dto::GenericRangeErrorDto;
+use chrono::{DateTime, DurationRound, Local, TimeDelta, Utc};
+use ecommerce_common::api::dto::{CurrencyDto, GenericRangeErrorDto};
 use ecommerce_common::api::rpc::dto::{OrderReplicaPaymentDto, OrderReplicaPaymentReqDto};
 use ecommerce_common::api::web::dto::BillingErrorDto;
+use ecommerce_common::logging::{app_log_event, AppLogContext, AppLogLevel};
 use ecommerce_common::model::order::BillingModel;
 
 use crate::adapter::cache::{AbstractOrderSyncLockCache, OrderSyncLockError};
 use crate::adapter::processor::{AbstractPaymentProcessor, AppProcessorError};
-use crate::adapter::repository::{AbstractChargeRepo, AppRepoError};
+use crate::adapter::repository::{AbstractChargeRepo, AbstractMerchantRepo, AppRepoError};
 use crate::adapter::rpc::{AbstractRpcContext, AppRpcClientRequest, AppRpcCtxError};
 use crate::api::web::dto::{
     ChargeCreateRespDto, ChargeReqDto, ChargeRespErrorDto, PaymentMethodErrorReason,
@@ -17,6 +18,9 @@ use crate::api::web::dto::{
 use crate::model::{ChargeBuyerModel, OrderLineModelSet, OrderModelError};
 use crate::{AppAuthPermissionCode, AppAuthQuotaMatCode, AppAuthedClaim};
 
+use super::route_pay_method::{PayInMethodRouter, PayInRouteError};
+use super::sync_order_pay::{OrderAppSyncError, OrderAppSyncHandler};
+
 // TODO, consider to add debug function for logging purpose
 pub enum ChargeCreateUcError {
     OrderOwnerMismatch,                   // client error, e.g. status code 403
@@ -69,6 +73,27 @@ impl From<Vec<OrderModelError>> for ChargeCreateUcError {
         Self::RpcOlineParseError(value)
     }
 }
+impl From<PayInRouteError> for ChargeCreateUcError {
+    fn from(value: PayInRouteError) -> Self {
+        match value {
+            PayInRouteError::DataStore(e) => Self::DataStoreError(e),
+            PayInRouteError::MissingCurrency(_usr_id) => {
+                let e = ChargeRespErrorDto {
+                    currency: Some(CurrencyDto::Unknown),
+                    ..Default::default()
+                };
+                Self::ClientBadRequest(e)
+            }
+            PayInRouteError::SellerUnsupported(sellers) => {
+                let e = ChargeRespErrorDto {
+                    sellers: Some(sellers),
+                    ..Default::default()
+                };
+                Self::ClientBadRequest(e)
+            }
+        }
+    }
+}
 impl From<ChargeRespErrorDto> for ChargeCreateUcError {
     fn from(value: ChargeRespErrorDto) -> Self {
         Self::ClientBadRequest(value)
@@ -80,6 +105,8 @@ pub struct ChargeCreateUseCase {
     pub rpc_ctx: Arc<Box<dyn AbstractRpcContext>>,
     pub ordersync_lockset: Arc<Box<dyn AbstractOrderSyncLockCache>>,
     pub repo: Box<dyn AbstractChargeRepo>,
+    pub repo_m: Box<dyn AbstractMerchantRepo>,
+    pub logctx: Arc<AppLogContext>,
 }
 
 impl ChargeCreateUseCase {
@@ -98,8 +125,6 @@ impl ChargeCreateUseCase {
             let d = self.rpc_sync_order(usr_id, oid).await?;
             self.try_save_order(usr_id, oid, d).await?
         };
-        // TODO, verify whether all merchants/shops in the client request support
-        // the specific 3rd-party processor
         let resp = self
             .try_execute_processor(validated_order, req_body)
             .await?;
@@ -146,10 +171,10 @@ impl ChargeCreateUseCase {
         usr_id: u32,
         oid: &str,
     ) -> Result<OrderReplicaPaymentDto, ChargeCreateUcError> {
-        let success = self.ordersync_lockset.acquire(usr_id, oid).await?;
-        if success {
+        let maybe_token = self.ordersync_lockset.acquire(usr_id, oid).await?;
+        if let Some(token) = maybe_token {
             let out = self._rpc_sync_order(oid, usr_id).await;
-            self.ordersync_lockset.release(usr_id, oid).await?;
+            self.ordersync_lockset.release(usr_id, oid, token).await?;
             out
         } else {
             Err(ChargeCreateUcError::LoadOrderConflict)
@@ -191,9 +216,11 @@ impl ChargeCreateUseCase {
             lines,
             billing,
             currency,
+            shipping,
         } = rpc_data;
         let billing = BillingModel::try_from(billing)?;
-        let olines = OrderLineModelSet::try_from((oid, usr_id, lines, currency))?;
+        let mut olines = OrderLineModelSet::try_from((oid, usr_id, lines, currency))?;
+        olines.try_update_shipping(shipping)?;
         self.repo.create_order(&olines, &billing).await?;
         let mismatch = (olines.id.as_str() != oid_uncheck) || (olines.buyer_id != usr_id_uncheck);
         if mismatch {
@@ -210,18 +237,84 @@ impl ChargeCreateUseCase {
     ) -> Result<ChargeCreateRespDto, ChargeCreateUcError> {
         let (req_order, req_mthd) = reqbody.into_parts();
         let mut charge_buyer = ChargeBuyerModel::try_from((saved_order, req_order))?;
+        let router = PayInMethodRouter {
+            repo_m: self.repo_m.as_ref(),
+        };
+        router.route(&charge_buyer, &req_mthd).await?;
         let (result, method_m) = self
             .processors
             .pay_in_start(&charge_buyer, req_mthd)
             .await?;
         charge_buyer.meta.update_progress(&result.state);
         charge_buyer.meta.update_3party(method_m);
+        let (owner, ctime) = (charge_buyer.meta.owner(), *charge_buyer.meta.create_time());
         self.repo.create_charge(charge_buyer).await?;
         if result.completed {
-            // TODO, if the pay-in process is complete, invoke RPC to order service
-            // for payment status update
+            // the pending sync has been saved along with the new charge, if any
+            // error happens here, the periodic job will retry later, no need to
+            // report error to client
+            if let Err(e) = self.sync_order_app(owner, ctime).await {
+                self.log_sync_error(owner, ctime, e);
+            }
         }
         let resp = ChargeCreateRespDto::from(result);
         Ok(resp)
     }
+
+    /// Synchronisation is intentionally performed within the request of
+    /// charge creation, so the order service can reflect the payment as soon
+    /// as the client receives the response, the extra latency is bounded by
+    /// the RPC timeout and few resends in the sync handler. The payment itself
+    /// has been saved at this point, the periodic job takes over on failure.
+    async fn sync_order_app(
+        &self,
+        owner: u32,
+        ctime: DateTime<Utc>,
+    ) -> Result<(), OrderAppSyncError> {
+        let handler = OrderAppSyncHandler {
+            repo: self.repo.as_ref(),
+            rpc_ctx: self.rpc_ctx.as_ref().as_ref(),
+        };
+        let maybe_meta = self
+            .repo
+            .fetch_charge_meta(owner, ctime)
+            .await
+            .map_err(OrderAppSyncError::DataStore)?;
+        let mut meta = match maybe_meta {
+            Some(v) => v,
+            None => return Ok(()),
+        };
+        let next_retry = OrderAppSyncHandler::next_retry_time(1);
+        handler.sync(&mut meta, next_retry).await?;
+        self.repo
+            .update_charge_progress(meta)
+            .await
+            .map_err(OrderAppSyncError::DataStore)?;
+        handler
+            .finish(owner, ctime)
+            .await
+            .map_err(OrderAppSyncError::DataStore)
+    }
+
+    fn log_sync_error(&self, owner: u32, ctime: DateTime<Utc>, e: OrderAppSyncError) {
+        let logctx = &self.logctx;
+        match e {
+            OrderAppSyncError::DataStore(e) => {
+                app_log_event!(logctx, AppLogLevel::ERROR, "{owner}, {ctime}, {:?}", e);
+            }
+            OrderAppSyncError::RpcContext(_e) => {
+                app_log_event!(
+                    logctx,
+                    AppLogLevel::WARNING,
+                    "{owner}, {ctime}, low-lvl-rpc-ctx"
+                );
+            }
+            OrderAppSyncError::RpcContentSerialisation(detail) => {
+                app_log_event!(logctx, AppLogLevel::ERROR, "{owner}, {ctime}, {detail}");
+            }
+            OrderAppSyncError::RpcUpdateOrder(detail) => {
+                app_log_event!(logctx, AppLogLevel::ERROR, "{owner}, {ctime}, {:?}", detail);
+            }
+        }
+    }
 } // end of impl ChargeCreateUseCase
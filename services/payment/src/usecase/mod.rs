@@ -2,16 +2,23 @@ mod capture_charge;
 mod create_charge;
 mod finalize_refund;
 mod onboard;
+mod processor_webhook;
 mod refresh_charge_status;
 mod reporting;
+mod route_pay_method;
+mod sync_order_pay;
 mod sync_refund_req;
 
 pub use capture_charge::{ChargeCaptureUcError, ChargeCaptureUseCase};
 pub use create_charge::{ChargeCreateUcError, ChargeCreateUseCase};
 pub use finalize_refund::{FinalizeRefundUcError, FinalizeRefundUseCase};
 pub use onboard::{OnboardStoreUcError, OnboardStoreUseCase, RefreshOnboardStatusUseCase};
+pub use processor_webhook::{
+    ProcessorWebhookUcError, ProcessorWebhookUcOk, ProcessorWebhookUseCase,
+};
 pub use refresh_charge_status::{ChargeRefreshUcError, ChargeStatusRefreshUseCase};
 pub use reporting::{MerchantReportChargeUcError, MerchantReportChargeUseCase};
+pub use sync_order_pay::{SyncOrderPayUcError, SyncOrderPayUcOk, SyncOrderPayUseCase};
 pub use sync_refund_req::{SyncRefundReqUcError, SyncRefundReqUseCase};
 
 use chrono::{DateTime, Utc};
@@ -0,0 +1,288 @@
+use std::boxed::Box;
+use std::result::Result;
+use std::sync::Arc;
+
+use chrono::Local;
+
+use ecommerce_common::error::AppErrorCode;
+use ecommerce_common::logging::{app_log_event, AppLogContext, AppLogLevel};
+
+use crate::adapter::processor::{
+    AbstractPaymentProcessor, AppProcessorError, AppProcessorWebhookDetail,
+};
+use crate::adapter::repository::{
+    AbstractChargeRepo, AbstractMerchantRepo, AbstractProcessorEventRepo, AppRepoError,
+    ProcessorEventClaim,
+};
+use crate::model::{
+    BuyerPayInState, Charge3partyModel, ChargeBuyerMetaModel, Label3party, Merchant3partyModel,
+    PayoutTransferStateModel,
+};
+
+use super::sync_order_pay::OrderAppSyncHandler;
+use super::try_parse_charge_id;
+
+pub enum ProcessorWebhookUcError {
+    ThirdParty(AppProcessorError),
+    RepoOpFailure(AppRepoError),
+    ChargeIdDecode(AppErrorCode, String),
+    MissingCharge(String),
+    MissingPayout(u32, String), // store ID and serialised charge ID
+    CorruptedEvent(String),
+    EventInProgress(String),
+}
+
+pub enum ProcessorWebhookUcOk {
+    Applied,
+    Duplicate,
+    Ignored(String),
+}
+
+pub struct ProcessorWebhookUseCase {
+    pub processors: Arc<Box<dyn AbstractPaymentProcessor>>,
+    pub repo_c: Box<dyn AbstractChargeRepo>,
+    pub repo_m: Box<dyn AbstractMerchantRepo>,
+    pub repo_evt: Box<dyn AbstractProcessorEventRepo>,
+    pub logctx: Arc<AppLogContext>,
+}
+
+impl ProcessorWebhookUseCase {
+    pub async fn execute(
+        self,
+        label3pty: Label3party,
+        signature: String,
+        payload: Vec<u8>,
+    ) -> Result<ProcessorWebhookUcOk, ProcessorWebhookUcError> {
+        let event = self
+            .processors
+            .parse_webhook_event(label3pty, signature.as_str(), &payload)
+            .map_err(ProcessorWebhookUcError::ThirdParty)?;
+        // 3rd party may notify the same event several times, claim the event
+        // first so concurrent requests carrying the same event can be skipped
+        let claim = self
+            .repo_evt
+            .try_claim(event.label, event.id.as_str())
+            .await
+            .map_err(ProcessorWebhookUcError::RepoOpFailure)?;
+        match claim {
+            ProcessorEventClaim::Claimed => {}
+            ProcessorEventClaim::Applied => return Ok(ProcessorWebhookUcOk::Duplicate),
+            // the event is not applied yet, the 3rd party has to notify again
+            // in case the request holding the claim fails
+            ProcessorEventClaim::InProgress => {
+                return Err(ProcessorWebhookUcError::EventInProgress(event.id));
+            }
+        }
+        let result = self.apply(event.label, event.detail).await;
+        if result.is_ok() {
+            self.repo_evt
+                .complete(event.label, event.id.as_str())
+                .await
+                .map_err(ProcessorWebhookUcError::RepoOpFailure)?;
+        } else if let Err(e) = self.repo_evt.release(event.label, event.id.as_str()).await {
+            // the result of applying the event is more important to caller,
+            // the claim will be taken over by later notification once expired
+            let logctx = &self.logctx;
+            app_log_event!(
+                logctx,
+                AppLogLevel::WARNING,
+                "claim-release-failure: {}, {:?}",
+                event.id,
+                e
+            );
+        }
+        result
+    } // end of fn execute
+
+    async fn apply(
+        &self,
+        label3pty: Label3party,
+        detail: AppProcessorWebhookDetail,
+    ) -> Result<ProcessorWebhookUcOk, ProcessorWebhookUcError> {
+        match detail {
+            AppProcessorWebhookDetail::PayInCompleted {
+                buyer_id,
+                order_id,
+                session_id,
+            } => self.complete_pay_in(buyer_id, order_id, session_id).await,
+            AppProcessorWebhookDetail::ChargeRefunded {
+                charge_id,
+                payment_ref,
+            } => self.verify_refunded(charge_id, payment_ref).await,
+            AppProcessorWebhookDetail::MerchantUpdated { account_id, model } => {
+                self.update_merchant(label3pty, account_id, model).await
+            }
+            AppProcessorWebhookDetail::PayoutUpdated {
+                charge_id,
+                account_id,
+                transfer_id,
+                reversed,
+            } => {
+                let args = (charge_id, account_id, transfer_id, reversed);
+                self.verify_payout(label3pty, args).await
+            }
+            AppProcessorWebhookDetail::Unhandled(evt_type) => {
+                Ok(ProcessorWebhookUcOk::Ignored(evt_type))
+            }
+        }
+    } // end of fn apply
+
+    async fn complete_pay_in(
+        &self,
+        buyer_id: u32,
+        order_id: String,
+        session_id: String,
+    ) -> Result<ProcessorWebhookUcOk, ProcessorWebhookUcError> {
+        let (owner_id, ctimes) = self
+            .repo_c
+            .fetch_charge_ids(order_id.as_str())
+            .await
+            .map_err(ProcessorWebhookUcError::RepoOpFailure)?
+            .ok_or(ProcessorWebhookUcError::MissingCharge(order_id.clone()))?;
+        if owner_id != buyer_id {
+            let msg = format!("buyer-mismatch, {owner_id}, {buyer_id}");
+            return Err(ProcessorWebhookUcError::CorruptedEvent(msg));
+        }
+        let mut found = None;
+        // the latest charge is more likely the one to complete
+        for ctime in ctimes.into_iter().rev() {
+            let maybe_meta = self
+                .repo_c
+                .fetch_charge_meta(owner_id, ctime)
+                .await
+                .map_err(ProcessorWebhookUcError::RepoOpFailure)?;
+            if let Some(meta) = maybe_meta {
+                if meta.method_3party().session_matched(session_id.as_str()) {
+                    found = Some(meta);
+                    break;
+                }
+            }
+        }
+        let mut meta = found.ok_or(ProcessorWebhookUcError::MissingCharge(order_id))?;
+        if meta.method_3party().pay_in_comfirmed().is_some() {
+            // the charge status has been refreshed by client
+            return Ok(ProcessorWebhookUcOk::Applied);
+        }
+        // always read the latest state from the 3rd party, instead of the
+        // object carried in the event
+        let mthd_3pty = self
+            .processors
+            .pay_in_progress(&meta)
+            .await
+            .map_err(ProcessorWebhookUcError::ThirdParty)?;
+        if mthd_3pty.pay_in_comfirmed().is_none() {
+            let msg = "3party-pay-in-incomplete".to_string();
+            return Err(ProcessorWebhookUcError::CorruptedEvent(msg));
+        }
+        Self::update_pay_in_progress(&mut meta, mthd_3pty);
+        // let the periodic job sync the payment status to order service, the
+        // pending record is saved along with the charge progress
+        let next_retry = OrderAppSyncHandler::next_retry_time(0);
+        self.repo_c
+            .update_charge_progress_sync_pending(meta, next_retry)
+            .await
+            .map_err(ProcessorWebhookUcError::RepoOpFailure)?;
+        Ok(ProcessorWebhookUcOk::Applied)
+    } // end of fn complete_pay_in
+
+    fn update_pay_in_progress(meta: &mut ChargeBuyerMetaModel, mthd_3pty: Charge3partyModel) {
+        let now = Local::now().to_utc();
+        let new_state = BuyerPayInState::ProcessorCompleted(now);
+        meta.update_progress(&new_state);
+        meta.update_3party(mthd_3pty);
+    }
+
+    async fn verify_refunded(
+        &self,
+        charge_id: String,
+        payment_ref: String,
+    ) -> Result<ProcessorWebhookUcOk, ProcessorWebhookUcError> {
+        let (buyer_id, ctime) = try_parse_charge_id(charge_id.as_str())
+            .map_err(|e| ProcessorWebhookUcError::ChargeIdDecode(e.0, e.1))?;
+        let meta = self
+            .repo_c
+            .fetch_charge_meta(buyer_id, ctime)
+            .await
+            .map_err(ProcessorWebhookUcError::RepoOpFailure)?
+            .ok_or(ProcessorWebhookUcError::MissingCharge(charge_id))?;
+        // amount of each refunded line has been saved at the time merchants
+        // finalized the refund request, the event is only for confirmation.
+        if !meta.method_3party().payment_matched(payment_ref.as_str()) {
+            let msg = format!("payment-mismatch, {payment_ref}");
+            return Err(ProcessorWebhookUcError::CorruptedEvent(msg));
+        }
+        let t_confirmed = Local::now().to_utc();
+        self.repo_c
+            .save_refund_confirmed(buyer_id, ctime, t_confirmed)
+            .await
+            .map_err(ProcessorWebhookUcError::RepoOpFailure)?;
+        Ok(ProcessorWebhookUcOk::Applied)
+    }
+
+    async fn update_merchant(
+        &self,
+        label3pty: Label3party,
+        account_id: String,
+        notified: Merchant3partyModel,
+    ) -> Result<ProcessorWebhookUcOk, ProcessorWebhookUcError> {
+        let maybe_saved = self
+            .repo_m
+            .fetch_by_3party_account(label3pty, account_id.as_str())
+            .await
+            .map_err(ProcessorWebhookUcError::RepoOpFailure)?;
+        let (store_id, saved) = if let Some(v) = maybe_saved {
+            v
+        } else {
+            // the account is not created by this application, or the
+            // merchant has not been saved yet
+            let msg = format!("unknown-account, {account_id}");
+            return Ok(ProcessorWebhookUcOk::Ignored(msg));
+        };
+        let m3pty = notified.merge_notified(saved);
+        self.repo_m
+            .update_3party(store_id, m3pty)
+            .await
+            .map_err(ProcessorWebhookUcError::RepoOpFailure)?;
+        Ok(ProcessorWebhookUcOk::Applied)
+    }
+
+    async fn verify_payout(
+        &self,
+        label3pty: Label3party,
+        args: (String, String, String, bool),
+    ) -> Result<ProcessorWebhookUcOk, ProcessorWebhookUcError> {
+        let (charge_id, account_id, transfer_id, reversed) = args;
+        let (buyer_id, ctime) = try_parse_charge_id(charge_id.as_str())
+            .map_err(|e| ProcessorWebhookUcError::ChargeIdDecode(e.0, e.1))?;
+        let (store_id, _m3pty) = self
+            .repo_m
+            .fetch_by_3party_account(label3pty, account_id.as_str())
+            .await
+            .map_err(ProcessorWebhookUcError::RepoOpFailure)?
+            .ok_or(ProcessorWebhookUcError::CorruptedEvent(format!(
+                "unknown-account, {account_id}"
+            )))?;
+        // the event might arrive before the payout is saved by the capture
+        // use case, in such case the 3rd party will notify again later.
+        let payout_m = self
+            .repo_c
+            .fetch_payout(store_id, buyer_id, ctime)
+            .await
+            .map_err(ProcessorWebhookUcError::RepoOpFailure)?
+            .ok_or(ProcessorWebhookUcError::MissingPayout(store_id, charge_id))?;
+        if !payout_m.transfer_matched(transfer_id.as_str()) {
+            let msg = format!("transfer-mismatch, {transfer_id}");
+            return Err(ProcessorWebhookUcError::CorruptedEvent(msg));
+        }
+        let state = PayoutTransferStateModel {
+            transfer_id,
+            reversed,
+            notified_time: Local::now().to_utc(),
+        };
+        self.repo_c
+            .save_payout_transfer_state(store_id, buyer_id, ctime, state)
+            .await
+            .map_err(ProcessorWebhookUcError::RepoOpFailure)?;
+        Ok(ProcessorWebhookUcOk::Applied)
+    } // end of fn verify_payout
+} // end of impl ProcessorWebhookUseCase
@@ -0,0 +1,66 @@
+use std::result::Result;
+
+use crate::adapter::repository::{AbstractMerchantRepo, AppRepoError};
+use crate::api::web::dto::{ChargeSellerErrorDto, PaymentMethodReqDto};
+use crate::model::{ChargeBuyerModel, Label3party};
+
+pub(super) enum PayInRouteError {
+    DataStore(AppRepoError),
+    MissingCurrency(u32),
+    SellerUnsupported(Vec<ChargeSellerErrorDto>),
+}
+
+/// decide whether the 3rd-party processor of the chosen method is able to
+/// serve all sellers in a charge, this has to be done before any request is
+/// sent to the processor.
+pub(super) struct PayInMethodRouter<'a> {
+    pub(super) repo_m: &'a dyn AbstractMerchantRepo,
+}
+
+impl PayInMethodRouter<'_> {
+    pub(super) async fn route(
+        &self,
+        charge_buyer: &ChargeBuyerModel,
+        req_mthd: &PaymentMethodReqDto,
+    ) -> Result<(), PayInRouteError> {
+        let label = Label3party::from(req_mthd);
+        let currency_buyer = charge_buyer
+            .get_buyer_currency()
+            .ok_or(PayInRouteError::MissingCurrency(charge_buyer.meta.owner()))?
+            .label;
+        // all sellers are paid out later through the same processor, which
+        // holds the fund in the currency the buyer pays with
+        let currency_err = if label.support_currency(&currency_buyer) {
+            None
+        } else {
+            Some(currency_buyer)
+        };
+        let mut errors = Vec::new();
+        for seller_id in charge_buyer.seller_ids() {
+            let maybe_saved = self
+                .repo_m
+                .fetch(seller_id, label)
+                .await
+                .map_err(PayInRouteError::DataStore)?;
+            let onboarded = maybe_saved.is_some();
+            // e.g. Stripe disables charges or payouts of the connected account
+            // when the merchant has to submit more documents
+            let enabled = maybe_saved
+                .as_ref()
+                .is_some_and(|(_prof, m3pty)| m3pty.can_accept_pay_in());
+            if !onboarded || !enabled || currency_err.is_some() {
+                errors.push(ChargeSellerErrorDto {
+                    seller_id,
+                    not_onboarded: !onboarded,
+                    not_enabled: onboarded && !enabled,
+                    currency: currency_err.clone(),
+                });
+            }
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(PayInRouteError::SellerUnsupported(errors))
+        }
+    } // end of fn route
+} // end of impl PayInMethodRouter
@@ -0,0 +1,208 @@
+use std::boxed::Box;
+use std::result::Result;
+use std::sync::Arc;
+
+use chrono::{DateTime, Duration, Local, Utc};
+
+use ecommerce_common::api::rpc::dto::OrderPaymentUpdateErrorDto;
+
+use crate::adapter::repository::{AbstractChargeRepo, AppRepoError};
+use crate::adapter::rpc::{AbstractRpcContext, AppRpcClientRequest, AppRpcCtxError, AppRpcReply};
+use crate::model::{BuyerPayInState, ChargeBuyerMetaModel};
+
+// number of extra attempts to send the same request through RPC context
+// , when low-level connection error happens.
+const RPC_MAX_RETRY: u8 = 2;
+// the pending sync records are retried by periodic job, the delay grows
+// exponentially then stays at the capped interval, the records are never
+// discarded, those still failing after several attempts are reported instead
+const RETRY_BASE_DELAY_SECS: i64 = 120;
+const RETRY_MAX_NUM_DOUBLING: u16 = 7;
+const NUM_ATTEMPTS_REPORT_OVERDUE: u16 = 12;
+const MAX_NUM_PENDING_PER_ROUND: u16 = 50;
+
+pub(super) enum OrderAppSyncError {
+    DataStore(AppRepoError),
+    RpcContext(AppRpcCtxError),
+    RpcContentSerialisation(String),
+    RpcUpdateOrder(OrderPaymentUpdateErrorDto),
+}
+
+/// shared path which pushes payment status of a charge to order service, the
+/// caller has to save the charge progress after this function returns.
+pub(super) struct OrderAppSyncHandler<'a> {
+    pub(super) repo: &'a dyn AbstractChargeRepo,
+    pub(super) rpc_ctx: &'a dyn AbstractRpcContext,
+}
+
+impl OrderAppSyncHandler<'_> {
+    pub(super) fn next_retry_time(num_attempts: u16) -> DateTime<Utc> {
+        let exp = num_attempts.min(RETRY_MAX_NUM_DOUBLING) as u32;
+        let delay = RETRY_BASE_DELAY_SECS * 2i64.pow(exp);
+        Local::now().to_utc() + Duration::seconds(delay)
+    }
+
+    pub(super) async fn sync(
+        &self,
+        meta: &mut ChargeBuyerMetaModel,
+        next_retry: DateTime<Utc>,
+    ) -> Result<(), OrderAppSyncError> {
+        let (owner, ctime) = (meta.owner(), *meta.create_time());
+        // record the attempt before sending request, in case this application
+        // restarts before the order service confirms.
+        self.repo
+            .save_order_sync_pending(owner, ctime, next_retry)
+            .await
+            .map_err(OrderAppSyncError::DataStore)?;
+        let message = self.rpc_build_charge_lines(meta).await?;
+        let mut result = self.rpc_send(meta, message.clone()).await;
+        let mut num_retry = 0u8;
+        while result.is_err() && num_retry < RPC_MAX_RETRY {
+            result = self.rpc_send(meta, message.clone()).await;
+            num_retry += 1;
+        }
+        let reply = result.map_err(OrderAppSyncError::RpcContext)?;
+        let resp_detail = serde_json::from_slice::<OrderPaymentUpdateErrorDto>(&reply.message)
+            .map_err(|e| OrderAppSyncError::RpcContentSerialisation(e.to_string()))?;
+        let has_err = resp_detail.charge_time.is_some() | !resp_detail.lines.is_empty();
+        if has_err {
+            Err(OrderAppSyncError::RpcUpdateOrder(resp_detail))
+        } else {
+            let now = Local::now().to_utc();
+            let new_state = BuyerPayInState::OrderAppSynced(now);
+            meta.update_progress(&new_state);
+            Ok(())
+        }
+    } // end of fn sync
+
+    /// remove the pending record, this function should be invoked only after
+    /// the charge progress `OrderAppSynced` is saved.
+    pub(super) async fn finish(
+        &self,
+        owner: u32,
+        ctime: DateTime<Utc>,
+    ) -> Result<(), AppRepoError> {
+        self.repo.remove_order_sync_pending(owner, ctime).await
+    }
+
+    async fn rpc_send(
+        &self,
+        meta: &ChargeBuyerMetaModel,
+        message: Vec<u8>,
+    ) -> Result<AppRpcReply, AppRpcCtxError> {
+        let client = self.rpc_ctx.acquire().await?;
+        let props = AppRpcClientRequest {
+            usr_id: meta.owner(),
+            // Note, the reason to specify this `create-time` field instead of current
+            // time is that order-processing service can handle idempotency based on
+            // this create time, TODO, find better design approach
+            time: *meta.create_time(),
+            route: "rpc.order.order_reserved_update_payment".to_string(),
+            message,
+        };
+        let mut event = client.send_request(props).await?;
+        event.receive_response().await
+    }
+
+    async fn rpc_build_charge_lines(
+        &self,
+        meta: &ChargeBuyerMetaModel,
+    ) -> Result<Vec<u8>, OrderAppSyncError> {
+        let chg_lines = self
+            .repo
+            .fetch_all_charge_lines(meta.owner(), *meta.create_time())
+            .await
+            .map_err(OrderAppSyncError::DataStore)?;
+        let update_dto = meta.pay_update_dto(chg_lines);
+        let serialised = serde_json::to_vec(&update_dto)
+            .map_err(|e| OrderAppSyncError::RpcContentSerialisation(e.to_string()))?;
+        Ok(serialised)
+    }
+} // end of impl OrderAppSyncHandler
+
+#[derive(Debug)]
+pub enum SyncOrderPayUcError {
+    Datastore(AppRepoError),
+}
+
+pub struct SyncOrderPayUcOk {
+    pub num_synced: usize,
+    pub num_failed: usize,
+    /// buyer ID, create time and number of attempts of each charge which
+    /// cannot be synced for long time, the caller should raise an alert
+    pub overdue: Vec<(u32, DateTime<Utc>, u16)>,
+}
+
+/// periodic job which retries the charges not synced to order service yet
+pub struct SyncOrderPayUseCase;
+
+impl SyncOrderPayUseCase {
+    pub async fn execute(
+        repo: Box<dyn AbstractChargeRepo>,
+        rpc_ctx: Arc<Box<dyn AbstractRpcContext>>,
+    ) -> Result<SyncOrderPayUcOk, SyncOrderPayUcError> {
+        let t_now = Local::now().to_utc();
+        let pendings = repo
+            .fetch_order_sync_pending(t_now, MAX_NUM_PENDING_PER_ROUND)
+            .await
+            .map_err(SyncOrderPayUcError::Datastore)?;
+        let handler = OrderAppSyncHandler {
+            repo: repo.as_ref(),
+            rpc_ctx: rpc_ctx.as_ref().as_ref(),
+        };
+        let (mut num_synced, mut num_failed) = (0usize, 0usize);
+        let mut overdue = Vec::new();
+        for (owner, ctime, num_attempts) in pendings {
+            let maybe_meta = repo
+                .fetch_charge_meta(owner, ctime)
+                .await
+                .map_err(SyncOrderPayUcError::Datastore)?;
+            let mut meta = match maybe_meta {
+                Some(m) if Self::sync_required(&m) => m,
+                // the charge has been synced by other flows, or the pay-in
+                // completion was not saved, the latter case can be recovered
+                // by client refreshing the charge status
+                _others => {
+                    handler
+                        .finish(owner, ctime)
+                        .await
+                        .map_err(SyncOrderPayUcError::Datastore)?;
+                    continue;
+                }
+            };
+            let next_retry = OrderAppSyncHandler::next_retry_time(num_attempts);
+            match handler.sync(&mut meta, next_retry).await {
+                Ok(()) => {
+                    repo.update_charge_progress(meta)
+                        .await
+                        .map_err(SyncOrderPayUcError::Datastore)?;
+                    handler
+                        .finish(owner, ctime)
+                        .await
+                        .map_err(SyncOrderPayUcError::Datastore)?;
+                    num_synced += 1;
+                }
+                Err(OrderAppSyncError::DataStore(e)) => {
+                    return Err(SyncOrderPayUcError::Datastore(e));
+                }
+                // the pending record remains, retry at later time
+                Err(_others) => {
+                    num_failed += 1;
+                    if num_attempts + 1 >= NUM_ATTEMPTS_REPORT_OVERDUE {
+                        overdue.push((owner, ctime, num_attempts + 1));
+                    }
+                }
+            }
+        } // end of loop
+        Ok(SyncOrderPayUcOk {
+            num_synced,
+            num_failed,
+            overdue,
+        })
+    } // end of fn execute
+
+    fn sync_required(meta: &ChargeBuyerMetaModel) -> bool {
+        let completed = matches!(meta.progress(), BuyerPayInState::ProcessorCompleted(_));
+        completed && meta.method_3party().pay_in_comfirmed() == Some(true)
+    }
+} // end of impl SyncOrderPayUseCase
@@ -0,0 +1,97 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use actix_web::rt;
+use futures_util::future::join_all;
+
+use ecommerce_common::config::{AppCacheCfg, AppInMemoryCacheCfg};
+use payment::adapter::cache::{app_cache_order_sync_lock, AbstractOrderSyncLockCache};
+
+use super::ut_setup_cfdntl;
+use crate::ut_setup_logctx;
+
+fn ut_setup_inmem_cache(max_items: u32, ttl_secs: u16) -> Box<dyn AbstractOrderSyncLockCache> {
+    let cfg = AppCacheCfg::InMemory(AppInMemoryCacheCfg {
+        max_items,
+        ttl_secs,
+    });
+    let cfdntl = ut_setup_cfdntl(String::new());
+    let result = app_cache_order_sync_lock(Some(&cfg), cfdntl, ut_setup_logctx());
+    result.unwrap()
+}
+
+#[actix_web::test]
+async fn acquire_release_ok() {
+    let cch = ut_setup_inmem_cache(10, 30);
+    let result = cch.acquire(1234, "9d0f2e").await;
+    assert!(matches!(result, Ok(Some(_))));
+    let token = result.ok().unwrap().unwrap();
+    let result = cch.acquire(1234, "9d0f2e").await;
+    assert!(matches!(result, Ok(None)));
+    // lock is identified by both of the buyer and the order
+    let result = cch.acquire(1235, "9d0f2e").await;
+    assert!(matches!(result, Ok(Some(_))));
+    let result = cch.acquire(1234, "9d0f2f").await;
+    assert!(matches!(result, Ok(Some(_))));
+    let result = cch.release(1234, "9d0f2e", token).await;
+    assert!(result.is_ok());
+    let result = cch.acquire(1234, "9d0f2e").await;
+    assert!(matches!(result, Ok(Some(_))));
+}
+
+#[actix_web::test]
+async fn lock_expired() {
+    let cch = ut_setup_inmem_cache(10, 1);
+    let result = cch.acquire(1234, "a1b2c3").await;
+    assert!(matches!(result, Ok(Some(_))));
+    let stale_token = result.ok().unwrap().unwrap();
+    let result = cch.acquire(1234, "a1b2c3").await;
+    assert!(matches!(result, Ok(None)));
+    rt::time::sleep(Duration::from_millis(1100)).await;
+    let result = cch.acquire(1234, "a1b2c3").await;
+    assert!(matches!(result, Ok(Some(_))));
+    let token = result.ok().unwrap().unwrap();
+    assert_ne!(stale_token, token);
+    // the holder of the expired lock should not remove the lock currently
+    // held by others
+    let result = cch.release(1234, "a1b2c3", stale_token).await;
+    assert!(result.is_ok());
+    let result = cch.acquire(1234, "a1b2c3").await;
+    assert!(matches!(result, Ok(None)));
+    let result = cch.release(1234, "a1b2c3", token).await;
+    assert!(result.is_ok());
+    let result = cch.acquire(1234, "a1b2c3").await;
+    assert!(matches!(result, Ok(Some(_))));
+}
+
+#[actix_web::test]
+async fn exceed_capacity() {
+    let cch = ut_setup_inmem_cache(2, 1);
+    let result = cch.acquire(1234, "a1b2c3").await;
+    assert!(matches!(result, Ok(Some(_))));
+    let result = cch.acquire(1234, "a1b2c4").await;
+    assert!(matches!(result, Ok(Some(_))));
+    let result = cch.acquire(1234, "a1b2c5").await;
+    assert!(result.is_err());
+    // space is reclaimed from expired locks
+    rt::time::sleep(Duration::from_millis(1100)).await;
+    let result = cch.acquire(1234, "a1b2c5").await;
+    assert!(matches!(result, Ok(Some(_))));
+}
+
+#[actix_web::test]
+async fn concurrent_acquire_exclusive() {
+    let cch = Arc::new(ut_setup_inmem_cache(10, 30));
+    let handles = (0..12)
+        .map(|_| {
+            let c = cch.clone();
+            rt::spawn(async move { c.acquire(1234, "e0e1e2").await })
+        })
+        .collect::<Vec<_>>();
+    let results = join_all(handles).await;
+    let num_acquired = results
+        .into_iter()
+        .filter(|r| matches!(r, Ok(Ok(Some(_)))))
+        .count();
+    assert_eq!(num_acquired, 1);
+}
@@ -0,0 +1,23 @@
+mod in_mem;
+mod redis;
+mod resp_server;
+
+use std::sync::Arc;
+
+use ecommerce_common::confidentiality::AbstractConfidentiality;
+use ecommerce_common::error::AppConfidentialityError;
+
+struct UtMockConfidentiality {
+    payload: String,
+}
+
+impl AbstractConfidentiality for UtMockConfidentiality {
+    fn try_get_payload(&self, _id: &str) -> Result<String, AppConfidentialityError> {
+        Ok(self.payload.clone())
+    }
+}
+
+fn ut_setup_cfdntl(payload: String) -> Arc<Box<dyn AbstractConfidentiality>> {
+    let obj = UtMockConfidentiality { payload };
+    Arc::new(Box::new(obj))
+}
@@ -0,0 +1,154 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use actix_web::rt;
+use futures_util::future::join_all;
+
+use ecommerce_common::config::{AppCacheCfg, AppCacheServerCfg};
+use payment::adapter::cache::{app_cache_order_sync_lock, AbstractOrderSyncLockCache};
+
+use super::resp_server::UtRespServer;
+use super::ut_setup_cfdntl;
+use crate::ut_setup_logctx;
+
+fn ut_setup_redis_cache(
+    port: u16,
+    password: &str,
+    max_conns: u16,
+    ttl_secs: u16,
+) -> Box<dyn AbstractOrderSyncLockCache> {
+    let cfg = AppCacheCfg::Redis(AppCacheServerCfg {
+        confidentiality_path: "backend_apps/caches/payment_service".to_string(),
+        max_conns,
+        ttl_secs,
+    });
+    let secret = format!(
+        r#"{{"HOST":"127.0.0.1","PORT":{},"PASSWORD":"{}"}}"#,
+        port, password
+    );
+    let cfdntl = ut_setup_cfdntl(secret);
+    let result = app_cache_order_sync_lock(Some(&cfg), cfdntl, ut_setup_logctx());
+    result.unwrap()
+}
+
+#[actix_web::test]
+async fn acquire_release_ok() {
+    let server = UtRespServer::start("un1t-t3st").await;
+    let cch = ut_setup_redis_cache(server.port, "un1t-t3st", 2, 30);
+    let result = cch.acquire(1234, "9d0f2e").await;
+    assert!(matches!(result, Ok(Some(_))));
+    let token = result.ok().unwrap().unwrap();
+    let result = cch.acquire(1234, "9d0f2e").await;
+    assert!(matches!(result, Ok(None)));
+    let result = cch.acquire(1235, "9d0f2e").await;
+    assert!(matches!(result, Ok(Some(_))));
+    let result = cch.release(1234, "9d0f2e", token).await;
+    assert!(result.is_ok());
+    let result = cch.acquire(1234, "9d0f2e").await;
+    assert!(matches!(result, Ok(Some(_))));
+    // connections are reused
+    let num_conns = *server.num_conns.lock().unwrap();
+    assert_eq!(num_conns, 1);
+}
+
+#[actix_web::test]
+async fn release_by_other_instance() {
+    let server = UtRespServer::start("un1t-t3st").await;
+    let cch_a = ut_setup_redis_cache(server.port, "un1t-t3st", 2, 30);
+    let cch_b = ut_setup_redis_cache(server.port, "un1t-t3st", 2, 30);
+    let result = cch_a.acquire(1234, "c0ffee").await;
+    assert!(matches!(result, Ok(Some(_))));
+    let token_a = result.ok().unwrap().unwrap();
+    let result = cch_b.acquire(1234, "c0ffee").await;
+    assert!(matches!(result, Ok(None)));
+    // the instance never holding the lock cannot release it
+    let result = cch_b.acquire(1234, "c0ffef").await;
+    let token_b = result.ok().unwrap().unwrap();
+    let result = cch_b.release(1234, "c0ffee", token_b).await;
+    assert!(result.is_ok());
+    let result = cch_b.acquire(1234, "c0ffee").await;
+    assert!(matches!(result, Ok(None)));
+    let result = cch_a.release(1234, "c0ffee", token_a).await;
+    assert!(result.is_ok());
+    let result = cch_b.acquire(1234, "c0ffee").await;
+    assert!(matches!(result, Ok(Some(_))));
+}
+
+#[actix_web::test]
+async fn lock_expired_then_taken() {
+    let server = UtRespServer::start("un1t-t3st").await;
+    let cch_a = ut_setup_redis_cache(server.port, "un1t-t3st", 2, 1);
+    let cch_b = ut_setup_redis_cache(server.port, "un1t-t3st", 2, 30);
+    let result = cch_a.acquire(1234, "beef01").await;
+    assert!(matches!(result, Ok(Some(_))));
+    let token_a = result.ok().unwrap().unwrap();
+    rt::time::sleep(Duration::from_millis(1100)).await;
+    let result = cch_b.acquire(1234, "beef01").await;
+    assert!(matches!(result, Ok(Some(_))));
+    // the lock expired in the instance A should not remove the lock
+    // currently held by the instance B
+    let result = cch_a.release(1234, "beef01", token_a).await;
+    assert!(result.is_ok());
+    let result = cch_a.acquire(1234, "beef01").await;
+    assert!(matches!(result, Ok(None)));
+}
+
+#[actix_web::test]
+async fn lock_expired_then_taken_same_instance() {
+    let server = UtRespServer::start("un1t-t3st").await;
+    let cch = ut_setup_redis_cache(server.port, "un1t-t3st", 2, 1);
+    let result = cch.acquire(1234, "beef02").await;
+    let stale_token = result.ok().unwrap().unwrap();
+    rt::time::sleep(Duration::from_millis(1100)).await;
+    let result = cch.acquire(1234, "beef02").await;
+    let token = result.ok().unwrap().unwrap();
+    // the earlier holder in the same instance should not remove the lock
+    // acquired by the later holder
+    let result = cch.release(1234, "beef02", stale_token).await;
+    assert!(result.is_ok());
+    let result = cch.acquire(1234, "beef02").await;
+    assert!(matches!(result, Ok(None)));
+    let result = cch.release(1234, "beef02", token).await;
+    assert!(result.is_ok());
+    let result = cch.acquire(1234, "beef02").await;
+    assert!(matches!(result, Ok(Some(_))));
+}
+
+#[actix_web::test]
+async fn concurrent_acquire_exclusive() {
+    let server = UtRespServer::start("un1t-t3st").await;
+    let cch = Arc::new(ut_setup_redis_cache(server.port, "un1t-t3st", 3, 30));
+    let handles = (0..12)
+        .map(|_| {
+            let c = cch.clone();
+            rt::spawn(async move { c.acquire(1234, "e0e1e2").await })
+        })
+        .collect::<Vec<_>>();
+    let results = join_all(handles).await;
+    let num_acquired = results
+        .into_iter()
+        .filter(|r| matches!(r, Ok(Ok(Some(_)))))
+        .count();
+    assert_eq!(num_acquired, 1);
+    let num_conns = *server.num_conns.lock().unwrap();
+    assert!(num_conns <= 3);
+}
+
+#[actix_web::test]
+async fn auth_failure() {
+    let server = UtRespServer::start("un1t-t3st").await;
+    let cch = ut_setup_redis_cache(server.port, "wrong-passwd", 2, 30);
+    let result = cch.acquire(1234, "9d0f2e").await;
+    assert!(result.is_err());
+}
+
+#[actix_web::test]
+async fn server_unavailable() {
+    let port = {
+        let server = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        server.local_addr().unwrap().port()
+    }; // the port is released immediately, nothing listens to it
+    let cch = ut_setup_redis_cache(port, "un1t-t3st", 2, 30);
+    let result = cch.acquire(1234, "9d0f2e").await;
+    assert!(result.is_err());
+}
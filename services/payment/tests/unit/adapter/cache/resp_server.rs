@@ -0,0 +1,210 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufStream};
+use tokio::net::{TcpListener, TcpStream};
+
+// Minimal stand-in of a cache server, which speaks only the subset of Redis
+// serialisation protocol used by the order-sync lock, each key is tracked
+// with a version number for optimistic locking (`WATCH` command)
+#[derive(Default)]
+struct UtStoreInner {
+    items: HashMap<String, (Vec<u8>, Option<Instant>)>,
+    versions: HashMap<String, u64>,
+}
+
+type UtStore = Arc<Mutex<UtStoreInner>>;
+
+impl UtStoreInner {
+    fn purge_expired(&mut self, key: &str) {
+        let expired = self
+            .items
+            .get(key)
+            .and_then(|(_v, t)| t.as_ref())
+            .is_some_and(|t| *t <= Instant::now());
+        if expired {
+            let _old = self.items.remove(key);
+            self.bump_version(key);
+        }
+    }
+    fn bump_version(&mut self, key: &str) {
+        let v = self.versions.entry(key.to_string()).or_insert(0);
+        *v += 1;
+    }
+    fn version(&mut self, key: &str) -> u64 {
+        self.purge_expired(key);
+        self.versions.get(key).cloned().unwrap_or(0)
+    }
+}
+
+struct UtSession {
+    authed: bool,
+    watched: Vec<(String, u64)>,
+    queued: Option<Vec<Vec<String>>>,
+}
+
+pub(super) struct UtRespServer {
+    pub port: u16,
+    pub num_conns: Arc<Mutex<usize>>,
+}
+
+impl UtRespServer {
+    pub(super) async fn start(password: &'static str) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let num_conns = Arc::new(Mutex::new(0usize));
+        let store: UtStore = Arc::new(Mutex::new(UtStoreInner::default()));
+        let num_conns_cpy = num_conns.clone();
+        let _handle = tokio::spawn(async move {
+            while let Ok((stream, _addr)) = listener.accept().await {
+                *num_conns_cpy.lock().unwrap() += 1;
+                let _handle = tokio::spawn(ut_serve_conn(stream, store.clone(), password));
+            }
+        });
+        Self { port, num_conns }
+    }
+}
+
+async fn ut_read_command(stream: &mut BufStream<TcpStream>) -> Option<Vec<String>> {
+    let mut line = String::new();
+    let nread = stream.read_line(&mut line).await.ok()?;
+    if nread == 0 {
+        return None;
+    }
+    let nargs = line.trim_end().strip_prefix('*')?.parse::<usize>().ok()?;
+    let mut args = Vec::new();
+    for _ in 0..nargs {
+        line.clear();
+        let _ = stream.read_line(&mut line).await.ok()?;
+        let sz = line.trim_end().strip_prefix('$')?.parse::<usize>().ok()?;
+        let mut data = vec![0u8; sz + 2];
+        stream.read_exact(data.as_mut_slice()).await.ok()?;
+        data.truncate(sz);
+        args.push(String::from_utf8(data).ok()?);
+    }
+    Some(args)
+}
+
+fn ut_bulk_reply(value: Option<&Vec<u8>>) -> Vec<u8> {
+    match value {
+        Some(v) => {
+            let mut out = format!("${}\r\n", v.len()).into_bytes();
+            out.extend_from_slice(v.as_slice());
+            out.extend_from_slice(b"\r\n");
+            out
+        }
+        None => b"$-1\r\n".to_vec(),
+    }
+}
+
+fn ut_exec_data_command(inner: &mut UtStoreInner, args: &[String]) -> Vec<u8> {
+    let key = args[1].as_str();
+    inner.purge_expired(key);
+    match args[0].to_uppercase().as_str() {
+        "GET" => ut_bulk_reply(inner.items.get(key).map(|(v, _t)| v)),
+        "DEL" => {
+            let removed = inner.items.remove(key).is_some();
+            if removed {
+                inner.bump_version(key);
+            }
+            format!(":{}\r\n", removed as u8).into_bytes()
+        }
+        "SET" => {
+            let opts = args[3..]
+                .iter()
+                .map(|a| a.to_uppercase())
+                .collect::<Vec<_>>();
+            let nx = opts.contains(&"NX".to_string());
+            let expiry = opts
+                .iter()
+                .position(|a| a == "PX")
+                .and_then(|idx| args.get(3 + idx + 1))
+                .and_then(|a| a.parse::<u64>().ok())
+                .map(|ms| Instant::now() + Duration::from_millis(ms));
+            if nx && inner.items.contains_key(key) {
+                b"$-1\r\n".to_vec()
+            } else {
+                let value = (args[2].as_bytes().to_vec(), expiry);
+                let _old = inner.items.insert(key.to_string(), value);
+                inner.bump_version(key);
+                b"+OK\r\n".to_vec()
+            }
+        }
+        _others => b"-ERR unknown command\r\n".to_vec(),
+    }
+}
+
+fn ut_handle_command(store: &UtStore, sess: &mut UtSession, args: Vec<String>) -> Vec<u8> {
+    let cmd = args[0].to_uppercase();
+    if !sess.authed && cmd.as_str() != "AUTH" {
+        return b"-NOAUTH Authentication required.\r\n".to_vec();
+    }
+    if let Some(q) = sess.queued.as_mut() {
+        if cmd.as_str() != "EXEC" {
+            q.push(args);
+            return b"+QUEUED\r\n".to_vec();
+        }
+    }
+    let mut inner = store.lock().unwrap();
+    match cmd.as_str() {
+        "WATCH" => {
+            let key = args[1].clone();
+            let ver = inner.version(key.as_str());
+            sess.watched.push((key, ver));
+            b"+OK\r\n".to_vec()
+        }
+        "UNWATCH" => {
+            sess.watched.clear();
+            b"+OK\r\n".to_vec()
+        }
+        "MULTI" => {
+            sess.queued = Some(Vec::new());
+            b"+OK\r\n".to_vec()
+        }
+        "EXEC" => {
+            let queued = sess.queued.take().unwrap_or_default();
+            let watched = std::mem::take(&mut sess.watched);
+            let modified = watched
+                .into_iter()
+                .any(|(k, ver)| inner.version(k.as_str()) != ver);
+            if modified {
+                b"*-1\r\n".to_vec()
+            } else {
+                let mut out = format!("*{}\r\n", queued.len()).into_bytes();
+                for q in queued {
+                    out.extend(ut_exec_data_command(&mut inner, q.as_slice()));
+                }
+                out
+            }
+        }
+        _others => ut_exec_data_command(&mut inner, args.as_slice()),
+    }
+} // end of fn ut_handle_command
+
+async fn ut_serve_conn(stream: TcpStream, store: UtStore, password: &'static str) {
+    let mut stream = BufStream::new(stream);
+    let mut sess = UtSession {
+        authed: password.is_empty(),
+        watched: Vec::new(),
+        queued: None,
+    };
+    while let Some(args) = ut_read_command(&mut stream).await {
+        let reply = if args[0].to_uppercase().as_str() == "AUTH" {
+            sess.authed = args.get(1).is_some_and(|p| p.as_str() == password);
+            if sess.authed {
+                b"+OK\r\n".to_vec()
+            } else {
+                b"-WRONGPASS invalid password\r\n".to_vec()
+            }
+        } else {
+            ut_handle_command(&store, &mut sess, args)
+        };
+        if stream.write_all(reply.as_slice()).await.is_err() {
+            break;
+        }
+        if stream.flush().await.is_err() {
+            break;
+        }
+    }
+}
@@ -1 +1,2 @@
 mod stripe;
+mod xendit;
@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::thread::sleep;
 use std::time::Duration;
 
@@ -80,6 +81,7 @@ fn ut_setup_chargebuyer_stripe(
         lines,
         meta,
         currency_snapshot,
+        shipping: HashMap::new(),
     }
 } // end of fn ut_setup_chargebuyer_stripe
 
@@ -110,7 +112,7 @@ macro_rules! ut_verify_charge_stripe_model {
                 let cond = matches!(&m.payment_state, $expect_pay_state);
                 assert!(cond);
             }
-            Charge3partyModel::Unknown => {
+            _others => {
                 assert!(false);
             }
         }
@@ -132,6 +134,7 @@ fn ut_verify_stripe_session_ok(
             assert!(s.redirect_url.is_some());
             assert!(s.client_session.is_none());
         }
+        _others => assert!(false),
     }
     ut_verify_charge_stripe_model!(
         &charge_3pty_m,
@@ -166,6 +169,7 @@ async fn ut_autofill_stripe_hosted_webform(
         .unwrap();
     let url = match &pay_in_res.method {
         PaymentMethodRespDto::Stripe(s) => s.redirect_url.clone().unwrap(),
+        _others => panic!("unexpected-method"),
     };
     let result = controller.goto(url.as_str()).await;
     assert!(result.is_ok());
@@ -93,6 +93,7 @@ pub(super) async fn ok_exact_once(
                 let amt_serial = s.amount().unwrap();
                 assert_eq!(amt_serial, Decimal::new(3222, 2));
             }
+            _others => assert!(false),
         }
     }
 } // end of fn ok_exact_once
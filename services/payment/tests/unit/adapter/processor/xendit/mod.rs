@@ -0,0 +1,42 @@
+mod onboard;
+mod pay_in;
+mod pay_out;
+mod refund;
+mod webhook;
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use ecommerce_common::api::dto::CurrencyDto;
+use rust_decimal::Decimal;
+
+use payment::model::{BuyerPayInState, Charge3partyModel, ChargeBuyerModel, OrderCurrencySnapshot};
+
+use crate::model::{ut_setup_buyer_charge, UTestChargeLineRawData};
+
+// the buyer and all merchants in the charge apply the same currency, which
+// is always the case in the e-wallets Xendit supports
+#[rustfmt::skip]
+fn ut_setup_buyer_charge_xendit(
+    buyer_usr_id: u32,
+    merchant_ids: Vec<u32>,
+    create_time: DateTime<Utc>,
+    currency: (CurrencyDto, i64, u32),
+    method: Charge3partyModel,
+    d_lines: Vec<UTestChargeLineRawData>,
+) -> ChargeBuyerModel {
+    let (label, rate_mantissa, rate_scale) = currency;
+    let currency_snapshot = {
+        let iter = merchant_ids.into_iter().chain([buyer_usr_id])
+            .map(|usr_id| {
+                let rate = Decimal::new(rate_mantissa, rate_scale);
+                let obj = OrderCurrencySnapshot { label: label.clone(), rate };
+                (usr_id, obj)
+            });
+        HashMap::from_iter(iter)
+    };
+    ut_setup_buyer_charge(
+        buyer_usr_id, create_time, "ba7e0dd1c2".to_string(),
+        BuyerPayInState::Initialized, method, d_lines, currency_snapshot,
+    )
+}
@@ -0,0 +1,90 @@
+use chrono::Local;
+
+use payment::adapter::processor::AppProcessorErrorReason;
+use payment::api::web::dto::{StoreOnboardReqDto, StoreOnboardRespDto};
+use payment::model::{Merchant3partyModel, XenditAccountStatusModel};
+
+use crate::dto::{ut_default_store_onboard_req_stripe, ut_setup_storeprofile_dto};
+use crate::model::ut_default_merchant_3party_xendit;
+use crate::ut_setup_sharestate_mock_3party;
+
+#[actix_web::test]
+async fn create_merchant_account_ok() {
+    let shr_state = ut_setup_sharestate_mock_3party();
+    let proc_ctx = shr_state.processor_context();
+    let mock_profile =
+        ut_setup_storeprofile_dto("hex bolt", 134, vec![241, 242], Local::now().to_utc());
+    let result = proc_ctx
+        .onboard_merchant(mock_profile, StoreOnboardReqDto::Xendit)
+        .await;
+    assert!(result.is_ok());
+    let (respdto, m3pty) = result.unwrap().into_parts();
+    if let StoreOnboardRespDto::Xendit { status } = respdto {
+        assert_eq!(status.as_str(), "INVITED");
+    } else {
+        assert!(false);
+    }
+    if let Merchant3partyModel::Xendit(m) = &m3pty {
+        assert!(!m.id.is_empty());
+        assert_eq!(m.business_name.as_str(), "hex bolt");
+        assert!(!m.email.is_empty());
+        assert!(matches!(m.status, XenditAccountStatusModel::INVITED));
+    } else {
+        assert!(false);
+    }
+
+    let result = proc_ctx
+        .refresh_onboard_status(m3pty, StoreOnboardReqDto::Xendit)
+        .await;
+    assert!(result.is_ok());
+    let (respdto, m3pty) = result.unwrap().into_parts();
+    if let StoreOnboardRespDto::Xendit { status } = respdto {
+        assert_eq!(status.as_str(), "LIVE");
+    } else {
+        assert!(false);
+    }
+    if let Merchant3partyModel::Xendit(m) = m3pty {
+        assert_eq!(m.business_name.as_str(), "hex bolt");
+        assert!(matches!(m.status, XenditAccountStatusModel::LIVE));
+    } else {
+        assert!(false);
+    }
+} // end of fn create_merchant_account_ok
+
+#[actix_web::test]
+async fn create_merchant_profile_error() {
+    let shr_state = ut_setup_sharestate_mock_3party();
+    let proc_ctx = shr_state.processor_context();
+    let mut mock_profile =
+        ut_setup_storeprofile_dto("hex bolt", 134, vec![241, 242], Local::now().to_utc());
+    mock_profile.active = false;
+    mock_profile.emails = None;
+    let result = proc_ctx
+        .onboard_merchant(mock_profile, StoreOnboardReqDto::Xendit)
+        .await;
+    assert!(result.is_err());
+    if let Err(e) = result {
+        if let AppProcessorErrorReason::InvalidStoreProfileDto(detail) = e.reason {
+            assert!(detail.iter().any(|d| d.as_str() == "not-active"));
+            assert!(detail.iter().any(|d| d.as_str() == "missing-email"));
+        } else {
+            assert!(false);
+        }
+    }
+}
+
+#[actix_web::test]
+async fn refresh_status_method_mismatch() {
+    let shr_state = ut_setup_sharestate_mock_3party();
+    let proc_ctx = shr_state.processor_context();
+    let mock_m3pty = Merchant3partyModel::Xendit(ut_default_merchant_3party_xendit());
+    let mock_req = ut_default_store_onboard_req_stripe();
+    let result = proc_ctx.refresh_onboard_status(mock_m3pty, mock_req).await;
+    assert!(result.is_err());
+    if let Err(e) = result {
+        assert!(matches!(
+            e.reason,
+            AppProcessorErrorReason::InvalidMethod(_)
+        ));
+    }
+}
@@ -0,0 +1,139 @@
+use chrono::Local;
+use ecommerce_common::api::dto::CurrencyDto;
+
+use payment::adapter::processor::{AppProcessorErrorReason, AppProcessorFnLabel};
+use payment::api::web::dto::{
+    PaymentMethodReqDto, PaymentMethodRespDto, XenditEwalletChannelDto, XenditEwalletChargeReqDto,
+};
+use payment::model::{BuyerPayInState, Charge3partyModel, XenditChargeStatusModel};
+
+use super::ut_setup_buyer_charge_xendit;
+use crate::{ut_setup_sharestate, ut_setup_sharestate_mock_3party};
+
+fn ut_default_method_xendit_request(
+    channel_code: XenditEwalletChannelDto,
+    success_redirect_url: Option<&str>,
+    mobile_number: Option<&str>,
+) -> PaymentMethodReqDto {
+    let inner = XenditEwalletChargeReqDto {
+        channel_code,
+        success_redirect_url: success_redirect_url.map(String::from),
+        failure_redirect_url: None,
+        mobile_number: mobile_number.map(String::from),
+    };
+    PaymentMethodReqDto::Xendit(inner)
+}
+
+#[rustfmt::skip]
+#[actix_web::test]
+async fn pay_in_start_ok() {
+    let shr_state = ut_setup_sharestate_mock_3party();
+    let proc_ctx = shr_state.processor_context();
+    let mut mock_charge_m = ut_setup_buyer_charge_xendit(
+        8299, vec![3034, 3037], Local::now().to_utc(),
+        (CurrencyDto::IDR, 1625000, 2), Charge3partyModel::Unknown,
+        vec![
+            ((3034, 9001, 0), ((15000, 0), (45000, 0), 3), ((0, 0), (0, 0), 0), 0),
+            ((3037, 9002, 1), ((27500, 0), (55000, 0), 2), ((0, 0), (0, 0), 0), 0),
+        ],
+    );
+    let mock_req = ut_default_method_xendit_request(
+        XenditEwalletChannelDto::ID_DANA, Some("https://www.example.com/pay/done"), None,
+    );
+    let result = proc_ctx.pay_in_start(&mock_charge_m, mock_req).await;
+    assert!(result.is_ok());
+    let (payin_result, charge_3pty) = result.unwrap();
+    assert!(!payin_result.completed);
+    assert!(matches!(payin_result.state, BuyerPayInState::ProcessorAccepted(_)));
+    if let PaymentMethodRespDto::Xendit(d) = &payin_result.method {
+        assert!(d.id.starts_with("ewc_"));
+        assert!(d.desktop_url.is_some());
+    } else {
+        assert!(false);
+    }
+    if let Charge3partyModel::Xendit(c) = &charge_3pty {
+        assert_eq!(c.currency, CurrencyDto::IDR);
+        assert_eq!(c.channel_code.as_str(), "ID_DANA");
+        assert!(matches!(c.status, XenditChargeStatusModel::PENDING));
+    } else {
+        assert!(false);
+    }
+    assert!(charge_3pty.pay_in_comfirmed().is_none());
+
+    mock_charge_m.meta.update_3party(charge_3pty);
+    let result = proc_ctx.pay_in_progress(&mock_charge_m.meta).await;
+    assert!(result.is_ok());
+    let charge_3pty = result.unwrap();
+    assert_eq!(charge_3pty.pay_in_comfirmed(), Some(true));
+} // end of fn pay_in_start_ok
+
+#[rustfmt::skip]
+#[actix_web::test]
+async fn pay_in_start_err_method() {
+    let shr_state = ut_setup_sharestate_mock_3party();
+    let proc_ctx = shr_state.processor_context();
+    let mock_redirect = Some("https://www.example.com/pay/done");
+    let mock_mobile = Some("+628123456789");
+    let cases = [
+        (CurrencyDto::IDR, 45000i64, XenditEwalletChannelDto::ID_OVO, None, None,
+         "missing-mobile-number"),
+        (CurrencyDto::IDR, 45000, XenditEwalletChannelDto::ID_SHOPEEPAY, None, mock_mobile,
+         "missing-success-redirect-url"),
+        (CurrencyDto::TWD, 45000, XenditEwalletChannelDto::ID_DANA, mock_redirect, None,
+         "currency-mismatch"),
+        (CurrencyDto::IDR, 450005, XenditEwalletChannelDto::ID_OVO, None, mock_mobile,
+         "fraction-unsupported"),
+    ];
+    for (currency, amt_total, channel, redirect_url, mobile, expect_msg) in cases {
+        let mock_charge_m = ut_setup_buyer_charge_xendit(
+            8299, vec![3034], Local::now().to_utc(),
+            (currency, 3210, 2), Charge3partyModel::Unknown,
+            vec![((3034, 9001, 0), ((15000, 0), (amt_total, 1), 3), ((0, 0), (0, 0), 0), 0)],
+        );
+        let mock_req = ut_default_method_xendit_request(channel, redirect_url, mobile);
+        let result = proc_ctx.pay_in_start(&mock_charge_m, mock_req).await;
+        assert!(result.is_err());
+        if let Err(e) = result {
+            assert!(matches!(e.fn_label, AppProcessorFnLabel::PayInStart));
+            if let AppProcessorErrorReason::InvalidMethod(msg) = e.reason {
+                assert!(msg.starts_with(expect_msg));
+            } else {
+                assert!(false);
+            }
+        }
+    }
+} // end of fn pay_in_start_err_method
+
+#[actix_web::test]
+async fn pay_in_start_err_processor_unavailable() {
+    // the configuration for this test case does not include Xendit
+    let shr_state = ut_setup_sharestate();
+    let proc_ctx = shr_state.processor_context();
+    let mock_charge_m = ut_setup_buyer_charge_xendit(
+        8299,
+        vec![3034],
+        Local::now().to_utc(),
+        (CurrencyDto::IDR, 1625000, 2),
+        Charge3partyModel::Unknown,
+        vec![(
+            (3034, 9001, 0),
+            ((15000, 0), (45000, 0), 3),
+            ((0, 0), (0, 0), 0),
+            0,
+        )],
+    );
+    let mock_req = ut_default_method_xendit_request(
+        XenditEwalletChannelDto::ID_OVO,
+        None,
+        Some("+628123456789"),
+    );
+    let result = proc_ctx.pay_in_start(&mock_charge_m, mock_req).await;
+    assert!(result.is_err());
+    if let Err(e) = result {
+        if let AppProcessorErrorReason::ProcessorUnavailable(label) = e.reason {
+            assert_eq!(label.as_str(), "Xendit");
+        } else {
+            assert!(false);
+        }
+    }
+}
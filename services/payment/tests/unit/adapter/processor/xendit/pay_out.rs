@@ -0,0 +1,102 @@
+use chrono::{Duration, Local};
+use rust_decimal::Decimal;
+
+use ecommerce_common::api::dto::CurrencyDto;
+use payment::adapter::processor::AppProcessorErrorReason;
+use payment::api::web::dto::CapturePay3partyRespDto;
+use payment::model::{
+    ChargeBuyerModel, Payout3partyModel, Payout3partyXenditModel, PayoutAmountModel, PayoutModel,
+};
+
+use super::ut_setup_buyer_charge_xendit;
+use crate::model::ut_default_charge_method_xendit;
+use crate::ut_setup_sharestate_mock_3party;
+
+#[rustfmt::skip]
+fn ut_setup_payout_model(
+    merchant_id: u32,
+    sub_account: &str,
+    tot_amt_buyer: Decimal,
+    charge_buyer: &ChargeBuyerModel,
+) -> PayoutModel {
+    let buyer_usr_id = charge_buyer.meta.owner();
+    let arg = (
+        "mock-xendit-reference-id".to_string(), sub_account.to_string(),
+        CurrencyDto::IDR, None, None,
+    );
+    let mock_3pty = Payout3partyModel::Xendit(Payout3partyXenditModel::from(arg));
+    let arg = (
+        tot_amt_buyer,
+        charge_buyer.currency_snapshot.get(&merchant_id).unwrap().clone(),
+        charge_buyer.currency_snapshot.get(&buyer_usr_id).unwrap().clone(),
+    );
+    let mock_amount = PayoutAmountModel::try_from(arg).unwrap();
+    let arg = (
+        merchant_id, Local::now().to_utc(), buyer_usr_id, *charge_buyer.meta.create_time(),
+        "ba7e0dd1c2".to_string(), 5566u32, mock_amount, mock_3pty,
+    );
+    PayoutModel::from(arg)
+}
+
+#[rustfmt::skip]
+fn ut_setup_charge_buyer(merchant_id: u32) -> ChargeBuyerModel {
+    let charge_ctime = Local::now().to_utc() - Duration::minutes(30);
+    ut_setup_buyer_charge_xendit(
+        8299, vec![merchant_id], charge_ctime, (CurrencyDto::IDR, 1625000, 2),
+        ut_default_charge_method_xendit(&charge_ctime, CurrencyDto::IDR),
+        vec![((merchant_id, 9001, 0), ((50000, 0), (150000, 0), 3), ((0, 0), (0, 0), 0), 0)],
+    )
+}
+
+#[actix_web::test]
+async fn ok_fraction_truncated() {
+    let shr_state = ut_setup_sharestate_mock_3party();
+    let proc_ctx = shr_state.processor_context();
+    let mock_merchant_id = 3034u32;
+    let mock_charge_m = ut_setup_charge_buyer(mock_merchant_id);
+    let mock_payout_m = ut_setup_payout_model(
+        mock_merchant_id,
+        "5cafeb170a2b18519b1b8768",
+        Decimal::new(14999950, 2),
+        &mock_charge_m,
+    );
+    let result = proc_ctx.pay_out(mock_payout_m).await;
+    assert!(result.is_ok());
+    let (payout_dto, payout_m) = result.unwrap().into_parts();
+    assert_eq!(payout_dto.store_id, mock_merchant_id);
+    assert_eq!(payout_dto.currency, CurrencyDto::IDR);
+    if let CapturePay3partyRespDto::Xendit { amount, currency } = payout_dto.processor {
+        assert_eq!(amount.as_str(), "149999");
+        assert_eq!(currency, CurrencyDto::IDR);
+    } else {
+        assert!(false);
+    }
+    if let Payout3partyModel::Xendit(x) = payout_m.thirdparty() {
+        assert_eq!(x.amount(), Some(Decimal::new(149999, 0)));
+        assert_eq!(x.currency(), &CurrencyDto::IDR);
+    } else {
+        assert!(false);
+    }
+} // end of fn ok_fraction_truncated
+
+#[actix_web::test]
+async fn err_missing_sub_account() {
+    let shr_state = ut_setup_sharestate_mock_3party();
+    let proc_ctx = shr_state.processor_context();
+    let mock_merchant_id = 3034u32;
+    let mock_charge_m = ut_setup_charge_buyer(mock_merchant_id);
+    let mock_payout_m = ut_setup_payout_model(
+        mock_merchant_id,
+        "",
+        Decimal::new(150000, 0),
+        &mock_charge_m,
+    );
+    let result = proc_ctx.pay_out(mock_payout_m).await;
+    assert!(result.is_err());
+    if let Err(e) = result {
+        assert!(matches!(
+            e.reason,
+            AppProcessorErrorReason::InvalidMethod(_)
+        ));
+    }
+}
@@ -0,0 +1,60 @@
+use chrono::{DateTime, Duration, Local, Utc};
+
+use ecommerce_common::api::dto::CurrencyDto;
+use payment::adapter::processor::{AppProcessorErrorReason, AppProcessorFnLabel};
+use payment::model::RefundReqResolutionModel;
+
+use super::ut_setup_buyer_charge_xendit;
+use crate::model::refund::ut_setup_refund_cmplt_dto;
+use crate::model::ut_default_charge_method_xendit;
+use crate::ut_setup_sharestate_mock_3party;
+
+#[rustfmt::skip]
+fn ut_setup_refund_resolution(
+    time_base: DateTime<Utc>,
+    merchant_id: u32,
+    charge_currency: CurrencyDto,
+) -> RefundReqResolutionModel {
+    let charge_ctime = time_base - Duration::minutes(86);
+    let mock_charge_m = ut_setup_buyer_charge_xendit(
+        8299, vec![merchant_id], charge_ctime, (CurrencyDto::IDR, 1625000, 2),
+        ut_default_charge_method_xendit(&charge_ctime, charge_currency),
+        vec![
+            ((merchant_id, 9001, 0), ((50000, 0), (150000, 0), 3), ((0, 0), (0, 0), 0), 0),
+            ((merchant_id, 9002, 0), ((27500, 0), (55000, 0), 2), ((0, 0), (0, 0), 0), 0),
+        ],
+    );
+    // the fraction in approved amount cannot be refunded through e-wallets
+    let mock_cmplt_req = ut_setup_refund_cmplt_dto(time_base, vec![
+        ((9001, 0), 0, 500005, 1, 0, 0),
+        ((9002, 0), 1, 275000, 1, 0, 0),
+    ]);
+    let arg = (merchant_id, &mock_charge_m, &mock_cmplt_req);
+    RefundReqResolutionModel::try_from(arg).unwrap()
+}
+
+#[actix_web::test]
+async fn refund_ok() {
+    let shr_state = ut_setup_sharestate_mock_3party();
+    let proc_ctx = shr_state.processor_context();
+    let rfd_rslv_m = ut_setup_refund_resolution(Local::now().to_utc(), 3034, CurrencyDto::IDR);
+    let result = proc_ctx.refund(rfd_rslv_m).await;
+    assert!(result.is_ok());
+}
+
+#[actix_web::test]
+async fn err_currency_mismatch() {
+    let shr_state = ut_setup_sharestate_mock_3party();
+    let proc_ctx = shr_state.processor_context();
+    let rfd_rslv_m = ut_setup_refund_resolution(Local::now().to_utc(), 3034, CurrencyDto::PHP);
+    let result = proc_ctx.refund(rfd_rslv_m).await;
+    assert!(result.is_err());
+    if let Err(e) = result {
+        assert!(matches!(e.fn_label, AppProcessorFnLabel::Refund));
+        if let AppProcessorErrorReason::InvalidMethod(msg) = e.reason {
+            assert!(msg.starts_with("currency-mismatch"));
+        } else {
+            assert!(false);
+        }
+    }
+}
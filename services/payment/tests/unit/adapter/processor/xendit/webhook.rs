@@ -0,0 +1,109 @@
+use serde_json::json;
+
+use payment::adapter::processor::{AppProcessorErrorReason, AppProcessorWebhookDetail};
+use payment::model::Label3party;
+
+use crate::ut_setup_sharestate_mock_3party;
+
+const UT_CALLBACK_TOKEN: &str = "xnd_mock_callback_unit_test_9z8y7x";
+
+fn ut_setup_ewallet_capture_payload(status: &str, buyer_id: &str) -> Vec<u8> {
+    let obj = json!({
+        "event": "ewallet.capture",
+        "business_id": "5f218745736e619164dc8608",
+        "created": "2026-10-18T04:30:02.931Z",
+        "data": {
+            "id": "ewc_bb8c3po-c3po-r2d2-c3po-r2d2c3por2d2",
+            "business_id": "5f218745736e619164dc8608",
+            "reference_id": "00000d7c2b6e0ed9",
+            "status": status,
+            "currency": "IDR",
+            "charge_amount": 100000.0,
+            "channel_code": "ID_DANA",
+            "metadata": {"buyer_id": buyer_id, "order_id": "ba7e0dd1c2"},
+        }
+    });
+    serde_json::to_vec(&obj).unwrap()
+}
+
+#[actix_web::test]
+async fn parse_capture_ok() {
+    let shr_state = ut_setup_sharestate_mock_3party();
+    let proc_ctx = shr_state.processor_context();
+    let payload = ut_setup_ewallet_capture_payload("SUCCEEDED", "8299");
+    let result =
+        proc_ctx.parse_webhook_event(Label3party::Xendit, UT_CALLBACK_TOKEN, payload.as_slice());
+    assert!(result.is_ok());
+    let event = result.unwrap();
+    assert!(matches!(event.label, Label3party::Xendit));
+    assert_eq!(
+        event.id.as_str(),
+        "ewallet.capture-ewc_bb8c3po-c3po-r2d2-c3po-r2d2c3por2d2"
+    );
+    if let AppProcessorWebhookDetail::PayInCompleted {
+        buyer_id,
+        order_id,
+        session_id,
+    } = event.detail
+    {
+        assert_eq!(buyer_id, 8299);
+        assert_eq!(order_id.as_str(), "ba7e0dd1c2");
+        assert_eq!(
+            session_id.as_str(),
+            "ewc_bb8c3po-c3po-r2d2-c3po-r2d2c3por2d2"
+        );
+    } else {
+        assert!(false);
+    }
+}
+
+#[actix_web::test]
+async fn parse_capture_pending_unhandled() {
+    let shr_state = ut_setup_sharestate_mock_3party();
+    let proc_ctx = shr_state.processor_context();
+    let payload = ut_setup_ewallet_capture_payload("PENDING", "8299");
+    let result =
+        proc_ctx.parse_webhook_event(Label3party::Xendit, UT_CALLBACK_TOKEN, payload.as_slice());
+    assert!(result.is_ok());
+    let event = result.unwrap();
+    assert!(matches!(
+        event.detail,
+        AppProcessorWebhookDetail::Unhandled(_)
+    ));
+}
+
+#[actix_web::test]
+async fn parse_err_invalid_token() {
+    let shr_state = ut_setup_sharestate_mock_3party();
+    let proc_ctx = shr_state.processor_context();
+    let payload = ut_setup_ewallet_capture_payload("SUCCEEDED", "8299");
+    let result = proc_ctx.parse_webhook_event(
+        Label3party::Xendit,
+        "xnd_mock_callback_unit_test_9z8y7w",
+        payload.as_slice(),
+    );
+    assert!(result.is_err());
+    if let Err(e) = result {
+        assert!(matches!(
+            e.reason,
+            AppProcessorErrorReason::InvalidSignature(_)
+        ));
+    }
+}
+
+#[actix_web::test]
+async fn parse_err_corrupted_metadata() {
+    let shr_state = ut_setup_sharestate_mock_3party();
+    let proc_ctx = shr_state.processor_context();
+    let payload = ut_setup_ewallet_capture_payload("SUCCEEDED", "not-a-number");
+    let result =
+        proc_ctx.parse_webhook_event(Label3party::Xendit, UT_CALLBACK_TOKEN, payload.as_slice());
+    assert!(result.is_err());
+    if let Err(e) = result {
+        if let AppProcessorErrorReason::ThirdParty(msg) = e.reason {
+            assert!(msg.starts_with("invalid-metadata"));
+        } else {
+            assert!(false);
+        }
+    }
+}
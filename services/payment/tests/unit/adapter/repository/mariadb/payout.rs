@@ -131,6 +131,7 @@ async fn create_fetch_ok() {
         Payout3partyModel::Stripe(s) => {
             assert_eq!(s.amount().unwrap(), Decimal::new(1037, 2));
         }
+        _others => assert!(false),
     }
 } // end of fn create_fetch_ok
 
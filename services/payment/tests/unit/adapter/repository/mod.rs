@@ -47,6 +47,7 @@ fn ut_setup_orderline_set(
         id: order_id_hex.to_string(),
         buyer_id, lines, num_charges, currency_snapshot,
         create_time: create_time.to_utc(),
+        shipping: Vec::new(),
     }
 } // end of fn ut_setup_orderline_set
 
@@ -6,9 +6,11 @@ mod usecase;
 
 use std::collections::HashMap;
 use std::env;
+use std::sync::Arc;
 
-use ecommerce_common::config::{AppCfgHardLimit, AppCfgInitArgs, AppConfig};
+use ecommerce_common::config::{App3rdPartyCfg, AppCfgHardLimit, AppCfgInitArgs, AppConfig};
 use ecommerce_common::constant::env_vars::{CFG_FILEPATH, EXPECTED_LABELS};
+use ecommerce_common::logging::AppLogContext;
 use payment::AppSharedState;
 
 pub(crate) const EXAMPLE_REL_PATH: &'static str = "/tests/unit/examples/";
@@ -29,7 +31,29 @@ fn ut_setup_config(cfg_filename: &str) -> AppConfig {
     AppConfig::new(args).unwrap()
 }
 
+fn ut_setup_logctx() -> Arc<AppLogContext> {
+    let cfg = ut_setup_config("config_ok.json");
+    let lc = AppLogContext::new(&cfg.basepath, &cfg.api_server.logging);
+    Arc::new(lc)
+}
+
 fn ut_setup_sharestate() -> AppSharedState {
     let cfg = ut_setup_config("config_ok.json");
     AppSharedState::new(cfg).unwrap()
 }
+
+// all the 3rd-party processors are replaced with mock contexts, for the
+// test cases which would not send requests to remote servers
+fn ut_setup_sharestate_mock_3party() -> AppSharedState {
+    let mut cfg = ut_setup_config("config_ok.json");
+    let mock_stripe = App3rdPartyCfg::test {
+        name: "Stripe".to_string(),
+        data_src: String::new(),
+    };
+    let mock_xendit = App3rdPartyCfg::test {
+        name: "Xendit".to_string(),
+        data_src: String::new(),
+    };
+    cfg.api_server.third_parties = Some(vec![Arc::new(mock_stripe), Arc::new(mock_xendit)]);
+    AppSharedState::new(cfg).unwrap()
+}
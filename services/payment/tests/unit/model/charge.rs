@@ -11,12 +11,12 @@ use payment::api::web::dto::{
 };
 use payment::model::{
     BuyerPayInState, Charge3partyModel, Charge3partyStripeModel, ChargeBuyerMetaModel,
-    ChargeBuyerModel, ChargeToken, PayLineAmountModel, StripeCheckoutPaymentStatusModel,
-    StripeSessionStatusModel,
+    ChargeBuyerModel, ChargeToken, OrderShippingModel, PayLineAmountModel,
+    StripeCheckoutPaymentStatusModel, StripeSessionStatusModel, XenditChargeStatusModel,
 };
 
 use super::order_replica::ut_setup_order_replica;
-use super::ut_partial_eq_charge_status_dto;
+use super::{ut_default_charge_method_xendit, ut_partial_eq_charge_status_dto};
 
 #[test]
 fn buyer_convert_ok_1() {
@@ -88,6 +88,59 @@ fn buyer_convert_ok_1() {
     }
 } // end of fn buyer_convert_ok_1
 
+#[test]
+fn buyer_convert_with_shipping() {
+    let (mock_usr_id, mock_oid) = (583, "phidix".to_string());
+    let reserved_until = Local::now().fixed_offset() + Duration::minutes(2);
+    let mut mock_order = ut_setup_order_replica(mock_usr_id, mock_oid.clone(), reserved_until);
+    mock_order.shipping = [
+        (140, 150i64, 1u32, false),
+        (141, 8, 0, true),
+        (142, 6, 0, false),
+        (143, 45, 1, false),
+    ]
+    .into_iter()
+    .map(|d| OrderShippingModel {
+        seller_id: d.0,
+        amount: Decimal::new(d.1, d.2),
+        paid: d.3,
+    })
+    .collect();
+    let mock_lines = [
+        (140, 1005, 0, 6, "17.15", "102.9"),
+        (141, 1006, 0, 1, "21", "21"),
+        (143, 1008, 2, 4, "10.60", "42.40"),
+        (143, 1008, 0, 4, "10", "40"),
+    ]
+    .into_iter()
+    .map(|d| ChargeAmountOlineDto {
+        seller_id: d.0,
+        product_id: d.1,
+        attr_set_seq: d.2,
+        quantity: d.3,
+        amount: PayAmountDto {
+            unit: d.4.to_string(),
+            total: d.5.to_string(),
+        },
+    })
+    .collect();
+    let mock_new_req = ChargeReqOrderDto {
+        id: mock_oid,
+        lines: mock_lines,
+        currency: CurrencyDto::TWD,
+    };
+    let result = ChargeBuyerModel::try_from((mock_order, mock_new_req));
+    assert!(result.is_ok());
+    if let Ok(v) = result {
+        // paid fee of seller 141 and the fee of seller 142 which is absent
+        // in the request are excluded
+        assert_eq!(v.shipping.len(), 2);
+        assert_eq!(v.shipping.get(&140).unwrap(), &Decimal::new(150, 1));
+        assert_eq!(v.shipping.get(&143).unwrap(), &Decimal::new(45, 1));
+        assert_eq!(v.total_amount(), Decimal::new(2258, 1));
+    }
+} // end of fn buyer_convert_with_shipping
+
 #[test]
 fn buyer_convert_ok_2() {
     let (mock_usr_id, mock_oid) = (584, "NikuSan".to_string());
@@ -572,6 +625,32 @@ fn buyer_3pty_pay_in_confirm() {
     .count();
 } // end of fn buyer_3pty_pay_in_confirm
 
+#[test]
+fn buyer_3pty_xendit_pay_in_confirm() {
+    let t_now = Local::now().to_utc();
+    [
+        (XenditChargeStatusModel::PENDING, 3i64, None),
+        (XenditChargeStatusModel::PENDING, -3, Some(false)),
+        (XenditChargeStatusModel::SUCCEEDED, 3, Some(true)),
+        (XenditChargeStatusModel::SUCCEEDED, -3, Some(true)),
+        (XenditChargeStatusModel::REFUNDED, -3, Some(true)),
+        (XenditChargeStatusModel::FAILED, 3, Some(false)),
+        (XenditChargeStatusModel::VOIDED, 3, Some(false)),
+    ]
+    .into_iter()
+    .map(|(status, expiry_mins, expect)| {
+        // the default expiry is 5 minutes after the given time
+        let t0 = t_now + Duration::minutes(expiry_mins - 5);
+        let mut mock_3pty = ut_default_charge_method_xendit(&t0, CurrencyDto::IDR);
+        if let Charge3partyModel::Xendit(c) = &mut mock_3pty {
+            c.status = status;
+        }
+        let actual = mock_3pty.pay_in_comfirmed();
+        assert_eq!(actual, expect);
+    })
+    .count();
+} // end of fn buyer_3pty_xendit_pay_in_confirm
+
 #[rustfmt::skip]
 #[test]
 fn charge_token_encode_ok() {
@@ -13,11 +13,12 @@ use ecommerce_common::api::dto::{CountryCode, CurrencyDto};
 use ecommerce_common::model::BaseProductIdentity;
 use payment::api::web::dto::ChargeStatusDto;
 use payment::model::{
-    BuyerPayInState, Charge3partyModel, Charge3partyStripeModel, ChargeBuyerMetaModel,
-    ChargeBuyerModel, ChargeLineBuyerModel, Merchant3partyStripeModel, OrderCurrencySnapshot,
-    PayLineAmountModel, StripeAccountCapabilityModel, StripeAccountCapableState,
-    StripeAccountLinkModel, StripeAccountSettingModel, StripeCheckoutPaymentStatusModel,
-    StripeSessionStatusModel,
+    BuyerPayInState, Charge3partyModel, Charge3partyStripeModel, Charge3partyXenditModel,
+    ChargeBuyerMetaModel, ChargeBuyerModel, ChargeLineBuyerModel, Merchant3partyStripeModel,
+    Merchant3partyXenditModel, OrderCurrencySnapshot, PayLineAmountModel,
+    StripeAccountCapabilityModel, StripeAccountCapableState, StripeAccountLinkModel,
+    StripeAccountSettingModel, StripeCheckoutPaymentStatusModel, StripeSessionStatusModel,
+    XenditAccountStatusModel, XenditChargeStatusModel,
 };
 
 pub(crate) fn ut_default_currency_snapshot(
@@ -47,6 +48,21 @@ pub(crate) fn ut_default_charge_method_stripe(t0: &DateTime<Utc>) -> Charge3part
     Charge3partyModel::Stripe(sess)
 }
 
+pub(crate) fn ut_default_charge_method_xendit(
+    t0: &DateTime<Utc>,
+    currency: CurrencyDto,
+) -> Charge3partyModel {
+    let c = Charge3partyXenditModel {
+        charge_id: "ewc_mock-f0e1d2c3-b4a5".to_string(),
+        reference_id: "mock-xendit-reference-id".to_string(),
+        channel_code: "ID_DANA".to_string(),
+        currency,
+        status: XenditChargeStatusModel::SUCCEEDED,
+        expiry: *t0 + Duration::minutes(5),
+    };
+    Charge3partyModel::Xendit(c)
+}
+
 pub(crate) type UTestChargeLineRawData = (
     (u32, u64, u16),               // id-of-charge-line
     ((i64, u32), (i64, u32), u32), // amount-paid
@@ -71,6 +87,7 @@ pub(crate) fn ut_setup_buyer_charge(
         meta,
         lines,
         currency_snapshot,
+        shipping: HashMap::new(),
     }
 }
 
@@ -143,3 +160,13 @@ pub(super) fn ut_default_merchant_3party_stripe() -> Merchant3partyStripeModel {
         update_link,
     }
 }
+
+pub(super) fn ut_default_merchant_3party_xendit() -> Merchant3partyXenditModel {
+    Merchant3partyXenditModel {
+        id: "5cafeb170a2b18519b1b8768".to_string(),
+        email: "hayley@wo0dberry.org".to_string(),
+        business_name: "cement tile".to_string(),
+        status: XenditAccountStatusModel::REGISTERED,
+        created: Local::now().to_utc() - Duration::days(3),
+    }
+}
@@ -3,7 +3,7 @@ use rust_decimal::Decimal;
 
 use ecommerce_common::api::dto::{
     CurrencyDto, CurrencySnapshotDto, OrderCurrencySnapshotDto, OrderLinePayDto,
-    OrderSellerCurrencyDto, PayAmountDto,
+    OrderSellerCurrencyDto, OrderShippingPayDto, PayAmountDto,
 };
 use ecommerce_common::model::BaseProductIdentity;
 
@@ -46,6 +46,8 @@ pub(super) fn ut_setup_order_replica(
         seller_id: d.0, product_id: d.1, attr_set_seq: d.2, quantity: d.3,
         amount: PayAmountDto {unit: d.4.to_string(), total: d.5.to_string()},
         reserved_until: (reserved_until + d.6).to_rfc3339(),
+        discount: None,
+        tax: None,
     })
     .collect::<Vec<_>>();
     let mock_currency_snapshot = {
@@ -123,6 +125,8 @@ fn convert_qty_zero() {
             unit: d.4.to_string(),
             total: d.5.to_string(),
         },
+        discount: None,
+        tax: None,
     })
     .collect::<Vec<_>>();
     let mock_currency_snapshot = ut_default_currency_snapshot_dto(vec![140, 141, 142]);
@@ -161,6 +165,8 @@ fn convert_line_expired() {
             unit: d.3.to_string(),
             total: d.4.to_string(),
         },
+        discount: None,
+        tax: None,
     })
     .collect::<Vec<_>>();
     let mock_currency_snapshot = ut_default_currency_snapshot_dto(vec![140, 141, 142]);
@@ -194,6 +200,8 @@ fn convert_missing_currency_1() {
         seller_id: d.0, product_id: d.1, attr_set_seq: 0, quantity: d.2,
         reserved_until: (now + d.5).to_rfc3339(),
         amount: PayAmountDto {unit: d.3.to_string(), total: d.4.to_string()},
+        discount: None,
+        tax: None,
     })
     .collect::<Vec<_>>();
     let mock_currency_snapshot = {
@@ -237,6 +245,8 @@ fn convert_missing_currency_2() {
             unit: d.3.to_string(),
             total: d.4.to_string(),
         },
+        discount: None,
+        tax: None,
     })
     .collect::<Vec<_>>();
     let mock_currency_snapshot = ut_default_currency_snapshot_dto(vec![143, 145]);
@@ -268,6 +278,8 @@ fn convert_corrupted_currency_rate() {
         seller_id: d.0, product_id: d.1, attr_set_seq: 0, quantity: d.2,
         reserved_until: (now + d.5).to_rfc3339(),
         amount: PayAmountDto {unit: d.3.to_string(), total: d.4.to_string()},
+        discount: None,
+        tax: None,
     })
     .collect::<Vec<_>>();
     let mock_currency_snapshot = {
@@ -307,6 +319,8 @@ fn convert_invalid_amount() {
         seller_id: d.0, product_id: d.1, attr_set_seq: d.2, quantity: d.3,
         reserved_until: reserved_until.to_rfc3339(),
         amount: PayAmountDto {unit: d.4.to_string(), total: d.5.to_string()},
+        discount: None,
+        tax: None,
     })
     .collect::<Vec<_>>();
     let mock_currency_snapshot = ut_default_currency_snapshot_dto(vec![140, 141, 142, 143, 144]);
@@ -350,3 +364,54 @@ fn convert_invalid_amount() {
         }).count();
     }
 } // end of fn convert_invalid_amount
+
+#[test]
+fn update_shipping_ok() {
+    let (mock_usr_id, mock_oid) = (456, "xyz987".to_string());
+    let reserved_until = Local::now().fixed_offset() + Duration::minutes(3);
+    let mut olines = ut_setup_order_replica(mock_usr_id, mock_oid, reserved_until);
+    let data = [(141, "12.5"), (143, "0"), (142, "7.05")]
+        .into_iter()
+        .map(|d| OrderShippingPayDto {
+            seller_id: d.0,
+            amount: d.1.to_string(),
+        })
+        .collect::<Vec<_>>();
+    let result = olines.try_update_shipping(data);
+    assert!(result.is_ok());
+    // zero fee is discarded
+    assert_eq!(olines.shipping.len(), 2);
+    let actual = olines
+        .shipping
+        .iter()
+        .map(|s| (s.seller_id, s.amount, s.paid))
+        .collect::<Vec<_>>();
+    assert_eq!(actual[0], (141, Decimal::new(125, 1), false));
+    assert_eq!(actual[1], (142, Decimal::new(705, 2), false));
+} // end of fn update_shipping_ok
+
+#[test]
+fn update_shipping_error() {
+    let (mock_usr_id, mock_oid) = (456, "xyz987".to_string());
+    let reserved_until = Local::now().fixed_offset() + Duration::minutes(3);
+    let mut olines = ut_setup_order_replica(mock_usr_id, mock_oid, reserved_until);
+    let data = [(141, "-3.5"), (149, "12"), (142, "7.0a"), (143, "9.9")]
+        .into_iter()
+        .map(|d| OrderShippingPayDto {
+            seller_id: d.0,
+            amount: d.1.to_string(),
+        })
+        .collect::<Vec<_>>();
+    let result = olines.try_update_shipping(data);
+    assert!(result.is_err());
+    if let Err(es) = result {
+        assert_eq!(es.len(), 3);
+        let cond = matches!(&es[0], OrderModelError::InvalidShippingFee(141, a) if a == "-3.5");
+        assert!(cond);
+        let cond = matches!(&es[1], OrderModelError::ShippingSellerNotFound(149));
+        assert!(cond);
+        let cond = matches!(&es[2], OrderModelError::InvalidShippingFee(142, a) if a == "7.0a");
+        assert!(cond);
+    }
+    assert!(olines.shipping.is_empty());
+} // end of fn update_shipping_error
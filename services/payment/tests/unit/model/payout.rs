@@ -7,12 +7,14 @@ use ecommerce_common::api::dto::CurrencyDto;
 use ecommerce_common::error::AppErrorCode;
 use payment::model::{
     BuyerPayInState, Charge3partyModel, ChargeBuyerModel, ChargeLineBuyerModel,
-    Merchant3partyModel, MerchantProfileModel, OrderCurrencySnapshot, PayoutModel,
-    PayoutModelError, StripeAccountCapableState, StripeCheckoutPaymentStatusModel,
+    Merchant3partyModel, MerchantProfileModel, OrderCurrencySnapshot, Payout3partyModel,
+    PayoutModel, PayoutModelError, StripeAccountCapableState, StripeCheckoutPaymentStatusModel,
+    XenditAccountStatusModel,
 };
 
 use super::{
-    ut_default_charge_method_stripe, ut_default_merchant_3party_stripe, ut_setup_buyer_charge,
+    ut_default_charge_method_stripe, ut_default_charge_method_xendit,
+    ut_default_merchant_3party_stripe, ut_default_merchant_3party_xendit, ut_setup_buyer_charge,
 };
 use crate::dto::ut_setup_storeprofile_dto;
 
@@ -127,6 +129,38 @@ fn create_ok() {
     }
 }
 
+#[test]
+fn create_with_shipping_ok() {
+    let (mock_buyer_id, mock_store_id, staff_usr_id) = (518u32, 1009u32, 2074u32);
+    let charge_ctime = Local::now().to_utc() - Duration::minutes(97);
+    let done_time = charge_ctime + Duration::minutes(15);
+    let payin_state = BuyerPayInState::OrderAppSynced(done_time);
+    let mut mock_charge_m = ut_setup_buyer_charge_inner(mock_buyer_id, charge_ctime, payin_state);
+    mock_charge_m
+        .shipping
+        .insert(mock_store_id, Decimal::new(1200, 1));
+    mock_charge_m.shipping.insert(1008, Decimal::new(350, 1));
+    let mock_merchant_prof = ut_setup_merchant_profile(mock_store_id, staff_usr_id);
+    let mock_merchant_3pty = ut_setup_merchant_3party_stripe();
+    let arg = (
+        mock_charge_m,
+        mock_merchant_prof,
+        mock_merchant_3pty,
+        staff_usr_id,
+        None,
+    );
+    let result = PayoutModel::try_from(arg);
+    assert!(result.is_ok());
+    if let Ok(v) = result {
+        // product lines of the store sum up to 7506.0, the shipping fee of
+        // other store is not transferred to this store
+        let amount_buyer = v.amount_buyer();
+        assert_eq!(amount_buyer, Decimal::new(76260, 1));
+        let amount_bs = v.amount_base();
+        assert_eq!(amount_bs, Decimal::new(23943, 2));
+    }
+} // end of fn create_with_shipping_ok
+
 #[rustfmt::skip]
 #[test]
 fn create_after_refund_ok() {
@@ -136,7 +170,7 @@ fn create_after_refund_ok() {
         let done_time = charge_ctime + Duration::minutes(15);
         let payin_state = BuyerPayInState::OrderAppSynced(done_time);
         let c = ut_setup_buyer_charge_inner(mock_buyer_id, charge_ctime, payin_state);
-        let ChargeBuyerModel { meta, currency_snapshot, lines }  = c;
+        let ChargeBuyerModel { meta, currency_snapshot, lines, shipping }  = c;
         let lines = lines.into_iter()
             .map(|line| {
                 let mut arg = line.into_parts();
@@ -151,7 +185,7 @@ fn create_after_refund_ok() {
                 ChargeLineBuyerModel::from(arg)
             })
             .collect::<Vec<_>>();
-        ChargeBuyerModel { meta, currency_snapshot, lines }
+        ChargeBuyerModel { meta, currency_snapshot, lines, shipping }
     };
     let mock_merchant_prof = ut_setup_merchant_profile(orig_store_id, staff_usr_id);
     let mock_merchant_3pty = ut_setup_merchant_3party_stripe();
@@ -380,3 +414,65 @@ fn create_err_3party_stripe_tx_grp_mismatch() {
         assert!(cond);
     }
 } // end of fn create_err_3party_stripe_tx_grp_mismatch
+
+#[rustfmt::skip]
+#[test]
+fn create_xendit_ok() {
+    let (mock_buyer_id, mock_store_id, staff_usr_id) = (518u32, 1009u32, 2074u32);
+    let charge_ctime = Local::now().to_utc() - Duration::minutes(96);
+    let mock_charge_m = {
+        let payin_state = BuyerPayInState::OrderAppSynced(charge_ctime + Duration::minutes(5));
+        let mut cm = ut_setup_buyer_charge_inner(mock_buyer_id, charge_ctime, payin_state);
+        let paymethod = ut_default_charge_method_xendit(&charge_ctime, CurrencyDto::TWD);
+        cm.meta.update_3party(paymethod);
+        cm
+    };
+    let mock_merchant_prof = ut_setup_merchant_profile(mock_store_id, staff_usr_id);
+    let mock_merchant_3pty = {
+        let mut mx = ut_default_merchant_3party_xendit();
+        mx.status = XenditAccountStatusModel::LIVE;
+        Merchant3partyModel::Xendit(mx)
+    };
+    let arg = (
+        mock_charge_m, mock_merchant_prof, mock_merchant_3pty,
+        staff_usr_id, None,
+    );
+    let result = PayoutModel::try_from(arg);
+    assert!(result.is_ok());
+    if let Ok(v) = result {
+        assert_eq!(v.merchant_id(), mock_store_id);
+        if let Payout3partyModel::Xendit(p3x) = v.thirdparty() {
+            assert_eq!(p3x.currency(), &CurrencyDto::TWD);
+            assert!(p3x.amount().is_none());
+        } else {
+            assert!(false);
+        }
+    }
+} // end of fn create_xendit_ok
+
+#[rustfmt::skip]
+#[test]
+fn create_err_xendit_merchant_not_live() {
+    let (mock_buyer_id, mock_store_id, staff_usr_id) = (518u32, 1009u32, 2074u32);
+    let charge_ctime = Local::now().to_utc() - Duration::minutes(96);
+    let mock_charge_m = {
+        let payin_state = BuyerPayInState::OrderAppSynced(charge_ctime + Duration::minutes(5));
+        let mut cm = ut_setup_buyer_charge_inner(mock_buyer_id, charge_ctime, payin_state);
+        let paymethod = ut_default_charge_method_xendit(&charge_ctime, CurrencyDto::TWD);
+        cm.meta.update_3party(paymethod);
+        cm
+    };
+    let mock_merchant_prof = ut_setup_merchant_profile(mock_store_id, staff_usr_id);
+    // Xendit hasn't completed verification of the sub-account
+    let mock_merchant_3pty = Merchant3partyModel::Xendit(ut_default_merchant_3party_xendit());
+    let arg = (
+        mock_charge_m, mock_merchant_prof, mock_merchant_3pty,
+        staff_usr_id, None,
+    );
+    let result = PayoutModel::try_from(arg);
+    assert!(result.is_err());
+    if let Err(e) = result {
+        let cond = matches!(e, PayoutModelError::MerchantPermissionDenied(sid) if sid == mock_store_id);
+        assert!(cond);
+    }
+} // end of fn create_err_xendit_merchant_not_live
@@ -39,7 +39,7 @@ fn ut_setup_processor(
 fn ut_setup_repo_merchant(
     res: Option<(MerchantProfileModel, Merchant3partyModel)>,
 ) -> Box<dyn AbstractMerchantRepo> {
-    MockMerchantRepo::build(None, res, None, None)
+    MockMerchantRepo::build(None, res, None, None, None)
 }
 
 #[rustfmt::skip]
@@ -13,33 +13,36 @@ use ecommerce_common::api::rpc::dto::OrderReplicaPaymentDto;
 
 use ecommerce_common::error::AppErrorCode;
 use ecommerce_common::model::BaseProductIdentity;
-use payment::adapter::cache::OrderSyncLockError;
+use payment::adapter::cache::{OrderSyncLockError, OrderSyncLockToken};
 use payment::adapter::processor::{
     AbstractPaymentProcessor, AppProcessorError, AppProcessorErrorReason, AppProcessorFnLabel,
     AppProcessorPayInResult,
 };
 use payment::adapter::repository::{
-    AbstractChargeRepo, AppRepoError, AppRepoErrorDetail, AppRepoErrorFnLabel,
+    AbstractChargeRepo, AbstractMerchantRepo, AppRepoError, AppRepoErrorDetail, AppRepoErrorFnLabel,
 };
 use payment::adapter::rpc::{AppRpcCtxError, AppRpcErrorFnLabel, AppRpcErrorReason, AppRpcReply};
 use payment::api::web::dto::{
     ChargeAmountOlineDto, ChargeReqDto, ChargeReqOrderDto, PaymentMethodErrorReason,
     PaymentMethodReqDto, PaymentMethodRespDto, StripeCheckoutSessionReqDto,
-    StripeCheckoutSessionRespDto, StripeCheckoutUImodeDto,
+    StripeCheckoutSessionRespDto, StripeCheckoutUImodeDto, XenditEwalletChannelDto,
+    XenditEwalletChargeReqDto,
 };
 use payment::model::{
-    BuyerPayInState, Charge3partyModel, OrderCurrencySnapshot, OrderLineModel, OrderLineModelSet,
-    PayLineAmountModel,
+    BuyerPayInState, Charge3partyModel, Merchant3partyModel, OrderCurrencySnapshot, OrderLineModel,
+    OrderLineModelSet, PayLineAmountModel,
 };
 use payment::usecase::{ChargeCreateUcError, ChargeCreateUseCase};
 use rust_decimal::Decimal;
 
 use super::{
-    MockChargeRepo, MockOrderSyncLockCache, MockPaymentProcessor, MockRpcClient, MockRpcContext,
-    MockRpcPublishEvent,
+    MockChargeRepo, MockMerchantRepo, MockOrderSyncLockCache, MockPaymentProcessor, MockRpcClient,
+    MockRpcContext, MockRpcPublishEvent,
 };
 use crate::auth::ut_setup_auth_claim;
+use crate::model::payout::{ut_setup_merchant_3party_stripe, ut_setup_merchant_profile};
 use crate::model::ut_default_charge_method_stripe;
+use crate::ut_setup_logctx;
 
 fn ut_saved_oline_set(mock_order_id: String, mock_usr_id: u32) -> OrderLineModelSet {
     let mock_seller_id = 379u32;
@@ -85,6 +88,7 @@ fn ut_saved_oline_set(mock_order_id: String, mock_usr_id: u32) -> OrderLineModel
         num_charges: 0,
         create_time: now.to_utc(),
         lines: vec![line],
+        shipping: Vec::new(),
     }
 } // end of fn ut_saved_oline_set
 
@@ -133,6 +137,8 @@ fn ut_orderpay_replica(mock_usr_id: u32, mock_order_id: String) -> Vec<u8> {
                 unit: "300.01".to_string(),
                 total: "1800.06".to_string(),
             },
+            discount: None,
+            tax: None,
         }],
         currency: OrderCurrencySnapshotDto {
             snapshot: vec![
@@ -163,6 +169,7 @@ fn ut_orderpay_replica(mock_usr_id: u32, mock_order_id: String) -> Vec<u8> {
             },
             address: None,
         },
+        shipping: vec![],
     };
     serde_json::to_vec(&replica).unwrap()
 } // end of fn ut_orderpay_replica
@@ -194,6 +201,17 @@ fn ut_setup_repo(
     )
 }
 
+// the seller in the saved order lines onboarded with the 3rd party or not
+fn ut_setup_repo_merchant(onboarded: bool) -> Box<dyn AbstractMerchantRepo> {
+    let res = if onboarded {
+        let mprof = ut_setup_merchant_profile(379, 5566);
+        Some((mprof, ut_setup_merchant_3party_stripe()))
+    } else {
+        None
+    };
+    MockMerchantRepo::build(None, res, None, None, None)
+}
+
 #[rustfmt::skip]
 fn ut_setup_processor(
     res: Option<Result<(AppProcessorPayInResult, Charge3partyModel), AppProcessorError>>,
@@ -225,6 +243,8 @@ async fn ok_with_existing_order_replica() {
         rpc_ctx: Arc::new(mock_rpc_ctx),
         ordersync_lockset: Arc::new(Box::new(mock_sync_cache)),
         repo: mock_repo,
+        repo_m: ut_setup_repo_merchant(true),
+        logctx: ut_setup_logctx(),
     };
     let mock_authed_claim = ut_setup_auth_claim(mock_usr_id, 600i64);
     let mock_req = ut_charge_req_dto(mock_order_id.clone());
@@ -241,7 +261,7 @@ async fn ok_with_rpc_replica_order() {
     let mock_order_id = "ut-origin-order-id".to_string();
     let mock_repo = ut_setup_repo(Some(Ok(None)), Some(Ok(())), Some(Ok(())));
     let mock_sync_cache = MockOrderSyncLockCache {
-        _acquire_result: Mutex::new(Some(Ok(true))),
+        _acquire_result: Mutex::new(Some(Ok(Some(OrderSyncLockToken("1a2b".to_string()))))),
         _release_result: Mutex::new(Some(Ok(()))),
     };
     let mock_reply = AppRpcReply {
@@ -260,6 +280,8 @@ async fn ok_with_rpc_replica_order() {
         rpc_ctx: Arc::new(mock_rpc_ctx),
         ordersync_lockset: Arc::new(Box::new(mock_sync_cache)),
         repo: mock_repo,
+        repo_m: ut_setup_repo_merchant(true),
+        logctx: ut_setup_logctx(),
     };
     let mock_authed_claim = ut_setup_auth_claim(mock_usr_id, 600i64);
     let mock_req = ut_charge_req_dto(mock_order_id.clone());
@@ -291,6 +313,8 @@ async fn load_unpaid_order_failure() {
         rpc_ctx: Arc::new(mock_rpc_ctx),
         ordersync_lockset: Arc::new(Box::new(mock_sync_cache)),
         repo: mock_repo,
+        repo_m: ut_setup_repo_merchant(true),
+        logctx: ut_setup_logctx(),
     };
     let mock_authed_claim = ut_setup_auth_claim(mock_usr_id, 600i64);
     let mock_req = ut_charge_req_dto(mock_order_id.clone());
@@ -310,7 +334,7 @@ async fn sync_order_get_lock_failure() {
     let mock_order_id = "ut-origin-order-id".to_string();
     let mock_repo = ut_setup_repo(Some(Ok(None)), None, None);
     let mock_sync_cache = MockOrderSyncLockCache {
-        _acquire_result: Mutex::new(Some(Ok(false))),
+        _acquire_result: Mutex::new(Some(Ok(None))),
         _release_result: Mutex::new(None),
     };
     let mock_rpc_ctx = MockRpcContext::build(None);
@@ -320,6 +344,8 @@ async fn sync_order_get_lock_failure() {
         rpc_ctx: Arc::new(mock_rpc_ctx),
         ordersync_lockset: Arc::new(Box::new(mock_sync_cache)),
         repo: mock_repo,
+        repo_m: ut_setup_repo_merchant(true),
+        logctx: ut_setup_logctx(),
     };
     let mock_authed_claim = ut_setup_auth_claim(mock_usr_id, 600i64);
     let mock_req = ut_charge_req_dto(mock_order_id.clone());
@@ -337,7 +363,7 @@ async fn sync_order_release_lock_failure() {
     let mock_order_id = "ut-origin-order-id".to_string();
     let mock_repo = ut_setup_repo(Some(Ok(None)), None, None);
     let mock_sync_cache = MockOrderSyncLockCache {
-        _acquire_result: Mutex::new(Some(Ok(true))),
+        _acquire_result: Mutex::new(Some(Ok(Some(OrderSyncLockToken("1a2b".to_string()))))),
         _release_result: Mutex::new(Some(Err(OrderSyncLockError))),
     };
     let mock_reply = AppRpcReply {
@@ -352,6 +378,8 @@ async fn sync_order_release_lock_failure() {
         rpc_ctx: Arc::new(mock_rpc_ctx),
         ordersync_lockset: Arc::new(Box::new(mock_sync_cache)),
         repo: mock_repo,
+        repo_m: ut_setup_repo_merchant(true),
+        logctx: ut_setup_logctx(),
     };
     let mock_authed_claim = ut_setup_auth_claim(mock_usr_id, 600i64);
     let mock_req = ut_charge_req_dto(mock_order_id.clone());
@@ -369,7 +397,7 @@ async fn rpc_acquire_conn_error() {
     let mock_order_id = "ut-origin-order-id".to_string();
     let mock_repo = ut_setup_repo(Some(Ok(None)), None, None);
     let mock_sync_cache = MockOrderSyncLockCache {
-        _acquire_result: Mutex::new(Some(Ok(true))),
+        _acquire_result: Mutex::new(Some(Ok(Some(OrderSyncLockToken("1a2b".to_string()))))),
         _release_result: Mutex::new(Some(Ok(()))),
     };
     let rpc_expect_error = AppRpcCtxError {
@@ -383,6 +411,8 @@ async fn rpc_acquire_conn_error() {
         rpc_ctx: Arc::new(mock_rpc_ctx),
         ordersync_lockset: Arc::new(Box::new(mock_sync_cache)),
         repo: mock_repo,
+        repo_m: ut_setup_repo_merchant(true),
+        logctx: ut_setup_logctx(),
     };
     let mock_authed_claim = ut_setup_auth_claim(mock_usr_id, 600i64);
     let mock_req = ut_charge_req_dto(mock_order_id.clone());
@@ -404,7 +434,7 @@ async fn rpc_publish_error_replica_order() {
     let mock_order_id = "ut-origin-order-id".to_string();
     let mock_repo = ut_setup_repo(Some(Ok(None)), None, None);
     let mock_sync_cache = MockOrderSyncLockCache {
-        _acquire_result: Mutex::new(Some(Ok(true))),
+        _acquire_result: Mutex::new(Some(Ok(Some(OrderSyncLockToken("1a2b".to_string()))))),
         _release_result: Mutex::new(Some(Ok(()))),
     };
     let rpc_expect_error = AppRpcCtxError {
@@ -419,6 +449,8 @@ async fn rpc_publish_error_replica_order() {
         rpc_ctx: Arc::new(mock_rpc_ctx),
         ordersync_lockset: Arc::new(Box::new(mock_sync_cache)),
         repo: mock_repo,
+        repo_m: ut_setup_repo_merchant(true),
+        logctx: ut_setup_logctx(),
     };
     let mock_authed_claim = ut_setup_auth_claim(mock_usr_id, 600i64);
     let mock_req = ut_charge_req_dto(mock_order_id.clone());
@@ -440,7 +472,7 @@ async fn rpc_reply_error_replica_order() {
     let mock_order_id = "ut-origin-order-id".to_string();
     let mock_repo = ut_setup_repo(Some(Ok(None)), None, None);
     let mock_sync_cache = MockOrderSyncLockCache {
-        _acquire_result: Mutex::new(Some(Ok(true))),
+        _acquire_result: Mutex::new(Some(Ok(Some(OrderSyncLockToken("1a2b".to_string()))))),
         _release_result: Mutex::new(Some(Ok(()))),
     };
     let rpc_expect_error = AppRpcCtxError {
@@ -456,6 +488,8 @@ async fn rpc_reply_error_replica_order() {
         rpc_ctx: Arc::new(mock_rpc_ctx),
         ordersync_lockset: Arc::new(Box::new(mock_sync_cache)),
         repo: mock_repo,
+        repo_m: ut_setup_repo_merchant(true),
+        logctx: ut_setup_logctx(),
     };
     let mock_authed_claim = ut_setup_auth_claim(mock_usr_id, 600i64);
     let mock_req = ut_charge_req_dto(mock_order_id.clone());
@@ -482,7 +516,7 @@ async fn save_replica_order_failure() {
     };
     let mock_repo = ut_setup_repo(Some(Ok(None)), Some(Err(repo_expect_error)), None);
     let mock_sync_cache = MockOrderSyncLockCache {
-        _acquire_result: Mutex::new(Some(Ok(true))),
+        _acquire_result: Mutex::new(Some(Ok(Some(OrderSyncLockToken("1a2b".to_string()))))),
         _release_result: Mutex::new(Some(Ok(()))),
     };
     let mock_reply = AppRpcReply {
@@ -497,6 +531,8 @@ async fn save_replica_order_failure() {
         rpc_ctx: Arc::new(mock_rpc_ctx),
         ordersync_lockset: Arc::new(Box::new(mock_sync_cache)),
         repo: mock_repo,
+        repo_m: ut_setup_repo_merchant(true),
+        logctx: ut_setup_logctx(),
     };
     let mock_authed_claim = ut_setup_auth_claim(mock_usr_id, 600i64);
     let mock_req = ut_charge_req_dto(mock_order_id.clone());
@@ -516,7 +552,7 @@ async fn processor_start_payin_failure() {
     let mock_order_id = "ut-origin-order-id".to_string();
     let mock_repo = ut_setup_repo(Some(Ok(None)), Some(Ok(())), Some(Ok(())));
     let mock_sync_cache = MockOrderSyncLockCache {
-        _acquire_result: Mutex::new(Some(Ok(true))),
+        _acquire_result: Mutex::new(Some(Ok(Some(OrderSyncLockToken("1a2b".to_string()))))),
         _release_result: Mutex::new(Some(Ok(()))),
     };
     let mock_reply = AppRpcReply {
@@ -537,6 +573,8 @@ async fn processor_start_payin_failure() {
         rpc_ctx: Arc::new(mock_rpc_ctx),
         ordersync_lockset: Arc::new(Box::new(mock_sync_cache)),
         repo: mock_repo,
+        repo_m: ut_setup_repo_merchant(true),
+        logctx: ut_setup_logctx(),
     };
     let mock_authed_claim = ut_setup_auth_claim(mock_usr_id, 600i64);
     let mock_req = ut_charge_req_dto(mock_order_id.clone());
@@ -561,7 +599,7 @@ async fn save_new_chargeline_failure() {
     };
     let mock_repo = ut_setup_repo(Some(Ok(None)), Some(Ok(())), Some(Err(repo_expect_error)));
     let mock_sync_cache = MockOrderSyncLockCache {
-        _acquire_result: Mutex::new(Some(Ok(true))),
+        _acquire_result: Mutex::new(Some(Ok(Some(OrderSyncLockToken("1a2b".to_string()))))),
         _release_result: Mutex::new(Some(Ok(()))),
     };
     let mock_reply = AppRpcReply {
@@ -580,6 +618,8 @@ async fn save_new_chargeline_failure() {
         rpc_ctx: Arc::new(mock_rpc_ctx),
         ordersync_lockset: Arc::new(Box::new(mock_sync_cache)),
         repo: mock_repo,
+        repo_m: ut_setup_repo_merchant(true),
+        logctx: ut_setup_logctx(),
     };
     let mock_authed_claim = ut_setup_auth_claim(mock_usr_id, 600i64);
     let mock_req = ut_charge_req_dto(mock_order_id.clone());
@@ -592,3 +632,124 @@ async fn save_new_chargeline_failure() {
         assert!(false);
     }
 } // end of fn save_new_chargeline_failure
+
+#[actix_web::test]
+async fn route_err_seller_not_onboarded() {
+    let mock_usr_id = 1234u32;
+    let mock_order_id = "ut-origin-order-id".to_string();
+    let mock_oline_set = ut_saved_oline_set(mock_order_id.clone(), mock_usr_id);
+    let mock_repo = ut_setup_repo(Some(Ok(Some(mock_oline_set))), None, None);
+    let mock_sync_cache = MockOrderSyncLockCache {
+        _acquire_result: Mutex::new(None),
+        _release_result: Mutex::new(None),
+    };
+    let uc = ChargeCreateUseCase {
+        processors: Arc::new(ut_setup_processor(None)),
+        rpc_ctx: Arc::new(MockRpcContext::build(None)),
+        ordersync_lockset: Arc::new(Box::new(mock_sync_cache)),
+        repo: mock_repo,
+        repo_m: ut_setup_repo_merchant(false),
+        logctx: ut_setup_logctx(),
+    };
+    let mock_authed_claim = ut_setup_auth_claim(mock_usr_id, 600i64);
+    let mock_req = ut_charge_req_dto(mock_order_id.clone());
+    let result = uc.execute(mock_authed_claim, mock_req).await;
+    assert!(result.is_err());
+    if let Err(ChargeCreateUcError::ClientBadRequest(e)) = result {
+        let sellers = e.sellers.unwrap();
+        assert_eq!(sellers.len(), 1);
+        assert_eq!(sellers[0].seller_id, 379);
+        assert!(sellers[0].not_onboarded);
+        assert!(!sellers[0].not_enabled);
+        assert!(sellers[0].currency.is_none());
+    } else {
+        assert!(false);
+    }
+} // end of fn route_err_seller_not_onboarded
+
+#[actix_web::test]
+async fn route_err_seller_not_enabled() {
+    let mock_usr_id = 1234u32;
+    let mock_order_id = "ut-origin-order-id".to_string();
+    let mock_oline_set = ut_saved_oline_set(mock_order_id.clone(), mock_usr_id);
+    let mock_repo = ut_setup_repo(Some(Ok(Some(mock_oline_set))), None, None);
+    let mock_sync_cache = MockOrderSyncLockCache {
+        _acquire_result: Mutex::new(None),
+        _release_result: Mutex::new(None),
+    };
+    let mock_repo_m = {
+        let mprof = ut_setup_merchant_profile(379, 5566);
+        let mut m3pty = ut_setup_merchant_3party_stripe();
+        if let Merchant3partyModel::Stripe(s) = &mut m3pty {
+            // Stripe disabled the connected account, e.g. documents required
+            s.charges_enabled = false;
+        }
+        MockMerchantRepo::build(None, Some((mprof, m3pty)), None, None, None)
+    };
+    let uc = ChargeCreateUseCase {
+        processors: Arc::new(ut_setup_processor(None)),
+        rpc_ctx: Arc::new(MockRpcContext::build(None)),
+        ordersync_lockset: Arc::new(Box::new(mock_sync_cache)),
+        repo: mock_repo,
+        repo_m: mock_repo_m,
+        logctx: ut_setup_logctx(),
+    };
+    let mock_authed_claim = ut_setup_auth_claim(mock_usr_id, 600i64);
+    let mock_req = ut_charge_req_dto(mock_order_id.clone());
+    let result = uc.execute(mock_authed_claim, mock_req).await;
+    assert!(result.is_err());
+    if let Err(ChargeCreateUcError::ClientBadRequest(e)) = result {
+        let sellers = e.sellers.unwrap();
+        assert_eq!(sellers.len(), 1);
+        assert_eq!(sellers[0].seller_id, 379);
+        assert!(!sellers[0].not_onboarded);
+        assert!(sellers[0].not_enabled);
+        assert!(sellers[0].currency.is_none());
+    } else {
+        assert!(false);
+    }
+} // end of fn route_err_seller_not_enabled
+
+#[actix_web::test]
+async fn route_err_currency_unsupported() {
+    let mock_usr_id = 1234u32;
+    let mock_order_id = "ut-origin-order-id".to_string();
+    let mock_oline_set = ut_saved_oline_set(mock_order_id.clone(), mock_usr_id);
+    let mock_repo = ut_setup_repo(Some(Ok(Some(mock_oline_set))), None, None);
+    let mock_sync_cache = MockOrderSyncLockCache {
+        _acquire_result: Mutex::new(None),
+        _release_result: Mutex::new(None),
+    };
+    let uc = ChargeCreateUseCase {
+        processors: Arc::new(ut_setup_processor(None)),
+        rpc_ctx: Arc::new(MockRpcContext::build(None)),
+        ordersync_lockset: Arc::new(Box::new(mock_sync_cache)),
+        repo: mock_repo,
+        repo_m: ut_setup_repo_merchant(true),
+        logctx: ut_setup_logctx(),
+    };
+    let mock_authed_claim = ut_setup_auth_claim(mock_usr_id, 600i64);
+    let mock_req = {
+        let mut r = ut_charge_req_dto(mock_order_id.clone());
+        // the buyer chose TWD, which is not supported by e-wallets in Xendit
+        r.method = PaymentMethodReqDto::Xendit(XenditEwalletChargeReqDto {
+            channel_code: XenditEwalletChannelDto::ID_OVO,
+            success_redirect_url: None,
+            failure_redirect_url: None,
+            mobile_number: Some("+628123456789".to_string()),
+        });
+        r
+    };
+    let result = uc.execute(mock_authed_claim, mock_req).await;
+    assert!(result.is_err());
+    if let Err(ChargeCreateUcError::ClientBadRequest(e)) = result {
+        let sellers = e.sellers.unwrap();
+        assert_eq!(sellers.len(), 1);
+        assert_eq!(sellers[0].seller_id, 379);
+        assert!(!sellers[0].not_onboarded);
+        assert!(!sellers[0].not_enabled);
+        assert_eq!(sellers[0].currency, Some(CurrencyDto::TWD));
+    } else {
+        assert!(false);
+    }
+} // end of fn route_err_currency_unsupported
@@ -52,7 +52,7 @@ fn ut_setup_repo_charge(
 fn ut_setup_repo_merchant(
     saved_prof: Option<MerchantProfileModel>
 ) -> Box<dyn AbstractMerchantRepo> {
-    MockMerchantRepo::build(None, None, saved_prof, None)
+    MockMerchantRepo::build(None, None, saved_prof, None, None)
 }
 
 fn ut_setup_repo_refund(saved_req: Option<OrderRefundModel>) -> Box<dyn AbstractRefundRepo> {
@@ -2,7 +2,9 @@ mod capture_charge;
 mod create_charge;
 mod finalize_refund;
 mod onboard;
+mod processor_webhook;
 mod refresh_charge_status;
+mod sync_order_pay;
 
 use std::boxed::Box;
 use std::result::Result;
@@ -16,14 +18,16 @@ use ecommerce_common::api::rpc::dto::StoreProfileReplicaDto;
 use ecommerce_common::error::AppErrorCode;
 use ecommerce_common::model::order::BillingModel;
 
-use payment::adapter::cache::{AbstractOrderSyncLockCache, OrderSyncLockError};
+use payment::adapter::cache::{AbstractOrderSyncLockCache, OrderSyncLockError, OrderSyncLockToken};
 use payment::adapter::processor::{
     AbstractPaymentProcessor, AppProcessorError, AppProcessorErrorReason, AppProcessorFnLabel,
     AppProcessorMerchantResult, AppProcessorPayInResult, AppProcessorPayoutResult,
+    AppProcessorWebhookEvent,
 };
 use payment::adapter::repository::{
-    AbstractChargeRepo, AbstractMerchantRepo, AbstractRefundRepo, AppRefundRslvReqCallback,
-    AppRefundRslvReqOkReturn, AppRepoError, AppRepoErrorDetail, AppRepoErrorFnLabel,
+    AbstractChargeRepo, AbstractMerchantRepo, AbstractProcessorEventRepo, AbstractRefundRepo,
+    AppRefundRslvReqCallback, AppRefundRslvReqOkReturn, AppRepoError, AppRepoErrorDetail,
+    AppRepoErrorFnLabel, ProcessorEventClaim,
 };
 use payment::adapter::rpc::{
     AbsRpcClientContext, AbstractRpcClient, AbstractRpcContext, AbstractRpcPublishEvent,
@@ -33,7 +37,7 @@ use payment::api::web::dto::{PaymentMethodReqDto, RefundCompletionReqDto, StoreO
 use payment::model::{
     Charge3partyModel, ChargeBuyerMetaModel, ChargeBuyerModel, ChargeLineBuyerModel,
     ChargeRefundMap, Label3party, Merchant3partyModel, MerchantProfileModel, OrderLineModelSet,
-    OrderRefundModel, PayoutModel, RefundReqResolutionModel,
+    OrderRefundModel, PayoutModel, PayoutTransferStateModel, RefundReqResolutionModel,
 };
 
 struct MockChargeRepo {
@@ -48,9 +52,11 @@ struct MockChargeRepo {
     _create_payout_result: Mutex<Option<Result<(), AppRepoError>>>,
     _read_charge_ids: Mutex<Option<Option<(u32, Vec<DateTime<Utc>>)>>>,
     _update_linerefund_result: Mutex<Option<Result<(), AppRepoError>>>,
+    _read_order_sync_pending: Mutex<Vec<(u32, DateTime<Utc>, u16)>>,
 }
 
 impl MockChargeRepo {
+    #[rustfmt::skip]
     fn build(
         unpaid_olines: Option<Result<Option<OrderLineModelSet>, AppRepoError>>,
         create_order_res: Option<Result<(), AppRepoError>>,
@@ -64,7 +70,43 @@ impl MockChargeRepo {
         rd_chrg_ids: Option<(u32, Vec<DateTime<Utc>>)>,
         update_linerfd_res: Option<Result<(), AppRepoError>>,
     ) -> Box<dyn AbstractChargeRepo> {
-        Box::new(Self {
+        let obj = Self::_new(
+            unpaid_olines, create_order_res, create_charge_res,
+            chargemeta, all_chargelines, update_meta_res,
+            charge_by_merchant, rd_payout, create_payout_res,
+            rd_chrg_ids, update_linerfd_res,
+        );
+        Box::new(obj)
+    }
+    #[rustfmt::skip]
+    fn build_order_sync(
+        pending: Vec<(u32, DateTime<Utc>, u16)>,
+        chargemeta: Option<Result<Option<ChargeBuyerMetaModel>, AppRepoError>>,
+        all_chargelines: Option<Result<Vec<ChargeLineBuyerModel>, AppRepoError>>,
+        update_meta_res: Option<Result<(), AppRepoError>>,
+    ) -> Box<dyn AbstractChargeRepo> {
+        let mut obj = Self::_new(
+            None, None, None,
+            chargemeta, all_chargelines, update_meta_res,
+            None, None, None, None, None,
+        );
+        obj._read_order_sync_pending = Mutex::new(pending);
+        Box::new(obj)
+    }
+    fn _new(
+        unpaid_olines: Option<Result<Option<OrderLineModelSet>, AppRepoError>>,
+        create_order_res: Option<Result<(), AppRepoError>>,
+        create_charge_res: Option<Result<(), AppRepoError>>,
+        chargemeta: Option<Result<Option<ChargeBuyerMetaModel>, AppRepoError>>,
+        all_chargelines: Option<Result<Vec<ChargeLineBuyerModel>, AppRepoError>>,
+        update_meta_res: Option<Result<(), AppRepoError>>,
+        charge_by_merchant: Option<Vec<ChargeBuyerModel>>,
+        rd_payout: Option<PayoutModel>,
+        create_payout_res: Option<Result<(), AppRepoError>>,
+        rd_chrg_ids: Option<(u32, Vec<DateTime<Utc>>)>,
+        update_linerfd_res: Option<Result<(), AppRepoError>>,
+    ) -> Self {
+        Self {
             _expect_unpaid_olines: Mutex::new(unpaid_olines),
             _create_order_result: Mutex::new(create_order_res),
             _create_charge_result: Mutex::new(create_charge_res),
@@ -76,7 +118,8 @@ impl MockChargeRepo {
             _create_payout_result: Mutex::new(create_payout_res),
             _read_charge_ids: Mutex::new(Some(rd_chrg_ids)),
             _update_linerefund_result: Mutex::new(update_linerfd_res),
-        })
+            _read_order_sync_pending: Mutex::new(Vec::new()),
+        }
     }
 } // end of impl MockChargeRepo
 
@@ -142,6 +185,16 @@ impl AbstractChargeRepo for MockChargeRepo {
         out
     }
 
+    async fn update_charge_progress_sync_pending(
+        &self,
+        _meta: ChargeBuyerMetaModel,
+        _next_retry: DateTime<Utc>,
+    ) -> Result<(), AppRepoError> {
+        let mut g = self._update_chargemeta_result.lock().await;
+        let out = g.take().unwrap();
+        out
+    }
+
     async fn update_lines_refund(&self, _cl_map: ChargeRefundMap) -> Result<(), AppRepoError> {
         let mut g = self._update_linerefund_result.lock().await;
         let out = g.take().unwrap();
@@ -175,6 +228,52 @@ impl AbstractChargeRepo for MockChargeRepo {
         let out = g.take().unwrap();
         out
     }
+
+    async fn save_order_sync_pending(
+        &self,
+        _buyer_id: u32,
+        _charge_ctime: DateTime<Utc>,
+        _next_retry: DateTime<Utc>,
+    ) -> Result<(), AppRepoError> {
+        Ok(())
+    }
+
+    async fn fetch_order_sync_pending(
+        &self,
+        _t_due: DateTime<Utc>,
+        _limit: u16,
+    ) -> Result<Vec<(u32, DateTime<Utc>, u16)>, AppRepoError> {
+        let mut g = self._read_order_sync_pending.lock().await;
+        let out = g.split_off(0);
+        Ok(out)
+    }
+
+    async fn remove_order_sync_pending(
+        &self,
+        _buyer_id: u32,
+        _charge_ctime: DateTime<Utc>,
+    ) -> Result<(), AppRepoError> {
+        Ok(())
+    }
+
+    async fn save_refund_confirmed(
+        &self,
+        _buyer_id: u32,
+        _charge_ctime: DateTime<Utc>,
+        _t_confirmed: DateTime<Utc>,
+    ) -> Result<(), AppRepoError> {
+        Ok(())
+    }
+
+    async fn save_payout_transfer_state(
+        &self,
+        _store_id: u32,
+        _buyer_id: u32,
+        _charge_ctime: DateTime<Utc>,
+        _state: PayoutTransferStateModel,
+    ) -> Result<(), AppRepoError> {
+        Ok(())
+    }
 } // end of impl MockChargeRepo
 
 struct MockMerchantRepo {
@@ -182,6 +281,7 @@ struct MockMerchantRepo {
     _fetch_result: Mutex<Option<(MerchantProfileModel, Merchant3partyModel)>>,
     _fetch_profile_result: Mutex<Option<MerchantProfileModel>>,
     _update3pty_result: Mutex<Option<Result<(), AppRepoError>>>,
+    _fetch_by_acct_result: Mutex<Option<(u32, Merchant3partyModel)>>,
 } // end of trait AbstractMerchantRepo
 
 impl MockMerchantRepo {
@@ -190,12 +290,14 @@ impl MockMerchantRepo {
         fetch_res: Option<(MerchantProfileModel, Merchant3partyModel)>,
         fetch_prof_res: Option<MerchantProfileModel>,
         update3pt_res: Option<Result<(), AppRepoError>>,
+        fetch_by_acct_res: Option<(u32, Merchant3partyModel)>,
     ) -> Box<dyn AbstractMerchantRepo> {
         let obj = Self {
             _create_result: Mutex::new(create_res),
             _fetch_result: Mutex::new(fetch_res),
             _fetch_profile_result: Mutex::new(fetch_prof_res),
             _update3pty_result: Mutex::new(update3pt_res),
+            _fetch_by_acct_result: Mutex::new(fetch_by_acct_res),
         };
         Box::new(obj)
     }
@@ -238,6 +340,15 @@ impl AbstractMerchantRepo for MockMerchantRepo {
         let out = g.take();
         Ok(out)
     }
+    async fn fetch_by_3party_account(
+        &self,
+        _label3pty: Label3party,
+        _account_id: &str,
+    ) -> Result<Option<(u32, Merchant3partyModel)>, AppRepoError> {
+        let mut g = self._fetch_by_acct_result.lock().await;
+        let out = g.take();
+        Ok(out)
+    }
 } // end of impl MockMerchantRepo
 
 struct MockRefundRepo {
@@ -298,19 +409,80 @@ impl AbstractRefundRepo for MockRefundRepo {
     }
 } // end of impl MockRefundRepo
 
+struct MockProcessorEventRepo {
+    // event ID, and whether the event has been applied
+    _claimed: Mutex<Vec<(String, bool)>>,
+}
+
+impl MockProcessorEventRepo {
+    fn build(claimed: Vec<(&str, bool)>) -> Box<dyn AbstractProcessorEventRepo> {
+        let claimed = claimed
+            .into_iter()
+            .map(|(evt_id, applied)| (evt_id.to_string(), applied))
+            .collect::<Vec<_>>();
+        let obj = Self {
+            _claimed: Mutex::new(claimed),
+        };
+        Box::new(obj)
+    }
+}
+
+#[async_trait]
+impl AbstractProcessorEventRepo for MockProcessorEventRepo {
+    async fn try_claim(
+        &self,
+        _label3pty: Label3party,
+        event_id: &str,
+    ) -> Result<ProcessorEventClaim, AppRepoError> {
+        let mut g = self._claimed.lock().await;
+        let out = match g.iter().find(|v| v.0.as_str() == event_id) {
+            Some((_, true)) => ProcessorEventClaim::Applied,
+            Some((_, false)) => ProcessorEventClaim::InProgress,
+            None => {
+                g.push((event_id.to_string(), false));
+                ProcessorEventClaim::Claimed
+            }
+        };
+        Ok(out)
+    }
+    async fn complete(&self, _label3pty: Label3party, event_id: &str) -> Result<(), AppRepoError> {
+        let mut g = self._claimed.lock().await;
+        g.iter_mut()
+            .filter(|v| v.0.as_str() == event_id)
+            .for_each(|v| {
+                v.1 = true;
+            });
+        Ok(())
+    }
+    async fn release(&self, _label3pty: Label3party, event_id: &str) -> Result<(), AppRepoError> {
+        let mut g = self._claimed.lock().await;
+        g.retain(|v| v.0.as_str() != event_id || v.1);
+        Ok(())
+    }
+} // end of impl MockProcessorEventRepo
+
 struct MockOrderSyncLockCache {
-    _acquire_result: Mutex<Option<Result<bool, OrderSyncLockError>>>,
+    _acquire_result: Mutex<Option<Result<Option<OrderSyncLockToken>, OrderSyncLockError>>>,
     _release_result: Mutex<Option<Result<(), OrderSyncLockError>>>,
 }
 
 #[async_trait]
 impl AbstractOrderSyncLockCache for MockOrderSyncLockCache {
-    async fn acquire(&self, _usr_id: u32, _oid: &str) -> Result<bool, OrderSyncLockError> {
+    async fn acquire(
+        &self,
+        _usr_id: u32,
+        _oid: &str,
+    ) -> Result<Option<OrderSyncLockToken>, OrderSyncLockError> {
         let mut g = self._acquire_result.lock().await;
         let out = g.take().unwrap();
         out
     }
-    async fn release(&self, _usr_id: u32, _oid: &str) -> Result<(), OrderSyncLockError> {
+    async fn release(
+        &self,
+        _usr_id: u32,
+        _oid: &str,
+        _token: OrderSyncLockToken,
+    ) -> Result<(), OrderSyncLockError> {
         let mut g = self._release_result.lock().await;
         let out = g.take().unwrap();
         out
@@ -319,6 +491,7 @@ impl AbstractOrderSyncLockCache for MockOrderSyncLockCache {
 
 struct MockRpcContext {
     _acquire_result: Mutex<Option<Result<Box<dyn AbstractRpcClient>, AppRpcCtxError>>>,
+    _last_error: Mutex<Option<AppRpcCtxError>>,
 }
 struct MockRpcClient {
     _send_req_result: Mutex<Option<Result<Box<dyn AbstractRpcPublishEvent>, AppRpcCtxError>>>,
@@ -333,8 +506,17 @@ impl AbstractRpcContext for MockRpcContext {}
 impl AbsRpcClientContext for MockRpcContext {
     async fn acquire(&self) -> Result<Box<dyn AbstractRpcClient>, AppRpcCtxError> {
         let mut g = self._acquire_result.lock().await;
-        let out = g.take().unwrap();
-        out
+        let mut g_err = self._last_error.lock().await;
+        // the caller may retry after low-level error, the same error is
+        // returned in such case
+        match g.take() {
+            Some(Err(e)) => {
+                *g_err = Some(e.clone());
+                Err(e)
+            }
+            Some(Ok(client)) => Ok(client),
+            None => Err(g_err.clone().unwrap()),
+        }
     }
 }
 impl MockRpcContext {
@@ -343,6 +525,7 @@ impl MockRpcContext {
     ) -> Box<dyn AbstractRpcContext> {
         Box::new(Self {
             _acquire_result: Mutex::new(acquire_res),
+            _last_error: Mutex::new(None),
         })
     }
 }
@@ -480,4 +663,15 @@ impl AbstractPaymentProcessor for MockPaymentProcessor {
             Ok(resolve_m)
         }
     }
+    fn parse_webhook_event(
+        &self,
+        _label3pty: Label3party,
+        _signature: &str,
+        _payload: &[u8],
+    ) -> Result<AppProcessorWebhookEvent, AppProcessorError> {
+        Err(AppProcessorError {
+            reason: AppProcessorErrorReason::NotImplemented,
+            fn_label: AppProcessorFnLabel::ParseWebhookEvent,
+        })
+    }
 } // end of impl MockPaymentProcessor
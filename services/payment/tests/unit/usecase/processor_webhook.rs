@@ -0,0 +1,393 @@
+use std::boxed::Box;
+
+use chrono::{DateTime, Duration, Local, Utc};
+use ring::hmac;
+use serde_json::json;
+
+use payment::adapter::processor::AppProcessorErrorReason;
+use payment::adapter::repository::{AbstractChargeRepo, AbstractMerchantRepo};
+use payment::model::{
+    BuyerPayInState, Charge3partyModel, Charge3partyStripeModel, ChargeBuyerMetaModel, ChargeToken,
+    Label3party, StripeCheckoutPaymentStatusModel, StripeSessionStatusModel,
+};
+use payment::usecase::{ProcessorWebhookUcError, ProcessorWebhookUcOk, ProcessorWebhookUseCase};
+
+use super::{MockChargeRepo, MockMerchantRepo, MockProcessorEventRepo};
+use crate::model::payout::ut_setup_merchant_3party_stripe;
+use crate::ut_setup_sharestate_mock_3party;
+
+// must be consistent with the secret in the mock Stripe context
+const UT_WEBHOOK_SECRET: &str = "whsec_mock_unit_test_1a2b3c";
+
+fn ut_sign_payload(payload: &[u8], t_signed: DateTime<Utc>) -> String {
+    let ts = t_signed.timestamp();
+    let key = hmac::Key::new(hmac::HMAC_SHA256, UT_WEBHOOK_SECRET.as_bytes());
+    let mut msg = format!("{ts}.").into_bytes();
+    msg.extend_from_slice(payload);
+    let tag = hmac::sign(&key, msg.as_slice());
+    let hexstr = tag
+        .as_ref()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect::<String>();
+    format!("t={ts},v1={hexstr}")
+}
+
+fn ut_setup_buyer_meta_stripe(
+    usr_id: u32,
+    order_id: &str,
+    charge_time: DateTime<Utc>,
+) -> ChargeBuyerMetaModel {
+    let arg = (order_id.to_string(), usr_id, charge_time);
+    let mut obj = ChargeBuyerMetaModel::from(arg);
+    let stripe3pty = Charge3partyStripeModel {
+        checkout_session_id: "mock-session-id".to_string(),
+        session_state: StripeSessionStatusModel::open,
+        payment_state: StripeCheckoutPaymentStatusModel::unpaid,
+        transfer_group: "mock-transfer-group".to_string(),
+        payment_intent_id: "mock-payment-intent-id".to_string(),
+        expiry: charge_time + Duration::minutes(5),
+    };
+    obj.update_3party(Charge3partyModel::Stripe(stripe3pty));
+    let value = BuyerPayInState::ProcessorAccepted(charge_time + Duration::seconds(7));
+    obj.update_progress(&value);
+    obj
+}
+
+#[rustfmt::skip]
+fn ut_setup_charge_repo(
+    chargemeta: Option<ChargeBuyerMetaModel>,
+    update_meta_ok: bool,
+    rd_chrg_ids: Option<(u32, Vec<DateTime<Utc>>)>,
+) -> Box<dyn AbstractChargeRepo> {
+    let chargemeta = chargemeta.map(|v| Ok(Some(v)));
+    let update_meta_res = if update_meta_ok { Some(Ok(())) } else { None };
+    MockChargeRepo::build(
+        None, None, None,
+        chargemeta, None, update_meta_res,
+        None, None, None,
+        rd_chrg_ids, None,
+    )
+}
+
+fn ut_setup_merchant_repo(
+    update3pt_ok: bool,
+    fetch_by_acct: bool,
+) -> Box<dyn AbstractMerchantRepo> {
+    let update3pt_res = if update3pt_ok { Some(Ok(())) } else { None };
+    let fetch_by_acct_res = if fetch_by_acct {
+        Some((1009u32, ut_setup_merchant_3party_stripe()))
+    } else {
+        None
+    };
+    MockMerchantRepo::build(None, None, None, update3pt_res, fetch_by_acct_res)
+}
+
+fn ut_checkout_completed_event(evt_id: &str, buyer_id: u32, order_id: &str) -> Vec<u8> {
+    let obj = json!({
+        "id": evt_id,
+        "type": "checkout.session.completed",
+        "data": {"object": {
+            "id": "mock-session-id",
+            "client_secret": null,
+            "url": null,
+            "status": "complete",
+            "payment_status": "paid",
+            "payment_intent": "mock-payment-intent-id",
+            "expires_at": Local::now().timestamp() + 300,
+            "client_reference_id": format!("{buyer_id}-{order_id}"),
+        }}
+    });
+    serde_json::to_vec(&obj).unwrap()
+}
+
+#[actix_web::test]
+async fn complete_pay_in_ok() {
+    let shr_state = ut_setup_sharestate_mock_3party();
+    let (mock_buyer_id, mock_oid) = (4009u32, "0e3a7f");
+    let charge_time = Local::now().to_utc() - Duration::minutes(2);
+    let chargemeta = ut_setup_buyer_meta_stripe(mock_buyer_id, mock_oid, charge_time);
+    let rd_chrg_ids = Some((mock_buyer_id, vec![charge_time]));
+    let uc = ProcessorWebhookUseCase {
+        processors: shr_state.processor_context(),
+        repo_c: ut_setup_charge_repo(Some(chargemeta), true, rd_chrg_ids),
+        repo_m: ut_setup_merchant_repo(false, false),
+        repo_evt: MockProcessorEventRepo::build(vec![]),
+        logctx: shr_state.log_context(),
+    };
+    let payload = ut_checkout_completed_event("evt_1xyz", mock_buyer_id, mock_oid);
+    let signature = ut_sign_payload(&payload, Local::now().to_utc());
+    let result = uc.execute(Label3party::Stripe, signature, payload).await;
+    assert!(matches!(result, Ok(ProcessorWebhookUcOk::Applied)));
+}
+
+#[actix_web::test]
+async fn complete_pay_in_buyer_mismatch() {
+    let shr_state = ut_setup_sharestate_mock_3party();
+    let (mock_buyer_id, mock_oid) = (4009u32, "0e3a7f");
+    let charge_time = Local::now().to_utc() - Duration::minutes(2);
+    let rd_chrg_ids = Some((mock_buyer_id + 1, vec![charge_time]));
+    let uc = ProcessorWebhookUseCase {
+        processors: shr_state.processor_context(),
+        repo_c: ut_setup_charge_repo(None, false, rd_chrg_ids),
+        repo_m: ut_setup_merchant_repo(false, false),
+        repo_evt: MockProcessorEventRepo::build(vec![]),
+        logctx: shr_state.log_context(),
+    };
+    let payload = ut_checkout_completed_event("evt_1xyz", mock_buyer_id, mock_oid);
+    let signature = ut_sign_payload(&payload, Local::now().to_utc());
+    let result = uc.execute(Label3party::Stripe, signature, payload).await;
+    assert!(matches!(
+        result,
+        Err(ProcessorWebhookUcError::CorruptedEvent(_))
+    ));
+}
+
+#[actix_web::test]
+async fn duplicate_event() {
+    let shr_state = ut_setup_sharestate_mock_3party();
+    let uc = ProcessorWebhookUseCase {
+        processors: shr_state.processor_context(),
+        repo_c: ut_setup_charge_repo(None, false, None),
+        repo_m: ut_setup_merchant_repo(false, false),
+        repo_evt: MockProcessorEventRepo::build(vec![("evt_1xyz", true)]),
+        logctx: shr_state.log_context(),
+    };
+    let payload = ut_checkout_completed_event("evt_1xyz", 4009, "0e3a7f");
+    let signature = ut_sign_payload(&payload, Local::now().to_utc());
+    let result = uc.execute(Label3party::Stripe, signature, payload).await;
+    assert!(matches!(result, Ok(ProcessorWebhookUcOk::Duplicate)));
+}
+
+#[actix_web::test]
+async fn event_claimed_in_progress() {
+    let shr_state = ut_setup_sharestate_mock_3party();
+    let uc = ProcessorWebhookUseCase {
+        processors: shr_state.processor_context(),
+        repo_c: ut_setup_charge_repo(None, false, None),
+        repo_m: ut_setup_merchant_repo(false, false),
+        // another request claimed the event but has not applied it yet
+        repo_evt: MockProcessorEventRepo::build(vec![("evt_1xyz", false)]),
+        logctx: shr_state.log_context(),
+    };
+    let payload = ut_checkout_completed_event("evt_1xyz", 4009, "0e3a7f");
+    let signature = ut_sign_payload(&payload, Local::now().to_utc());
+    let result = uc.execute(Label3party::Stripe, signature, payload).await;
+    if let Err(ProcessorWebhookUcError::EventInProgress(evt_id)) = result {
+        assert_eq!(evt_id.as_str(), "evt_1xyz");
+    } else {
+        assert!(false);
+    }
+}
+
+async fn ut_verify_signature_error(signature: String, payload: Vec<u8>) {
+    let shr_state = ut_setup_sharestate_mock_3party();
+    let uc = ProcessorWebhookUseCase {
+        processors: shr_state.processor_context(),
+        repo_c: ut_setup_charge_repo(None, false, None),
+        repo_m: ut_setup_merchant_repo(false, false),
+        repo_evt: MockProcessorEventRepo::build(vec![]),
+        logctx: shr_state.log_context(),
+    };
+    let result = uc.execute(Label3party::Stripe, signature, payload).await;
+    if let Err(ProcessorWebhookUcError::ThirdParty(e)) = result {
+        assert!(matches!(
+            e.reason,
+            AppProcessorErrorReason::InvalidSignature(_)
+        ));
+    } else {
+        assert!(false);
+    }
+}
+
+#[actix_web::test]
+async fn invalid_signature() {
+    let payload = ut_checkout_completed_event("evt_1xyz", 4009, "0e3a7f");
+    let signature = ut_sign_payload(&payload, Local::now().to_utc());
+    // payload modified after signed
+    let tampered = ut_checkout_completed_event("evt_1xyz", 4010, "0e3a7f");
+    ut_verify_signature_error(signature, tampered).await;
+    let payload = ut_checkout_completed_event("evt_1xyz", 4009, "0e3a7f");
+    ut_verify_signature_error("t=1234,v1=".to_string(), payload).await;
+    let payload = ut_checkout_completed_event("evt_1xyz", 4009, "0e3a7f");
+    ut_verify_signature_error(String::new(), payload).await;
+}
+
+#[actix_web::test]
+async fn stale_signature() {
+    let payload = ut_checkout_completed_event("evt_1xyz", 4009, "0e3a7f");
+    let t_signed = Local::now().to_utc() - Duration::minutes(6);
+    let signature = ut_sign_payload(&payload, t_signed);
+    ut_verify_signature_error(signature, payload).await;
+}
+
+fn ut_account_updated_event(evt_id: &str) -> Vec<u8> {
+    let obj = json!({
+        "id": evt_id,
+        "type": "account.updated",
+        "data": {"object": {
+            "id": "acct_1oi3gwtiy832yt",
+            "type": "express",
+            "country": "ID",
+            "email": "hayley@wo0dberry.org",
+            "capabilities": {"transfers": "active"},
+            "requirements": {"currently_due": [], "disabled_reason": null},
+            "tos_acceptance": {"date": Local::now().timestamp(), "service_agreement": "recipient"},
+            "charges_enabled": true,
+            "payouts_enabled": true,
+            "details_submitted": true,
+            "created": Local::now().timestamp() - 86400,
+            "settings": {"payouts": {
+                "schedule": {"delay_days": 7, "interval": "daily"},
+                "debit_negative_balances": false
+            }}
+        }}
+    });
+    serde_json::to_vec(&obj).unwrap()
+}
+
+#[actix_web::test]
+async fn update_merchant_ok() {
+    let shr_state = ut_setup_sharestate_mock_3party();
+    let uc = ProcessorWebhookUseCase {
+        processors: shr_state.processor_context(),
+        repo_c: ut_setup_charge_repo(None, false, None),
+        repo_m: ut_setup_merchant_repo(true, true),
+        repo_evt: MockProcessorEventRepo::build(vec![]),
+        logctx: shr_state.log_context(),
+    };
+    let payload = ut_account_updated_event("evt_2abc");
+    let signature = ut_sign_payload(&payload, Local::now().to_utc());
+    let result = uc.execute(Label3party::Stripe, signature, payload).await;
+    assert!(matches!(result, Ok(ProcessorWebhookUcOk::Applied)));
+}
+
+#[actix_web::test]
+async fn update_merchant_unknown_account() {
+    let shr_state = ut_setup_sharestate_mock_3party();
+    let uc = ProcessorWebhookUseCase {
+        processors: shr_state.processor_context(),
+        repo_c: ut_setup_charge_repo(None, false, None),
+        repo_m: ut_setup_merchant_repo(false, false),
+        repo_evt: MockProcessorEventRepo::build(vec![]),
+        logctx: shr_state.log_context(),
+    };
+    let payload = ut_account_updated_event("evt_2abc");
+    let signature = ut_sign_payload(&payload, Local::now().to_utc());
+    let result = uc.execute(Label3party::Stripe, signature, payload).await;
+    assert!(matches!(result, Ok(ProcessorWebhookUcOk::Ignored(_))));
+}
+
+#[actix_web::test]
+async fn transfer_payout_not_saved() {
+    let shr_state = ut_setup_sharestate_mock_3party();
+    let charge_time = Local::now().to_utc() - Duration::minutes(20);
+    let charge_id = ChargeToken::encode(4009, charge_time).to_string();
+    let obj = json!({
+        "id": "evt_3def",
+        "type": "transfer.created",
+        "data": {"object": {
+            "id": "tr_mock_1a2b",
+            "destination": "acct_1oi3gwtiy832yt",
+            "amount": 1250,
+            "transfer_group": charge_id,
+            "amount_reversed": 0
+        }}
+    });
+    let payload = serde_json::to_vec(&obj).unwrap();
+    let signature = ut_sign_payload(&payload, Local::now().to_utc());
+    let uc = ProcessorWebhookUseCase {
+        processors: shr_state.processor_context(),
+        repo_c: ut_setup_charge_repo(None, false, None),
+        repo_m: ut_setup_merchant_repo(false, true),
+        repo_evt: MockProcessorEventRepo::build(vec![]),
+        logctx: shr_state.log_context(),
+    };
+    let result = uc.execute(Label3party::Stripe, signature, payload).await;
+    if let Err(ProcessorWebhookUcError::MissingPayout(store_id, _)) = result {
+        assert_eq!(store_id, 1009);
+    } else {
+        assert!(false);
+    }
+}
+
+#[actix_web::test]
+async fn unhandled_event_ignored() {
+    let shr_state = ut_setup_sharestate_mock_3party();
+    let uc = ProcessorWebhookUseCase {
+        processors: shr_state.processor_context(),
+        repo_c: ut_setup_charge_repo(None, false, None),
+        repo_m: ut_setup_merchant_repo(false, false),
+        repo_evt: MockProcessorEventRepo::build(vec![]),
+        logctx: shr_state.log_context(),
+    };
+    let obj = json!({
+        "id": "evt_4ghi",
+        "type": "customer.created",
+        "data": {"object": {"id": "cus_mock"}}
+    });
+    let payload = serde_json::to_vec(&obj).unwrap();
+    let signature = ut_sign_payload(&payload, Local::now().to_utc());
+    let result = uc.execute(Label3party::Stripe, signature, payload).await;
+    if let Ok(ProcessorWebhookUcOk::Ignored(evt_type)) = result {
+        assert_eq!(evt_type.as_str(), "customer.created");
+    } else {
+        assert!(false);
+    }
+}
+
+fn ut_charge_refunded_event(evt_id: &str, charge_id: &str, payment_intent: &str) -> Vec<u8> {
+    let obj = json!({
+        "id": evt_id,
+        "type": "charge.refunded",
+        "data": {"object": {
+            "id": "ch_mock_9z8y",
+            "payment_intent": payment_intent,
+            "transfer_group": charge_id
+        }}
+    });
+    serde_json::to_vec(&obj).unwrap()
+}
+
+#[actix_web::test]
+async fn refund_confirmed_ok() {
+    let shr_state = ut_setup_sharestate_mock_3party();
+    let (mock_buyer_id, mock_oid) = (4011u32, "0e3a80");
+    let charge_time = Local::now().to_utc() - Duration::minutes(30);
+    let chargemeta = ut_setup_buyer_meta_stripe(mock_buyer_id, mock_oid, charge_time);
+    let charge_id = ChargeToken::encode(mock_buyer_id, charge_time).to_string();
+    let uc = ProcessorWebhookUseCase {
+        processors: shr_state.processor_context(),
+        repo_c: ut_setup_charge_repo(Some(chargemeta), false, None),
+        repo_m: ut_setup_merchant_repo(false, false),
+        repo_evt: MockProcessorEventRepo::build(vec![]),
+        logctx: shr_state.log_context(),
+    };
+    let payload = ut_charge_refunded_event("evt_5jkl", &charge_id, "mock-payment-intent-id");
+    let signature = ut_sign_payload(&payload, Local::now().to_utc());
+    let result = uc.execute(Label3party::Stripe, signature, payload).await;
+    assert!(matches!(result, Ok(ProcessorWebhookUcOk::Applied)));
+}
+
+#[actix_web::test]
+async fn refund_payment_mismatch() {
+    let shr_state = ut_setup_sharestate_mock_3party();
+    let (mock_buyer_id, mock_oid) = (4011u32, "0e3a80");
+    let charge_time = Local::now().to_utc() - Duration::minutes(30);
+    let chargemeta = ut_setup_buyer_meta_stripe(mock_buyer_id, mock_oid, charge_time);
+    let charge_id = ChargeToken::encode(mock_buyer_id, charge_time).to_string();
+    let uc = ProcessorWebhookUseCase {
+        processors: shr_state.processor_context(),
+        repo_c: ut_setup_charge_repo(Some(chargemeta), false, None),
+        repo_m: ut_setup_merchant_repo(false, false),
+        repo_evt: MockProcessorEventRepo::build(vec![]),
+        logctx: shr_state.log_context(),
+    };
+    let payload = ut_charge_refunded_event("evt_5jkm", &charge_id, "other-payment-intent");
+    let signature = ut_sign_payload(&payload, Local::now().to_utc());
+    let result = uc.execute(Label3party::Stripe, signature, payload).await;
+    assert!(matches!(
+        result,
+        Err(ProcessorWebhookUcError::CorruptedEvent(_))
+    ));
+}
@@ -0,0 +1,212 @@
+use std::sync::Arc;
+
+use chrono::{DateTime, Duration, Local, Utc};
+
+use ecommerce_common::api::rpc::dto::{
+    OrderLinePayUpdateErrorDto, OrderLinePayUpdateErrorReason, OrderPaymentUpdateErrorDto,
+};
+use payment::adapter::rpc::{AppRpcCtxError, AppRpcErrorFnLabel, AppRpcErrorReason, AppRpcReply};
+use payment::model::{
+    BuyerPayInState, Charge3partyModel, Charge3partyStripeModel, ChargeBuyerMetaModel,
+    ChargeLineBuyerModel, StripeCheckoutPaymentStatusModel, StripeSessionStatusModel,
+};
+use payment::usecase::{SyncOrderPayUcOk, SyncOrderPayUseCase};
+
+use super::{MockChargeRepo, MockRpcClient, MockRpcContext, MockRpcPublishEvent};
+use crate::model::ut_setup_buyer_charge_lines;
+
+fn ut_setup_buyer_meta(
+    usr_id: u32,
+    order_id: &str,
+    charge_time: DateTime<Utc>,
+    completed: bool,
+) -> ChargeBuyerMetaModel {
+    let arg = (order_id.to_string(), usr_id, charge_time);
+    let mut obj = ChargeBuyerMetaModel::from(arg);
+    let (session_state, payment_state) = if completed {
+        (
+            StripeSessionStatusModel::complete,
+            StripeCheckoutPaymentStatusModel::paid,
+        )
+    } else {
+        (
+            StripeSessionStatusModel::open,
+            StripeCheckoutPaymentStatusModel::unpaid,
+        )
+    };
+    let stripe3pty = Charge3partyStripeModel {
+        checkout_session_id: "mock-session-id".to_string(),
+        session_state,
+        payment_state,
+        transfer_group: "mock-transfer-group".to_string(),
+        payment_intent_id: "mock-payment-intent-id".to_string(),
+        expiry: charge_time + Duration::minutes(5),
+    };
+    obj.update_3party(Charge3partyModel::Stripe(stripe3pty));
+    let t = charge_time + Duration::seconds(15);
+    let value = if completed {
+        BuyerPayInState::ProcessorCompleted(t)
+    } else {
+        BuyerPayInState::ProcessorAccepted(t)
+    };
+    obj.update_progress(&value);
+    obj
+}
+
+#[rustfmt::skip]
+fn ut_setup_charge_lines() -> Vec<ChargeLineBuyerModel> {
+    let d = vec![
+        ((8298, 471, 0), ((9028, 2), (36112, 2), 4), ((0,0), (0,0), 0), 0),
+    ];
+    ut_setup_buyer_charge_lines(d)
+}
+
+fn ut_rpc_reply(oid: &str, lines: Vec<OrderLinePayUpdateErrorDto>) -> AppRpcReply {
+    let obj = OrderPaymentUpdateErrorDto {
+        oid: oid.to_string(),
+        charge_time: None,
+        lines,
+    };
+    AppRpcReply {
+        message: serde_json::to_vec(&obj).unwrap(),
+    }
+}
+
+#[actix_web::test]
+async fn ok_synced() {
+    let (mock_usr_id, mock_oid) = (8010095u32, "shout-out");
+    let charge_time = Local::now().to_utc() - Duration::minutes(30);
+    let mock_meta = ut_setup_buyer_meta(mock_usr_id, mock_oid, charge_time, true);
+    let mock_repo = MockChargeRepo::build_order_sync(
+        vec![(mock_usr_id, charge_time, 2)],
+        Some(Ok(Some(mock_meta))),
+        Some(Ok(ut_setup_charge_lines())),
+        Some(Ok(())),
+    );
+    let rpc_pub_evt = MockRpcPublishEvent::build(Some(Ok(ut_rpc_reply(mock_oid, Vec::new()))));
+    let mock_rpc_client = MockRpcClient::build(Some(Ok(rpc_pub_evt)));
+    let mock_rpc_ctx = MockRpcContext::build(Some(Ok(mock_rpc_client)));
+    let result = SyncOrderPayUseCase::execute(mock_repo, Arc::new(mock_rpc_ctx)).await;
+    assert!(result.is_ok());
+    let SyncOrderPayUcOk {
+        num_synced,
+        num_failed,
+        overdue,
+    } = result.unwrap();
+    assert_eq!(num_synced, 1);
+    assert_eq!(num_failed, 0);
+    assert!(overdue.is_empty());
+}
+
+#[actix_web::test]
+async fn skip_unconfirmed_charge() {
+    let (mock_usr_id, mock_oid) = (8010095u32, "shout-out");
+    let charge_time = Local::now().to_utc() - Duration::minutes(30);
+    let mock_meta = ut_setup_buyer_meta(mock_usr_id, mock_oid, charge_time, false);
+    let mock_repo = MockChargeRepo::build_order_sync(
+        vec![(mock_usr_id, charge_time, 0)],
+        Some(Ok(Some(mock_meta))),
+        None,
+        None,
+    );
+    let mock_rpc_ctx = MockRpcContext::build(None);
+    let result = SyncOrderPayUseCase::execute(mock_repo, Arc::new(mock_rpc_ctx)).await;
+    assert!(result.is_ok());
+    let SyncOrderPayUcOk {
+        num_synced,
+        num_failed,
+        overdue,
+    } = result.unwrap();
+    assert_eq!(num_synced, 0);
+    assert_eq!(num_failed, 0);
+    assert!(overdue.is_empty());
+}
+
+#[actix_web::test]
+async fn rpc_lowlvl_error_retry() {
+    let (mock_usr_id, mock_oid) = (8010095u32, "shout-out");
+    let charge_time = Local::now().to_utc() - Duration::minutes(30);
+    let mock_meta = ut_setup_buyer_meta(mock_usr_id, mock_oid, charge_time, true);
+    let mock_repo = MockChargeRepo::build_order_sync(
+        vec![(mock_usr_id, charge_time, 5)],
+        Some(Ok(Some(mock_meta))),
+        Some(Ok(ut_setup_charge_lines())),
+        None,
+    );
+    let error_rpc = AppRpcCtxError {
+        fn_label: AppRpcErrorFnLabel::AcquireClientConn,
+        reason: AppRpcErrorReason::LowLevelConn("unit-test".to_string()),
+    };
+    let mock_rpc_ctx = MockRpcContext::build(Some(Err(error_rpc)));
+    let result = SyncOrderPayUseCase::execute(mock_repo, Arc::new(mock_rpc_ctx)).await;
+    assert!(result.is_ok());
+    let SyncOrderPayUcOk {
+        num_synced,
+        num_failed,
+        overdue,
+    } = result.unwrap();
+    assert_eq!(num_synced, 0);
+    assert_eq!(num_failed, 1);
+    assert!(overdue.is_empty());
+}
+
+#[actix_web::test]
+async fn order_app_rejected() {
+    let (mock_usr_id, mock_oid) = (8010095u32, "shout-out");
+    let charge_time = Local::now().to_utc() - Duration::minutes(30);
+    let mock_meta = ut_setup_buyer_meta(mock_usr_id, mock_oid, charge_time, true);
+    let mock_repo = MockChargeRepo::build_order_sync(
+        vec![(mock_usr_id, charge_time, 1)],
+        Some(Ok(Some(mock_meta))),
+        Some(Ok(ut_setup_charge_lines())),
+        None,
+    );
+    let e_lines = vec![OrderLinePayUpdateErrorDto {
+        seller_id: 8298,
+        product_id: 471,
+        attr_set_seq: 0,
+        reason: OrderLinePayUpdateErrorReason::InvalidQuantity,
+    }];
+    let rpc_pub_evt = MockRpcPublishEvent::build(Some(Ok(ut_rpc_reply(mock_oid, e_lines))));
+    let mock_rpc_client = MockRpcClient::build(Some(Ok(rpc_pub_evt)));
+    let mock_rpc_ctx = MockRpcContext::build(Some(Ok(mock_rpc_client)));
+    let result = SyncOrderPayUseCase::execute(mock_repo, Arc::new(mock_rpc_ctx)).await;
+    assert!(result.is_ok());
+    let SyncOrderPayUcOk {
+        num_synced,
+        num_failed,
+        overdue,
+    } = result.unwrap();
+    assert_eq!(num_synced, 0);
+    assert_eq!(num_failed, 1);
+    assert!(overdue.is_empty());
+}
+
+#[actix_web::test]
+async fn report_overdue_keep_pending() {
+    let (mock_usr_id, mock_oid) = (8010095u32, "shout-out");
+    let charge_time = Local::now().to_utc() - Duration::days(3);
+    let mock_meta = ut_setup_buyer_meta(mock_usr_id, mock_oid, charge_time, true);
+    let mock_repo = MockChargeRepo::build_order_sync(
+        vec![(mock_usr_id, charge_time, 15)],
+        Some(Ok(Some(mock_meta))),
+        Some(Ok(ut_setup_charge_lines())),
+        None,
+    );
+    let error_rpc = AppRpcCtxError {
+        fn_label: AppRpcErrorFnLabel::AcquireClientConn,
+        reason: AppRpcErrorReason::LowLevelConn("unit-test".to_string()),
+    };
+    let mock_rpc_ctx = MockRpcContext::build(Some(Err(error_rpc)));
+    let result = SyncOrderPayUseCase::execute(mock_repo, Arc::new(mock_rpc_ctx)).await;
+    assert!(result.is_ok());
+    let SyncOrderPayUcOk {
+        num_synced,
+        num_failed,
+        overdue,
+    } = result.unwrap();
+    assert_eq!(num_synced, 0);
+    assert_eq!(num_failed, 1);
+    assert_eq!(overdue.len(), 1);
+    assert_eq!(overdue[0], (mock_usr_id, charge_time, 16));
+}